@@ -35,12 +35,593 @@ pub struct RuntimeFunctionDecl {
 
 pub const RUNTIME_PRINT: RuntimeFunctionId = RuntimeFunctionId::from_index(0);
 pub const RUNTIME_PRINTLN: RuntimeFunctionId = RuntimeFunctionId::from_index(1);
+pub const RUNTIME_ALLOC: RuntimeFunctionId = RuntimeFunctionId::from_index(2);
+pub const RUNTIME_RETAIN: RuntimeFunctionId = RuntimeFunctionId::from_index(3);
+pub const RUNTIME_RELEASE: RuntimeFunctionId = RuntimeFunctionId::from_index(4);
+pub const RUNTIME_UNCAUGHT_ERROR: RuntimeFunctionId = RuntimeFunctionId::from_index(5);
+pub const RUNTIME_SET_DROP: RuntimeFunctionId = RuntimeFunctionId::from_index(6);
+pub const RUNTIME_VEC_NEW: RuntimeFunctionId = RuntimeFunctionId::from_index(7);
+pub const RUNTIME_VEC_PUSH: RuntimeFunctionId = RuntimeFunctionId::from_index(8);
+pub const RUNTIME_VEC_POP: RuntimeFunctionId = RuntimeFunctionId::from_index(9);
+pub const RUNTIME_INDEX_OUT_OF_BOUNDS: RuntimeFunctionId = RuntimeFunctionId::from_index(10);
+pub const RUNTIME_SLICE_OUT_OF_BOUNDS: RuntimeFunctionId = RuntimeFunctionId::from_index(11);
+pub const RUNTIME_MAP_NEW: RuntimeFunctionId = RuntimeFunctionId::from_index(12);
+pub const RUNTIME_MAP_INSERT: RuntimeFunctionId = RuntimeFunctionId::from_index(13);
+pub const RUNTIME_MAP_GET: RuntimeFunctionId = RuntimeFunctionId::from_index(14);
+pub const RUNTIME_MAP_REMOVE: RuntimeFunctionId = RuntimeFunctionId::from_index(15);
+pub const RUNTIME_MAP_KEYS: RuntimeFunctionId = RuntimeFunctionId::from_index(16);
+pub const RUNTIME_MAP_VALUES: RuntimeFunctionId = RuntimeFunctionId::from_index(17);
+pub const RUNTIME_INPUT: RuntimeFunctionId = RuntimeFunctionId::from_index(18);
+pub const RUNTIME_POW: RuntimeFunctionId = RuntimeFunctionId::from_index(19);
+pub const RUNTIME_ROUND: RuntimeFunctionId = RuntimeFunctionId::from_index(20);
+pub const RUNTIME_SIN: RuntimeFunctionId = RuntimeFunctionId::from_index(21);
+pub const RUNTIME_COS: RuntimeFunctionId = RuntimeFunctionId::from_index(22);
+pub const RUNTIME_TAN: RuntimeFunctionId = RuntimeFunctionId::from_index(23);
+pub const RUNTIME_ASIN: RuntimeFunctionId = RuntimeFunctionId::from_index(24);
+pub const RUNTIME_ACOS: RuntimeFunctionId = RuntimeFunctionId::from_index(25);
+pub const RUNTIME_ATAN: RuntimeFunctionId = RuntimeFunctionId::from_index(26);
+pub const RUNTIME_ATAN2: RuntimeFunctionId = RuntimeFunctionId::from_index(27);
+pub const RUNTIME_EXP: RuntimeFunctionId = RuntimeFunctionId::from_index(28);
+pub const RUNTIME_LN: RuntimeFunctionId = RuntimeFunctionId::from_index(29);
+pub const RUNTIME_LOG2: RuntimeFunctionId = RuntimeFunctionId::from_index(30);
+pub const RUNTIME_LOG10: RuntimeFunctionId = RuntimeFunctionId::from_index(31);
+pub const RUNTIME_READ_TO_STRING: RuntimeFunctionId = RuntimeFunctionId::from_index(32);
+pub const RUNTIME_WRITE: RuntimeFunctionId = RuntimeFunctionId::from_index(33);
+pub const RUNTIME_APPEND: RuntimeFunctionId = RuntimeFunctionId::from_index(34);
+pub const RUNTIME_EXISTS: RuntimeFunctionId = RuntimeFunctionId::from_index(35);
+pub const RUNTIME_REMOVE: RuntimeFunctionId = RuntimeFunctionId::from_index(36);
+pub const RUNTIME_LIST_DIR: RuntimeFunctionId = RuntimeFunctionId::from_index(37);
+pub const RUNTIME_INSTANT_NOW: RuntimeFunctionId = RuntimeFunctionId::from_index(38);
+pub const RUNTIME_INSTANT_ELAPSED: RuntimeFunctionId = RuntimeFunctionId::from_index(39);
+pub const RUNTIME_SLEEP: RuntimeFunctionId = RuntimeFunctionId::from_index(40);
+pub const RUNTIME_UNIX_TIME: RuntimeFunctionId = RuntimeFunctionId::from_index(41);
+pub const RUNTIME_RANDOM_SEED: RuntimeFunctionId = RuntimeFunctionId::from_index(42);
+pub const RUNTIME_RANDOM_OS_SEED: RuntimeFunctionId = RuntimeFunctionId::from_index(43);
+pub const RUNTIME_RANDOM_NEXT: RuntimeFunctionId = RuntimeFunctionId::from_index(44);
+pub const RUNTIME_RANDOM_INT_RANGE: RuntimeFunctionId = RuntimeFunctionId::from_index(45);
+pub const RUNTIME_RANDOM_FLOAT: RuntimeFunctionId = RuntimeFunctionId::from_index(46);
+pub const RUNTIME_RANDOM_FLOAT_RANGE: RuntimeFunctionId = RuntimeFunctionId::from_index(47);
+pub const RUNTIME_RANDOM_SHUFFLE: RuntimeFunctionId = RuntimeFunctionId::from_index(48);
+pub const RUNTIME_JSON_PARSE: RuntimeFunctionId = RuntimeFunctionId::from_index(49);
+pub const RUNTIME_JSON_STRINGIFY: RuntimeFunctionId = RuntimeFunctionId::from_index(50);
+pub const RUNTIME_JSON_PRETTY: RuntimeFunctionId = RuntimeFunctionId::from_index(51);
+pub const RUNTIME_JSON_NEW_NULL: RuntimeFunctionId = RuntimeFunctionId::from_index(52);
+pub const RUNTIME_JSON_FROM_BOOL: RuntimeFunctionId = RuntimeFunctionId::from_index(53);
+pub const RUNTIME_JSON_FROM_NUMBER: RuntimeFunctionId = RuntimeFunctionId::from_index(54);
+pub const RUNTIME_JSON_FROM_STR: RuntimeFunctionId = RuntimeFunctionId::from_index(55);
+pub const RUNTIME_JSON_NEW_ARRAY: RuntimeFunctionId = RuntimeFunctionId::from_index(56);
+pub const RUNTIME_JSON_NEW_OBJECT: RuntimeFunctionId = RuntimeFunctionId::from_index(57);
+pub const RUNTIME_JSON_IS_NULL: RuntimeFunctionId = RuntimeFunctionId::from_index(58);
+pub const RUNTIME_JSON_AS_BOOL: RuntimeFunctionId = RuntimeFunctionId::from_index(59);
+pub const RUNTIME_JSON_AS_NUMBER: RuntimeFunctionId = RuntimeFunctionId::from_index(60);
+pub const RUNTIME_JSON_AS_STR: RuntimeFunctionId = RuntimeFunctionId::from_index(61);
+pub const RUNTIME_JSON_LEN: RuntimeFunctionId = RuntimeFunctionId::from_index(62);
+pub const RUNTIME_JSON_AT: RuntimeFunctionId = RuntimeFunctionId::from_index(63);
+pub const RUNTIME_JSON_GET: RuntimeFunctionId = RuntimeFunctionId::from_index(64);
+pub const RUNTIME_JSON_KEYS: RuntimeFunctionId = RuntimeFunctionId::from_index(65);
+pub const RUNTIME_JSON_PUSH: RuntimeFunctionId = RuntimeFunctionId::from_index(66);
+pub const RUNTIME_JSON_INSERT: RuntimeFunctionId = RuntimeFunctionId::from_index(67);
+pub const RUNTIME_DECIMAL_PARSE: RuntimeFunctionId = RuntimeFunctionId::from_index(68);
+pub const RUNTIME_DECIMAL_FROM_INT: RuntimeFunctionId = RuntimeFunctionId::from_index(69);
+pub const RUNTIME_DECIMAL_TO_STRING: RuntimeFunctionId = RuntimeFunctionId::from_index(70);
+pub const RUNTIME_DECIMAL_ADD: RuntimeFunctionId = RuntimeFunctionId::from_index(71);
+pub const RUNTIME_DECIMAL_SUB: RuntimeFunctionId = RuntimeFunctionId::from_index(72);
+pub const RUNTIME_DECIMAL_MUL: RuntimeFunctionId = RuntimeFunctionId::from_index(73);
+pub const RUNTIME_DECIMAL_DIV: RuntimeFunctionId = RuntimeFunctionId::from_index(74);
+pub const RUNTIME_DECIMAL_ROUND: RuntimeFunctionId = RuntimeFunctionId::from_index(75);
+pub const RUNTIME_DECIMAL_CMP: RuntimeFunctionId = RuntimeFunctionId::from_index(76);
+pub const RUNTIME_DECIMAL_NEG: RuntimeFunctionId = RuntimeFunctionId::from_index(77);
+pub const RUNTIME_DECIMAL_SCALE: RuntimeFunctionId = RuntimeFunctionId::from_index(78);
+pub const RUNTIME_FLUSH: RuntimeFunctionId = RuntimeFunctionId::from_index(79);
+pub const RUNTIME_SET_STDOUT_BUFFERED: RuntimeFunctionId = RuntimeFunctionId::from_index(80);
+pub const RUNTIME_READER_OPEN: RuntimeFunctionId = RuntimeFunctionId::from_index(81);
+pub const RUNTIME_READER_STDIN: RuntimeFunctionId = RuntimeFunctionId::from_index(82);
+pub const RUNTIME_READER_READ_LINE: RuntimeFunctionId = RuntimeFunctionId::from_index(83);
+pub const RUNTIME_READER_LINES: RuntimeFunctionId = RuntimeFunctionId::from_index(84);
+pub const RUNTIME_WRITER_CREATE: RuntimeFunctionId = RuntimeFunctionId::from_index(85);
+pub const RUNTIME_WRITER_STDOUT: RuntimeFunctionId = RuntimeFunctionId::from_index(86);
+pub const RUNTIME_WRITER_WRITE: RuntimeFunctionId = RuntimeFunctionId::from_index(87);
+pub const RUNTIME_WRITER_WRITE_LINE: RuntimeFunctionId = RuntimeFunctionId::from_index(88);
+pub const RUNTIME_WRITER_FLUSH: RuntimeFunctionId = RuntimeFunctionId::from_index(89);
+pub const RUNTIME_PANIC: RuntimeFunctionId = RuntimeFunctionId::from_index(90);
+pub const RUNTIME_LINES_NEXT: RuntimeFunctionId = RuntimeFunctionId::from_index(91);
+pub const RUNTIME_SET_TRACE: RuntimeFunctionId = RuntimeFunctionId::from_index(92);
+pub const RUNTIME_COLLECT_CYCLES: RuntimeFunctionId = RuntimeFunctionId::from_index(93);
+pub const RUNTIME_BEGIN_RUN: RuntimeFunctionId = RuntimeFunctionId::from_index(94);
+pub const RUNTIME_END_RUN: RuntimeFunctionId = RuntimeFunctionId::from_index(95);
+pub const RUNTIME_INT_TO_STRING: RuntimeFunctionId = RuntimeFunctionId::from_index(96);
+pub const RUNTIME_STR_CMP: RuntimeFunctionId = RuntimeFunctionId::from_index(97);
+pub const RUNTIME_STR_HASH: RuntimeFunctionId = RuntimeFunctionId::from_index(98);
+pub const RUNTIME_MAP_NEW_RECORDS: RuntimeFunctionId = RuntimeFunctionId::from_index(99);
+pub const RUNTIME_RANDOM_EMPTY_RANGE: RuntimeFunctionId = RuntimeFunctionId::from_index(100);
+pub const RUNTIME_JSON_CHECK_KIND: RuntimeFunctionId = RuntimeFunctionId::from_index(101);
+pub const RUNTIME_JSON_CHECK_INT: RuntimeFunctionId = RuntimeFunctionId::from_index(102);
+
+/// Reference count of managed payloads that are never freed, such as string
+/// literals in the program image. Retaining and releasing them does nothing.
+pub const IMMORTAL_REFCOUNT: usize = usize::MAX;
+/// Pointer-sized words of the header in front of every managed payload: the
+/// reference count, the payload size and alignment, and the drop function.
+/// Code generation lays string literals out behind such a header.
+pub const HEADER_WORDS: usize = 4;
+
+/// Vector elements and map values copied as plain bytes.
+pub const ELEM_PLAIN: usize = 0;
+/// Vector elements and map values that start with a pointer to a managed
+/// payload, which the container holds a reference to.
+pub const ELEM_MANAGED: usize = 1;
+
+/// Map keys hashed and compared by their bytes.
+pub const MAP_KEY_BYTES: usize = 0;
+/// Map keys that are strings, hashed and compared by their contents. The map
+/// holds a reference to each of them.
+pub const MAP_KEY_STR: usize = 1;
+
+/// Rounds half-way values to the even neighbour.
+pub const ROUND_HALF_EVEN: usize = 0;
+/// Rounds half-way values away from zero.
+pub const ROUND_HALF_UP: usize = 1;
+/// Rounds toward zero.
+pub const ROUND_DOWN: usize = 2;
+/// Rounds away from zero.
+pub const ROUND_UP: usize = 3;
+/// Rounds toward negative infinity.
+pub const ROUND_FLOOR: usize = 4;
+/// Rounds toward positive infinity.
+pub const ROUND_CEILING: usize = 5;
+
+/// Kinds of JSON documents, as checked by `__runeway_json_check_kind`.
+pub const JSON_KIND_NULL: usize = 0;
+pub const JSON_KIND_BOOL: usize = 1;
+pub const JSON_KIND_NUMBER: usize = 2;
+pub const JSON_KIND_STRING: usize = 3;
+pub const JSON_KIND_ARRAY: usize = 4;
+pub const JSON_KIND_OBJECT: usize = 5;
 
 const STRING_PARAMS: &[AbiType] = &[AbiType::Pointer, AbiType::Usize];
+/// `(size, align)` of the requested payload.
+const ALLOC_PARAMS: &[AbiType] = &[AbiType::Usize, AbiType::Usize];
+const MANAGED_PARAMS: &[AbiType] = &[AbiType::Pointer];
+/// `(payload, drop)`, where `drop` is called with the payload before it is
+/// freed.
+const SET_DROP_PARAMS: &[AbiType] = &[AbiType::Pointer, AbiType::Pointer];
+/// `(payload, trace)`, where `trace(payload, visit)` calls `visit` with every
+/// managed pointer held by the payload.
+const SET_TRACE_PARAMS: &[AbiType] = &[AbiType::Pointer, AbiType::Pointer];
+/// `(elem_size, elem_align, elem_kind)` of the vector elements, where
+/// `elem_kind` is [`ELEM_PLAIN`] or [`ELEM_MANAGED`].
+const VEC_NEW_PARAMS: &[AbiType] = &[AbiType::Usize, AbiType::Usize, AbiType::Usize];
+/// `(index, len)`, returning the panic message as a pointer to `(ptr, len)`
+/// that generated code passes on to `__runeway_panic`.
+const INDEX_OUT_OF_BOUNDS_PARAMS: &[AbiType] = &[AbiType::Usize, AbiType::Usize];
+/// `(start, end, len)`, returning the panic message like
+/// [`INDEX_OUT_OF_BOUNDS_PARAMS`].
+const SLICE_OUT_OF_BOUNDS_PARAMS: &[AbiType] = &[AbiType::Usize, AbiType::Usize, AbiType::Usize];
+/// `(key_size, key_align, key_kind, value_size, value_align, value_kind)`,
+/// where `key_kind` is [`MAP_KEY_BYTES`] or [`MAP_KEY_STR`] and `value_kind`
+/// is [`ELEM_PLAIN`] or [`ELEM_MANAGED`].
+const MAP_NEW_PARAMS: &[AbiType] = &[
+    AbiType::Usize,
+    AbiType::Usize,
+    AbiType::Usize,
+    AbiType::Usize,
+    AbiType::Usize,
+    AbiType::Usize,
+];
+/// `(key_hash, key_eq, value_size, value_align, value_kind)` for maps keyed
+/// by struct or enum records, where `key_hash` and `key_eq` are the
+/// functions generated to hash and compare records of the key type.
+const MAP_NEW_RECORDS_PARAMS: &[AbiType] =
+    &[AbiType::Pointer, AbiType::Pointer, AbiType::Usize, AbiType::Usize, AbiType::Usize];
+/// `(map, key, value)`, pointing to the key and value to copy in.
+const MAP_INSERT_PARAMS: &[AbiType] = &[AbiType::Pointer, AbiType::Pointer, AbiType::Pointer];
+/// `(map, key)`
+const MAP_KEY_PARAMS: &[AbiType] = &[AbiType::Pointer, AbiType::Pointer];
+/// `(path_ptr, path_len, contents_ptr, contents_len)`
+const WRITE_PARAMS: &[AbiType] =
+    &[AbiType::Pointer, AbiType::Usize, AbiType::Pointer, AbiType::Usize];
+/// `(lhs_ptr, lhs_len, rhs_ptr, rhs_len)`
+const STRING_PAIR_PARAMS: &[AbiType] =
+    &[AbiType::Pointer, AbiType::Usize, AbiType::Pointer, AbiType::Usize];
+const FLOAT_PARAMS: &[AbiType] = &[AbiType::F64];
+const FLOAT_PAIR_PARAMS: &[AbiType] = &[AbiType::F64, AbiType::F64];
+/// Instants and durations, as nanoseconds.
+const NANOS_PARAMS: &[AbiType] = &[AbiType::I64];
+/// `(json, key_ptr, key_len)`
+const JSON_GET_PARAMS: &[AbiType] = &[AbiType::Pointer, AbiType::Pointer, AbiType::Usize];
+/// `(json, key_ptr, key_len, value)`
+const JSON_INSERT_PARAMS: &[AbiType] =
+    &[AbiType::Pointer, AbiType::Pointer, AbiType::Usize, AbiType::Pointer];
+/// `(json, kind)`, where `kind` is one of the `JSON_KIND_*` codes.
+const JSON_CHECK_KIND_PARAMS: &[AbiType] = &[AbiType::Pointer, AbiType::Usize];
+/// `(json, signed, bits)` of the integer type the number must fit.
+const JSON_CHECK_INT_PARAMS: &[AbiType] = &[AbiType::Pointer, AbiType::I8, AbiType::Usize];
+const INT_PAIR_PARAMS: &[AbiType] = &[AbiType::I64, AbiType::I64];
+const MANAGED_PAIR_PARAMS: &[AbiType] = &[AbiType::Pointer, AbiType::Pointer];
+/// `(lhs, rhs, scale, rounding_mode)`
+const DECIMAL_DIV_PARAMS: &[AbiType] =
+    &[AbiType::Pointer, AbiType::Pointer, AbiType::Usize, AbiType::Usize];
+/// `(writer, text_ptr, text_len)`
+const WRITER_WRITE_PARAMS: &[AbiType] = &[AbiType::Pointer, AbiType::Pointer, AbiType::Usize];
+/// `(elems, len, elem_size)`
+const SHUFFLE_PARAMS: &[AbiType] = &[AbiType::Pointer, AbiType::Usize, AbiType::Usize];
+/// `(decimal, scale, rounding_mode)`
+const DECIMAL_ROUND_PARAMS: &[AbiType] = &[AbiType::Pointer, AbiType::Usize, AbiType::Usize];
+/// `(msg_ptr, msg_len, file_ptr, file_len, line, col)`, where the line and
+/// column of the call are counted from 1.
+const PANIC_PARAMS: &[AbiType] = &[
+    AbiType::Pointer,
+    AbiType::Usize,
+    AbiType::Pointer,
+    AbiType::Usize,
+    AbiType::Usize,
+    AbiType::Usize,
+];
 
 pub const RUNTIME_FUNCTIONS: &[RuntimeFunctionDecl] = &[
     RuntimeFunctionDecl { symbol: "__runeway_print", params: STRING_PARAMS, ret: AbiType::Unit },
     RuntimeFunctionDecl { symbol: "__runeway_println", params: STRING_PARAMS, ret: AbiType::Unit },
+    RuntimeFunctionDecl { symbol: "__runeway_alloc", params: ALLOC_PARAMS, ret: AbiType::Pointer },
+    RuntimeFunctionDecl { symbol: "__runeway_retain", params: MANAGED_PARAMS, ret: AbiType::Unit },
+    RuntimeFunctionDecl { symbol: "__runeway_release", params: MANAGED_PARAMS, ret: AbiType::Unit },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_uncaught_error",
+        params: PANIC_PARAMS,
+        ret: AbiType::Unit,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_set_drop",
+        params: SET_DROP_PARAMS,
+        ret: AbiType::Unit,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_vec_new",
+        params: VEC_NEW_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_vec_push",
+        params: MANAGED_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_vec_pop",
+        params: MANAGED_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_index_out_of_bounds",
+        params: INDEX_OUT_OF_BOUNDS_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_slice_out_of_bounds",
+        params: SLICE_OUT_OF_BOUNDS_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_map_new",
+        params: MAP_NEW_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_map_insert",
+        params: MAP_INSERT_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_map_get",
+        params: MAP_KEY_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_map_remove",
+        params: MAP_KEY_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_map_keys",
+        params: MANAGED_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_map_values",
+        params: MANAGED_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl { symbol: "__runeway_input", params: STRING_PARAMS, ret: AbiType::Pointer },
+    RuntimeFunctionDecl { symbol: "__runeway_pow", params: FLOAT_PAIR_PARAMS, ret: AbiType::F64 },
+    RuntimeFunctionDecl { symbol: "__runeway_round", params: FLOAT_PARAMS, ret: AbiType::F64 },
+    RuntimeFunctionDecl { symbol: "__runeway_sin", params: FLOAT_PARAMS, ret: AbiType::F64 },
+    RuntimeFunctionDecl { symbol: "__runeway_cos", params: FLOAT_PARAMS, ret: AbiType::F64 },
+    RuntimeFunctionDecl { symbol: "__runeway_tan", params: FLOAT_PARAMS, ret: AbiType::F64 },
+    RuntimeFunctionDecl { symbol: "__runeway_asin", params: FLOAT_PARAMS, ret: AbiType::F64 },
+    RuntimeFunctionDecl { symbol: "__runeway_acos", params: FLOAT_PARAMS, ret: AbiType::F64 },
+    RuntimeFunctionDecl { symbol: "__runeway_atan", params: FLOAT_PARAMS, ret: AbiType::F64 },
+    RuntimeFunctionDecl { symbol: "__runeway_atan2", params: FLOAT_PAIR_PARAMS, ret: AbiType::F64 },
+    RuntimeFunctionDecl { symbol: "__runeway_exp", params: FLOAT_PARAMS, ret: AbiType::F64 },
+    RuntimeFunctionDecl { symbol: "__runeway_ln", params: FLOAT_PARAMS, ret: AbiType::F64 },
+    RuntimeFunctionDecl { symbol: "__runeway_log2", params: FLOAT_PARAMS, ret: AbiType::F64 },
+    RuntimeFunctionDecl { symbol: "__runeway_log10", params: FLOAT_PARAMS, ret: AbiType::F64 },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_read_to_string",
+        params: STRING_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl { symbol: "__runeway_write", params: WRITE_PARAMS, ret: AbiType::Pointer },
+    RuntimeFunctionDecl { symbol: "__runeway_append", params: WRITE_PARAMS, ret: AbiType::Pointer },
+    RuntimeFunctionDecl { symbol: "__runeway_exists", params: STRING_PARAMS, ret: AbiType::I8 },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_remove",
+        params: STRING_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_list_dir",
+        params: STRING_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl { symbol: "__runeway_instant_now", params: &[], ret: AbiType::I64 },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_instant_elapsed",
+        params: NANOS_PARAMS,
+        ret: AbiType::I64,
+    },
+    RuntimeFunctionDecl { symbol: "__runeway_sleep", params: NANOS_PARAMS, ret: AbiType::Unit },
+    RuntimeFunctionDecl { symbol: "__runeway_unix_time", params: &[], ret: AbiType::I64 },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_random_seed",
+        params: &[AbiType::I64],
+        ret: AbiType::Unit,
+    },
+    RuntimeFunctionDecl { symbol: "__runeway_random_os_seed", params: &[], ret: AbiType::I64 },
+    RuntimeFunctionDecl { symbol: "__runeway_random_next", params: &[], ret: AbiType::I64 },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_random_int_range",
+        params: INT_PAIR_PARAMS,
+        ret: AbiType::I64,
+    },
+    RuntimeFunctionDecl { symbol: "__runeway_random_float", params: &[], ret: AbiType::F64 },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_random_float_range",
+        params: FLOAT_PAIR_PARAMS,
+        ret: AbiType::F64,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_random_shuffle",
+        params: SHUFFLE_PARAMS,
+        ret: AbiType::Unit,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_json_parse",
+        params: STRING_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_json_stringify",
+        params: MANAGED_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_json_pretty",
+        params: MANAGED_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl { symbol: "__runeway_json_new_null", params: &[], ret: AbiType::Pointer },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_json_from_bool",
+        params: &[AbiType::I8],
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_json_from_number",
+        params: FLOAT_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_json_from_str",
+        params: STRING_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl { symbol: "__runeway_json_new_array", params: &[], ret: AbiType::Pointer },
+    RuntimeFunctionDecl { symbol: "__runeway_json_new_object", params: &[], ret: AbiType::Pointer },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_json_is_null",
+        params: MANAGED_PARAMS,
+        ret: AbiType::I8,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_json_as_bool",
+        params: MANAGED_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_json_as_number",
+        params: MANAGED_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_json_as_str",
+        params: MANAGED_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_json_len",
+        params: MANAGED_PARAMS,
+        ret: AbiType::Usize,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_json_at",
+        params: &[AbiType::Pointer, AbiType::Usize],
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_json_get",
+        params: JSON_GET_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_json_keys",
+        params: MANAGED_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_json_push",
+        params: &[AbiType::Pointer, AbiType::Pointer],
+        ret: AbiType::Unit,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_json_insert",
+        params: JSON_INSERT_PARAMS,
+        ret: AbiType::Unit,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_decimal_parse",
+        params: STRING_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_decimal_from_int",
+        params: &[AbiType::I64],
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_decimal_to_string",
+        params: MANAGED_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_decimal_add",
+        params: MANAGED_PAIR_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_decimal_sub",
+        params: MANAGED_PAIR_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_decimal_mul",
+        params: MANAGED_PAIR_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_decimal_div",
+        params: DECIMAL_DIV_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_decimal_round",
+        params: DECIMAL_ROUND_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_decimal_cmp",
+        params: MANAGED_PAIR_PARAMS,
+        ret: AbiType::I64,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_decimal_neg",
+        params: MANAGED_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_decimal_scale",
+        params: MANAGED_PARAMS,
+        ret: AbiType::Usize,
+    },
+    RuntimeFunctionDecl { symbol: "__runeway_flush", params: &[], ret: AbiType::Unit },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_set_stdout_buffered",
+        params: &[AbiType::I8],
+        ret: AbiType::Unit,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_reader_open",
+        params: STRING_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl { symbol: "__runeway_reader_stdin", params: &[], ret: AbiType::Pointer },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_reader_read_line",
+        params: MANAGED_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_reader_lines",
+        params: MANAGED_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_writer_create",
+        params: STRING_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl { symbol: "__runeway_writer_stdout", params: &[], ret: AbiType::Pointer },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_writer_write",
+        params: WRITER_WRITE_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_writer_write_line",
+        params: WRITER_WRITE_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_writer_flush",
+        params: MANAGED_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl { symbol: "__runeway_panic", params: PANIC_PARAMS, ret: AbiType::Unit },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_lines_next",
+        params: MANAGED_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_set_trace",
+        params: SET_TRACE_PARAMS,
+        ret: AbiType::Unit,
+    },
+    RuntimeFunctionDecl { symbol: "__runeway_collect_cycles", params: &[], ret: AbiType::Unit },
+    RuntimeFunctionDecl { symbol: "__runeway_begin_run", params: &[], ret: AbiType::Unit },
+    RuntimeFunctionDecl { symbol: "__runeway_end_run", params: &[AbiType::I8], ret: AbiType::Unit },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_int_to_string",
+        params: &[AbiType::I64, AbiType::I8],
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_str_cmp",
+        params: STRING_PAIR_PARAMS,
+        ret: AbiType::I64,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_str_hash",
+        params: &[AbiType::Pointer, AbiType::Usize],
+        ret: AbiType::I64,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_map_new_records",
+        params: MAP_NEW_RECORDS_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_random_empty_range",
+        params: INT_PAIR_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_json_check_kind",
+        params: JSON_CHECK_KIND_PARAMS,
+        ret: AbiType::Pointer,
+    },
+    RuntimeFunctionDecl {
+        symbol: "__runeway_json_check_int",
+        params: JSON_CHECK_INT_PARAMS,
+        ret: AbiType::Pointer,
+    },
 ];
 
 pub fn runtime_function(id: RuntimeFunctionId) -> Option<&'static RuntimeFunctionDecl> {
@@ -49,7 +630,7 @@ pub fn runtime_function(id: RuntimeFunctionId) -> Option<&'static RuntimeFunctio
 
 #[cfg(test)]
 mod tests {
-    use super::{AbiType, RUNTIME_PRINTLN, runtime_function};
+    use super::{AbiType, RUNTIME_ALLOC, RUNTIME_PRINTLN, runtime_function};
 
     #[test]
     fn exposes_stable_runtime_declarations() {
@@ -58,4 +639,12 @@ mod tests {
         assert_eq!(declaration.params, &[AbiType::Pointer, AbiType::Usize]);
         assert_eq!(declaration.ret, AbiType::Unit);
     }
+
+    #[test]
+    fn allocation_returns_a_pointer() {
+        let declaration = runtime_function(RUNTIME_ALLOC).expect("alloc declaration");
+        assert_eq!(declaration.symbol, "__runeway_alloc");
+        assert_eq!(declaration.params, &[AbiType::Usize, AbiType::Usize]);
+        assert_eq!(declaration.ret, AbiType::Pointer);
+    }
 }
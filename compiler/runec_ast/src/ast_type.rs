@@ -7,9 +7,32 @@ pub enum TypeAnnotation<'src> {
     Unit,
     Ident(&'src str),
     Tuple(Box<[SpannedTypeAnnotation<'src>]>),
-    Path { from_root: bool, path: Box<[SpannedTypeAnnotation<'src>]> },
-    Array { item: Box<SpannedTypeAnnotation<'src>>, length: SpannedExpr<'src> },
-    Generic { ty: Box<SpannedTypeAnnotation<'src>>, args: Box<[GenericArgument<'src>]> },
+    Path {
+        from_root: bool,
+        path: Box<[SpannedTypeAnnotation<'src>]>,
+    },
+    Array {
+        item: Box<SpannedTypeAnnotation<'src>>,
+        length: SpannedExpr<'src>,
+    },
+    Generic {
+        ty: Box<SpannedTypeAnnotation<'src>>,
+        args: Box<[GenericArgument<'src>]>,
+    },
+    /// `T[]`
+    Slice(Box<SpannedTypeAnnotation<'src>>),
+    /// `T?`
+    Nullable(Box<SpannedTypeAnnotation<'src>>),
+    /// `*const T` or `*mut T`
+    Pointer {
+        mutable: bool,
+        pointee: Box<SpannedTypeAnnotation<'src>>,
+    },
+    /// `act(A, B) -> R`
+    Function {
+        params: Box<[SpannedTypeAnnotation<'src>]>,
+        ret: Box<SpannedTypeAnnotation<'src>>,
+    },
 }
 
 #[derive(Debug, PartialEq)]
@@ -3,9 +3,9 @@ use std::borrow::Cow;
 use runec_source::span::Spanned;
 
 use crate::SpannedStr;
-use crate::ast_type::SpannedTypeAnnotation;
+use crate::ast_type::{GenericArgument, SpannedTypeAnnotation};
 use crate::operators::{BinaryOp, UnaryOp};
-use crate::statement::{SpannedStmtBlock, StmtBlock};
+use crate::statement::{SpannedDestructPattern, SpannedStmtBlock};
 
 #[derive(Debug, PartialEq)]
 pub enum Expr<'src> {
@@ -14,30 +14,105 @@ pub enum Expr<'src> {
     If(IfExpr<'src>),
     Ident(&'src str),
     Path(Box<[SpannedStr<'src>]>),
-    TypeCast { from: Box<SpannedExpr<'src>>, ty: Box<SpannedTypeAnnotation<'src>> },
-    Call { callee: Box<SpannedExpr<'src>>, args: Box<[SpannedExpr<'src>]> },
-    Binary { lhs: Box<SpannedExpr<'src>>, rhs: Box<SpannedExpr<'src>>, op: BinaryOp },
-    Unary { operand: Box<SpannedExpr<'src>>, op: UnaryOp },
+    /// `path::<A, B>`; a function or builtin path with explicit generic
+    /// arguments, such as `size_of::<i32>`.
+    Turbofish {
+        path: Box<SpannedExpr<'src>>,
+        args: Box<[GenericArgument<'src>]>,
+    },
+    TypeCast {
+        from: Box<SpannedExpr<'src>>,
+        ty: Box<SpannedTypeAnnotation<'src>>,
+    },
+    Call {
+        callee: Box<SpannedExpr<'src>>,
+        args: Box<[SpannedExpr<'src>]>,
+    },
+    Binary {
+        lhs: Box<SpannedExpr<'src>>,
+        rhs: Box<SpannedExpr<'src>>,
+        op: BinaryOp,
+    },
+    Unary {
+        operand: Box<SpannedExpr<'src>>,
+        op: UnaryOp,
+    },
     Tuple(Box<[SpannedExpr<'src>]>),
     FullyDefinedArray(Box<[SpannedExpr<'src>]>),
-    RepeatingArray { value: Box<SpannedExpr<'src>>, count: Box<SpannedExpr<'src>> },
+    RepeatingArray {
+        value: Box<SpannedExpr<'src>>,
+        count: Box<SpannedExpr<'src>>,
+    },
+    /// `*ptr`; reads through a raw pointer.
     Deref(Box<SpannedExpr<'src>>),
-    AttributeAccess { value: Box<SpannedExpr<'src>>, name: SpannedStr<'src> },
+    /// `unsafe { ... }`
+    Unsafe(SpannedStmtBlock<'src>),
+    AttributeAccess {
+        value: Box<SpannedExpr<'src>>,
+        name: SpannedStr<'src>,
+    },
+    /// `value?`; passes an error thrown by `value` on to the caller.
+    Try(Box<SpannedExpr<'src>>),
+    /// `value[index]`, where `index` may be a range.
+    Index {
+        value: Box<SpannedExpr<'src>>,
+        index: Box<SpannedExpr<'src>>,
+    },
+    /// `|a, b: int| body`
+    Closure {
+        params: Box<[ClosureParam<'src>]>,
+        body: Box<SpannedExpr<'src>>,
+    },
+    /// `start..end`, or `start..=end` when `inclusive`.
+    Range {
+        start: Box<SpannedExpr<'src>>,
+        end: Box<SpannedExpr<'src>>,
+        inclusive: bool,
+    },
+    /// `for binding in iter { ... }`, where the binding may destructure
+    /// tuples like `let` does: `for (i, item) in iter { ... }`.
+    For {
+        binding: SpannedDestructPattern<'src>,
+        iter: Box<SpannedExpr<'src>>,
+        body: SpannedStmtBlock<'src>,
+    },
+    /// `Name { field: value, ... }`; `path` is an `Ident` or a `Path`.
+    Struct {
+        path: Box<SpannedExpr<'src>>,
+        fields: Box<[FieldValue<'src>]>,
+    },
 }
 
 pub type SpannedExpr<'src> = Spanned<Expr<'src>>;
 
+/// A closure parameter; its type may be left out when the closure is passed
+/// where a function type is expected.
+#[derive(Debug, PartialEq)]
+pub struct ClosureParam<'src> {
+    pub ident: SpannedStr<'src>,
+    pub ty: Option<SpannedTypeAnnotation<'src>>,
+}
+
+/// `name: value` in a struct expression.
+#[derive(Debug, PartialEq)]
+pub struct FieldValue<'src> {
+    pub name: SpannedStr<'src>,
+    pub value: SpannedExpr<'src>,
+}
+
 #[derive(Debug, PartialEq)]
 pub struct IfExpr<'src> {
     pub cond: Box<SpannedExpr<'src>>,
-    pub then: StmtBlock<'src>,
+    pub then: SpannedStmtBlock<'src>,
     pub else_: Option<ElseBranch<'src>>,
 }
 
+pub type SpannedIfExpr<'src> = Spanned<IfExpr<'src>>;
+
 #[derive(Debug, PartialEq)]
 pub enum ElseBranch<'src> {
-    Block(StmtBlock<'src>),
-    If(Box<IfExpr<'src>>),
+    Block(SpannedStmtBlock<'src>),
+    If(Box<SpannedIfExpr<'src>>),
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -103,4 +178,5 @@ pub enum PrimitiveValue<'src> {
     Float { value: f64, suffix: Option<FloatSuffix> },
     Char(char),
     String(Cow<'src, str>),
+    Null,
 }
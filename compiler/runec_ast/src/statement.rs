@@ -18,17 +18,86 @@ pub enum Stmt<'src> {
         ident: SpannedStr<'src>,
         expr: SpannedExpr<'src>,
     },
+    /// `value[index] = expr` or `*pointer = expr`, where `target` is the
+    /// [`Expr::Index`] or [`Expr::Deref`].
+    AssignPlace {
+        target: SpannedExpr<'src>,
+        expr: SpannedExpr<'src>,
+    },
     DefineConst {
+        vis: Visibility,
         ident: SpannedStr<'src>,
         ty: SpannedTypeAnnotation<'src>,
         expr: SpannedExpr<'src>,
     },
     DefineFunction {
+        vis: Visibility,
         ident: SpannedStr<'src>,
+        generics: Box<[GenericParam<'src>]>,
         args: Box<[FunctionArg<'src>]>,
         ret_ty: SpannedTypeAnnotation<'src>,
+        /// Error type from a `throws` clause.
+        throws: Option<SpannedTypeAnnotation<'src>>,
         body: SpannedStmtBlock<'src>,
     },
+    /// `extern act name(a: T) -> R;`; a native function linked in by symbol name.
+    DefineExternFunction {
+        vis: Visibility,
+        ident: SpannedStr<'src>,
+        args: Box<[FunctionArg<'src>]>,
+        ret_ty: SpannedTypeAnnotation<'src>,
+    },
+    /// `throw value;`
+    Throw(SpannedExpr<'src>),
+    /// `break;`; leaves the innermost `for` loop.
+    Break,
+    /// `struct Name<T> { field: T }`
+    DefineStruct {
+        vis: Visibility,
+        ident: SpannedStr<'src>,
+        generics: Box<[GenericParam<'src>]>,
+        fields: Box<[StructField<'src>]>,
+    },
+    /// `union Name<T> { a: T, b: int }`; all fields share the same storage.
+    DefineUnion {
+        vis: Visibility,
+        ident: SpannedStr<'src>,
+        generics: Box<[GenericParam<'src>]>,
+        fields: Box<[StructField<'src>]>,
+    },
+    /// `alias Name<T> = Type;`
+    DefineAlias {
+        vis: Visibility,
+        ident: SpannedStr<'src>,
+        generics: Box<[GenericParam<'src>]>,
+        ty: SpannedTypeAnnotation<'src>,
+    },
+    /// `import a::b;` loads the module file `a/b.rnw`.
+    Import(Box<[SpannedStr<'src>]>),
+    /// `use a::b;`, `use a::b as c;` or `use a::*;`
+    Use {
+        from_root: bool,
+        path: Box<[SpannedStr<'src>]>,
+        kind: UseKind<'src>,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+pub enum UseKind<'src> {
+    /// Binds the last segment of the path, or `alias` when renamed with `as`.
+    Single { alias: Option<SpannedStr<'src>> },
+    /// `::*` binds every name of the module.
+    Glob,
+}
+
+/// Whether an item can be named from outside the module defining it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Visibility {
+    /// Visible in the defining module and its submodules.
+    #[default]
+    Private,
+    /// `pub`
+    Public,
 }
 
 pub type StmtBlock<'src> = Box<[SpannedStmt<'src>]>;
@@ -40,6 +109,28 @@ pub struct FunctionArg<'src> {
     pub ty: SpannedTypeAnnotation<'src>,
 }
 
+#[derive(Debug, PartialEq)]
+pub struct StructField<'src> {
+    pub vis: Visibility,
+    pub ident: SpannedStr<'src>,
+    pub ty: SpannedTypeAnnotation<'src>,
+}
+
+/// Generic parameter of an `act`, `struct`, `union` or `alias` declaration.
+#[derive(Debug, PartialEq)]
+pub struct GenericParam<'src> {
+    pub ident: SpannedStr<'src>,
+    pub kind: GenericParamKind<'src>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum GenericParamKind<'src> {
+    /// `T` or `T: First + Second`, bounded by contracts.
+    Type { bounds: Box<[SpannedTypeAnnotation<'src>]> },
+    /// `const N: int`
+    Const { ty: SpannedTypeAnnotation<'src> },
+}
+
 pub type SpannedStmt<'src> = Spanned<Stmt<'src>>;
 
 #[derive(Debug, PartialEq)]
@@ -1,6 +1,26 @@
+use std::borrow::Cow;
 use std::fmt;
 
-use runec_abi::{RUNTIME_PRINT, RUNTIME_PRINTLN, RuntimeFunctionId};
+use runec_abi::{
+    ROUND_CEILING, ROUND_DOWN, ROUND_FLOOR, ROUND_HALF_EVEN, ROUND_HALF_UP, ROUND_UP, RUNTIME_ACOS,
+    RUNTIME_APPEND, RUNTIME_ASIN, RUNTIME_ATAN, RUNTIME_ATAN2, RUNTIME_COS, RUNTIME_DECIMAL_ADD,
+    RUNTIME_DECIMAL_CMP, RUNTIME_DECIMAL_DIV, RUNTIME_DECIMAL_FROM_INT, RUNTIME_DECIMAL_MUL,
+    RUNTIME_DECIMAL_NEG, RUNTIME_DECIMAL_PARSE, RUNTIME_DECIMAL_ROUND, RUNTIME_DECIMAL_SCALE,
+    RUNTIME_DECIMAL_SUB, RUNTIME_DECIMAL_TO_STRING, RUNTIME_EXISTS, RUNTIME_EXP, RUNTIME_FLUSH,
+    RUNTIME_INPUT, RUNTIME_INSTANT_ELAPSED, RUNTIME_INSTANT_NOW, RUNTIME_JSON_AS_BOOL,
+    RUNTIME_JSON_AS_NUMBER, RUNTIME_JSON_AS_STR, RUNTIME_JSON_AT, RUNTIME_JSON_FROM_BOOL,
+    RUNTIME_JSON_FROM_NUMBER, RUNTIME_JSON_FROM_STR, RUNTIME_JSON_GET, RUNTIME_JSON_INSERT,
+    RUNTIME_JSON_IS_NULL, RUNTIME_JSON_KEYS, RUNTIME_JSON_LEN, RUNTIME_JSON_NEW_ARRAY,
+    RUNTIME_JSON_NEW_NULL, RUNTIME_JSON_NEW_OBJECT, RUNTIME_JSON_PARSE, RUNTIME_JSON_PRETTY,
+    RUNTIME_JSON_PUSH, RUNTIME_JSON_STRINGIFY, RUNTIME_LINES_NEXT, RUNTIME_LIST_DIR, RUNTIME_LN,
+    RUNTIME_LOG2, RUNTIME_LOG10, RUNTIME_POW, RUNTIME_PRINT, RUNTIME_PRINTLN, RUNTIME_RANDOM_FLOAT,
+    RUNTIME_RANDOM_FLOAT_RANGE, RUNTIME_RANDOM_INT_RANGE, RUNTIME_RANDOM_NEXT,
+    RUNTIME_RANDOM_OS_SEED, RUNTIME_RANDOM_SEED, RUNTIME_READ_TO_STRING, RUNTIME_READER_LINES,
+    RUNTIME_READER_OPEN, RUNTIME_READER_READ_LINE, RUNTIME_READER_STDIN, RUNTIME_REMOVE,
+    RUNTIME_ROUND, RUNTIME_SET_STDOUT_BUFFERED, RUNTIME_SIN, RUNTIME_SLEEP, RUNTIME_TAN,
+    RUNTIME_UNIX_TIME, RUNTIME_WRITE, RUNTIME_WRITER_CREATE, RUNTIME_WRITER_FLUSH,
+    RUNTIME_WRITER_STDOUT, RUNTIME_WRITER_WRITE, RUNTIME_WRITER_WRITE_LINE, RuntimeFunctionId,
+};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct BuiltinId(u32);
@@ -30,6 +50,20 @@ impl ContractId {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct BuiltinTypeId(u32);
+
+impl BuiltinTypeId {
+    pub const fn from_index(index: usize) -> Self {
+        assert!(index <= u32::MAX as usize);
+        Self(index as u32)
+    }
+
+    pub const fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum TypeBits {
     B8,
@@ -41,32 +75,257 @@ pub enum TypeBits {
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum PrimitiveType {
+    Bool,
+    Char,
+    /// Integers of every width and signedness.
+    Int,
+    /// Floats of every width.
+    Float,
     Str,
 }
 
+/// Type in the signature of a builtin, written in terms of its generic
+/// parameters.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BuiltinTy {
+    Unit,
+    Bool,
+    Str,
+    /// `usize`, used for lengths and indices.
+    Usize,
+    /// `u64`, used for counts that are not lengths, such as nanoseconds.
+    U64,
+    I64,
+    F64,
+    /// Generic parameter of the builtin, by index.
+    Generic(usize),
+    /// `T?`
+    Nullable(&'static BuiltinTy),
+    /// `T[]`
+    Slice(&'static BuiltinTy),
+    /// Builtin type applied to generic arguments, such as `Vec<T>`.
+    Builtin(BuiltinTypeId, &'static [BuiltinTy]),
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum TypeConstraint {
+    /// Accepts a value of any type.
+    Any,
     Implements(ContractId),
+    /// Accepts a value of exactly this type. The generic parameters of the
+    /// builtin are inferred from the arguments and the expected result.
+    Exactly(BuiltinTy),
+    /// Accepts a value of any of these types, checked like `Exactly`
+    /// against the first one the argument has the shape of.
+    OneOf(&'static [BuiltinTy]),
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum BuiltinReturn {
     Unit,
+    Value(BuiltinTy),
+    /// `T?` the runtime hands back as a pointer to a `T`, or null for
+    /// `null`.
+    Slot(BuiltinTy),
+    /// `T` the runtime hands back as a pointer to it, for values that do
+    /// not fit in a single register, such as strings.
+    Indirect(BuiltinTy),
+    /// `T`, or a thrown `str` error message. The runtime hands back a
+    /// pointer to both laid out like a MIR fallible value.
+    Fallible(BuiltinTy),
+    /// Never returns, like `panic`.
+    Never,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum BuiltinLowering {
     Runtime(RuntimeFunctionId),
+    /// Consumes the argument and destroys it at the call site.
+    Drop,
+    /// Expanded by the code generator for the argument types at hand.
+    Intrinsic(Intrinsic),
+    /// Constant such as `PI`: the bits of an `f64`, or the value itself for
+    /// scalar builtin types such as `RoundingMode`. The builtin is used as a
+    /// value rather than called.
+    Constant(u64),
+    /// Reads and writes the places passed as its leading arguments at the
+    /// call site.
+    Place(PlaceOp),
+    /// Size in bytes of the generic argument, taken from its layout when
+    /// the call is lowered.
+    SizeOf,
+    /// Alignment in bytes of the generic argument, like [`Self::SizeOf`].
+    AlignOf,
+    /// Ends the program with the message and the source location of the
+    /// call; control never returns to the caller.
+    Panic,
+    /// Builds the JSON document of the argument, expanded by MIR lowering
+    /// for its type.
+    ToJson,
+    /// Reads a value of the generic argument out of a JSON document, or
+    /// throws a message naming the first mismatch. Expanded like
+    /// [`Self::ToJson`].
+    FromJson,
+    /// The default value of the generic argument, as given by the
+    /// `Default` contract; what [`PlaceOp::Take`] leaves behind.
+    Default,
+}
+
+/// Builtin of `core::mem` that takes places rather than values: local
+/// bindings or elements, each evaluated once.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PlaceOp {
+    /// `swap(a, b)`; exchanges the values of two places.
+    Swap,
+    /// `replace(place, value)`; stores `value` and returns the previous
+    /// value.
+    Replace,
+    /// `take(place)`; stores the default value of the place's type, as
+    /// given by the `Default` contract, and returns the previous value.
+    Take,
+}
+
+impl PlaceOp {
+    /// Number of leading arguments that are places.
+    pub const fn places(self) -> usize {
+        match self {
+            PlaceOp::Swap => 2,
+            PlaceOp::Replace | PlaceOp::Take => 1,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Intrinsic {
+    /// `Vec::new()`
+    VecNew,
+    /// `v.push(value)`
+    VecPush,
+    /// `v.pop()`; `null` once the vector is empty.
+    VecPop,
+    /// `len()` of a vector, a slice, a map or a set.
+    Len,
+    /// `HashMap::new()` or `HashSet::new()`
+    MapNew,
+    /// `m.insert(key, value)`; the previous value, or `null` for a new key.
+    MapInsert,
+    /// `s.insert(value)`; `true` if the value was not in the set yet.
+    SetInsert,
+    /// `m.get(key)`; `null` if the key is absent.
+    MapGet,
+    /// `m.contains_key(key)` or `s.contains(value)`
+    MapContains,
+    /// `m.remove(key)`; the removed value, or `null` if the key was absent.
+    MapRemove,
+    /// `s.remove(value)`; `true` if the value was in the set.
+    SetRemove,
+    /// `m.keys()` or `s.to_vec()`; a new vector of the keys.
+    MapKeys,
+    /// `m.values()`; a new vector of the values, in the order of `keys()`.
+    MapValues,
+    /// `sqrt(x)` of a float.
+    Sqrt,
+    /// `abs(x)` of a number; unsigned integers are returned as is.
+    Abs,
+    /// `min(a, b)` of two numbers.
+    Min,
+    /// `max(a, b)` of two numbers.
+    Max,
+    /// `floor(x)` of a float.
+    Floor,
+    /// `ceil(x)` of a float.
+    Ceil,
+    /// `trunc(x)` of a float.
+    Trunc,
+    /// `checked_add(a, b)` of two integers; `null` on overflow.
+    CheckedAdd,
+    /// `checked_sub(a, b)` of two integers; `null` on overflow.
+    CheckedSub,
+    /// `checked_mul(a, b)` of two integers; `null` on overflow.
+    CheckedMul,
+    /// `wrapping_add(a, b)` of two integers, modulo their width.
+    WrappingAdd,
+    /// `wrapping_sub(a, b)` of two integers, modulo their width.
+    WrappingSub,
+    /// `wrapping_mul(a, b)` of two integers, modulo their width.
+    WrappingMul,
+    /// Unsigned addition clamped to the largest value.
+    SaturatingAdd,
+    /// Unsigned subtraction clamped to zero.
+    SaturatingSub,
+    /// Multiplication of an integer by a constant, e.g. seconds to
+    /// nanoseconds.
+    MulConst(u64),
+    /// Unsigned division of an integer by a constant.
+    DivConst(u64),
+    /// Conversion of an integer count of nanoseconds to `f64` seconds.
+    NanosToSecs,
+    /// Decimal text of an integer of at most 64 bits, for `Display`.
+    IntToString,
+    /// `-1`, `0` or `1` as an `i64` as the first string orders before, equal
+    /// to or after the second, for comparisons of strings.
+    StrCmp,
+    /// Conversion of a number to the number type of the result, truncating
+    /// floats toward zero. Used to read numbers out of JSON documents.
+    NumberCast,
+    /// `shuffle(v)` of a vector or slice, in place.
+    Shuffle,
+}
+
+impl Intrinsic {
+    /// Whether the method changes the contents of its receiver, which must
+    /// then be rooted at a mutable binding.
+    pub const fn writes_receiver(self) -> bool {
+        matches!(
+            self,
+            Intrinsic::VecPush
+                | Intrinsic::VecPop
+                | Intrinsic::MapInsert
+                | Intrinsic::SetInsert
+                | Intrinsic::MapRemove
+                | Intrinsic::SetRemove
+        )
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct ContractDecl {
     pub canonical_name: &'static str,
+    /// Names of the type parameters, given in bounds as
+    /// `T: IntoIterator<U>`.
+    pub generics: &'static [&'static str],
+    /// Methods every implementation provides.
+    pub methods: &'static [&'static str],
+}
+
+/// Generic type provided by the compiler, such as `core::ops::Range<T>`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BuiltinTypeDecl {
+    pub name: &'static str,
+    pub module: &'static str,
+    /// Names of the type parameters.
+    pub generics: &'static [&'static str],
+    /// Contracts the type implements for any generic arguments.
+    pub implements: &'static [ContractId],
+    /// Contracts each type parameter must implement, by index. Parameters
+    /// past the end have no bounds.
+    pub bounds: &'static [&'static [ContractId]],
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct BuiltinDecl {
     pub name: &'static str,
+    /// Module the builtin lives in, e.g. `core::mem` for `drop`, or the
+    /// builtin type it is a method or associated function of, e.g.
+    /// `core::vec::Vec` for `push`.
+    pub module: &'static str,
+    /// Names of the generic parameters used by [`TypeConstraint::Exactly`]
+    /// and [`BuiltinReturn::Value`].
+    pub generics: &'static [&'static str],
+    /// Contracts each generic parameter must implement, by index, as in
+    /// [`BuiltinTypeDecl::bounds`].
+    pub bounds: &'static [&'static [ContractId]],
+    /// Parameters, starting with the receiver for methods.
     pub params: &'static [TypeConstraint],
     pub ret: BuiltinReturn,
     pub lowering: BuiltinLowering,
@@ -78,34 +337,1783 @@ pub struct BuiltinContractImpl {
     pub for_type: PrimitiveType,
 }
 
+/// Module of the standard library written in RuneWay, compiled along with
+/// every crate.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct StdModule {
+    /// Module path, such as `["std", "itertools"]`.
+    pub path: &'static [&'static str],
+    pub source: &'static str,
+}
+
+/// Values can be printed. Integers are converted to their decimal text and
+/// builtin types other than `str` to a string by their `to_string` method
+/// first.
 pub const DISPLAY_CONTRACT: ContractId = ContractId::from_index(0);
+/// `next()` returns the next item, or `null` once the iterator is exhausted.
+pub const ITERATOR_CONTRACT: ContractId = ContractId::from_index(1);
+/// Values can be hashed; required of hash map keys and set elements.
+pub const HASH_CONTRACT: ContractId = ContractId::from_index(2);
+/// Values can be compared for equality.
+pub const EQ_CONTRACT: ContractId = ContractId::from_index(3);
+/// Implemented by integers of every width and signedness.
+pub const INTEGER_CONTRACT: ContractId = ContractId::from_index(4);
+/// Implemented by floats of every width.
+pub const FLOAT_CONTRACT: ContractId = ContractId::from_index(5);
+/// Implemented by integers and floats.
+pub const NUMBER_CONTRACT: ContractId = ContractId::from_index(6);
+/// `+`, lowered to the `add` method of the implementing type.
+pub const ADD_CONTRACT: ContractId = ContractId::from_index(7);
+/// `-`, lowered to the `sub` method of the implementing type.
+pub const SUB_CONTRACT: ContractId = ContractId::from_index(8);
+/// `*`, lowered to the `mul` method of the implementing type.
+pub const MUL_CONTRACT: ContractId = ContractId::from_index(9);
+/// Types with a default value: zero, `false`, `'\0'`, the empty string or
+/// collection, and `null` for every nullable type. `take` leaves it behind.
+pub const DEFAULT_CONTRACT: ContractId = ContractId::from_index(10);
+/// Values are ordered by `<`, `<=`, `>` and `>=`, and compared by `==` and
+/// `!=`. Comparisons of builtin types are lowered to comparing the result of
+/// their `cmp` method with zero; numbers, chars and strings are compared
+/// directly.
+pub const ORD_CONTRACT: ContractId = ContractId::from_index(11);
+/// Values convert to JSON documents with `to_json`: booleans, numbers and
+/// strings to their JSON counterparts, `null` to `null`, vectors and slices
+/// to arrays, structs to objects keyed by their field names, and enum
+/// variants without fields to their names. Variants with fields become an
+/// object with the name of the variant as its only key, holding an object
+/// of the fields of a struct variant or an array of those of a tuple one.
+pub const TO_JSON_CONTRACT: ContractId = ContractId::from_index(12);
+/// Values are read back out of JSON documents with `from_json`, the
+/// inverse of `to_json` for every type but slices.
+pub const FROM_JSON_CONTRACT: ContractId = ContractId::from_index(13);
+/// Values a `for` loop walks, yielding items of the contract's type
+/// argument: vectors and slices by index, and iterators by `next()`.
+pub const INTO_ITERATOR_CONTRACT: ContractId = ContractId::from_index(14);
+/// Contracts user structs and enums implement when all of their fields do.
+pub const DERIVED_CONTRACTS: &[ContractId] = &[HASH_CONTRACT, EQ_CONTRACT];
+/// Contracts user structs and enums, but not tuples, implement when all of
+/// their fields do.
+pub const DERIVED_JSON_CONTRACTS: &[ContractId] = &[TO_JSON_CONTRACT, FROM_JSON_CONTRACT];
 pub const PRINT: BuiltinId = BuiltinId::from_index(0);
 pub const PRINTLN: BuiltinId = BuiltinId::from_index(1);
+pub const DROP: BuiltinId = BuiltinId::from_index(2);
+pub const VEC_NEW: BuiltinId = BuiltinId::from_index(3);
+pub const VEC_PUSH: BuiltinId = BuiltinId::from_index(4);
+pub const VEC_POP: BuiltinId = BuiltinId::from_index(5);
+pub const VEC_LEN: BuiltinId = BuiltinId::from_index(6);
+pub const SLICE_LEN: BuiltinId = BuiltinId::from_index(7);
+pub const MAP_NEW: BuiltinId = BuiltinId::from_index(8);
+pub const MAP_INSERT: BuiltinId = BuiltinId::from_index(9);
+pub const MAP_GET: BuiltinId = BuiltinId::from_index(10);
+pub const MAP_CONTAINS_KEY: BuiltinId = BuiltinId::from_index(11);
+pub const MAP_REMOVE: BuiltinId = BuiltinId::from_index(12);
+pub const MAP_LEN: BuiltinId = BuiltinId::from_index(13);
+pub const MAP_KEYS: BuiltinId = BuiltinId::from_index(14);
+pub const MAP_VALUES: BuiltinId = BuiltinId::from_index(15);
+pub const SET_NEW: BuiltinId = BuiltinId::from_index(16);
+pub const SET_INSERT: BuiltinId = BuiltinId::from_index(17);
+pub const SET_CONTAINS: BuiltinId = BuiltinId::from_index(18);
+pub const SET_REMOVE: BuiltinId = BuiltinId::from_index(19);
+pub const SET_LEN: BuiltinId = BuiltinId::from_index(20);
+pub const SET_TO_VEC: BuiltinId = BuiltinId::from_index(21);
+pub const INPUT: BuiltinId = BuiltinId::from_index(22);
+pub const MATH_SQRT: BuiltinId = BuiltinId::from_index(23);
+pub const MATH_ABS: BuiltinId = BuiltinId::from_index(24);
+pub const MATH_MIN: BuiltinId = BuiltinId::from_index(25);
+pub const MATH_MAX: BuiltinId = BuiltinId::from_index(26);
+pub const MATH_FLOOR: BuiltinId = BuiltinId::from_index(27);
+pub const MATH_CEIL: BuiltinId = BuiltinId::from_index(28);
+pub const MATH_TRUNC: BuiltinId = BuiltinId::from_index(29);
+pub const MATH_ROUND: BuiltinId = BuiltinId::from_index(30);
+pub const MATH_POW: BuiltinId = BuiltinId::from_index(31);
+pub const MATH_SIN: BuiltinId = BuiltinId::from_index(32);
+pub const MATH_COS: BuiltinId = BuiltinId::from_index(33);
+pub const MATH_TAN: BuiltinId = BuiltinId::from_index(34);
+pub const MATH_ASIN: BuiltinId = BuiltinId::from_index(35);
+pub const MATH_ACOS: BuiltinId = BuiltinId::from_index(36);
+pub const MATH_ATAN: BuiltinId = BuiltinId::from_index(37);
+pub const MATH_ATAN2: BuiltinId = BuiltinId::from_index(38);
+pub const MATH_EXP: BuiltinId = BuiltinId::from_index(39);
+pub const MATH_LN: BuiltinId = BuiltinId::from_index(40);
+pub const MATH_LOG2: BuiltinId = BuiltinId::from_index(41);
+pub const MATH_LOG10: BuiltinId = BuiltinId::from_index(42);
+pub const MATH_PI: BuiltinId = BuiltinId::from_index(43);
+pub const MATH_E: BuiltinId = BuiltinId::from_index(44);
+pub const MATH_TAU: BuiltinId = BuiltinId::from_index(45);
+pub const MATH_CHECKED_ADD: BuiltinId = BuiltinId::from_index(46);
+pub const MATH_CHECKED_SUB: BuiltinId = BuiltinId::from_index(47);
+pub const MATH_CHECKED_MUL: BuiltinId = BuiltinId::from_index(48);
+pub const MATH_WRAPPING_ADD: BuiltinId = BuiltinId::from_index(49);
+pub const MATH_WRAPPING_SUB: BuiltinId = BuiltinId::from_index(50);
+pub const MATH_WRAPPING_MUL: BuiltinId = BuiltinId::from_index(51);
+pub const FILES_READ_TO_STRING: BuiltinId = BuiltinId::from_index(52);
+pub const FILES_WRITE: BuiltinId = BuiltinId::from_index(53);
+pub const FILES_APPEND: BuiltinId = BuiltinId::from_index(54);
+pub const FILES_EXISTS: BuiltinId = BuiltinId::from_index(55);
+pub const FILES_REMOVE: BuiltinId = BuiltinId::from_index(56);
+pub const FILES_LIST_DIR: BuiltinId = BuiltinId::from_index(57);
+pub const INSTANT_NOW: BuiltinId = BuiltinId::from_index(58);
+pub const INSTANT_ELAPSED: BuiltinId = BuiltinId::from_index(59);
+pub const INSTANT_DURATION_SINCE: BuiltinId = BuiltinId::from_index(60);
+pub const DURATION_FROM_SECS: BuiltinId = BuiltinId::from_index(61);
+pub const DURATION_FROM_MILLIS: BuiltinId = BuiltinId::from_index(62);
+pub const DURATION_FROM_MICROS: BuiltinId = BuiltinId::from_index(63);
+pub const DURATION_FROM_NANOS: BuiltinId = BuiltinId::from_index(64);
+pub const DURATION_AS_SECS: BuiltinId = BuiltinId::from_index(65);
+pub const DURATION_AS_MILLIS: BuiltinId = BuiltinId::from_index(66);
+pub const DURATION_AS_MICROS: BuiltinId = BuiltinId::from_index(67);
+pub const DURATION_AS_NANOS: BuiltinId = BuiltinId::from_index(68);
+pub const DURATION_AS_SECS_F64: BuiltinId = BuiltinId::from_index(69);
+pub const DURATION_ADD: BuiltinId = BuiltinId::from_index(70);
+pub const DURATION_SUB: BuiltinId = BuiltinId::from_index(71);
+pub const TIME_SLEEP: BuiltinId = BuiltinId::from_index(72);
+pub const TIME_UNIX_TIME: BuiltinId = BuiltinId::from_index(73);
+pub const RANDOM_SEED: BuiltinId = BuiltinId::from_index(74);
+pub const RANDOM_OS_SEED: BuiltinId = BuiltinId::from_index(75);
+pub const RANDOM_NEXT_U64: BuiltinId = BuiltinId::from_index(76);
+pub const RANDOM_INT_RANGE: BuiltinId = BuiltinId::from_index(77);
+pub const RANDOM_FLOAT: BuiltinId = BuiltinId::from_index(78);
+pub const RANDOM_FLOAT_RANGE: BuiltinId = BuiltinId::from_index(79);
+pub const RANDOM_SHUFFLE: BuiltinId = BuiltinId::from_index(80);
+pub const JSON_PARSE: BuiltinId = BuiltinId::from_index(81);
+pub const JSON_STRINGIFY: BuiltinId = BuiltinId::from_index(82);
+pub const JSON_PRETTY: BuiltinId = BuiltinId::from_index(83);
+pub const JSON_NEW_NULL: BuiltinId = BuiltinId::from_index(84);
+pub const JSON_FROM_BOOL: BuiltinId = BuiltinId::from_index(85);
+pub const JSON_FROM_NUMBER: BuiltinId = BuiltinId::from_index(86);
+pub const JSON_FROM_STR: BuiltinId = BuiltinId::from_index(87);
+pub const JSON_NEW_ARRAY: BuiltinId = BuiltinId::from_index(88);
+pub const JSON_NEW_OBJECT: BuiltinId = BuiltinId::from_index(89);
+pub const JSON_IS_NULL: BuiltinId = BuiltinId::from_index(90);
+pub const JSON_AS_BOOL: BuiltinId = BuiltinId::from_index(91);
+pub const JSON_AS_NUMBER: BuiltinId = BuiltinId::from_index(92);
+pub const JSON_AS_STR: BuiltinId = BuiltinId::from_index(93);
+pub const JSON_LEN: BuiltinId = BuiltinId::from_index(94);
+pub const JSON_AT: BuiltinId = BuiltinId::from_index(95);
+pub const JSON_GET: BuiltinId = BuiltinId::from_index(96);
+pub const JSON_KEYS: BuiltinId = BuiltinId::from_index(97);
+pub const JSON_PUSH: BuiltinId = BuiltinId::from_index(98);
+pub const JSON_INSERT: BuiltinId = BuiltinId::from_index(99);
+pub const DECIMAL_PARSE: BuiltinId = BuiltinId::from_index(100);
+pub const DECIMAL_FROM_INT: BuiltinId = BuiltinId::from_index(101);
+pub const DECIMAL_TO_STRING: BuiltinId = BuiltinId::from_index(102);
+pub const DECIMAL_ADD: BuiltinId = BuiltinId::from_index(103);
+pub const DECIMAL_SUB: BuiltinId = BuiltinId::from_index(104);
+pub const DECIMAL_MUL: BuiltinId = BuiltinId::from_index(105);
+pub const DECIMAL_DIV: BuiltinId = BuiltinId::from_index(106);
+pub const DECIMAL_ROUND: BuiltinId = BuiltinId::from_index(107);
+pub const DECIMAL_CMP: BuiltinId = BuiltinId::from_index(108);
+pub const DECIMAL_NEG: BuiltinId = BuiltinId::from_index(109);
+pub const DECIMAL_SCALE: BuiltinId = BuiltinId::from_index(110);
+pub const ROUNDING_HALF_EVEN: BuiltinId = BuiltinId::from_index(111);
+pub const ROUNDING_HALF_UP: BuiltinId = BuiltinId::from_index(112);
+pub const ROUNDING_DOWN: BuiltinId = BuiltinId::from_index(113);
+pub const ROUNDING_UP: BuiltinId = BuiltinId::from_index(114);
+pub const ROUNDING_FLOOR: BuiltinId = BuiltinId::from_index(115);
+pub const ROUNDING_CEILING: BuiltinId = BuiltinId::from_index(116);
+pub const FLUSH: BuiltinId = BuiltinId::from_index(117);
+pub const SET_STDOUT_BUFFERED: BuiltinId = BuiltinId::from_index(118);
+pub const OPEN_READER: BuiltinId = BuiltinId::from_index(119);
+pub const STDIN_READER: BuiltinId = BuiltinId::from_index(120);
+pub const READER_READ_LINE: BuiltinId = BuiltinId::from_index(121);
+pub const READER_LINES: BuiltinId = BuiltinId::from_index(122);
+pub const CREATE_WRITER: BuiltinId = BuiltinId::from_index(123);
+pub const STDOUT_WRITER: BuiltinId = BuiltinId::from_index(124);
+pub const WRITER_WRITE: BuiltinId = BuiltinId::from_index(125);
+pub const WRITER_WRITE_LINE: BuiltinId = BuiltinId::from_index(126);
+pub const WRITER_FLUSH: BuiltinId = BuiltinId::from_index(127);
+pub const MEM_SIZE_OF: BuiltinId = BuiltinId::from_index(128);
+pub const MEM_ALIGN_OF: BuiltinId = BuiltinId::from_index(129);
+pub const MEM_SWAP: BuiltinId = BuiltinId::from_index(130);
+pub const MEM_REPLACE: BuiltinId = BuiltinId::from_index(131);
+pub const MEM_TAKE: BuiltinId = BuiltinId::from_index(132);
+pub const PANIC: BuiltinId = BuiltinId::from_index(133);
+pub const LINES_NEXT: BuiltinId = BuiltinId::from_index(134);
+pub const JSON_TO_JSON: BuiltinId = BuiltinId::from_index(135);
+pub const JSON_FROM_JSON: BuiltinId = BuiltinId::from_index(136);
+pub const DEFAULT: BuiltinId = BuiltinId::from_index(137);
+
+/// Builtins that are in scope everywhere by their bare name.
+pub const PRELUDE: &[BuiltinId] = &[PRINT, PRINTLN, INPUT, FLUSH, PANIC];
 
 const DISPLAY_PARAM: &[TypeConstraint] = &[TypeConstraint::Implements(DISPLAY_CONTRACT)];
+const ANY_PARAM: &[TypeConstraint] = &[TypeConstraint::Any];
+const ELEM: BuiltinTy = BuiltinTy::Generic(0);
+const VEC_OF_ELEM: BuiltinTy = BuiltinTy::Builtin(VEC, &[ELEM]);
+const VEC_PARAM: &[TypeConstraint] = &[TypeConstraint::Exactly(VEC_OF_ELEM)];
+const SLICE_OF_ELEM: BuiltinTy = BuiltinTy::Slice(&ELEM);
+const SLICE_PARAM: &[TypeConstraint] = &[TypeConstraint::Exactly(SLICE_OF_ELEM)];
+const SEQUENCE_PARAM: &[TypeConstraint] = &[TypeConstraint::OneOf(&[VEC_OF_ELEM, SLICE_OF_ELEM])];
+const KEY: BuiltinTy = BuiltinTy::Generic(0);
+const VALUE: BuiltinTy = BuiltinTy::Generic(1);
+const MAP: BuiltinTy = BuiltinTy::Builtin(HASH_MAP, &[KEY, VALUE]);
+const MAP_PARAM: &[TypeConstraint] = &[TypeConstraint::Exactly(MAP)];
+const MAP_KEY_PARAMS: &[TypeConstraint] =
+    &[TypeConstraint::Exactly(MAP), TypeConstraint::Exactly(KEY)];
+const SET: BuiltinTy = BuiltinTy::Builtin(HASH_SET, &[ELEM]);
+const SET_PARAM: &[TypeConstraint] = &[TypeConstraint::Exactly(SET)];
+const SET_ELEM_PARAMS: &[TypeConstraint] =
+    &[TypeConstraint::Exactly(SET), TypeConstraint::Exactly(ELEM)];
+const HASH_KEY: &[&[ContractId]] = &[&[HASH_CONTRACT, EQ_CONTRACT]];
+const NUM: BuiltinTy = BuiltinTy::Generic(0);
+const NUM_PARAM: &[TypeConstraint] = &[TypeConstraint::Exactly(NUM)];
+const NUM_PAIR_PARAMS: &[TypeConstraint] =
+    &[TypeConstraint::Exactly(NUM), TypeConstraint::Exactly(NUM)];
+const F64_PARAM: &[TypeConstraint] = &[TypeConstraint::Exactly(BuiltinTy::F64)];
+const F64_PAIR_PARAMS: &[TypeConstraint] =
+    &[TypeConstraint::Exactly(BuiltinTy::F64), TypeConstraint::Exactly(BuiltinTy::F64)];
+const STR_PARAM: &[TypeConstraint] = &[TypeConstraint::Exactly(BuiltinTy::Str)];
+const STR_PAIR_PARAMS: &[TypeConstraint] =
+    &[TypeConstraint::Exactly(BuiltinTy::Str), TypeConstraint::Exactly(BuiltinTy::Str)];
+const INSTANT_TY: BuiltinTy = BuiltinTy::Builtin(INSTANT, &[]);
+const INSTANT_PARAM: &[TypeConstraint] = &[TypeConstraint::Exactly(INSTANT_TY)];
+const DURATION_TY: BuiltinTy = BuiltinTy::Builtin(DURATION, &[]);
+const DURATION_PARAM: &[TypeConstraint] = &[TypeConstraint::Exactly(DURATION_TY)];
+const DURATION_PAIR_PARAMS: &[TypeConstraint] =
+    &[TypeConstraint::Exactly(DURATION_TY), TypeConstraint::Exactly(DURATION_TY)];
+const JSON_TY: BuiltinTy = BuiltinTy::Builtin(JSON, &[]);
+const JSON_PARAM: &[TypeConstraint] = &[TypeConstraint::Exactly(JSON_TY)];
+/// Type `to_json` and `from_json` convert to and from documents.
+const JSON_VALUE: BuiltinTy = BuiltinTy::Generic(0);
+const DECIMAL_TY: BuiltinTy = BuiltinTy::Builtin(DECIMAL, &[]);
+const DECIMAL_PARAM: &[TypeConstraint] = &[TypeConstraint::Exactly(DECIMAL_TY)];
+const DECIMAL_PAIR_PARAMS: &[TypeConstraint] =
+    &[TypeConstraint::Exactly(DECIMAL_TY), TypeConstraint::Exactly(DECIMAL_TY)];
+const READER_TY: BuiltinTy = BuiltinTy::Builtin(READER, &[]);
+const WRITER_TY: BuiltinTy = BuiltinTy::Builtin(WRITER, &[]);
+const LINES_TY: BuiltinTy = BuiltinTy::Builtin(LINES, &[]);
+const WRITER_PARAM: &[TypeConstraint] = &[TypeConstraint::Exactly(WRITER_TY)];
+const WRITER_STR_PARAMS: &[TypeConstraint] =
+    &[TypeConstraint::Exactly(WRITER_TY), TypeConstraint::Exactly(BuiltinTy::Str)];
+const ROUNDING_MODE_TY: BuiltinTy = BuiltinTy::Builtin(ROUNDING_MODE, &[]);
+const U64_PARAM: &[TypeConstraint] = &[TypeConstraint::Exactly(BuiltinTy::U64)];
+/// Type of the places `core::mem` builtins write to.
+const PLACE: BuiltinTy = BuiltinTy::Generic(0);
+const PLACE_PAIR_PARAMS: &[TypeConstraint] =
+    &[TypeConstraint::Exactly(PLACE), TypeConstraint::Exactly(PLACE)];
+const INTEGER_BOUND: &[&[ContractId]] = &[&[INTEGER_CONTRACT]];
+const FLOAT_BOUND: &[&[ContractId]] = &[&[FLOAT_CONTRACT]];
+const NUMBER_BOUND: &[&[ContractId]] = &[&[NUMBER_CONTRACT]];
+
+pub const RANGE: BuiltinTypeId = BuiltinTypeId::from_index(0);
+/// Growable vector; a managed value, so copies share the same elements.
+pub const VEC: BuiltinTypeId = BuiltinTypeId::from_index(1);
+/// Hash map from keys to values; a managed value like [`VEC`].
+pub const HASH_MAP: BuiltinTypeId = BuiltinTypeId::from_index(2);
+/// Hash set of values; a managed value like [`VEC`].
+pub const HASH_SET: BuiltinTypeId = BuiltinTypeId::from_index(3);
+/// Point on the monotonic clock, held as nanoseconds since the first time
+/// the program read it.
+pub const INSTANT: BuiltinTypeId = BuiltinTypeId::from_index(4);
+/// Span of time, held as a `u64` count of nanoseconds.
+pub const DURATION: BuiltinTypeId = BuiltinTypeId::from_index(5);
+/// JSON document: `null`, a boolean, number, string, array or object. A
+/// managed value like [`VEC`], so arrays and objects share their elements.
+pub const JSON: BuiltinTypeId = BuiltinTypeId::from_index(6);
+/// Exact base-10 number with any number of digits; a managed value like
+/// [`VEC`], though decimals are never changed in place.
+pub const DECIMAL: BuiltinTypeId = BuiltinTypeId::from_index(7);
+/// How a decimal drops digits, held as one of the `ROUND_*` codes of the
+/// runtime ABI.
+pub const ROUNDING_MODE: BuiltinTypeId = BuiltinTypeId::from_index(8);
+/// Line reader over a file or standard input; a managed value like [`VEC`].
+pub const READER: BuiltinTypeId = BuiltinTypeId::from_index(9);
+/// Buffered writer to a file or standard output; a managed value like
+/// [`VEC`] that flushes once the last copy is dropped.
+pub const WRITER: BuiltinTypeId = BuiltinTypeId::from_index(10);
+/// Iterator over the lines of a reader, reading one line each time it is
+/// advanced; a managed value like [`VEC`] that holds on to its reader.
+pub const LINES: BuiltinTypeId = BuiltinTypeId::from_index(11);
+
+pub const CONTRACTS: &[ContractDecl] = &[
+    ContractDecl { canonical_name: "core::fmt::Display", generics: &[], methods: &[] },
+    ContractDecl { canonical_name: "core::iter::Iterator", generics: &[], methods: &["next"] },
+    ContractDecl { canonical_name: "core::hash::Hash", generics: &[], methods: &[] },
+    ContractDecl { canonical_name: "core::cmp::Eq", generics: &[], methods: &[] },
+    ContractDecl { canonical_name: "core::num::Integer", generics: &[], methods: &[] },
+    ContractDecl { canonical_name: "core::num::Float", generics: &[], methods: &[] },
+    ContractDecl { canonical_name: "core::num::Number", generics: &[], methods: &[] },
+    ContractDecl { canonical_name: "core::ops::Add", generics: &[], methods: &["add"] },
+    ContractDecl { canonical_name: "core::ops::Sub", generics: &[], methods: &["sub"] },
+    ContractDecl { canonical_name: "core::ops::Mul", generics: &[], methods: &["mul"] },
+    ContractDecl { canonical_name: "core::default::Default", generics: &[], methods: &[] },
+    ContractDecl { canonical_name: "core::cmp::Ord", generics: &[], methods: &["cmp"] },
+    ContractDecl { canonical_name: "std::json::ToJson", generics: &[], methods: &[] },
+    ContractDecl { canonical_name: "std::json::FromJson", generics: &[], methods: &[] },
+    ContractDecl { canonical_name: "core::iter::IntoIterator", generics: &["T"], methods: &[] },
+];
 
-pub const CONTRACTS: &[ContractDecl] = &[ContractDecl { canonical_name: "core::fmt::Display" }];
+pub const BUILTIN_TYPES: &[BuiltinTypeDecl] = &[
+    BuiltinTypeDecl {
+        name: "Range",
+        module: "core::ops",
+        generics: &["T"],
+        implements: &[ITERATOR_CONTRACT, INTO_ITERATOR_CONTRACT],
+        bounds: &[],
+    },
+    BuiltinTypeDecl {
+        name: "Vec",
+        module: "core::vec",
+        generics: &["T"],
+        implements: &[DEFAULT_CONTRACT, INTO_ITERATOR_CONTRACT],
+        bounds: &[],
+    },
+    BuiltinTypeDecl {
+        name: "HashMap",
+        module: "std::collections",
+        generics: &["K", "V"],
+        implements: &[DEFAULT_CONTRACT],
+        bounds: HASH_KEY,
+    },
+    BuiltinTypeDecl {
+        name: "HashSet",
+        module: "std::collections",
+        generics: &["T"],
+        implements: &[DEFAULT_CONTRACT],
+        bounds: HASH_KEY,
+    },
+    BuiltinTypeDecl {
+        name: "Instant",
+        module: "std::time",
+        generics: &[],
+        implements: &[],
+        bounds: &[],
+    },
+    BuiltinTypeDecl {
+        name: "Duration",
+        module: "std::time",
+        generics: &[],
+        implements: &[],
+        bounds: &[],
+    },
+    BuiltinTypeDecl {
+        name: "Json",
+        module: "std::json",
+        generics: &[],
+        implements: &[TO_JSON_CONTRACT, FROM_JSON_CONTRACT],
+        bounds: &[],
+    },
+    BuiltinTypeDecl {
+        name: "Decimal",
+        module: "std::decimal",
+        generics: &[],
+        implements: &[DISPLAY_CONTRACT, ADD_CONTRACT, SUB_CONTRACT, MUL_CONTRACT, ORD_CONTRACT],
+        bounds: &[],
+    },
+    BuiltinTypeDecl {
+        name: "RoundingMode",
+        module: "std::decimal",
+        generics: &[],
+        implements: &[],
+        bounds: &[],
+    },
+    BuiltinTypeDecl {
+        name: "Reader",
+        module: "std::buffered",
+        generics: &[],
+        implements: &[],
+        bounds: &[],
+    },
+    BuiltinTypeDecl {
+        name: "Writer",
+        module: "std::buffered",
+        generics: &[],
+        implements: &[],
+        bounds: &[],
+    },
+    BuiltinTypeDecl {
+        name: "Lines",
+        module: "std::buffered",
+        generics: &[],
+        implements: &[ITERATOR_CONTRACT, INTO_ITERATOR_CONTRACT],
+        bounds: &[],
+    },
+];
 
 pub const BUILTINS: &[BuiltinDecl] = &[
     BuiltinDecl {
         name: "print",
+        module: "std::io",
+        generics: &[],
+        bounds: &[],
         params: DISPLAY_PARAM,
         ret: BuiltinReturn::Unit,
         lowering: BuiltinLowering::Runtime(RUNTIME_PRINT),
     },
     BuiltinDecl {
         name: "println",
+        module: "std::io",
+        generics: &[],
+        bounds: &[],
         params: DISPLAY_PARAM,
         ret: BuiltinReturn::Unit,
         lowering: BuiltinLowering::Runtime(RUNTIME_PRINTLN),
     },
+    BuiltinDecl {
+        name: "drop",
+        module: "core::mem",
+        generics: &[],
+        bounds: &[],
+        params: ANY_PARAM,
+        ret: BuiltinReturn::Unit,
+        lowering: BuiltinLowering::Drop,
+    },
+    BuiltinDecl {
+        name: "new",
+        module: "core::vec::Vec",
+        generics: &["T"],
+        bounds: &[],
+        params: &[],
+        ret: BuiltinReturn::Value(VEC_OF_ELEM),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::VecNew),
+    },
+    BuiltinDecl {
+        name: "push",
+        module: "core::vec::Vec",
+        generics: &["T"],
+        bounds: &[],
+        params: &[TypeConstraint::Exactly(VEC_OF_ELEM), TypeConstraint::Exactly(ELEM)],
+        ret: BuiltinReturn::Unit,
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::VecPush),
+    },
+    BuiltinDecl {
+        name: "pop",
+        module: "core::vec::Vec",
+        generics: &["T"],
+        bounds: &[],
+        params: VEC_PARAM,
+        ret: BuiltinReturn::Value(BuiltinTy::Nullable(&ELEM)),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::VecPop),
+    },
+    BuiltinDecl {
+        name: "len",
+        module: "core::vec::Vec",
+        generics: &["T"],
+        bounds: &[],
+        params: VEC_PARAM,
+        ret: BuiltinReturn::Value(BuiltinTy::Usize),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::Len),
+    },
+    BuiltinDecl {
+        name: "len",
+        module: "core::slice",
+        generics: &["T"],
+        bounds: &[],
+        params: SLICE_PARAM,
+        ret: BuiltinReturn::Value(BuiltinTy::Usize),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::Len),
+    },
+    BuiltinDecl {
+        name: "new",
+        module: "std::collections::HashMap",
+        generics: &["K", "V"],
+        bounds: &[],
+        params: &[],
+        ret: BuiltinReturn::Value(MAP),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::MapNew),
+    },
+    BuiltinDecl {
+        name: "insert",
+        module: "std::collections::HashMap",
+        generics: &["K", "V"],
+        bounds: &[],
+        params: &[
+            TypeConstraint::Exactly(MAP),
+            TypeConstraint::Exactly(KEY),
+            TypeConstraint::Exactly(VALUE),
+        ],
+        ret: BuiltinReturn::Value(BuiltinTy::Nullable(&VALUE)),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::MapInsert),
+    },
+    BuiltinDecl {
+        name: "get",
+        module: "std::collections::HashMap",
+        generics: &["K", "V"],
+        bounds: &[],
+        params: MAP_KEY_PARAMS,
+        ret: BuiltinReturn::Value(BuiltinTy::Nullable(&VALUE)),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::MapGet),
+    },
+    BuiltinDecl {
+        name: "contains_key",
+        module: "std::collections::HashMap",
+        generics: &["K", "V"],
+        bounds: &[],
+        params: MAP_KEY_PARAMS,
+        ret: BuiltinReturn::Value(BuiltinTy::Bool),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::MapContains),
+    },
+    BuiltinDecl {
+        name: "remove",
+        module: "std::collections::HashMap",
+        generics: &["K", "V"],
+        bounds: &[],
+        params: MAP_KEY_PARAMS,
+        ret: BuiltinReturn::Value(BuiltinTy::Nullable(&VALUE)),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::MapRemove),
+    },
+    BuiltinDecl {
+        name: "len",
+        module: "std::collections::HashMap",
+        generics: &["K", "V"],
+        bounds: &[],
+        params: MAP_PARAM,
+        ret: BuiltinReturn::Value(BuiltinTy::Usize),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::Len),
+    },
+    BuiltinDecl {
+        name: "keys",
+        module: "std::collections::HashMap",
+        generics: &["K", "V"],
+        bounds: &[],
+        params: MAP_PARAM,
+        ret: BuiltinReturn::Value(BuiltinTy::Builtin(VEC, &[KEY])),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::MapKeys),
+    },
+    BuiltinDecl {
+        name: "values",
+        module: "std::collections::HashMap",
+        generics: &["K", "V"],
+        bounds: &[],
+        params: MAP_PARAM,
+        ret: BuiltinReturn::Value(BuiltinTy::Builtin(VEC, &[VALUE])),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::MapValues),
+    },
+    BuiltinDecl {
+        name: "new",
+        module: "std::collections::HashSet",
+        generics: &["T"],
+        bounds: &[],
+        params: &[],
+        ret: BuiltinReturn::Value(SET),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::MapNew),
+    },
+    BuiltinDecl {
+        name: "insert",
+        module: "std::collections::HashSet",
+        generics: &["T"],
+        bounds: &[],
+        params: SET_ELEM_PARAMS,
+        ret: BuiltinReturn::Value(BuiltinTy::Bool),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::SetInsert),
+    },
+    BuiltinDecl {
+        name: "contains",
+        module: "std::collections::HashSet",
+        generics: &["T"],
+        bounds: &[],
+        params: SET_ELEM_PARAMS,
+        ret: BuiltinReturn::Value(BuiltinTy::Bool),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::MapContains),
+    },
+    BuiltinDecl {
+        name: "remove",
+        module: "std::collections::HashSet",
+        generics: &["T"],
+        bounds: &[],
+        params: SET_ELEM_PARAMS,
+        ret: BuiltinReturn::Value(BuiltinTy::Bool),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::SetRemove),
+    },
+    BuiltinDecl {
+        name: "len",
+        module: "std::collections::HashSet",
+        generics: &["T"],
+        bounds: &[],
+        params: SET_PARAM,
+        ret: BuiltinReturn::Value(BuiltinTy::Usize),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::Len),
+    },
+    BuiltinDecl {
+        name: "to_vec",
+        module: "std::collections::HashSet",
+        generics: &["T"],
+        bounds: &[],
+        params: SET_PARAM,
+        ret: BuiltinReturn::Value(VEC_OF_ELEM),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::MapKeys),
+    },
+    BuiltinDecl {
+        name: "input",
+        module: "std::io",
+        generics: &[],
+        bounds: &[],
+        params: &[TypeConstraint::Exactly(BuiltinTy::Str)],
+        ret: BuiltinReturn::Slot(BuiltinTy::Str),
+        lowering: BuiltinLowering::Runtime(RUNTIME_INPUT),
+    },
+    BuiltinDecl {
+        name: "sqrt",
+        module: "std::math",
+        generics: &["T"],
+        bounds: FLOAT_BOUND,
+        params: NUM_PARAM,
+        ret: BuiltinReturn::Value(NUM),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::Sqrt),
+    },
+    BuiltinDecl {
+        name: "abs",
+        module: "std::math",
+        generics: &["T"],
+        bounds: NUMBER_BOUND,
+        params: NUM_PARAM,
+        ret: BuiltinReturn::Value(NUM),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::Abs),
+    },
+    BuiltinDecl {
+        name: "min",
+        module: "std::math",
+        generics: &["T"],
+        bounds: NUMBER_BOUND,
+        params: NUM_PAIR_PARAMS,
+        ret: BuiltinReturn::Value(NUM),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::Min),
+    },
+    BuiltinDecl {
+        name: "max",
+        module: "std::math",
+        generics: &["T"],
+        bounds: NUMBER_BOUND,
+        params: NUM_PAIR_PARAMS,
+        ret: BuiltinReturn::Value(NUM),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::Max),
+    },
+    BuiltinDecl {
+        name: "floor",
+        module: "std::math",
+        generics: &["T"],
+        bounds: FLOAT_BOUND,
+        params: NUM_PARAM,
+        ret: BuiltinReturn::Value(NUM),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::Floor),
+    },
+    BuiltinDecl {
+        name: "ceil",
+        module: "std::math",
+        generics: &["T"],
+        bounds: FLOAT_BOUND,
+        params: NUM_PARAM,
+        ret: BuiltinReturn::Value(NUM),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::Ceil),
+    },
+    BuiltinDecl {
+        name: "trunc",
+        module: "std::math",
+        generics: &["T"],
+        bounds: FLOAT_BOUND,
+        params: NUM_PARAM,
+        ret: BuiltinReturn::Value(NUM),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::Trunc),
+    },
+    BuiltinDecl {
+        name: "round",
+        module: "std::math",
+        generics: &[],
+        bounds: &[],
+        params: F64_PARAM,
+        ret: BuiltinReturn::Value(BuiltinTy::F64),
+        lowering: BuiltinLowering::Runtime(RUNTIME_ROUND),
+    },
+    BuiltinDecl {
+        name: "pow",
+        module: "std::math",
+        generics: &[],
+        bounds: &[],
+        params: F64_PAIR_PARAMS,
+        ret: BuiltinReturn::Value(BuiltinTy::F64),
+        lowering: BuiltinLowering::Runtime(RUNTIME_POW),
+    },
+    BuiltinDecl {
+        name: "sin",
+        module: "std::math",
+        generics: &[],
+        bounds: &[],
+        params: F64_PARAM,
+        ret: BuiltinReturn::Value(BuiltinTy::F64),
+        lowering: BuiltinLowering::Runtime(RUNTIME_SIN),
+    },
+    BuiltinDecl {
+        name: "cos",
+        module: "std::math",
+        generics: &[],
+        bounds: &[],
+        params: F64_PARAM,
+        ret: BuiltinReturn::Value(BuiltinTy::F64),
+        lowering: BuiltinLowering::Runtime(RUNTIME_COS),
+    },
+    BuiltinDecl {
+        name: "tan",
+        module: "std::math",
+        generics: &[],
+        bounds: &[],
+        params: F64_PARAM,
+        ret: BuiltinReturn::Value(BuiltinTy::F64),
+        lowering: BuiltinLowering::Runtime(RUNTIME_TAN),
+    },
+    BuiltinDecl {
+        name: "asin",
+        module: "std::math",
+        generics: &[],
+        bounds: &[],
+        params: F64_PARAM,
+        ret: BuiltinReturn::Value(BuiltinTy::F64),
+        lowering: BuiltinLowering::Runtime(RUNTIME_ASIN),
+    },
+    BuiltinDecl {
+        name: "acos",
+        module: "std::math",
+        generics: &[],
+        bounds: &[],
+        params: F64_PARAM,
+        ret: BuiltinReturn::Value(BuiltinTy::F64),
+        lowering: BuiltinLowering::Runtime(RUNTIME_ACOS),
+    },
+    BuiltinDecl {
+        name: "atan",
+        module: "std::math",
+        generics: &[],
+        bounds: &[],
+        params: F64_PARAM,
+        ret: BuiltinReturn::Value(BuiltinTy::F64),
+        lowering: BuiltinLowering::Runtime(RUNTIME_ATAN),
+    },
+    BuiltinDecl {
+        name: "atan2",
+        module: "std::math",
+        generics: &[],
+        bounds: &[],
+        params: F64_PAIR_PARAMS,
+        ret: BuiltinReturn::Value(BuiltinTy::F64),
+        lowering: BuiltinLowering::Runtime(RUNTIME_ATAN2),
+    },
+    BuiltinDecl {
+        name: "exp",
+        module: "std::math",
+        generics: &[],
+        bounds: &[],
+        params: F64_PARAM,
+        ret: BuiltinReturn::Value(BuiltinTy::F64),
+        lowering: BuiltinLowering::Runtime(RUNTIME_EXP),
+    },
+    BuiltinDecl {
+        name: "ln",
+        module: "std::math",
+        generics: &[],
+        bounds: &[],
+        params: F64_PARAM,
+        ret: BuiltinReturn::Value(BuiltinTy::F64),
+        lowering: BuiltinLowering::Runtime(RUNTIME_LN),
+    },
+    BuiltinDecl {
+        name: "log2",
+        module: "std::math",
+        generics: &[],
+        bounds: &[],
+        params: F64_PARAM,
+        ret: BuiltinReturn::Value(BuiltinTy::F64),
+        lowering: BuiltinLowering::Runtime(RUNTIME_LOG2),
+    },
+    BuiltinDecl {
+        name: "log10",
+        module: "std::math",
+        generics: &[],
+        bounds: &[],
+        params: F64_PARAM,
+        ret: BuiltinReturn::Value(BuiltinTy::F64),
+        lowering: BuiltinLowering::Runtime(RUNTIME_LOG10),
+    },
+    BuiltinDecl {
+        name: "PI",
+        module: "std::math",
+        generics: &[],
+        bounds: &[],
+        params: &[],
+        ret: BuiltinReturn::Value(BuiltinTy::F64),
+        lowering: BuiltinLowering::Constant(std::f64::consts::PI.to_bits()),
+    },
+    BuiltinDecl {
+        name: "E",
+        module: "std::math",
+        generics: &[],
+        bounds: &[],
+        params: &[],
+        ret: BuiltinReturn::Value(BuiltinTy::F64),
+        lowering: BuiltinLowering::Constant(std::f64::consts::E.to_bits()),
+    },
+    BuiltinDecl {
+        name: "TAU",
+        module: "std::math",
+        generics: &[],
+        bounds: &[],
+        params: &[],
+        ret: BuiltinReturn::Value(BuiltinTy::F64),
+        lowering: BuiltinLowering::Constant(std::f64::consts::TAU.to_bits()),
+    },
+    BuiltinDecl {
+        name: "checked_add",
+        module: "std::math",
+        generics: &["T"],
+        bounds: INTEGER_BOUND,
+        params: NUM_PAIR_PARAMS,
+        ret: BuiltinReturn::Value(BuiltinTy::Nullable(&NUM)),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::CheckedAdd),
+    },
+    BuiltinDecl {
+        name: "checked_sub",
+        module: "std::math",
+        generics: &["T"],
+        bounds: INTEGER_BOUND,
+        params: NUM_PAIR_PARAMS,
+        ret: BuiltinReturn::Value(BuiltinTy::Nullable(&NUM)),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::CheckedSub),
+    },
+    BuiltinDecl {
+        name: "checked_mul",
+        module: "std::math",
+        generics: &["T"],
+        bounds: INTEGER_BOUND,
+        params: NUM_PAIR_PARAMS,
+        ret: BuiltinReturn::Value(BuiltinTy::Nullable(&NUM)),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::CheckedMul),
+    },
+    BuiltinDecl {
+        name: "wrapping_add",
+        module: "std::math",
+        generics: &["T"],
+        bounds: INTEGER_BOUND,
+        params: NUM_PAIR_PARAMS,
+        ret: BuiltinReturn::Value(NUM),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::WrappingAdd),
+    },
+    BuiltinDecl {
+        name: "wrapping_sub",
+        module: "std::math",
+        generics: &["T"],
+        bounds: INTEGER_BOUND,
+        params: NUM_PAIR_PARAMS,
+        ret: BuiltinReturn::Value(NUM),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::WrappingSub),
+    },
+    BuiltinDecl {
+        name: "wrapping_mul",
+        module: "std::math",
+        generics: &["T"],
+        bounds: INTEGER_BOUND,
+        params: NUM_PAIR_PARAMS,
+        ret: BuiltinReturn::Value(NUM),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::WrappingMul),
+    },
+    BuiltinDecl {
+        name: "read_to_string",
+        module: "std::files",
+        generics: &[],
+        bounds: &[],
+        params: STR_PARAM,
+        ret: BuiltinReturn::Fallible(BuiltinTy::Str),
+        lowering: BuiltinLowering::Runtime(RUNTIME_READ_TO_STRING),
+    },
+    BuiltinDecl {
+        name: "write",
+        module: "std::files",
+        generics: &[],
+        bounds: &[],
+        params: STR_PAIR_PARAMS,
+        ret: BuiltinReturn::Fallible(BuiltinTy::Unit),
+        lowering: BuiltinLowering::Runtime(RUNTIME_WRITE),
+    },
+    BuiltinDecl {
+        name: "append",
+        module: "std::files",
+        generics: &[],
+        bounds: &[],
+        params: STR_PAIR_PARAMS,
+        ret: BuiltinReturn::Fallible(BuiltinTy::Unit),
+        lowering: BuiltinLowering::Runtime(RUNTIME_APPEND),
+    },
+    BuiltinDecl {
+        name: "exists",
+        module: "std::files",
+        generics: &[],
+        bounds: &[],
+        params: STR_PARAM,
+        ret: BuiltinReturn::Value(BuiltinTy::Bool),
+        lowering: BuiltinLowering::Runtime(RUNTIME_EXISTS),
+    },
+    BuiltinDecl {
+        name: "remove",
+        module: "std::files",
+        generics: &[],
+        bounds: &[],
+        params: STR_PARAM,
+        ret: BuiltinReturn::Fallible(BuiltinTy::Unit),
+        lowering: BuiltinLowering::Runtime(RUNTIME_REMOVE),
+    },
+    BuiltinDecl {
+        name: "list_dir",
+        module: "std::files",
+        generics: &[],
+        bounds: &[],
+        params: STR_PARAM,
+        ret: BuiltinReturn::Fallible(BuiltinTy::Builtin(VEC, &[BuiltinTy::Str])),
+        lowering: BuiltinLowering::Runtime(RUNTIME_LIST_DIR),
+    },
+    BuiltinDecl {
+        name: "now",
+        module: "std::time::Instant",
+        generics: &[],
+        bounds: &[],
+        params: &[],
+        ret: BuiltinReturn::Value(INSTANT_TY),
+        lowering: BuiltinLowering::Runtime(RUNTIME_INSTANT_NOW),
+    },
+    BuiltinDecl {
+        name: "elapsed",
+        module: "std::time::Instant",
+        generics: &[],
+        bounds: &[],
+        params: INSTANT_PARAM,
+        ret: BuiltinReturn::Value(DURATION_TY),
+        lowering: BuiltinLowering::Runtime(RUNTIME_INSTANT_ELAPSED),
+    },
+    BuiltinDecl {
+        name: "duration_since",
+        module: "std::time::Instant",
+        generics: &[],
+        bounds: &[],
+        params: &[TypeConstraint::Exactly(INSTANT_TY), TypeConstraint::Exactly(INSTANT_TY)],
+        ret: BuiltinReturn::Value(DURATION_TY),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::SaturatingSub),
+    },
+    BuiltinDecl {
+        name: "from_secs",
+        module: "std::time::Duration",
+        generics: &[],
+        bounds: &[],
+        params: U64_PARAM,
+        ret: BuiltinReturn::Value(DURATION_TY),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::MulConst(1_000_000_000)),
+    },
+    BuiltinDecl {
+        name: "from_millis",
+        module: "std::time::Duration",
+        generics: &[],
+        bounds: &[],
+        params: U64_PARAM,
+        ret: BuiltinReturn::Value(DURATION_TY),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::MulConst(1_000_000)),
+    },
+    BuiltinDecl {
+        name: "from_micros",
+        module: "std::time::Duration",
+        generics: &[],
+        bounds: &[],
+        params: U64_PARAM,
+        ret: BuiltinReturn::Value(DURATION_TY),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::MulConst(1_000)),
+    },
+    BuiltinDecl {
+        name: "from_nanos",
+        module: "std::time::Duration",
+        generics: &[],
+        bounds: &[],
+        params: U64_PARAM,
+        ret: BuiltinReturn::Value(DURATION_TY),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::MulConst(1)),
+    },
+    BuiltinDecl {
+        name: "as_secs",
+        module: "std::time::Duration",
+        generics: &[],
+        bounds: &[],
+        params: DURATION_PARAM,
+        ret: BuiltinReturn::Value(BuiltinTy::U64),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::DivConst(1_000_000_000)),
+    },
+    BuiltinDecl {
+        name: "as_millis",
+        module: "std::time::Duration",
+        generics: &[],
+        bounds: &[],
+        params: DURATION_PARAM,
+        ret: BuiltinReturn::Value(BuiltinTy::U64),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::DivConst(1_000_000)),
+    },
+    BuiltinDecl {
+        name: "as_micros",
+        module: "std::time::Duration",
+        generics: &[],
+        bounds: &[],
+        params: DURATION_PARAM,
+        ret: BuiltinReturn::Value(BuiltinTy::U64),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::DivConst(1_000)),
+    },
+    BuiltinDecl {
+        name: "as_nanos",
+        module: "std::time::Duration",
+        generics: &[],
+        bounds: &[],
+        params: DURATION_PARAM,
+        ret: BuiltinReturn::Value(BuiltinTy::U64),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::DivConst(1)),
+    },
+    BuiltinDecl {
+        name: "as_secs_f64",
+        module: "std::time::Duration",
+        generics: &[],
+        bounds: &[],
+        params: DURATION_PARAM,
+        ret: BuiltinReturn::Value(BuiltinTy::F64),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::NanosToSecs),
+    },
+    BuiltinDecl {
+        name: "add",
+        module: "std::time::Duration",
+        generics: &[],
+        bounds: &[],
+        params: DURATION_PAIR_PARAMS,
+        ret: BuiltinReturn::Value(DURATION_TY),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::SaturatingAdd),
+    },
+    BuiltinDecl {
+        name: "sub",
+        module: "std::time::Duration",
+        generics: &[],
+        bounds: &[],
+        params: DURATION_PAIR_PARAMS,
+        ret: BuiltinReturn::Value(DURATION_TY),
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::SaturatingSub),
+    },
+    BuiltinDecl {
+        name: "sleep",
+        module: "std::time",
+        generics: &[],
+        bounds: &[],
+        params: DURATION_PARAM,
+        ret: BuiltinReturn::Unit,
+        lowering: BuiltinLowering::Runtime(RUNTIME_SLEEP),
+    },
+    BuiltinDecl {
+        name: "unix_time",
+        module: "std::time",
+        generics: &[],
+        bounds: &[],
+        params: &[],
+        ret: BuiltinReturn::Value(DURATION_TY),
+        lowering: BuiltinLowering::Runtime(RUNTIME_UNIX_TIME),
+    },
+    BuiltinDecl {
+        name: "seed",
+        module: "std::random",
+        generics: &[],
+        bounds: &[],
+        params: U64_PARAM,
+        ret: BuiltinReturn::Unit,
+        lowering: BuiltinLowering::Runtime(RUNTIME_RANDOM_SEED),
+    },
+    BuiltinDecl {
+        name: "os_seed",
+        module: "std::random",
+        generics: &[],
+        bounds: &[],
+        params: &[],
+        ret: BuiltinReturn::Value(BuiltinTy::U64),
+        lowering: BuiltinLowering::Runtime(RUNTIME_RANDOM_OS_SEED),
+    },
+    BuiltinDecl {
+        name: "next_u64",
+        module: "std::random",
+        generics: &[],
+        bounds: &[],
+        params: &[],
+        ret: BuiltinReturn::Value(BuiltinTy::U64),
+        lowering: BuiltinLowering::Runtime(RUNTIME_RANDOM_NEXT),
+    },
+    BuiltinDecl {
+        name: "int_range",
+        module: "std::random",
+        generics: &[],
+        bounds: &[],
+        params: &[TypeConstraint::Exactly(BuiltinTy::I64), TypeConstraint::Exactly(BuiltinTy::I64)],
+        ret: BuiltinReturn::Value(BuiltinTy::I64),
+        lowering: BuiltinLowering::Runtime(RUNTIME_RANDOM_INT_RANGE),
+    },
+    BuiltinDecl {
+        name: "float",
+        module: "std::random",
+        generics: &[],
+        bounds: &[],
+        params: &[],
+        ret: BuiltinReturn::Value(BuiltinTy::F64),
+        lowering: BuiltinLowering::Runtime(RUNTIME_RANDOM_FLOAT),
+    },
+    BuiltinDecl {
+        name: "float_range",
+        module: "std::random",
+        generics: &[],
+        bounds: &[],
+        params: F64_PAIR_PARAMS,
+        ret: BuiltinReturn::Value(BuiltinTy::F64),
+        lowering: BuiltinLowering::Runtime(RUNTIME_RANDOM_FLOAT_RANGE),
+    },
+    BuiltinDecl {
+        name: "shuffle",
+        module: "std::random",
+        generics: &["T"],
+        bounds: &[],
+        params: SEQUENCE_PARAM,
+        ret: BuiltinReturn::Unit,
+        lowering: BuiltinLowering::Intrinsic(Intrinsic::Shuffle),
+    },
+    BuiltinDecl {
+        name: "parse",
+        module: "std::json",
+        generics: &[],
+        bounds: &[],
+        params: STR_PARAM,
+        ret: BuiltinReturn::Fallible(JSON_TY),
+        lowering: BuiltinLowering::Runtime(RUNTIME_JSON_PARSE),
+    },
+    BuiltinDecl {
+        name: "stringify",
+        module: "std::json",
+        generics: &[],
+        bounds: &[],
+        params: JSON_PARAM,
+        ret: BuiltinReturn::Fallible(BuiltinTy::Str),
+        lowering: BuiltinLowering::Runtime(RUNTIME_JSON_STRINGIFY),
+    },
+    BuiltinDecl {
+        name: "pretty",
+        module: "std::json",
+        generics: &[],
+        bounds: &[],
+        params: JSON_PARAM,
+        ret: BuiltinReturn::Fallible(BuiltinTy::Str),
+        lowering: BuiltinLowering::Runtime(RUNTIME_JSON_PRETTY),
+    },
+    BuiltinDecl {
+        name: "new_null",
+        module: "std::json::Json",
+        generics: &[],
+        bounds: &[],
+        params: &[],
+        ret: BuiltinReturn::Value(JSON_TY),
+        lowering: BuiltinLowering::Runtime(RUNTIME_JSON_NEW_NULL),
+    },
+    BuiltinDecl {
+        name: "from_bool",
+        module: "std::json::Json",
+        generics: &[],
+        bounds: &[],
+        params: &[TypeConstraint::Exactly(BuiltinTy::Bool)],
+        ret: BuiltinReturn::Value(JSON_TY),
+        lowering: BuiltinLowering::Runtime(RUNTIME_JSON_FROM_BOOL),
+    },
+    BuiltinDecl {
+        name: "from_number",
+        module: "std::json::Json",
+        generics: &[],
+        bounds: &[],
+        params: F64_PARAM,
+        ret: BuiltinReturn::Value(JSON_TY),
+        lowering: BuiltinLowering::Runtime(RUNTIME_JSON_FROM_NUMBER),
+    },
+    BuiltinDecl {
+        name: "from_str",
+        module: "std::json::Json",
+        generics: &[],
+        bounds: &[],
+        params: STR_PARAM,
+        ret: BuiltinReturn::Value(JSON_TY),
+        lowering: BuiltinLowering::Runtime(RUNTIME_JSON_FROM_STR),
+    },
+    BuiltinDecl {
+        name: "new_array",
+        module: "std::json::Json",
+        generics: &[],
+        bounds: &[],
+        params: &[],
+        ret: BuiltinReturn::Value(JSON_TY),
+        lowering: BuiltinLowering::Runtime(RUNTIME_JSON_NEW_ARRAY),
+    },
+    BuiltinDecl {
+        name: "new_object",
+        module: "std::json::Json",
+        generics: &[],
+        bounds: &[],
+        params: &[],
+        ret: BuiltinReturn::Value(JSON_TY),
+        lowering: BuiltinLowering::Runtime(RUNTIME_JSON_NEW_OBJECT),
+    },
+    BuiltinDecl {
+        name: "is_null",
+        module: "std::json::Json",
+        generics: &[],
+        bounds: &[],
+        params: JSON_PARAM,
+        ret: BuiltinReturn::Value(BuiltinTy::Bool),
+        lowering: BuiltinLowering::Runtime(RUNTIME_JSON_IS_NULL),
+    },
+    BuiltinDecl {
+        name: "as_bool",
+        module: "std::json::Json",
+        generics: &[],
+        bounds: &[],
+        params: JSON_PARAM,
+        ret: BuiltinReturn::Slot(BuiltinTy::Bool),
+        lowering: BuiltinLowering::Runtime(RUNTIME_JSON_AS_BOOL),
+    },
+    BuiltinDecl {
+        name: "as_number",
+        module: "std::json::Json",
+        generics: &[],
+        bounds: &[],
+        params: JSON_PARAM,
+        ret: BuiltinReturn::Slot(BuiltinTy::F64),
+        lowering: BuiltinLowering::Runtime(RUNTIME_JSON_AS_NUMBER),
+    },
+    BuiltinDecl {
+        name: "as_str",
+        module: "std::json::Json",
+        generics: &[],
+        bounds: &[],
+        params: JSON_PARAM,
+        ret: BuiltinReturn::Slot(BuiltinTy::Str),
+        lowering: BuiltinLowering::Runtime(RUNTIME_JSON_AS_STR),
+    },
+    BuiltinDecl {
+        name: "len",
+        module: "std::json::Json",
+        generics: &[],
+        bounds: &[],
+        params: JSON_PARAM,
+        ret: BuiltinReturn::Value(BuiltinTy::Usize),
+        lowering: BuiltinLowering::Runtime(RUNTIME_JSON_LEN),
+    },
+    BuiltinDecl {
+        name: "at",
+        module: "std::json::Json",
+        generics: &[],
+        bounds: &[],
+        params: &[TypeConstraint::Exactly(JSON_TY), TypeConstraint::Exactly(BuiltinTy::Usize)],
+        ret: BuiltinReturn::Slot(JSON_TY),
+        lowering: BuiltinLowering::Runtime(RUNTIME_JSON_AT),
+    },
+    BuiltinDecl {
+        name: "get",
+        module: "std::json::Json",
+        generics: &[],
+        bounds: &[],
+        params: &[TypeConstraint::Exactly(JSON_TY), TypeConstraint::Exactly(BuiltinTy::Str)],
+        ret: BuiltinReturn::Slot(JSON_TY),
+        lowering: BuiltinLowering::Runtime(RUNTIME_JSON_GET),
+    },
+    BuiltinDecl {
+        name: "keys",
+        module: "std::json::Json",
+        generics: &[],
+        bounds: &[],
+        params: JSON_PARAM,
+        ret: BuiltinReturn::Value(BuiltinTy::Builtin(VEC, &[BuiltinTy::Str])),
+        lowering: BuiltinLowering::Runtime(RUNTIME_JSON_KEYS),
+    },
+    BuiltinDecl {
+        name: "push",
+        module: "std::json::Json",
+        generics: &[],
+        bounds: &[],
+        params: &[TypeConstraint::Exactly(JSON_TY), TypeConstraint::Exactly(JSON_TY)],
+        ret: BuiltinReturn::Unit,
+        lowering: BuiltinLowering::Runtime(RUNTIME_JSON_PUSH),
+    },
+    BuiltinDecl {
+        name: "insert",
+        module: "std::json::Json",
+        generics: &[],
+        bounds: &[],
+        params: &[
+            TypeConstraint::Exactly(JSON_TY),
+            TypeConstraint::Exactly(BuiltinTy::Str),
+            TypeConstraint::Exactly(JSON_TY),
+        ],
+        ret: BuiltinReturn::Unit,
+        lowering: BuiltinLowering::Runtime(RUNTIME_JSON_INSERT),
+    },
+    BuiltinDecl {
+        name: "parse",
+        module: "std::decimal",
+        generics: &[],
+        bounds: &[],
+        params: STR_PARAM,
+        ret: BuiltinReturn::Fallible(DECIMAL_TY),
+        lowering: BuiltinLowering::Runtime(RUNTIME_DECIMAL_PARSE),
+    },
+    BuiltinDecl {
+        name: "from_int",
+        module: "std::decimal",
+        generics: &[],
+        bounds: &[],
+        params: &[TypeConstraint::Exactly(BuiltinTy::I64)],
+        ret: BuiltinReturn::Value(DECIMAL_TY),
+        lowering: BuiltinLowering::Runtime(RUNTIME_DECIMAL_FROM_INT),
+    },
+    BuiltinDecl {
+        name: "to_string",
+        module: "std::decimal::Decimal",
+        generics: &[],
+        bounds: &[],
+        params: DECIMAL_PARAM,
+        ret: BuiltinReturn::Indirect(BuiltinTy::Str),
+        lowering: BuiltinLowering::Runtime(RUNTIME_DECIMAL_TO_STRING),
+    },
+    BuiltinDecl {
+        name: "add",
+        module: "std::decimal::Decimal",
+        generics: &[],
+        bounds: &[],
+        params: DECIMAL_PAIR_PARAMS,
+        ret: BuiltinReturn::Value(DECIMAL_TY),
+        lowering: BuiltinLowering::Runtime(RUNTIME_DECIMAL_ADD),
+    },
+    BuiltinDecl {
+        name: "sub",
+        module: "std::decimal::Decimal",
+        generics: &[],
+        bounds: &[],
+        params: DECIMAL_PAIR_PARAMS,
+        ret: BuiltinReturn::Value(DECIMAL_TY),
+        lowering: BuiltinLowering::Runtime(RUNTIME_DECIMAL_SUB),
+    },
+    BuiltinDecl {
+        name: "mul",
+        module: "std::decimal::Decimal",
+        generics: &[],
+        bounds: &[],
+        params: DECIMAL_PAIR_PARAMS,
+        ret: BuiltinReturn::Value(DECIMAL_TY),
+        lowering: BuiltinLowering::Runtime(RUNTIME_DECIMAL_MUL),
+    },
+    BuiltinDecl {
+        name: "div",
+        module: "std::decimal::Decimal",
+        generics: &[],
+        bounds: &[],
+        params: &[
+            TypeConstraint::Exactly(DECIMAL_TY),
+            TypeConstraint::Exactly(DECIMAL_TY),
+            TypeConstraint::Exactly(BuiltinTy::Usize),
+            TypeConstraint::Exactly(ROUNDING_MODE_TY),
+        ],
+        ret: BuiltinReturn::Fallible(DECIMAL_TY),
+        lowering: BuiltinLowering::Runtime(RUNTIME_DECIMAL_DIV),
+    },
+    BuiltinDecl {
+        name: "round",
+        module: "std::decimal::Decimal",
+        generics: &[],
+        bounds: &[],
+        params: &[
+            TypeConstraint::Exactly(DECIMAL_TY),
+            TypeConstraint::Exactly(BuiltinTy::Usize),
+            TypeConstraint::Exactly(ROUNDING_MODE_TY),
+        ],
+        ret: BuiltinReturn::Value(DECIMAL_TY),
+        lowering: BuiltinLowering::Runtime(RUNTIME_DECIMAL_ROUND),
+    },
+    BuiltinDecl {
+        name: "cmp",
+        module: "std::decimal::Decimal",
+        generics: &[],
+        bounds: &[],
+        params: DECIMAL_PAIR_PARAMS,
+        ret: BuiltinReturn::Value(BuiltinTy::I64),
+        lowering: BuiltinLowering::Runtime(RUNTIME_DECIMAL_CMP),
+    },
+    BuiltinDecl {
+        name: "neg",
+        module: "std::decimal::Decimal",
+        generics: &[],
+        bounds: &[],
+        params: DECIMAL_PARAM,
+        ret: BuiltinReturn::Value(DECIMAL_TY),
+        lowering: BuiltinLowering::Runtime(RUNTIME_DECIMAL_NEG),
+    },
+    BuiltinDecl {
+        name: "scale",
+        module: "std::decimal::Decimal",
+        generics: &[],
+        bounds: &[],
+        params: DECIMAL_PARAM,
+        ret: BuiltinReturn::Value(BuiltinTy::Usize),
+        lowering: BuiltinLowering::Runtime(RUNTIME_DECIMAL_SCALE),
+    },
+    BuiltinDecl {
+        name: "HalfEven",
+        module: "std::decimal::RoundingMode",
+        generics: &[],
+        bounds: &[],
+        params: &[],
+        ret: BuiltinReturn::Value(ROUNDING_MODE_TY),
+        lowering: BuiltinLowering::Constant(ROUND_HALF_EVEN as u64),
+    },
+    BuiltinDecl {
+        name: "HalfUp",
+        module: "std::decimal::RoundingMode",
+        generics: &[],
+        bounds: &[],
+        params: &[],
+        ret: BuiltinReturn::Value(ROUNDING_MODE_TY),
+        lowering: BuiltinLowering::Constant(ROUND_HALF_UP as u64),
+    },
+    BuiltinDecl {
+        name: "Down",
+        module: "std::decimal::RoundingMode",
+        generics: &[],
+        bounds: &[],
+        params: &[],
+        ret: BuiltinReturn::Value(ROUNDING_MODE_TY),
+        lowering: BuiltinLowering::Constant(ROUND_DOWN as u64),
+    },
+    BuiltinDecl {
+        name: "Up",
+        module: "std::decimal::RoundingMode",
+        generics: &[],
+        bounds: &[],
+        params: &[],
+        ret: BuiltinReturn::Value(ROUNDING_MODE_TY),
+        lowering: BuiltinLowering::Constant(ROUND_UP as u64),
+    },
+    BuiltinDecl {
+        name: "Floor",
+        module: "std::decimal::RoundingMode",
+        generics: &[],
+        bounds: &[],
+        params: &[],
+        ret: BuiltinReturn::Value(ROUNDING_MODE_TY),
+        lowering: BuiltinLowering::Constant(ROUND_FLOOR as u64),
+    },
+    BuiltinDecl {
+        name: "Ceiling",
+        module: "std::decimal::RoundingMode",
+        generics: &[],
+        bounds: &[],
+        params: &[],
+        ret: BuiltinReturn::Value(ROUNDING_MODE_TY),
+        lowering: BuiltinLowering::Constant(ROUND_CEILING as u64),
+    },
+    BuiltinDecl {
+        name: "flush",
+        module: "std::io",
+        generics: &[],
+        bounds: &[],
+        params: &[],
+        ret: BuiltinReturn::Unit,
+        lowering: BuiltinLowering::Runtime(RUNTIME_FLUSH),
+    },
+    BuiltinDecl {
+        name: "set_stdout_buffered",
+        module: "std::buffered",
+        generics: &[],
+        bounds: &[],
+        params: &[TypeConstraint::Exactly(BuiltinTy::Bool)],
+        ret: BuiltinReturn::Unit,
+        lowering: BuiltinLowering::Runtime(RUNTIME_SET_STDOUT_BUFFERED),
+    },
+    BuiltinDecl {
+        name: "open_reader",
+        module: "std::buffered",
+        generics: &[],
+        bounds: &[],
+        params: STR_PARAM,
+        ret: BuiltinReturn::Fallible(READER_TY),
+        lowering: BuiltinLowering::Runtime(RUNTIME_READER_OPEN),
+    },
+    BuiltinDecl {
+        name: "stdin_reader",
+        module: "std::buffered",
+        generics: &[],
+        bounds: &[],
+        params: &[],
+        ret: BuiltinReturn::Value(READER_TY),
+        lowering: BuiltinLowering::Runtime(RUNTIME_READER_STDIN),
+    },
+    BuiltinDecl {
+        name: "read_line",
+        module: "std::buffered::Reader",
+        generics: &[],
+        bounds: &[],
+        params: &[TypeConstraint::Exactly(READER_TY)],
+        ret: BuiltinReturn::Slot(BuiltinTy::Str),
+        lowering: BuiltinLowering::Runtime(RUNTIME_READER_READ_LINE),
+    },
+    BuiltinDecl {
+        name: "lines",
+        module: "std::buffered::Reader",
+        generics: &[],
+        bounds: &[],
+        params: &[TypeConstraint::Exactly(READER_TY)],
+        ret: BuiltinReturn::Value(LINES_TY),
+        lowering: BuiltinLowering::Runtime(RUNTIME_READER_LINES),
+    },
+    BuiltinDecl {
+        name: "create_writer",
+        module: "std::buffered",
+        generics: &[],
+        bounds: &[],
+        params: STR_PARAM,
+        ret: BuiltinReturn::Fallible(WRITER_TY),
+        lowering: BuiltinLowering::Runtime(RUNTIME_WRITER_CREATE),
+    },
+    BuiltinDecl {
+        name: "stdout_writer",
+        module: "std::buffered",
+        generics: &[],
+        bounds: &[],
+        params: &[],
+        ret: BuiltinReturn::Value(WRITER_TY),
+        lowering: BuiltinLowering::Runtime(RUNTIME_WRITER_STDOUT),
+    },
+    BuiltinDecl {
+        name: "write",
+        module: "std::buffered::Writer",
+        generics: &[],
+        bounds: &[],
+        params: WRITER_STR_PARAMS,
+        ret: BuiltinReturn::Fallible(BuiltinTy::Unit),
+        lowering: BuiltinLowering::Runtime(RUNTIME_WRITER_WRITE),
+    },
+    BuiltinDecl {
+        name: "write_line",
+        module: "std::buffered::Writer",
+        generics: &[],
+        bounds: &[],
+        params: WRITER_STR_PARAMS,
+        ret: BuiltinReturn::Fallible(BuiltinTy::Unit),
+        lowering: BuiltinLowering::Runtime(RUNTIME_WRITER_WRITE_LINE),
+    },
+    BuiltinDecl {
+        name: "flush",
+        module: "std::buffered::Writer",
+        generics: &[],
+        bounds: &[],
+        params: WRITER_PARAM,
+        ret: BuiltinReturn::Fallible(BuiltinTy::Unit),
+        lowering: BuiltinLowering::Runtime(RUNTIME_WRITER_FLUSH),
+    },
+    BuiltinDecl {
+        name: "size_of",
+        module: "core::mem",
+        generics: &["T"],
+        bounds: &[],
+        params: &[],
+        ret: BuiltinReturn::Value(BuiltinTy::Usize),
+        lowering: BuiltinLowering::SizeOf,
+    },
+    BuiltinDecl {
+        name: "align_of",
+        module: "core::mem",
+        generics: &["T"],
+        bounds: &[],
+        params: &[],
+        ret: BuiltinReturn::Value(BuiltinTy::Usize),
+        lowering: BuiltinLowering::AlignOf,
+    },
+    BuiltinDecl {
+        name: "swap",
+        module: "core::mem",
+        generics: &["T"],
+        bounds: &[],
+        params: PLACE_PAIR_PARAMS,
+        ret: BuiltinReturn::Unit,
+        lowering: BuiltinLowering::Place(PlaceOp::Swap),
+    },
+    BuiltinDecl {
+        name: "replace",
+        module: "core::mem",
+        generics: &["T"],
+        bounds: &[],
+        params: PLACE_PAIR_PARAMS,
+        ret: BuiltinReturn::Value(PLACE),
+        lowering: BuiltinLowering::Place(PlaceOp::Replace),
+    },
+    BuiltinDecl {
+        name: "take",
+        module: "core::mem",
+        generics: &["T"],
+        bounds: &[&[DEFAULT_CONTRACT]],
+        params: &[TypeConstraint::Exactly(PLACE)],
+        ret: BuiltinReturn::Value(PLACE),
+        lowering: BuiltinLowering::Place(PlaceOp::Take),
+    },
+    BuiltinDecl {
+        name: "panic",
+        module: "core::panic",
+        generics: &[],
+        bounds: &[],
+        params: STR_PARAM,
+        ret: BuiltinReturn::Never,
+        lowering: BuiltinLowering::Panic,
+    },
+    BuiltinDecl {
+        name: "next",
+        module: "std::buffered::Lines",
+        generics: &[],
+        bounds: &[],
+        params: &[TypeConstraint::Exactly(LINES_TY)],
+        ret: BuiltinReturn::Slot(BuiltinTy::Str),
+        lowering: BuiltinLowering::Runtime(RUNTIME_LINES_NEXT),
+    },
+    BuiltinDecl {
+        name: "to_json",
+        module: "std::json",
+        generics: &["T"],
+        bounds: &[&[TO_JSON_CONTRACT]],
+        params: &[TypeConstraint::Exactly(JSON_VALUE)],
+        ret: BuiltinReturn::Value(JSON_TY),
+        lowering: BuiltinLowering::ToJson,
+    },
+    BuiltinDecl {
+        name: "from_json",
+        module: "std::json",
+        generics: &["T"],
+        bounds: &[&[FROM_JSON_CONTRACT]],
+        params: JSON_PARAM,
+        ret: BuiltinReturn::Fallible(JSON_VALUE),
+        lowering: BuiltinLowering::FromJson,
+    },
+    BuiltinDecl {
+        name: "default",
+        module: "core::default",
+        generics: &["T"],
+        bounds: &[&[DEFAULT_CONTRACT]],
+        params: &[],
+        ret: BuiltinReturn::Value(BuiltinTy::Generic(0)),
+        lowering: BuiltinLowering::Default,
+    },
+];
+
+pub const BUILTIN_CONTRACT_IMPLS: &[BuiltinContractImpl] = &[
+    BuiltinContractImpl { contract_id: DISPLAY_CONTRACT, for_type: PrimitiveType::Str },
+    BuiltinContractImpl { contract_id: DISPLAY_CONTRACT, for_type: PrimitiveType::Int },
+    BuiltinContractImpl { contract_id: HASH_CONTRACT, for_type: PrimitiveType::Bool },
+    BuiltinContractImpl { contract_id: HASH_CONTRACT, for_type: PrimitiveType::Char },
+    BuiltinContractImpl { contract_id: HASH_CONTRACT, for_type: PrimitiveType::Int },
+    BuiltinContractImpl { contract_id: HASH_CONTRACT, for_type: PrimitiveType::Str },
+    BuiltinContractImpl { contract_id: EQ_CONTRACT, for_type: PrimitiveType::Bool },
+    BuiltinContractImpl { contract_id: EQ_CONTRACT, for_type: PrimitiveType::Char },
+    BuiltinContractImpl { contract_id: EQ_CONTRACT, for_type: PrimitiveType::Int },
+    BuiltinContractImpl { contract_id: EQ_CONTRACT, for_type: PrimitiveType::Str },
+    BuiltinContractImpl { contract_id: INTEGER_CONTRACT, for_type: PrimitiveType::Int },
+    BuiltinContractImpl { contract_id: FLOAT_CONTRACT, for_type: PrimitiveType::Float },
+    BuiltinContractImpl { contract_id: NUMBER_CONTRACT, for_type: PrimitiveType::Int },
+    BuiltinContractImpl { contract_id: NUMBER_CONTRACT, for_type: PrimitiveType::Float },
+    BuiltinContractImpl { contract_id: DEFAULT_CONTRACT, for_type: PrimitiveType::Bool },
+    BuiltinContractImpl { contract_id: DEFAULT_CONTRACT, for_type: PrimitiveType::Char },
+    BuiltinContractImpl { contract_id: DEFAULT_CONTRACT, for_type: PrimitiveType::Int },
+    BuiltinContractImpl { contract_id: DEFAULT_CONTRACT, for_type: PrimitiveType::Float },
+    BuiltinContractImpl { contract_id: DEFAULT_CONTRACT, for_type: PrimitiveType::Str },
+    BuiltinContractImpl { contract_id: ORD_CONTRACT, for_type: PrimitiveType::Char },
+    BuiltinContractImpl { contract_id: ORD_CONTRACT, for_type: PrimitiveType::Int },
+    BuiltinContractImpl { contract_id: ORD_CONTRACT, for_type: PrimitiveType::Float },
+    BuiltinContractImpl { contract_id: ORD_CONTRACT, for_type: PrimitiveType::Str },
+    BuiltinContractImpl { contract_id: TO_JSON_CONTRACT, for_type: PrimitiveType::Bool },
+    BuiltinContractImpl { contract_id: TO_JSON_CONTRACT, for_type: PrimitiveType::Int },
+    BuiltinContractImpl { contract_id: TO_JSON_CONTRACT, for_type: PrimitiveType::Float },
+    BuiltinContractImpl { contract_id: TO_JSON_CONTRACT, for_type: PrimitiveType::Str },
+    BuiltinContractImpl { contract_id: FROM_JSON_CONTRACT, for_type: PrimitiveType::Bool },
+    BuiltinContractImpl { contract_id: FROM_JSON_CONTRACT, for_type: PrimitiveType::Int },
+    BuiltinContractImpl { contract_id: FROM_JSON_CONTRACT, for_type: PrimitiveType::Float },
+    BuiltinContractImpl { contract_id: FROM_JSON_CONTRACT, for_type: PrimitiveType::Str },
 ];
 
-pub const BUILTIN_CONTRACT_IMPLS: &[BuiltinContractImpl] =
-    &[BuiltinContractImpl { contract_id: DISPLAY_CONTRACT, for_type: PrimitiveType::Str }];
+/// Iterator adapters such as `map` and `windows`. Its public functions are
+/// also methods of every type implementing [`INTO_ITERATOR_CONTRACT`], taking
+/// the receiver as their first argument.
+pub const ITERTOOLS: StdModule =
+    StdModule { path: &["std", "itertools"], source: include_str!("../std/itertools.rnw") };
 
+pub const STD_MODULES: &[StdModule] = &[ITERTOOLS];
+
+/// Looks a prelude builtin up by its bare name, such as `println`.
 pub fn builtin_from_name(name: &str) -> Option<BuiltinId> {
-    BUILTINS.iter().position(|decl| decl.name == name).map(BuiltinId::from_index)
+    PRELUDE.iter().copied().find(|id| builtin_decl(*id).is_some_and(|decl| decl.name == name))
+}
+
+/// Modules of `std` that re-export a module of `core` under their own path.
+const STD_REEXPORTS: &[(&str, &str)] = &[("std::mem", "core::mem")];
+
+/// Rewrites a path through a `std` re-export to the `core` path it names,
+/// e.g. `std::mem::swap` to `core::mem::swap`.
+fn canonical_path(path: &str) -> Cow<'_, str> {
+    for (reexport, module) in STD_REEXPORTS {
+        if let Some(rest) = path.strip_prefix(reexport)
+            && (rest.is_empty() || rest.starts_with("::"))
+        {
+            return Cow::Owned(format!("{module}{rest}"));
+        }
+    }
+    Cow::Borrowed(path)
+}
+
+/// Looks a builtin up by its full path, such as `core::mem::drop` or its
+/// `std::mem::drop` re-export.
+pub fn builtin_from_path(path: &str) -> Option<BuiltinId> {
+    let path = canonical_path(path);
+    BUILTINS
+        .iter()
+        .position(|decl| {
+            path.strip_prefix(decl.module).and_then(|rest| rest.strip_prefix("::"))
+                == Some(decl.name)
+        })
+        .map(BuiltinId::from_index)
+}
+
+/// Whether `path` names a builtin module or one of its ancestors, such as
+/// `core` or `core::mem`.
+pub fn is_builtin_module(path: &str) -> bool {
+    let path = canonical_path(path);
+    let path = path.as_ref();
+    BUILTINS.iter().any(|decl| {
+        decl.module == path
+            || decl.module.strip_prefix(path).is_some_and(|rest| rest.starts_with("::"))
+    })
+}
+
+/// Looks a contract up by its canonical path or by its last segment alone.
+pub fn contract_from_name(name: &str) -> Option<ContractId> {
+    CONTRACTS
+        .iter()
+        .position(|decl| {
+            decl.canonical_name == name || decl.canonical_name.rsplit("::").next() == Some(name)
+        })
+        .map(ContractId::from_index)
+}
+
+/// Looks a builtin type up by its name or its full path, such as
+/// `core::ops::Range`.
+pub fn builtin_type_from_name(name: &str) -> Option<BuiltinTypeId> {
+    BUILTIN_TYPES
+        .iter()
+        .position(|decl| {
+            decl.name == name
+                || name.strip_prefix(decl.module).and_then(|rest| rest.strip_prefix("::"))
+                    == Some(decl.name)
+        })
+        .map(BuiltinTypeId::from_index)
+}
+
+/// Returns the full path of a builtin type, such as `core::vec::Vec`, which
+/// is also the module of its methods.
+pub fn builtin_type_path(id: BuiltinTypeId) -> Option<String> {
+    builtin_type_decl(id).map(|decl| format!("{}::{}", decl.module, decl.name))
+}
+
+pub fn builtin_type_decl(id: BuiltinTypeId) -> Option<&'static BuiltinTypeDecl> {
+    BUILTIN_TYPES.get(id.index())
+}
+
+pub fn builtin_type_implements(id: BuiltinTypeId, contract_id: ContractId) -> bool {
+    builtin_type_decl(id).is_some_and(|decl| decl.implements.contains(&contract_id))
 }
 
 pub fn builtin_decl(id: BuiltinId) -> Option<&'static BuiltinDecl> {
@@ -133,22 +2141,145 @@ impl fmt::Display for ContractId {
 
 #[cfg(test)]
 mod tests {
-    use runec_abi::{RUNTIME_PRINTLN, runtime_function};
+    use runec_abi::{RUNTIME_FLUSH, RUNTIME_INPUT, RUNTIME_PRINTLN, RUNTIME_SIN, runtime_function};
 
     use super::{
-        BuiltinLowering, DISPLAY_CONTRACT, PRINTLN, PrimitiveType, builtin_decl, builtin_from_name,
-        primitive_implements,
+        ADD_CONTRACT, BuiltinLowering, BuiltinReturn, BuiltinTy, DECIMAL, DECIMAL_MUL,
+        DECIMAL_TO_STRING, DEFAULT, DEFAULT_CONTRACT, DISPLAY_CONTRACT, DROP, EQ_CONTRACT,
+        FLOAT_CONTRACT, FLUSH, FROM_JSON_CONTRACT, HASH_CONTRACT, HASH_MAP, HASH_SET, INPUT,
+        INTEGER_CONTRACT, INTO_ITERATOR_CONTRACT, ITERATOR_CONTRACT, Intrinsic, JSON,
+        JSON_FROM_JSON, JSON_TO_JSON, LINES, MAP_INSERT, MATH_CHECKED_ADD, MATH_PI, MATH_SIN,
+        MATH_SQRT, MEM_SWAP, MEM_TAKE, MUL_CONTRACT, NUMBER_CONTRACT, OPEN_READER, PANIC, PRINTLN,
+        PlaceOp, PrimitiveType, RANGE, READER_READ_LINE, SET_CONTAINS, SLICE_LEN, SUB_CONTRACT,
+        TO_JSON_CONTRACT, TypeConstraint, VEC, VEC_POP, VEC_PUSH, WRITER, WRITER_FLUSH,
+        builtin_decl, builtin_from_name, builtin_from_path, builtin_type_decl,
+        builtin_type_from_name, builtin_type_implements, builtin_type_path, contract_decl,
+        contract_from_name, is_builtin_module, primitive_implements,
     };
 
     #[test]
     fn resolves_builtin_from_its_language_name() {
         assert_eq!(builtin_from_name("println"), Some(PRINTLN));
+        assert_eq!(builtin_from_name("panic"), Some(PANIC));
         assert_eq!(builtin_from_name("unknown"), None);
     }
 
     #[test]
-    fn display_is_implemented_for_strings() {
+    fn resolves_builtin_from_its_module_path() {
+        assert_eq!(builtin_from_path("core::mem::drop"), Some(DROP));
+        assert_eq!(builtin_from_path("std::io::println"), Some(PRINTLN));
+        assert_eq!(builtin_from_path("core::panic::panic"), Some(PANIC));
+        assert_eq!(builtin_from_path("core::drop"), None);
+        assert!(is_builtin_module("core"));
+        assert!(is_builtin_module("core::mem"));
+        assert!(!is_builtin_module("core::me"));
+    }
+
+    #[test]
+    fn mem_builtins_resolve_through_core_and_std_but_not_the_prelude() {
+        assert_eq!(builtin_from_path("core::mem::swap"), Some(MEM_SWAP));
+        assert_eq!(builtin_from_path("std::mem::swap"), Some(MEM_SWAP));
+        assert_eq!(builtin_from_path("std::mem::drop"), Some(DROP));
+        assert_eq!(builtin_from_path("std::memory::drop"), None);
+        assert!(is_builtin_module("std::mem"));
+        assert!(!is_builtin_module("std::me"));
+        assert_eq!(builtin_from_name("drop"), None);
+        assert_eq!(builtin_from_name("size_of"), None);
+
+        assert_eq!(builtin_from_path("std::mem::take"), Some(MEM_TAKE));
+        let take = builtin_decl(MEM_TAKE).expect("take declaration");
+        assert_eq!(take.lowering, BuiltinLowering::Place(PlaceOp::Take));
+        assert_eq!(take.bounds, &[&[DEFAULT_CONTRACT]]);
+        assert_eq!(take.ret, BuiltinReturn::Value(BuiltinTy::Generic(0)));
+        assert_eq!(PlaceOp::Swap.places(), 2);
+        assert_eq!(PlaceOp::Replace.places(), 1);
+    }
+
+    #[test]
+    fn resolves_contract_from_canonical_or_short_name() {
+        assert_eq!(contract_from_name("core::fmt::Display"), Some(DISPLAY_CONTRACT));
+        assert_eq!(contract_from_name("Display"), Some(DISPLAY_CONTRACT));
+        assert_eq!(contract_from_name("fmt::Display"), None);
+    }
+
+    #[test]
+    fn range_is_an_iterator_named_by_name_or_path() {
+        assert_eq!(builtin_type_from_name("Range"), Some(RANGE));
+        assert_eq!(builtin_type_from_name("core::ops::Range"), Some(RANGE));
+        assert_eq!(builtin_type_from_name("ops::Range"), None);
+        assert!(builtin_type_implements(RANGE, ITERATOR_CONTRACT));
+        assert!(!builtin_type_implements(RANGE, DISPLAY_CONTRACT));
+        assert_eq!(contract_from_name("Iterator"), Some(ITERATOR_CONTRACT));
+        assert_eq!(contract_decl(ITERATOR_CONTRACT).map(|decl| decl.methods), Some(&["next"][..]));
+    }
+
+    #[test]
+    fn vectors_ranges_and_lines_are_into_iterators_of_their_items() {
+        assert_eq!(contract_from_name("core::iter::IntoIterator"), Some(INTO_ITERATOR_CONTRACT));
+        assert_eq!(
+            contract_decl(INTO_ITERATOR_CONTRACT).map(|decl| decl.generics),
+            Some(&["T"][..])
+        );
+        for ty in [VEC, RANGE, LINES] {
+            assert!(builtin_type_implements(ty, INTO_ITERATOR_CONTRACT));
+        }
+        assert!(!builtin_type_implements(HASH_MAP, INTO_ITERATOR_CONTRACT));
+    }
+
+    #[test]
+    fn decimal_operators_name_the_methods_they_lower_to() {
+        assert_eq!(contract_from_name("core::ops::Add"), Some(ADD_CONTRACT));
+        assert_eq!(contract_decl(SUB_CONTRACT).map(|decl| decl.methods), Some(&["sub"][..]));
+        for contract in [DISPLAY_CONTRACT, ADD_CONTRACT, SUB_CONTRACT, MUL_CONTRACT] {
+            assert!(builtin_type_implements(DECIMAL, contract));
+        }
+        assert!(!builtin_type_implements(DECIMAL, HASH_CONTRACT));
+        assert_eq!(builtin_from_path("std::decimal::Decimal::mul"), Some(DECIMAL_MUL));
+        assert_eq!(builtin_from_path("std::decimal::Decimal::to_string"), Some(DECIMAL_TO_STRING));
+    }
+
+    #[test]
+    fn vec_methods_live_under_the_type_path_and_not_in_the_prelude() {
+        assert_eq!(builtin_type_from_name("Vec"), Some(VEC));
+        assert_eq!(builtin_type_path(VEC).as_deref(), Some("core::vec::Vec"));
+        assert_eq!(builtin_from_path("core::vec::Vec::push"), Some(VEC_PUSH));
+        assert_eq!(builtin_from_path("core::slice::len"), Some(SLICE_LEN));
+        assert!(is_builtin_module("core::vec::Vec"));
+        assert_eq!(builtin_from_name("push"), None);
+
+        let pop = builtin_decl(VEC_POP).expect("pop declaration");
+        assert_eq!(pop.ret, BuiltinReturn::Value(BuiltinTy::Nullable(&BuiltinTy::Generic(0))));
+        assert_eq!(pop.lowering, BuiltinLowering::Intrinsic(Intrinsic::VecPop));
+    }
+
+    #[test]
+    fn hash_collections_live_in_std_collections_with_hashable_keys() {
+        assert_eq!(builtin_type_from_name("std::collections::HashMap"), Some(HASH_MAP));
+        assert_eq!(builtin_type_from_name("HashSet"), Some(HASH_SET));
+        assert!(is_builtin_module("std::collections::HashMap"));
+        assert_eq!(builtin_from_path("std::collections::HashMap::insert"), Some(MAP_INSERT));
+        assert_eq!(builtin_from_path("std::collections::HashSet::contains"), Some(SET_CONTAINS));
+        assert_eq!(
+            builtin_type_decl(HASH_MAP).map(|decl| decl.bounds),
+            Some(&[&[HASH_CONTRACT, EQ_CONTRACT][..]][..])
+        );
+
+        let insert = builtin_decl(MAP_INSERT).expect("insert declaration");
+        assert_eq!(insert.ret, BuiltinReturn::Value(BuiltinTy::Nullable(&BuiltinTy::Generic(1))));
+        assert_eq!(contract_from_name("core::hash::Hash"), Some(HASH_CONTRACT));
+        for primitive in [PrimitiveType::Bool, PrimitiveType::Int, PrimitiveType::Str] {
+            assert!(primitive_implements(primitive, HASH_CONTRACT));
+            assert!(primitive_implements(primitive, EQ_CONTRACT));
+        }
+        assert!(!primitive_implements(PrimitiveType::Float, HASH_CONTRACT));
+        assert!(!primitive_implements(PrimitiveType::Float, EQ_CONTRACT));
+        assert!(!primitive_implements(PrimitiveType::Float, DISPLAY_CONTRACT));
+    }
+
+    #[test]
+    fn display_is_implemented_for_strings_and_integers() {
         assert!(primitive_implements(PrimitiveType::Str, DISPLAY_CONTRACT));
+        assert!(primitive_implements(PrimitiveType::Int, DISPLAY_CONTRACT));
     }
 
     #[test]
@@ -160,4 +2291,95 @@ mod tests {
             "__runeway_println"
         );
     }
+
+    #[test]
+    fn input_reads_a_nullable_line_through_the_runtime() {
+        assert_eq!(builtin_from_name("input"), Some(INPUT));
+        assert_eq!(builtin_from_path("std::io::input"), Some(INPUT));
+
+        let builtin = builtin_decl(INPUT).expect("input declaration");
+        assert_eq!(builtin.ret, BuiltinReturn::Slot(BuiltinTy::Str));
+        assert_eq!(builtin.lowering, BuiltinLowering::Runtime(RUNTIME_INPUT));
+        assert_eq!(
+            runtime_function(RUNTIME_INPUT).expect("runtime declaration").symbol,
+            "__runeway_input"
+        );
+    }
+
+    #[test]
+    fn math_lives_in_std_math_with_numeric_bounds() {
+        assert!(is_builtin_module("std::math"));
+        assert_eq!(builtin_from_path("std::math::sqrt"), Some(MATH_SQRT));
+        assert_eq!(builtin_from_name("sqrt"), None);
+
+        let sqrt = builtin_decl(MATH_SQRT).expect("sqrt declaration");
+        assert_eq!(sqrt.bounds, &[&[FLOAT_CONTRACT][..]]);
+        assert_eq!(sqrt.lowering, BuiltinLowering::Intrinsic(Intrinsic::Sqrt));
+        let checked = builtin_decl(MATH_CHECKED_ADD).expect("checked_add declaration");
+        assert_eq!(checked.bounds, &[&[INTEGER_CONTRACT][..]]);
+        assert_eq!(checked.ret, BuiltinReturn::Value(BuiltinTy::Nullable(&BuiltinTy::Generic(0))));
+        assert_eq!(
+            builtin_decl(MATH_SIN).map(|decl| decl.lowering),
+            Some(BuiltinLowering::Runtime(RUNTIME_SIN))
+        );
+        assert_eq!(
+            builtin_decl(MATH_PI).map(|decl| decl.lowering),
+            Some(BuiltinLowering::Constant(std::f64::consts::PI.to_bits()))
+        );
+        for primitive in [PrimitiveType::Int, PrimitiveType::Float] {
+            assert!(primitive_implements(primitive, NUMBER_CONTRACT));
+        }
+        assert!(!primitive_implements(PrimitiveType::Float, INTEGER_CONTRACT));
+    }
+
+    #[test]
+    fn drop_accepts_any_value_and_is_lowered_in_place() {
+        let builtin = builtin_decl(DROP).expect("drop declaration");
+        assert_eq!(builtin.params, &[TypeConstraint::Any]);
+        assert_eq!(builtin.lowering, BuiltinLowering::Drop);
+    }
+
+    #[test]
+    fn flush_is_in_the_prelude_and_buffered_io_in_std_buffered() {
+        assert_eq!(builtin_from_name("flush"), Some(FLUSH));
+        assert_eq!(builtin_from_path("std::io::flush"), Some(FLUSH));
+        assert_eq!(builtin_from_path("std::buffered::open_reader"), Some(OPEN_READER));
+        assert_eq!(builtin_type_from_name("std::buffered::Writer"), Some(WRITER));
+        assert_eq!(builtin_from_path("std::buffered::Writer::flush"), Some(WRITER_FLUSH));
+
+        let flush = builtin_decl(FLUSH).expect("flush declaration");
+        assert_eq!(flush.lowering, BuiltinLowering::Runtime(RUNTIME_FLUSH));
+        assert_eq!(
+            runtime_function(RUNTIME_FLUSH).expect("runtime declaration").symbol,
+            "__runeway_flush"
+        );
+        let read_line = builtin_decl(READER_READ_LINE).expect("read_line declaration");
+        assert_eq!(read_line.ret, BuiltinReturn::Slot(BuiltinTy::Str));
+    }
+
+    #[test]
+    fn default_values_are_bounded_by_the_default_contract() {
+        assert_eq!(builtin_from_path("core::default::default"), Some(DEFAULT));
+
+        let default = builtin_decl(DEFAULT).expect("default declaration");
+        assert_eq!(default.bounds, &[&[DEFAULT_CONTRACT][..]]);
+        assert_eq!(default.ret, BuiltinReturn::Value(BuiltinTy::Generic(0)));
+        assert_eq!(default.lowering, BuiltinLowering::Default);
+    }
+
+    #[test]
+    fn json_conversions_are_bounded_by_contracts_json_and_primitives_implement() {
+        assert_eq!(builtin_from_path("std::json::to_json"), Some(JSON_TO_JSON));
+        assert_eq!(contract_from_name("FromJson"), Some(FROM_JSON_CONTRACT));
+
+        let from_json = builtin_decl(JSON_FROM_JSON).expect("from_json declaration");
+        assert_eq!(from_json.bounds, &[&[FROM_JSON_CONTRACT][..]]);
+        assert_eq!(from_json.ret, BuiltinReturn::Fallible(BuiltinTy::Generic(0)));
+        assert_eq!(from_json.lowering, BuiltinLowering::FromJson);
+        for contract in [TO_JSON_CONTRACT, FROM_JSON_CONTRACT] {
+            assert!(builtin_type_implements(JSON, contract));
+            assert!(primitive_implements(PrimitiveType::Float, contract));
+            assert!(!primitive_implements(PrimitiveType::Char, contract));
+        }
+    }
 }
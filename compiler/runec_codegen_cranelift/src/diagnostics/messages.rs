@@ -8,6 +8,11 @@ define_messages! {
     UNKNOWN_LOCAL => "unknown local { local } during code generation",
     MISSING_ENTRY_BLOCK => "missing MIR entry block during code generation",
     UNKNOWN_FUNCTION => "unknown function { function } during code generation",
+    UNRESOLVED_EXTERN => "cannot find extern function `{ symbol }` in the running process",
+    MISSING_CLOSURE_ENV => "closure capture outside a closure body during code generation",
+    UNTYPED_OPERAND => "operand without a local type during code generation",
     ABI_ARITY_MISMATCH => "assignment ABI arity mismatch during code generation",
     BACKEND_FAILURE => "code generation backend failed: { error }",
+    PROGRAM_PANICKED => "panicked at { location }: { message }",
+    UNCAUGHT_ERROR => "uncaught error: { message }",
 }
@@ -20,3 +20,8 @@ pub(crate) fn backend(error_value: impl std::fmt::Display, span: Span) -> Box<Di
     let error_value = error_value.to_string();
     error(messages::BACKEND_FAILURE, &[("error", &error_value)], span)
 }
+
+pub(crate) fn unsupported_type(ty: &impl std::fmt::Debug, span: Span) -> Box<Diagnostic<'static>> {
+    let ty = format!("{ty:?}");
+    error(messages::UNSUPPORTED_TYPE, &[("type", &ty)], span)
+}
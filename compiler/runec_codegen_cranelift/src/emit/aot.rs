@@ -1,18 +1,20 @@
-use cranelift_codegen::isa::OwnedTargetIsa;
-use cranelift_codegen::settings;
 use cranelift_object::{ObjectBuilder, ObjectModule};
 use runec_mir::MirModule;
+use runec_source::source_map::SourceMap;
 use runec_source::span::Span;
 
+use super::native_isa;
 use crate::diagnostics::backend;
 use crate::{CodegenOptions, CodegenResult, CraneliftLowerer};
 
 /// Emits an object file from the same Cranelift IR path used by the JIT.
+/// A panic in the program reports its message and exits with status 101.
 pub struct AotBackend;
 
 impl AotBackend {
     pub fn emit_object(
         mir: &MirModule<'_>,
+        source_map: &SourceMap,
         name: &str,
         diagnostic_span: Span,
     ) -> CodegenResult<Vec<u8>> {
@@ -23,24 +25,22 @@ impl AotBackend {
         )
         .map_err(|error| backend(error, diagnostic_span))?;
         let mut module = ObjectModule::new(builder);
-        CraneliftLowerer::new(CodegenOptions::aot()).compile(&mut module, mir, diagnostic_span)?;
+        CraneliftLowerer::new(CodegenOptions::aot()).compile(
+            &mut module,
+            mir,
+            source_map,
+            diagnostic_span,
+        )?;
         module.finish().emit().map_err(|error| backend(error, diagnostic_span))
     }
 }
 
-fn native_isa(diagnostic_span: Span) -> CodegenResult<OwnedTargetIsa> {
-    cranelift_native::builder()
-        .map_err(|error| backend(error.to_string(), diagnostic_span))?
-        .finish(settings::Flags::new(settings::builder()))
-        .map_err(|error| backend(error, diagnostic_span))
-}
-
 #[cfg(test)]
 mod tests {
     use runec_hir::ids::HirId;
     use runec_mir::{MirBlock, MirFunction, MirModule, MirTerminator, MirTy};
     use runec_source::byte_pos::BytePos;
-    use runec_source::source_map::SourceId;
+    use runec_source::source_map::{SourceId, SourceMap};
     use runec_source::span::Span;
 
     use super::AotBackend;
@@ -55,14 +55,23 @@ mod tests {
         main.entry = main.push_block(MirBlock::new(MirTerminator::Return(None)));
         let main = module.push_function(main);
         module.entry = Some(main);
-        assert!(!AotBackend::emit_object(&module, "runeway_test", span()).unwrap().is_empty());
+        assert!(
+            !AotBackend::emit_object(&module, &SourceMap::new(), "runeway_test", span())
+                .unwrap()
+                .is_empty()
+        );
     }
 
     #[test]
     fn missing_entry_diagnostic_uses_fallback_span() {
         let diagnostic_span = span();
-        let error = AotBackend::emit_object(&MirModule::new(), "runeway_test", diagnostic_span)
-            .expect_err("module without an entry should fail codegen");
+        let error = AotBackend::emit_object(
+            &MirModule::new(),
+            &SourceMap::new(),
+            "runeway_test",
+            diagnostic_span,
+        )
+        .expect_err("module without an entry should fail codegen");
 
         assert_eq!(error.labels[0].span, diagnostic_span);
     }
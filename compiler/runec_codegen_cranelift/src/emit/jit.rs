@@ -1,18 +1,112 @@
-use cranelift_codegen::isa::OwnedTargetIsa;
-use cranelift_codegen::settings;
+use std::collections::HashSet;
+use std::ffi::CString;
+use std::panic::{self, AssertUnwindSafe};
+
 use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::Module;
+use runec_abi::{
+    RUNTIME_BEGIN_RUN, RUNTIME_COLLECT_CYCLES, RUNTIME_END_RUN, RUNTIME_FLUSH, RUNTIME_PANIC,
+    RUNTIME_UNCAUGHT_ERROR, runtime_function,
+};
 use runec_mir::{MirModule, MirTy};
+use runec_source::source_map::SourceMap;
 use runec_source::span::Span;
 
+use super::native_isa;
+use super::unwind::RegisteredFrames;
 use crate::diagnostics::{backend, error, messages};
+use crate::lowering::panic_sites;
 use crate::{CodegenOptions, CodegenResult, CraneliftLowerer};
 
 /// Finalizes shared Cranelift IR in memory and invokes its entry point.
+///
+/// A panic in the program, or an error thrown out of `main`, unwinds back
+/// out of it and is returned as an error by [`JitBackend::run`], rather than
+/// exiting the host process as the runtime does for AOT builds. Unwinding
+/// does not release the values held by the frames it leaves; the runtime
+/// frees everything the run allocated that is still live instead.
 pub struct JitBackend {
     module: JITModule,
+    /// Symbols registered up front; everything else is looked up with `dlsym`.
+    symbols: HashSet<&'static str>,
+    /// The runtime function writing out buffered output, if registered.
+    flush: Option<*const u8>,
+    /// The runtime function collecting reference cycles, if registered.
+    collect_cycles: Option<*const u8>,
+    /// The runtime functions recording the allocations of a run, if
+    /// registered.
+    begin_run: Option<*const u8>,
+    end_run: Option<*const u8>,
+    /// Unwind information of the compiled functions, registered while the
+    /// code they describe is alive.
+    frames: Vec<RegisteredFrames>,
     diagnostic_span: Span,
 }
 
+/// Failure of the program, carried by unwinding from [`jit_panic`] or
+/// [`jit_uncaught_error`] to [`JitBackend::run`].
+enum JitFailure {
+    /// `panic` call or failed bounds check, with the location it was made at.
+    Panic { message: String, file: String, line: usize, col: usize },
+    /// Error thrown out of `main`, with the location of the `throw` or `?`
+    /// it escaped through.
+    Uncaught { message: String, file: String, line: usize, col: usize },
+}
+
+/// Reads the text a program passes to the runtime.
+///
+/// # Safety
+///
+/// `ptr` must be null only when `len` is zero. Otherwise it must point to
+/// `len` readable bytes.
+unsafe fn abi_text(ptr: *const u8, len: usize) -> String {
+    if ptr.is_null() {
+        return String::new();
+    }
+    // SAFETY: Forwarded from the contract of this function.
+    String::from_utf8_lossy(unsafe { std::slice::from_raw_parts(ptr, len) }).into_owned()
+}
+
+/// Takes the place of the runtime's `__runeway_panic`, unwinding through the
+/// generated code instead of exiting the process.
+///
+/// # Safety
+///
+/// Each pointer must be null only when its length is zero. Otherwise it
+/// must point to that many readable bytes.
+unsafe extern "C-unwind" fn jit_panic(
+    msg_ptr: *const u8,
+    msg_len: usize,
+    file_ptr: *const u8,
+    file_len: usize,
+    line: usize,
+    col: usize,
+) {
+    // SAFETY: Forwarded from the contract of this function.
+    let (message, file) = unsafe { (abi_text(msg_ptr, msg_len), abi_text(file_ptr, file_len)) };
+    panic::resume_unwind(Box::new(JitFailure::Panic { message, file, line, col }));
+}
+
+/// Takes the place of the runtime's `__runeway_uncaught_error`, unwinding
+/// through the generated code instead of exiting the process.
+///
+/// # Safety
+///
+/// Each pointer must be null only when its length is zero. Otherwise it
+/// must point to that many readable bytes.
+unsafe extern "C-unwind" fn jit_uncaught_error(
+    msg_ptr: *const u8,
+    msg_len: usize,
+    file_ptr: *const u8,
+    file_len: usize,
+    line: usize,
+    col: usize,
+) {
+    // SAFETY: Forwarded from the contract of this function.
+    let (message, file) = unsafe { (abi_text(msg_ptr, msg_len), abi_text(file_ptr, file_len)) };
+    panic::resume_unwind(Box::new(JitFailure::Uncaught { message, file, line, col }));
+}
+
 impl JitBackend {
     pub fn new(
         symbols: impl IntoIterator<Item = (&'static str, *const u8)>,
@@ -22,19 +116,80 @@ impl JitBackend {
             native_isa(diagnostic_span)?,
             cranelift_module::default_libcall_names(),
         );
+        let flush_symbol = runtime_function(RUNTIME_FLUSH).map(|decl| decl.symbol);
+        let collect_symbol = runtime_function(RUNTIME_COLLECT_CYCLES).map(|decl| decl.symbol);
+        let begin_symbol = runtime_function(RUNTIME_BEGIN_RUN).map(|decl| decl.symbol);
+        let end_symbol = runtime_function(RUNTIME_END_RUN).map(|decl| decl.symbol);
+        let mut names = HashSet::new();
+        let mut flush = None;
+        let mut collect_cycles = None;
+        let mut begin_run = None;
+        let mut end_run = None;
         for (name, address) in symbols {
             builder.symbol(name, address);
+            names.insert(name);
+            if Some(name) == flush_symbol {
+                flush = Some(address);
+            } else if Some(name) == collect_symbol {
+                collect_cycles = Some(address);
+            } else if Some(name) == begin_symbol {
+                begin_run = Some(address);
+            } else if Some(name) == end_symbol {
+                end_run = Some(address);
+            }
         }
-        Ok(Self { module: JITModule::new(builder), diagnostic_span })
+        for (id, address) in [
+            (RUNTIME_PANIC, jit_panic as *const u8),
+            (RUNTIME_UNCAUGHT_ERROR, jit_uncaught_error as *const u8),
+        ] {
+            if let Some(decl) = runtime_function(id) {
+                builder.symbol(decl.symbol, address);
+                names.insert(decl.symbol);
+            }
+        }
+        Ok(Self {
+            module: JITModule::new(builder),
+            symbols: names,
+            flush,
+            collect_cycles,
+            begin_run,
+            end_run,
+            frames: Vec::new(),
+            diagnostic_span,
+        })
     }
 
-    pub fn run(&mut self, mir: &MirModule<'_>) -> CodegenResult<()> {
+    /// Compiles `mir` and runs its entry point, returning an error if the
+    /// program panics or throws an error out of `main`. Panic locations are
+    /// resolved through `source_map`.
+    pub fn run(&mut self, mir: &MirModule<'_>, source_map: &SourceMap) -> CodegenResult<()> {
+        // The JIT resolves imports while finalizing and panics on a missing
+        // one, so extern functions are checked beforehand.
+        for function in &mir.externs {
+            if !self.symbols.contains(function.name) && lookup_symbol(function.name).is_none() {
+                return Err(error(
+                    messages::UNRESOLVED_EXTERN,
+                    &[("symbol", function.name)],
+                    function.span,
+                ));
+            }
+        }
         let compiled = CraneliftLowerer::new(CodegenOptions::jit()).compile(
             &mut self.module,
             mir,
+            source_map,
             self.diagnostic_span,
         )?;
         self.module.finalize_definitions().map_err(|error| backend(error, self.diagnostic_span))?;
+        let functions = compiled
+            .unwind
+            .iter()
+            .map(|(id, info)| (self.module.get_finalized_function(*id), info));
+        if let Some(frames) =
+            RegisteredFrames::register(self.module.isa(), functions, self.diagnostic_span)?
+        {
+            self.frames.push(frames);
+        }
         let function = mir.function(compiled.entry);
         if !function.params.is_empty() || function.ret_ty != MirTy::Unit {
             let function_id = format!("{:?}", compiled.entry);
@@ -46,31 +201,103 @@ impl JitBackend {
         }
         let address = self.module.get_finalized_function(compiled.entry_func);
         // SAFETY: the entry signature is checked above and finalized by JITModule.
-        let entry: unsafe extern "C" fn() = unsafe { std::mem::transmute(address) };
-        unsafe { entry() };
-        Ok(())
+        let entry: unsafe extern "C-unwind" fn() = unsafe { std::mem::transmute(address) };
+        if let Some(begin_run) = self.begin_run {
+            // SAFETY: The runtime function starting a run takes no arguments.
+            let begin_run: extern "C" fn() = unsafe { std::mem::transmute(begin_run) };
+            begin_run();
+        }
+        let result = panic::catch_unwind(AssertUnwindSafe(|| unsafe { entry() }));
+        // Output the program buffered is written out when it returns or
+        // panics, as it would be when an AOT build exits.
+        if let Some(flush) = self.flush {
+            // SAFETY: The runtime flush function takes no arguments.
+            let flush: extern "C" fn() = unsafe { std::mem::transmute(flush) };
+            flush();
+        }
+        // Cycles the program left behind are freed before the next run
+        // rather than kept alive for as long as the host process.
+        if let Some(collect_cycles) = self.collect_cycles {
+            // SAFETY: The runtime cycle collector takes no arguments.
+            let collect_cycles: extern "C" fn() = unsafe { std::mem::transmute(collect_cycles) };
+            collect_cycles();
+        }
+        // The values the unwound frames held are freed, as the process exit
+        // would free them for AOT builds.
+        if let Some(end_run) = self.end_run {
+            // SAFETY: The runtime function ending a run takes whether it
+            // unwound, after which nothing the run allocated is used.
+            let end_run: unsafe extern "C" fn(bool) = unsafe { std::mem::transmute(end_run) };
+            unsafe { end_run(result.is_err()) };
+        }
+        let payload = match result {
+            Ok(()) => return Ok(()),
+            Err(payload) => payload,
+        };
+        // The failure is labelled at the site whose location it carries.
+        let site = |file: &str, line, col| {
+            panic_sites(mir)
+                .find(|span| {
+                    source_map.location(*span).is_some_and(|location| {
+                        location.line == line
+                            && location.col == col
+                            && location.path.display().to_string() == file
+                    })
+                })
+                .unwrap_or(self.diagnostic_span)
+        };
+        match payload.downcast::<JitFailure>().map(|failure| *failure) {
+            Ok(JitFailure::Panic { message, file, line, col }) => {
+                let location = format!("{file}:{line}:{col}");
+                Err(error(
+                    messages::PROGRAM_PANICKED,
+                    &[("location", &location), ("message", &message)],
+                    site(&file, line, col),
+                ))
+            }
+            Ok(JitFailure::Uncaught { message, file, line, col }) => Err(error(
+                messages::UNCAUGHT_ERROR,
+                &[("message", &message)],
+                site(&file, line, col),
+            )),
+            // Anything else unwinding out of the program is not its failure
+            // to report.
+            Err(payload) => panic::resume_unwind(payload),
+        }
     }
 }
 
-fn native_isa(diagnostic_span: Span) -> CodegenResult<OwnedTargetIsa> {
-    cranelift_native::builder()
-        .map_err(|error| backend(error.to_string(), diagnostic_span))?
-        .finish(settings::Flags::new(settings::builder()))
-        .map_err(|error| backend(error, diagnostic_span))
+/// Looks `name` up in the running process, as the JIT does for imports.
+fn lookup_symbol(name: &str) -> Option<*const u8> {
+    let name = CString::new(name).ok()?;
+    // SAFETY: `name` is a valid NUL-terminated string for the whole call.
+    let address = unsafe { libc::dlsym(libc::RTLD_DEFAULT, name.as_ptr()) };
+    (!address.is_null()).then_some(address as *const u8)
 }
 
 #[cfg(test)]
 mod tests {
-    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::io::Write;
+    use std::path::PathBuf;
+    use std::process::{Command, Stdio};
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU8, AtomicUsize, Ordering};
 
-    use runec_abi::RUNTIME_PRINTLN;
+    use runec_abi::{
+        RUNTIME_ALLOC, RUNTIME_INPUT, RUNTIME_INSTANT_ELAPSED, RUNTIME_INSTANT_NOW, RUNTIME_POW,
+        RUNTIME_PRINTLN, RUNTIME_RANDOM_INT_RANGE, RUNTIME_RANDOM_SEED, RUNTIME_READ_TO_STRING,
+        RUNTIME_ROUND, RUNTIME_SLEEP, RUNTIME_UNCAUGHT_ERROR, RUNTIME_WRITE,
+    };
+    use runec_builtins::{Intrinsic, TypeBits};
     use runec_hir::ids::HirId;
     use runec_mir::{
-        MirBlock, MirCallee, MirConstant, MirFunction, MirModule, MirOperand, MirPlace, MirRvalue,
-        MirStmt, MirTerminator, MirTy,
+        MirBinOp, MirBlock, MirBlockId, MirCallee, MirClosureId, MirConstant, MirExtern,
+        MirExternId, MirFloatTy, MirFunction, MirImmediate, MirInstance, MirIntTy, MirLocalId,
+        MirModule, MirOperand, MirPlace, MirRvalue, MirStmt, MirStruct, MirTerminator, MirTy,
     };
+    use runec_semantic::typeck::{GenericArg, Ty};
     use runec_source::byte_pos::BytePos;
-    use runec_source::source_map::SourceId;
+    use runec_source::source_map::{SourceId, SourceMap};
     use runec_source::span::Span;
 
     use super::JitBackend;
@@ -108,7 +335,2252 @@ mod tests {
         CALLED.store(false, Ordering::SeqCst);
         let mut backend =
             JitBackend::new([("__runeway_println", test_println as *const u8)], span()).unwrap();
-        backend.run(&hello_module()).unwrap();
+        backend.run(&hello_module(), &SourceMap::new()).unwrap();
         assert!(CALLED.load(Ordering::SeqCst));
     }
+
+    fn usize_operand(value: u128) -> MirOperand {
+        MirOperand::Immediate(MirImmediate::Int {
+            value,
+            ty: MirIntTy { signed: false, bits: TypeBits::B64 },
+        })
+    }
+
+    /// `make` allocates and returns a managed value; `main` keeps two copies
+    /// of it.
+    fn managed_module() -> MirModule<'static> {
+        let mut module = MirModule::new();
+
+        let mut make =
+            MirFunction::new(HirId::from_usize(0), "make", MirTy::Managed, span(), span());
+        let allocated = make.push_local(None, MirTy::Managed, span());
+        let mut entry =
+            MirBlock::new(MirTerminator::Return(Some(MirOperand::Copy(MirPlace::new(allocated)))));
+        entry.stmts.push(MirStmt::Assign {
+            dst: MirPlace::new(allocated),
+            rhs: MirRvalue::Call {
+                callee: MirCallee::Runtime(RUNTIME_ALLOC),
+                args: Box::new([usize_operand(16), usize_operand(8)]),
+            },
+            span: span(),
+        });
+        make.entry = make.push_block(entry);
+        module.push_function(make);
+
+        let mut main = MirFunction::new(HirId::from_usize(1), "main", MirTy::Unit, span(), span());
+        let first = main.push_local(Some("first"), MirTy::Managed, span());
+        let second = main.push_local(Some("second"), MirTy::Managed, span());
+        let mut entry = MirBlock::new(MirTerminator::Return(None));
+        entry.stmts.push(MirStmt::Assign {
+            dst: MirPlace::new(first),
+            rhs: MirRvalue::Call {
+                callee: MirCallee::Function(HirId::from_usize(0)),
+                args: Box::new([]),
+            },
+            span: span(),
+        });
+        entry.stmts.push(MirStmt::Assign {
+            dst: MirPlace::new(second),
+            rhs: MirRvalue::Use(MirOperand::Copy(MirPlace::new(first))),
+            span: span(),
+        });
+        main.entry = main.push_block(entry);
+        let main = module.push_function(main);
+        module.entry = Some(main);
+        module
+    }
+
+    #[test]
+    fn releases_managed_locals_without_leaks() {
+        let symbols = runec_runtime::symbols().map(|symbol| (symbol.name, symbol.address));
+        let mut backend = JitBackend::new(symbols, span()).unwrap();
+
+        let before = runec_runtime::heap_stats();
+        backend.run(&managed_module(), &SourceMap::new()).unwrap();
+        let after = runec_runtime::heap_stats();
+
+        assert_eq!(after.allocations, before.allocations + 1);
+        assert_eq!(after.live_allocations(), before.live_allocations());
+        assert_eq!(after.live_bytes, before.live_bytes);
+    }
+
+    #[test]
+    fn moves_and_drops_hand_over_references() {
+        let mut module = managed_module();
+        module.functions[0].blocks[0].terminator =
+            MirTerminator::Return(Some(MirOperand::Move(MirPlace::new(MirLocalId::from_usize(0)))));
+        let main = &mut module.functions[1];
+        let third = main.push_local(Some("third"), MirTy::Managed, span());
+        let stmts = &mut main.blocks[0].stmts;
+        stmts.push(MirStmt::Assign {
+            dst: MirPlace::new(third),
+            rhs: MirRvalue::Use(MirOperand::Move(MirPlace::new(MirLocalId::from_usize(1)))),
+            span: span(),
+        });
+        stmts.push(MirStmt::Drop { operand: MirOperand::Move(MirPlace::new(third)), span: span() });
+
+        let symbols = runec_runtime::symbols().map(|symbol| (symbol.name, symbol.address));
+        let mut backend = JitBackend::new(symbols, span()).unwrap();
+
+        let before = runec_runtime::heap_stats();
+        backend.run(&module, &SourceMap::new()).unwrap();
+        let after = runec_runtime::heap_stats();
+
+        assert_eq!(after.allocations, before.allocations + 1);
+        assert_eq!(after.live_allocations(), before.live_allocations());
+    }
+
+    static TAKEN_BRANCH: AtomicU8 = AtomicU8::new(0);
+    unsafe extern "C" fn record_branch(ptr: *const u8, len: usize) {
+        let bytes = unsafe { std::slice::from_raw_parts(ptr, len) };
+        TAKEN_BRANCH.store(bytes[0], Ordering::SeqCst);
+    }
+
+    /// `if false { println("t") } else { println("e") }`
+    fn branching_module() -> MirModule<'static> {
+        let mut module = MirModule::new();
+        let mut main = MirFunction::new(HirId::from_usize(0), "main", MirTy::Unit, span(), span());
+        let result = main.push_local(None, MirTy::Unit, span());
+        let entry = main.push_block(MirBlock::new(MirTerminator::Return(None)));
+        let join = main.push_block(MirBlock::new(MirTerminator::Return(None)));
+
+        let mut arms = Vec::new();
+        for text in ["t", "e"] {
+            let text = module.push_constant(MirConstant::Str(text.into()));
+            let mut arm = MirBlock::new(MirTerminator::Goto(join));
+            arm.stmts.push(MirStmt::Assign {
+                dst: MirPlace::new(result),
+                rhs: MirRvalue::Call {
+                    callee: MirCallee::Runtime(RUNTIME_PRINTLN),
+                    args: Box::new([MirOperand::Constant(text)]),
+                },
+                span: span(),
+            });
+            arms.push(main.push_block(arm));
+        }
+        main.blocks[entry.to_usize()].terminator = MirTerminator::Branch {
+            cond: MirOperand::Immediate(MirImmediate::Bool(false)),
+            then_block: arms[0],
+            else_block: arms[1],
+            span: span(),
+        };
+        main.entry = entry;
+
+        let main = module.push_function(main);
+        module.entry = Some(main);
+        module
+    }
+
+    #[test]
+    fn follows_branch_terminators() {
+        let mut backend =
+            JitBackend::new([("__runeway_println", record_branch as *const u8)], span()).unwrap();
+        backend.run(&branching_module(), &SourceMap::new()).unwrap();
+        assert_eq!(TAKEN_BRANCH.load(Ordering::SeqCst), b'e');
+    }
+
+    static NULL_CHECK: AtomicU8 = AtomicU8::new(0);
+    unsafe extern "C" fn record_null_check(ptr: *const u8, len: usize) {
+        let bytes = unsafe { std::slice::from_raw_parts(ptr, len) };
+        NULL_CHECK.store(bytes[0], Ordering::SeqCst);
+    }
+
+    /// `let a: i32? = null; let b: i32? = 7;` then prints `y` if
+    /// `a == null && b != null` and `n` otherwise.
+    fn null_check_module() -> MirModule<'static> {
+        let mut module = MirModule::new();
+        let mut main = MirFunction::new(HirId::from_usize(0), "main", MirTy::Unit, span(), span());
+        let nullable =
+            MirTy::Nullable(Box::new(MirTy::Int(MirIntTy { signed: true, bits: TypeBits::B32 })));
+        let a = main.push_local(Some("a"), nullable.clone(), span());
+        let b = main.push_local(Some("b"), nullable, span());
+        let a_is_null = main.push_local(None, MirTy::Bool, span());
+        let b_is_set = main.push_local(None, MirTy::Bool, span());
+        let result = main.push_local(None, MirTy::Unit, span());
+
+        let null = MirOperand::Immediate(MirImmediate::Null);
+        let assign = |dst, rhs| MirStmt::Assign { dst: MirPlace::new(dst), rhs, span: span() };
+        let compare = |op, local| MirRvalue::Binary {
+            op,
+            lhs: MirOperand::Copy(MirPlace::new(local)),
+            rhs: MirOperand::Immediate(MirImmediate::Null),
+        };
+        let print = |module: &mut MirModule<'static>, text: &'static str| {
+            let text = module.push_constant(MirConstant::Str(text.into()));
+            let mut block = MirBlock::new(MirTerminator::Return(None));
+            block.stmts.push(assign(
+                result,
+                MirRvalue::Call {
+                    callee: MirCallee::Runtime(RUNTIME_PRINTLN),
+                    args: Box::new([MirOperand::Constant(text)]),
+                },
+            ));
+            block
+        };
+
+        let yes = main.push_block(print(&mut module, "y"));
+        let no = main.push_block(print(&mut module, "n"));
+        let mut check_b = MirBlock::new(MirTerminator::Branch {
+            cond: MirOperand::Copy(MirPlace::new(b_is_set)),
+            then_block: yes,
+            else_block: no,
+            span: span(),
+        });
+        check_b.stmts.push(assign(b_is_set, compare(MirBinOp::Ne, b)));
+        let check_b = main.push_block(check_b);
+
+        let mut entry = MirBlock::new(MirTerminator::Branch {
+            cond: MirOperand::Copy(MirPlace::new(a_is_null)),
+            then_block: check_b,
+            else_block: no,
+            span: span(),
+        });
+        entry.stmts.push(assign(a, MirRvalue::Use(null)));
+        entry.stmts.push(assign(
+            b,
+            MirRvalue::WrapNullable(MirOperand::Immediate(MirImmediate::Int {
+                value: 7,
+                ty: MirIntTy { signed: true, bits: TypeBits::B32 },
+            })),
+        ));
+        entry.stmts.push(assign(a_is_null, compare(MirBinOp::Eq, a)));
+        main.entry = main.push_block(entry);
+
+        let main = module.push_function(main);
+        module.entry = Some(main);
+        module
+    }
+
+    #[test]
+    fn compares_tagged_nullables_with_null() {
+        let mut backend =
+            JitBackend::new([("__runeway_println", record_null_check as *const u8)], span())
+                .unwrap();
+        backend.run(&null_check_module(), &SourceMap::new()).unwrap();
+        assert_eq!(NULL_CHECK.load(Ordering::SeqCst), b'y');
+    }
+
+    const UNCAUGHT_SOURCE: &str = "act main() throws str {\n    fail()?;\n}\n";
+
+    /// `act fail() -> i32 throws str { throw "e"; }` called from the `main`
+    /// of [`UNCAUGHT_SOURCE`], which reports the error it receives as
+    /// uncaught at its `fail()?`.
+    fn uncaught_error_module(source: SourceId) -> MirModule<'static> {
+        let mut module = MirModule::new();
+        let try_span = Span::new(BytePos::from_usize(28), BytePos::from_usize(35), source);
+        let message = module.push_constant(MirConstant::Str("e".into()));
+        let i32_ty = MirTy::Int(MirIntTy { signed: true, bits: TypeBits::B32 });
+        let fallible = MirTy::Fallible { ok: Box::new(i32_ty.clone()), err: Box::new(MirTy::Str) };
+        let assign = |dst, rhs| MirStmt::Assign { dst: MirPlace::new(dst), rhs, span: span() };
+
+        let mut fail =
+            MirFunction::new(HirId::from_usize(0), "fail", fallible.clone(), span(), span());
+        let thrown = fail.push_local(None, fallible.clone(), span());
+        let mut entry =
+            MirBlock::new(MirTerminator::Return(Some(MirOperand::Copy(MirPlace::new(thrown)))));
+        entry.stmts.push(assign(thrown, MirRvalue::WrapErr(MirOperand::Constant(message))));
+        fail.entry = fail.push_block(entry);
+        module.push_function(fail);
+
+        let mut main = MirFunction::new(HirId::from_usize(1), "main", MirTy::Unit, span(), span());
+        let result = main.push_local(None, fallible, span());
+        let is_err = main.push_local(None, MirTy::Bool, span());
+        let error = main.push_local(None, MirTy::Str, span());
+        let value = main.push_local(None, i32_ty, span());
+        let reported = main.push_local(None, MirTy::Unit, span());
+        let result_operand = || MirOperand::Copy(MirPlace::new(result));
+
+        let mut err_block = MirBlock::new(MirTerminator::Return(None));
+        err_block.stmts.push(assign(error, MirRvalue::UnwrapErr(result_operand())));
+        err_block.stmts.push(MirStmt::Assign {
+            dst: MirPlace::new(reported),
+            rhs: MirRvalue::Call {
+                callee: MirCallee::Runtime(RUNTIME_UNCAUGHT_ERROR),
+                args: Box::new([MirOperand::Copy(MirPlace::new(error))]),
+            },
+            span: try_span,
+        });
+        let err_block = main.push_block(err_block);
+        let mut ok_block = MirBlock::new(MirTerminator::Return(None));
+        ok_block.stmts.push(assign(value, MirRvalue::UnwrapOk(result_operand())));
+        let ok_block = main.push_block(ok_block);
+
+        let mut entry = MirBlock::new(MirTerminator::Branch {
+            cond: MirOperand::Copy(MirPlace::new(is_err)),
+            then_block: err_block,
+            else_block: ok_block,
+            span: span(),
+        });
+        entry.stmts.push(assign(
+            result,
+            MirRvalue::Call {
+                callee: MirCallee::Function(HirId::from_usize(0)),
+                args: Box::new([]),
+            },
+        ));
+        entry.stmts.push(assign(is_err, MirRvalue::IsErr(result_operand())));
+        main.entry = main.push_block(entry);
+
+        let main = module.push_function(main);
+        module.entry = Some(main);
+        module
+    }
+
+    #[test]
+    fn returns_errors_thrown_out_of_main_as_errors_at_their_source_location() {
+        let mut source_map = SourceMap::new();
+        let mock = runec_test_utils::MockSourceFileLoader { source: UNCAUGHT_SOURCE };
+        let source = source_map.add_file(mock.load(PathBuf::from("main.rnw")).unwrap());
+        let symbols =
+            runec_runtime::symbols().into_iter().map(|symbol| (symbol.name, symbol.address));
+        let mut backend = JitBackend::new(symbols, span()).unwrap();
+
+        let error = backend
+            .run(&uncaught_error_module(source), &source_map)
+            .expect_err("the uncaught error should be returned from the run");
+
+        assert_eq!(error.message.message, "uncaught error: e");
+        assert_eq!(error.labels[0].span.lo, BytePos::from_usize(28));
+    }
+
+    static PRINTED_AFTER_PANIC: AtomicBool = AtomicBool::new(false);
+    unsafe extern "C" fn record_println_after_panic(_ptr: *const u8, _len: usize) {
+        PRINTED_AFTER_PANIC.store(true, Ordering::SeqCst);
+    }
+
+    const PANIC_SOURCE: &str = "act fail() {\n    panic(\"boom\");\n}\n";
+
+    /// `fail` from [`PANIC_SOURCE`], called from a `main` that prints once
+    /// it returns.
+    fn panic_module(source: SourceId) -> MirModule<'static> {
+        let mut module = MirModule::new();
+        let boom = module.push_constant(MirConstant::Str("boom".into()));
+        let call_span = Span::new(BytePos::from_usize(17), BytePos::from_usize(30), source);
+
+        let mut fail = MirFunction::new(HirId::from_usize(0), "fail", MirTy::Unit, span(), span());
+        fail.entry = fail.push_block(MirBlock::new(MirTerminator::Panic {
+            message: MirOperand::Constant(boom),
+            span: call_span,
+        }));
+        module.push_function(fail);
+
+        let mut main = MirFunction::new(HirId::from_usize(1), "main", MirTy::Unit, span(), span());
+        let result = main.push_local(None, MirTy::Unit, span());
+        let mut entry = MirBlock::new(MirTerminator::Return(None));
+        for callee in
+            [MirCallee::Function(HirId::from_usize(0)), MirCallee::Runtime(RUNTIME_PRINTLN)]
+        {
+            let args = match callee {
+                MirCallee::Runtime(_) => Box::new([MirOperand::Constant(boom)]) as Box<[_]>,
+                _ => Box::new([]),
+            };
+            entry.stmts.push(MirStmt::Assign {
+                dst: MirPlace::new(result),
+                rhs: MirRvalue::Call { callee, args },
+                span: span(),
+            });
+        }
+        main.entry = main.push_block(entry);
+        let main = module.push_function(main);
+        module.entry = Some(main);
+        module
+    }
+
+    #[test]
+    fn returns_panics_as_errors_with_their_source_location() {
+        let mut source_map = SourceMap::new();
+        let mock = runec_test_utils::MockSourceFileLoader { source: PANIC_SOURCE };
+        let source = source_map.add_file(mock.load(PathBuf::from("main.rnw")).unwrap());
+        let symbols = runec_runtime::symbols()
+            .into_iter()
+            .map(|symbol| (symbol.name, symbol.address))
+            .chain([("__runeway_println", record_println_after_panic as *const u8)]);
+        let mut backend = JitBackend::new(symbols, span()).unwrap();
+
+        let error = backend
+            .run(&panic_module(source), &source_map)
+            .expect_err("the panic should be returned from the run");
+
+        assert_eq!(error.message.message, "panicked at main.rnw:2:5: boom");
+        assert_eq!(error.labels[0].span.lo, BytePos::from_usize(17));
+        assert!(!PRINTED_AFTER_PANIC.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn frees_values_held_by_frames_a_panic_unwinds() {
+        let mut module = panic_module(SourceId::from_usize(0));
+        let fail = &mut module.functions[0];
+        let held = fail.push_local(Some("held"), MirTy::Managed, span());
+        let entry = fail.entry.to_usize();
+        fail.blocks[entry].stmts.push(MirStmt::Assign {
+            dst: MirPlace::new(held),
+            rhs: MirRvalue::Call {
+                callee: MirCallee::Runtime(RUNTIME_ALLOC),
+                args: Box::new([usize_operand(16), usize_operand(8)]),
+            },
+            span: span(),
+        });
+        let symbols =
+            runec_runtime::symbols().into_iter().map(|symbol| (symbol.name, symbol.address));
+        let mut backend = JitBackend::new(symbols, span()).unwrap();
+
+        let before = runec_runtime::heap_stats();
+        backend.run(&module, &SourceMap::new()).expect_err("the panic should be returned");
+        let after = runec_runtime::heap_stats();
+
+        assert_eq!(after.allocations, before.allocations + 1);
+        assert_eq!(after.live_allocations(), before.live_allocations());
+        assert_eq!(after.live_bytes, before.live_bytes);
+    }
+
+    const BOUNDS_SOURCE: &str = "act main() {\n    let v = Vec::new();\n    let x = v[3];\n}\n";
+
+    #[test]
+    fn returns_out_of_bounds_indexing_as_a_panic_at_its_source_location() {
+        let mut source_map = SourceMap::new();
+        let mock = runec_test_utils::MockSourceFileLoader { source: BOUNDS_SOURCE };
+        let source = source_map.add_file(mock.load(PathBuf::from("main.rnw")).unwrap());
+        let index_span = Span::new(BytePos::from_usize(49), BytePos::from_usize(53), source);
+
+        let mut module = MirModule::new();
+        let int = MirIntTy { signed: true, bits: TypeBits::B64 };
+        let mut main = MirFunction::new(HirId::from_usize(0), "main", MirTy::Unit, span(), span());
+        let vec = main.push_local(Some("v"), MirTy::Vec(Box::new(MirTy::Int(int))), span());
+        let item = main.push_local(Some("x"), MirTy::Int(int), span());
+        let mut entry = MirBlock::new(MirTerminator::Return(None));
+        entry.stmts.push(MirStmt::Assign {
+            dst: MirPlace::new(vec),
+            rhs: MirRvalue::Intrinsic { intrinsic: Intrinsic::VecNew, args: Box::new([]) },
+            span: span(),
+        });
+        entry.stmts.push(MirStmt::Assign {
+            dst: MirPlace::new(item),
+            rhs: MirRvalue::Index {
+                base: MirOperand::Copy(MirPlace::new(vec)),
+                index: MirOperand::Immediate(MirImmediate::Int { value: 3, ty: int }),
+            },
+            span: index_span,
+        });
+        main.entry = main.push_block(entry);
+        let main = module.push_function(main);
+        module.entry = Some(main);
+        let symbols =
+            runec_runtime::symbols().into_iter().map(|symbol| (symbol.name, symbol.address));
+        let mut backend = JitBackend::new(symbols, span()).unwrap();
+
+        let error = backend
+            .run(&module, &source_map)
+            .expect_err("the failed bounds check should be returned from the run");
+
+        assert_eq!(
+            error.message.message,
+            "panicked at main.rnw:3:13: index out of bounds: the len is 0 but the index is 3"
+        );
+    }
+
+    static INSTANCE_PRINTED: AtomicBool = AtomicBool::new(false);
+    unsafe extern "C" fn record_instance_println(ptr: *const u8, len: usize) {
+        let bytes = unsafe { std::slice::from_raw_parts(ptr, len) };
+        assert_eq!(bytes, b"generic");
+        INSTANCE_PRINTED.store(true, Ordering::SeqCst);
+    }
+
+    /// Two instances of the generic `show`, which share a HIR id but are
+    /// emitted under their own symbols.
+    fn instances_module() -> MirModule<'static> {
+        let mut module = MirModule::new();
+        let text = module.push_constant(MirConstant::Str("generic".into()));
+        let i32_ty = MirIntTy { signed: true, bits: TypeBits::B32 };
+        let show = HirId::from_usize(0);
+        let mut callees = Vec::new();
+        let i32_arg = Ty::Int { signed: true, bits: TypeBits::B32 };
+        for (arg, param_ty) in [(Ty::Str, MirTy::Str), (i32_arg, MirTy::Int(i32_ty))] {
+            let instance = module.push_instance(MirInstance {
+                def: show,
+                args: Box::new([GenericArg::Type(arg)]),
+                arg_tys: Box::new([Some(param_ty.clone())]),
+            });
+            let mut function = MirFunction::new(show, "show", MirTy::Unit, span(), span());
+            function.instance = Some(instance);
+            let param = function.push_local(Some("value"), param_ty.clone(), span());
+            function.params = Box::new([param]);
+            let mut entry = MirBlock::new(MirTerminator::Return(None));
+            if param_ty == MirTy::Str {
+                let result = function.push_local(None, MirTy::Unit, span());
+                entry.stmts.push(MirStmt::Assign {
+                    dst: MirPlace::new(result),
+                    rhs: MirRvalue::Call {
+                        callee: MirCallee::Runtime(RUNTIME_PRINTLN),
+                        args: Box::new([MirOperand::Copy(MirPlace::new(param))]),
+                    },
+                    span: span(),
+                });
+            }
+            function.entry = function.push_block(entry);
+            module.push_function(function);
+            callees.push(MirCallee::Instance(instance));
+        }
+
+        let mut main = MirFunction::new(HirId::from_usize(1), "main", MirTy::Unit, span(), span());
+        let mut entry = MirBlock::new(MirTerminator::Return(None));
+        let args = [
+            MirOperand::Constant(text),
+            MirOperand::Immediate(MirImmediate::Int { value: 7, ty: i32_ty }),
+        ];
+        for (callee, arg) in callees.into_iter().zip(args) {
+            let result = main.push_local(None, MirTy::Unit, span());
+            entry.stmts.push(MirStmt::Assign {
+                dst: MirPlace::new(result),
+                rhs: MirRvalue::Call { callee, args: Box::new([arg]) },
+                span: span(),
+            });
+        }
+        main.entry = main.push_block(entry);
+        let main = module.push_function(main);
+        module.entry = Some(main);
+        module
+    }
+
+    #[test]
+    fn calls_generic_instances_by_symbol() {
+        let module = instances_module();
+        assert_eq!(module.symbol(&module.functions[0]), "show$str");
+        assert_eq!(module.symbol(&module.functions[1]), "show$i32");
+
+        let symbols = runec_runtime::symbols()
+            .into_iter()
+            .map(|symbol| (symbol.name, symbol.address))
+            .chain([("__runeway_println", record_instance_println as *const u8)]);
+        let mut backend = JitBackend::new(symbols, span()).unwrap();
+        backend.run(&module, &SourceMap::new()).unwrap();
+        assert!(INSTANCE_PRINTED.load(Ordering::SeqCst));
+    }
+
+    /// Two functions named `helper`, one in the crate root and one in the
+    /// `util` module.
+    fn same_name_in_two_modules() -> MirModule<'static> {
+        let mut module = MirModule::new();
+        for (id, module_path) in [(0, &[][..]), (1, &["util"][..])] {
+            let mut helper =
+                MirFunction::new(HirId::from_usize(id), "helper", MirTy::Unit, span(), span());
+            helper.module_path = module_path.into();
+            helper.entry = helper.push_block(MirBlock::new(MirTerminator::Return(None)));
+            module.push_function(helper);
+        }
+
+        let mut main = MirFunction::new(HirId::from_usize(2), "main", MirTy::Unit, span(), span());
+        let mut entry = MirBlock::new(MirTerminator::Return(None));
+        for id in 0..2 {
+            let result = main.push_local(None, MirTy::Unit, span());
+            entry.stmts.push(MirStmt::Assign {
+                dst: MirPlace::new(result),
+                rhs: MirRvalue::Call {
+                    callee: MirCallee::Function(HirId::from_usize(id)),
+                    args: Box::new([]),
+                },
+                span: span(),
+            });
+        }
+        main.entry = main.push_block(entry);
+        let main = module.push_function(main);
+        module.entry = Some(main);
+        module
+    }
+
+    #[test]
+    fn emits_same_named_functions_of_different_modules() {
+        let module = same_name_in_two_modules();
+        assert_eq!(module.symbol(&module.functions[1]), "util::helper");
+
+        let mut backend = JitBackend::new([], span()).unwrap();
+        backend.run(&module, &SourceMap::new()).unwrap();
+    }
+
+    static EXTERN_RESULT: AtomicU8 = AtomicU8::new(0);
+    unsafe extern "C" fn record_extern(ptr: *const u8, len: usize) {
+        let bytes = unsafe { std::slice::from_raw_parts(ptr, len) };
+        EXTERN_RESULT.store(bytes[0], Ordering::SeqCst);
+    }
+
+    static ANSWER: AtomicI64 = AtomicI64::new(0);
+    extern "C" fn answer() -> *mut i64 {
+        ANSWER.as_ptr()
+    }
+
+    /// `extern act labs(v: i64) -> i64; extern act answer() -> *mut i64;`
+    /// with `main` storing `labs(-42)` to `*answer()` and printing `y` if
+    /// reading it back gives `42` and `n` otherwise.
+    fn extern_module() -> MirModule<'static> {
+        let mut module = MirModule::new();
+        let i64_ty = MirTy::Int(MirIntTy { signed: true, bits: TypeBits::B64 });
+        for (id, name, params, ret_ty) in [
+            (0, "labs", vec![i64_ty.clone()], i64_ty.clone()),
+            (1, "answer", vec![], MirTy::Pointer),
+        ] {
+            module.push_extern(MirExtern {
+                hir_id: HirId::from_usize(id),
+                name,
+                params: params.into_boxed_slice(),
+                ret_ty,
+                span: span(),
+            });
+        }
+
+        let mut main = MirFunction::new(HirId::from_usize(2), "main", MirTy::Unit, span(), span());
+        let absolute = main.push_local(None, i64_ty.clone(), span());
+        let pointer = main.push_local(None, MirTy::Pointer, span());
+        let loaded = main.push_local(None, i64_ty, span());
+        let equal = main.push_local(None, MirTy::Bool, span());
+        let result = main.push_local(None, MirTy::Unit, span());
+        let assign = |dst, rhs| MirStmt::Assign { dst: MirPlace::new(dst), rhs, span: span() };
+        let copy = |local| MirOperand::Copy(MirPlace::new(local));
+
+        let entry = main.push_block(MirBlock::new(MirTerminator::Return(None)));
+        let join = main.push_block(MirBlock::new(MirTerminator::Return(None)));
+        let mut arms = Vec::new();
+        for text in ["y", "n"] {
+            let text = module.push_constant(MirConstant::Str(text.into()));
+            let mut arm = MirBlock::new(MirTerminator::Goto(join));
+            arm.stmts.push(assign(
+                result,
+                MirRvalue::Call {
+                    callee: MirCallee::Runtime(RUNTIME_PRINTLN),
+                    args: Box::new([MirOperand::Constant(text)]),
+                },
+            ));
+            arms.push(main.push_block(arm));
+        }
+
+        let int = |value: i64| {
+            MirOperand::Immediate(MirImmediate::Int {
+                value: value as u128,
+                ty: MirIntTy { signed: true, bits: TypeBits::B64 },
+            })
+        };
+        let (minus_42, forty_two) = (int(-42), int(42));
+        let block = &mut main.blocks[entry.to_usize()];
+        block.stmts.push(assign(
+            absolute,
+            MirRvalue::Call {
+                callee: MirCallee::Extern(MirExternId::from_usize(0)),
+                args: Box::new([minus_42]),
+            },
+        ));
+        block.stmts.push(assign(
+            pointer,
+            MirRvalue::Call {
+                callee: MirCallee::Extern(MirExternId::from_usize(1)),
+                args: Box::new([]),
+            },
+        ));
+        block.stmts.push(MirStmt::Store {
+            pointer: copy(pointer),
+            value: copy(absolute),
+            span: span(),
+        });
+        block.stmts.push(assign(loaded, MirRvalue::Load(copy(pointer))));
+        block.stmts.push(assign(
+            equal,
+            MirRvalue::Binary { op: MirBinOp::Eq, lhs: copy(loaded), rhs: forty_two },
+        ));
+        block.terminator = MirTerminator::Branch {
+            cond: copy(equal),
+            then_block: arms[0],
+            else_block: arms[1],
+            span: span(),
+        };
+        main.entry = entry;
+
+        let main = module.push_function(main);
+        module.entry = Some(main);
+        module
+    }
+
+    #[test]
+    fn calls_extern_functions_and_stores_and_loads_through_pointers() {
+        let mut backend = JitBackend::new(
+            [("__runeway_println", record_extern as *const u8), ("answer", answer as *const u8)],
+            span(),
+        )
+        .unwrap();
+        backend.run(&extern_module(), &SourceMap::new()).unwrap();
+        assert_eq!(EXTERN_RESULT.load(Ordering::SeqCst), b'y');
+        assert_eq!(ANSWER.load(Ordering::SeqCst), 42);
+    }
+
+    #[test]
+    fn reports_unresolved_extern_functions() {
+        let mut backend =
+            JitBackend::new([("__runeway_println", record_extern as *const u8)], span()).unwrap();
+        assert!(backend.run(&extern_module(), &SourceMap::new()).is_err());
+    }
+
+    static CLOSURE_RESULT: AtomicU8 = AtomicU8::new(0);
+    unsafe extern "C" fn record_closure(ptr: *const u8, len: usize) {
+        let bytes = unsafe { std::slice::from_raw_parts(ptr, len) };
+        CLOSURE_RESULT.store(bytes[0], Ordering::SeqCst);
+    }
+
+    /// `let mut counter = 0; let f = |value| { keep(managed); counter = value; };`
+    /// with `main` calling `f(42)` and printing `y` if `counter == 42` and
+    /// `n` otherwise.
+    fn closure_module() -> MirModule<'static> {
+        let mut module = MirModule::new();
+        let i32_ty = MirIntTy { signed: true, bits: TypeBits::B32 };
+        let counter_ty = MirTy::Cell(Box::new(MirTy::Int(i32_ty)));
+        let assign = |dst, rhs| MirStmt::Assign { dst: MirPlace::new(dst), rhs, span: span() };
+        let copy = |local| MirOperand::Copy(MirPlace::new(local));
+
+        let mut body = MirFunction::new(HirId::from_usize(0), "main", MirTy::Unit, span(), span());
+        body.closure = Some(MirClosureId::from_usize(0));
+        body.env = Some(MirStruct { fields: Box::new([MirTy::Managed, counter_ty.clone()]) });
+        let env = body.push_local(None, MirTy::Closure, span());
+        let value = body.push_local(Some("value"), MirTy::Int(i32_ty), span());
+        body.params = Box::new([env, value]);
+        let managed = body.push_local(Some("managed"), MirTy::Managed, span());
+        let counter = body.push_local(Some("counter"), counter_ty.clone(), span());
+        let mut entry = MirBlock::new(MirTerminator::Return(None));
+        entry.stmts.push(assign(managed, MirRvalue::Capture(0)));
+        entry.stmts.push(assign(counter, MirRvalue::Capture(1)));
+        entry.stmts.push(MirStmt::StoreCell {
+            cell: copy(counter),
+            value: copy(value),
+            span: span(),
+        });
+        body.entry = body.push_block(entry);
+        module.push_function(body);
+
+        let mut main = MirFunction::new(HirId::from_usize(0), "main", MirTy::Unit, span(), span());
+        let managed = main.push_local(Some("managed"), MirTy::Managed, span());
+        let counter = main.push_local(Some("counter"), counter_ty, span());
+        let closure = main.push_local(Some("f"), MirTy::Closure, span());
+        let called = main.push_local(None, MirTy::Unit, span());
+        let loaded = main.push_local(None, MirTy::Int(i32_ty), span());
+        let equal = main.push_local(None, MirTy::Bool, span());
+        let result = main.push_local(None, MirTy::Unit, span());
+        let int = |value| MirOperand::Immediate(MirImmediate::Int { value, ty: i32_ty });
+
+        let entry = main.push_block(MirBlock::new(MirTerminator::Return(None)));
+        let join = main.push_block(MirBlock::new(MirTerminator::Return(None)));
+        let mut arms = Vec::new();
+        for text in ["y", "n"] {
+            let text = module.push_constant(MirConstant::Str(text.into()));
+            let mut arm = MirBlock::new(MirTerminator::Goto(join));
+            arm.stmts.push(assign(
+                result,
+                MirRvalue::Call {
+                    callee: MirCallee::Runtime(RUNTIME_PRINTLN),
+                    args: Box::new([MirOperand::Constant(text)]),
+                },
+            ));
+            arms.push(main.push_block(arm));
+        }
+
+        let block = &mut main.blocks[entry.to_usize()];
+        block.stmts.push(assign(
+            managed,
+            MirRvalue::Call {
+                callee: MirCallee::Runtime(RUNTIME_ALLOC),
+                args: Box::new([usize_operand(16), usize_operand(8)]),
+            },
+        ));
+        block.stmts.push(assign(counter, MirRvalue::MakeCell(int(0))));
+        block.stmts.push(assign(
+            closure,
+            MirRvalue::MakeClosure {
+                function: MirCallee::Closure(MirClosureId::from_usize(0)),
+                captures: Box::new([MirOperand::Move(MirPlace::new(managed)), copy(counter)]),
+            },
+        ));
+        block.stmts.push(assign(
+            called,
+            MirRvalue::CallIndirect { callee: copy(closure), args: Box::new([int(42)]) },
+        ));
+        block.stmts.push(assign(loaded, MirRvalue::LoadCell(copy(counter))));
+        block.stmts.push(assign(
+            equal,
+            MirRvalue::Binary { op: MirBinOp::Eq, lhs: copy(loaded), rhs: int(42) },
+        ));
+        block.terminator = MirTerminator::Branch {
+            cond: copy(equal),
+            then_block: arms[0],
+            else_block: arms[1],
+            span: span(),
+        };
+        main.entry = entry;
+
+        let main = module.push_function(main);
+        module.entry = Some(main);
+        module
+    }
+
+    #[test]
+    fn calls_closures_through_their_environment_without_leaks() {
+        let module = closure_module();
+        assert_eq!(module.symbol(&module.functions[0]), "main$closure0");
+
+        let symbols = runec_runtime::symbols()
+            .into_iter()
+            .map(|symbol| (symbol.name, symbol.address))
+            .filter(|(name, _)| *name != "__runeway_println")
+            .chain([("__runeway_println", record_closure as *const u8)]);
+        let mut backend = JitBackend::new(symbols, span()).unwrap();
+
+        let before = runec_runtime::heap_stats();
+        backend.run(&module, &SourceMap::new()).unwrap();
+        let after = runec_runtime::heap_stats();
+
+        assert_eq!(CLOSURE_RESULT.load(Ordering::SeqCst), b'y');
+        // The managed value, the cell, and the environment.
+        assert_eq!(after.allocations, before.allocations + 3);
+        assert_eq!(after.live_allocations(), before.live_allocations());
+        assert_eq!(after.live_bytes, before.live_bytes);
+    }
+
+    /// `let v = Vec::new(); let f = || keep(v); v.push(f);`, leaving the
+    /// vector and the closure environment referenced only by each other.
+    fn closure_cycle_module() -> MirModule<'static> {
+        let mut module = MirModule::new();
+        let vec_ty = MirTy::Vec(Box::new(MirTy::Closure));
+        let assign = |dst, rhs| MirStmt::Assign { dst: MirPlace::new(dst), rhs, span: span() };
+        let copy = |local| MirOperand::Copy(MirPlace::new(local));
+
+        let mut body = MirFunction::new(HirId::from_usize(0), "main", MirTy::Unit, span(), span());
+        body.closure = Some(MirClosureId::from_usize(0));
+        body.env = Some(MirStruct { fields: Box::new([vec_ty.clone()]) });
+        let env = body.push_local(None, MirTy::Closure, span());
+        body.params = Box::new([env]);
+        let captured = body.push_local(Some("v"), vec_ty.clone(), span());
+        let mut entry = MirBlock::new(MirTerminator::Return(None));
+        entry.stmts.push(assign(captured, MirRvalue::Capture(0)));
+        body.entry = body.push_block(entry);
+        module.push_function(body);
+
+        let mut main = MirFunction::new(HirId::from_usize(0), "main", MirTy::Unit, span(), span());
+        let vec = main.push_local(Some("v"), vec_ty, span());
+        let closure = main.push_local(Some("f"), MirTy::Closure, span());
+        let result = main.push_local(None, MirTy::Unit, span());
+        let mut entry = MirBlock::new(MirTerminator::Return(None));
+        entry.stmts.push(assign(
+            vec,
+            MirRvalue::Intrinsic { intrinsic: Intrinsic::VecNew, args: Box::new([]) },
+        ));
+        entry.stmts.push(assign(
+            closure,
+            MirRvalue::MakeClosure {
+                function: MirCallee::Closure(MirClosureId::from_usize(0)),
+                captures: Box::new([copy(vec)]),
+            },
+        ));
+        entry.stmts.push(assign(
+            result,
+            MirRvalue::Intrinsic {
+                intrinsic: Intrinsic::VecPush,
+                args: Box::new([copy(vec), copy(closure)]),
+            },
+        ));
+        main.entry = main.push_block(entry);
+
+        let main = module.push_function(main);
+        module.entry = Some(main);
+        module
+    }
+
+    #[test]
+    fn collects_closures_stored_in_the_vectors_they_capture() {
+        let symbols =
+            runec_runtime::symbols().into_iter().map(|symbol| (symbol.name, symbol.address));
+        let mut backend = JitBackend::new(symbols, span()).unwrap();
+
+        let before = runec_runtime::heap_stats();
+        backend.run(&closure_cycle_module(), &SourceMap::new()).unwrap();
+        let after = runec_runtime::heap_stats();
+
+        // The vector and the environment.
+        assert_eq!(after.allocations, before.allocations + 2);
+        assert_eq!(after.live_allocations(), before.live_allocations());
+        assert_eq!(after.live_bytes, before.live_bytes);
+    }
+
+    static ITERATIONS: AtomicUsize = AtomicUsize::new(0);
+    unsafe extern "C" fn count_iteration(_ptr: *const u8, _len: usize) {
+        ITERATIONS.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// `let r = -2..=1; for i in r { println("x") }`, stepping the range with
+    /// `next()`.
+    fn range_loop_module() -> MirModule<'static> {
+        let mut module = MirModule::new();
+        let text = module.push_constant(MirConstant::Str("x".into()));
+        let i32_ty = MirIntTy { signed: true, bits: TypeBits::B32 };
+        let int = |value: i128| {
+            MirOperand::Immediate(MirImmediate::Int { value: value as u128, ty: i32_ty })
+        };
+        let copy = |local| MirOperand::Copy(MirPlace::new(local));
+        let assign = |dst, rhs| MirStmt::Assign { dst: MirPlace::new(dst), rhs, span: span() };
+
+        let mut main = MirFunction::new(HirId::from_usize(0), "main", MirTy::Unit, span(), span());
+        let range = main.push_local(Some("r"), MirTy::Range(i32_ty), span());
+        let next = main.push_local(None, MirTy::Nullable(Box::new(MirTy::Int(i32_ty))), span());
+        let done = main.push_local(None, MirTy::Bool, span());
+        let item = main.push_local(Some("i"), MirTy::Int(i32_ty), span());
+        let result = main.push_local(None, MirTy::Unit, span());
+
+        let exit = main.push_block(MirBlock::new(MirTerminator::Return(None)));
+        let head = main.push_block(MirBlock::new(MirTerminator::Return(None)));
+        let mut body = MirBlock::new(MirTerminator::Goto(head));
+        body.stmts.push(assign(item, MirRvalue::UnwrapNullable(copy(next))));
+        body.stmts.push(assign(
+            result,
+            MirRvalue::Call {
+                callee: MirCallee::Runtime(RUNTIME_PRINTLN),
+                args: Box::new([MirOperand::Constant(text)]),
+            },
+        ));
+        let body = main.push_block(body);
+
+        let block = &mut main.blocks[head.to_usize()];
+        block.stmts.push(MirStmt::Next {
+            iter: copy(range),
+            dst: MirPlace::new(next),
+            span: span(),
+        });
+        block.stmts.push(assign(
+            done,
+            MirRvalue::Binary {
+                op: MirBinOp::Eq,
+                lhs: copy(next),
+                rhs: MirOperand::Immediate(MirImmediate::Null),
+            },
+        ));
+        block.terminator = MirTerminator::Branch {
+            cond: copy(done),
+            then_block: exit,
+            else_block: body,
+            span: span(),
+        };
+
+        let mut entry = MirBlock::new(MirTerminator::Goto(head));
+        entry.stmts.push(assign(
+            range,
+            MirRvalue::MakeRange { start: int(-2), end: int(1), inclusive: true },
+        ));
+        main.entry = main.push_block(entry);
+
+        let main = module.push_function(main);
+        module.entry = Some(main);
+        module
+    }
+
+    #[test]
+    fn steps_ranges_through_their_inclusive_end() {
+        ITERATIONS.store(0, Ordering::SeqCst);
+        let mut backend =
+            JitBackend::new([("__runeway_println", count_iteration as *const u8)], span()).unwrap();
+        backend.run(&range_loop_module(), &SourceMap::new()).unwrap();
+        assert_eq!(ITERATIONS.load(Ordering::SeqCst), 4);
+    }
+
+    static RECORDED: Mutex<Vec<i64>> = Mutex::new(Vec::new());
+    extern "C" fn record(value: i64) {
+        RECORDED.lock().unwrap().push(value);
+    }
+
+    /// Builds `[10, 20, 30]`, sets `v[1] = 25`, and records `v[1]`, the
+    /// length and last element of `v[1..=2]`, the popped element and the
+    /// length left. The slice then shrank to one element; it grows back as
+    /// `v` grows past its capacity, and still reads the last element of
+    /// `v[1..=2]` and its length once `v` is replaced.
+    fn vec_module() -> MirModule<'static> {
+        let mut module = MirModule::new();
+        let i64_int = MirIntTy { signed: true, bits: TypeBits::B64 };
+        let u64_int = MirIntTy { signed: false, bits: TypeBits::B64 };
+        for (id, name, param) in
+            [(0, "record", MirTy::Int(i64_int)), (1, "record_len", MirTy::Int(u64_int))]
+        {
+            module.push_extern(MirExtern {
+                hir_id: HirId::from_usize(id),
+                name,
+                params: Box::new([param]),
+                ret_ty: MirTy::Unit,
+                span: span(),
+            });
+        }
+        let int = |value| MirOperand::Immediate(MirImmediate::Int { value, ty: i64_int });
+        let copy = |local| MirOperand::Copy(MirPlace::new(local));
+        let assign = |dst, rhs| MirStmt::Assign { dst: MirPlace::new(dst), rhs, span: span() };
+        let record = |extern_id, local| MirRvalue::Call {
+            callee: MirCallee::Extern(MirExternId::from_usize(extern_id)),
+            args: Box::new([copy(local)]),
+        };
+
+        let mut main = MirFunction::new(HirId::from_usize(2), "main", MirTy::Unit, span(), span());
+        let elem = MirTy::Int(i64_int);
+        let vec = main.push_local(Some("v"), MirTy::Vec(Box::new(elem.clone())), span());
+        let result = main.push_local(None, MirTy::Unit, span());
+        let item = main.push_local(None, elem.clone(), span());
+        let range = main.push_local(None, MirTy::Range(i64_int), span());
+        let slice = main.push_local(None, MirTy::Slice(Box::new(elem.clone())), span());
+        let len = main.push_local(None, MirTy::Int(u64_int), span());
+        let popped = main.push_local(None, MirTy::Nullable(Box::new(elem)), span());
+
+        let mut entry = MirBlock::new(MirTerminator::Return(None));
+        let stmts = &mut entry.stmts;
+        stmts.push(assign(
+            vec,
+            MirRvalue::Intrinsic { intrinsic: Intrinsic::VecNew, args: Box::new([]) },
+        ));
+        for value in [10, 20, 30] {
+            stmts.push(assign(
+                result,
+                MirRvalue::Intrinsic {
+                    intrinsic: Intrinsic::VecPush,
+                    args: Box::new([copy(vec), int(value)]),
+                },
+            ));
+        }
+        stmts.push(MirStmt::StoreIndex {
+            base: copy(vec),
+            index: int(1),
+            value: int(25),
+            span: span(),
+        });
+        stmts.push(assign(item, MirRvalue::Index { base: copy(vec), index: int(1) }));
+        stmts.push(assign(result, record(0, item)));
+        stmts.push(assign(
+            range,
+            MirRvalue::MakeRange { start: int(1), end: int(2), inclusive: true },
+        ));
+        stmts.push(assign(slice, MirRvalue::Slice { base: copy(vec), range: copy(range) }));
+        stmts.push(assign(
+            len,
+            MirRvalue::Intrinsic { intrinsic: Intrinsic::Len, args: Box::new([copy(slice)]) },
+        ));
+        stmts.push(assign(result, record(1, len)));
+        stmts.push(assign(item, MirRvalue::Index { base: copy(slice), index: int(1) }));
+        stmts.push(assign(result, record(0, item)));
+        stmts.push(assign(
+            popped,
+            MirRvalue::Intrinsic { intrinsic: Intrinsic::VecPop, args: Box::new([copy(vec)]) },
+        ));
+        stmts.push(assign(item, MirRvalue::UnwrapNullable(copy(popped))));
+        stmts.push(assign(result, record(0, item)));
+        stmts.push(assign(
+            len,
+            MirRvalue::Intrinsic { intrinsic: Intrinsic::Len, args: Box::new([copy(vec)]) },
+        ));
+        stmts.push(assign(result, record(1, len)));
+        let slice_len =
+            MirRvalue::Intrinsic { intrinsic: Intrinsic::Len, args: Box::new([copy(slice)]) };
+        stmts.push(assign(len, slice_len.clone()));
+        stmts.push(assign(result, record(1, len)));
+        for value in 40..80 {
+            stmts.push(assign(
+                result,
+                MirRvalue::Intrinsic {
+                    intrinsic: Intrinsic::VecPush,
+                    args: Box::new([copy(vec), int(value)]),
+                },
+            ));
+        }
+        stmts.push(assign(
+            vec,
+            MirRvalue::Intrinsic { intrinsic: Intrinsic::VecNew, args: Box::new([]) },
+        ));
+        stmts.push(assign(item, MirRvalue::Index { base: copy(slice), index: int(1) }));
+        stmts.push(assign(result, record(0, item)));
+        stmts.push(assign(len, slice_len));
+        stmts.push(assign(result, record(1, len)));
+        main.entry = main.push_block(entry);
+
+        let main = module.push_function(main);
+        module.entry = Some(main);
+        module
+    }
+
+    #[test]
+    fn pushes_indexes_and_slices_vectors_without_leaks() {
+        let symbols = runec_runtime::symbols()
+            .into_iter()
+            .map(|symbol| (symbol.name, symbol.address))
+            .chain([("record", record as *const u8), ("record_len", record as *const u8)]);
+        let mut backend = JitBackend::new(symbols, span()).unwrap();
+
+        let before = runec_runtime::heap_stats();
+        backend.run(&vec_module(), &SourceMap::new()).unwrap();
+        let after = runec_runtime::heap_stats();
+
+        assert_eq!(*RECORDED.lock().unwrap(), [25, 2, 30, 30, 2, 1, 40, 2]);
+        assert_eq!(after.allocations, before.allocations + 2);
+        assert_eq!(after.live_allocations(), before.live_allocations());
+    }
+
+    static LOOKUPS: Mutex<Vec<i64>> = Mutex::new(Vec::new());
+    extern "C" fn record_lookup(value: i64) {
+        LOOKUPS.lock().unwrap().push(value);
+    }
+
+    /// Inserts `1 => 10` and `2 => 20`, replaces the value of `1` with `15`,
+    /// and records the replaced value, `m.get(2)`, the removed value of `1`,
+    /// the length left and the only key.
+    fn map_module() -> MirModule<'static> {
+        let mut module = MirModule::new();
+        let i64_int = MirIntTy { signed: true, bits: TypeBits::B64 };
+        for (id, name, param) in
+            [(0, "record", MirTy::Int(i64_int)), (1, "record_len", MirTy::Int(MirIntTy::USIZE))]
+        {
+            module.push_extern(MirExtern {
+                hir_id: HirId::from_usize(id),
+                name,
+                params: Box::new([param]),
+                ret_ty: MirTy::Unit,
+                span: span(),
+            });
+        }
+        let int = |value| MirOperand::Immediate(MirImmediate::Int { value, ty: i64_int });
+        let copy = |local| MirOperand::Copy(MirPlace::new(local));
+        let assign = |dst, rhs| MirStmt::Assign { dst: MirPlace::new(dst), rhs, span: span() };
+        let intrinsic = |intrinsic, args: Vec<MirOperand>| MirRvalue::Intrinsic {
+            intrinsic,
+            args: args.into_boxed_slice(),
+        };
+        let record = |extern_id, local| MirRvalue::Call {
+            callee: MirCallee::Extern(MirExternId::from_usize(extern_id)),
+            args: Box::new([copy(local)]),
+        };
+
+        let mut main = MirFunction::new(HirId::from_usize(2), "main", MirTy::Unit, span(), span());
+        let elem = MirTy::Int(i64_int);
+        let map_ty = MirTy::Map { key: Box::new(elem.clone()), value: Box::new(elem.clone()) };
+        let map = main.push_local(Some("m"), map_ty, span());
+        let result = main.push_local(None, MirTy::Unit, span());
+        let found = main.push_local(None, MirTy::Nullable(Box::new(elem.clone())), span());
+        let item = main.push_local(None, elem.clone(), span());
+        let len = main.push_local(None, MirTy::Int(MirIntTy::USIZE), span());
+        let keys = main.push_local(None, MirTy::Vec(Box::new(elem)), span());
+
+        let mut entry = MirBlock::new(MirTerminator::Return(None));
+        let stmts = &mut entry.stmts;
+        stmts.push(assign(map, intrinsic(Intrinsic::MapNew, vec![])));
+        for (key, value) in [(1, 10), (2, 20), (1, 15)] {
+            stmts.push(assign(
+                found,
+                intrinsic(Intrinsic::MapInsert, vec![copy(map), int(key), int(value)]),
+            ));
+        }
+        stmts.push(assign(item, MirRvalue::UnwrapNullable(copy(found))));
+        stmts.push(assign(result, record(0, item)));
+        stmts.push(assign(found, intrinsic(Intrinsic::MapGet, vec![copy(map), int(2)])));
+        stmts.push(assign(item, MirRvalue::UnwrapNullable(copy(found))));
+        stmts.push(assign(result, record(0, item)));
+        stmts.push(assign(found, intrinsic(Intrinsic::MapRemove, vec![copy(map), int(1)])));
+        stmts.push(assign(item, MirRvalue::UnwrapNullable(copy(found))));
+        stmts.push(assign(result, record(0, item)));
+        stmts.push(assign(len, intrinsic(Intrinsic::Len, vec![copy(map)])));
+        stmts.push(assign(result, record(1, len)));
+        stmts.push(assign(keys, intrinsic(Intrinsic::MapKeys, vec![copy(map)])));
+        stmts.push(assign(item, MirRvalue::Index { base: copy(keys), index: int(0) }));
+        stmts.push(assign(result, record(0, item)));
+        main.entry = main.push_block(entry);
+
+        let main = module.push_function(main);
+        module.entry = Some(main);
+        module
+    }
+
+    #[test]
+    fn inserts_looks_up_and_removes_map_entries_without_leaks() {
+        let symbols = runec_runtime::symbols()
+            .into_iter()
+            .map(|symbol| (symbol.name, symbol.address))
+            .chain([
+                ("record", record_lookup as *const u8),
+                ("record_len", record_lookup as *const u8),
+            ]);
+        let mut backend = JitBackend::new(symbols, span()).unwrap();
+
+        let before = runec_runtime::heap_stats();
+        backend.run(&map_module(), &SourceMap::new()).unwrap();
+        let after = runec_runtime::heap_stats();
+
+        assert_eq!(*LOOKUPS.lock().unwrap(), [10, 20, 15, 1, 2]);
+        // The map and the vector of its keys.
+        assert_eq!(after.allocations, before.allocations + 2);
+        assert_eq!(after.live_allocations(), before.live_allocations());
+    }
+
+    static LIVE_AFTER_INSERTS: Mutex<Vec<usize>> = Mutex::new(Vec::new());
+    extern "C" fn record_live_allocations() {
+        LIVE_AFTER_INSERTS.lock().unwrap().push(runec_runtime::heap_stats().live_allocations());
+    }
+
+    /// Inserts two equal strings built at runtime into a map, each held only
+    /// by its own local, the way `m.insert(k(), ...)` does with temporaries.
+    /// The map keeps the first and releases the second, which its local
+    /// still holds until the function returns.
+    fn temporary_key_module() -> MirModule<'static> {
+        let mut module = MirModule::new();
+        module.push_extern(MirExtern {
+            hir_id: HirId::from_usize(0),
+            name: "record_live_allocations",
+            params: Box::new([]),
+            ret_ty: MirTy::Unit,
+            span: span(),
+        });
+        let i64_int = MirIntTy { signed: true, bits: TypeBits::B64 };
+        let int = |value| MirOperand::Immediate(MirImmediate::Int { value, ty: i64_int });
+        let copy = |local| MirOperand::Copy(MirPlace::new(local));
+        let assign = |dst, rhs| MirStmt::Assign { dst: MirPlace::new(dst), rhs, span: span() };
+        let intrinsic = |intrinsic, args: Vec<MirOperand>| MirRvalue::Intrinsic {
+            intrinsic,
+            args: args.into_boxed_slice(),
+        };
+
+        let mut main = MirFunction::new(HirId::from_usize(1), "main", MirTy::Unit, span(), span());
+        let elem = MirTy::Int(i64_int);
+        let map_ty = MirTy::Map { key: Box::new(MirTy::Str), value: Box::new(elem.clone()) };
+        let map = main.push_local(Some("m"), map_ty, span());
+        let keys: Vec<_> = (0..2).map(|_| main.push_local(None, MirTy::Str, span())).collect();
+        let result = main.push_local(None, MirTy::Unit, span());
+        let found = main.push_local(None, MirTy::Nullable(Box::new(elem)), span());
+
+        let mut entry = MirBlock::new(MirTerminator::Return(None));
+        let stmts = &mut entry.stmts;
+        stmts.push(assign(map, intrinsic(Intrinsic::MapNew, vec![])));
+        for (key, value) in keys.iter().zip([10, 20]) {
+            stmts.push(assign(*key, intrinsic(Intrinsic::IntToString, vec![int(7)])));
+            stmts.push(assign(
+                found,
+                intrinsic(Intrinsic::MapInsert, vec![copy(map), copy(*key), int(value)]),
+            ));
+        }
+        stmts.push(assign(
+            result,
+            MirRvalue::Call {
+                callee: MirCallee::Extern(MirExternId::from_usize(0)),
+                args: Box::new([]),
+            },
+        ));
+        main.entry = main.push_block(entry);
+
+        let main = module.push_function(main);
+        module.entry = Some(main);
+        module
+    }
+
+    #[test]
+    fn inserting_a_temporary_key_already_in_the_map_keeps_it_alive_until_released() {
+        let symbols = runec_runtime::symbols()
+            .into_iter()
+            .map(|symbol| (symbol.name, symbol.address))
+            .chain([("record_live_allocations", record_live_allocations as *const u8)]);
+        let mut backend = JitBackend::new(symbols, span()).unwrap();
+
+        let before = runec_runtime::heap_stats();
+        backend.run(&temporary_key_module(), &SourceMap::new()).unwrap();
+        let after = runec_runtime::heap_stats();
+
+        // The map and both strings are live until the function returns,
+        // and each is freed exactly once.
+        let live = before.live_allocations() + 3;
+        assert_eq!(*LIVE_AFTER_INSERTS.lock().unwrap(), [live]);
+        assert_eq!(after.allocations, before.allocations + 3);
+        assert_eq!(after.frees, before.frees + 3);
+    }
+
+    static RECORD_LOOKUPS: Mutex<Vec<i64>> = Mutex::new(Vec::new());
+    extern "C" fn record_record_lookup(value: i64) {
+        RECORD_LOOKUPS.lock().unwrap().push(value);
+    }
+    static RECORD_EQUALITIES: Mutex<Vec<bool>> = Mutex::new(Vec::new());
+    extern "C" fn record_equality(value: bool) {
+        RECORD_EQUALITIES.lock().unwrap().push(value);
+    }
+
+    /// Keys a map by records of an `i64` and a string, built separately so
+    /// that equal keys are distinct allocations, and compares them with `==`.
+    fn record_map_module() -> MirModule<'static> {
+        let mut module = MirModule::new();
+        let i64_int = MirIntTy { signed: true, bits: TypeBits::B64 };
+        for (id, name, param) in [
+            (0, "record_record_lookup", MirTy::Int(i64_int)),
+            (1, "record_equality", MirTy::Bool),
+            (2, "record_record_len", MirTy::Int(MirIntTy::USIZE)),
+        ] {
+            module.push_extern(MirExtern {
+                hir_id: HirId::from_usize(id),
+                name,
+                params: Box::new([param]),
+                ret_ty: MirTy::Unit,
+                span: span(),
+            });
+        }
+        let int = |value| MirOperand::Immediate(MirImmediate::Int { value, ty: i64_int });
+        let copy = |local| MirOperand::Copy(MirPlace::new(local));
+        let assign = |dst, rhs| MirStmt::Assign { dst: MirPlace::new(dst), rhs, span: span() };
+        let intrinsic = |intrinsic, args: Vec<MirOperand>| MirRvalue::Intrinsic {
+            intrinsic,
+            args: args.into_boxed_slice(),
+        };
+        let record = |extern_id, local| MirRvalue::Call {
+            callee: MirCallee::Extern(MirExternId::from_usize(extern_id)),
+            args: Box::new([copy(local)]),
+        };
+        let [a, b] = ["a", "b"]
+            .map(|text| MirOperand::Constant(module.push_constant(MirConstant::Str(text.into()))));
+
+        let mut main = MirFunction::new(HirId::from_usize(3), "main", MirTy::Unit, span(), span());
+        let elem = MirTy::Int(i64_int);
+        let key_ty = MirTy::Record {
+            def: Some(HirId::from_usize(4)),
+            fields: Box::new([elem.clone(), MirTy::Str]),
+        };
+        let map_ty = MirTy::Map { key: Box::new(key_ty.clone()), value: Box::new(elem.clone()) };
+        let map = main.push_local(Some("m"), map_ty, span());
+        let keys: Vec<_> = (0..3).map(|_| main.push_local(None, key_ty.clone(), span())).collect();
+        let result = main.push_local(None, MirTy::Unit, span());
+        let found = main.push_local(None, MirTy::Nullable(Box::new(elem.clone())), span());
+        let item = main.push_local(None, elem, span());
+        let equal = main.push_local(None, MirTy::Bool, span());
+        let len = main.push_local(None, MirTy::Int(MirIntTy::USIZE), span());
+
+        let mut entry = MirBlock::new(MirTerminator::Return(None));
+        let stmts = &mut entry.stmts;
+        for (key, text) in keys.iter().zip([a.clone(), a, b]) {
+            let fields = Box::new([Some(int(1)), Some(text)]);
+            stmts.push(assign(*key, MirRvalue::MakeRecord(fields)));
+        }
+        stmts.push(assign(map, intrinsic(Intrinsic::MapNew, vec![])));
+        for (key, value) in [(keys[0], 10), (keys[2], 30), (keys[1], 15)] {
+            stmts.push(assign(
+                found,
+                intrinsic(Intrinsic::MapInsert, vec![copy(map), copy(key), int(value)]),
+            ));
+        }
+        stmts.push(assign(item, MirRvalue::UnwrapNullable(copy(found))));
+        stmts.push(assign(result, record(0, item)));
+        stmts.push(assign(found, intrinsic(Intrinsic::MapGet, vec![copy(map), copy(keys[2])])));
+        stmts.push(assign(item, MirRvalue::UnwrapNullable(copy(found))));
+        stmts.push(assign(result, record(0, item)));
+        stmts.push(assign(item, MirRvalue::LoadField { record: copy(keys[2]), index: 0 }));
+        stmts.push(assign(result, record(0, item)));
+        stmts.push(assign(len, intrinsic(Intrinsic::Len, vec![copy(map)])));
+        stmts.push(assign(result, record(2, len)));
+        for (op, rhs) in [(MirBinOp::Eq, keys[1]), (MirBinOp::Eq, keys[2]), (MirBinOp::Ne, keys[2])]
+        {
+            let rhs = MirRvalue::Binary { op, lhs: copy(keys[0]), rhs: copy(rhs) };
+            stmts.push(assign(equal, rhs));
+            stmts.push(assign(result, record(1, equal)));
+        }
+        main.entry = main.push_block(entry);
+
+        let main = module.push_function(main);
+        module.entry = Some(main);
+        module
+    }
+
+    #[test]
+    fn keys_maps_by_records_compared_field_by_field_without_leaks() {
+        let symbols = runec_runtime::symbols()
+            .into_iter()
+            .map(|symbol| (symbol.name, symbol.address))
+            .chain([
+                ("record_record_lookup", record_record_lookup as *const u8),
+                ("record_equality", record_equality as *const u8),
+                ("record_record_len", record_record_lookup as *const u8),
+            ]);
+        let mut backend = JitBackend::new(symbols, span()).unwrap();
+
+        let before = runec_runtime::heap_stats();
+        backend.run(&record_map_module(), &SourceMap::new()).unwrap();
+        let after = runec_runtime::heap_stats();
+
+        // Inserting the second record equal to the first replaces its value.
+        assert_eq!(*RECORD_LOOKUPS.lock().unwrap(), [10, 30, 1, 2]);
+        assert_eq!(*RECORD_EQUALITIES.lock().unwrap(), [true, false, true]);
+        assert_eq!(after.live_allocations(), before.live_allocations());
+    }
+
+    static LIVE_AFTER_RECORD_INSERTS: Mutex<Vec<usize>> = Mutex::new(Vec::new());
+    extern "C" fn record_live_record_allocations() {
+        let live = runec_runtime::heap_stats().live_allocations();
+        LIVE_AFTER_RECORD_INSERTS.lock().unwrap().push(live);
+    }
+
+    /// Inserts two equal records, each held only by its own local, into a
+    /// map and a set, the way `m.insert(P { ... }, ...)` does.
+    fn temporary_record_key_module() -> MirModule<'static> {
+        let mut module = MirModule::new();
+        module.push_extern(MirExtern {
+            hir_id: HirId::from_usize(0),
+            name: "record_live_record_allocations",
+            params: Box::new([]),
+            ret_ty: MirTy::Unit,
+            span: span(),
+        });
+        let i64_int = MirIntTy { signed: true, bits: TypeBits::B64 };
+        let int = |value| MirOperand::Immediate(MirImmediate::Int { value, ty: i64_int });
+        let copy = |local| MirOperand::Copy(MirPlace::new(local));
+        let assign = |dst, rhs| MirStmt::Assign { dst: MirPlace::new(dst), rhs, span: span() };
+        let intrinsic = |intrinsic, args: Vec<MirOperand>| MirRvalue::Intrinsic {
+            intrinsic,
+            args: args.into_boxed_slice(),
+        };
+
+        let mut main = MirFunction::new(HirId::from_usize(1), "main", MirTy::Unit, span(), span());
+        let elem = MirTy::Int(i64_int);
+        let key_ty =
+            MirTy::Record { def: Some(HirId::from_usize(2)), fields: Box::new([elem.clone()]) };
+        let map_ty = MirTy::Map { key: Box::new(key_ty.clone()), value: Box::new(elem.clone()) };
+        let set_ty = MirTy::Map { key: Box::new(key_ty.clone()), value: Box::new(MirTy::Unit) };
+        let map = main.push_local(Some("m"), map_ty, span());
+        let set = main.push_local(Some("s"), set_ty, span());
+        let keys: Vec<_> = (0..2).map(|_| main.push_local(None, key_ty.clone(), span())).collect();
+        let result = main.push_local(None, MirTy::Unit, span());
+        let found = main.push_local(None, MirTy::Nullable(Box::new(elem)), span());
+        let inserted = main.push_local(None, MirTy::Bool, span());
+
+        let mut entry = MirBlock::new(MirTerminator::Return(None));
+        let stmts = &mut entry.stmts;
+        stmts.push(assign(map, intrinsic(Intrinsic::MapNew, vec![])));
+        stmts.push(assign(set, intrinsic(Intrinsic::MapNew, vec![])));
+        for (key, value) in keys.iter().zip([10, 20]) {
+            stmts.push(assign(*key, MirRvalue::MakeRecord(Box::new([Some(int(1))]))));
+            stmts.push(assign(
+                found,
+                intrinsic(Intrinsic::MapInsert, vec![copy(map), copy(*key), int(value)]),
+            ));
+            stmts.push(assign(
+                inserted,
+                intrinsic(Intrinsic::SetInsert, vec![copy(set), copy(*key)]),
+            ));
+        }
+        stmts.push(assign(
+            result,
+            MirRvalue::Call {
+                callee: MirCallee::Extern(MirExternId::from_usize(0)),
+                args: Box::new([]),
+            },
+        ));
+        main.entry = main.push_block(entry);
+
+        let main = module.push_function(main);
+        module.entry = Some(main);
+        module
+    }
+
+    #[test]
+    fn inserting_a_temporary_record_key_already_held_keeps_it_alive_until_released() {
+        let symbols =
+            runec_runtime::symbols().into_iter().map(|symbol| (symbol.name, symbol.address)).chain(
+                [("record_live_record_allocations", record_live_record_allocations as *const u8)],
+            );
+        let mut backend = JitBackend::new(symbols, span()).unwrap();
+
+        let before = runec_runtime::heap_stats();
+        backend.run(&temporary_record_key_module(), &SourceMap::new()).unwrap();
+        let after = runec_runtime::heap_stats();
+
+        // The map, the set and both records.
+        let live = before.live_allocations() + 4;
+        assert_eq!(*LIVE_AFTER_RECORD_INSERTS.lock().unwrap(), [live]);
+        assert_eq!(after.allocations, before.allocations + 4);
+        assert_eq!(after.frees, before.frees + 4);
+    }
+
+    static UNION_READS: Mutex<Vec<i64>> = Mutex::new(Vec::new());
+    extern "C" fn record_union_narrow(value: i32) {
+        UNION_READS.lock().unwrap().push(i64::from(value));
+    }
+    extern "C" fn record_union_wide(value: i64) {
+        UNION_READS.lock().unwrap().push(value);
+    }
+
+    /// `union Bits { wide: i64, narrow: i32 }`, built from one field and
+    /// read through the other.
+    fn union_module() -> MirModule<'static> {
+        let mut module = MirModule::new();
+        let i64_int = MirIntTy { signed: true, bits: TypeBits::B64 };
+        let i32_int = MirIntTy { signed: true, bits: TypeBits::B32 };
+        for (id, name, param) in [
+            (0, "record_union_wide", MirTy::Int(i64_int)),
+            (1, "record_union_narrow", MirTy::Int(i32_int)),
+        ] {
+            module.push_extern(MirExtern {
+                hir_id: HirId::from_usize(id),
+                name,
+                params: Box::new([param]),
+                ret_ty: MirTy::Unit,
+                span: span(),
+            });
+        }
+        let copy = |local| MirOperand::Copy(MirPlace::new(local));
+        let assign = |dst, rhs| MirStmt::Assign { dst: MirPlace::new(dst), rhs, span: span() };
+        let record = |extern_id, local| MirRvalue::Call {
+            callee: MirCallee::Extern(MirExternId::from_usize(extern_id)),
+            args: Box::new([copy(local)]),
+        };
+
+        let mut main = MirFunction::new(HirId::from_usize(2), "main", MirTy::Unit, span(), span());
+        let union_ty = MirTy::Union {
+            def: HirId::from_usize(3),
+            fields: Box::new([MirTy::Int(i64_int), MirTy::Int(i32_int)]),
+        };
+        let wide_bits = main.push_local(Some("w"), union_ty.clone(), span());
+        let narrow_bits = main.push_local(Some("n"), union_ty, span());
+        let wide = main.push_local(None, MirTy::Int(i64_int), span());
+        let narrow = main.push_local(None, MirTy::Int(i32_int), span());
+        let result = main.push_local(None, MirTy::Unit, span());
+
+        let mut entry = MirBlock::new(MirTerminator::Return(None));
+        let stmts = &mut entry.stmts;
+        let wide_value = MirImmediate::Int { value: 0x1_0000_0005, ty: i64_int };
+        let fields = Box::new([Some(MirOperand::Immediate(wide_value)), None]);
+        stmts.push(assign(wide_bits, MirRvalue::MakeRecord(fields)));
+        stmts.push(assign(narrow, MirRvalue::LoadField { record: copy(wide_bits), index: 1 }));
+        stmts.push(assign(result, record(1, narrow)));
+        let narrow_value = MirImmediate::Int { value: 7, ty: i32_int };
+        let fields = Box::new([None, Some(MirOperand::Immediate(narrow_value))]);
+        stmts.push(assign(narrow_bits, MirRvalue::MakeRecord(fields)));
+        stmts.push(assign(wide, MirRvalue::LoadField { record: copy(narrow_bits), index: 0 }));
+        stmts.push(assign(result, record(0, wide)));
+        main.entry = main.push_block(entry);
+
+        let main = module.push_function(main);
+        module.entry = Some(main);
+        module
+    }
+
+    #[test]
+    fn reads_union_fields_from_the_storage_another_field_wrote() {
+        let symbols = runec_runtime::symbols()
+            .into_iter()
+            .map(|symbol| (symbol.name, symbol.address))
+            .chain([
+                ("record_union_wide", record_union_wide as *const u8),
+                ("record_union_narrow", record_union_narrow as *const u8),
+            ]);
+        let mut backend = JitBackend::new(symbols, span()).unwrap();
+
+        let before = runec_runtime::heap_stats();
+        backend.run(&union_module(), &SourceMap::new()).unwrap();
+        let after = runec_runtime::heap_stats();
+
+        // The narrow field is the low half of the wide one, and the bytes a
+        // narrow value leaves out of a wide read are zeroed.
+        assert_eq!(*UNION_READS.lock().unwrap(), [5, 7]);
+        assert_eq!(after.allocations, before.allocations + 2);
+        assert_eq!(after.frees, before.frees + 2);
+    }
+
+    /// `while let line = input("> ") { println(line) }`, with the slot that
+    /// `input` returns read the way MIR lowering does.
+    fn echo_module() -> MirModule<'static> {
+        let mut module = MirModule::new();
+        let prompt = module.push_constant(MirConstant::Str("> ".into()));
+        let mut main = MirFunction::new(HirId::from_usize(0), "main", MirTy::Unit, span(), span());
+        let slot = main.push_local(None, MirTy::Pointer, span());
+        let is_null = main.push_local(None, MirTy::Bool, span());
+        let line = main.push_local(Some("line"), MirTy::Str, span());
+        let result = main.push_local(None, MirTy::Unit, span());
+        let copy = |local| MirOperand::Copy(MirPlace::new(local));
+        let assign = |dst, rhs| MirStmt::Assign { dst: MirPlace::new(dst), rhs, span: span() };
+
+        let exit = main.push_block(MirBlock::new(MirTerminator::Return(None)));
+        let head = MirBlockId::from_usize(2);
+        let mut body = MirBlock::new(MirTerminator::Goto(head));
+        body.stmts.push(assign(line, MirRvalue::Load(copy(slot))));
+        body.stmts.push(assign(
+            result,
+            MirRvalue::Call {
+                callee: MirCallee::Runtime(RUNTIME_PRINTLN),
+                args: Box::new([copy(line)]),
+            },
+        ));
+        let body = main.push_block(body);
+        let mut read = MirBlock::new(MirTerminator::Branch {
+            cond: copy(is_null),
+            then_block: exit,
+            else_block: body,
+            span: span(),
+        });
+        read.stmts.push(assign(
+            slot,
+            MirRvalue::Call {
+                callee: MirCallee::Runtime(RUNTIME_INPUT),
+                args: Box::new([MirOperand::Constant(prompt)]),
+            },
+        ));
+        read.stmts.push(assign(
+            is_null,
+            MirRvalue::Binary {
+                op: MirBinOp::Eq,
+                lhs: copy(slot),
+                rhs: MirOperand::Immediate(MirImmediate::Null),
+            },
+        ));
+        assert_eq!(main.push_block(read), head);
+        main.entry = head;
+
+        let main = module.push_function(main);
+        module.entry = Some(main);
+        module
+    }
+
+    /// Set in the child process that [`echoes_lines_piped_to_input`] spawns
+    /// to run the program with its stdin.
+    const INPUT_CHILD: &str = "RUNEWAY_JIT_INPUT_CHILD";
+
+    #[test]
+    fn echoes_lines_piped_to_input() {
+        if std::env::var_os(INPUT_CHILD).is_some() {
+            let symbols =
+                runec_runtime::symbols().into_iter().map(|symbol| (symbol.name, symbol.address));
+            JitBackend::new(symbols, span())
+                .unwrap()
+                .run(&echo_module(), &SourceMap::new())
+                .unwrap();
+            return;
+        }
+
+        let mut child = Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", "emit::jit::tests::echoes_lines_piped_to_input", "--nocapture"])
+            .env(INPUT_CHILD, "1")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child.stdin.take().unwrap().write_all(b"first\r\nsecond\n").unwrap();
+        let output = child.wait_with_output().unwrap();
+
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert!(stdout.contains("> first\n> second\n> "), "{stdout}");
+    }
+
+    static FLOATS: Mutex<Vec<f64>> = Mutex::new(Vec::new());
+    extern "C" fn record_float(value: f64) {
+        FLOATS.lock().unwrap().push(value);
+    }
+    static INTS: Mutex<Vec<i64>> = Mutex::new(Vec::new());
+    extern "C" fn record_int(value: i64) {
+        INTS.lock().unwrap().push(value);
+    }
+
+    /// Records `sqrt(2.25)`, `floor(-1.5)`, `max(1.0, 3.0)`, `pow(2.0, 10.0)`
+    /// and `round(2.5)`, then `abs(-7)`, `min(-3, 4)`, `wrapping_mul(MAX, 2)`,
+    /// `checked_add(2, 3)` and whether `checked_mul(MAX, 2)` is `null`.
+    fn math_module() -> MirModule<'static> {
+        let mut module = MirModule::new();
+        let f64_ty = MirFloatTy { bits: TypeBits::B64 };
+        let i64_ty = MirIntTy { signed: true, bits: TypeBits::B64 };
+        for (id, name, param) in [
+            (0, "record_float", MirTy::Float(f64_ty)),
+            (1, "record_int", MirTy::Int(i64_ty)),
+            (2, "record_null", MirTy::Bool),
+        ] {
+            module.push_extern(MirExtern {
+                hir_id: HirId::from_usize(id),
+                name,
+                params: Box::new([param]),
+                ret_ty: MirTy::Unit,
+                span: span(),
+            });
+        }
+        let float = |value| MirOperand::Immediate(MirImmediate::Float { value, ty: f64_ty });
+        let int = |value: i64| {
+            MirOperand::Immediate(MirImmediate::Int { value: value as u64 as u128, ty: i64_ty })
+        };
+        let copy = |local| MirOperand::Copy(MirPlace::new(local));
+        let assign = |dst, rhs| MirStmt::Assign { dst: MirPlace::new(dst), rhs, span: span() };
+        let intrinsic = |intrinsic, args: Vec<MirOperand>| MirRvalue::Intrinsic {
+            intrinsic,
+            args: args.into_boxed_slice(),
+        };
+        let runtime = |id, args: Vec<MirOperand>| MirRvalue::Call {
+            callee: MirCallee::Runtime(id),
+            args: args.into_boxed_slice(),
+        };
+        let record = |extern_id, local| MirRvalue::Call {
+            callee: MirCallee::Extern(MirExternId::from_usize(extern_id)),
+            args: Box::new([copy(local)]),
+        };
+
+        let mut main = MirFunction::new(HirId::from_usize(3), "main", MirTy::Unit, span(), span());
+        let result = main.push_local(None, MirTy::Unit, span());
+        let f = main.push_local(None, MirTy::Float(f64_ty), span());
+        let i = main.push_local(None, MirTy::Int(i64_ty), span());
+        let checked = main.push_local(None, MirTy::Nullable(Box::new(MirTy::Int(i64_ty))), span());
+        let is_null = main.push_local(None, MirTy::Bool, span());
+
+        let mut entry = MirBlock::new(MirTerminator::Return(None));
+        let stmts = &mut entry.stmts;
+        for rhs in [
+            intrinsic(Intrinsic::Sqrt, vec![float(2.25)]),
+            intrinsic(Intrinsic::Floor, vec![float(-1.5)]),
+            intrinsic(Intrinsic::Max, vec![float(1.0), float(3.0)]),
+            runtime(RUNTIME_POW, vec![float(2.0), float(10.0)]),
+            runtime(RUNTIME_ROUND, vec![float(2.5)]),
+        ] {
+            stmts.push(assign(f, rhs));
+            stmts.push(assign(result, record(0, f)));
+        }
+        for rhs in [
+            intrinsic(Intrinsic::Abs, vec![int(-7)]),
+            intrinsic(Intrinsic::Min, vec![int(-3), int(4)]),
+            intrinsic(Intrinsic::WrappingMul, vec![int(i64::MAX), int(2)]),
+        ] {
+            stmts.push(assign(i, rhs));
+            stmts.push(assign(result, record(1, i)));
+        }
+        stmts.push(assign(checked, intrinsic(Intrinsic::CheckedAdd, vec![int(2), int(3)])));
+        stmts.push(assign(i, MirRvalue::UnwrapNullable(copy(checked))));
+        stmts.push(assign(result, record(1, i)));
+        stmts.push(assign(checked, intrinsic(Intrinsic::CheckedMul, vec![int(i64::MAX), int(2)])));
+        stmts.push(assign(
+            is_null,
+            MirRvalue::Binary {
+                op: MirBinOp::Eq,
+                lhs: copy(checked),
+                rhs: MirOperand::Immediate(MirImmediate::Null),
+            },
+        ));
+        stmts.push(assign(result, record(2, is_null)));
+        main.entry = main.push_block(entry);
+
+        let main = module.push_function(main);
+        module.entry = Some(main);
+        module
+    }
+
+    #[test]
+    fn expands_math_intrinsics_and_calls_math_runtime_functions() {
+        static OVERFLOWED: AtomicBool = AtomicBool::new(false);
+        extern "C" fn record_null(value: bool) {
+            OVERFLOWED.store(value, Ordering::SeqCst);
+        }
+        let symbols = runec_runtime::symbols()
+            .into_iter()
+            .map(|symbol| (symbol.name, symbol.address))
+            .chain([
+                ("record_float", record_float as *const u8),
+                ("record_int", record_int as *const u8),
+                ("record_null", record_null as *const u8),
+            ]);
+        let mut backend = JitBackend::new(symbols, span()).unwrap();
+        backend.run(&math_module(), &SourceMap::new()).unwrap();
+
+        assert_eq!(*FLOATS.lock().unwrap(), [1.5, -2.0, 3.0, 1024.0, 3.0]);
+        assert_eq!(*INTS.lock().unwrap(), [7, -3, -2, 5]);
+        assert!(OVERFLOWED.load(Ordering::SeqCst));
+    }
+
+    static INT_TEXTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    unsafe extern "C" fn record_int_text(ptr: *const u8, len: usize) {
+        let bytes = unsafe { std::slice::from_raw_parts(ptr, len) };
+        INT_TEXTS.lock().unwrap().push(String::from_utf8(bytes.to_vec()).unwrap());
+    }
+
+    /// Records the text of `-7i8`, `200u8` and `u64::MAX`.
+    fn int_text_module() -> MirModule<'static> {
+        let mut module = MirModule::new();
+        module.push_extern(MirExtern {
+            hir_id: HirId::from_usize(0),
+            name: "record_int_text",
+            params: Box::new([MirTy::Str]),
+            ret_ty: MirTy::Unit,
+            span: span(),
+        });
+        let int = |value, signed, bits| {
+            MirOperand::Immediate(MirImmediate::Int { value, ty: MirIntTy { signed, bits } })
+        };
+        let assign = |dst, rhs| MirStmt::Assign { dst: MirPlace::new(dst), rhs, span: span() };
+
+        let mut main = MirFunction::new(HirId::from_usize(1), "main", MirTy::Unit, span(), span());
+        let result = main.push_local(None, MirTy::Unit, span());
+        let text = main.push_local(None, MirTy::Str, span());
+
+        let mut entry = MirBlock::new(MirTerminator::Return(None));
+        for value in [
+            int(0xf9, true, TypeBits::B8),
+            int(200, false, TypeBits::B8),
+            int(u128::from(u64::MAX), false, TypeBits::B64),
+        ] {
+            let rhs =
+                MirRvalue::Intrinsic { intrinsic: Intrinsic::IntToString, args: Box::new([value]) };
+            entry.stmts.push(assign(text, rhs));
+            entry.stmts.push(assign(
+                result,
+                MirRvalue::Call {
+                    callee: MirCallee::Extern(MirExternId::from_usize(0)),
+                    args: Box::new([MirOperand::Copy(MirPlace::new(text))]),
+                },
+            ));
+        }
+        main.entry = main.push_block(entry);
+
+        let main = module.push_function(main);
+        module.entry = Some(main);
+        module
+    }
+
+    #[test]
+    fn formats_integers_by_their_width_and_signedness() {
+        let symbols = runec_runtime::symbols()
+            .into_iter()
+            .map(|symbol| (symbol.name, symbol.address))
+            .chain([("record_int_text", record_int_text as *const u8)]);
+        let mut backend = JitBackend::new(symbols, span()).unwrap();
+        let before = runec_runtime::heap_stats();
+        backend.run(&int_text_module(), &SourceMap::new()).unwrap();
+        let after = runec_runtime::heap_stats();
+
+        assert_eq!(after.live_allocations(), before.live_allocations());
+        assert_eq!(*INT_TEXTS.lock().unwrap(), ["-7", "200", "18446744073709551615"]);
+    }
+
+    static ORDERINGS: Mutex<Vec<i64>> = Mutex::new(Vec::new());
+    extern "C" fn record_ordering(value: i64) {
+        ORDERINGS.lock().unwrap().push(value);
+    }
+
+    /// Records the orderings of `apple` and `banana`, `b` and `a`, and `same`
+    /// and itself.
+    fn str_cmp_module() -> MirModule<'static> {
+        let mut module = MirModule::new();
+        module.push_extern(MirExtern {
+            hir_id: HirId::from_usize(0),
+            name: "record_ordering",
+            params: Box::new([MirTy::Int(MirIntTy { signed: true, bits: TypeBits::B64 })]),
+            ret_ty: MirTy::Unit,
+            span: span(),
+        });
+        let assign = |dst, rhs| MirStmt::Assign { dst: MirPlace::new(dst), rhs, span: span() };
+
+        let mut main = MirFunction::new(HirId::from_usize(1), "main", MirTy::Unit, span(), span());
+        let result = main.push_local(None, MirTy::Unit, span());
+        let ordering_ty = MirTy::Int(MirIntTy { signed: true, bits: TypeBits::B64 });
+        let ordering = main.push_local(None, ordering_ty, span());
+
+        let mut entry = MirBlock::new(MirTerminator::Return(None));
+        for (lhs, rhs) in [("apple", "banana"), ("b", "a"), ("same", "same")] {
+            let [lhs, rhs] = [lhs, rhs].map(|text| {
+                MirOperand::Constant(module.push_constant(MirConstant::Str(text.into())))
+            });
+            let rhs =
+                MirRvalue::Intrinsic { intrinsic: Intrinsic::StrCmp, args: Box::new([lhs, rhs]) };
+            entry.stmts.push(assign(ordering, rhs));
+            entry.stmts.push(assign(
+                result,
+                MirRvalue::Call {
+                    callee: MirCallee::Extern(MirExternId::from_usize(0)),
+                    args: Box::new([MirOperand::Copy(MirPlace::new(ordering))]),
+                },
+            ));
+        }
+        main.entry = main.push_block(entry);
+
+        let main = module.push_function(main);
+        module.entry = Some(main);
+        module
+    }
+
+    #[test]
+    fn orders_strings_through_the_runtime() {
+        let symbols = runec_runtime::symbols()
+            .into_iter()
+            .map(|symbol| (symbol.name, symbol.address))
+            .chain([("record_ordering", record_ordering as *const u8)]);
+        let mut backend = JitBackend::new(symbols, span()).unwrap();
+        backend.run(&str_cmp_module(), &SourceMap::new()).unwrap();
+
+        assert_eq!(*ORDERINGS.lock().unwrap(), [-1, 1, 0]);
+    }
+
+    static FILE_FLAGS: Mutex<Vec<bool>> = Mutex::new(Vec::new());
+    extern "C" fn record_flag(value: bool) {
+        FILE_FLAGS.lock().unwrap().push(value);
+    }
+    static FILE_TEXTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    unsafe extern "C" fn record_text(ptr: *const u8, len: usize) {
+        let bytes = unsafe { std::slice::from_raw_parts(ptr, len) };
+        FILE_TEXTS.lock().unwrap().push(String::from_utf8(bytes.to_vec()).unwrap());
+    }
+
+    /// Writes `hello` to `path` and reads it back, then reads `missing`,
+    /// recording whether each operation failed and the text or error read.
+    fn files_module(path: String, missing: String) -> MirModule<'static> {
+        let mut module = MirModule::new();
+        for (id, name, param) in [(0, "record_flag", MirTy::Bool), (1, "record_text", MirTy::Str)] {
+            module.push_extern(MirExtern {
+                hir_id: HirId::from_usize(id),
+                name,
+                params: Box::new([param]),
+                ret_ty: MirTy::Unit,
+                span: span(),
+            });
+        }
+        let path = module.push_constant(MirConstant::Str(path.into()));
+        let missing = module.push_constant(MirConstant::Str(missing.into()));
+        let hello = module.push_constant(MirConstant::Str("hello".into()));
+        let copy = |local| MirOperand::Copy(MirPlace::new(local));
+        let assign = |dst, rhs| MirStmt::Assign { dst: MirPlace::new(dst), rhs, span: span() };
+        let runtime = |id, args: Vec<MirOperand>| MirRvalue::Call {
+            callee: MirCallee::Runtime(id),
+            args: args.into_boxed_slice(),
+        };
+        let record = |extern_id, local| MirRvalue::Call {
+            callee: MirCallee::Extern(MirExternId::from_usize(extern_id)),
+            args: Box::new([copy(local)]),
+        };
+        let fallible = |ok| MirTy::Fallible { ok: Box::new(ok), err: Box::new(MirTy::Str) };
+
+        let mut main = MirFunction::new(HirId::from_usize(2), "main", MirTy::Unit, span(), span());
+        let result = main.push_local(None, MirTy::Unit, span());
+        let slot = main.push_local(None, MirTy::Pointer, span());
+        let written = main.push_local(None, fallible(MirTy::Unit), span());
+        let read = main.push_local(None, fallible(MirTy::Str), span());
+        let is_err = main.push_local(None, MirTy::Bool, span());
+        let text = main.push_local(None, MirTy::Str, span());
+
+        let mut entry = MirBlock::new(MirTerminator::Return(None));
+        let stmts = &mut entry.stmts;
+        let constant = MirOperand::Constant;
+        stmts.push(assign(slot, runtime(RUNTIME_WRITE, vec![constant(path), constant(hello)])));
+        stmts.push(assign(written, MirRvalue::Load(copy(slot))));
+        stmts.push(assign(is_err, MirRvalue::IsErr(copy(written))));
+        stmts.push(assign(result, record(0, is_err)));
+        stmts.push(assign(slot, runtime(RUNTIME_READ_TO_STRING, vec![constant(path)])));
+        stmts.push(assign(read, MirRvalue::Load(copy(slot))));
+        stmts.push(assign(text, MirRvalue::UnwrapOk(copy(read))));
+        stmts.push(assign(result, record(1, text)));
+        stmts.push(assign(slot, runtime(RUNTIME_READ_TO_STRING, vec![constant(missing)])));
+        stmts.push(assign(read, MirRvalue::Load(copy(slot))));
+        stmts.push(assign(is_err, MirRvalue::IsErr(copy(read))));
+        stmts.push(assign(result, record(0, is_err)));
+        stmts.push(assign(text, MirRvalue::UnwrapErr(copy(read))));
+        stmts.push(assign(result, record(1, text)));
+        main.entry = main.push_block(entry);
+
+        let main = module.push_function(main);
+        module.entry = Some(main);
+        module
+    }
+
+    #[test]
+    fn reads_file_results_and_errors_from_runtime_slots() {
+        let dir = std::env::temp_dir().join(format!("runeway-jit-files-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("hello.txt").to_str().unwrap().to_owned();
+        let missing = dir.join("missing.txt").to_str().unwrap().to_owned();
+        let symbols = runec_runtime::symbols()
+            .into_iter()
+            .map(|symbol| (symbol.name, symbol.address))
+            .chain([
+                ("record_flag", record_flag as *const u8),
+                ("record_text", record_text as *const u8),
+            ]);
+        let mut backend = JitBackend::new(symbols, span()).unwrap();
+        let before = runec_runtime::heap_stats();
+        backend.run(&files_module(path, missing.clone()), &SourceMap::new()).unwrap();
+        let after = runec_runtime::heap_stats();
+        std::fs::remove_dir_all(dir).unwrap();
+
+        // The strings read and the error message are released on return.
+        assert_eq!(after.live_allocations(), before.live_allocations());
+
+        assert_eq!(*FILE_FLAGS.lock().unwrap(), [false, true]);
+        let texts = FILE_TEXTS.lock().unwrap();
+        assert_eq!(texts[0], "hello");
+        assert!(texts[1].starts_with(&format!("cannot read `{missing}`: ")), "{}", texts[1]);
+    }
+
+    static TIME_NANOS: Mutex<Vec<u64>> = Mutex::new(Vec::new());
+    extern "C" fn record_nanos(value: u64) {
+        TIME_NANOS.lock().unwrap().push(value);
+    }
+    static TIME_SECS: Mutex<Vec<f64>> = Mutex::new(Vec::new());
+    extern "C" fn record_secs(value: f64) {
+        TIME_SECS.lock().unwrap().push(value);
+    }
+
+    /// Records `from_secs(3)`, `as_millis` of 2.5ms, saturating `5 - 7` and
+    /// `MAX + 1`, and `as_secs_f64` of 1.5s, then how long a 1ms sleep took.
+    fn time_module() -> MirModule<'static> {
+        let mut module = MirModule::new();
+        let u64_ty = MirIntTy { signed: false, bits: TypeBits::B64 };
+        let f64_ty = MirFloatTy { bits: TypeBits::B64 };
+        for (id, name, param) in
+            [(0, "record_nanos", MirTy::Int(u64_ty)), (1, "record_secs", MirTy::Float(f64_ty))]
+        {
+            module.push_extern(MirExtern {
+                hir_id: HirId::from_usize(id),
+                name,
+                params: Box::new([param]),
+                ret_ty: MirTy::Unit,
+                span: span(),
+            });
+        }
+        let int = |value: u64| {
+            MirOperand::Immediate(MirImmediate::Int { value: u128::from(value), ty: u64_ty })
+        };
+        let copy = |local| MirOperand::Copy(MirPlace::new(local));
+        let assign = |dst, rhs| MirStmt::Assign { dst: MirPlace::new(dst), rhs, span: span() };
+        let intrinsic = |intrinsic, args: Vec<MirOperand>| MirRvalue::Intrinsic {
+            intrinsic,
+            args: args.into_boxed_slice(),
+        };
+        let runtime = |id, args: Vec<MirOperand>| MirRvalue::Call {
+            callee: MirCallee::Runtime(id),
+            args: args.into_boxed_slice(),
+        };
+        let record = |extern_id, local| MirRvalue::Call {
+            callee: MirCallee::Extern(MirExternId::from_usize(extern_id)),
+            args: Box::new([copy(local)]),
+        };
+
+        let mut main = MirFunction::new(HirId::from_usize(2), "main", MirTy::Unit, span(), span());
+        let result = main.push_local(None, MirTy::Unit, span());
+        let nanos = main.push_local(None, MirTy::Int(u64_ty), span());
+        let secs = main.push_local(None, MirTy::Float(f64_ty), span());
+        let start = main.push_local(None, MirTy::Int(u64_ty), span());
+
+        let mut entry = MirBlock::new(MirTerminator::Return(None));
+        let stmts = &mut entry.stmts;
+        for rhs in [
+            intrinsic(Intrinsic::MulConst(1_000_000_000), vec![int(3)]),
+            intrinsic(Intrinsic::DivConst(1_000_000), vec![int(2_500_000)]),
+            intrinsic(Intrinsic::SaturatingSub, vec![int(5), int(7)]),
+            intrinsic(Intrinsic::SaturatingAdd, vec![int(u64::MAX), int(1)]),
+        ] {
+            stmts.push(assign(nanos, rhs));
+            stmts.push(assign(result, record(0, nanos)));
+        }
+        stmts.push(assign(secs, intrinsic(Intrinsic::NanosToSecs, vec![int(1_500_000_000)])));
+        stmts.push(assign(result, record(1, secs)));
+        stmts.push(assign(start, runtime(RUNTIME_INSTANT_NOW, Vec::new())));
+        stmts.push(assign(result, runtime(RUNTIME_SLEEP, vec![int(1_000_000)])));
+        stmts.push(assign(nanos, runtime(RUNTIME_INSTANT_ELAPSED, vec![copy(start)])));
+        stmts.push(assign(result, record(0, nanos)));
+        main.entry = main.push_block(entry);
+
+        let main = module.push_function(main);
+        module.entry = Some(main);
+        module
+    }
+
+    #[test]
+    fn expands_time_intrinsics_and_reads_the_runtime_clock() {
+        let symbols = runec_runtime::symbols()
+            .into_iter()
+            .map(|symbol| (symbol.name, symbol.address))
+            .chain([
+                ("record_nanos", record_nanos as *const u8),
+                ("record_secs", record_secs as *const u8),
+            ]);
+        let mut backend = JitBackend::new(symbols, span()).unwrap();
+        backend.run(&time_module(), &SourceMap::new()).unwrap();
+
+        let nanos = TIME_NANOS.lock().unwrap();
+        assert_eq!(nanos[..4], [3_000_000_000, 2, 0, u64::MAX]);
+        assert!(nanos[4] >= 1_000_000, "slept for {}ns", nanos[4]);
+        assert_eq!(*TIME_SECS.lock().unwrap(), [1.5]);
+    }
+
+    static RANDOM_INTS: Mutex<Vec<i64>> = Mutex::new(Vec::new());
+    extern "C" fn record_random(value: i64) {
+        RANDOM_INTS.lock().unwrap().push(value);
+    }
+
+    /// Seeds the generator with 9 and records two `int_range(0, 1000)` draws,
+    /// twice over.
+    fn random_module() -> MirModule<'static> {
+        let mut module = MirModule::new();
+        let i64_ty = MirIntTy { signed: true, bits: TypeBits::B64 };
+        let u64_ty = MirIntTy { signed: false, bits: TypeBits::B64 };
+        module.push_extern(MirExtern {
+            hir_id: HirId::from_usize(0),
+            name: "record_random",
+            params: Box::new([MirTy::Int(i64_ty)]),
+            ret_ty: MirTy::Unit,
+            span: span(),
+        });
+        let int = |value: i64, ty| {
+            MirOperand::Immediate(MirImmediate::Int { value: value as u64 as u128, ty })
+        };
+        let assign = |dst, rhs| MirStmt::Assign { dst: MirPlace::new(dst), rhs, span: span() };
+        let runtime = |id, args: Vec<MirOperand>| MirRvalue::Call {
+            callee: MirCallee::Runtime(id),
+            args: args.into_boxed_slice(),
+        };
+
+        let mut main = MirFunction::new(HirId::from_usize(1), "main", MirTy::Unit, span(), span());
+        let result = main.push_local(None, MirTy::Unit, span());
+        let drawn = main.push_local(None, MirTy::Int(i64_ty), span());
+
+        let mut entry = MirBlock::new(MirTerminator::Return(None));
+        for _ in 0..2 {
+            entry.stmts.push(assign(result, runtime(RUNTIME_RANDOM_SEED, vec![int(9, u64_ty)])));
+            for _ in 0..2 {
+                let range = vec![int(0, i64_ty), int(1000, i64_ty)];
+                entry.stmts.push(assign(drawn, runtime(RUNTIME_RANDOM_INT_RANGE, range)));
+                entry.stmts.push(assign(
+                    result,
+                    MirRvalue::Call {
+                        callee: MirCallee::Extern(MirExternId::from_usize(0)),
+                        args: Box::new([MirOperand::Copy(MirPlace::new(drawn))]),
+                    },
+                ));
+            }
+        }
+        main.entry = main.push_block(entry);
+
+        let main = module.push_function(main);
+        module.entry = Some(main);
+        module
+    }
+
+    #[test]
+    fn reseeding_the_runtime_generator_repeats_its_draws() {
+        let symbols = runec_runtime::symbols()
+            .into_iter()
+            .map(|symbol| (symbol.name, symbol.address))
+            .chain([("record_random", record_random as *const u8)]);
+        let mut backend = JitBackend::new(symbols, span()).unwrap();
+        backend.run(&random_module(), &SourceMap::new()).unwrap();
+
+        let draws = RANDOM_INTS.lock().unwrap();
+        assert_eq!(draws[..2], draws[2..]);
+        assert!(draws.iter().all(|draw| (0..1000).contains(draw)), "{draws:?}");
+    }
+
+    static SHUFFLED: Mutex<Vec<i64>> = Mutex::new(Vec::new());
+    extern "C" fn record_shuffled(value: i64) {
+        SHUFFLED.lock().unwrap().push(value);
+    }
+
+    /// Pushes `0..20` to a vector, shuffles the slice `5..=14` of it and
+    /// records every element.
+    fn shuffle_module() -> MirModule<'static> {
+        let mut module = MirModule::new();
+        let i64_ty = MirIntTy { signed: true, bits: TypeBits::B64 };
+        module.push_extern(MirExtern {
+            hir_id: HirId::from_usize(0),
+            name: "record_shuffled",
+            params: Box::new([MirTy::Int(i64_ty)]),
+            ret_ty: MirTy::Unit,
+            span: span(),
+        });
+        let int = |value| MirOperand::Immediate(MirImmediate::Int { value, ty: i64_ty });
+        let copy = |local| MirOperand::Copy(MirPlace::new(local));
+        let assign = |dst, rhs| MirStmt::Assign { dst: MirPlace::new(dst), rhs, span: span() };
+        let intrinsic = |intrinsic, args: Vec<MirOperand>| MirRvalue::Intrinsic {
+            intrinsic,
+            args: args.into_boxed_slice(),
+        };
+
+        let mut main = MirFunction::new(HirId::from_usize(1), "main", MirTy::Unit, span(), span());
+        let elem = MirTy::Int(i64_ty);
+        let vec = main.push_local(Some("v"), MirTy::Vec(Box::new(elem.clone())), span());
+        let result = main.push_local(None, MirTy::Unit, span());
+        let item = main.push_local(None, elem.clone(), span());
+        let range = main.push_local(None, MirTy::Range(i64_ty), span());
+        let slice = main.push_local(None, MirTy::Slice(Box::new(elem)), span());
+
+        let mut entry = MirBlock::new(MirTerminator::Return(None));
+        let stmts = &mut entry.stmts;
+        stmts.push(assign(vec, intrinsic(Intrinsic::VecNew, vec![])));
+        for value in 0..20 {
+            stmts.push(assign(result, intrinsic(Intrinsic::VecPush, vec![copy(vec), int(value)])));
+        }
+        let seed =
+            MirImmediate::Int { value: 5, ty: MirIntTy { signed: false, bits: TypeBits::B64 } };
+        stmts.push(assign(
+            result,
+            MirRvalue::Call {
+                callee: MirCallee::Runtime(RUNTIME_RANDOM_SEED),
+                args: Box::new([MirOperand::Immediate(seed)]),
+            },
+        ));
+        stmts.push(assign(
+            range,
+            MirRvalue::MakeRange { start: int(5), end: int(14), inclusive: true },
+        ));
+        stmts.push(assign(slice, MirRvalue::Slice { base: copy(vec), range: copy(range) }));
+        stmts.push(assign(result, intrinsic(Intrinsic::Shuffle, vec![copy(slice)])));
+        for index in 0..20 {
+            stmts.push(assign(item, MirRvalue::Index { base: copy(vec), index: int(index) }));
+            stmts.push(assign(
+                result,
+                MirRvalue::Call {
+                    callee: MirCallee::Extern(MirExternId::from_usize(0)),
+                    args: Box::new([copy(item)]),
+                },
+            ));
+        }
+        main.entry = main.push_block(entry);
+
+        let main = module.push_function(main);
+        module.entry = Some(main);
+        module
+    }
+
+    #[test]
+    fn shuffles_only_the_elements_a_slice_views() {
+        let symbols = runec_runtime::symbols()
+            .into_iter()
+            .map(|symbol| (symbol.name, symbol.address))
+            .chain([("record_shuffled", record_shuffled as *const u8)]);
+        let mut backend = JitBackend::new(symbols, span()).unwrap();
+        backend.run(&shuffle_module(), &SourceMap::new()).unwrap();
+
+        let values = SHUFFLED.lock().unwrap();
+        assert_eq!(values[..5], [0, 1, 2, 3, 4]);
+        assert_eq!(values[15..], [15, 16, 17, 18, 19]);
+        let mut shuffled = values[5..15].to_vec();
+        assert_ne!(shuffled, (5..15).collect::<Vec<_>>());
+        shuffled.sort_unstable();
+        assert_eq!(shuffled, (5..15).collect::<Vec<_>>());
+    }
+
+    const EMPTY_RANGE_SOURCE: &str =
+        "use std::random;\nact main() {\n    let x = random::int_range(5, 5);\n}\n";
+
+    #[test]
+    fn returns_draws_from_empty_ranges_as_a_panic_at_their_source_location() {
+        let mut source_map = SourceMap::new();
+        let mock = runec_test_utils::MockSourceFileLoader { source: EMPTY_RANGE_SOURCE };
+        let source = source_map.add_file(mock.load(PathBuf::from("main.rnw")).unwrap());
+        let call_span = Span::new(BytePos::from_usize(42), BytePos::from_usize(65), source);
+
+        let mut module = MirModule::new();
+        let int = MirIntTy { signed: true, bits: TypeBits::B64 };
+        let five = MirOperand::Immediate(MirImmediate::Int { value: 5, ty: int });
+        let mut main = MirFunction::new(HirId::from_usize(0), "main", MirTy::Unit, span(), span());
+        let drawn = main.push_local(Some("x"), MirTy::Int(int), span());
+        let mut entry = MirBlock::new(MirTerminator::Return(None));
+        entry.stmts.push(MirStmt::Assign {
+            dst: MirPlace::new(drawn),
+            rhs: MirRvalue::Call {
+                callee: MirCallee::Runtime(RUNTIME_RANDOM_INT_RANGE),
+                args: Box::new([five.clone(), five]),
+            },
+            span: call_span,
+        });
+        main.entry = main.push_block(entry);
+        let main = module.push_function(main);
+        module.entry = Some(main);
+        let symbols =
+            runec_runtime::symbols().into_iter().map(|symbol| (symbol.name, symbol.address));
+        let mut backend = JitBackend::new(symbols, span()).unwrap();
+
+        let error = backend
+            .run(&module, &source_map)
+            .expect_err("the draw from an empty range should be returned from the run");
+
+        assert_eq!(
+            error.message.message,
+            "panicked at main.rnw:3:13: cannot draw from the empty range 5..5"
+        );
+    }
 }
@@ -1,5 +1,26 @@
+use cranelift_codegen::isa::OwnedTargetIsa;
+use cranelift_codegen::settings::{self, Configurable};
+use runec_source::span::Span;
+
+use crate::CodegenResult;
+use crate::diagnostics::backend;
+
 mod aot;
 mod jit;
+mod unwind;
 
 pub use aot::AotBackend;
 pub use jit::JitBackend;
+
+fn native_isa(diagnostic_span: Span) -> CodegenResult<OwnedTargetIsa> {
+    let mut flags = settings::builder();
+    // Fallible values can need more return registers than the target has;
+    // the rest are returned through memory.
+    flags
+        .enable("enable_multi_ret_implicit_sret")
+        .map_err(|error| backend(error.to_string(), diagnostic_span))?;
+    cranelift_native::builder()
+        .map_err(|error| backend(error.to_string(), diagnostic_span))?
+        .finish(settings::Flags::new(flags))
+        .map_err(|error| backend(error, diagnostic_span))
+}
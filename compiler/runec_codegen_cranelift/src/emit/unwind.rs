@@ -0,0 +1,94 @@
+use cranelift_codegen::gimli::RunTimeEndian;
+use cranelift_codegen::gimli::write::{Address, EhFrame, EndianVec, FrameTable};
+use cranelift_codegen::ir::Endianness;
+use cranelift_codegen::isa::TargetIsa;
+use cranelift_codegen::isa::unwind::UnwindInfo;
+use runec_source::span::Span;
+
+use crate::CodegenResult;
+use crate::diagnostics::backend;
+
+unsafe extern "C" {
+    // Provided by the unwinder the Rust standard library links against.
+    fn __register_frame(begin: *const u8);
+    fn __deregister_frame(begin: *const u8);
+}
+
+/// `.eh_frame` section describing functions finalized by the JIT, registered
+/// with the system unwinder for as long as it is alive so that Rust panics
+/// can unwind through the generated code.
+pub(super) struct RegisteredFrames {
+    eh_frame: Box<[u8]>,
+}
+
+impl RegisteredFrames {
+    /// Registers the unwind information of functions placed at the given
+    /// addresses.
+    pub(super) fn register<'a>(
+        isa: &dyn TargetIsa,
+        functions: impl IntoIterator<Item = (*const u8, &'a UnwindInfo)>,
+        diagnostic_span: Span,
+    ) -> CodegenResult<Option<Self>> {
+        let Some(cie) = isa.create_systemv_cie() else {
+            return Ok(None);
+        };
+        let mut table = FrameTable::default();
+        let cie = table.add_cie(cie);
+        let mut empty = true;
+        for (address, info) in functions {
+            if let UnwindInfo::SystemV(info) = info {
+                table.add_fde(cie, info.to_fde(Address::Constant(address as u64)));
+                empty = false;
+            }
+        }
+        if empty {
+            return Ok(None);
+        }
+
+        let endian = match isa.endianness() {
+            Endianness::Little => RunTimeEndian::Little,
+            Endianness::Big => RunTimeEndian::Big,
+        };
+        let mut eh_frame = EhFrame(EndianVec::new(endian));
+        table.write_eh_frame(&mut eh_frame).map_err(|error| backend(error, diagnostic_span))?;
+        let mut eh_frame = eh_frame.0.into_vec();
+        // A zero length ends the section.
+        eh_frame.extend_from_slice(&[0; 4]);
+
+        let frames = Self { eh_frame: eh_frame.into_boxed_slice() };
+        // SAFETY: The section is well formed and outlives its registration,
+        // which is undone when `frames` is dropped.
+        frames.for_each_entry(|entry| unsafe { __register_frame(entry) });
+        Ok(Some(frames))
+    }
+
+    /// Calls `f` with what the unwinder registers: the whole section for
+    /// libgcc, and each entry after the leading CIE for the libunwind used
+    /// on macOS.
+    fn for_each_entry(&self, f: impl Fn(*const u8)) {
+        let start = self.eh_frame.as_ptr();
+        if !cfg!(target_os = "macos") {
+            f(start);
+            return;
+        }
+        let mut offset = 0;
+        while offset + 4 <= self.eh_frame.len() {
+            let len = u32::from_ne_bytes(self.eh_frame[offset..offset + 4].try_into().unwrap());
+            if len == 0 {
+                break;
+            }
+            if offset != 0 {
+                f(start.wrapping_add(offset));
+            }
+            // The length does not include its own four bytes.
+            offset += len as usize + 4;
+        }
+    }
+}
+
+impl Drop for RegisteredFrames {
+    fn drop(&mut self) {
+        // SAFETY: Each entry was registered by `register`.
+        self.for_each_entry(|entry| unsafe { __deregister_frame(entry) });
+    }
+}
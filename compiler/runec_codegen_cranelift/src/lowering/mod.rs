@@ -1,8 +1,20 @@
 use std::collections::HashSet;
 
-use runec_abi::{RuntimeFunctionId, runtime_function};
-use runec_builtins::TypeBits;
-use runec_mir::{MirCallee, MirFunction, MirFunctionId, MirModule, MirRvalue, MirTy};
+use runec_abi::{
+    RUNTIME_ALLOC, RUNTIME_INDEX_OUT_OF_BOUNDS, RUNTIME_INT_TO_STRING, RUNTIME_MAP_GET,
+    RUNTIME_MAP_INSERT, RUNTIME_MAP_KEYS, RUNTIME_MAP_NEW, RUNTIME_MAP_NEW_RECORDS,
+    RUNTIME_MAP_REMOVE, RUNTIME_MAP_VALUES, RUNTIME_PANIC, RUNTIME_RANDOM_EMPTY_RANGE,
+    RUNTIME_RANDOM_INT_RANGE, RUNTIME_RANDOM_SHUFFLE, RUNTIME_RELEASE, RUNTIME_RETAIN,
+    RUNTIME_SET_DROP, RUNTIME_SET_TRACE, RUNTIME_SLICE_OUT_OF_BOUNDS, RUNTIME_STR_CMP,
+    RUNTIME_STR_HASH, RUNTIME_VEC_NEW, RUNTIME_VEC_POP, RUNTIME_VEC_PUSH, RuntimeFunctionId,
+    runtime_function,
+};
+use runec_builtins::{Intrinsic, TypeBits};
+use runec_mir::{
+    MirCallee, MirFunction, MirFunctionId, MirModule, MirRvalue, MirStmt, MirTerminator, MirTy,
+};
+use runec_source::source_map::SourceMap;
+use runec_source::span::Span;
 
 use crate::diagnostics::{CodegenResult, error, messages};
 use crate::signature::{AbiType, FunctionSignature};
@@ -10,6 +22,7 @@ use crate::signature::{AbiType, FunctionSignature};
 mod native;
 
 pub use native::CompiledModule;
+pub(crate) use native::panic_sites;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum EmitMode {
@@ -84,48 +97,37 @@ impl CraneliftLowerer {
         })
     }
 
-    /// Lowers MIR into a backend-neutral Cranelift module.
+    /// Lowers MIR into a backend-neutral Cranelift module, resolving the
+    /// locations `panic` reports through `source_map`.
     ///
     /// JIT and AOT adapters share this path and differ only in module
-    /// finalization.
+    /// finalization; the JIT also gets the unwind information it registers
+    /// so that panics can unwind out of the generated code.
     pub fn compile<M: cranelift_module::Module>(
         &mut self,
         backend: &mut M,
         module: &MirModule<'_>,
-        diagnostic_span: runec_source::span::Span,
+        source_map: &SourceMap,
+        diagnostic_span: Span,
     ) -> CodegenResult<CompiledModule> {
-        native::compile_module(backend, module, diagnostic_span)
+        let collect_unwind = self.options.mode == EmitMode::Jit;
+        native::compile_module(backend, module, (source_map, collect_unwind), diagnostic_span)
     }
 
     fn lower_runtime_functions(
         &self,
         module: &MirModule,
     ) -> CodegenResult<Vec<LoweredRuntimeFunction>> {
-        let mut seen = HashSet::new();
-        let mut functions = Vec::new();
-
-        for function in &module.functions {
-            for block in &function.blocks {
-                for stmt in &block.stmts {
-                    let runec_mir::MirStmt::Assign { rhs, span, .. } = stmt;
-                    let MirRvalue::Call { callee: MirCallee::Runtime(id), .. } = rhs else {
-                        continue;
-                    };
-
-                    if seen.insert(*id) {
-                        functions.push(self.lower_runtime_function(*id, *span)?);
-                    }
-                }
-            }
-        }
-
-        Ok(functions)
+        runtime_dependencies(module)
+            .into_iter()
+            .map(|(id, span)| self.lower_runtime_function(id, span))
+            .collect()
     }
 
     fn lower_runtime_function(
         &self,
         id: RuntimeFunctionId,
-        span: runec_source::span::Span,
+        span: Span,
     ) -> CodegenResult<LoweredRuntimeFunction> {
         let declaration = runtime_function(id).ok_or_else(|| {
             let function = format!("{id:?}");
@@ -150,21 +152,16 @@ impl CraneliftLowerer {
                 let local = format!("{param:?}");
                 error(messages::UNKNOWN_LOCAL, &[("local", &local)], function.span)
             })?;
-            self.lower_type(local.ty, local.span, &mut params)?;
+            self.lower_type(&local.ty, local.span, &mut params)?;
         }
 
         let mut returns = Vec::new();
-        self.lower_type(function.ret_ty, function.ret_span, &mut returns)?;
+        self.lower_type(&function.ret_ty, function.ret_span, &mut returns)?;
 
         Ok(FunctionSignature::new(params, returns))
     }
 
-    fn lower_type(
-        &self,
-        ty: MirTy,
-        span: runec_source::span::Span,
-        output: &mut Vec<AbiType>,
-    ) -> CodegenResult<()> {
+    fn lower_type(&self, ty: &MirTy, span: Span, output: &mut Vec<AbiType>) -> CodegenResult<()> {
         match ty {
             MirTy::Unit => {}
             MirTy::Bool => output.push(AbiType::I8),
@@ -188,15 +185,160 @@ impl CraneliftLowerer {
                 output.push(AbiType::Pointer);
                 output.push(AbiType::Usize);
             }
+            MirTy::Slice(_) => {
+                output.push(AbiType::Pointer);
+                output.push(AbiType::Usize);
+                output.push(AbiType::Usize);
+            }
+            MirTy::Managed
+            | MirTy::Pointer
+            | MirTy::Closure
+            | MirTy::Cell(_)
+            | MirTy::Vec(_)
+            | MirTy::Map { .. }
+            | MirTy::Record { .. }
+            | MirTy::Union { .. } => output.push(AbiType::Pointer),
+            MirTy::Nullable(inner) => {
+                if !inner.is_pointer_like() {
+                    output.push(AbiType::I8);
+                }
+                self.lower_type(inner, span, output)?;
+            }
+            MirTy::Fallible { ok, err } => {
+                output.push(AbiType::I8);
+                self.lower_type(ok, span, output)?;
+                self.lower_type(err, span, output)?;
+            }
+            MirTy::Range(int) => {
+                self.lower_type(&MirTy::Int(*int), span, output)?;
+                self.lower_type(&MirTy::Int(*int), span, output)?;
+                output.push(AbiType::I8);
+            }
         }
 
         Ok(())
     }
 }
 
+/// Collects the runtime functions referenced by `module` in first-use order,
+/// paired with the span of their first use.
+///
+/// Copies and returns of managed locals are lowered to `retain`/`release`
+/// calls, so those entries are included whenever a function has a managed
+/// local even if the MIR never calls them directly. Closures and cells are
+/// allocated on the runtime heap along with their drop and trace functions,
+/// as are struct and enum records, whose hash and equality functions hash
+/// and compare string fields through the runtime. Failed bounds checks,
+/// and draws from empty random ranges, format their message through the
+/// runtime before panicking like `panic` calls.
+pub(crate) fn runtime_dependencies(module: &MirModule) -> Vec<(RuntimeFunctionId, Span)> {
+    let mut seen = HashSet::new();
+    let mut dependencies = Vec::new();
+
+    for function in &module.functions {
+        for block in &function.blocks {
+            for stmt in &block.stmts {
+                let ids: &[RuntimeFunctionId] = match stmt {
+                    MirStmt::StoreIndex { .. } => &[RUNTIME_INDEX_OUT_OF_BOUNDS, RUNTIME_PANIC],
+                    MirStmt::Assign { dst, rhs, .. } => match rhs {
+                        MirRvalue::Call {
+                            callee: MirCallee::Runtime(RUNTIME_RANDOM_INT_RANGE),
+                            ..
+                        } => &[RUNTIME_RANDOM_INT_RANGE, RUNTIME_RANDOM_EMPTY_RANGE, RUNTIME_PANIC],
+                        MirRvalue::Call { callee: MirCallee::Runtime(id), .. } => &[*id],
+                        MirRvalue::MakeClosure { .. }
+                        | MirRvalue::MakeCell(_)
+                        | MirRvalue::MakeRecord(_) => {
+                            &[RUNTIME_ALLOC, RUNTIME_SET_DROP, RUNTIME_SET_TRACE]
+                        }
+                        MirRvalue::Intrinsic { intrinsic: Intrinsic::MapNew, .. }
+                            if matches!(
+                                &function.locals[dst.local.to_usize()].ty,
+                                MirTy::Map { key, .. } if matches!(**key, MirTy::Record { .. })
+                            ) =>
+                        {
+                            &[RUNTIME_MAP_NEW_RECORDS, RUNTIME_STR_HASH, RUNTIME_STR_CMP]
+                        }
+                        MirRvalue::Binary { lhs, .. }
+                            if matches!(function.operand_ty(lhs), Some(MirTy::Record { .. })) =>
+                        {
+                            &[RUNTIME_STR_HASH, RUNTIME_STR_CMP]
+                        }
+                        MirRvalue::Intrinsic { intrinsic, .. } => intrinsic_runtimes(*intrinsic),
+                        MirRvalue::Index { .. } => &[RUNTIME_INDEX_OUT_OF_BOUNDS, RUNTIME_PANIC],
+                        MirRvalue::Slice { .. } => &[RUNTIME_SLICE_OUT_OF_BOUNDS, RUNTIME_PANIC],
+                        _ => continue,
+                    },
+                    _ => continue,
+                };
+                let span = stmt.span();
+
+                for id in ids {
+                    if seen.insert(*id) {
+                        dependencies.push((*id, span));
+                    }
+                }
+            }
+            if let MirTerminator::Panic { span, .. } = &block.terminator
+                && seen.insert(RUNTIME_PANIC)
+            {
+                dependencies.push((RUNTIME_PANIC, *span));
+            }
+        }
+
+        if function.locals.iter().any(|local| local.ty.is_managed()) {
+            for id in [RUNTIME_RETAIN, RUNTIME_RELEASE] {
+                if seen.insert(id) {
+                    dependencies.push((id, function.span));
+                }
+            }
+        }
+    }
+
+    dependencies
+}
+
+/// Returns the runtime functions an intrinsic expands to calls of.
+fn intrinsic_runtimes(intrinsic: Intrinsic) -> &'static [RuntimeFunctionId] {
+    match intrinsic {
+        Intrinsic::VecNew => &[RUNTIME_VEC_NEW],
+        Intrinsic::VecPush => &[RUNTIME_VEC_PUSH],
+        Intrinsic::VecPop => &[RUNTIME_VEC_POP],
+        Intrinsic::Len => &[],
+        Intrinsic::MapNew => &[RUNTIME_MAP_NEW],
+        Intrinsic::MapInsert | Intrinsic::SetInsert => &[RUNTIME_MAP_INSERT],
+        Intrinsic::MapGet | Intrinsic::MapContains => &[RUNTIME_MAP_GET],
+        Intrinsic::MapRemove | Intrinsic::SetRemove => &[RUNTIME_MAP_REMOVE],
+        Intrinsic::MapKeys => &[RUNTIME_MAP_KEYS],
+        Intrinsic::MapValues => &[RUNTIME_MAP_VALUES],
+        Intrinsic::IntToString => &[RUNTIME_INT_TO_STRING],
+        Intrinsic::StrCmp => &[RUNTIME_STR_CMP],
+        Intrinsic::Shuffle => &[RUNTIME_RANDOM_SHUFFLE],
+        Intrinsic::Sqrt
+        | Intrinsic::Abs
+        | Intrinsic::Min
+        | Intrinsic::Max
+        | Intrinsic::Floor
+        | Intrinsic::Ceil
+        | Intrinsic::Trunc
+        | Intrinsic::CheckedAdd
+        | Intrinsic::CheckedSub
+        | Intrinsic::CheckedMul
+        | Intrinsic::WrappingAdd
+        | Intrinsic::WrappingSub
+        | Intrinsic::WrappingMul
+        | Intrinsic::SaturatingAdd
+        | Intrinsic::SaturatingSub
+        | Intrinsic::MulConst(_)
+        | Intrinsic::DivConst(_)
+        | Intrinsic::NanosToSecs
+        | Intrinsic::NumberCast => &[],
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use runec_abi::RUNTIME_PRINT;
+    use runec_abi::{RUNTIME_PRINT, RUNTIME_RELEASE, RUNTIME_RETAIN};
     use runec_builtins::TypeBits;
     use runec_mir::{
         MirBlock, MirCallee, MirFunction, MirModule, MirOperand, MirPlace, MirRvalue, MirStmt,
@@ -283,14 +425,37 @@ mod tests {
             .lower_module(&module)
             .expect("runtime calls should have declarations");
 
-        assert_eq!(artifact.runtime_functions.len(), 1);
-        assert_eq!(artifact.runtime_functions[0].id, RUNTIME_PRINT);
-        assert_eq!(artifact.runtime_functions[0].symbol, "__runeway_print");
-        assert_eq!(
-            artifact.runtime_functions[0].signature.params,
-            [AbiType::Pointer, AbiType::Usize]
+        // Strings are reference counted, so the function also releases its
+        // local on return.
+        let ids: Vec<_> = artifact.runtime_functions.iter().map(|decl| decl.id).collect();
+        assert_eq!(ids, [RUNTIME_PRINT, RUNTIME_RETAIN, RUNTIME_RELEASE]);
+        let print = &artifact.runtime_functions[0];
+        assert_eq!(print.symbol, "__runeway_print");
+        assert_eq!(print.signature.params, [AbiType::Pointer, AbiType::Usize]);
+        assert!(print.signature.returns.is_empty());
+    }
+
+    #[test]
+    fn managed_locals_require_reference_counting_entries() {
+        let mut module = MirModule::new();
+        let mut function = MirFunction::new(
+            runec_hir::ids::HirId::from_usize(0),
+            "keep",
+            MirTy::Unit,
+            span(0, 20),
+            span(8, 8),
         );
-        assert!(artifact.runtime_functions[0].signature.returns.is_empty());
+        let value = function.push_local(Some("value"), MirTy::Managed, span(10, 15));
+        function.params = Box::new([value]);
+        module.push_function(function);
+
+        let artifact = CraneliftLowerer::new(CodegenOptions::jit())
+            .lower_module(&module)
+            .expect("managed parameters should have a supported ABI shape");
+
+        assert_eq!(artifact.functions[0].signature.params, [AbiType::Pointer]);
+        let runtime_ids: Vec<_> = artifact.runtime_functions.iter().map(|f| f.id).collect();
+        assert_eq!(runtime_ids, [RUNTIME_RETAIN, RUNTIME_RELEASE]);
     }
 
     #[test]
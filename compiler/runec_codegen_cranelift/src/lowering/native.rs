@@ -1,20 +1,72 @@
 use std::collections::HashMap;
 
-use cranelift_codegen::ir::{AbiParam, InstBuilder, Signature, UserFuncName, Value, types};
+use cranelift_codegen::Context;
+use cranelift_codegen::ir::Endianness;
+use cranelift_codegen::ir::condcodes::{FloatCC, IntCC};
+use cranelift_codegen::ir::{
+    AbiParam, FuncRef, InstBuilder, MemFlags, Signature, StackSlotData, StackSlotKind, TrapCode,
+    Type, UserFuncName, Value, types,
+};
+use cranelift_codegen::isa::unwind::UnwindInfo;
 use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext, Variable};
 use cranelift_module::{DataDescription, DataId, FuncId, Linkage, Module};
-use runec_abi::{RuntimeFunctionDecl, RuntimeFunctionId, runtime_function};
-use runec_hir::ids::HirId;
+use runec_abi::{
+    ELEM_MANAGED, ELEM_PLAIN, HEADER_WORDS, IMMORTAL_REFCOUNT, MAP_KEY_BYTES, MAP_KEY_STR,
+    RUNTIME_ALLOC, RUNTIME_INDEX_OUT_OF_BOUNDS, RUNTIME_INT_TO_STRING, RUNTIME_MAP_GET,
+    RUNTIME_MAP_INSERT, RUNTIME_MAP_KEYS, RUNTIME_MAP_NEW, RUNTIME_MAP_NEW_RECORDS,
+    RUNTIME_MAP_REMOVE, RUNTIME_MAP_VALUES, RUNTIME_PANIC, RUNTIME_RANDOM_EMPTY_RANGE,
+    RUNTIME_RANDOM_INT_RANGE, RUNTIME_RANDOM_SHUFFLE, RUNTIME_RELEASE, RUNTIME_RETAIN,
+    RUNTIME_SET_DROP, RUNTIME_SET_TRACE, RUNTIME_SLICE_OUT_OF_BOUNDS, RUNTIME_STR_CMP,
+    RUNTIME_STR_HASH, RUNTIME_UNCAUGHT_ERROR, RUNTIME_VEC_NEW, RUNTIME_VEC_POP, RUNTIME_VEC_PUSH,
+    RuntimeFunctionDecl, RuntimeFunctionId, runtime_function,
+};
+use runec_builtins::{Intrinsic, TypeBits};
 use runec_mir::{
-    MirCallee, MirConstant, MirFunction, MirFunctionId, MirImmediate, MirModule, MirOperand,
-    MirRvalue, MirStmt, MirTerminator, MirTy,
+    MirBinOp, MirCallee, MirConstant, MirExtern, MirExternId, MirFunction, MirFunctionId,
+    MirImmediate, MirIntTy, MirModule, MirOperand, MirPlace, MirRvalue, MirStmt, MirTerminator,
+    MirTy,
 };
+use runec_source::source_map::{SourceId, SourceMap};
+use runec_source::span::Span;
 
-use crate::diagnostics::{CodegenResult, backend, error, messages};
+use crate::diagnostics::{CodegenResult, backend, error, messages, unsupported_type};
 
 pub struct CompiledModule {
     pub entry: MirFunctionId,
     pub entry_func: FuncId,
+    /// Unwind information of every defined function, collected when the
+    /// module is compiled for the JIT.
+    pub unwind: Vec<(FuncId, UnwindInfo)>,
+}
+
+/// State shared by the functions of a module while they are compiled.
+struct ModuleCtx {
+    constants: Vec<Constant>,
+    /// Location reported by each `panic` call and bounds check, by the span
+    /// of the call or indexing expression.
+    panics: HashMap<Span, PanicLocation>,
+    /// Unwind information of the functions defined so far, if collected.
+    unwind: Option<Vec<(FuncId, UnwindInfo)>>,
+    records: RecordGlue,
+}
+
+/// Data of a MIR constant. String literals are preceded by an immortal heap
+/// header, so that they can be shared like any other string; `offset` skips
+/// over it to the bytes.
+#[derive(Clone, Copy)]
+struct Constant {
+    data: DataId,
+    offset: i64,
+    len: usize,
+}
+
+/// Source location passed to the runtime when a `panic` call is reached, a
+/// bounds check fails, or an error escapes `main`.
+struct PanicLocation {
+    /// Data holding the path of the source file, along with its length.
+    file: (DataId, usize),
+    line: usize,
+    col: usize,
 }
 
 /// Backend-neutral declaration and Cranelift IR generation used by
@@ -22,70 +74,100 @@ pub struct CompiledModule {
 pub(super) fn compile_module<M: Module>(
     module: &mut M,
     mir: &MirModule<'_>,
+    (source_map, collect_unwind): (&SourceMap, bool),
     diagnostic_span: runec_source::span::Span,
 ) -> CodegenResult<CompiledModule> {
     let entry = mir.entry.ok_or_else(|| error(messages::MISSING_ENTRY, &[], diagnostic_span))?;
-    let mut functions = HashMap::<HirId, FuncId>::new();
+    let mut functions = HashMap::<MirCallee, FuncId>::new();
     for function in &mir.functions {
         let id = module
-            .declare_function(function.name, Linkage::Export, &signature_for(module, function)?)
+            .declare_function(
+                &mir.symbol(function),
+                Linkage::Export,
+                &signature_for(module, function)?,
+            )
+            .map_err(|error| backend(error, function.span))?;
+        functions.insert(function.callee(), id);
+    }
+    // Extern symbols are resolved by the linker in AOT builds and through
+    // `dlsym` by the JIT.
+    for (index, function) in mir.externs.iter().enumerate() {
+        let id = module
+            .declare_function(function.name, Linkage::Import, &extern_signature(module, function)?)
             .map_err(|error| backend(error, function.span))?;
-        functions.insert(function.hir_id, id);
+        functions.insert(MirCallee::Extern(MirExternId::from_usize(index)), id);
     }
 
     let mut runtimes = HashMap::new();
-    for function in &mir.functions {
-        for block in &function.blocks {
-            for stmt in &block.stmts {
-                let MirStmt::Assign {
-                    rhs: MirRvalue::Call { callee: MirCallee::Runtime(id), .. },
-                    span,
-                    ..
-                } = stmt
-                else {
-                    continue;
-                };
-                if runtimes.contains_key(id) {
-                    continue;
-                }
-                let decl = runtime_function(*id).ok_or_else(|| {
-                    let function = format!("{id:?}");
-                    error(messages::UNSUPPORTED_RUNTIME_FUNCTION, &[("function", &function)], *span)
-                })?;
-                let func = module
-                    .declare_function(
-                        decl.symbol,
-                        Linkage::Import,
-                        &runtime_signature(module, decl),
-                    )
-                    .map_err(|error| backend(error, *span))?;
-                runtimes.insert(*id, func);
-            }
-        }
+    for (id, span) in super::runtime_dependencies(mir) {
+        let decl = runtime_function(id).ok_or_else(|| {
+            let function = format!("{id:?}");
+            error(messages::UNSUPPORTED_RUNTIME_FUNCTION, &[("function", &function)], span)
+        })?;
+        let func = module
+            .declare_function(decl.symbol, Linkage::Import, &runtime_signature(module, decl))
+            .map_err(|error| backend(error, span))?;
+        runtimes.insert(id, func);
     }
 
-    let constants = declare_constants(module, mir, diagnostic_span)?;
+    let mut ctx = ModuleCtx {
+        constants: declare_constants(module, mir, diagnostic_span)?,
+        panics: declare_panic_locations(module, mir, source_map, diagnostic_span)?,
+        unwind: collect_unwind.then(Vec::new),
+        records: RecordGlue::default(),
+    };
+    let mut drops = DropGlue::default();
     for function in &mir.functions {
         compile_function(
             module,
             function,
-            functions[&function.hir_id],
+            functions[&function.callee()],
             &functions,
             &runtimes,
-            &constants,
+            &mut ctx,
+            &mut drops,
         )?;
     }
-    Ok(CompiledModule { entry, entry_func: functions[&mir.function(entry).hir_id] })
+    drops.define(module, &runtimes, &mut ctx.unwind, diagnostic_span)?;
+    ctx.records.define(module, &runtimes, &mut ctx.unwind, diagnostic_span)?;
+    Ok(CompiledModule {
+        entry,
+        entry_func: functions[&mir.function(entry).callee()],
+        unwind: ctx.unwind.unwrap_or_default(),
+    })
+}
+
+/// Defines `id` from the function in `context`, recording its unwind
+/// information in `unwind` when it is collected.
+fn define_function<M: Module>(
+    module: &mut M,
+    id: FuncId,
+    context: &mut Context,
+    unwind: &mut Option<Vec<(FuncId, UnwindInfo)>>,
+    span: Span,
+) -> CodegenResult<()> {
+    module.define_function(id, context).map_err(|error| backend(error, span))?;
+    if let Some(unwind) = unwind
+        && let Some(code) = context.compiled_code()
+        && let Some(info) =
+            code.create_unwind_info(module.isa()).map_err(|error| backend(error, span))?
+    {
+        unwind.push((id, info));
+    }
+    module.clear_context(context);
+    Ok(())
 }
 
 fn compile_function<M: Module>(
     module: &mut M,
     function: &MirFunction<'_>,
     id: FuncId,
-    functions: &HashMap<HirId, FuncId>,
+    functions: &HashMap<MirCallee, FuncId>,
     runtimes: &HashMap<RuntimeFunctionId, FuncId>,
-    constants: &[(DataId, usize)],
+    ctx: &mut ModuleCtx,
+    drops: &mut DropGlue,
 ) -> CodegenResult<()> {
+    let constants = &ctx.constants;
     let mut context = module.make_context();
     context.func.signature = signature_for(module, function)?;
     context.func.name = UserFuncName::user(0, id.as_u32());
@@ -99,11 +181,12 @@ fn compile_function<M: Module>(
     let mut locals = Vec::with_capacity(function.locals.len());
     for local in &function.locals {
         let mut local_vars = Vec::new();
-        for ty in clif_types(module, local.ty, local.span)? {
+        for ty in clif_types(module, &local.ty, local.span)? {
             local_vars.push(builder.declare_var(ty));
         }
         locals.push(local_vars);
     }
+    let rc = RefCounting::declare(module, &mut builder, function, runtimes);
     let params = builder.block_params(entry).to_vec();
     let mut offset = 0;
     for param in function.params.iter() {
@@ -111,77 +194,1925 @@ fn compile_function<M: Module>(
             builder.def_var(*var, params[offset]);
             offset += 1;
         }
+        // Parameters are owned like any other local and released on return.
+        if let Some(rc) = &rc
+            && function.locals[param.to_usize()].ty.is_managed()
+        {
+            let value = builder.use_var(locals[param.to_usize()][0]);
+            rc.retain(&mut builder, value);
+        }
     }
 
-    let block = function
-        .blocks
-        .get(function.entry.to_usize())
-        .ok_or_else(|| error(messages::MISSING_ENTRY_BLOCK, &[], function.span))?;
-    for stmt in &block.stmts {
-        let MirStmt::Assign { dst, rhs, span } = stmt;
-        let values = match rhs {
-            MirRvalue::Use(operand) => {
-                lower_operand(&mut builder, module, operand, &locals, constants, *span)?
-            }
-            MirRvalue::Call { callee, args } => {
-                let func_id = match callee {
-                    MirCallee::Runtime(id) => *runtimes.get(id).ok_or_else(|| {
-                        let function = format!("{id:?}");
-                        error(
-                            messages::UNSUPPORTED_RUNTIME_FUNCTION,
-                            &[("function", &function)],
-                            *span,
-                        )
-                    })?,
-                    MirCallee::Function(id) => *functions.get(id).ok_or_else(|| {
-                        let function = format!("{id:?}");
-                        error(messages::UNKNOWN_FUNCTION, &[("function", &function)], *span)
-                    })?,
-                };
-                let func_ref = module.declare_func_in_func(func_id, builder.func);
-                let mut call_args = Vec::new();
-                for arg in args.iter() {
-                    call_args.extend(lower_operand(
+    if function.blocks.get(function.entry.to_usize()).is_none() {
+        return Err(error(messages::MISSING_ENTRY_BLOCK, &[], function.span));
+    }
+    let blocks = function.blocks.iter().map(|_| builder.create_block()).collect::<Vec<_>>();
+    builder.ins().jump(blocks[function.entry.to_usize()], &[]);
+
+    for (block, clif_block) in function.blocks.iter().zip(&blocks) {
+        builder.switch_to_block(*clif_block);
+        for stmt in &block.stmts {
+            let (dst, rhs, span) = match stmt {
+                MirStmt::Assign { dst, rhs, span } => (dst, rhs, span),
+                MirStmt::Drop { operand, span } => {
+                    let values =
+                        lower_operand(&mut builder, module, operand, &locals, constants, *span)?;
+                    if let Some(rc) = &rc
+                        && let Some(place) = operand.place()
+                        && function.locals[place.local.to_usize()].ty.is_managed()
+                    {
+                        rc.release(&mut builder, values[0]);
+                        rc.forget(&mut builder, module, &locals[place.local.to_usize()]);
+                    }
+                    continue;
+                }
+                MirStmt::StoreCell { cell, value, span } => {
+                    let Some(MirTy::Cell(inner)) = function.operand_ty(cell) else {
+                        return Err(error(messages::UNTYPED_OPERAND, &[], *span));
+                    };
+                    let cell =
+                        lower_operand(&mut builder, module, cell, &locals, constants, *span)?;
+                    let values =
+                        lower_operand(&mut builder, module, value, &locals, constants, *span)?;
+                    let fields = std::slice::from_ref(inner.as_ref());
+                    let layout = RecordLayout::new(module, fields, *span)?;
+                    let rc = rc.as_ref().filter(|_| inner.is_managed());
+                    // The previous value is released only once it is replaced.
+                    let previous = rc.map(|_| {
+                        let (ty, offset) = layout.fields[0][0];
+                        builder.ins().load(ty, MemFlags::new(), cell[0], offset as i32)
+                    });
+                    layout.store(&mut builder, cell[0], 0, &values);
+                    if let (Some(rc), Some(previous)) = (rc, previous) {
+                        rc.take(&mut builder, module, function, &locals, value, &values);
+                        rc.release(&mut builder, previous);
+                    }
+                    continue;
+                }
+                // Ranges step in place: the current start is yielded and the
+                // range is advanced without branching.
+                MirStmt::Next { iter, dst, span } => {
+                    let (Some(MirTy::Range(int)), Some(place)) =
+                        (function.operand_ty(iter), iter.place())
+                    else {
+                        let ty = format!("{:?}", function.operand_ty(iter));
+                        return Err(error(messages::UNSUPPORTED_TYPE, &[("type", &ty)], *span));
+                    };
+                    let vars = &locals[place.local.to_usize()];
+                    let [start, end, inclusive] =
+                        [vars[0], vars[1], vars[2]].map(|var| builder.use_var(var));
+                    let lt =
+                        if int.signed { IntCC::SignedLessThan } else { IntCC::UnsignedLessThan };
+                    let before_end = builder.ins().icmp(lt, start, end);
+                    let at_end = builder.ins().icmp(IntCC::Equal, start, end);
+                    let last = builder.ins().band(inclusive, at_end);
+                    let has_item = builder.ins().bor(before_end, last);
+                    let next = builder.ins().iadd_imm(start, 1);
+                    let next = builder.ins().select(before_end, next, start);
+                    let zero = builder.ins().iconst(types::I8, 0);
+                    let inclusive = builder.ins().select(before_end, inclusive, zero);
+                    builder.def_var(vars[0], next);
+                    builder.def_var(vars[2], inclusive);
+                    for (var, value) in locals[dst.local.to_usize()].iter().zip([has_item, start]) {
+                        builder.def_var(*var, value);
+                    }
+                    continue;
+                }
+                MirStmt::StoreIndex { base, index, value, span } => {
+                    let base_ty = function.operand_ty(base);
+                    let index_ty = function.operand_ty(index);
+                    let base =
+                        lower_operand(&mut builder, module, base, &locals, constants, *span)?;
+                    let index =
+                        lower_operand(&mut builder, module, index, &locals, constants, *span)?;
+                    let values =
+                        lower_operand(&mut builder, module, value, &locals, constants, *span)?;
+                    let (layout, slot) = lower_element_slot(
+                        &mut builder,
+                        module,
+                        runtimes,
+                        (base_ty.as_ref(), &base),
+                        (index_ty.as_ref(), &index),
+                        (*span, &ctx.panics[span]),
+                    )?;
+                    let managed = function.operand_ty(value).is_some_and(|ty| ty.is_managed());
+                    let rc = rc.as_ref().filter(|_| managed);
+                    // Like cells, elements release what they held once replaced.
+                    let previous = rc.map(|_| {
+                        let (ty, offset) = layout.fields[0][0];
+                        builder.ins().load(ty, MemFlags::new(), slot, offset as i32)
+                    });
+                    layout.store(&mut builder, slot, 0, &values);
+                    if let (Some(rc), Some(previous)) = (rc, previous) {
+                        rc.take(&mut builder, module, function, &locals, value, &values);
+                        rc.release(&mut builder, previous);
+                    }
+                    continue;
+                }
+                // Only values that are not reference counted are stored
+                // through pointers, so nothing is retained or released.
+                MirStmt::Store { pointer, value, span } => {
+                    let Some(ty) = function.operand_ty(value) else {
+                        return Err(error(messages::UNTYPED_OPERAND, &[], *span));
+                    };
+                    let pointer =
+                        lower_operand(&mut builder, module, pointer, &locals, constants, *span)?;
+                    let Some(pointer) = pointer.first().copied() else {
+                        return Err(error(messages::ABI_ARITY_MISMATCH, &[], *span));
+                    };
+                    let values =
+                        lower_operand(&mut builder, module, value, &locals, constants, *span)?;
+                    let mut offset = 0u32;
+                    for (ty, value) in clif_types(module, &ty, *span)?.into_iter().zip(values) {
+                        offset = offset.next_multiple_of(ty.bytes());
+                        builder.ins().store(MemFlags::new(), value, pointer, offset as i32);
+                        offset += ty.bytes();
+                    }
+                    continue;
+                }
+            };
+            let dst_ty = &function.locals[dst.local.to_usize()].ty;
+            let values = match rhs {
+                // `null` is all zeros in both nullable layouts.
+                MirRvalue::Use(MirOperand::Immediate(MirImmediate::Null)) => {
+                    clif_types(module, dst_ty, *span)?
+                        .into_iter()
+                        .map(|ty| zero_value(&mut builder, ty))
+                        .collect()
+                }
+                MirRvalue::Use(operand) => {
+                    lower_operand(&mut builder, module, operand, &locals, constants, *span)?
+                }
+                MirRvalue::WrapNullable(operand) => {
+                    let mut values =
+                        lower_operand(&mut builder, module, operand, &locals, constants, *span)?;
+                    if let MirTy::Nullable(inner) = dst_ty
+                        && !inner.is_pointer_like()
+                    {
+                        values.insert(0, builder.ins().iconst(types::I8, 1));
+                    }
+                    values
+                }
+                MirRvalue::UnwrapNullable(operand) => {
+                    let mut values =
+                        lower_operand(&mut builder, module, operand, &locals, constants, *span)?;
+                    if !dst_ty.is_pointer_like() {
+                        values.remove(0);
+                    }
+                    values
+                }
+                // The unused side of a fallible value is zero-filled.
+                MirRvalue::WrapOk(operand) | MirRvalue::WrapErr(operand) => {
+                    let MirTy::Fallible { ok, err } = dst_ty else {
+                        return Err(error(messages::ABI_ARITY_MISMATCH, &[], *span));
+                    };
+                    let is_err = matches!(rhs, MirRvalue::WrapErr(_));
+                    let value =
+                        lower_operand(&mut builder, module, operand, &locals, constants, *span)?;
+                    // The result owns the wrapped value until it is unwrapped.
+                    if let Some(rc) = &rc {
+                        rc.take(&mut builder, module, function, &locals, operand, &value);
+                    }
+                    let mut values = vec![builder.ins().iconst(types::I8, i64::from(is_err))];
+                    if is_err {
+                        for ty in clif_types(module, ok, *span)? {
+                            values.push(zero_value(&mut builder, ty));
+                        }
+                        values.extend(value);
+                    } else {
+                        values.extend(value);
+                        for ty in clif_types(module, err, *span)? {
+                            values.push(zero_value(&mut builder, ty));
+                        }
+                    }
+                    values
+                }
+                MirRvalue::UnwrapOk(operand) => {
+                    let values =
+                        lower_operand(&mut builder, module, operand, &locals, constants, *span)?;
+                    let len = clif_types(module, dst_ty, *span)?.len();
+                    values.into_iter().skip(1).take(len).collect()
+                }
+                MirRvalue::UnwrapErr(operand) => {
+                    let values =
+                        lower_operand(&mut builder, module, operand, &locals, constants, *span)?;
+                    let len = clif_types(module, dst_ty, *span)?.len();
+                    values[values.len().saturating_sub(len)..].to_vec()
+                }
+                MirRvalue::IsErr(operand) => {
+                    let values =
+                        lower_operand(&mut builder, module, operand, &locals, constants, *span)?;
+                    values.into_iter().take(1).collect()
+                }
+                // Values spanning several Cranelift values are read in order,
+                // each at its natural alignment.
+                MirRvalue::Load(operand) => {
+                    let pointer =
+                        lower_operand(&mut builder, module, operand, &locals, constants, *span)?;
+                    let Some(pointer) = pointer.first().copied() else {
+                        return Err(error(messages::ABI_ARITY_MISMATCH, &[], *span));
+                    };
+                    let mut offset = 0u32;
+                    let mut values = Vec::new();
+                    for ty in clif_types(module, dst_ty, *span)? {
+                        offset = offset.next_multiple_of(ty.bytes());
+                        values.push(builder.ins().load(
+                            ty,
+                            MemFlags::new(),
+                            pointer,
+                            offset as i32,
+                        ));
+                        offset += ty.bytes();
+                    }
+                    values
+                }
+                MirRvalue::MakeRange { start, end, inclusive } => {
+                    let mut values =
+                        lower_operand(&mut builder, module, start, &locals, constants, *span)?;
+                    values.extend(lower_operand(
                         &mut builder,
                         module,
-                        arg,
+                        end,
                         &locals,
                         constants,
                         *span,
                     )?);
+                    values.push(builder.ins().iconst(types::I8, i64::from(*inclusive)));
+                    values
+                }
+                // Record keys are hashed and compared by generated functions,
+                // which the map is created with.
+                MirRvalue::Intrinsic { intrinsic: Intrinsic::MapNew, .. }
+                    if let MirTy::Map { key, value } = dst_ty
+                        && matches!(**key, MirTy::Record { .. }) =>
+                {
+                    let (hash, eq) = ctx.records.get(module, key, *span)?;
+                    let pointer_ty = module.target_config().pointer_type();
+                    let [hash, eq] = [hash, eq].map(|id| {
+                        let func = module.declare_func_in_func(id, builder.func);
+                        builder.ins().func_addr(pointer_ty, func)
+                    });
+                    let layout = RecordLayout::new(module, std::slice::from_ref(&**value), *span)?;
+                    let [size, align, kind] = [
+                        i64::from(layout.stride()),
+                        i64::from(layout.align),
+                        elem_kind(value) as i64,
+                    ]
+                    .map(|arg| builder.ins().iconst(pointer_ty, arg));
+                    let new = module
+                        .declare_func_in_func(runtimes[&RUNTIME_MAP_NEW_RECORDS], builder.func);
+                    let call = builder.ins().call(new, &[hash, eq, size, align, kind]);
+                    builder.inst_results(call).to_vec()
+                }
+                MirRvalue::Intrinsic { intrinsic, args } => {
+                    let mut arg_values = Vec::with_capacity(args.len());
+                    for arg in args.iter() {
+                        arg_values.push(lower_operand(
+                            &mut builder,
+                            module,
+                            arg,
+                            &locals,
+                            constants,
+                            *span,
+                        )?);
+                    }
+                    // Containers take their own reference to the values stored
+                    // into them. It is taken before the call, since maps
+                    // release a key they already hold right away.
+                    let stored = match intrinsic {
+                        Intrinsic::VecPush | Intrinsic::SetInsert => 1..2,
+                        Intrinsic::MapInsert => 1..3,
+                        _ => 0..0,
+                    };
+                    if let Some(rc) = &rc {
+                        for index in stored {
+                            if let (Some(arg), Some(values)) =
+                                (args.get(index), arg_values.get(index))
+                            {
+                                rc.take(&mut builder, module, function, &locals, arg, values);
+                            }
+                        }
+                    }
+                    let arg_tys = args.iter().map(|arg| function.operand_ty(arg)).collect();
+                    let values = lower_intrinsic(
+                        &mut builder,
+                        module,
+                        runtimes,
+                        *intrinsic,
+                        (arg_tys, arg_values),
+                        dst_ty,
+                        *span,
+                    )?;
+                    if let Some(rc) = &rc {
+                        rc.release_moved(&mut builder, module, function, &locals, args.iter());
+                    }
+                    values
+                }
+                MirRvalue::Index { base, index } => {
+                    let base_ty = function.operand_ty(base);
+                    let index_ty = function.operand_ty(index);
+                    let base =
+                        lower_operand(&mut builder, module, base, &locals, constants, *span)?;
+                    let index =
+                        lower_operand(&mut builder, module, index, &locals, constants, *span)?;
+                    let (layout, slot) = lower_element_slot(
+                        &mut builder,
+                        module,
+                        runtimes,
+                        (base_ty.as_ref(), &base),
+                        (index_ty.as_ref(), &index),
+                        (*span, &ctx.panics[span]),
+                    )?;
+                    layout.load(&mut builder, slot, 0)
+                }
+                MirRvalue::Slice { base, range } => {
+                    let base_ty = function.operand_ty(base);
+                    let range_ty = function.operand_ty(range);
+                    let base =
+                        lower_operand(&mut builder, module, base, &locals, constants, *span)?;
+                    let range =
+                        lower_operand(&mut builder, module, range, &locals, constants, *span)?;
+                    lower_slice(
+                        &mut builder,
+                        module,
+                        runtimes,
+                        (base_ty.as_ref(), &base),
+                        (range_ty.as_ref(), &range),
+                        (*span, &ctx.panics[span]),
+                    )?
+                }
+                MirRvalue::Binary { op: MirBinOp::Add, lhs, rhs } => {
+                    let lhs = lower_operand(&mut builder, module, lhs, &locals, constants, *span)?;
+                    let rhs = lower_operand(&mut builder, module, rhs, &locals, constants, *span)?;
+                    let (Some(lhs), Some(rhs)) = (lhs.first(), rhs.first()) else {
+                        return Err(error(messages::ABI_ARITY_MISMATCH, &[], *span));
+                    };
+                    vec![builder.ins().iadd(*lhs, *rhs)]
+                }
+                MirRvalue::Binary { op, lhs, rhs }
+                    if let Some(ty @ MirTy::Record { .. }) = function.operand_ty(lhs) =>
+                {
+                    let (_, eq) = ctx.records.get(module, &ty, *span)?;
+                    let eq = module.declare_func_in_func(eq, builder.func);
+                    let lhs = lower_operand(&mut builder, module, lhs, &locals, constants, *span)?;
+                    let rhs = lower_operand(&mut builder, module, rhs, &locals, constants, *span)?;
+                    let call = builder.ins().call(eq, &[lhs[0], rhs[0]]);
+                    let equal = builder.inst_results(call)[0];
+                    match op {
+                        MirBinOp::Ne => vec![builder.ins().bxor_imm(equal, 1)],
+                        _ => vec![equal],
+                    }
+                }
+                MirRvalue::Binary { op, lhs, rhs } => {
+                    let signed = matches!(
+                        function.operand_ty(lhs),
+                        Some(MirTy::Int(MirIntTy { signed: true, .. }))
+                    );
+                    vec![lower_comparison(
+                        &mut builder,
+                        module,
+                        (*op, signed),
+                        (lhs, rhs),
+                        &locals,
+                        constants,
+                        *span,
+                    )?]
+                }
+                MirRvalue::MakeClosure { function: callee, captures } => {
+                    let code = *functions.get(callee).ok_or_else(|| {
+                        let function = format!("{callee:?}");
+                        error(messages::UNKNOWN_FUNCTION, &[("function", &function)], *span)
+                    })?;
+                    let code = module.declare_func_in_func(code, builder.func);
+                    let pointer_ty = module.target_config().pointer_type();
+                    let code = builder.ins().func_addr(pointer_ty, code);
+                    let mut fields = vec![MirTy::Pointer];
+                    let mut values = vec![vec![code]];
+                    for capture in captures.iter() {
+                        let ty = function
+                            .operand_ty(capture)
+                            .ok_or_else(|| error(messages::UNTYPED_OPERAND, &[], *span))?;
+                        let capture_values = lower_operand(
+                            &mut builder,
+                            module,
+                            capture,
+                            &locals,
+                            constants,
+                            *span,
+                        )?;
+                        if let Some(rc) = &rc {
+                            rc.take(
+                                &mut builder,
+                                module,
+                                function,
+                                &locals,
+                                capture,
+                                &capture_values,
+                            );
+                        }
+                        fields.push(ty);
+                        values.push(capture_values);
+                    }
+                    vec![alloc_record(
+                        &mut builder,
+                        module,
+                        (&fields, &values),
+                        runtimes,
+                        drops,
+                        *span,
+                    )?]
+                }
+                MirRvalue::MakeCell(operand) => {
+                    let ty = function
+                        .operand_ty(operand)
+                        .ok_or_else(|| error(messages::UNTYPED_OPERAND, &[], *span))?;
+                    let values =
+                        lower_operand(&mut builder, module, operand, &locals, constants, *span)?;
+                    if let Some(rc) = &rc {
+                        rc.take(&mut builder, module, function, &locals, operand, &values);
+                    }
+                    vec![alloc_record(
+                        &mut builder,
+                        module,
+                        (&[ty], &[values]),
+                        runtimes,
+                        drops,
+                        *span,
+                    )?]
+                }
+                // The zeros of the fields of a union that are not given are
+                // stored before the given one, so that reading a field wider
+                // than it sees no uninitialized bytes. Union fields are never
+                // managed, so the record needs no drop function.
+                MirRvalue::MakeRecord(operands) if let MirTy::Union { fields, .. } = dst_ty => {
+                    let layout = RecordLayout::overlapping(module, fields, *span)?;
+                    let record = alloc_uninit(&mut builder, module, &layout, runtimes);
+                    let (given, zeroed): (Vec<_>, Vec<_>) = fields
+                        .iter()
+                        .zip(operands.iter())
+                        .enumerate()
+                        .partition(|(_, (_, operand))| operand.is_some());
+                    for (index, (field, operand)) in zeroed.into_iter().chain(given) {
+                        let values = match operand {
+                            Some(operand) => lower_operand(
+                                &mut builder,
+                                module,
+                                operand,
+                                &locals,
+                                constants,
+                                *span,
+                            )?,
+                            None => clif_types(module, field, *span)?
+                                .into_iter()
+                                .map(|ty| zero_value(&mut builder, ty))
+                                .collect(),
+                        };
+                        layout.store(&mut builder, record, index, &values);
+                    }
+                    vec![record]
+                }
+                // Fields no operand is given for, such as those of the other
+                // variants of an enum, are zeroed.
+                MirRvalue::MakeRecord(operands) => {
+                    let MirTy::Record { fields, .. } = dst_ty else {
+                        return Err(unsupported_type(dst_ty, *span));
+                    };
+                    let mut values = Vec::with_capacity(fields.len());
+                    for (field, operand) in fields.iter().zip(operands.iter()) {
+                        let Some(operand) = operand else {
+                            let zeros = clif_types(module, field, *span)?;
+                            values.push(
+                                zeros.into_iter().map(|ty| zero_value(&mut builder, ty)).collect(),
+                            );
+                            continue;
+                        };
+                        let operand_values = lower_operand(
+                            &mut builder,
+                            module,
+                            operand,
+                            &locals,
+                            constants,
+                            *span,
+                        )?;
+                        if let Some(rc) = &rc {
+                            rc.take(
+                                &mut builder,
+                                module,
+                                function,
+                                &locals,
+                                operand,
+                                &operand_values,
+                            );
+                        }
+                        values.push(operand_values);
+                    }
+                    vec![alloc_record(
+                        &mut builder,
+                        module,
+                        (fields, &values),
+                        runtimes,
+                        drops,
+                        *span,
+                    )?]
+                }
+                // Records keep their own reference to managed fields, as do
+                // the environment and cells, so reading one out takes another.
+                MirRvalue::LoadField { record, index } => {
+                    let Some(record_ty) = function.operand_ty(record) else {
+                        return Err(error(messages::UNTYPED_OPERAND, &[], *span));
+                    };
+                    let record =
+                        lower_operand(&mut builder, module, record, &locals, constants, *span)?;
+                    let layout = RecordLayout::of(module, &record_ty, *span)?;
+                    let values = layout.load(&mut builder, record[0], *index);
+                    if let Some(rc) = &rc
+                        && dst_ty.is_managed()
+                    {
+                        rc.retain(&mut builder, values[0]);
+                    }
+                    values
+                }
+                // The environment and the cell keep their own reference to
+                // managed values, so reading one out takes another.
+                MirRvalue::Capture(index) => {
+                    let (Some(env), Some(fields)) = (function.params.first(), &function.env) else {
+                        return Err(error(messages::MISSING_CLOSURE_ENV, &[], *span));
+                    };
+                    let env = builder.use_var(locals[env.to_usize()][0]);
+                    let fields =
+                        std::iter::once(MirTy::Pointer).chain(fields.fields.iter().cloned());
+                    let layout = RecordLayout::new(module, &fields.collect::<Vec<_>>(), *span)?;
+                    let values = layout.load(&mut builder, env, index + 1);
+                    if let Some(rc) = &rc
+                        && dst_ty.is_managed()
+                    {
+                        rc.retain(&mut builder, values[0]);
+                    }
+                    values
                 }
-                let call = builder.ins().call(func_ref, &call_args);
-                builder.inst_results(call).to_vec()
+                MirRvalue::LoadCell(operand) => {
+                    let cell =
+                        lower_operand(&mut builder, module, operand, &locals, constants, *span)?;
+                    let layout = RecordLayout::new(module, std::slice::from_ref(dst_ty), *span)?;
+                    let values = layout.load(&mut builder, cell[0], 0);
+                    if let Some(rc) = &rc
+                        && dst_ty.is_managed()
+                    {
+                        rc.retain(&mut builder, values[0]);
+                    }
+                    values
+                }
+                // The code pointer is the first field of the environment.
+                MirRvalue::CallIndirect { callee, args } => {
+                    let env =
+                        lower_operand(&mut builder, module, callee, &locals, constants, *span)?;
+                    let pointer_ty = module.target_config().pointer_type();
+                    let code = builder.ins().load(pointer_ty, MemFlags::new(), env[0], 0);
+                    let mut call_args = env;
+                    for arg in args.iter() {
+                        call_args.extend(lower_operand(
+                            &mut builder,
+                            module,
+                            arg,
+                            &locals,
+                            constants,
+                            *span,
+                        )?);
+                    }
+                    let mut signature = module.make_signature();
+                    for arg in &call_args {
+                        signature.params.push(AbiParam::new(builder.func.dfg.value_type(*arg)));
+                    }
+                    for ty in clif_types(module, dst_ty, *span)? {
+                        signature.returns.push(AbiParam::new(ty));
+                    }
+                    let signature = builder.import_signature(signature);
+                    let call = builder.ins().call_indirect(signature, code, &call_args);
+                    let results = builder.inst_results(call).to_vec();
+                    if let Some(rc) = &rc {
+                        let operands = std::iter::once(callee).chain(args.iter());
+                        rc.release_moved(&mut builder, module, function, &locals, operands);
+                    }
+                    results
+                }
+                MirRvalue::Call { callee, args } => {
+                    let func_id = match callee {
+                        MirCallee::Runtime(id) => *runtimes.get(id).ok_or_else(|| {
+                            let function = format!("{id:?}");
+                            error(
+                                messages::UNSUPPORTED_RUNTIME_FUNCTION,
+                                &[("function", &function)],
+                                *span,
+                            )
+                        })?,
+                        MirCallee::Function(_)
+                        | MirCallee::Instance(_)
+                        | MirCallee::Extern(_)
+                        | MirCallee::Closure(_) => *functions.get(callee).ok_or_else(|| {
+                            let function = format!("{callee:?}");
+                            error(messages::UNKNOWN_FUNCTION, &[("function", &function)], *span)
+                        })?,
+                    };
+                    let func_ref = module.declare_func_in_func(func_id, builder.func);
+                    let mut call_args = Vec::new();
+                    for arg in args.iter() {
+                        call_args.extend(lower_operand(
+                            &mut builder,
+                            module,
+                            arg,
+                            &locals,
+                            constants,
+                            *span,
+                        )?);
+                    }
+                    // Drawing from an empty range panics like a failed
+                    // bounds check.
+                    if let (MirCallee::Runtime(RUNTIME_RANDOM_INT_RANGE), [lo, hi]) =
+                        (callee, call_args.as_slice())
+                    {
+                        let non_empty = builder.ins().icmp(IntCC::SignedLessThan, *lo, *hi);
+                        check_bounds(
+                            &mut builder,
+                            module,
+                            runtimes,
+                            non_empty,
+                            (RUNTIME_RANDOM_EMPTY_RANGE, &[*lo, *hi]),
+                            &ctx.panics[span],
+                        );
+                    }
+                    // Errors escaping `main` are reported where they were
+                    // thrown.
+                    if let MirCallee::Runtime(RUNTIME_UNCAUGHT_ERROR) = callee {
+                        push_location(&mut builder, module, &mut call_args, &ctx.panics[span]);
+                    }
+                    let call = builder.ins().call(func_ref, &call_args);
+                    let results = builder.inst_results(call).to_vec();
+                    if let Some(rc) = &rc {
+                        rc.release_moved(&mut builder, module, function, &locals, args.iter());
+                    }
+                    results
+                }
+            };
+            let vars = &locals[dst.local.to_usize()];
+            if vars.len() != values.len() {
+                return Err(error(messages::ABI_ARITY_MISMATCH, &[], *span));
+            }
+            if let Some(rc) = &rc
+                && function.locals[dst.local.to_usize()].ty.is_managed()
+            {
+                // A copy creates a new reference, as does reading an element a
+                // container keeps or slicing a vector; moves and call results
+                // hand over the one they already own.
+                match rhs {
+                    MirRvalue::Use(MirOperand::Copy(_))
+                    | MirRvalue::Index { .. }
+                    | MirRvalue::Slice { .. }
+                    | MirRvalue::Intrinsic { intrinsic: Intrinsic::MapGet, .. }
+                    | MirRvalue::WrapNullable(MirOperand::Copy(_))
+                    | MirRvalue::UnwrapNullable(MirOperand::Copy(_)) => {
+                        rc.retain(&mut builder, values[0])
+                    }
+                    MirRvalue::Use(MirOperand::Move(MirPlace { local }))
+                    | MirRvalue::WrapNullable(MirOperand::Move(MirPlace { local }))
+                    | MirRvalue::UnwrapNullable(MirOperand::Move(MirPlace { local })) => {
+                        rc.forget(&mut builder, module, &locals[local.to_usize()]);
+                    }
+                    _ => {}
+                }
+                // Undefined variables read as null, which the runtime ignores.
+                let previous = builder.use_var(vars[0]);
+                rc.release(&mut builder, previous);
+            }
+            for (var, value) in vars.iter().zip(values) {
+                builder.def_var(*var, value);
+            }
+        }
+        match &block.terminator {
+            MirTerminator::Return(None) => {
+                if let Some(rc) = &rc {
+                    rc.release_locals(&mut builder, function, &locals);
+                }
+                builder.ins().return_(&[]);
+            }
+            MirTerminator::Return(Some(operand)) => {
+                let values = lower_operand(
+                    &mut builder,
+                    module,
+                    operand,
+                    &locals,
+                    constants,
+                    function.ret_span,
+                )?;
+                if let Some(rc) = &rc {
+                    // The caller receives its own reference to a returned local.
+                    match operand {
+                        MirOperand::Copy(MirPlace { local })
+                            if function.locals[local.to_usize()].ty.is_managed() =>
+                        {
+                            rc.retain(&mut builder, values[0]);
+                        }
+                        MirOperand::Move(MirPlace { local })
+                            if function.locals[local.to_usize()].ty.is_managed() =>
+                        {
+                            rc.forget(&mut builder, module, &locals[local.to_usize()]);
+                        }
+                        _ => {}
+                    }
+                    rc.release_locals(&mut builder, function, &locals);
+                }
+                builder.ins().return_(&values);
+            }
+            MirTerminator::Goto(target) => {
+                builder.ins().jump(blocks[target.to_usize()], &[]);
+            }
+            MirTerminator::Branch { cond, then_block, else_block, span } => {
+                let cond = lower_operand(&mut builder, module, cond, &locals, constants, *span)?;
+                builder.ins().brif(
+                    cond[0],
+                    blocks[then_block.to_usize()],
+                    &[],
+                    blocks[else_block.to_usize()],
+                    &[],
+                );
+            }
+            MirTerminator::Panic { message, span } => {
+                let message =
+                    lower_operand(&mut builder, module, message, &locals, constants, *span)?;
+                lower_panic(&mut builder, module, runtimes, message, &ctx.panics[span]);
+            }
+            MirTerminator::Unreachable => {
+                builder.ins().trap(TrapCode::unwrap_user(1));
+            }
+        }
+    }
+    builder.seal_all_blocks();
+    builder.finalize();
+    define_function(module, id, &mut context, &mut ctx.unwind, function.span)
+}
+
+/// Runtime entries used to maintain reference counts of managed locals.
+///
+/// Every managed local owns one reference: parameters are retained on entry,
+/// copies are retained when assigned, overwritten values are released, and
+/// all managed locals are released before returning. Moving a local hands its
+/// reference to the destination and leaves the local null.
+struct RefCounting {
+    retain: FuncRef,
+    release: FuncRef,
+}
+
+impl RefCounting {
+    fn declare<M: Module>(
+        module: &mut M,
+        builder: &mut FunctionBuilder<'_>,
+        function: &MirFunction<'_>,
+        runtimes: &HashMap<RuntimeFunctionId, FuncId>,
+    ) -> Option<Self> {
+        if !function.locals.iter().any(|local| local.ty.is_managed()) {
+            return None;
+        }
+
+        Some(Self {
+            retain: module.declare_func_in_func(runtimes[&RUNTIME_RETAIN], builder.func),
+            release: module.declare_func_in_func(runtimes[&RUNTIME_RELEASE], builder.func),
+        })
+    }
+
+    fn retain(&self, builder: &mut FunctionBuilder<'_>, value: Value) {
+        builder.ins().call(self.retain, &[value]);
+    }
+
+    fn release(&self, builder: &mut FunctionBuilder<'_>, value: Value) {
+        builder.ins().call(self.release, &[value]);
+    }
+
+    /// Clears a local whose reference was moved out, so that releasing or
+    /// overwriting it later is a no-op.
+    fn forget<M: Module>(&self, builder: &mut FunctionBuilder<'_>, module: &M, vars: &[Variable]) {
+        let null = builder.ins().iconst(module.target_config().pointer_type(), 0);
+        builder.def_var(vars[0], null);
+    }
+
+    /// Gives a heap record its own reference to a value stored into it: a
+    /// copied local is retained and a moved one hands its reference over.
+    fn take<M: Module>(
+        &self,
+        builder: &mut FunctionBuilder<'_>,
+        module: &M,
+        function: &MirFunction<'_>,
+        locals: &[Vec<Variable>],
+        operand: &MirOperand,
+        values: &[Value],
+    ) {
+        match operand {
+            MirOperand::Copy(MirPlace { local })
+                if function.locals[local.to_usize()].ty.is_managed() =>
+            {
+                self.retain(builder, values[0]);
+            }
+            MirOperand::Move(MirPlace { local })
+                if function.locals[local.to_usize()].ty.is_managed() =>
+            {
+                self.forget(builder, module, &locals[local.to_usize()]);
+            }
+            _ => {}
+        }
+    }
+
+    /// Callees borrow their arguments, so moved values end after the call.
+    fn release_moved<'a, M: Module>(
+        &self,
+        builder: &mut FunctionBuilder<'_>,
+        module: &M,
+        function: &MirFunction<'_>,
+        locals: &[Vec<Variable>],
+        operands: impl Iterator<Item = &'a MirOperand>,
+    ) {
+        for operand in operands {
+            if let MirOperand::Move(MirPlace { local }) = operand
+                && function.locals[local.to_usize()].ty.is_managed()
+            {
+                let vars = &locals[local.to_usize()];
+                let value = builder.use_var(vars[0]);
+                self.release(builder, value);
+                self.forget(builder, module, vars);
+            }
+        }
+    }
+
+    fn release_locals(
+        &self,
+        builder: &mut FunctionBuilder<'_>,
+        function: &MirFunction<'_>,
+        locals: &[Vec<Variable>],
+    ) {
+        for (local, vars) in function.locals.iter().zip(locals).rev() {
+            if local.ty.is_managed() {
+                let value = builder.use_var(vars[0]);
+                self.release(builder, value);
+            }
+        }
+    }
+}
+
+/// Placement of the fields of a heap record such as a closure environment or
+/// a cell. Fields follow each other like a value read by `MirRvalue::Load`,
+/// with every Cranelift value at its natural alignment.
+struct RecordLayout {
+    /// Type and offset of every Cranelift value of every field.
+    fields: Vec<Vec<(Type, u32)>>,
+    size: u32,
+    align: u32,
+}
+
+impl RecordLayout {
+    fn new<M: Module>(module: &M, fields: &[MirTy], span: Span) -> CodegenResult<Self> {
+        let mut layout = Self { fields: Vec::with_capacity(fields.len()), size: 0, align: 1 };
+        for field in fields {
+            let mut values = Vec::new();
+            for ty in clif_types(module, field, span)? {
+                let offset = layout.size.next_multiple_of(ty.bytes());
+                values.push((ty, offset));
+                layout.size = offset + ty.bytes();
+                layout.align = layout.align.max(ty.bytes());
             }
+            layout.fields.push(values);
+        }
+        Ok(layout)
+    }
+
+    /// Places every field of a union at its beginning, so that the record
+    /// is as large as its largest field.
+    fn overlapping<M: Module>(module: &M, fields: &[MirTy], span: Span) -> CodegenResult<Self> {
+        let mut layout = Self { fields: Vec::with_capacity(fields.len()), size: 0, align: 1 };
+        for field in fields {
+            let field = Self::new(module, std::slice::from_ref(field), span)?;
+            layout.size = layout.size.max(field.size);
+            layout.align = layout.align.max(field.align);
+            layout.fields.extend(field.fields);
+        }
+        Ok(layout)
+    }
+
+    /// Returns the layout of a struct, enum or union record of type `ty`.
+    fn of<M: Module>(module: &M, ty: &MirTy, span: Span) -> CodegenResult<Self> {
+        match ty {
+            MirTy::Record { fields, .. } => Self::new(module, fields, span),
+            MirTy::Union { fields, .. } => Self::overlapping(module, fields, span),
+            _ => Err(unsupported_type(ty, span)),
+        }
+    }
+
+    fn load(&self, builder: &mut FunctionBuilder<'_>, record: Value, field: usize) -> Vec<Value> {
+        self.fields[field]
+            .iter()
+            .map(|(ty, offset)| builder.ins().load(*ty, MemFlags::new(), record, *offset as i32))
+            .collect()
+    }
+
+    fn store(
+        &self,
+        builder: &mut FunctionBuilder<'_>,
+        record: Value,
+        field: usize,
+        values: &[Value],
+    ) {
+        for (value, (_, offset)) in values.iter().zip(&self.fields[field]) {
+            builder.ins().store(MemFlags::new(), *value, record, *offset as i32);
+        }
+    }
+
+    /// Distance between consecutive records in an array of them.
+    fn stride(&self) -> u32 {
+        self.size.next_multiple_of(self.align)
+    }
+
+    /// Offsets of the reference-counted pointers held by managed fields.
+    fn managed_offsets(&self, fields: &[MirTy]) -> Box<[u32]> {
+        fields
+            .iter()
+            .zip(&self.fields)
+            .filter(|(ty, _)| ty.is_managed())
+            .map(|(_, values)| values[0].1)
+            .collect()
+    }
+}
+
+/// Allocates a heap record holding `values`, one entry per field, and
+/// registers a drop function that releases its managed fields and a trace
+/// function that reports them to the cycle collector.
+fn alloc_record<M: Module>(
+    builder: &mut FunctionBuilder<'_>,
+    module: &mut M,
+    (fields, values): (&[MirTy], &[Vec<Value>]),
+    runtimes: &HashMap<RuntimeFunctionId, FuncId>,
+    drops: &mut DropGlue,
+    span: Span,
+) -> CodegenResult<Value> {
+    let layout = RecordLayout::new(module, fields, span)?;
+    let pointer_ty = module.target_config().pointer_type();
+    let record = alloc_uninit(builder, module, &layout, runtimes);
+    for (field, values) in values.iter().enumerate() {
+        layout.store(builder, record, field, values);
+    }
+
+    let managed = layout.managed_offsets(fields);
+    if !managed.is_empty() {
+        let (drop, trace) = drops.get(module, managed, span)?;
+        let drop = module.declare_func_in_func(drop, builder.func);
+        let drop = builder.ins().func_addr(pointer_ty, drop);
+        let set_drop = module.declare_func_in_func(runtimes[&RUNTIME_SET_DROP], builder.func);
+        builder.ins().call(set_drop, &[record, drop]);
+        let trace = module.declare_func_in_func(trace, builder.func);
+        let trace = builder.ins().func_addr(pointer_ty, trace);
+        let set_trace = module.declare_func_in_func(runtimes[&RUNTIME_SET_TRACE], builder.func);
+        builder.ins().call(set_trace, &[record, trace]);
+    }
+    Ok(record)
+}
+
+/// Allocates a heap record of `layout` whose fields are left uninitialized.
+fn alloc_uninit<M: Module>(
+    builder: &mut FunctionBuilder<'_>,
+    module: &mut M,
+    layout: &RecordLayout,
+    runtimes: &HashMap<RuntimeFunctionId, FuncId>,
+) -> Value {
+    let pointer_ty = module.target_config().pointer_type();
+    let alloc = module.declare_func_in_func(runtimes[&RUNTIME_ALLOC], builder.func);
+    let size = builder.ins().iconst(pointer_ty, i64::from(layout.size));
+    let align = builder.ins().iconst(pointer_ty, i64::from(layout.align));
+    let call = builder.ins().call(alloc, &[size, align]);
+    builder.inst_results(call)[0]
+}
+
+/// Drop and trace functions of heap records, one pair per set of managed
+/// field offsets. They are declared while functions are compiled and
+/// defined afterwards.
+#[derive(Default)]
+struct DropGlue {
+    functions: HashMap<Box<[u32]>, (FuncId, FuncId)>,
+}
+
+impl DropGlue {
+    /// Returns the drop and trace functions of records with managed fields
+    /// at `offsets`.
+    fn get<M: Module>(
+        &mut self,
+        module: &mut M,
+        offsets: Box<[u32]>,
+        span: Span,
+    ) -> CodegenResult<(FuncId, FuncId)> {
+        if let Some(ids) = self.functions.get(&offsets) {
+            return Ok(*ids);
+        }
+
+        let pointer_ty = module.target_config().pointer_type();
+        let index = self.functions.len();
+        let mut signature = module.make_signature();
+        signature.params.push(AbiParam::new(pointer_ty));
+        let drop = module
+            .declare_function(&format!("__runeway_drop_{index}"), Linkage::Local, &signature)
+            .map_err(|error| backend(error, span))?;
+        signature.params.push(AbiParam::new(pointer_ty));
+        let trace = module
+            .declare_function(&format!("__runeway_trace_{index}"), Linkage::Local, &signature)
+            .map_err(|error| backend(error, span))?;
+        self.functions.insert(offsets, (drop, trace));
+        Ok((drop, trace))
+    }
+
+    /// Defines every declared drop function as a release of the pointer at
+    /// each of its offsets, and every trace function as a call of its visit
+    /// function with each of them.
+    fn define<M: Module>(
+        self,
+        module: &mut M,
+        runtimes: &HashMap<RuntimeFunctionId, FuncId>,
+        unwind: &mut Option<Vec<(FuncId, UnwindInfo)>>,
+        span: Span,
+    ) -> CodegenResult<()> {
+        let pointer_ty = module.target_config().pointer_type();
+        for (offsets, (drop, trace)) in self.functions {
+            let mut visit = module.make_signature();
+            visit.params.push(AbiParam::new(pointer_ty));
+            for (id, is_trace) in [(drop, false), (trace, true)] {
+                let mut context = module.make_context();
+                context.func.signature.params.push(AbiParam::new(pointer_ty));
+                if is_trace {
+                    context.func.signature.params.push(AbiParam::new(pointer_ty));
+                }
+                context.func.name = UserFuncName::user(0, id.as_u32());
+                let mut builder_context = FunctionBuilderContext::new();
+                let mut builder = FunctionBuilder::new(&mut context.func, &mut builder_context);
+                let entry = builder.create_block();
+                builder.append_block_params_for_function_params(entry);
+                builder.switch_to_block(entry);
+                builder.seal_block(entry);
+
+                let record = builder.block_params(entry)[0];
+                let values: Vec<_> = offsets
+                    .iter()
+                    .map(|offset| {
+                        builder.ins().load(pointer_ty, MemFlags::new(), record, *offset as i32)
+                    })
+                    .collect();
+                if is_trace {
+                    let callback = builder.block_params(entry)[1];
+                    let visit = builder.import_signature(visit.clone());
+                    for value in values {
+                        builder.ins().call_indirect(visit, callback, &[value]);
+                    }
+                } else {
+                    let release =
+                        module.declare_func_in_func(runtimes[&RUNTIME_RELEASE], builder.func);
+                    for value in values {
+                        builder.ins().call(release, &[value]);
+                    }
+                }
+                builder.ins().return_(&[]);
+                builder.finalize();
+                define_function(module, id, &mut context, unwind, span)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Hash and equality functions of struct and enum records, one pair per
+/// record type, which maps keyed by records are created with and `==`
+/// calls. Like drop glue, they are declared while functions are compiled
+/// and defined afterwards.
+#[derive(Default)]
+struct RecordGlue {
+    functions: HashMap<MirTy, (FuncId, FuncId)>,
+}
+
+impl RecordGlue {
+    /// Returns the hash and equality functions of records of type `ty`,
+    /// declaring those of the records it holds along with them.
+    fn get<M: Module>(
+        &mut self,
+        module: &mut M,
+        ty: &MirTy,
+        span: Span,
+    ) -> CodegenResult<(FuncId, FuncId)> {
+        if let Some(ids) = self.functions.get(ty) {
+            return Ok(*ids);
+        }
+        let MirTy::Record { fields, .. } = ty else {
+            return Err(unsupported_type(ty, span));
         };
-        let vars = &locals[dst.local.to_usize()];
-        if vars.len() != values.len() {
-            return Err(error(messages::ABI_ARITY_MISMATCH, &[], *span));
+        for field in fields.iter().filter(|field| matches!(field, MirTy::Record { .. })) {
+            self.get(module, field, span)?;
         }
-        for (var, value) in vars.iter().zip(values) {
-            builder.def_var(*var, value);
+
+        let pointer_ty = module.target_config().pointer_type();
+        let index = self.functions.len();
+        let mut signature = module.make_signature();
+        signature.params.push(AbiParam::new(pointer_ty));
+        signature.returns.push(AbiParam::new(types::I64));
+        let hash = module
+            .declare_function(&format!("__runeway_hash_{index}"), Linkage::Local, &signature)
+            .map_err(|error| backend(error, span))?;
+        signature.params.push(AbiParam::new(pointer_ty));
+        signature.returns[0] = AbiParam::new(types::I8);
+        let eq = module
+            .declare_function(&format!("__runeway_eq_{index}"), Linkage::Local, &signature)
+            .map_err(|error| backend(error, span))?;
+        self.functions.insert(ty.clone(), (hash, eq));
+        Ok((hash, eq))
+    }
+
+    /// Defines every declared hash function as a combination of the hashes
+    /// of the fields of its record, and every equality function as the
+    /// conjunction of the comparisons of their fields. Null records, which
+    /// only appear in zeroed fields, hash to zero and equal only each other.
+    fn define<M: Module>(
+        &self,
+        module: &mut M,
+        runtimes: &HashMap<RuntimeFunctionId, FuncId>,
+        unwind: &mut Option<Vec<(FuncId, UnwindInfo)>>,
+        span: Span,
+    ) -> CodegenResult<()> {
+        let pointer_ty = module.target_config().pointer_type();
+        for (ty, (hash, eq)) in &self.functions {
+            let MirTy::Record { fields, .. } = ty else {
+                return Err(unsupported_type(ty, span));
+            };
+            let layout = RecordLayout::new(module, fields, span)?;
+            for (id, is_eq) in [(*hash, false), (*eq, true)] {
+                let mut context = module.make_context();
+                context.func.signature.params.push(AbiParam::new(pointer_ty));
+                if is_eq {
+                    context.func.signature.params.push(AbiParam::new(pointer_ty));
+                }
+                let ret = if is_eq { types::I8 } else { types::I64 };
+                context.func.signature.returns.push(AbiParam::new(ret));
+                context.func.name = UserFuncName::user(0, id.as_u32());
+                let mut builder_context = FunctionBuilderContext::new();
+                let mut builder = FunctionBuilder::new(&mut context.func, &mut builder_context);
+                let entry = builder.create_block();
+                builder.append_block_params_for_function_params(entry);
+                builder.switch_to_block(entry);
+                builder.seal_block(entry);
+
+                let records = builder.block_params(entry).to_vec();
+                let result = if is_eq {
+                    let same = builder.ins().icmp(IntCC::Equal, records[0], records[1]);
+                    let [lhs_null, rhs_null] =
+                        [0, 1].map(|index| builder.ins().icmp_imm(IntCC::Equal, records[index], 0));
+                    let either_null = builder.ins().bor(lhs_null, rhs_null);
+                    self.return_early(&mut builder, same, 1, ret);
+                    self.return_early(&mut builder, either_null, 0, ret);
+                    self.define_eq(
+                        &mut builder,
+                        module,
+                        runtimes,
+                        (fields, &layout),
+                        &records,
+                        span,
+                    )?
+                } else {
+                    let null = builder.ins().icmp_imm(IntCC::Equal, records[0], 0);
+                    self.return_early(&mut builder, null, 0, ret);
+                    self.define_hash(
+                        &mut builder,
+                        module,
+                        runtimes,
+                        (fields, &layout),
+                        records[0],
+                        span,
+                    )?
+                };
+                builder.ins().return_(&[result]);
+                builder.finalize();
+                define_function(module, id, &mut context, unwind, span)?;
+            }
         }
+        Ok(())
+    }
+
+    /// Returns `value` when `condition` holds and continues in a new block
+    /// otherwise.
+    fn return_early(
+        &self,
+        builder: &mut FunctionBuilder<'_>,
+        condition: Value,
+        value: i64,
+        ty: Type,
+    ) {
+        let done = builder.create_block();
+        let rest = builder.create_block();
+        builder.ins().brif(condition, done, &[], rest, &[]);
+        builder.switch_to_block(done);
+        builder.seal_block(done);
+        let value = builder.ins().iconst(ty, value);
+        builder.ins().return_(&[value]);
+        builder.switch_to_block(rest);
+        builder.seal_block(rest);
     }
-    match &block.terminator {
-        MirTerminator::Return(None) => {
-            builder.ins().return_(&[]);
+
+    /// Combines the hashes of the fields of `record`, FxHash-style.
+    fn define_hash<M: Module>(
+        &self,
+        builder: &mut FunctionBuilder<'_>,
+        module: &mut M,
+        runtimes: &HashMap<RuntimeFunctionId, FuncId>,
+        (fields, layout): (&[MirTy], &RecordLayout),
+        record: Value,
+        span: Span,
+    ) -> CodegenResult<Value> {
+        let mut hash = builder.ins().iconst(types::I64, 0);
+        for (index, field) in fields.iter().enumerate() {
+            let values = layout.load(builder, record, index);
+            let mut words = Vec::new();
+            match field {
+                MirTy::Unit => {}
+                MirTy::Bool | MirTy::Char | MirTy::Int(_) => {
+                    let value = values[0];
+                    let ty = builder.func.dfg.value_type(value);
+                    if ty == types::I128 {
+                        let (low, high) = builder.ins().isplit(value);
+                        words.extend([low, high]);
+                    } else if ty == types::I64 {
+                        words.push(value);
+                    } else {
+                        words.push(builder.ins().uextend(types::I64, value));
+                    }
+                }
+                MirTy::Str => {
+                    let str_hash =
+                        module.declare_func_in_func(runtimes[&RUNTIME_STR_HASH], builder.func);
+                    let call = builder.ins().call(str_hash, &values);
+                    words.push(builder.inst_results(call)[0]);
+                }
+                MirTy::Record { .. } => {
+                    let nested = module.declare_func_in_func(self.functions[field].0, builder.func);
+                    let call = builder.ins().call(nested, &values);
+                    words.push(builder.inst_results(call)[0]);
+                }
+                _ => return Err(unsupported_type(field, span)),
+            }
+            for word in words {
+                let rotated = builder.ins().rotl_imm(hash, 5);
+                let mixed = builder.ins().bxor(rotated, word);
+                hash = builder.ins().imul_imm(mixed, 0x517c_c1b7_2722_0a95);
+            }
         }
-        MirTerminator::Return(Some(operand)) => {
-            let values = lower_operand(
-                &mut builder,
-                module,
-                operand,
-                &locals,
-                constants,
-                function.ret_span,
-            )?;
-            builder.ins().return_(&values);
+        Ok(hash)
+    }
+
+    /// Compares the fields of two records, all of which must be equal.
+    fn define_eq<M: Module>(
+        &self,
+        builder: &mut FunctionBuilder<'_>,
+        module: &mut M,
+        runtimes: &HashMap<RuntimeFunctionId, FuncId>,
+        (fields, layout): (&[MirTy], &RecordLayout),
+        records: &[Value],
+        span: Span,
+    ) -> CodegenResult<Value> {
+        let mut equal = builder.ins().iconst(types::I8, 1);
+        for (index, field) in fields.iter().enumerate() {
+            let lhs = layout.load(builder, records[0], index);
+            let rhs = layout.load(builder, records[1], index);
+            let field_equal = match field {
+                MirTy::Unit => continue,
+                MirTy::Bool | MirTy::Char | MirTy::Int(_) => {
+                    builder.ins().icmp(IntCC::Equal, lhs[0], rhs[0])
+                }
+                MirTy::Str => {
+                    let cmp = module.declare_func_in_func(runtimes[&RUNTIME_STR_CMP], builder.func);
+                    let call = builder.ins().call(cmp, &[lhs, rhs].concat());
+                    let ordering = builder.inst_results(call)[0];
+                    builder.ins().icmp_imm(IntCC::Equal, ordering, 0)
+                }
+                MirTy::Record { .. } => {
+                    let nested = module.declare_func_in_func(self.functions[field].1, builder.func);
+                    let call = builder.ins().call(nested, &[lhs[0], rhs[0]]);
+                    builder.inst_results(call)[0]
+                }
+                _ => return Err(unsupported_type(field, span)),
+            };
+            equal = builder.ins().band(equal, field_equal);
         }
+        Ok(equal)
+    }
+}
+
+/// Expands a builtin intrinsic applied to already lowered arguments.
+fn lower_intrinsic<M: Module>(
+    builder: &mut FunctionBuilder<'_>,
+    module: &mut M,
+    runtimes: &HashMap<RuntimeFunctionId, FuncId>,
+    intrinsic: Intrinsic,
+    (arg_tys, args): (Vec<Option<MirTy>>, Vec<Vec<Value>>),
+    dst_ty: &MirTy,
+    span: Span,
+) -> CodegenResult<Vec<Value>> {
+    let pointer_ty = module.target_config().pointer_type();
+    let receiver = args.first().and_then(|values| values.first().copied());
+    Ok(match (intrinsic, arg_tys.first().cloned().flatten(), receiver) {
+        (Intrinsic::VecNew, _, _) => {
+            let MirTy::Vec(elem) = dst_ty else {
+                return Err(unsupported_type(dst_ty, span));
+            };
+            let layout = RecordLayout::new(module, std::slice::from_ref(elem.as_ref()), span)?;
+            let args =
+                [i64::from(layout.stride()), i64::from(layout.align), elem_kind(elem) as i64]
+                    .map(|arg| builder.ins().iconst(pointer_ty, arg));
+            let new = module.declare_func_in_func(runtimes[&RUNTIME_VEC_NEW], builder.func);
+            let call = builder.ins().call(new, &args);
+            builder.inst_results(call).to_vec()
+        }
+        // The runtime hands out the slot of the new element, which is
+        // written here.
+        (Intrinsic::VecPush, Some(MirTy::Vec(elem)), Some(vec)) => {
+            let layout = RecordLayout::new(module, std::slice::from_ref(elem.as_ref()), span)?;
+            let push = module.declare_func_in_func(runtimes[&RUNTIME_VEC_PUSH], builder.func);
+            let call = builder.ins().call(push, &[vec]);
+            let slot = builder.inst_results(call)[0];
+            let value =
+                args.get(1).ok_or_else(|| error(messages::ABI_ARITY_MISMATCH, &[], span))?;
+            layout.store(builder, slot, 0, value);
+            Vec::new()
+        }
+        // The popped element is read only when the runtime returns a slot.
+        (Intrinsic::VecPop, Some(MirTy::Vec(elem)), Some(vec)) => {
+            let pop = module.declare_func_in_func(runtimes[&RUNTIME_VEC_POP], builder.func);
+            let call = builder.ins().call(pop, &[vec]);
+            let slot = builder.inst_results(call)[0];
+            load_nullable_slot(builder, module, slot, &elem, dst_ty, span)?
+        }
+        (Intrinsic::Len, Some(ty @ (MirTy::Vec(_) | MirTy::Slice(_))), Some(_)) => {
+            vec![sequence_parts(builder, module, &ty, &args[0], span)?.1]
+        }
+        // The runtime shuffles the elements a vector holds or a slice views.
+        (Intrinsic::Shuffle, Some(ty @ (MirTy::Vec(_) | MirTy::Slice(_))), Some(_)) => {
+            let (MirTy::Vec(elem) | MirTy::Slice(elem)) = &ty else {
+                return Err(unsupported_type(&ty, span));
+            };
+            let layout = RecordLayout::new(module, std::slice::from_ref(elem.as_ref()), span)?;
+            let (elems, len) = sequence_parts(builder, module, &ty, &args[0], span)?;
+            let stride = builder.ins().iconst(pointer_ty, i64::from(layout.stride()));
+            let shuffle =
+                module.declare_func_in_func(runtimes[&RUNTIME_RANDOM_SHUFFLE], builder.func);
+            builder.ins().call(shuffle, &[elems, len, stride]);
+            Vec::new()
+        }
+        // Maps hold their length at the start of their payload.
+        (Intrinsic::Len, Some(MirTy::Map { .. }), Some(map)) => {
+            vec![builder.ins().load(pointer_ty, MemFlags::new(), map, 0)]
+        }
+        (Intrinsic::MapNew, _, _) => {
+            let MirTy::Map { key, value } = dst_ty else {
+                return Err(unsupported_type(dst_ty, span));
+            };
+            let kind = if **key == MirTy::Str { MAP_KEY_STR } else { MAP_KEY_BYTES };
+            let value_kind = elem_kind(value);
+            let key = RecordLayout::new(module, std::slice::from_ref(key.as_ref()), span)?;
+            let value = RecordLayout::new(module, std::slice::from_ref(value.as_ref()), span)?;
+            let args = [
+                i64::from(key.stride()),
+                i64::from(key.align),
+                kind as i64,
+                i64::from(value.stride()),
+                i64::from(value.align),
+                value_kind as i64,
+            ]
+            .map(|arg| builder.ins().iconst(pointer_ty, arg));
+            let new = module.declare_func_in_func(runtimes[&RUNTIME_MAP_NEW], builder.func);
+            let call = builder.ins().call(new, &args);
+            builder.inst_results(call).to_vec()
+        }
+        (Intrinsic::MapKeys | Intrinsic::MapValues, Some(MirTy::Map { .. }), Some(map)) => {
+            let id =
+                if intrinsic == Intrinsic::MapKeys { RUNTIME_MAP_KEYS } else { RUNTIME_MAP_VALUES };
+            let collect = module.declare_func_in_func(runtimes[&id], builder.func);
+            let call = builder.ins().call(collect, &[map]);
+            builder.inst_results(call).to_vec()
+        }
+        // The runtime formats the value extended to 64 bits and hands out the
+        // `(ptr, len)` of the text, which the caller owns.
+        (Intrinsic::IntToString, Some(MirTy::Int(ty)), Some(value))
+            if ty.bits != TypeBits::B128 =>
+        {
+            let value = match ty.bits {
+                TypeBits::B64 => value,
+                _ if ty.signed => builder.ins().sextend(types::I64, value),
+                _ => builder.ins().uextend(types::I64, value),
+            };
+            let signed = builder.ins().iconst(types::I8, i64::from(ty.signed));
+            let format =
+                module.declare_func_in_func(runtimes[&RUNTIME_INT_TO_STRING], builder.func);
+            let call = builder.ins().call(format, &[value, signed]);
+            let slot = builder.inst_results(call)[0];
+            [0, pointer_ty.bytes() as i32]
+                .map(|offset| builder.ins().load(pointer_ty, MemFlags::new(), slot, offset))
+                .to_vec()
+        }
+        // Both strings are passed as their `(ptr, len)`.
+        (Intrinsic::StrCmp, Some(MirTy::Str), Some(_)) => {
+            let [lhs, rhs] = [0, 1].map(|index| args.get(index).map_or(&[][..], Vec::as_slice));
+            if lhs.len() != 2 || rhs.len() != 2 {
+                return Err(error(messages::ABI_ARITY_MISMATCH, &[], span));
+            }
+            let cmp = module.declare_func_in_func(runtimes[&RUNTIME_STR_CMP], builder.func);
+            let call = builder.ins().call(cmp, &[lhs, rhs].concat());
+            builder.inst_results(call).to_vec()
+        }
+        (Intrinsic::NumberCast, Some(from), Some(x)) => {
+            vec![cast_number(builder, module, x, (&from, dst_ty), span)?]
+        }
+        (_, Some(ty @ (MirTy::Int(_) | MirTy::Float(_))), Some(_)) => {
+            lower_math(builder, intrinsic, &ty, &args, span)?
+        }
+        // The key, and the value for `insert`, are passed to the runtime
+        // through stack slots.
+        (_, Some(MirTy::Map { key, value }), Some(map)) => {
+            let arg = |index: usize| {
+                args.get(index).ok_or_else(|| error(messages::ABI_ARITY_MISMATCH, &[], span))
+            };
+            let key_ptr = spill(builder, module, &key, arg(1)?, span)?;
+            let (id, call_args) = match intrinsic {
+                Intrinsic::MapInsert => {
+                    let value_ptr = spill(builder, module, &value, arg(2)?, span)?;
+                    (RUNTIME_MAP_INSERT, vec![map, key_ptr, value_ptr])
+                }
+                // Set values are zero-sized, so any address will do.
+                Intrinsic::SetInsert => (RUNTIME_MAP_INSERT, vec![map, key_ptr, key_ptr]),
+                Intrinsic::MapGet | Intrinsic::MapContains => (RUNTIME_MAP_GET, vec![map, key_ptr]),
+                Intrinsic::MapRemove | Intrinsic::SetRemove => {
+                    (RUNTIME_MAP_REMOVE, vec![map, key_ptr])
+                }
+                _ => return Err(unsupported_type(&MirTy::Map { key, value }, span)),
+            };
+            let function = module.declare_func_in_func(runtimes[&id], builder.func);
+            let call = builder.ins().call(function, &call_args);
+            let slot = builder.inst_results(call)[0];
+            match intrinsic {
+                Intrinsic::SetInsert => vec![builder.ins().icmp_imm(IntCC::Equal, slot, 0)],
+                Intrinsic::MapContains | Intrinsic::SetRemove => {
+                    vec![builder.ins().icmp_imm(IntCC::NotEqual, slot, 0)]
+                }
+                _ => load_nullable_slot(builder, module, slot, &value, dst_ty, span)?,
+            }
+        }
+        (_, ty, _) => {
+            return Err(unsupported_type(&ty.unwrap_or(MirTy::Unit), span));
+        }
+    })
+}
+
+/// Tells the runtime whether a container holds references to its elements.
+fn elem_kind(elem: &MirTy) -> usize {
+    if elem.is_managed() { ELEM_MANAGED } else { ELEM_PLAIN }
+}
+
+/// Converts the number `x` between number types. Floats are truncated
+/// toward zero and saturate at the bounds of 64-bit integers, which are
+/// then narrowed to the width of the result.
+fn cast_number<M: Module>(
+    builder: &mut FunctionBuilder<'_>,
+    module: &M,
+    x: Value,
+    (from, to): (&MirTy, &MirTy),
+    span: Span,
+) -> CodegenResult<Value> {
+    let to_ty = match clif_types(module, to, span)?.as_slice() {
+        [ty] => *ty,
+        _ => return Err(unsupported_type(to, span)),
+    };
+    let ins = builder.ins();
+    Ok(match (from, to) {
+        (MirTy::Int(from), MirTy::Int(int)) if from.bits == int.bits => x,
+        (MirTy::Int(from), MirTy::Int(_)) if int_type(from.bits).bits() > to_ty.bits() => {
+            ins.ireduce(to_ty, x)
+        }
+        (MirTy::Int(from), MirTy::Int(_)) if from.signed => ins.sextend(to_ty, x),
+        (MirTy::Int(_), MirTy::Int(_)) => ins.uextend(to_ty, x),
+        // Integers are widened to 64 bits first, the narrowest width every
+        // target converts from.
+        (MirTy::Int(from), MirTy::Float(_)) => {
+            let wide = match (from.bits, from.signed) {
+                (TypeBits::B64, _) => x,
+                (_, true) => ins.sextend(types::I64, x),
+                (_, false) => ins.uextend(types::I64, x),
+            };
+            if from.signed {
+                builder.ins().fcvt_from_sint(to_ty, wide)
+            } else {
+                builder.ins().fcvt_from_uint(to_ty, wide)
+            }
+        }
+        (MirTy::Float(_), MirTy::Int(int)) => {
+            let wide = if int.signed {
+                ins.fcvt_to_sint_sat(types::I64, x)
+            } else {
+                ins.fcvt_to_uint_sat(types::I64, x)
+            };
+            match int.bits {
+                TypeBits::B64 => wide,
+                _ => builder.ins().ireduce(to_ty, wide),
+            }
+        }
+        (MirTy::Float(from), MirTy::Float(float)) if from.bits == float.bits => x,
+        (MirTy::Float(_), MirTy::Float(float)) if float.bits == TypeBits::B64 => {
+            ins.fpromote(to_ty, x)
+        }
+        (MirTy::Float(_), MirTy::Float(_)) => ins.fdemote(to_ty, x),
+        _ => return Err(unsupported_type(to, span)),
+    })
+}
+
+/// Expands a `std::math` or `std::time` intrinsic over numbers of type `ty`
+/// to Cranelift instructions. Checked operations produce a tagged `ty?`.
+fn lower_math(
+    builder: &mut FunctionBuilder<'_>,
+    intrinsic: Intrinsic,
+    ty: &MirTy,
+    args: &[Vec<Value>],
+    span: Span,
+) -> CodegenResult<Vec<Value>> {
+    let arg = |index: usize| {
+        args.get(index)
+            .and_then(|values| values.first().copied())
+            .ok_or_else(|| error(messages::ABI_ARITY_MISMATCH, &[], span))
+    };
+    let x = arg(0)?;
+    let ins = builder.ins();
+    let value = match (intrinsic, ty) {
+        (Intrinsic::Sqrt, MirTy::Float(_)) => ins.sqrt(x),
+        (Intrinsic::Floor, MirTy::Float(_)) => ins.floor(x),
+        (Intrinsic::Ceil, MirTy::Float(_)) => ins.ceil(x),
+        (Intrinsic::Trunc, MirTy::Float(_)) => ins.trunc(x),
+        (Intrinsic::Abs, MirTy::Float(_)) => ins.fabs(x),
+        (Intrinsic::Abs, MirTy::Int(MirIntTy { signed: true, .. })) => ins.iabs(x),
+        (Intrinsic::Abs, MirTy::Int(_)) => x,
+        (Intrinsic::Min, MirTy::Float(_)) => ins.fmin(x, arg(1)?),
+        (Intrinsic::Min, MirTy::Int(MirIntTy { signed: true, .. })) => ins.smin(x, arg(1)?),
+        (Intrinsic::Min, MirTy::Int(_)) => ins.umin(x, arg(1)?),
+        (Intrinsic::Max, MirTy::Float(_)) => ins.fmax(x, arg(1)?),
+        (Intrinsic::Max, MirTy::Int(MirIntTy { signed: true, .. })) => ins.smax(x, arg(1)?),
+        (Intrinsic::Max, MirTy::Int(_)) => ins.umax(x, arg(1)?),
+        (Intrinsic::WrappingAdd, MirTy::Int(_)) => ins.iadd(x, arg(1)?),
+        (Intrinsic::WrappingSub, MirTy::Int(_)) => ins.isub(x, arg(1)?),
+        (Intrinsic::WrappingMul, MirTy::Int(_)) => ins.imul(x, arg(1)?),
+        (Intrinsic::MulConst(1) | Intrinsic::DivConst(1), MirTy::Int(_)) => x,
+        (Intrinsic::MulConst(factor), MirTy::Int(_)) => ins.imul_imm(x, factor as i64),
+        (Intrinsic::DivConst(divisor), MirTy::Int(MirIntTy { signed: false, .. })) => {
+            ins.udiv_imm(x, divisor as i64)
+        }
+        (Intrinsic::NanosToSecs, MirTy::Int(MirIntTy { signed: false, .. })) => {
+            let nanos = ins.fcvt_from_uint(types::F64, x);
+            let per_sec = builder.ins().f64const(1e9);
+            builder.ins().fdiv(nanos, per_sec)
+        }
+        (
+            Intrinsic::SaturatingAdd | Intrinsic::SaturatingSub,
+            MirTy::Int(MirIntTy { signed: false, .. }),
+        ) => {
+            let y = arg(1)?;
+            let (value, overflow) = if intrinsic == Intrinsic::SaturatingAdd {
+                ins.uadd_overflow(x, y)
+            } else {
+                ins.usub_overflow(x, y)
+            };
+            let ty = builder.func.dfg.value_type(value);
+            let bound = if intrinsic == Intrinsic::SaturatingAdd { -1 } else { 0 };
+            let bound = builder.ins().iconst(ty, bound);
+            builder.ins().select(overflow, bound, value)
+        }
+        (
+            Intrinsic::CheckedAdd | Intrinsic::CheckedSub | Intrinsic::CheckedMul,
+            MirTy::Int(MirIntTy { signed, .. }),
+        ) => {
+            let y = arg(1)?;
+            let (value, overflow) = match (intrinsic, signed) {
+                (Intrinsic::CheckedAdd, true) => ins.sadd_overflow(x, y),
+                (Intrinsic::CheckedAdd, false) => ins.uadd_overflow(x, y),
+                (Intrinsic::CheckedSub, true) => ins.ssub_overflow(x, y),
+                (Intrinsic::CheckedSub, false) => ins.usub_overflow(x, y),
+                (_, true) => ins.smul_overflow(x, y),
+                (_, false) => ins.umul_overflow(x, y),
+            };
+            let present = builder.ins().icmp_imm(IntCC::Equal, overflow, 0);
+            return Ok(vec![present, value]);
+        }
+        _ => return Err(unsupported_type(ty, span)),
+    };
+    Ok(vec![value])
+}
+
+/// Reads a value of type `ty` out of `slot` into a `ty?` of type `dst_ty`,
+/// which is `null` when `slot` is a null pointer.
+fn load_nullable_slot<M: Module>(
+    builder: &mut FunctionBuilder<'_>,
+    module: &M,
+    slot: Value,
+    ty: &MirTy,
+    dst_ty: &MirTy,
+    span: Span,
+) -> CodegenResult<Vec<Value>> {
+    let layout = RecordLayout::new(module, std::slice::from_ref(ty), span)?;
+    let vars = clif_types(module, dst_ty, span)?
+        .into_iter()
+        .map(|ty| {
+            let var = builder.declare_var(ty);
+            let zero = zero_value(builder, ty);
+            builder.def_var(var, zero);
+            var
+        })
+        .collect::<Vec<_>>();
+    let some = builder.create_block();
+    let done = builder.create_block();
+    builder.ins().brif(slot, some, &[], done, &[]);
+
+    builder.switch_to_block(some);
+    let mut values = layout.load(builder, slot, 0);
+    if !ty.is_pointer_like() {
+        values.insert(0, builder.ins().iconst(types::I8, 1));
+    }
+    for (var, value) in vars.iter().zip(values) {
+        builder.def_var(*var, value);
+    }
+    builder.ins().jump(done, &[]);
+
+    builder.switch_to_block(done);
+    Ok(vars.iter().map(|var| builder.use_var(*var)).collect())
+}
+
+/// Stores a value of type `ty` in a new stack slot and returns its address.
+fn spill<M: Module>(
+    builder: &mut FunctionBuilder<'_>,
+    module: &M,
+    ty: &MirTy,
+    values: &[Value],
+    span: Span,
+) -> CodegenResult<Value> {
+    let layout = RecordLayout::new(module, std::slice::from_ref(ty), span)?;
+    let slot = builder.create_sized_stack_slot(StackSlotData::new(
+        StackSlotKind::ExplicitSlot,
+        layout.stride(),
+        layout.align.trailing_zeros() as u8,
+    ));
+    let addr = builder.ins().stack_addr(module.target_config().pointer_type(), slot, 0);
+    layout.store(builder, addr, 0, values);
+    Ok(addr)
+}
+
+/// Returns the pointer to the first element and the length of a vector or
+/// slice. Vectors hold both at the start of their payload. A slice reads the
+/// current buffer of its vector and is cut short if the vector has shrunk
+/// since the slice was taken.
+fn sequence_parts<M: Module>(
+    builder: &mut FunctionBuilder<'_>,
+    module: &M,
+    ty: &MirTy,
+    values: &[Value],
+    span: Span,
+) -> CodegenResult<(Value, Value)> {
+    let pointer_ty = module.target_config().pointer_type();
+    let vector_parts = |builder: &mut FunctionBuilder<'_>, payload: Value| {
+        let ptr = builder.ins().load(pointer_ty, MemFlags::new(), payload, 0);
+        let len =
+            builder.ins().load(pointer_ty, MemFlags::new(), payload, pointer_ty.bytes() as i32);
+        (ptr, len)
+    };
+    Ok(match (ty, values) {
+        (MirTy::Vec(_), [payload]) => vector_parts(builder, *payload),
+        (MirTy::Slice(elem), [vec, start, len]) => {
+            let layout = RecordLayout::new(module, std::slice::from_ref(elem.as_ref()), span)?;
+            let (buffer, vec_len) = vector_parts(builder, *vec);
+            let offset = builder.ins().imul_imm(*start, i64::from(layout.stride()));
+            let ptr = builder.ins().iadd(buffer, offset);
+            let remaining = builder.ins().isub(vec_len, *start);
+            let zero = builder.ins().iconst(pointer_ty, 0);
+            let past_end = builder.ins().icmp(IntCC::UnsignedLessThan, vec_len, *start);
+            let remaining = builder.ins().select(past_end, zero, remaining);
+            (ptr, builder.ins().umin(*len, remaining))
+        }
+        _ => return Err(unsupported_type(ty, span)),
+    })
+}
+
+/// Converts an integer index to the pointer width. Negative indices become
+/// large unsigned ones, which fail every bounds check.
+fn index_to_usize<M: Module>(
+    builder: &mut FunctionBuilder<'_>,
+    module: &M,
+    index: Value,
+    signed: bool,
+) -> Value {
+    let pointer_ty = module.target_config().pointer_type();
+    let ty = builder.func.dfg.value_type(index);
+    if ty.bits() < pointer_ty.bits() {
+        if signed {
+            builder.ins().sextend(pointer_ty, index)
+        } else {
+            builder.ins().uextend(pointer_ty, index)
+        }
+    } else if ty.bits() > pointer_ty.bits() {
+        builder.ins().ireduce(pointer_ty, index)
+    } else {
+        index
+    }
+}
+
+/// Calls the runtime `panic` with the `(ptr, len)` of `message` and
+/// `location`. The runtime never returns: AOT builds exit, and the JIT
+/// unwinds back to its caller without releasing the managed locals.
+fn lower_panic<M: Module>(
+    builder: &mut FunctionBuilder<'_>,
+    module: &mut M,
+    runtimes: &HashMap<RuntimeFunctionId, FuncId>,
+    mut args: Vec<Value>,
+    location: &PanicLocation,
+) {
+    push_location(builder, module, &mut args, location);
+    let panic = module.declare_func_in_func(runtimes[&RUNTIME_PANIC], builder.func);
+    builder.ins().call(panic, &args);
+    builder.ins().trap(TrapCode::unwrap_user(1));
+}
+
+/// Appends the `(ptr, len)` of the file of `location`, then its line and
+/// column, to the arguments of a runtime call.
+fn push_location<M: Module>(
+    builder: &mut FunctionBuilder<'_>,
+    module: &mut M,
+    args: &mut Vec<Value>,
+    location: &PanicLocation,
+) {
+    let pointer_ty = module.target_config().pointer_type();
+    let (file, file_len) = location.file;
+    let file = module.declare_data_in_func(file, builder.func);
+    args.push(builder.ins().global_value(pointer_ty, file));
+    for value in [file_len, location.line, location.col] {
+        args.push(builder.ins().iconst(pointer_ty, value as i64));
+    }
+}
+
+/// Continues in a new block when `in_bounds` holds. Otherwise `report` is
+/// called with `args` to format the message, and the program panics at
+/// `location`.
+fn check_bounds<M: Module>(
+    builder: &mut FunctionBuilder<'_>,
+    module: &mut M,
+    runtimes: &HashMap<RuntimeFunctionId, FuncId>,
+    in_bounds: Value,
+    (report, args): (RuntimeFunctionId, &[Value]),
+    location: &PanicLocation,
+) {
+    let pointer_ty = module.target_config().pointer_type();
+    let ok = builder.create_block();
+    let failed = builder.create_block();
+    builder.ins().brif(in_bounds, ok, &[], failed, &[]);
+
+    builder.switch_to_block(failed);
+    let report = module.declare_func_in_func(runtimes[&report], builder.func);
+    let call = builder.ins().call(report, args);
+    let message = builder.inst_results(call)[0];
+    let message = [0, pointer_ty.bytes()]
+        .map(|offset| builder.ins().load(pointer_ty, MemFlags::trusted(), message, offset as i32));
+    lower_panic(builder, module, runtimes, message.to_vec(), location);
+
+    builder.switch_to_block(ok);
+}
+
+/// Returns the layout of an element of a vector or slice and the address of
+/// the element at `index`, after checking that it is in bounds.
+fn lower_element_slot<M: Module>(
+    builder: &mut FunctionBuilder<'_>,
+    module: &mut M,
+    runtimes: &HashMap<RuntimeFunctionId, FuncId>,
+    (base_ty, base): (Option<&MirTy>, &[Value]),
+    (index_ty, index): (Option<&MirTy>, &[Value]),
+    (span, location): (Span, &PanicLocation),
+) -> CodegenResult<(RecordLayout, Value)> {
+    let (Some(base_ty @ (MirTy::Vec(elem) | MirTy::Slice(elem))), Some(MirTy::Int(int)), [index]) =
+        (base_ty, index_ty, index)
+    else {
+        return Err(unsupported_type(base_ty.unwrap_or(&MirTy::Unit), span));
+    };
+    let layout = RecordLayout::new(module, std::slice::from_ref(elem.as_ref()), span)?;
+    let (ptr, len) = sequence_parts(builder, module, base_ty, base, span)?;
+    let index = index_to_usize(builder, module, *index, int.signed);
+    let in_bounds = builder.ins().icmp(IntCC::UnsignedLessThan, index, len);
+    check_bounds(
+        builder,
+        module,
+        runtimes,
+        in_bounds,
+        (RUNTIME_INDEX_OUT_OF_BOUNDS, &[index, len]),
+        location,
+    );
+    let offset = builder.ins().imul_imm(index, i64::from(layout.stride()));
+    Ok((layout, builder.ins().iadd(ptr, offset)))
+}
+
+/// Takes the elements of a vector or slice covered by a range as a slice of
+/// the same vector, after checking that `start <= end <= len`.
+fn lower_slice<M: Module>(
+    builder: &mut FunctionBuilder<'_>,
+    module: &mut M,
+    runtimes: &HashMap<RuntimeFunctionId, FuncId>,
+    (base_ty, base): (Option<&MirTy>, &[Value]),
+    (range_ty, range): (Option<&MirTy>, &[Value]),
+    (span, location): (Span, &PanicLocation),
+) -> CodegenResult<Vec<Value>> {
+    let (
+        Some(base_ty @ (MirTy::Vec(_) | MirTy::Slice(_))),
+        Some(MirTy::Range(int)),
+        [start, end, inclusive],
+    ) = (base_ty, range_ty, range)
+    else {
+        return Err(unsupported_type(base_ty.unwrap_or(&MirTy::Unit), span));
+    };
+    let pointer_ty = module.target_config().pointer_type();
+    let (_, len) = sequence_parts(builder, module, base_ty, base, span)?;
+    let start = index_to_usize(builder, module, *start, int.signed);
+    let end = index_to_usize(builder, module, *end, int.signed);
+    let inclusive = builder.ins().uextend(pointer_ty, *inclusive);
+    let end = builder.ins().iadd(end, inclusive);
+    let ordered = builder.ins().icmp(IntCC::UnsignedLessThanOrEqual, start, end);
+    let within = builder.ins().icmp(IntCC::UnsignedLessThanOrEqual, end, len);
+    let in_bounds = builder.ins().band(ordered, within);
+    check_bounds(
+        builder,
+        module,
+        runtimes,
+        in_bounds,
+        (RUNTIME_SLICE_OUT_OF_BOUNDS, &[start, end, len]),
+        location,
+    );
+    let (vec, first) = match base {
+        [vec, first, _] => (*vec, builder.ins().iadd(*first, start)),
+        _ => (base[0], start),
+    };
+    Ok(vec![vec, first, builder.ins().isub(end, start)])
+}
+
+/// Lowers `==`, `!=`, `<` or `<=`. A comparison with `null` tests the first
+/// value of the other side, which is the tag or the niche pointer.
+fn lower_comparison<M: Module>(
+    builder: &mut FunctionBuilder<'_>,
+    module: &mut M,
+    (op, signed): (MirBinOp, bool),
+    (lhs, rhs): (&MirOperand, &MirOperand),
+    locals: &[Vec<Variable>],
+    constants: &[Constant],
+    span: runec_source::span::Span,
+) -> CodegenResult<Value> {
+    let cc = match (op, signed) {
+        (MirBinOp::Eq, _) => IntCC::Equal,
+        (MirBinOp::Ne, _) => IntCC::NotEqual,
+        (MirBinOp::Lt, true) => IntCC::SignedLessThan,
+        (MirBinOp::Lt, false) => IntCC::UnsignedLessThan,
+        (MirBinOp::Le, true) => IntCC::SignedLessThanOrEqual,
+        (MirBinOp::Le, false) => IntCC::UnsignedLessThanOrEqual,
+        (MirBinOp::Add, _) => {
+            let op = format!("{op:?}");
+            return Err(error(messages::UNSUPPORTED_TYPE, &[("type", &op)], span));
+        }
+    };
+    let null = MirOperand::Immediate(MirImmediate::Null);
+    if *lhs == null || *rhs == null {
+        let other = if *lhs == null { rhs } else { lhs };
+        let values = lower_operand(builder, module, other, locals, constants, span)?;
+        return Ok(match values.first() {
+            Some(value) => builder.ins().icmp_imm(cc, *value, 0),
+            // `null == null`
+            None => builder.ins().iconst(types::I8, i64::from(op == MirBinOp::Eq)),
+        });
+    }
+
+    let lhs = lower_operand(builder, module, lhs, locals, constants, span)?;
+    let rhs = lower_operand(builder, module, rhs, locals, constants, span)?;
+    let (Some(lhs), Some(rhs)) = (lhs.first().copied(), rhs.first().copied()) else {
+        return Ok(builder.ins().iconst(types::I8, i64::from(op == MirBinOp::Eq)));
+    };
+    Ok(if builder.func.dfg.value_type(lhs).is_float() {
+        let cc = match op {
+            MirBinOp::Eq => FloatCC::Equal,
+            MirBinOp::Ne => FloatCC::NotEqual,
+            MirBinOp::Lt => FloatCC::LessThan,
+            MirBinOp::Le | MirBinOp::Add => FloatCC::LessThanOrEqual,
+        };
+        builder.ins().fcmp(cc, lhs, rhs)
+    } else {
+        builder.ins().icmp(cc, lhs, rhs)
+    })
+}
+
+fn zero_value(builder: &mut FunctionBuilder<'_>, ty: cranelift_codegen::ir::Type) -> Value {
+    if ty == types::F32 {
+        builder.ins().f32const(0.0)
+    } else if ty == types::F64 {
+        builder.ins().f64const(0.0)
+    } else {
+        builder.ins().iconst(ty, 0)
     }
-    builder.finalize();
-    module.define_function(id, &mut context).map_err(|error| backend(error, function.span))?;
-    module.clear_context(&mut context);
-    Ok(())
 }
 
 fn lower_operand<M: Module>(
@@ -189,26 +2120,30 @@ fn lower_operand<M: Module>(
     module: &mut M,
     operand: &MirOperand,
     locals: &[Vec<Variable>],
-    constants: &[(DataId, usize)],
+    constants: &[Constant],
     _span: runec_source::span::Span,
 ) -> CodegenResult<Vec<Value>> {
     Ok(match operand {
-        MirOperand::Copy(place) => {
+        MirOperand::Copy(place) | MirOperand::Move(place) => {
             locals[place.local.to_usize()].iter().map(|v| builder.use_var(*v)).collect()
         }
         MirOperand::Constant(id) => {
-            let (data_id, len) = constants[id.to_usize()];
-            let data = module.declare_data_in_func(data_id, builder.func);
+            let constant = constants[id.to_usize()];
+            let data = module.declare_data_in_func(constant.data, builder.func);
             let pointer_ty = module.target_config().pointer_type();
+            let base = builder.ins().global_value(pointer_ty, data);
             vec![
-                builder.ins().global_value(pointer_ty, data),
-                builder.ins().iconst(pointer_ty, len as i64),
+                builder.ins().iadd_imm(base, constant.offset),
+                builder.ins().iconst(pointer_ty, constant.len as i64),
             ]
         }
         MirOperand::Immediate(value) => match value {
             MirImmediate::Unit => vec![],
             MirImmediate::Bool(v) => vec![builder.ins().iconst(types::I8, i64::from(*v))],
             MirImmediate::Char(v) => vec![builder.ins().iconst(types::I32, *v as i64)],
+            // `null` only reaches codegen through typed assignments and
+            // comparisons, which materialize it themselves.
+            MirImmediate::Null => vec![],
             MirImmediate::Int { value, ty } => {
                 vec![builder.ins().iconst(int_type(ty.bits), *value as i64)]
             }
@@ -224,35 +2159,138 @@ fn declare_constants<M: Module>(
     module: &mut M,
     mir: &MirModule<'_>,
     diagnostic_span: runec_source::span::Span,
-) -> CodegenResult<Vec<(DataId, usize)>> {
+) -> CodegenResult<Vec<Constant>> {
+    let pointer_bytes = usize::from(module.target_config().pointer_bytes());
+    let big_endian = module.isa().endianness() == Endianness::Big;
     mir.constants
         .iter()
         .enumerate()
         .map(|(index, constant)| {
+            let mut contents = Vec::new();
             let bytes: &[u8] = match constant {
-                MirConstant::Str(v) => v.as_bytes(),
+                MirConstant::Str(v) => {
+                    let header: [u64; HEADER_WORDS] =
+                        [IMMORTAL_REFCOUNT as u64, v.len() as u64, 1, 0];
+                    for word in header {
+                        let word = if big_endian {
+                            word.to_be_bytes()[8 - pointer_bytes..].to_vec()
+                        } else {
+                            word.to_le_bytes()[..pointer_bytes].to_vec()
+                        };
+                        contents.extend(word);
+                    }
+                    v.as_bytes()
+                }
                 MirConstant::Bytes(v) => v.as_ref(),
             };
+            let offset = contents.len() as i64;
+            contents.extend_from_slice(bytes);
             let id = module
                 .declare_data(&format!("__runeway_const_{index}"), Linkage::Local, false, false)
                 .map_err(|error| backend(error, diagnostic_span))?;
             let mut data = DataDescription::new();
-            data.define(bytes.to_vec().into_boxed_slice());
+            data.define(contents.into_boxed_slice());
+            data.set_align(pointer_bytes as u64);
             module.define_data(id, &data).map_err(|error| backend(error, diagnostic_span))?;
-            Ok((id, bytes.len()))
+            Ok(Constant { data: id, offset, len: bytes.len() })
         })
         .collect()
 }
 
+/// Returns the spans of the `panic` calls and bounds checks in `mir`, which
+/// pass their source location to the runtime when they fail. Draws from a
+/// random range are checked like bounds, and errors thrown out of `main`
+/// are reported at their `throw` or `?`.
+pub(crate) fn panic_sites<'a>(mir: &'a MirModule<'_>) -> impl Iterator<Item = Span> + 'a {
+    mir.functions.iter().flat_map(|function| &function.blocks).flat_map(|block| {
+        let checks = block.stmts.iter().filter_map(|stmt| match stmt {
+            MirStmt::StoreIndex { span, .. }
+            | MirStmt::Assign {
+                rhs:
+                    MirRvalue::Index { .. }
+                    | MirRvalue::Slice { .. }
+                    | MirRvalue::Call {
+                        callee:
+                            MirCallee::Runtime(RUNTIME_RANDOM_INT_RANGE | RUNTIME_UNCAUGHT_ERROR),
+                        ..
+                    },
+                span,
+                ..
+            } => Some(*span),
+            _ => None,
+        });
+        let panic = match block.terminator {
+            MirTerminator::Panic { span, .. } => Some(span),
+            _ => None,
+        };
+        checks.chain(panic)
+    })
+}
+
+/// Declares the path of every source file with a `panic` call or bounds
+/// check as data and resolves the line and column of each. Spans outside
+/// the source map are reported at line 0 of `<unknown>`.
+fn declare_panic_locations<M: Module>(
+    module: &mut M,
+    mir: &MirModule<'_>,
+    source_map: &SourceMap,
+    diagnostic_span: Span,
+) -> CodegenResult<HashMap<Span, PanicLocation>> {
+    let mut files = HashMap::<Option<SourceId>, (DataId, usize)>::new();
+    let mut panics = HashMap::new();
+    for span in panic_sites(mir) {
+        let location = source_map.location(span);
+        let source = location.map(|_| span.src_id);
+        let file = match files.get(&source) {
+            Some(file) => *file,
+            None => {
+                let path = location.map_or_else(
+                    || "<unknown>".to_owned(),
+                    |location| location.path.display().to_string(),
+                );
+                let id = module
+                    .declare_data(
+                        &format!("__runeway_source_{}", files.len()),
+                        Linkage::Local,
+                        false,
+                        false,
+                    )
+                    .map_err(|error| backend(error, diagnostic_span))?;
+                let mut data = DataDescription::new();
+                data.define(path.clone().into_bytes().into_boxed_slice());
+                module.define_data(id, &data).map_err(|error| backend(error, diagnostic_span))?;
+                files.insert(source, (id, path.len()));
+                (id, path.len())
+            }
+        };
+        let (line, col) = location.map_or((0, 0), |location| (location.line, location.col));
+        panics.insert(span, PanicLocation { file, line, col });
+    }
+    Ok(panics)
+}
+
 fn signature_for<M: Module>(module: &M, function: &MirFunction<'_>) -> CodegenResult<Signature> {
     let mut signature = module.make_signature();
     for param in function.params.iter() {
         let local = &function.locals[param.to_usize()];
-        for ty in clif_types(module, local.ty, local.span)? {
+        for ty in clif_types(module, &local.ty, local.span)? {
             signature.params.push(AbiParam::new(ty));
         }
     }
-    for ty in clif_types(module, function.ret_ty, function.ret_span)? {
+    for ty in clif_types(module, &function.ret_ty, function.ret_span)? {
+        signature.returns.push(AbiParam::new(ty));
+    }
+    Ok(signature)
+}
+
+fn extern_signature<M: Module>(module: &M, function: &MirExtern<'_>) -> CodegenResult<Signature> {
+    let mut signature = module.make_signature();
+    for ty in function.params.iter() {
+        for ty in clif_types(module, ty, function.span)? {
+            signature.params.push(AbiParam::new(ty));
+        }
+    }
+    for ty in clif_types(module, &function.ret_ty, function.span)? {
         signature.returns.push(AbiParam::new(ty));
     }
     Ok(signature)
@@ -271,7 +2309,7 @@ fn runtime_signature<M: Module>(module: &M, decl: &RuntimeFunctionDecl) -> Signa
 
 fn clif_types<M: Module>(
     module: &M,
-    ty: MirTy,
+    ty: &MirTy,
     span: runec_source::span::Span,
 ) -> CodegenResult<Vec<cranelift_codegen::ir::Type>> {
     Ok(match ty {
@@ -288,6 +2326,29 @@ fn clif_types<M: Module>(
             }
         }],
         MirTy::Str | MirTy::Bytes => vec![module.target_config().pointer_type(); 2],
+        MirTy::Slice(_) => vec![module.target_config().pointer_type(); 3],
+        MirTy::Managed
+        | MirTy::Pointer
+        | MirTy::Closure
+        | MirTy::Cell(_)
+        | MirTy::Vec(_)
+        | MirTy::Map { .. }
+        | MirTy::Record { .. }
+        | MirTy::Union { .. } => vec![module.target_config().pointer_type()],
+        MirTy::Nullable(inner) => {
+            let mut layout = clif_types(module, inner, span)?;
+            if !inner.is_pointer_like() {
+                layout.insert(0, types::I8);
+            }
+            layout
+        }
+        MirTy::Fallible { ok, err } => {
+            let mut layout = vec![types::I8];
+            layout.extend(clif_types(module, ok, span)?);
+            layout.extend(clif_types(module, err, span)?);
+            layout
+        }
+        MirTy::Range(int) => vec![int_type(int.bits), int_type(int.bits), types::I8],
     })
 }
 
@@ -5,7 +5,7 @@ pub enum LintAdm {
     Warn,
 }
 
-const KNOWN_LINTS: &[&str] = &["unknown_lint"];
+const KNOWN_LINTS: &[&str] = &["unknown_lint", "unreachable_pub"];
 
 /// Returns `true` if the given string contains in KNOWN_LINTS.
 pub fn is_lint_known(s: &str) -> bool {
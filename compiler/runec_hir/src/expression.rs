@@ -1,13 +1,17 @@
 use std::borrow::Cow;
 
+use runec_ast::SpannedStr;
 use runec_ast::expression::{FloatSuffix, IntSuffix};
+use runec_ast::operators::BinaryOp;
 use runec_source::span::Spanned;
 
-use crate::path::HirPath;
+use crate::ids::HirLocalId;
+use crate::path::{HirGenericArg, HirPath};
 use crate::resolution::Res;
 use crate::statement::HirBlock;
+use crate::ty::SpannedHirType;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum HirExpr<'src> {
     Error,
     Literal(HirLiteral<'src>),
@@ -18,21 +22,159 @@ pub enum HirExpr<'src> {
     /// A value namespace path filled in by name resolution.
     Resolved(Res),
 
+    /// A read of a nullable local inside a branch where it was checked
+    /// against `null`; filled in by name resolution.
+    NonNullLocal(HirLocalId),
+
+    /// `callee::<A, B>`; a function or builtin with explicit generic
+    /// arguments. Unlike those of a type path, they are kept next to the
+    /// callee, which name resolution resolves on its own.
+    Turbofish {
+        callee: Box<SpannedHirExpr<'src>>,
+        args: Box<[HirGenericArg<'src>]>,
+    },
+
     Call {
         callee: Box<SpannedHirExpr<'src>>,
         args: Box<[SpannedHirExpr<'src>]>,
     },
 
+    Binary {
+        op: BinaryOp,
+        lhs: Box<SpannedHirExpr<'src>>,
+        rhs: Box<SpannedHirExpr<'src>>,
+    },
+
+    /// `base[index]`; an element of a vector or slice, or a slice of it
+    /// when `index` is a range.
+    Index {
+        base: Box<SpannedHirExpr<'src>>,
+        index: Box<SpannedHirExpr<'src>>,
+    },
+
+    /// `base.name`; a struct or union field, or a method of a builtin type
+    /// when called.
+    Field {
+        base: Box<SpannedHirExpr<'src>>,
+        name: SpannedStr<'src>,
+    },
+
+    /// `base.0`; an element of a tuple, by its index.
+    TupleField {
+        base: Box<SpannedHirExpr<'src>>,
+        index: usize,
+    },
+
+    /// `(a, b)`; a tuple value.
+    Tuple(Box<[SpannedHirExpr<'src>]>),
+
+    /// `value?`; `value` is a call to a throwing function.
+    Try(Box<SpannedHirExpr<'src>>),
+
+    /// `*ptr`; only allowed inside `unsafe`.
+    Deref(Box<SpannedHirExpr<'src>>),
+
     Block(HirBlock<'src>),
+
+    /// `unsafe { ... }`
+    Unsafe(HirBlock<'src>),
+
+    /// `if cond { ... } else ...`; an `else if` chain nests another `If` in
+    /// `else_`.
+    If {
+        cond: Box<SpannedHirExpr<'src>>,
+        then: HirBlock<'src>,
+        else_: Option<Box<SpannedHirExpr<'src>>>,
+    },
+
+    /// `|a, b: T| body`
+    Closure(HirClosure<'src>),
+
+    /// `start..end`, or `start..=end` when `inclusive`.
+    Range {
+        start: Box<SpannedHirExpr<'src>>,
+        end: Box<SpannedHirExpr<'src>>,
+        inclusive: bool,
+    },
+
+    /// `for name in iter { ... }`; `local` is filled in by name resolution
+    /// and only visible in `body`. A tuple pattern such as `for (i, item) in
+    /// iter` is bound to a hidden local whose elements `body` starts by
+    /// binding with `let`s.
+    For {
+        local: Option<HirLocalId>,
+        name: SpannedStr<'src>,
+        iter: Box<SpannedHirExpr<'src>>,
+        body: HirBlock<'src>,
+    },
+
+    /// `Name { field: value, ... }`; a struct value, or a value of an enum
+    /// variant with named fields. `res` is filled in by name resolution.
+    Struct {
+        path: HirPath<'src>,
+        res: Option<Res>,
+        fields: Box<[HirFieldValue<'src>]>,
+    },
 }
 
 pub type SpannedHirExpr<'src> = Spanned<HirExpr<'src>>;
 
-#[derive(Debug, PartialEq)]
+/// `name: value` in a struct expression.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HirFieldValue<'src> {
+    pub name: SpannedStr<'src>,
+    pub value: SpannedHirExpr<'src>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HirClosure<'src> {
+    pub params: Box<[HirClosureParam<'src>]>,
+    pub body: Box<SpannedHirExpr<'src>>,
+    /// Locals of the enclosing function used in `body`; filled in by name
+    /// resolution.
+    pub captures: Box<[HirCapture]>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HirClosureParam<'src> {
+    /// Filled in by name resolution; closure parameters are locals of the
+    /// enclosing function.
+    pub local: Option<HirLocalId>,
+    pub name: SpannedStr<'src>,
+    pub ty: Option<SpannedHirType<'src>>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct HirCapture {
+    pub local: HirLocalId,
+    pub mode: HirCaptureMode,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HirCaptureMode {
+    /// The closure takes the value when it is created: copy values are
+    /// copied, and any other value is moved into the closure.
+    ByValue,
+    /// The closure shares the variable with the enclosing function, since
+    /// some closure assigns to it.
+    ByRef,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum HirLiteral<'src> {
     Int { value: u128, suffix: Option<IntSuffix> },
     Float { value: f64, suffix: Option<FloatSuffix> },
     Bool(bool),
     Char(char),
     Str(Cow<'src, str>),
+    Null,
+}
+
+/// Returns the function or builtin `expr` calls, looking through explicit
+/// generic arguments.
+pub fn strip_turbofish<'a, 'src>(expr: &'a SpannedHirExpr<'src>) -> &'a SpannedHirExpr<'src> {
+    match &expr.node {
+        HirExpr::Turbofish { callee, .. } => callee,
+        _ => expr,
+    }
 }
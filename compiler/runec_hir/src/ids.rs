@@ -24,3 +24,20 @@ impl HirLocalId {
         self.0 as usize
     }
 }
+
+/// Index of a module in the crate's module tree.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HirModuleId(u32);
+
+impl HirModuleId {
+    /// The crate root, i.e. the file compilation started from.
+    pub const ROOT: HirModuleId = HirModuleId(0);
+
+    pub fn from_usize(id: usize) -> HirModuleId {
+        assert!(id <= u32::MAX as usize, "HirModuleId overflow");
+        HirModuleId(id as u32)
+    }
+    pub fn to_usize(self) -> usize {
+        self.0 as usize
+    }
+}
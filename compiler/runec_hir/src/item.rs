@@ -1,39 +1,65 @@
 use runec_ast::SpannedStr;
+use runec_ast::statement::Visibility;
+use runec_builtins::ContractId;
 use runec_source::span::Span;
 
 use crate::ids::HirId;
+use crate::path::HirPath;
 use crate::statement::HirBlock;
 use crate::ty::SpannedHirType;
 
 #[derive(Debug, PartialEq)]
 pub enum HirItem<'src> {
     Struct(HirStruct<'src>),
+    Union(HirUnion<'src>),
     Enum(HirEnum<'src>),
     Function(HirFunction<'src>),
+    ExternFunction(HirExternFunction<'src>),
+    Alias(HirAlias<'src>),
 }
 
 impl<'src> HirItem<'src> {
     pub fn id(&self) -> HirId {
         match self {
             HirItem::Struct(s) => s.id,
+            HirItem::Union(u) => u.id,
             HirItem::Enum(e) => e.id,
             HirItem::Function(f) => f.id,
+            HirItem::ExternFunction(f) => f.id,
+            HirItem::Alias(a) => a.id,
         }
     }
 
     pub fn name(&self) -> &SpannedStr<'src> {
         match self {
             HirItem::Struct(s) => &s.name,
+            HirItem::Union(u) => &u.name,
             HirItem::Enum(e) => &e.name,
             HirItem::Function(f) => &f.name,
+            HirItem::ExternFunction(f) => &f.name,
+            HirItem::Alias(a) => &a.name,
+        }
+    }
+
+    pub fn vis(&self) -> Visibility {
+        match self {
+            HirItem::Struct(s) => s.vis,
+            HirItem::Union(u) => u.vis,
+            HirItem::Enum(e) => e.vis,
+            HirItem::Function(f) => f.vis,
+            HirItem::ExternFunction(f) => f.vis,
+            HirItem::Alias(a) => a.vis,
         }
     }
 
     pub fn span(&self) -> Span {
         match self {
             HirItem::Struct(s) => s.span,
+            HirItem::Union(u) => u.span,
             HirItem::Enum(e) => e.span,
             HirItem::Function(f) => f.span,
+            HirItem::ExternFunction(f) => f.span,
+            HirItem::Alias(a) => a.span,
         }
     }
 }
@@ -41,13 +67,57 @@ impl<'src> HirItem<'src> {
 #[derive(Debug, PartialEq)]
 pub struct HirFunction<'src> {
     pub id: HirId,
+    pub vis: Visibility,
     pub name: SpannedStr<'src>,
+    pub generics: Box<[HirGenericParam<'src>]>,
     pub params: Box<[HirFunctionParam<'src>]>,
     pub ret_ty: SpannedHirType<'src>,
+    /// Error type from a `throws` clause.
+    pub throws: Option<SpannedHirType<'src>>,
     pub body: HirBlock<'src>,
     pub span: Span,
 }
 
+/// `extern act`; a native function called through the C ABI by its
+/// unmangled name. Calling one is `unsafe`.
+#[derive(Debug, PartialEq)]
+pub struct HirExternFunction<'src> {
+    pub id: HirId,
+    pub vis: Visibility,
+    pub name: SpannedStr<'src>,
+    pub params: Box<[HirFunctionParam<'src>]>,
+    pub ret_ty: SpannedHirType<'src>,
+    pub span: Span,
+}
+
+/// Generic parameter of a function, struct, union or alias. Types inside the item refer to
+/// it by its index through [`crate::ty::HirType::Param`].
+#[derive(Debug, PartialEq)]
+pub struct HirGenericParam<'src> {
+    pub name: SpannedStr<'src>,
+    pub kind: HirGenericParamKind<'src>,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum HirGenericParamKind<'src> {
+    Type {
+        bounds: Box<[HirBound<'src>]>,
+    },
+    /// `const N: ty`
+    Const {
+        ty: SpannedHirType<'src>,
+    },
+}
+
+/// Contract a type parameter must implement.
+#[derive(Debug, PartialEq)]
+pub struct HirBound<'src> {
+    pub path: HirPath<'src>,
+    /// Filled in by the resolver.
+    pub contract: Option<ContractId>,
+}
+
 #[derive(Debug, PartialEq)]
 pub struct HirFunctionParam<'src> {
     pub name: SpannedStr<'src>,
@@ -58,13 +128,27 @@ pub struct HirFunctionParam<'src> {
 #[derive(Debug, PartialEq)]
 pub struct HirStruct<'src> {
     pub id: HirId,
+    pub vis: Visibility,
+    pub name: SpannedStr<'src>,
+    pub generics: Box<[HirGenericParam<'src>]>,
+    pub fields: Box<[HirField<'src>]>,
+    pub span: Span,
+}
+
+/// `union`; every field starts at offset 0 and reading one is `unsafe`.
+#[derive(Debug, PartialEq)]
+pub struct HirUnion<'src> {
+    pub id: HirId,
+    pub vis: Visibility,
     pub name: SpannedStr<'src>,
+    pub generics: Box<[HirGenericParam<'src>]>,
     pub fields: Box<[HirField<'src>]>,
     pub span: Span,
 }
 
 #[derive(Debug, PartialEq)]
 pub struct HirField<'src> {
+    pub vis: Visibility,
     pub name: SpannedStr<'src>,
     pub ty: SpannedHirType<'src>,
     pub span: Span,
@@ -73,6 +157,7 @@ pub struct HirField<'src> {
 #[derive(Debug, PartialEq)]
 pub struct HirEnum<'src> {
     pub id: HirId,
+    pub vis: Visibility,
     pub name: SpannedStr<'src>,
     pub variants: Box<[HirVariant<'src>]>,
     pub span: Span,
@@ -80,6 +165,8 @@ pub struct HirEnum<'src> {
 
 #[derive(Debug, PartialEq)]
 pub struct HirVariant<'src> {
+    /// Variants are as visible as their enum unless marked `pub`.
+    pub vis: Visibility,
     pub name: SpannedStr<'src>,
     pub payload: HirVariantPayload<'src>,
     pub span: Span,
@@ -91,3 +178,15 @@ pub enum HirVariantPayload<'src> {
     Tuple(Box<[SpannedHirType<'src>]>),
     Struct(Box<[HirField<'src>]>),
 }
+
+/// `alias Name<T> = Type;`. Uses of the alias are replaced by `ty` during
+/// name resolution, which also writes the resolved target back here.
+#[derive(Debug, PartialEq)]
+pub struct HirAlias<'src> {
+    pub id: HirId,
+    pub vis: Visibility,
+    pub name: SpannedStr<'src>,
+    pub generics: Box<[HirGenericParam<'src>]>,
+    pub ty: SpannedHirType<'src>,
+    pub span: Span,
+}
@@ -3,6 +3,7 @@ pub mod ids;
 pub mod item;
 pub mod lowering;
 pub mod map;
+pub mod module;
 pub mod path;
 pub mod resolution;
 pub mod statement;
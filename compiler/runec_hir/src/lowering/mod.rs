@@ -1,19 +1,33 @@
 use runec_ast::SpannedStr;
-use runec_ast::ast_type::{SpannedTypeAnnotation, TypeAnnotation};
-use runec_ast::expression::{Expr, PrimitiveValue, SpannedExpr};
-use runec_ast::statement::{DestructPattern, SpannedStmt, SpannedStmtBlock, Stmt};
+use runec_ast::ast_type::{GenericArgument, SpannedTypeAnnotation, TypeAnnotation};
+use runec_ast::expression::{ElseBranch, Expr, IfExpr, PrimitiveValue, SpannedExpr};
+use runec_ast::statement::{
+    DestructPattern, FunctionArg, GenericParam, GenericParamKind, SpannedDestructPattern,
+    SpannedStmt, SpannedStmtBlock, Stmt, StructField, UseKind,
+};
 use runec_errors::diagnostics::Diagnostic;
 use runec_errors::labels::DiagLabel;
 use runec_errors::message::DiagMessage;
 use runec_source::span::{Span, Spanned};
 
-use crate::expression::{HirExpr, HirLiteral, SpannedHirExpr};
-use crate::item::{HirFunction, HirFunctionParam, HirItem};
+use crate::expression::{
+    HirClosure, HirClosureParam, HirExpr, HirFieldValue, HirLiteral, SpannedHirExpr,
+};
+use crate::ids::HirModuleId;
+use crate::item::{
+    HirAlias, HirBound, HirExternFunction, HirField, HirFunction, HirFunctionParam,
+    HirGenericParam, HirGenericParamKind, HirItem, HirStruct, HirUnion,
+};
 use crate::map::HirMap;
-use crate::path::{HirPath, HirPathSegment};
+use crate::module::{HirImport, HirUse, HirUseKind};
+use crate::path::{HirGenericArg, HirPath, HirPathSegment};
 use crate::statement::{HirBlock, HirStmt};
 use crate::ty::{HirType, SpannedHirType};
 
+/// Name of the hidden local a tuple pattern binds its whole value to. No
+/// identifier starts with `$`, so it never shadows a user binding.
+const TUPLE_LOCAL: &str = "$tuple";
+
 pub struct HirLowerResult<'src, 'diag> {
     pub map: HirMap<'src>,
     pub diags: Vec<Diagnostic<'diag>>,
@@ -25,15 +39,25 @@ impl<'src, 'diag> HirLowerResult<'src, 'diag> {
     }
 }
 
+/// Parsed statements of one module file, with the module path counted from
+/// the crate root (empty for the root file itself).
+pub struct HirModuleSource<'a, 'src> {
+    pub path: Box<[&'src str]>,
+    pub stmts: &'a [SpannedStmt<'src>],
+}
+
 pub struct HirLowerer<'src, 'diag> {
     res: HirLowerResult<'src, 'diag>,
+    /// Module receiving the items being lowered.
+    module: HirModuleId,
 }
 
 impl<'src, 'diag> HirLowerer<'src, 'diag> {
     pub fn new() -> Self {
-        Self { res: HirLowerResult::new() }
+        Self { res: HirLowerResult::new(), module: HirModuleId::ROOT }
     }
 
+    /// Lowers a single-file crate.
     pub fn lower(mut self, stmts: &[SpannedStmt<'src>]) -> HirLowerResult<'src, 'diag> {
         for stmt in stmts {
             self.lower_top_stmt(stmt);
@@ -41,42 +65,194 @@ impl<'src, 'diag> HirLowerer<'src, 'diag> {
         self.res
     }
 
+    /// Lowers every file of a crate into its own module of the tree.
+    pub fn lower_modules(
+        mut self,
+        modules: &[HirModuleSource<'_, 'src>],
+    ) -> HirLowerResult<'src, 'diag> {
+        for source in modules {
+            self.module = source
+                .path
+                .iter()
+                .fold(HirModuleId::ROOT, |parent, name| self.res.map.add_module(parent, name));
+            for stmt in source.stmts {
+                self.lower_top_stmt(stmt);
+            }
+        }
+        self.res
+    }
+
     // ---- top-level items ----
 
     fn lower_top_stmt(&mut self, stmt: &SpannedStmt<'src>) {
         match &stmt.node {
-            Stmt::DefineFunction { ident, args, ret_ty, body } => {
+            Stmt::DefineFunction { vis, ident, generics, args, ret_ty, throws, body } => {
                 let id = self.res.map.reserve_id();
-                let params: Box<[_]> = args
-                    .iter()
-                    .map(|a| {
-                        let ty = self.lower_type(&a.ty);
-                        HirFunctionParam {
-                            name: SpannedStr::new(a.ident.node, a.ident.span),
-                            ty,
-                            span: a.ident.span,
-                        }
-                    })
-                    .collect();
+                let generics = self.lower_generic_params(generics);
+                let params = self.lower_params(args);
                 let ret_ty = self.lower_type(ret_ty);
+                let throws = throws.as_ref().map(|ty| self.lower_type(ty));
                 let body = self.lower_block(body);
-                self.res.map.push(HirItem::Function(HirFunction {
-                    id,
-                    name: SpannedStr::new(ident.node, ident.span),
-                    params,
-                    ret_ty,
-                    body,
-                    span: stmt.span,
-                }));
+                self.res.map.push_in(
+                    self.module,
+                    HirItem::Function(HirFunction {
+                        id,
+                        vis: *vis,
+                        name: SpannedStr::new(ident.node, ident.span),
+                        generics,
+                        params,
+                        ret_ty,
+                        throws,
+                        body,
+                        span: stmt.span,
+                    }),
+                );
+            }
+            Stmt::DefineExternFunction { vis, ident, args, ret_ty } => {
+                let id = self.res.map.reserve_id();
+                let params = self.lower_params(args);
+                let ret_ty = self.lower_type(ret_ty);
+                self.res.map.push_in(
+                    self.module,
+                    HirItem::ExternFunction(HirExternFunction {
+                        id,
+                        vis: *vis,
+                        name: SpannedStr::new(ident.node, ident.span),
+                        params,
+                        ret_ty,
+                        span: stmt.span,
+                    }),
+                );
+            }
+            Stmt::DefineStruct { vis, ident, generics, fields } => {
+                let id = self.res.map.reserve_id();
+                let generics = self.lower_generic_params(generics);
+                let fields = self.lower_fields(fields);
+                self.res.map.push_in(
+                    self.module,
+                    HirItem::Struct(HirStruct {
+                        id,
+                        vis: *vis,
+                        name: SpannedStr::new(ident.node, ident.span),
+                        generics,
+                        fields,
+                        span: stmt.span,
+                    }),
+                );
+            }
+            Stmt::DefineUnion { vis, ident, generics, fields } => {
+                let id = self.res.map.reserve_id();
+                let generics = self.lower_generic_params(generics);
+                let fields = self.lower_fields(fields);
+                self.res.map.push_in(
+                    self.module,
+                    HirItem::Union(HirUnion {
+                        id,
+                        vis: *vis,
+                        name: SpannedStr::new(ident.node, ident.span),
+                        generics,
+                        fields,
+                        span: stmt.span,
+                    }),
+                );
+            }
+            Stmt::DefineAlias { vis, ident, generics, ty } => {
+                let id = self.res.map.reserve_id();
+                let generics = self.lower_generic_params(generics);
+                let ty = self.lower_type(ty);
+                self.res.map.push_in(
+                    self.module,
+                    HirItem::Alias(HirAlias {
+                        id,
+                        vis: *vis,
+                        name: SpannedStr::new(ident.node, ident.span),
+                        generics,
+                        ty,
+                        span: stmt.span,
+                    }),
+                );
+            }
+            Stmt::Import(path) => {
+                let import = HirImport { path: Self::segments_path(false, path), span: stmt.span };
+                self.res.map.module_mut(self.module).imports.push(import);
+            }
+            Stmt::Use { from_root, path, kind } => {
+                let kind = match kind {
+                    UseKind::Single { alias } => HirUseKind::Single {
+                        alias: alias.as_ref().map(|alias| SpannedStr::new(alias.node, alias.span)),
+                    },
+                    UseKind::Glob => HirUseKind::Glob,
+                };
+                let use_ =
+                    HirUse { path: Self::segments_path(*from_root, path), kind, span: stmt.span };
+                self.res.map.module_mut(self.module).uses.push(use_);
             }
             Stmt::DefineLet { .. }
             | Stmt::DefineConst { .. }
             | Stmt::Assign { .. }
+            | Stmt::AssignPlace { .. }
+            | Stmt::Throw(_)
+            | Stmt::Break
             | Stmt::SemiExpr(_)
             | Stmt::TailExpr(_) => self.push_unsupported("top-level statement", stmt.span),
         }
     }
 
+    fn lower_params(&mut self, args: &[FunctionArg<'src>]) -> Box<[HirFunctionParam<'src>]> {
+        args.iter()
+            .map(|a| HirFunctionParam {
+                name: SpannedStr::new(a.ident.node, a.ident.span),
+                ty: self.lower_type(&a.ty),
+                span: a.ident.span,
+            })
+            .collect()
+    }
+
+    fn lower_fields(&mut self, fields: &[StructField<'src>]) -> Box<[HirField<'src>]> {
+        fields
+            .iter()
+            .map(|field| HirField {
+                vis: field.vis,
+                name: SpannedStr::new(field.ident.node, field.ident.span),
+                ty: self.lower_type(&field.ty),
+                span: Span::new(field.ident.span.lo, field.ty.span.hi, field.ty.span.src_id),
+            })
+            .collect()
+    }
+
+    fn lower_generic_params(
+        &mut self,
+        generics: &[GenericParam<'src>],
+    ) -> Box<[HirGenericParam<'src>]> {
+        generics
+            .iter()
+            .map(|param| {
+                let kind = match &param.kind {
+                    GenericParamKind::Type { bounds } => HirGenericParamKind::Type {
+                        bounds: bounds
+                            .iter()
+                            .filter_map(|bound| {
+                                let path = self.lower_type_path(bound);
+                                if path.is_none() {
+                                    self.push_unsupported("contract bound", bound.span);
+                                }
+                                Some(HirBound { path: path?, contract: None })
+                            })
+                            .collect(),
+                    },
+                    GenericParamKind::Const { ty } => {
+                        HirGenericParamKind::Const { ty: self.lower_type(ty) }
+                    }
+                };
+                HirGenericParam {
+                    name: SpannedStr::new(param.ident.node, param.ident.span),
+                    kind,
+                    span: param.ident.span,
+                }
+            })
+            .collect()
+    }
+
     // ---- blocks & statements ----
 
     fn lower_block(&mut self, block: &SpannedStmtBlock<'src>) -> HirBlock<'src> {
@@ -95,24 +271,61 @@ impl<'src, 'diag> HirLowerer<'src, 'diag> {
                 Stmt::DefineLet { pattern, is_mutable, ty, init_expr } => {
                     let name = match &pattern.node {
                         DestructPattern::Ident(n) => SpannedStr::new(n, pattern.span),
-                        DestructPattern::Tuple(_) | DestructPattern::AttributeAccess { .. } => {
+                        DestructPattern::Tuple(_) => SpannedStr::new(TUPLE_LOCAL, pattern.span),
+                        DestructPattern::AttributeAccess { .. } => {
                             self.push_unsupported("let destructuring", pattern.span);
                             continue;
                         }
                     };
                     let ty = ty.as_ref().map(|t| self.lower_type(t));
                     let init = init_expr.as_ref().map(|e| self.lower_expr(e));
+                    let is_tuple = name.node == TUPLE_LOCAL;
+                    if is_tuple && init.is_none() {
+                        self.push_unsupported("tuple pattern without a value", pattern.span);
+                        continue;
+                    }
+                    let path = Self::ident_path(&name);
                     stmts.push(HirStmt::Let {
                         local: None,
                         name,
-                        is_mutable: *is_mutable,
+                        is_mutable: *is_mutable && !is_tuple,
                         ty,
                         init,
                         span: s.span,
                     });
+                    if is_tuple {
+                        let tuple = Spanned::new(HirExpr::Path(path), pattern.span);
+                        self.lower_tuple_pattern(pattern, tuple, *is_mutable, s.span, &mut stmts);
+                    }
                 }
-                Stmt::DefineFunction { .. } | Stmt::DefineConst { .. } | Stmt::Assign { .. } => {
-                    self.push_unsupported("nested definition or assignment", s.span);
+                Stmt::Assign { ident, expr } => {
+                    stmts.push(HirStmt::Assign {
+                        target: Spanned::new(HirExpr::Path(Self::ident_path(ident)), ident.span),
+                        value: self.lower_expr(expr),
+                        span: s.span,
+                    });
+                }
+                Stmt::AssignPlace { target, expr } => {
+                    stmts.push(HirStmt::Assign {
+                        target: self.lower_expr(target),
+                        value: self.lower_expr(expr),
+                        span: s.span,
+                    });
+                }
+                Stmt::Throw(value) => {
+                    stmts.push(HirStmt::Throw { value: self.lower_expr(value), span: s.span });
+                }
+                Stmt::Break => stmts.push(HirStmt::Break { span: s.span }),
+                Stmt::DefineFunction { .. }
+                | Stmt::DefineExternFunction { .. }
+                | Stmt::DefineStruct { .. }
+                | Stmt::DefineUnion { .. }
+                | Stmt::DefineAlias { .. }
+                | Stmt::DefineConst { .. } => {
+                    self.push_unsupported("nested definition", s.span);
+                }
+                Stmt::Import(_) | Stmt::Use { .. } => {
+                    self.push_unsupported("nested import", s.span);
                 }
             }
         }
@@ -126,27 +339,14 @@ impl<'src, 'diag> HirLowerer<'src, 'diag> {
         let hir = match &expr.node {
             Expr::Primitive(p) => HirExpr::Literal(Self::lower_literal(p)),
 
-            Expr::Ident(name) => HirExpr::Path(HirPath {
-                from_root: false,
-                segments: Box::new([HirPathSegment {
-                    name: SpannedStr::new(name, expr.span),
-                    generics: None,
-                    span: expr.span,
-                }]),
-                span: expr.span,
-            }),
+            Expr::Ident(name) => HirExpr::Path(Self::ident_path(&SpannedStr::new(name, expr.span))),
 
-            Expr::Path(segments) => {
-                let segments: Box<[_]> = segments
-                    .iter()
-                    .map(|s| HirPathSegment {
-                        name: SpannedStr::new(s.node, s.span),
-                        generics: None,
-                        span: s.span,
-                    })
-                    .collect();
-                HirExpr::Path(HirPath { from_root: false, segments, span: expr.span })
-            }
+            Expr::Path(segments) => HirExpr::Path(Self::segments_path(false, segments)),
+
+            Expr::Turbofish { path, args } => HirExpr::Turbofish {
+                callee: Box::new(self.lower_expr(path)),
+                args: self.lower_generic_args(args),
+            },
 
             Expr::Call { callee, args } => HirExpr::Call {
                 callee: Box::new(self.lower_expr(callee)),
@@ -155,15 +355,107 @@ impl<'src, 'diag> HirLowerer<'src, 'diag> {
 
             Expr::Block(b) => HirExpr::Block(self.lower_block(b)),
 
-            Expr::If(_)
-            | Expr::TypeCast { .. }
-            | Expr::Binary { .. }
+            Expr::If(if_expr) => self.lower_if(if_expr),
+
+            Expr::Binary { lhs, rhs, op } => HirExpr::Binary {
+                op: *op,
+                lhs: Box::new(self.lower_expr(lhs)),
+                rhs: Box::new(self.lower_expr(rhs)),
+            },
+
+            Expr::Try(inner) => HirExpr::Try(Box::new(self.lower_expr(inner))),
+            Expr::Index { value, index } => HirExpr::Index {
+                base: Box::new(self.lower_expr(value)),
+                index: Box::new(self.lower_expr(index)),
+            },
+
+            Expr::Deref(inner) => HirExpr::Deref(Box::new(self.lower_expr(inner))),
+
+            Expr::Unsafe(b) => HirExpr::Unsafe(self.lower_block(b)),
+
+            Expr::AttributeAccess { value, name } => match name.node.parse() {
+                Ok(index) => HirExpr::TupleField { base: Box::new(self.lower_expr(value)), index },
+                Err(_) => HirExpr::Field {
+                    base: Box::new(self.lower_expr(value)),
+                    name: SpannedStr::new(name.node, name.span),
+                },
+            },
+
+            Expr::Tuple(items) => {
+                HirExpr::Tuple(items.iter().map(|e| self.lower_expr(e)).collect())
+            }
+
+            Expr::Closure { params, body } => HirExpr::Closure(HirClosure {
+                params: params
+                    .iter()
+                    .map(|param| HirClosureParam {
+                        local: None,
+                        name: SpannedStr::new(param.ident.node, param.ident.span),
+                        ty: param.ty.as_ref().map(|ty| self.lower_type(ty)),
+                    })
+                    .collect(),
+                body: Box::new(self.lower_expr(body)),
+                captures: Box::new([]),
+            }),
+
+            Expr::Range { start, end, inclusive } => HirExpr::Range {
+                start: Box::new(self.lower_expr(start)),
+                end: Box::new(self.lower_expr(end)),
+                inclusive: *inclusive,
+            },
+
+            Expr::For { binding, iter, body } => {
+                let name = match &binding.node {
+                    DestructPattern::Ident(name) => SpannedStr::new(name, binding.span),
+                    _ => SpannedStr::new(TUPLE_LOCAL, binding.span),
+                };
+                let iter = Box::new(self.lower_expr(iter));
+                let mut body = self.lower_block(body);
+                if name.node == TUPLE_LOCAL {
+                    let mut stmts = Vec::new();
+                    let tuple = Spanned::new(HirExpr::Path(Self::ident_path(&name)), binding.span);
+                    self.lower_tuple_pattern(binding, tuple, false, binding.span, &mut stmts);
+                    stmts.extend(body.stmts);
+                    body.stmts = stmts.into_boxed_slice();
+                }
+                HirExpr::For { local: None, name, iter, body }
+            }
+
+            Expr::Struct { path, fields } => {
+                // `Name::<A, B> { .. }` gives the generic arguments of the
+                // last segment.
+                let (path, generics) = match &path.node {
+                    Expr::Turbofish { path, args } => (&**path, Some(args)),
+                    _ => (&**path, None),
+                };
+                let mut path = match &path.node {
+                    Expr::Ident(name) => Self::ident_path(&SpannedStr::new(name, path.span)),
+                    Expr::Path(segments) => Self::segments_path(false, segments),
+                    _ => {
+                        self.push_unsupported("struct path", path.span);
+                        return Spanned::new(HirExpr::Error, expr.span);
+                    }
+                };
+                if let (Some(args), Some(segment)) = (generics, path.segments.last_mut()) {
+                    segment.generics = Some(self.lower_generic_args(args));
+                }
+                HirExpr::Struct {
+                    path,
+                    res: None,
+                    fields: fields
+                        .iter()
+                        .map(|field| HirFieldValue {
+                            name: SpannedStr::new(field.name.node, field.name.span),
+                            value: self.lower_expr(&field.value),
+                        })
+                        .collect(),
+                }
+            }
+
+            Expr::TypeCast { .. }
             | Expr::Unary { .. }
-            | Expr::Tuple(_)
             | Expr::FullyDefinedArray(_)
-            | Expr::RepeatingArray { .. }
-            | Expr::Deref(_)
-            | Expr::AttributeAccess { .. } => {
+            | Expr::RepeatingArray { .. } => {
                 self.push_unsupported("expression", expr.span);
                 HirExpr::Error
             }
@@ -171,6 +463,84 @@ impl<'src, 'diag> HirLowerer<'src, 'diag> {
         Spanned::new(hir, expr.span)
     }
 
+    /// Binds the elements of `value` to the identifiers of tuple pattern
+    /// `pattern` with one `let` each, reading nested elements through chains
+    /// of `.0`, `.1` and so on.
+    fn lower_tuple_pattern(
+        &mut self,
+        pattern: &SpannedDestructPattern<'src>,
+        value: SpannedHirExpr<'src>,
+        is_mutable: bool,
+        span: Span,
+        stmts: &mut Vec<HirStmt<'src>>,
+    ) {
+        match &pattern.node {
+            DestructPattern::Ident(name) => stmts.push(HirStmt::Let {
+                local: None,
+                name: SpannedStr::new(name, pattern.span),
+                is_mutable,
+                ty: None,
+                init: Some(value),
+                span,
+            }),
+            DestructPattern::Tuple(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    let element = HirExpr::TupleField { base: Box::new(value.clone()), index };
+                    let element = Spanned::new(element, item.span);
+                    self.lower_tuple_pattern(item, element, is_mutable, span, stmts);
+                }
+            }
+            DestructPattern::AttributeAccess { .. } => {
+                self.push_unsupported("destructuring of fields", pattern.span);
+            }
+        }
+    }
+
+    fn lower_if(&mut self, if_expr: &IfExpr<'src>) -> HirExpr<'src> {
+        let else_ = if_expr.else_.as_ref().map(|else_| {
+            Box::new(match else_ {
+                ElseBranch::Block(block) => {
+                    Spanned::new(HirExpr::Block(self.lower_block(block)), block.span)
+                }
+                ElseBranch::If(nested) => Spanned::new(self.lower_if(&nested.node), nested.span),
+            })
+        });
+
+        HirExpr::If {
+            cond: Box::new(self.lower_expr(&if_expr.cond)),
+            then: self.lower_block(&if_expr.then),
+            else_,
+        }
+    }
+
+    fn ident_path(ident: &SpannedStr<'src>) -> HirPath<'src> {
+        HirPath {
+            from_root: false,
+            segments: Box::new([HirPathSegment {
+                name: SpannedStr::new(ident.node, ident.span),
+                generics: None,
+                span: ident.span,
+            }]),
+            span: ident.span,
+        }
+    }
+
+    fn segments_path(from_root: bool, segments: &[SpannedStr<'src>]) -> HirPath<'src> {
+        let span = match (segments.first(), segments.last()) {
+            (Some(first), Some(last)) => Span::new(first.span.lo, last.span.hi, first.span.src_id),
+            _ => unreachable!("the parser never produces empty paths"),
+        };
+        let segments = segments
+            .iter()
+            .map(|s| HirPathSegment {
+                name: SpannedStr::new(s.node, s.span),
+                generics: None,
+                span: s.span,
+            })
+            .collect();
+        HirPath { from_root, segments, span }
+    }
+
     fn lower_literal(p: &PrimitiveValue<'src>) -> HirLiteral<'src> {
         match p {
             PrimitiveValue::Int { value, suffix } => {
@@ -183,6 +553,7 @@ impl<'src, 'diag> HirLowerer<'src, 'diag> {
             PrimitiveValue::False => HirLiteral::Bool(false),
             PrimitiveValue::Char(c) => HirLiteral::Char(*c),
             PrimitiveValue::String(s) => HirLiteral::Str(s.clone()),
+            PrimitiveValue::Null => HirLiteral::Null,
         }
     }
 
@@ -192,15 +563,15 @@ impl<'src, 'diag> HirLowerer<'src, 'diag> {
         let hir = match &ty.node {
             TypeAnnotation::Unit => HirType::Unit,
 
-            TypeAnnotation::Ident(name) => HirType::Unresolved(HirPath {
-                from_root: false,
-                segments: Box::new([HirPathSegment {
-                    name: SpannedStr::new(name, ty.span),
-                    generics: None,
-                    span: ty.span,
-                }]),
-                span: ty.span,
-            }),
+            TypeAnnotation::Ident(_)
+            | TypeAnnotation::Path { .. }
+            | TypeAnnotation::Generic { .. } => match self.lower_type_path(ty) {
+                Some(path) => HirType::Unresolved(path),
+                None => {
+                    self.push_unsupported("type", ty.span);
+                    HirType::Error
+                }
+            },
 
             TypeAnnotation::Tuple(items) => {
                 HirType::Tuple(items.iter().map(|t| self.lower_type(t)).collect())
@@ -211,14 +582,67 @@ impl<'src, 'diag> HirLowerer<'src, 'diag> {
                 len: Box::new(self.lower_expr(length)),
             },
 
-            TypeAnnotation::Path { .. } | TypeAnnotation::Generic { .. } => {
-                self.push_unsupported("type", ty.span);
-                HirType::Error
+            TypeAnnotation::Slice(elem) => HirType::Slice(Box::new(self.lower_type(elem))),
+            TypeAnnotation::Nullable(inner) => HirType::Nullable(Box::new(self.lower_type(inner))),
+
+            TypeAnnotation::Pointer { mutable, pointee } => {
+                HirType::Pointer { mutable: *mutable, pointee: Box::new(self.lower_type(pointee)) }
             }
+
+            TypeAnnotation::Function { params, ret } => HirType::Function {
+                params: params.iter().map(|t| self.lower_type(t)).collect(),
+                ret: Box::new(self.lower_type(ret)),
+            },
         };
         Spanned::new(hir, ty.span)
     }
 
+    /// Lowers `a::b<T>` style types to a path whose segments carry their
+    /// generic arguments. Returns `None` for types that are not paths.
+    fn lower_type_path(&mut self, ty: &SpannedTypeAnnotation<'src>) -> Option<HirPath<'src>> {
+        match &ty.node {
+            TypeAnnotation::Ident(name) => Some(HirPath {
+                from_root: false,
+                segments: Box::new([HirPathSegment {
+                    name: SpannedStr::new(name, ty.span),
+                    generics: None,
+                    span: ty.span,
+                }]),
+                span: ty.span,
+            }),
+            TypeAnnotation::Generic { ty: inner, args } => {
+                let mut path = self.lower_type_path(inner)?;
+                let args = self.lower_generic_args(args);
+                let last = path.segments.last_mut()?;
+                last.generics = Some(args);
+                last.span = ty.span;
+                path.span = ty.span;
+                Some(path)
+            }
+            TypeAnnotation::Path { from_root, path } => {
+                let mut segments = Vec::with_capacity(path.len());
+                for segment in path.iter() {
+                    segments.extend(self.lower_type_path(segment)?.segments);
+                }
+                Some(HirPath {
+                    from_root: *from_root,
+                    segments: segments.into_boxed_slice(),
+                    span: ty.span,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn lower_generic_args(&mut self, args: &[GenericArgument<'src>]) -> Box<[HirGenericArg<'src>]> {
+        args.iter()
+            .map(|arg| match arg {
+                GenericArgument::Type(ty) => HirGenericArg::Type(self.lower_type(ty)),
+                GenericArgument::Expr(expr) => HirGenericArg::Const(self.lower_expr(expr)),
+            })
+            .collect()
+    }
+
     fn push_unsupported(&mut self, construct: &'static str, span: Span) {
         self.res.diags.push(
             *Diagnostic::error(DiagMessage::new(
@@ -1,16 +1,22 @@
 use std::borrow::Cow;
 
-use runec_ast::ast_type::{SpannedTypeAnnotation, TypeAnnotation};
-use runec_ast::expression::{Expr, PrimitiveValue, SpannedExpr};
-use runec_ast::statement::{DestructPattern, FunctionArg, SpannedStmt, SpannedStmtBlock, Stmt};
+use runec_ast::ast_type::{GenericArgument, SpannedTypeAnnotation, TypeAnnotation};
+use runec_ast::expression::{ClosureParam, ElseBranch, Expr, IfExpr, PrimitiveValue, SpannedExpr};
+use runec_ast::operators::BinaryOp;
+use runec_ast::statement::{
+    DestructPattern, FunctionArg, GenericParam, GenericParamKind, SpannedStmt, SpannedStmtBlock,
+    Stmt, StructField, UseKind, Visibility,
+};
 use runec_source::byte_pos::BytePos;
 use runec_source::source_map::SourceId;
 use runec_source::span::{Span, Spanned};
 
-use super::HirLowerer;
+use super::{HirLowerer, HirModuleSource};
 use crate::expression::{HirExpr, HirLiteral};
-use crate::ids::HirId;
-use crate::item::HirItem;
+use crate::ids::{HirId, HirModuleId};
+use crate::item::{HirGenericParamKind, HirItem};
+use crate::module::HirUseKind;
+use crate::path::HirGenericArg;
 use crate::statement::HirStmt;
 use crate::ty::HirType;
 
@@ -52,7 +58,15 @@ fn fn_stmt<'a>(
     ret_ty: SpannedTypeAnnotation<'a>,
     body: SpannedStmtBlock<'a>,
 ) -> SpannedStmt<'a> {
-    s(Stmt::DefineFunction { ident: s(name), args, ret_ty, body })
+    s(Stmt::DefineFunction {
+        vis: Visibility::Private,
+        ident: s(name),
+        generics: Box::new([]),
+        args,
+        ret_ty,
+        throws: None,
+        body,
+    })
 }
 
 #[test]
@@ -325,7 +339,7 @@ fn lower_top_level_non_fn_stmts_report_diagnostics() {
 #[test]
 fn unsupported_expression_diagnostic_preserves_span() {
     let error_span = sp(12, 18);
-    let unsupported = Spanned::new(Expr::Tuple(Box::new([])), error_span);
+    let unsupported = Spanned::new(Expr::FullyDefinedArray(Box::new([])), error_span);
     let body = s(Box::new([s(Stmt::TailExpr(unsupported))]) as Box<[_]>);
     let result = HirLowerer::new().lower(&[fn_stmt("invalid", Box::new([]), unit_ty(), body)]);
 
@@ -367,3 +381,314 @@ fn lower_nested_block_expr() {
     let inner_tail = inner_block.tail.as_ref().expect("inner block should have tail");
     assert!(matches!(inner_tail.node, HirExpr::Literal(HirLiteral::Int { value: 99, .. })));
 }
+
+#[test]
+fn lower_assign_stmt_targets_path() {
+    let body = s(Box::new([s(Stmt::Assign { ident: s("x"), expr: int_expr(3) })]) as Box<[_]>);
+    let stmts = [fn_stmt("f", Box::new([]), unit_ty(), body)];
+    let result = HirLowerer::new().lower(&stmts);
+
+    assert!(result.diags.is_empty());
+    let HirItem::Function(f) = result.map.get(HirId::from_usize(0)) else { panic!() };
+    let HirStmt::Assign { target, value, .. } = &f.body.stmts[0] else {
+        panic!("expected HirStmt::Assign");
+    };
+    let HirExpr::Path(ref path) = target.node else { panic!("expected Path") };
+    assert_eq!(path.segments[0].name.node, "x");
+    assert!(matches!(value.node, HirExpr::Literal(HirLiteral::Int { value: 3, .. })));
+}
+
+#[test]
+fn lower_else_if_chain_to_nested_if() {
+    let nested = s(IfExpr {
+        cond: Box::new(ident_expr("b")),
+        then: empty_block(),
+        else_: Some(ElseBranch::Block(s(Box::new([s(Stmt::TailExpr(int_expr(2)))]) as Box<[_]>))),
+    });
+    let if_expr = s(Expr::If(IfExpr {
+        cond: Box::new(ident_expr("a")),
+        then: empty_block(),
+        else_: Some(ElseBranch::If(Box::new(nested))),
+    }));
+    let body = s(Box::new([s(Stmt::TailExpr(if_expr))]) as Box<[_]>);
+    let result = HirLowerer::new().lower(&[fn_stmt("f", Box::new([]), unit_ty(), body)]);
+
+    assert!(result.diags.is_empty());
+    let HirItem::Function(f) = result.map.get(HirId::from_usize(0)) else { panic!() };
+    let tail = f.body.tail.as_ref().expect("should have tail");
+    let HirExpr::If { ref else_, .. } = tail.node else { panic!("expected If") };
+    let else_ = else_.as_ref().expect("should have else branch");
+    let HirExpr::If { else_: Some(ref inner_else), .. } = else_.node else {
+        panic!("expected nested If")
+    };
+    assert!(matches!(inner_else.node, HirExpr::Block(_)));
+}
+
+#[test]
+fn lower_nullable_let_and_null_comparison() {
+    let let_stmt = s(Stmt::DefineLet {
+        pattern: s(DestructPattern::Ident("x")),
+        is_mutable: false,
+        ty: Some(s(TypeAnnotation::Nullable(Box::new(ident_ty("str"))))),
+        init_expr: Some(s(Expr::Primitive(PrimitiveValue::Null))),
+    });
+    let compare = s(Expr::Binary {
+        lhs: Box::new(ident_expr("x")),
+        rhs: Box::new(s(Expr::Primitive(PrimitiveValue::Null))),
+        op: BinaryOp::Ne,
+    });
+    let body = s(Box::new([let_stmt, s(Stmt::TailExpr(compare))]) as Box<[_]>);
+    let result = HirLowerer::new().lower(&[fn_stmt("f", Box::new([]), unit_ty(), body)]);
+
+    assert!(result.diags.is_empty());
+    let HirItem::Function(f) = result.map.get(HirId::from_usize(0)) else { panic!() };
+    let HirStmt::Let { ty: Some(ty), init: Some(init), .. } = &f.body.stmts[0] else {
+        panic!("expected typed let");
+    };
+    assert!(matches!(ty.node, HirType::Nullable(_)));
+    assert!(matches!(init.node, HirExpr::Literal(HirLiteral::Null)));
+    let tail = f.body.tail.as_ref().expect("should have tail");
+    let HirExpr::Binary { op: BinaryOp::Ne, ref rhs, .. } = tail.node else {
+        panic!("expected Binary")
+    };
+    assert!(matches!(rhs.node, HirExpr::Literal(HirLiteral::Null)));
+}
+
+#[test]
+fn lower_throws_clause_throw_and_try() {
+    let call = s(Expr::Call { callee: Box::new(ident_expr("g")), args: Box::new([]) });
+    let body = s(Box::new([
+        s(Stmt::Throw(ident_expr("e"))),
+        s(Stmt::TailExpr(s(Expr::Try(Box::new(call))))),
+    ]) as Box<[_]>);
+    let function = s(Stmt::DefineFunction {
+        vis: Visibility::Private,
+        ident: s("f"),
+        generics: Box::new([]),
+        args: Box::new([]),
+        ret_ty: unit_ty(),
+        throws: Some(ident_ty("str")),
+        body,
+    });
+    let result = HirLowerer::new().lower(&[function]);
+
+    assert!(result.diags.is_empty());
+    let HirItem::Function(f) = result.map.get(HirId::from_usize(0)) else { panic!() };
+    assert!(f.throws.is_some());
+    assert!(matches!(f.body.stmts[0], HirStmt::Throw { .. }));
+    let tail = f.body.tail.as_ref().expect("should have tail");
+    let HirExpr::Try(ref inner) = tail.node else { panic!("expected Try") };
+    assert!(matches!(inner.node, HirExpr::Call { .. }));
+}
+
+#[test]
+fn lower_generic_struct_with_bound_and_const_param() {
+    let field_ty = s(TypeAnnotation::Generic {
+        ty: Box::new(ident_ty("Inner")),
+        args: Box::new([GenericArgument::Type(ident_ty("T")), GenericArgument::Expr(int_expr(4))]),
+    });
+    let strukt = s(Stmt::DefineStruct {
+        vis: Visibility::Private,
+        ident: s("Buffer"),
+        generics: Box::new([
+            GenericParam {
+                ident: s("T"),
+                kind: GenericParamKind::Type { bounds: Box::new([ident_ty("Display")]) },
+            },
+            GenericParam { ident: s("N"), kind: GenericParamKind::Const { ty: ident_ty("int") } },
+        ]),
+        fields: Box::new([StructField {
+            vis: Visibility::Private,
+            ident: s("items"),
+            ty: field_ty,
+        }]),
+    });
+    let result = HirLowerer::new().lower(&[strukt]);
+
+    assert!(result.diags.is_empty());
+    let HirItem::Struct(strukt) = result.map.get(HirId::from_usize(0)) else { panic!() };
+    let HirGenericParamKind::Type { ref bounds } = strukt.generics[0].kind else {
+        panic!("expected type parameter")
+    };
+    assert_eq!(bounds[0].path.segments[0].name.node, "Display");
+    assert!(matches!(strukt.generics[1].kind, HirGenericParamKind::Const { .. }));
+    let HirType::Unresolved(ref path) = strukt.fields[0].ty.node else {
+        panic!("expected Unresolved")
+    };
+    let args = path.segments[0].generics.as_ref().expect("should have generics");
+    assert!(matches!(args[0], HirGenericArg::Type(_)));
+    assert!(matches!(args[1], HirGenericArg::Const(_)));
+}
+
+#[test]
+fn lower_modules_into_tree_with_imports_and_uses() {
+    let root = [
+        s(Stmt::Import(Box::new([s("a"), s("b")]))),
+        s(Stmt::Use {
+            from_root: false,
+            path: Box::new([s("b"), s("f")]),
+            kind: UseKind::Single { alias: Some(s("g")) },
+        }),
+        fn_stmt("main", Box::new([]), unit_ty(), empty_block()),
+    ];
+    let module = [
+        s(Stmt::Use { from_root: true, path: Box::new([s("c")]), kind: UseKind::Glob }),
+        fn_stmt("f", Box::new([]), unit_ty(), empty_block()),
+    ];
+    let result = HirLowerer::new().lower_modules(&[
+        HirModuleSource { path: Box::new([]), stmts: &root },
+        HirModuleSource { path: Box::new(["a", "b"]), stmts: &module },
+    ]);
+
+    assert!(result.diags.is_empty());
+    let map = &result.map;
+    let b = map.find_module(["a", "b"]).expect("module a::b");
+    assert_eq!(map.item_module(HirId::from_usize(0)), HirModuleId::ROOT);
+    assert_eq!(map.item_module(HirId::from_usize(1)), b);
+
+    let root = map.module(HirModuleId::ROOT);
+    assert_eq!(root.imports[0].path.segments.len(), 2);
+    assert_eq!(root.uses[0].binding().map(|name| name.node), Some("g"));
+    let uses = &map.module(b).uses;
+    assert!(uses[0].path.from_root);
+    assert_eq!(uses[0].kind, HirUseKind::Glob);
+}
+
+#[test]
+fn lower_union_alias_and_field_access() {
+    let union = s(Stmt::DefineUnion {
+        vis: Visibility::Public,
+        ident: s("Bits"),
+        generics: Box::new([]),
+        fields: Box::new([
+            StructField { vis: Visibility::Private, ident: s("i"), ty: ident_ty("int") },
+            StructField { vis: Visibility::Private, ident: s("f"), ty: ident_ty("f32") },
+        ]),
+    });
+    let alias = s(Stmt::DefineAlias {
+        vis: Visibility::Private,
+        ident: s("Raw"),
+        generics: Box::new([]),
+        ty: ident_ty("Bits"),
+    });
+    let field = s(Expr::AttributeAccess { value: Box::new(s(Expr::Ident("bits"))), name: s("i") });
+    let body = s(Box::new([s(Stmt::TailExpr(field))]) as Box<[_]>);
+    let function = fn_stmt("get", Box::new([]), ident_ty("int"), body);
+    let result = HirLowerer::new().lower(&[union, alias, function]);
+
+    assert!(result.diags.is_empty());
+    let HirItem::Union(union) = result.map.get(HirId::from_usize(0)) else { panic!() };
+    assert_eq!(union.fields.len(), 2);
+    assert_eq!(union.vis, Visibility::Public);
+    let HirItem::Alias(alias) = result.map.get(HirId::from_usize(1)) else { panic!() };
+    assert!(matches!(alias.ty.node, HirType::Unresolved(_)));
+    let HirItem::Function(f) = result.map.get(HirId::from_usize(2)) else { panic!() };
+    let tail = f.body.tail.as_ref().expect("should have tail");
+    let HirExpr::Field { ref base, ref name } = tail.node else { panic!("expected Field") };
+    assert!(matches!(base.node, HirExpr::Path(_)));
+    assert_eq!(name.node, "i");
+}
+
+#[test]
+fn lower_extern_fn_pointer_types_and_unsafe_deref() {
+    let pointer =
+        |mutable| s(TypeAnnotation::Pointer { mutable, pointee: Box::new(ident_ty("u8")) });
+    let extern_fn = s(Stmt::DefineExternFunction {
+        vis: Visibility::Public,
+        ident: s("puts"),
+        args: Box::new([FunctionArg { ident: s("s"), ty: pointer(false) }]),
+        ret_ty: ident_ty("i32"),
+    });
+    let deref = s(Expr::Deref(Box::new(ident_expr("p"))));
+    let unsafe_block = s(Expr::Unsafe(s(Box::new([s(Stmt::TailExpr(deref))]) as Box<[_]>)));
+    let body = s(Box::new([s(Stmt::TailExpr(unsafe_block))]) as Box<[_]>);
+    let args = Box::new([FunctionArg { ident: s("p"), ty: pointer(true) }]);
+    let function = fn_stmt("read", args, ident_ty("u8"), body);
+    let result = HirLowerer::new().lower(&[extern_fn, function]);
+
+    assert!(result.diags.is_empty());
+    let HirItem::ExternFunction(puts) = result.map.get(HirId::from_usize(0)) else { panic!() };
+    assert_eq!(puts.vis, Visibility::Public);
+    assert!(matches!(puts.params[0].ty.node, HirType::Pointer { mutable: false, .. }));
+    let HirItem::Function(f) = result.map.get(HirId::from_usize(1)) else { panic!() };
+    assert!(matches!(f.params[0].ty.node, HirType::Pointer { mutable: true, .. }));
+    let tail = f.body.tail.as_ref().expect("should have tail");
+    let HirExpr::Unsafe(ref block) = tail.node else { panic!("expected Unsafe") };
+    let inner = block.tail.as_ref().expect("unsafe block should have tail");
+    assert!(matches!(inner.node, HirExpr::Deref(_)));
+}
+
+#[test]
+fn lower_closure_and_function_type() {
+    let fn_ty = s(TypeAnnotation::Function {
+        params: Box::new([ident_ty("int")]),
+        ret: Box::new(ident_ty("int")),
+    });
+    let closure = s(Expr::Closure {
+        params: Box::new([ClosureParam { ident: s("x"), ty: None }]),
+        body: Box::new(ident_expr("x")),
+    });
+    let body = s(Box::new([s(Stmt::TailExpr(closure))]) as Box<[_]>);
+    let function = fn_stmt("make", Box::new([]), fn_ty, body);
+    let result = HirLowerer::new().lower(&[function]);
+
+    assert!(result.diags.is_empty());
+    let HirItem::Function(f) = result.map.get(HirId::from_usize(0)) else { panic!() };
+    let HirType::Function { ref params, ref ret } = f.ret_ty.node else {
+        panic!("expected a function type")
+    };
+    assert_eq!(params.len(), 1);
+    assert!(matches!(ret.node, HirType::Unresolved(_)));
+    let tail = f.body.tail.as_ref().expect("should have tail");
+    let HirExpr::Closure(ref closure) = tail.node else { panic!("expected Closure") };
+    assert_eq!(closure.params[0].name.node, "x");
+    assert!(closure.params[0].local.is_none() && closure.params[0].ty.is_none());
+    assert!(matches!(closure.body.node, HirExpr::Path(_)));
+    assert!(closure.captures.is_empty());
+}
+
+#[test]
+fn lower_tuple_patterns_to_element_lets() {
+    let ident_pat = |name| s(DestructPattern::Ident(name));
+    let pattern = s(DestructPattern::Tuple(Box::new([
+        ident_pat("a"),
+        s(DestructPattern::Tuple(Box::new([ident_pat("b"), ident_pat("c")]))),
+    ])));
+    let let_stmt = s(Stmt::DefineLet {
+        pattern,
+        is_mutable: false,
+        ty: None,
+        init_expr: Some(ident_expr("pair")),
+    });
+    let for_loop = s(Expr::For {
+        binding: s(DestructPattern::Tuple(Box::new([ident_pat("i"), ident_pat("x")]))),
+        iter: Box::new(ident_expr("items")),
+        body: empty_block(),
+    });
+    let field = s(Expr::AttributeAccess { value: Box::new(ident_expr("a")), name: s("1") });
+    let body =
+        s(Box::new([let_stmt, s(Stmt::SemiExpr(for_loop)), s(Stmt::TailExpr(field))]) as Box<[_]>);
+    let function = fn_stmt("f", Box::new([]), unit_ty(), body);
+    let result = HirLowerer::new().lower(&[function]);
+
+    assert!(result.diags.is_empty());
+    let HirItem::Function(f) = result.map.get(HirId::from_usize(0)) else { panic!() };
+    let names: Vec<_> = f.body.stmts[..4]
+        .iter()
+        .map(|stmt| match stmt {
+            HirStmt::Let { name, .. } => name.node,
+            _ => panic!("expected Let"),
+        })
+        .collect();
+    assert_eq!(names, ["$tuple", "a", "b", "c"]);
+    let HirStmt::Let { init: Some(ref c), .. } = f.body.stmts[3] else { panic!() };
+    let HirExpr::TupleField { ref base, index: 1 } = c.node else { panic!("expected .1") };
+    assert!(matches!(base.node, HirExpr::TupleField { index: 1, .. }));
+
+    let HirStmt::Expr(ref for_loop) = f.body.stmts[4] else { panic!("expected For") };
+    let HirExpr::For { ref name, ref body, .. } = for_loop.node else { panic!("expected For") };
+    assert_eq!(name.node, "$tuple");
+    assert_eq!(body.stmts.len(), 2);
+    let tail = f.body.tail.as_ref().expect("should have tail");
+    assert!(matches!(tail.node, HirExpr::TupleField { index: 1, .. }));
+}
@@ -1,13 +1,21 @@
-use crate::ids::HirId;
+use crate::ids::{HirId, HirModuleId};
 use crate::item::HirItem;
+use crate::module::HirModule;
 
 pub struct HirMap<'src> {
     items: Vec<HirItem<'src>>,
+    /// Module of each item, indexed like `items`.
+    item_modules: Vec<HirModuleId>,
+    modules: Vec<HirModule<'src>>,
 }
 
 impl<'src> HirMap<'src> {
     pub fn new() -> Self {
-        Self { items: Vec::new() }
+        Self {
+            items: Vec::new(),
+            item_modules: Vec::new(),
+            modules: vec![HirModule::new("", None)],
+        }
     }
 
     pub fn len(&self) -> usize {
@@ -24,13 +32,24 @@ impl<'src> HirMap<'src> {
         HirId::from_usize(self.items.len())
     }
 
+    /// Pushes an item into the crate root module.
     pub fn push(&mut self, item: HirItem<'src>) -> HirId {
+        self.push_in(HirModuleId::ROOT, item)
+    }
+
+    pub fn push_in(&mut self, module: HirModuleId, item: HirItem<'src>) -> HirId {
         let id = HirId::from_usize(self.items.len());
         debug_assert_eq!(id, item.id(), "HirItem.id does not match its position in HirMap");
         self.items.push(item);
+        self.item_modules.push(module);
+        self.modules[module.to_usize()].items.push(id);
         id
     }
 
+    pub fn item_module(&self, id: HirId) -> HirModuleId {
+        self.item_modules[id.to_usize()]
+    }
+
     pub fn get(&self, id: HirId) -> &HirItem<'src> {
         &self.items[id.to_usize()]
     }
@@ -54,6 +73,65 @@ impl<'src> HirMap<'src> {
     pub fn iter_mut(&mut self) -> impl Iterator<Item = (HirId, &mut HirItem<'src>)> {
         self.items.iter_mut().enumerate().map(|(i, it)| (HirId::from_usize(i), it))
     }
+
+    pub fn module(&self, id: HirModuleId) -> &HirModule<'src> {
+        &self.modules[id.to_usize()]
+    }
+
+    pub fn module_mut(&mut self, id: HirModuleId) -> &mut HirModule<'src> {
+        &mut self.modules[id.to_usize()]
+    }
+
+    pub fn modules(&self) -> impl Iterator<Item = (HirModuleId, &HirModule<'src>)> {
+        self.modules.iter().enumerate().map(|(i, module)| (HirModuleId::from_usize(i), module))
+    }
+
+    /// Returns the child `name` of `parent`, creating an empty module when it
+    /// does not exist yet.
+    pub fn add_module(&mut self, parent: HirModuleId, name: &'src str) -> HirModuleId {
+        if let Some(child) = self.child(parent, name) {
+            return child;
+        }
+
+        let id = HirModuleId::from_usize(self.modules.len());
+        self.modules.push(HirModule::new(name, Some(parent)));
+        self.modules[parent.to_usize()].children.push(id);
+        id
+    }
+
+    pub fn child(&self, parent: HirModuleId, name: &str) -> Option<HirModuleId> {
+        self.module(parent).children.iter().copied().find(|&child| self.module(child).name == name)
+    }
+
+    /// Finds the module at `path`, counted from the crate root.
+    pub fn find_module<'a>(&self, path: impl IntoIterator<Item = &'a str>) -> Option<HirModuleId> {
+        path.into_iter().try_fold(HirModuleId::ROOT, |module, name| self.child(module, name))
+    }
+
+    /// Whether `module` is `ancestor` or nested inside it.
+    pub fn is_within(&self, module: HirModuleId, ancestor: HirModuleId) -> bool {
+        let mut current = Some(module);
+        while let Some(id) = current {
+            if id == ancestor {
+                return true;
+            }
+            current = self.module(id).parent;
+        }
+        false
+    }
+
+    /// Names of the modules from the crate root down to `id`, excluding the
+    /// root itself.
+    pub fn module_path(&self, id: HirModuleId) -> Vec<&'src str> {
+        let mut path = Vec::new();
+        let mut current = id;
+        while let Some(parent) = self.module(current).parent {
+            path.push(self.module(current).name);
+            current = parent;
+        }
+        path.reverse();
+        path
+    }
 }
 
 impl<'src> Default for HirMap<'src> {
@@ -65,12 +143,13 @@ impl<'src> Default for HirMap<'src> {
 #[cfg(test)]
 mod tests {
     use runec_ast::SpannedStr;
+    use runec_ast::statement::Visibility;
     use runec_source::byte_pos::BytePos;
     use runec_source::source_map::SourceId;
     use runec_source::span::{Span, Spanned};
 
     use super::HirMap;
-    use crate::ids::HirId;
+    use crate::ids::{HirId, HirModuleId};
     use crate::item::{HirFunction, HirItem};
     use crate::statement::HirBlock;
     use crate::ty::HirType;
@@ -81,10 +160,13 @@ mod tests {
 
     fn function(id: HirId) -> HirItem<'static> {
         HirItem::Function(HirFunction {
+            vis: Visibility::Private,
             id,
             name: SpannedStr::new("main", span()),
+            generics: Box::new([]),
             params: Box::new([]),
             ret_ty: Spanned::new(HirType::Unit, span()),
+            throws: None,
             body: HirBlock { stmts: Box::new([]), tail: None, span: span() },
             span: span(),
         })
@@ -110,4 +192,23 @@ mod tests {
 
         assert_eq!(map.try_get(id).expect("function should exist").name().node, "renamed");
     }
+
+    #[test]
+    fn module_tree_tracks_items_and_paths() {
+        let mut map = HirMap::new();
+        let a = map.add_module(HirModuleId::ROOT, "a");
+        let b = map.add_module(a, "b");
+        assert_eq!(map.add_module(HirModuleId::ROOT, "a"), a);
+
+        let id = map.reserve_id();
+        map.push_in(b, function(id));
+
+        assert_eq!(map.item_module(id), b);
+        assert_eq!(map.module(b).items, [id]);
+        assert_eq!(map.module_path(b), ["a", "b"]);
+        assert_eq!(map.find_module(["a", "b"]), Some(b));
+        assert_eq!(map.find_module(["b"]), None);
+        assert!(map.is_within(b, a));
+        assert!(!map.is_within(a, b));
+    }
 }
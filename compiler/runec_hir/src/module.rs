@@ -0,0 +1,62 @@
+use runec_ast::SpannedStr;
+use runec_source::span::Span;
+
+use crate::ids::{HirId, HirModuleId};
+use crate::path::HirPath;
+
+/// A module of the crate: the root file or a file loaded by `import`.
+#[derive(Debug, PartialEq)]
+pub struct HirModule<'src> {
+    /// Last segment of the module path; empty for the crate root.
+    pub name: &'src str,
+    pub parent: Option<HirModuleId>,
+    pub children: Vec<HirModuleId>,
+    pub items: Vec<HirId>,
+    pub imports: Vec<HirImport<'src>>,
+    pub uses: Vec<HirUse<'src>>,
+}
+
+impl<'src> HirModule<'src> {
+    pub fn new(name: &'src str, parent: Option<HirModuleId>) -> Self {
+        Self {
+            name,
+            parent,
+            children: Vec::new(),
+            items: Vec::new(),
+            imports: Vec::new(),
+            uses: Vec::new(),
+        }
+    }
+}
+
+/// `import a::b;` binds `b` to the module `a::b`, always counted from the
+/// crate root.
+#[derive(Debug, PartialEq)]
+pub struct HirImport<'src> {
+    pub path: HirPath<'src>,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct HirUse<'src> {
+    pub path: HirPath<'src>,
+    pub kind: HirUseKind<'src>,
+    pub span: Span,
+}
+
+impl<'src> HirUse<'src> {
+    /// Name bound by a single `use`, `None` for globs.
+    pub fn binding(&self) -> Option<&SpannedStr<'src>> {
+        match &self.kind {
+            HirUseKind::Single { alias: Some(alias) } => Some(alias),
+            HirUseKind::Single { alias: None } => self.path.segments.last().map(|s| &s.name),
+            HirUseKind::Glob => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum HirUseKind<'src> {
+    Single { alias: Option<SpannedStr<'src>> },
+    Glob,
+}
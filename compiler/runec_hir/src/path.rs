@@ -4,14 +4,14 @@ use runec_source::span::Span;
 use crate::expression::SpannedHirExpr;
 use crate::ty::SpannedHirType;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct HirPath<'src> {
     pub from_root: bool,
     pub segments: Box<[HirPathSegment<'src>]>,
     pub span: Span,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct HirPathSegment<'src> {
     pub name: SpannedStr<'src>,
     /// `None` — segment without `<...>`. `Some(..)` — segment with generics
@@ -20,7 +20,7 @@ pub struct HirPathSegment<'src> {
     pub span: Span,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum HirGenericArg<'src> {
     Type(SpannedHirType<'src>),
     /// For const generics: `Type<T, 3>`, `Type<T, N>`.
@@ -6,5 +6,12 @@ use crate::ids::{HirId, HirLocalId};
 pub enum Res {
     Local(HirLocalId),
     Def(HirId),
+    /// Variant `index` of enum `def`.
+    Variant {
+        def: HirId,
+        index: usize,
+    },
     Builtin(BuiltinId),
+    /// Const generic parameter of the enclosing function, by index.
+    ConstParam(usize),
 }
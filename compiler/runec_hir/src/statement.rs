@@ -5,7 +5,7 @@ use crate::expression::SpannedHirExpr;
 use crate::ids::HirLocalId;
 use crate::ty::SpannedHirType;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct HirBlock<'src> {
     pub stmts: Box<[HirStmt<'src>]>,
     /// Tail expression without `;` — the value of the block.
@@ -13,7 +13,7 @@ pub struct HirBlock<'src> {
     pub span: Span,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum HirStmt<'src> {
     /// `expr;`
     Expr(SpannedHirExpr<'src>),
@@ -27,4 +27,13 @@ pub enum HirStmt<'src> {
         init: Option<SpannedHirExpr<'src>>,
         span: Span,
     },
+
+    /// `target = value;`
+    Assign { target: SpannedHirExpr<'src>, value: SpannedHirExpr<'src>, span: Span },
+
+    /// `throw value;`
+    Throw { value: SpannedHirExpr<'src>, span: Span },
+
+    /// `break;`
+    Break { span: Span },
 }
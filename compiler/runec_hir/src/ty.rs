@@ -1,10 +1,11 @@
+use runec_builtins::BuiltinTypeId;
 use runec_source::span::Spanned;
 
 use crate::expression::SpannedHirExpr;
 use crate::ids::HirId;
 use crate::path::{HirGenericArg, HirPath};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum HirType<'src> {
     Error,
     /// Path not yet resolved by name resolution.
@@ -16,10 +17,19 @@ pub enum HirType<'src> {
         def: HirId,
         generics: Box<[HirGenericArg<'src>]>,
     },
+    Union {
+        def: HirId,
+        generics: Box<[HirGenericArg<'src>]>,
+    },
     Enum {
         def: HirId,
         generics: Box<[HirGenericArg<'src>]>,
     },
+    /// Type provided by the compiler, such as `Range<int>`.
+    Builtin {
+        ty: BuiltinTypeId,
+        generics: Box<[HirGenericArg<'src>]>,
+    },
 
     Unit,
     Tuple(Box<[SpannedHirType<'src>]>),
@@ -27,6 +37,22 @@ pub enum HirType<'src> {
         elem: Box<SpannedHirType<'src>>,
         len: Box<SpannedHirExpr<'src>>,
     },
+    /// `T[]`
+    Slice(Box<SpannedHirType<'src>>),
+    /// `T?`
+    Nullable(Box<SpannedHirType<'src>>),
+    /// `*const T` or `*mut T`
+    Pointer {
+        mutable: bool,
+        pointee: Box<SpannedHirType<'src>>,
+    },
+    /// `act(A, B) -> R`; a function item or closure used as a value.
+    Function {
+        params: Box<[SpannedHirType<'src>]>,
+        ret: Box<SpannedHirType<'src>>,
+    },
+    /// Type parameter of the enclosing item, by index.
+    Param(usize),
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
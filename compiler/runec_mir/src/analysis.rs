@@ -0,0 +1,22 @@
+//! Checks that run over lowered MIR functions.
+
+use runec_errors::diagnostics::Diagnostic;
+
+use crate::function::MirFunction;
+
+mod dataflow;
+mod init;
+mod moves;
+
+pub use init::{check_initialization, check_reassignment};
+pub use moves::check_moves;
+
+/// Runs every MIR check over a lowered function.
+pub fn check_function(function: &MirFunction<'_>) -> Vec<Diagnostic<'static>> {
+    let mut diags = check_initialization(function);
+    diags.extend(check_reassignment(function));
+    diags.extend(check_moves(function));
+    diags
+}
+
+mod messages;
@@ -0,0 +1,59 @@
+//! Forward dataflow over the MIR control flow graph.
+
+use crate::block::{MirStmt, MirTerminator};
+use crate::function::MirFunction;
+
+pub(super) trait ForwardAnalysis {
+    type State: Clone + PartialEq;
+
+    fn entry_state(&self, function: &MirFunction<'_>) -> Self::State;
+
+    /// Merges the state flowing along an incoming edge into `state`.
+    fn join(&self, state: &mut Self::State, incoming: &Self::State);
+
+    fn apply_stmt(&self, stmt: &MirStmt, state: &mut Self::State);
+
+    fn apply_terminator(&self, _terminator: &MirTerminator, _state: &mut Self::State) {}
+}
+
+/// Computes the state at the start of every block by iterating to a fixed
+/// point. Blocks unreachable from the entry get `None`.
+pub(super) fn block_entry_states<A: ForwardAnalysis>(
+    analysis: &A,
+    function: &MirFunction<'_>,
+) -> Vec<Option<A::State>> {
+    let mut states = vec![None; function.blocks.len()];
+    let Some(entry) = states.get_mut(function.entry.to_usize()) else {
+        return states;
+    };
+    *entry = Some(analysis.entry_state(function));
+
+    let mut worklist = vec![function.entry];
+    while let Some(block_id) = worklist.pop() {
+        let block = &function.blocks[block_id.to_usize()];
+        let mut state = states[block_id.to_usize()].clone().expect("queued block has a state");
+        for stmt in &block.stmts {
+            analysis.apply_stmt(stmt, &mut state);
+        }
+        analysis.apply_terminator(&block.terminator, &mut state);
+
+        for successor in block.terminator.successors() {
+            let changed = match &mut states[successor.to_usize()] {
+                Some(existing) => {
+                    let before = existing.clone();
+                    analysis.join(existing, &state);
+                    *existing != before
+                }
+                slot @ None => {
+                    *slot = Some(state.clone());
+                    true
+                }
+            };
+            if changed && !worklist.contains(&successor) {
+                worklist.push(successor);
+            }
+        }
+    }
+
+    states
+}
@@ -0,0 +1,259 @@
+use runec_errors::diagnostics::Diagnostic;
+use runec_errors::labels::DiagLabel;
+use runec_errors::message::DiagMessage;
+use runec_source::span::Span;
+
+use super::dataflow::{ForwardAnalysis, block_entry_states};
+use super::messages;
+use crate::block::MirStmt;
+use crate::function::MirFunction;
+use crate::operand::MirOperand;
+
+/// Reports every read of a local that is not assigned on all paths leading to
+/// it.
+///
+/// Parameters start initialized; every other local starts uninitialized and
+/// becomes initialized once assigned, so `let x;` may be initialized later in
+/// each branch of an `if`.
+pub fn check_initialization(function: &MirFunction<'_>) -> Vec<Diagnostic<'static>> {
+    let analysis = UninitLocals;
+    let mut diags = Vec::new();
+
+    for (block, state) in function.blocks.iter().zip(block_entry_states(&analysis, function)) {
+        let Some(mut state) = state else {
+            continue;
+        };
+
+        for stmt in &block.stmts {
+            for operand in stmt.operands() {
+                check_operand(function, operand, stmt.span(), &state, &mut diags);
+            }
+            analysis.apply_stmt(stmt, &mut state);
+        }
+        let span = block.terminator.span().unwrap_or(function.ret_span);
+        for operand in block.terminator.operands() {
+            check_operand(function, operand, span, &state, &mut diags);
+        }
+    }
+
+    diags
+}
+
+fn check_operand(
+    function: &MirFunction<'_>,
+    operand: &MirOperand,
+    span: Span,
+    state: &UninitState,
+    diags: &mut Vec<Diagnostic<'static>>,
+) {
+    let Some(place) = operand.place() else {
+        return;
+    };
+    if !state[place.local.to_usize()] {
+        return;
+    }
+
+    let local = &function.locals[place.local.to_usize()];
+    let name = local.name.unwrap_or("<temporary>");
+    diags.push(
+        *Diagnostic::error(DiagMessage::new(messages::USED_BEFORE_INITIALIZED, &[("name", name)]))
+            .add_label(DiagLabel::simple_primary(messages::USED_HERE_UNINITIALIZED, span))
+            .add_label(DiagLabel::simple_secondary(messages::DECLARED_WITHOUT_VALUE, local.span)),
+    );
+}
+
+/// Reports every assignment to an immutable `let` declared without a value
+/// that some path reaches after the local was already assigned.
+pub fn check_reassignment(function: &MirFunction<'_>) -> Vec<Diagnostic<'static>> {
+    let analysis = AssignedLocals;
+    let mut diags = Vec::new();
+
+    for (block, state) in function.blocks.iter().zip(block_entry_states(&analysis, function)) {
+        let Some(mut state) = state else {
+            continue;
+        };
+
+        for stmt in &block.stmts {
+            if let Some(dst) = stmt.dst() {
+                let local = &function.locals[dst.local.to_usize()];
+                if local.assign_once && state[dst.local.to_usize()] {
+                    let name = local.name.unwrap_or("<temporary>");
+                    diags.push(
+                        *Diagnostic::error(DiagMessage::new(
+                            messages::ASSIGNED_TWICE,
+                            &[("name", name)],
+                        ))
+                        .add_label(DiagLabel::simple_primary(
+                            messages::ASSIGNED_AGAIN_HERE,
+                            stmt.span(),
+                        ))
+                        .add_label(DiagLabel::simple_secondary(
+                            messages::DECLARED_IMMUTABLE,
+                            local.span,
+                        )),
+                    );
+                }
+            }
+            analysis.apply_stmt(stmt, &mut state);
+        }
+    }
+
+    diags
+}
+
+/// For every local, whether some path reaches this point without assigning it.
+type UninitState = Vec<bool>;
+
+struct UninitLocals;
+
+impl ForwardAnalysis for UninitLocals {
+    type State = UninitState;
+
+    fn entry_state(&self, function: &MirFunction<'_>) -> UninitState {
+        let mut state = vec![true; function.locals.len()];
+        for param in function.params.iter() {
+            state[param.to_usize()] = false;
+        }
+        state
+    }
+
+    fn join(&self, state: &mut UninitState, incoming: &UninitState) {
+        for (uninit, incoming) in state.iter_mut().zip(incoming) {
+            *uninit |= *incoming;
+        }
+    }
+
+    fn apply_stmt(&self, stmt: &MirStmt, state: &mut UninitState) {
+        if let Some(dst) = stmt.dst() {
+            state[dst.local.to_usize()] = false;
+        }
+    }
+}
+
+/// For every local, whether some path reaches this point after assigning it.
+type AssignedState = Vec<bool>;
+
+struct AssignedLocals;
+
+impl ForwardAnalysis for AssignedLocals {
+    type State = AssignedState;
+
+    fn entry_state(&self, function: &MirFunction<'_>) -> AssignedState {
+        let mut state = vec![false; function.locals.len()];
+        for param in function.params.iter() {
+            state[param.to_usize()] = true;
+        }
+        state
+    }
+
+    fn join(&self, state: &mut AssignedState, incoming: &AssignedState) {
+        for (assigned, incoming) in state.iter_mut().zip(incoming) {
+            *assigned |= *incoming;
+        }
+    }
+
+    fn apply_stmt(&self, stmt: &MirStmt, state: &mut AssignedState) {
+        if let Some(dst) = stmt.dst() {
+            state[dst.local.to_usize()] = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use runec_builtins::TypeBits;
+    use runec_hir::ids::HirId;
+    use runec_source::byte_pos::BytePos;
+    use runec_source::source_map::SourceId;
+    use runec_source::span::Span;
+
+    use super::{check_initialization, check_reassignment};
+    use crate::block::{MirBlock, MirRvalue, MirStmt, MirTerminator};
+    use crate::function::MirFunction;
+    use crate::ids::MirLocalId;
+    use crate::operand::{MirImmediate, MirOperand, MirPlace};
+    use crate::ty::{MirIntTy, MirTy};
+
+    fn sp(lo: usize, hi: usize) -> Span {
+        Span::new(BytePos::from_usize(lo), BytePos::from_usize(hi), SourceId::from_usize(0))
+    }
+
+    const I32: MirIntTy = MirIntTy { signed: true, bits: TypeBits::B32 };
+
+    fn int_ty() -> MirTy {
+        MirTy::Int(I32)
+    }
+
+    fn assign_one(local: MirLocalId) -> MirStmt {
+        MirStmt::Assign {
+            dst: MirPlace::new(local),
+            rhs: MirRvalue::Use(MirOperand::Immediate(MirImmediate::Int { value: 1, ty: I32 })),
+            span: sp(0, 0),
+        }
+    }
+
+    /// `let x; if flag { x = 1; } [else { x = 1; }] return x;`
+    fn branching_function(assign_in_else: bool) -> MirFunction<'static> {
+        let mut function =
+            MirFunction::new(HirId::from_usize(0), "main", int_ty(), sp(0, 0), sp(40, 43));
+        let flag = function.push_local(Some("flag"), MirTy::Bool, sp(0, 0));
+        function.params = Box::new([flag]);
+        let x = function.push_local(Some("x"), int_ty(), sp(10, 16));
+
+        let entry = function.push_block(MirBlock::new(MirTerminator::Return(None)));
+        let then_block = function.push_block(MirBlock::new(MirTerminator::Return(None)));
+        let else_block = function.push_block(MirBlock::new(MirTerminator::Return(None)));
+        let join = function.push_block(MirBlock::new(MirTerminator::Return(Some(
+            MirOperand::Copy(MirPlace::new(x)),
+        ))));
+
+        function.blocks[entry.to_usize()].terminator = MirTerminator::Branch {
+            cond: MirOperand::Copy(MirPlace::new(flag)),
+            then_block,
+            else_block,
+            span: sp(20, 24),
+        };
+        function.blocks[then_block.to_usize()].stmts.push(assign_one(x));
+        function.blocks[then_block.to_usize()].terminator = MirTerminator::Goto(join);
+        if assign_in_else {
+            function.blocks[else_block.to_usize()].stmts.push(assign_one(x));
+        }
+        function.blocks[else_block.to_usize()].terminator = MirTerminator::Goto(join);
+        function.entry = entry;
+        function
+    }
+
+    #[test]
+    fn accepts_initialization_in_every_branch() {
+        assert!(check_initialization(&branching_function(true)).is_empty());
+    }
+
+    #[test]
+    fn reports_local_missing_initialization_on_one_path() {
+        let diags = check_initialization(&branching_function(false));
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].message.message, "`x` is used before being initialized");
+        assert_eq!(diags[0].labels[0].span, sp(40, 43));
+        assert_eq!(diags[0].labels[1].span, sp(10, 16));
+    }
+
+    #[test]
+    fn accepts_immutable_local_assigned_once_in_each_branch() {
+        let mut function = branching_function(true);
+        function.locals[1].assign_once = true;
+        assert!(check_reassignment(&function).is_empty());
+    }
+
+    #[test]
+    fn reports_immutable_local_assigned_after_a_branch_assigned_it() {
+        let mut function = branching_function(false);
+        function.locals[1].assign_once = true;
+        let x = MirLocalId::from_usize(1);
+        function.blocks[3].stmts.push(assign_one(x));
+
+        let diags = check_reassignment(&function);
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].message.message, "cannot assign twice to immutable binding `x`");
+        assert_eq!(diags[0].labels[1].span, sp(10, 16));
+    }
+}
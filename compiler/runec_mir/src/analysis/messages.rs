@@ -0,0 +1,15 @@
+use runec_utils::define_messages;
+
+define_messages! {
+    USE_OF_MOVED_VALUE => "use of moved value: `{ name }`",
+    VALUE_MOVED_HERE => "value moved here",
+    VALUE_USED_AFTER_MOVE => "value used here after move",
+    MOVE_OUT_OF_CAPTURE => "cannot move captured value `{ name }` out of its closure",
+    CAPTURE_MOVED_HERE => "moved out of the closure here",
+    USED_BEFORE_INITIALIZED => "`{ name }` is used before being initialized",
+    USED_HERE_UNINITIALIZED => "used here before being initialized",
+    DECLARED_WITHOUT_VALUE => "binding declared here without a value",
+    ASSIGNED_TWICE => "cannot assign twice to immutable binding `{ name }`",
+    ASSIGNED_AGAIN_HERE => "assigned again here",
+    DECLARED_IMMUTABLE => "declared here without `mut`",
+}
@@ -0,0 +1,206 @@
+use runec_errors::diagnostics::Diagnostic;
+use runec_errors::labels::DiagLabel;
+use runec_errors::message::DiagMessage;
+use runec_source::span::Span;
+
+use super::dataflow::{ForwardAnalysis, block_entry_states};
+use super::messages;
+use crate::block::{MirRvalue, MirStmt};
+use crate::function::MirFunction;
+use crate::ids::MirLocalId;
+use crate::operand::MirOperand;
+
+/// Reports every read of a local whose value may already have been moved out.
+///
+/// A local is moved by a [`MirOperand::Move`] and becomes usable again once it
+/// is assigned. A value moved on any path into a read counts as moved.
+/// Values a closure body captures belong to the closure, which may be called
+/// again, so the body cannot move them out at all.
+pub fn check_moves(function: &MirFunction<'_>) -> Vec<Diagnostic<'static>> {
+    let analysis = MovedLocals { function };
+    let mut diags = Vec::new();
+    let captured = captured_locals(function);
+
+    for (block, state) in function.blocks.iter().zip(block_entry_states(&analysis, function)) {
+        let Some(mut state) = state else {
+            continue;
+        };
+
+        for stmt in &block.stmts {
+            for operand in stmt.operands() {
+                check_operand(function, operand, stmt.span(), &state, &mut diags);
+                check_capture_move(function, operand, stmt.span(), &captured, &mut diags);
+            }
+            analysis.apply_stmt(stmt, &mut state);
+        }
+        let span = block.terminator.span().unwrap_or(function.ret_span);
+        for operand in block.terminator.operands() {
+            check_operand(function, operand, span, &state, &mut diags);
+            check_capture_move(function, operand, span, &captured, &mut diags);
+        }
+    }
+
+    diags
+}
+
+/// Locals of a closure body that are loaded from its environment.
+fn captured_locals(function: &MirFunction<'_>) -> Vec<MirLocalId> {
+    if function.closure.is_none() {
+        return Vec::new();
+    }
+    function.blocks[function.entry.to_usize()]
+        .stmts
+        .iter()
+        .filter_map(|stmt| match stmt {
+            MirStmt::Assign { dst, rhs: MirRvalue::Capture(_), .. } => Some(dst.local),
+            _ => None,
+        })
+        .collect()
+}
+
+fn check_capture_move(
+    function: &MirFunction<'_>,
+    operand: &MirOperand,
+    span: Span,
+    captured: &[MirLocalId],
+    diags: &mut Vec<Diagnostic<'static>>,
+) {
+    let MirOperand::Move(place) = operand else {
+        return;
+    };
+    if !captured.contains(&place.local) {
+        return;
+    }
+
+    let name = function.locals[place.local.to_usize()].name.unwrap_or("<temporary>");
+    let moved_at = function.move_span(span, place.local);
+    diags.push(
+        *Diagnostic::error(DiagMessage::new(messages::MOVE_OUT_OF_CAPTURE, &[("name", name)]))
+            .add_label(DiagLabel::simple_primary(messages::CAPTURE_MOVED_HERE, moved_at)),
+    );
+}
+
+fn check_operand(
+    function: &MirFunction<'_>,
+    operand: &MirOperand,
+    span: Span,
+    state: &MovedState,
+    diags: &mut Vec<Diagnostic<'static>>,
+) {
+    let Some(place) = operand.place() else {
+        return;
+    };
+    let Some(moved_at) = state[place.local.to_usize()] else {
+        return;
+    };
+
+    let name = function.locals[place.local.to_usize()].name.unwrap_or("<temporary>");
+    diags.push(
+        *Diagnostic::error(DiagMessage::new(messages::USE_OF_MOVED_VALUE, &[("name", name)]))
+            .add_label(DiagLabel::simple_primary(messages::VALUE_USED_AFTER_MOVE, span))
+            .add_label(DiagLabel::simple_secondary(messages::VALUE_MOVED_HERE, moved_at)),
+    );
+}
+
+/// For every local, the span of a move that may have left it uninitialized.
+type MovedState = Vec<Option<Span>>;
+
+struct MovedLocals<'a, 'src> {
+    function: &'a MirFunction<'src>,
+}
+
+impl ForwardAnalysis for MovedLocals<'_, '_> {
+    type State = MovedState;
+
+    fn entry_state(&self, function: &MirFunction<'_>) -> MovedState {
+        vec![None; function.locals.len()]
+    }
+
+    fn join(&self, state: &mut MovedState, incoming: &MovedState) {
+        for (moved, incoming) in state.iter_mut().zip(incoming) {
+            if moved.is_none() {
+                *moved = *incoming;
+            }
+        }
+    }
+
+    fn apply_stmt(&self, stmt: &MirStmt, state: &mut MovedState) {
+        for operand in stmt.operands() {
+            if let MirOperand::Move(place) = operand {
+                state[place.local.to_usize()] =
+                    Some(self.function.move_span(stmt.span(), place.local));
+            }
+        }
+        if let Some(dst) = stmt.dst() {
+            state[dst.local.to_usize()] = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use runec_hir::ids::HirId;
+    use runec_source::byte_pos::BytePos;
+    use runec_source::source_map::SourceId;
+    use runec_source::span::Span;
+
+    use super::check_moves;
+    use crate::block::{MirBlock, MirRvalue, MirStmt, MirTerminator};
+    use crate::function::MirFunction;
+    use crate::operand::{MirOperand, MirPlace};
+    use crate::ty::MirTy;
+
+    fn sp(lo: usize, hi: usize) -> Span {
+        Span::new(BytePos::from_usize(lo), BytePos::from_usize(hi), SourceId::from_usize(0))
+    }
+
+    #[test]
+    fn reassignment_makes_moved_local_usable_again() {
+        let mut function =
+            MirFunction::new(HirId::from_usize(0), "main", MirTy::Unit, sp(0, 0), sp(0, 0));
+        let value = function.push_local(Some("value"), MirTy::Managed, sp(0, 0));
+        let other = function.push_local(Some("other"), MirTy::Managed, sp(0, 0));
+        let mut entry = MirBlock::new(MirTerminator::Return(None));
+        entry.stmts.push(MirStmt::Drop {
+            operand: MirOperand::Move(MirPlace::new(value)),
+            span: sp(1, 2),
+        });
+        entry.stmts.push(MirStmt::Assign {
+            dst: MirPlace::new(value),
+            rhs: MirRvalue::Use(MirOperand::Copy(MirPlace::new(other))),
+            span: sp(3, 4),
+        });
+        entry.stmts.push(MirStmt::Drop {
+            operand: MirOperand::Move(MirPlace::new(value)),
+            span: sp(5, 6),
+        });
+        function.entry = function.push_block(entry);
+
+        assert!(check_moves(&function).is_empty());
+    }
+
+    #[test]
+    fn labels_both_move_and_later_use() {
+        let mut function =
+            MirFunction::new(HirId::from_usize(0), "main", MirTy::Unit, sp(0, 0), sp(0, 0));
+        let value = function.push_local(Some("value"), MirTy::Managed, sp(0, 0));
+        let copy = function.push_local(None, MirTy::Managed, sp(0, 0));
+        let mut entry = MirBlock::new(MirTerminator::Return(None));
+        entry.stmts.push(MirStmt::Drop {
+            operand: MirOperand::Move(MirPlace::new(value)),
+            span: sp(1, 2),
+        });
+        entry.stmts.push(MirStmt::Assign {
+            dst: MirPlace::new(copy),
+            rhs: MirRvalue::Use(MirOperand::Copy(MirPlace::new(value))),
+            span: sp(3, 4),
+        });
+        function.entry = function.push_block(entry);
+
+        let diags = check_moves(&function);
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].message.message, "use of moved value: `value`");
+        assert_eq!(diags[0].labels[0].span, sp(3, 4));
+        assert_eq!(diags[0].labels[1].span, sp(1, 2));
+    }
+}
@@ -1,6 +1,8 @@
+use runec_builtins::Intrinsic;
 use runec_source::span::Span;
 
 use crate::function::MirCallee;
+use crate::ids::MirBlockId;
 use crate::operand::{MirOperand, MirPlace};
 
 #[derive(Debug, Clone, PartialEq)]
@@ -17,16 +19,342 @@ impl MirBlock {
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum MirStmt {
-    Assign { dst: MirPlace, rhs: MirRvalue, span: Span },
+    Assign {
+        dst: MirPlace,
+        rhs: MirRvalue,
+        span: Span,
+    },
+    /// Destroys a value consumed by `drop`.
+    Drop {
+        operand: MirOperand,
+        span: Span,
+    },
+    /// Replaces the value held by a cell.
+    StoreCell {
+        cell: MirOperand,
+        value: MirOperand,
+        span: Span,
+    },
+    /// Advances the iterator stored in the place `iter` is a copy of, and
+    /// assigns its next item to `dst`, or `null` once it is exhausted.
+    Next {
+        iter: MirOperand,
+        dst: MirPlace,
+        span: Span,
+    },
+    /// Replaces the element at `index` of a vector or slice, after checking
+    /// that it is in bounds.
+    StoreIndex {
+        base: MirOperand,
+        index: MirOperand,
+        value: MirOperand,
+        span: Span,
+    },
+    /// Writes `value` to the memory a raw pointer points to.
+    Store {
+        pointer: MirOperand,
+        value: MirOperand,
+        span: Span,
+    },
+}
+
+impl MirStmt {
+    pub fn span(&self) -> Span {
+        match self {
+            MirStmt::Assign { span, .. }
+            | MirStmt::Drop { span, .. }
+            | MirStmt::StoreCell { span, .. }
+            | MirStmt::Next { span, .. }
+            | MirStmt::StoreIndex { span, .. }
+            | MirStmt::Store { span, .. } => *span,
+        }
+    }
+
+    pub fn span_mut(&mut self) -> &mut Span {
+        match self {
+            MirStmt::Assign { span, .. }
+            | MirStmt::Drop { span, .. }
+            | MirStmt::StoreCell { span, .. }
+            | MirStmt::Next { span, .. }
+            | MirStmt::StoreIndex { span, .. }
+            | MirStmt::Store { span, .. } => span,
+        }
+    }
+
+    /// Returns the place this statement assigns a new value to, if any.
+    pub fn dst(&self) -> Option<MirPlace> {
+        match self {
+            MirStmt::Assign { dst, .. } | MirStmt::Next { dst, .. } => Some(*dst),
+            MirStmt::Drop { .. }
+            | MirStmt::StoreCell { .. }
+            | MirStmt::StoreIndex { .. }
+            | MirStmt::Store { .. } => None,
+        }
+    }
+
+    /// Returns the operands read by this statement in evaluation order.
+    pub fn operands(&self) -> Vec<&MirOperand> {
+        match self {
+            MirStmt::Assign { rhs, .. } => rhs.operands(),
+            MirStmt::Drop { operand, .. } | MirStmt::Next { iter: operand, .. } => vec![operand],
+            MirStmt::StoreCell { cell, value, .. } => vec![cell, value],
+            MirStmt::StoreIndex { base, index, value, .. } => vec![base, index, value],
+            MirStmt::Store { pointer, value, .. } => vec![pointer, value],
+        }
+    }
+
+    /// Returns the operands read by this statement, in the order of
+    /// [`MirStmt::operands`], for rewriting.
+    pub fn operands_mut(&mut self) -> Vec<&mut MirOperand> {
+        match self {
+            MirStmt::Assign { rhs, .. } => rhs.operands_mut(),
+            MirStmt::Drop { operand, .. } | MirStmt::Next { iter: operand, .. } => vec![operand],
+            MirStmt::StoreCell { cell, value, .. } => vec![cell, value],
+            MirStmt::StoreIndex { base, index, value, .. } => vec![base, index, value],
+            MirStmt::Store { pointer, value, .. } => vec![pointer, value],
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum MirRvalue {
     Use(MirOperand),
-    Call { callee: MirCallee, args: Box<[MirOperand]> },
+    Call {
+        callee: MirCallee,
+        args: Box<[MirOperand]>,
+    },
+    /// Calls the closure `callee`, passing its environment before `args`.
+    CallIndirect {
+        callee: MirOperand,
+        args: Box<[MirOperand]>,
+    },
+    /// Allocates the environment of a closure whose body is `function` and
+    /// stores `captures` into it.
+    MakeClosure {
+        function: MirCallee,
+        captures: Box<[MirOperand]>,
+    },
+    /// Reads captured value `index` out of the environment of the closure
+    /// being run, which is always its first parameter.
+    Capture(usize),
+    /// Allocates a cell holding the operand.
+    MakeCell(MirOperand),
+    /// Reads the value held by a cell.
+    LoadCell(MirOperand),
+    /// Applies `op` to two operands of the same type. Either operand of `Eq`
+    /// and `Ne` may be `null`, and records are compared field by field; the
+    /// other operators take integers.
+    Binary {
+        op: MirBinOp,
+        lhs: MirOperand,
+        rhs: MirOperand,
+    },
+    /// Converts a value of `T` to `T?`.
+    WrapNullable(MirOperand),
+    /// Converts a `T?` already checked against `null` to `T`.
+    UnwrapNullable(MirOperand),
+    /// Builds a successful fallible value from its `ok` value.
+    WrapOk(MirOperand),
+    /// Builds a failed fallible value from its `err` value.
+    WrapErr(MirOperand),
+    /// Reads the `ok` value of a fallible value known to hold one.
+    UnwrapOk(MirOperand),
+    /// Reads the `err` value of a fallible value known to hold one.
+    UnwrapErr(MirOperand),
+    /// Tests whether a fallible value holds an error.
+    IsErr(MirOperand),
+    /// Reads the value a raw pointer points to, typed as the destination.
+    Load(MirOperand),
+    /// Allocates a record of the destination type holding one operand per
+    /// field; `None` fields are zeroed. Union records are given one operand.
+    MakeRecord(Box<[Option<MirOperand>]>),
+    /// Reads field `index` of a record or union.
+    LoadField {
+        record: MirOperand,
+        index: usize,
+    },
+    /// Builds an integer range out of its bounds.
+    MakeRange {
+        start: MirOperand,
+        end: MirOperand,
+        inclusive: bool,
+    },
+    /// Applies a builtin operation that codegen expands inline. Method
+    /// intrinsics take their receiver as the first operand.
+    Intrinsic {
+        intrinsic: Intrinsic,
+        args: Box<[MirOperand]>,
+    },
+    /// Reads the element at an integer `index` of a vector or slice, after
+    /// checking that it is in bounds.
+    Index {
+        base: MirOperand,
+        index: MirOperand,
+    },
+    /// Borrows the elements of a vector or slice covered by `range`, after
+    /// checking that it is in bounds.
+    Slice {
+        base: MirOperand,
+        range: MirOperand,
+    },
+}
+
+impl MirRvalue {
+    pub fn operands(&self) -> Vec<&MirOperand> {
+        match self {
+            MirRvalue::Use(operand)
+            | MirRvalue::WrapNullable(operand)
+            | MirRvalue::UnwrapNullable(operand)
+            | MirRvalue::WrapOk(operand)
+            | MirRvalue::WrapErr(operand)
+            | MirRvalue::UnwrapOk(operand)
+            | MirRvalue::UnwrapErr(operand)
+            | MirRvalue::IsErr(operand)
+            | MirRvalue::Load(operand)
+            | MirRvalue::MakeCell(operand)
+            | MirRvalue::LoadCell(operand)
+            | MirRvalue::LoadField { record: operand, .. } => vec![operand],
+            MirRvalue::Call { args, .. } | MirRvalue::Intrinsic { args, .. } => {
+                args.iter().collect()
+            }
+            MirRvalue::CallIndirect { callee, args } => {
+                std::iter::once(callee).chain(args.iter()).collect()
+            }
+            MirRvalue::MakeClosure { captures, .. } => captures.iter().collect(),
+            MirRvalue::MakeRecord(fields) => fields.iter().flatten().collect(),
+            MirRvalue::Capture(_) => Vec::new(),
+            MirRvalue::Binary { lhs, rhs, .. }
+            | MirRvalue::MakeRange { start: lhs, end: rhs, .. }
+            | MirRvalue::Index { base: lhs, index: rhs }
+            | MirRvalue::Slice { base: lhs, range: rhs } => {
+                vec![lhs, rhs]
+            }
+        }
+    }
+
+    /// Returns the operands of this rvalue, in the order of
+    /// [`MirRvalue::operands`], for rewriting.
+    pub fn operands_mut(&mut self) -> Vec<&mut MirOperand> {
+        match self {
+            MirRvalue::Use(operand)
+            | MirRvalue::WrapNullable(operand)
+            | MirRvalue::UnwrapNullable(operand)
+            | MirRvalue::WrapOk(operand)
+            | MirRvalue::WrapErr(operand)
+            | MirRvalue::UnwrapOk(operand)
+            | MirRvalue::UnwrapErr(operand)
+            | MirRvalue::IsErr(operand)
+            | MirRvalue::Load(operand)
+            | MirRvalue::MakeCell(operand)
+            | MirRvalue::LoadCell(operand)
+            | MirRvalue::LoadField { record: operand, .. } => vec![operand],
+            MirRvalue::Call { args, .. } | MirRvalue::Intrinsic { args, .. } => {
+                args.iter_mut().collect()
+            }
+            MirRvalue::CallIndirect { callee, args } => {
+                std::iter::once(callee).chain(args.iter_mut()).collect()
+            }
+            MirRvalue::MakeClosure { captures, .. } => captures.iter_mut().collect(),
+            MirRvalue::MakeRecord(fields) => fields.iter_mut().flatten().collect(),
+            MirRvalue::Capture(_) => Vec::new(),
+            MirRvalue::Binary { lhs, rhs, .. }
+            | MirRvalue::MakeRange { start: lhs, end: rhs, .. }
+            | MirRvalue::Index { base: lhs, index: rhs }
+            | MirRvalue::Slice { base: lhs, range: rhs } => {
+                vec![lhs, rhs]
+            }
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MirBinOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    /// Wrapping integer addition.
+    Add,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum MirTerminator {
     Return(Option<MirOperand>),
+    Goto(MirBlockId),
+    /// Continues to `then_block` when the `bool` condition holds and to
+    /// `else_block` otherwise.
+    Branch {
+        cond: MirOperand,
+        then_block: MirBlockId,
+        else_block: MirBlockId,
+        span: Span,
+    },
+    /// Ends the program with the `str` message, reporting the source
+    /// location of `span`.
+    Panic {
+        message: MirOperand,
+        span: Span,
+    },
+    /// Ends a block that control never reaches the end of, such as the
+    /// rest of a function body after every path has thrown.
+    Unreachable,
+}
+
+impl MirTerminator {
+    /// Returns the blocks control may continue to after this terminator.
+    pub fn successors(&self) -> Vec<MirBlockId> {
+        match self {
+            MirTerminator::Return(_) | MirTerminator::Panic { .. } | MirTerminator::Unreachable => {
+                Vec::new()
+            }
+            MirTerminator::Goto(target) => vec![*target],
+            MirTerminator::Branch { then_block, else_block, .. } => vec![*then_block, *else_block],
+        }
+    }
+
+    pub fn operands(&self) -> Vec<&MirOperand> {
+        match self {
+            MirTerminator::Return(operand) => operand.iter().collect(),
+            MirTerminator::Goto(_) | MirTerminator::Unreachable => Vec::new(),
+            MirTerminator::Branch { cond, .. } => vec![cond],
+            MirTerminator::Panic { message, .. } => vec![message],
+        }
+    }
+
+    pub fn operands_mut(&mut self) -> Vec<&mut MirOperand> {
+        match self {
+            MirTerminator::Return(operand) => operand.iter_mut().collect(),
+            MirTerminator::Goto(_) | MirTerminator::Unreachable => Vec::new(),
+            MirTerminator::Branch { cond, .. } => vec![cond],
+            MirTerminator::Panic { message, .. } => vec![message],
+        }
+    }
+
+    /// Returns the blocks control may continue to, in the order of
+    /// [`MirTerminator::successors`], for rewriting.
+    pub fn successors_mut(&mut self) -> Vec<&mut MirBlockId> {
+        match self {
+            MirTerminator::Return(_) | MirTerminator::Panic { .. } | MirTerminator::Unreachable => {
+                Vec::new()
+            }
+            MirTerminator::Goto(target) => vec![target],
+            MirTerminator::Branch { then_block, else_block, .. } => vec![then_block, else_block],
+        }
+    }
+
+    /// Returns the span of the source that produced this terminator, if it
+    /// has one. Returns are attributed to the function's return type.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            MirTerminator::Branch { span, .. } | MirTerminator::Panic { span, .. } => Some(*span),
+            MirTerminator::Return(_) | MirTerminator::Goto(_) | MirTerminator::Unreachable => None,
+        }
+    }
+
+    pub fn span_mut(&mut self) -> Option<&mut Span> {
+        match self {
+            MirTerminator::Branch { span, .. } | MirTerminator::Panic { span, .. } => Some(span),
+            MirTerminator::Return(_) | MirTerminator::Goto(_) | MirTerminator::Unreachable => None,
+        }
+    }
 }
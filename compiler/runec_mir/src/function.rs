@@ -1,45 +1,100 @@
+use std::collections::HashMap;
+
 use runec_abi::RuntimeFunctionId;
 use runec_hir::ids::HirId;
+use runec_semantic::typeck::GenericArg;
 use runec_source::span::Span;
 
 use crate::block::MirBlock;
-use crate::ids::{MirBlockId, MirLocalId};
-use crate::ty::MirTy;
+use crate::ids::{MirBlockId, MirClosureId, MirExternId, MirInstanceId, MirLocalId};
+use crate::operand::{MirImmediate, MirOperand};
+use crate::ty::{MirStruct, MirTy};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct MirFunction<'src> {
     pub hir_id: HirId,
+    /// Instantiation this function was lowered for, if it is generic.
+    pub instance: Option<MirInstanceId>,
+    /// Closure this function is the body of. Closure bodies take their
+    /// environment as the first parameter.
+    pub closure: Option<MirClosureId>,
+    /// Values captured by a closure body, in environment order.
+    pub env: Option<MirStruct>,
     pub span: Span,
     pub name: &'src str,
+    /// Modules from the crate root down to the one defining the function.
+    pub module_path: Box<[&'src str]>,
     pub params: Box<[MirLocalId]>,
     pub locals: Vec<MirLocal<'src>>,
     pub blocks: Vec<MirBlock>,
     pub entry: MirBlockId,
     pub ret_ty: MirTy,
     pub ret_span: Span,
+    /// Span of the expression each named local is moved by, keyed by the
+    /// span of the statement moving it.
+    pub move_spans: HashMap<(Span, MirLocalId), Span>,
 }
 
 impl<'src> MirFunction<'src> {
     pub fn new(hir_id: HirId, name: &'src str, ret_ty: MirTy, span: Span, ret_span: Span) -> Self {
         Self {
             hir_id,
+            instance: None,
+            closure: None,
+            env: None,
             span,
             name,
+            module_path: Box::new([]),
             params: Box::new([]),
             locals: Vec::new(),
             blocks: Vec::new(),
             entry: MirBlockId::from_usize(0),
             ret_ty,
             ret_span,
+            move_spans: HashMap::new(),
+        }
+    }
+
+    /// Returns the callee that refers to this function.
+    pub fn callee(&self) -> MirCallee {
+        match (self.closure, self.instance) {
+            (Some(closure), _) => MirCallee::Closure(closure),
+            (None, Some(instance)) => MirCallee::Instance(instance),
+            (None, None) => MirCallee::Function(self.hir_id),
         }
     }
 
     pub fn push_local(&mut self, name: Option<&'src str>, ty: MirTy, span: Span) -> MirLocalId {
         let id = MirLocalId::from_usize(self.locals.len());
-        self.locals.push(MirLocal { name, ty, span });
+        self.locals.push(MirLocal { name, ty, span, assign_once: false });
         id
     }
 
+    /// Returns the type of an operand, or `None` for `null`, which only gets a
+    /// type once it is coerced to a nullable one.
+    pub fn operand_ty(&self, operand: &MirOperand) -> Option<MirTy> {
+        Some(match operand {
+            MirOperand::Copy(place) | MirOperand::Move(place) => {
+                self.locals[place.local.to_usize()].ty.clone()
+            }
+            MirOperand::Constant(_) => MirTy::Str,
+            MirOperand::Immediate(value) => match value {
+                MirImmediate::Unit => MirTy::Unit,
+                MirImmediate::Bool(_) => MirTy::Bool,
+                MirImmediate::Int { ty, .. } => MirTy::Int(*ty),
+                MirImmediate::Float { ty, .. } => MirTy::Float(*ty),
+                MirImmediate::Char(_) => MirTy::Char,
+                MirImmediate::Null => return None,
+            },
+        })
+    }
+
+    /// Returns the span of the expression that `stmt_span` moves `local`
+    /// by, falling back to the statement itself.
+    pub fn move_span(&self, stmt_span: Span, local: MirLocalId) -> Span {
+        self.move_spans.get(&(stmt_span, local)).copied().unwrap_or(stmt_span)
+    }
+
     pub fn push_block(&mut self, block: MirBlock) -> MirBlockId {
         let id = MirBlockId::from_usize(self.blocks.len());
         self.blocks.push(block);
@@ -47,15 +102,46 @@ impl<'src> MirFunction<'src> {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MirLocal<'src> {
     pub name: Option<&'src str>,
     pub ty: MirTy,
     pub span: Span,
+    /// Immutable `let` declared without a value, which may be assigned at
+    /// most once.
+    pub assign_once: bool,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum MirCallee {
     Function(HirId),
+    /// Generic function instantiated with concrete generic arguments.
+    Instance(MirInstanceId),
     Runtime(RuntimeFunctionId),
+    /// Native function from an `extern act` declaration.
+    Extern(MirExternId),
+    /// Body of a closure, or the shim that lets a function item be used as a
+    /// value.
+    Closure(MirClosureId),
+}
+
+/// `extern act` declaration, imported under its unmangled name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MirExtern<'src> {
+    pub hir_id: HirId,
+    pub name: &'src str,
+    pub params: Box<[MirTy]>,
+    pub ret_ty: MirTy,
+    pub span: Span,
+}
+
+/// Generic function `def` instantiated with `args`, which contain no generic
+/// parameters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MirInstance {
+    pub def: HirId,
+    pub args: Box<[GenericArg]>,
+    /// Lowered types of the type arguments in `args`, which name the
+    /// instance; `None` for constants and types MIR cannot represent.
+    pub arg_tys: Box<[Option<MirTy>]>,
 }
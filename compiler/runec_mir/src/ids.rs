@@ -40,6 +40,34 @@ impl MirLocalId {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MirInstanceId(u32);
+
+impl MirInstanceId {
+    pub fn from_usize(id: usize) -> Self {
+        assert!(id <= u32::MAX as usize, "MirInstanceId overflow");
+        Self(id as u32)
+    }
+
+    pub fn to_usize(self) -> usize {
+        self.0 as usize
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MirExternId(u32);
+
+impl MirExternId {
+    pub fn from_usize(id: usize) -> Self {
+        assert!(id <= u32::MAX as usize, "MirExternId overflow");
+        Self(id as u32)
+    }
+
+    pub fn to_usize(self) -> usize {
+        self.0 as usize
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct MirConstantId(u32);
 
@@ -53,3 +81,17 @@ impl MirConstantId {
         self.0 as usize
     }
 }
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MirClosureId(u32);
+
+impl MirClosureId {
+    pub fn from_usize(id: usize) -> Self {
+        assert!(id <= u32::MAX as usize, "MirClosureId overflow");
+        Self(id as u32)
+    }
+
+    pub fn to_usize(self) -> usize {
+        self.0 as usize
+    }
+}
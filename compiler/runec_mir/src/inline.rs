@@ -0,0 +1,404 @@
+//! Inlines the iterator adapters of `std::itertools` and the closures they
+//! are given into their callers, so that a chain of adapters runs as plain
+//! loops without a call per item.
+
+use std::collections::HashMap;
+
+use crate::block::{MirBlock, MirRvalue, MirStmt, MirTerminator};
+use crate::function::{MirCallee, MirFunction};
+use crate::ids::{MirBlockId, MirLocalId};
+use crate::module::MirModule;
+use crate::operand::{MirImmediate, MirOperand, MirPlace};
+
+/// Module whose instances are inlined into every caller.
+const ITERTOOLS: [&str; 2] = ["std", "itertools"];
+
+/// Each round inlines the calls the previous one exposed: an adapter in the
+/// first, the closure it calls in the second, and so on.
+const MAX_ROUNDS: usize = 4;
+
+/// Inlines every call of a `std::itertools` instance, and every call of a
+/// closure that captures nothing, such as the shim of a function item, once
+/// the closure value is known.
+pub fn inline_adapters(module: &mut MirModule<'_>) {
+    let bodies: HashMap<_, _> = module
+        .functions
+        .iter()
+        .filter(|function| is_inlinable(function))
+        .map(|function| (function.callee(), function.clone()))
+        .collect();
+    if bodies.is_empty() {
+        return;
+    }
+    for function in &mut module.functions {
+        for _ in 0..MAX_ROUNDS {
+            devirtualize(function);
+            if !inline_calls(function, &bodies) {
+                break;
+            }
+        }
+    }
+}
+
+fn is_inlinable(function: &MirFunction<'_>) -> bool {
+    match function.closure {
+        // Captures are read from the environment of the running closure,
+        // which an inlined body no longer has.
+        Some(_) => function.env.as_ref().is_some_and(|env| env.fields.is_empty()),
+        None => function.instance.is_some() && *function.module_path == ITERTOOLS,
+    }
+}
+
+/// Turns indirect calls of locals that can only ever hold one closure body
+/// into direct calls of that body, passing the environment first.
+fn devirtualize(function: &mut MirFunction<'_>) {
+    let mut assignments = HashMap::<MirLocalId, usize>::new();
+    for stmt in function.blocks.iter().flat_map(|block| block.stmts.iter()) {
+        if let Some(dst) = stmt.dst() {
+            *assignments.entry(dst.local).or_default() += 1;
+        }
+    }
+
+    // Locals assigned once, either a closure or a copy of a local already
+    // known to hold one. Parameters hold whatever the caller passed.
+    let mut closures = HashMap::new();
+    loop {
+        let mut changed = false;
+        for stmt in function.blocks.iter().flat_map(|block| block.stmts.iter()) {
+            let MirStmt::Assign { dst, rhs, .. } = stmt else {
+                continue;
+            };
+            if assignments[&dst.local] != 1
+                || function.params.contains(&dst.local)
+                || closures.contains_key(&dst.local)
+            {
+                continue;
+            }
+            let body = match rhs {
+                MirRvalue::MakeClosure { function, .. } => Some(*function),
+                MirRvalue::Use(operand) => {
+                    operand.place().and_then(|place| closures.get(&place.local).copied())
+                }
+                _ => None,
+            };
+            if let Some(body) = body {
+                closures.insert(dst.local, body);
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    for stmt in function.blocks.iter_mut().flat_map(|block| block.stmts.iter_mut()) {
+        if let MirStmt::Assign { rhs, .. } = stmt
+            && let MirRvalue::CallIndirect { callee, args } = rhs
+            && let Some(body) = callee.place().and_then(|place| closures.get(&place.local))
+        {
+            let args = std::iter::once(callee.clone()).chain(args.iter().cloned()).collect();
+            *rhs = MirRvalue::Call { callee: *body, args };
+        }
+    }
+}
+
+/// Inlines the calls of `bodies` in the blocks `function` has now, leaving
+/// those of the inlined bodies to the next round. Returns whether any call
+/// was inlined.
+fn inline_calls<'src>(
+    function: &mut MirFunction<'src>,
+    bodies: &HashMap<MirCallee, MirFunction<'src>>,
+) -> bool {
+    let caller = function.callee();
+    let mut pending: Vec<_> = (0..function.blocks.len()).collect();
+    let mut inlined = false;
+    while let Some(block) = pending.pop() {
+        let call =
+            function.blocks[block].stmts.iter().enumerate().find_map(|(index, stmt)| match stmt {
+                MirStmt::Assign { rhs: MirRvalue::Call { callee, .. }, .. }
+                    if *callee != caller =>
+                {
+                    bodies.get(callee).map(|body| (index, body))
+                }
+                _ => None,
+            });
+        if let Some((index, body)) = call {
+            // The rest of the block may hold further calls.
+            pending.push(inline_call(function, block, index, body).to_usize());
+            inlined = true;
+        }
+    }
+    inlined
+}
+
+/// Replaces the call at `index` of `block` by a copy of `body` and returns
+/// the block holding the statements that followed the call.
+///
+/// Parameters take their argument like the callee would, and each return
+/// assigns the returned value to the destination of the call and then drops
+/// the managed locals of the body, which the callee would have released.
+/// The statements of an inlined adapter take the span of the call, so that
+/// its panics and bounds checks are reported in the program rather than in
+/// `std`; those of a closure keep their own.
+fn inline_call<'src>(
+    function: &mut MirFunction<'src>,
+    block: usize,
+    index: usize,
+    body: &MirFunction<'src>,
+) -> MirBlockId {
+    let rest = function.blocks[block].stmts.split_off(index + 1);
+    let Some(MirStmt::Assign { dst, rhs: MirRvalue::Call { args, .. }, span }) =
+        function.blocks[block].stmts.pop()
+    else {
+        unreachable!("inlined statement is a call");
+    };
+
+    let local_base = function.locals.len();
+    let block_base = function.blocks.len();
+    let local = |local: MirLocalId| MirLocalId::from_usize(local_base + local.to_usize());
+    let block_id = |block: MirBlockId| MirBlockId::from_usize(block_base + block.to_usize());
+    let after = MirBlockId::from_usize(block_base + body.blocks.len());
+    function.locals.extend(body.locals.iter().cloned());
+    for ((stmt_span, moved), expr_span) in &body.move_spans {
+        function.move_spans.insert((*stmt_span, local(*moved)), *expr_span);
+    }
+
+    for (param, arg) in body.params.iter().zip(args) {
+        function.blocks[block].stmts.push(MirStmt::Assign {
+            dst: MirPlace::new(local(*param)),
+            rhs: MirRvalue::Use(arg),
+            span,
+        });
+    }
+    let terminator = std::mem::replace(
+        &mut function.blocks[block].terminator,
+        MirTerminator::Goto(block_id(body.entry)),
+    );
+
+    let managed: Vec<_> = body
+        .locals
+        .iter()
+        .enumerate()
+        .filter(|(_, body_local)| body_local.ty.is_managed())
+        .map(|(id, _)| local(MirLocalId::from_usize(id)))
+        .collect();
+    let at_call = body.closure.is_none();
+    for body_block in &body.blocks {
+        let mut inlined = body_block.clone();
+        if at_call {
+            for stmt in &mut inlined.stmts {
+                *stmt.span_mut() = span;
+            }
+            if let Some(terminator_span) = inlined.terminator.span_mut() {
+                *terminator_span = span;
+            }
+        }
+        for stmt in &mut inlined.stmts {
+            match stmt {
+                MirStmt::Assign { dst, .. } | MirStmt::Next { dst, .. } => {
+                    dst.local = local(dst.local);
+                }
+                MirStmt::Drop { .. }
+                | MirStmt::StoreCell { .. }
+                | MirStmt::StoreIndex { .. }
+                | MirStmt::Store { .. } => {}
+            }
+            for operand in stmt.operands_mut() {
+                remap_operand(operand, local);
+            }
+        }
+        for operand in inlined.terminator.operands_mut() {
+            remap_operand(operand, local);
+        }
+        for target in inlined.terminator.successors_mut() {
+            *target = block_id(*target);
+        }
+        if let MirTerminator::Return(value) = &inlined.terminator {
+            let value = value.clone().unwrap_or(MirOperand::Immediate(MirImmediate::Unit));
+            inlined.stmts.push(MirStmt::Assign { dst, rhs: MirRvalue::Use(value), span });
+            for managed in managed.iter().rev() {
+                inlined.stmts.push(MirStmt::Drop {
+                    operand: MirOperand::Move(MirPlace::new(*managed)),
+                    span,
+                });
+            }
+            inlined.terminator = MirTerminator::Goto(after);
+        }
+        function.blocks.push(inlined);
+    }
+    function.push_block(MirBlock { stmts: rest, terminator })
+}
+
+fn remap_operand(operand: &mut MirOperand, local: impl Fn(MirLocalId) -> MirLocalId) {
+    if let MirOperand::Copy(place) | MirOperand::Move(place) = operand {
+        place.local = local(place.local);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use runec_hir::ids::HirId;
+    use runec_source::byte_pos::BytePos;
+    use runec_source::source_map::SourceId;
+    use runec_source::span::Span;
+
+    use super::inline_adapters;
+    use crate::block::{MirBlock, MirRvalue, MirStmt, MirTerminator};
+    use crate::function::{MirCallee, MirFunction};
+    use crate::ids::{MirClosureId, MirInstanceId};
+    use crate::module::MirModule;
+    use crate::operand::{MirImmediate, MirOperand, MirPlace};
+    use crate::ty::{MirIntTy, MirStruct, MirTy};
+
+    fn dummy() -> Span {
+        Span::new(BytePos::from_usize(0), BytePos::from_usize(0), SourceId::from_usize(0))
+    }
+
+    fn int(value: u128) -> MirOperand {
+        MirOperand::Immediate(MirImmediate::Int { value, ty: MirIntTy::USIZE })
+    }
+
+    /// Builds `main`, which calls the adapter `apply(f, 1)` with the closure
+    /// `|x| x`, whose environment holds `captures`.
+    fn module(captures: MirStruct) -> MirModule<'static> {
+        let i32_ty = MirTy::Int(MirIntTy::USIZE);
+        let mut module = MirModule::new();
+
+        let mut main =
+            MirFunction::new(HirId::from_usize(0), "main", MirTy::Unit, dummy(), dummy());
+        let closure = main.push_local(Some("f"), MirTy::Closure, dummy());
+        let result = main.push_local(Some("result"), i32_ty.clone(), dummy());
+        let mut entry = MirBlock::new(MirTerminator::Return(None));
+        entry.stmts.push(MirStmt::Assign {
+            dst: MirPlace::new(closure),
+            rhs: MirRvalue::MakeClosure {
+                function: MirCallee::Closure(MirClosureId::from_usize(0)),
+                captures: Box::new([]),
+            },
+            span: dummy(),
+        });
+        entry.stmts.push(MirStmt::Assign {
+            dst: MirPlace::new(result),
+            rhs: MirRvalue::Call {
+                callee: MirCallee::Instance(MirInstanceId::from_usize(0)),
+                args: Box::new([MirOperand::Copy(MirPlace::new(closure)), int(1)]),
+            },
+            span: dummy(),
+        });
+        main.entry = main.push_block(entry);
+        module.entry = Some(module.push_function(main));
+
+        let mut apply =
+            MirFunction::new(HirId::from_usize(1), "apply", i32_ty.clone(), dummy(), dummy());
+        apply.instance = Some(MirInstanceId::from_usize(0));
+        apply.module_path = Box::new(["std", "itertools"]);
+        let f = apply.push_local(Some("f"), MirTy::Closure, dummy());
+        let item = apply.push_local(Some("item"), i32_ty.clone(), dummy());
+        let applied = apply.push_local(None, i32_ty.clone(), dummy());
+        apply.params = Box::new([f, item]);
+        let mut entry =
+            MirBlock::new(MirTerminator::Return(Some(MirOperand::Copy(MirPlace::new(applied)))));
+        entry.stmts.push(MirStmt::Assign {
+            dst: MirPlace::new(applied),
+            rhs: MirRvalue::CallIndirect {
+                callee: MirOperand::Copy(MirPlace::new(f)),
+                args: Box::new([MirOperand::Copy(MirPlace::new(item))]),
+            },
+            span: dummy(),
+        });
+        apply.entry = apply.push_block(entry);
+        module.push_function(apply);
+
+        let mut identity =
+            MirFunction::new(HirId::from_usize(0), "main", i32_ty.clone(), dummy(), dummy());
+        identity.closure = Some(MirClosureId::from_usize(0));
+        identity.env = Some(captures);
+        let env = identity.push_local(None, MirTy::Closure, dummy());
+        let x = identity.push_local(Some("x"), i32_ty, dummy());
+        identity.params = Box::new([env, x]);
+        identity.entry = identity.push_block(MirBlock::new(MirTerminator::Return(Some(
+            MirOperand::Copy(MirPlace::new(x)),
+        ))));
+        module.push_function(identity);
+        module
+    }
+
+    fn calls(function: &MirFunction<'_>) -> Vec<MirRvalue> {
+        function
+            .blocks
+            .iter()
+            .flat_map(|block| block.stmts.iter())
+            .filter_map(|stmt| match stmt {
+                MirStmt::Assign {
+                    rhs: rhs @ (MirRvalue::Call { .. } | MirRvalue::CallIndirect { .. }),
+                    ..
+                } => Some(rhs.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn inlines_adapters_and_the_closures_they_call() {
+        let mut module = module(MirStruct { fields: Box::new([]) });
+        inline_adapters(&mut module);
+
+        let main = &module.functions[0];
+        assert_eq!(calls(main), []);
+        // The closure reaches the inlined body through the adapter's `f` and
+        // the closure's own environment parameter, which are both dropped
+        // once the body returns.
+        let drops = main
+            .blocks
+            .iter()
+            .flat_map(|block| block.stmts.iter())
+            .filter(|stmt| matches!(stmt, MirStmt::Drop { .. }))
+            .count();
+        assert_eq!(drops, 2);
+        assert!(main.blocks.iter().all(|block| {
+            block.terminator.successors().iter().all(|target| target.to_usize() < main.blocks.len())
+        }));
+    }
+
+    #[test]
+    fn calls_closures_that_capture_directly_without_inlining_them() {
+        let mut module = module(MirStruct { fields: Box::new([MirTy::Int(MirIntTy::USIZE)]) });
+        inline_adapters(&mut module);
+
+        let main = &module.functions[0];
+        let closure = MirCallee::Closure(MirClosureId::from_usize(0));
+        assert!(matches!(
+            calls(main).as_slice(),
+            [MirRvalue::Call { callee, args }] if *callee == closure && args.len() == 2
+        ));
+    }
+
+    #[test]
+    fn reports_inlined_adapters_at_their_call_and_closures_at_their_own_spans() {
+        let sp = |lo, hi| {
+            Span::new(BytePos::from_usize(lo), BytePos::from_usize(hi), SourceId::from_usize(0))
+        };
+        let (call, in_adapter, in_closure) = (sp(10, 20), sp(100, 110), sp(50, 60));
+        let mut module = module(MirStruct { fields: Box::new([]) });
+        *module.functions[0].blocks[0].stmts[1].span_mut() = call;
+        *module.functions[1].blocks[0].stmts[0].span_mut() = in_adapter;
+        let identity = &mut module.functions[2];
+        let x = identity.params[1];
+        identity.blocks[0].stmts.push(MirStmt::Assign {
+            dst: MirPlace::new(x),
+            rhs: MirRvalue::Use(MirOperand::Copy(MirPlace::new(x))),
+            span: in_closure,
+        });
+        inline_adapters(&mut module);
+
+        let spans: Vec<_> = module.functions[0]
+            .blocks
+            .iter()
+            .flat_map(|block| block.stmts.iter())
+            .map(|stmt| stmt.span())
+            .collect();
+        assert!(spans.contains(&call));
+        assert!(spans.contains(&in_closure));
+        assert!(!spans.contains(&in_adapter));
+    }
+}
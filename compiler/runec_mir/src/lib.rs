@@ -1,17 +1,21 @@
+pub mod analysis;
 pub mod block;
 pub mod constant;
 pub mod function;
 pub mod ids;
+pub mod inline;
 pub mod lowering;
 pub mod module;
 pub mod operand;
 pub mod ty;
 
-pub use block::{MirBlock, MirRvalue, MirStmt, MirTerminator};
+pub use block::{MirBinOp, MirBlock, MirRvalue, MirStmt, MirTerminator};
 pub use constant::MirConstant;
-pub use function::{MirCallee, MirFunction, MirLocal};
-pub use ids::{MirBlockId, MirConstantId, MirFunctionId, MirLocalId};
+pub use function::{MirCallee, MirExtern, MirFunction, MirInstance, MirLocal};
+pub use ids::{
+    MirBlockId, MirClosureId, MirConstantId, MirExternId, MirFunctionId, MirInstanceId, MirLocalId,
+};
 pub use lowering::{MirLowerResult, MirLowerer, lower_ty};
 pub use module::MirModule;
 pub use operand::{MirImmediate, MirOperand, MirPlace};
-pub use ty::{MirFloatTy, MirIntTy, MirTy};
+pub use ty::{MirFloatTy, MirIntTy, MirStruct, MirTy};
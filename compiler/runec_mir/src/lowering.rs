@@ -1,25 +1,35 @@
 use std::collections::HashMap;
 
-use runec_builtins::{BuiltinLowering, builtin_decl};
+use runec_abi::RUNTIME_UNCAUGHT_ERROR;
+use runec_ast::operators::BinaryOp;
+use runec_builtins::{
+    BuiltinId, BuiltinLowering, BuiltinReturn, DECIMAL, DISPLAY_CONTRACT, DURATION, HASH_MAP,
+    HASH_SET, INSTANT, Intrinsic, JSON, LINES, RANGE, READER, ROUNDING_MODE, TypeBits,
+    TypeConstraint, VEC, WRITER, builtin_decl, builtin_from_path, builtin_type_path,
+};
 use runec_errors::diagnostics::Diagnostic;
 use runec_errors::labels::DiagLabel;
 use runec_errors::message::DiagMessage;
-use runec_hir::expression::{HirExpr, HirLiteral, SpannedHirExpr};
+use runec_hir::expression::{
+    HirClosure, HirExpr, HirFieldValue, HirLiteral, SpannedHirExpr, strip_turbofish,
+};
 use runec_hir::ids::{HirId, HirLocalId};
-use runec_hir::item::{HirFunction, HirItem};
+use runec_hir::item::{HirExternFunction, HirFunction, HirItem};
 use runec_hir::map::HirMap;
 use runec_hir::resolution::Res;
 use runec_hir::statement::{HirBlock, HirStmt};
-use runec_semantic::typeck::{Ty, TypeInfo};
+use runec_semantic::typeck::{AdtKind, GenericArg, Ty, TypeInfo};
 use runec_source::span::Span;
 
-use crate::block::{MirBlock, MirRvalue, MirStmt, MirTerminator};
+use crate::analysis::check_function;
+use crate::block::{MirBinOp, MirBlock, MirRvalue, MirStmt, MirTerminator};
 use crate::constant::MirConstant;
-use crate::function::{MirCallee, MirFunction};
-use crate::ids::MirLocalId;
+use crate::function::{MirCallee, MirExtern, MirFunction, MirInstance};
+use crate::ids::{MirBlockId, MirClosureId, MirExternId, MirInstanceId, MirLocalId};
+use crate::inline::inline_adapters;
 use crate::module::MirModule;
 use crate::operand::{MirImmediate, MirOperand, MirPlace};
-use crate::ty::{MirFloatTy, MirIntTy, MirTy};
+use crate::ty::{MirFloatTy, MirIntTy, MirStruct, MirTy};
 
 #[derive(Debug, Default)]
 pub struct MirLowerResult<'src, 'diag> {
@@ -35,48 +45,334 @@ impl<'src, 'diag> MirLowerResult<'src, 'diag> {
 
 pub struct MirLowerer<'src, 'info, 'diag> {
     type_info: &'info TypeInfo<'src>,
+    /// Instantiations requested so far, so every one is lowered once.
+    instances: HashMap<(HirId, Box<[GenericArg]>), MirInstanceId>,
+    /// Number of instances each instance was requested through, indexed
+    /// by `MirInstanceId`.
+    instance_depths: Vec<usize>,
+    /// Instance depth of the function being lowered; `0` outside instances.
+    depth: usize,
+    externs: HashMap<HirId, MirExternId>,
+    /// Closure bodies lowered since the last function was pushed; they are
+    /// pushed right after it.
+    closures: Vec<MirFunction<'src>>,
+    /// Shims of function items used as values, built once every function
+    /// has been lowered.
+    shims: Vec<(HirId, MirClosureId)>,
+    closure_count: usize,
     res: MirLowerResult<'src, 'diag>,
 }
 
 struct FunctionLowerCtx<'src, 'mir> {
+    type_info: &'mir TypeInfo<'src>,
     function: HirId,
+    /// Generic arguments of the instance being lowered; empty otherwise.
+    generic_args: &'mir [GenericArg],
     lowered: &'mir mut MirFunction<'src>,
-    block: &'mir mut MirBlock,
+    /// Block that statements are currently appended to.
+    block: MirBlockId,
     locals: &'mir mut HashMap<HirLocalId, MirLocalId>,
+    /// Named locals read by value, with the span of the expression reading
+    /// them, until the statement moving them is pushed.
+    pending_moves: Vec<(MirLocalId, Span)>,
+    /// Blocks after the `for` loops around the current point, innermost
+    /// last, which `break` continues in.
+    loop_exits: Vec<MirBlockId>,
+}
+
+impl FunctionLowerCtx<'_, '_> {
+    fn push_stmt(&mut self, stmt: MirStmt) {
+        for operand in stmt.operands() {
+            if let MirOperand::Move(place) = operand
+                && let Some(pending) =
+                    self.pending_moves.iter().rposition(|(local, _)| *local == place.local)
+            {
+                let (local, span) = self.pending_moves.remove(pending);
+                self.lowered.move_spans.insert((stmt.span(), local), span);
+            }
+        }
+        self.lowered.blocks[self.block.to_usize()].stmts.push(stmt);
+    }
+
+    /// Reads a place that was about to be moved out by copy instead.
+    fn borrow(&mut self, place: MirPlace) -> MirOperand {
+        self.pending_moves.retain(|(local, _)| *local != place.local);
+        MirOperand::Copy(place)
+    }
+
+    /// Creates an empty block; its terminator is set once it is lowered.
+    fn new_block(&mut self) -> MirBlockId {
+        self.lowered.push_block(MirBlock::new(MirTerminator::Return(None)))
+    }
+
+    fn terminate(&mut self, terminator: MirTerminator) {
+        self.lowered.blocks[self.block.to_usize()].terminator = terminator;
+    }
+
+    /// Lowers a type of the function being lowered, substituting the
+    /// generic arguments of its instance.
+    fn lower_ty(&self, ty: &Ty) -> Option<MirTy> {
+        lower_ty(&ty.substitute(self.generic_args), self.type_info)
+    }
 }
 
 impl<'src, 'info, 'diag> MirLowerer<'src, 'info, 'diag> {
     pub fn new(type_info: &'info TypeInfo<'src>) -> Self {
-        Self { type_info, res: MirLowerResult::new() }
+        Self {
+            type_info,
+            instances: HashMap::new(),
+            instance_depths: Vec::new(),
+            depth: 0,
+            externs: HashMap::new(),
+            closures: Vec::new(),
+            shims: Vec::new(),
+            closure_count: 0,
+            res: MirLowerResult::new(),
+        }
     }
 
+    /// Lowers every `extern act` declaration and non-generic function, then
+    /// every instantiation of a generic function reachable from them.
     pub fn lower(mut self, hir: &HirMap<'src>) -> MirLowerResult<'src, 'diag> {
+        for (_, item) in hir.iter() {
+            if let HirItem::ExternFunction(function) = item {
+                self.lower_extern(function);
+            }
+        }
+
         for (_, item) in hir.iter() {
             let HirItem::Function(function) = item else {
                 continue;
             };
+            if function.generics.is_empty() {
+                self.lower_and_push(hir, function, None);
+            }
+        }
 
-            if let Some(function) = self.lower_function(function) {
-                let function_id = self.res.module.push_function(function);
-                if item.name().node == "main" {
-                    self.res.module.entry = Some(function_id);
-                }
+        // Lowering an instance may request further ones.
+        let mut next = 0;
+        while let Some(instance) = self.res.module.instances.get(next) {
+            if let HirItem::Function(function) = hir.get(instance.def) {
+                self.lower_and_push(hir, function, Some(MirInstanceId::from_usize(next)));
+            }
+            next += 1;
+        }
+
+        for (def, closure) in std::mem::take(&mut self.shims) {
+            if let HirItem::Function(function) = hir.get(def)
+                && let Some(mut shim) = self.lower_shim(function, closure)
+            {
+                shim.module_path = hir.module_path(hir.item_module(function.id)).into_boxed_slice();
+                self.res.module.push_function(shim);
             }
         }
 
+        // Inlining assumes well-formed MIR, like the dataflow checks.
+        if self.res.diags.is_empty() {
+            inline_adapters(&mut self.res.module);
+        }
         self.res
     }
 
-    fn lower_function(&mut self, function: &HirFunction<'src>) -> Option<MirFunction<'src>> {
+    fn lower_and_push(
+        &mut self,
+        hir: &HirMap<'src>,
+        function: &HirFunction<'src>,
+        instance: Option<MirInstanceId>,
+    ) {
+        let diags = self.res.diags.len();
+        self.depth = instance.map_or(0, |instance| self.instance_depths[instance.to_usize()]);
+        let lowered = self.lower_function(function, instance);
+        let closures = std::mem::take(&mut self.closures);
+        if let Some(mut lowered) = lowered {
+            lowered.module_path = hir.module_path(hir.item_module(function.id)).into_boxed_slice();
+            // Dataflow checks assume well-formed MIR, so skip them when
+            // lowering already failed.
+            let checked = self.res.diags.len() == diags;
+            if checked {
+                self.res.diags.extend(check_function(&lowered));
+            }
+            let module_path = lowered.module_path.clone();
+            let in_root = module_path.is_empty();
+            let function_id = self.res.module.push_function(lowered);
+            if function.name.node == "main" && in_root && instance.is_none() {
+                self.res.module.entry = Some(function_id);
+            }
+
+            for mut closure in closures {
+                closure.module_path = module_path.clone();
+                if checked {
+                    self.res.diags.extend(check_function(&closure));
+                }
+                self.res.module.push_function(closure);
+            }
+        }
+    }
+
+    fn next_closure_id(&mut self) -> MirClosureId {
+        let id = MirClosureId::from_usize(self.closure_count);
+        self.closure_count += 1;
+        id
+    }
+
+    /// Returns the closure a function item used as a value refers to,
+    /// requesting its shim the first time.
+    fn shim(&mut self, def: HirId) -> MirClosureId {
+        if let Some((_, closure)) = self.shims.iter().find(|(shim, _)| *shim == def) {
+            return *closure;
+        }
+        let closure = self.next_closure_id();
+        self.shims.push((def, closure));
+        closure
+    }
+
+    /// Lowers the closure body that forwards its arguments to `function`, so
+    /// the function can be called like any other closure.
+    fn lower_shim(
+        &mut self,
+        function: &HirFunction<'src>,
+        closure: MirClosureId,
+    ) -> Option<MirFunction<'src>> {
+        let sig = self.type_info.function_sig(function.id)?;
+        let Some(ret_ty) = lower_ty(&sig.ret, self.type_info) else {
+            self.push_unsupported_type(function.ret_ty.span, &sig.ret);
+            return None;
+        };
+        let mut shim = MirFunction::new(
+            function.id,
+            function.name.node,
+            ret_ty.clone(),
+            function.span,
+            function.ret_ty.span,
+        );
+        shim.closure = Some(closure);
+        shim.env = Some(MirStruct { fields: Box::new([]) });
+
+        let mut params = vec![shim.push_local(None, MirTy::Closure, function.span)];
+        for (param, ty) in function.params.iter().zip(sig.params.iter()) {
+            let Some(ty) = lower_ty(ty, self.type_info) else {
+                self.push_unsupported_type(param.ty.span, ty);
+                return None;
+            };
+            params.push(shim.push_local(Some(param.name.node), ty, param.span));
+        }
+        let args = params[1..].iter().map(|param| local_operand(&shim, *param)).collect();
+        shim.params = params.into_boxed_slice();
+
+        let result = shim.push_local(None, ret_ty, function.span);
+        let mut entry = MirBlock::new(MirTerminator::Return(Some(local_operand(&shim, result))));
+        entry.stmts.push(MirStmt::Assign {
+            dst: MirPlace::new(result),
+            rhs: MirRvalue::Call { callee: MirCallee::Function(function.id), args },
+            span: function.span,
+        });
+        shim.entry = shim.push_block(entry);
+        Some(shim)
+    }
+
+    fn lower_extern(&mut self, function: &HirExternFunction<'src>) {
+        let Some(sig) = self.type_info.function_sig(function.id) else {
+            self.push_diag(function.span, messages::MISSING_FUNCTION_SIGNATURE, &[]);
+            return;
+        };
+        let mut params = Vec::with_capacity(sig.params.len());
+        for (param, ty) in function.params.iter().zip(sig.params.iter()) {
+            let Some(lowered) = lower_ty(ty, self.type_info) else {
+                self.push_unsupported_type(param.ty.span, ty);
+                return;
+            };
+            params.push(lowered);
+        }
+        // Foreign functions cannot hand over a reference counted value.
+        let Some(ret_ty) = lower_ty(&sig.ret, self.type_info).filter(|ty| !ty.is_managed()) else {
+            self.push_unsupported_type(function.ret_ty.span, &sig.ret);
+            return;
+        };
+
+        let id = self.res.module.push_extern(MirExtern {
+            hir_id: function.id,
+            name: function.name.node,
+            params: params.into_boxed_slice(),
+            ret_ty,
+            span: function.span,
+        });
+        self.externs.insert(function.id, id);
+    }
+
+    /// Returns the instance of `def` for `args`, queueing it for lowering
+    /// the first time it is requested by the call at `span`.
+    fn instantiate(
+        &mut self,
+        def: HirId,
+        args: Box<[GenericArg]>,
+        span: Span,
+    ) -> Option<MirInstanceId> {
+        if let Some(id) = self.instances.get(&(def, args.clone())) {
+            return Some(*id);
+        }
+        // Generic calls whose arguments grow with every instance, as in
+        // `act f<T>(x: T) { f((x, x)) }`, would never stop requesting more.
+        if self.depth >= MAX_INSTANCE_DEPTH {
+            let limit = MAX_INSTANCE_DEPTH.to_string();
+            self.push_diag(span, messages::INSTANCE_DEPTH_LIMIT, &[("limit", &limit)]);
+            return None;
+        }
+        let size: usize = args
+            .iter()
+            .map(|arg| match arg {
+                GenericArg::Type(ty) => ty.size(),
+                GenericArg::Const(_) | GenericArg::ConstParam(_) => 1,
+            })
+            .sum();
+        if size > MAX_INSTANCE_SIZE {
+            let limit = MAX_INSTANCE_SIZE.to_string();
+            self.push_diag(span, messages::INSTANCE_SIZE_LIMIT, &[("limit", &limit)]);
+            return None;
+        }
+
+        let arg_tys = args
+            .iter()
+            .map(|arg| match arg {
+                GenericArg::Type(ty) => lower_ty(ty, self.type_info),
+                GenericArg::Const(_) | GenericArg::ConstParam(_) => None,
+            })
+            .collect();
+        let id = self.res.module.push_instance(MirInstance { def, args: args.clone(), arg_tys });
+        self.instances.insert((def, args), id);
+        self.instance_depths.push(self.depth + 1);
+        Some(id)
+    }
+
+    fn lower_function(
+        &mut self,
+        function: &HirFunction<'src>,
+        instance: Option<MirInstanceId>,
+    ) -> Option<MirFunction<'src>> {
         let Some(sig) = self.type_info.function_sig(function.id) else {
             self.push_diag(function.span, messages::MISSING_FUNCTION_SIGNATURE, &[]);
             return None;
         };
+        let generic_args = instance
+            .map(|instance| self.res.module.instance(instance).args.clone())
+            .unwrap_or_default();
+        let lower_sig_ty = |ty: &Ty| lower_ty(&ty.substitute(&generic_args), self.type_info);
 
-        let Some(ret_ty) = lower_ty(&sig.ret) else {
+        let Some(ok_ty) = lower_sig_ty(&sig.ret) else {
             self.push_unsupported_type(function.ret_ty.span, &sig.ret);
             return None;
         };
+        // Errors thrown out of `main` are reported by the runtime instead.
+        let ret_ty = match &sig.throws {
+            Some(err) if function.name.node != "main" => {
+                let Some(err_ty) = lower_sig_ty(err) else {
+                    let span = function.throws.as_ref().map_or(function.span, |ty| ty.span);
+                    self.push_unsupported_type(span, err);
+                    return None;
+                };
+                MirTy::Fallible { ok: Box::new(ok_ty), err: Box::new(err_ty) }
+            }
+            _ => ok_ty,
+        };
 
         let mut lowered = MirFunction::new(
             function.id,
@@ -85,6 +381,7 @@ impl<'src, 'info, 'diag> MirLowerer<'src, 'info, 'diag> {
             function.span,
             function.ret_ty.span,
         );
+        lowered.instance = instance;
         let mut locals = HashMap::new();
         let mut params = Vec::with_capacity(function.params.len());
 
@@ -94,7 +391,7 @@ impl<'src, 'info, 'diag> MirLowerer<'src, 'info, 'diag> {
                 self.push_missing_local_info(function.params[idx].span, hir_local);
                 continue;
             };
-            let Some(ty) = lower_ty(&local.ty) else {
+            let Some(ty) = lower_sig_ty(&local.ty) else {
                 self.push_unsupported_type(function.params[idx].ty.span, &local.ty);
                 continue;
             };
@@ -109,33 +406,61 @@ impl<'src, 'info, 'diag> MirLowerer<'src, 'info, 'diag> {
         }
 
         lowered.params = params.into_boxed_slice();
+        lowered.entry = lowered.push_block(MirBlock::new(MirTerminator::Return(None)));
 
-        let block = self.lower_block(function.id, &function.body, &mut lowered, &mut locals);
-        lowered.entry = lowered.push_block(block);
-        Some(lowered)
-    }
-
-    fn lower_block(
-        &mut self,
-        function: HirId,
-        block: &HirBlock<'src>,
-        lowered: &mut MirFunction<'src>,
-        locals: &mut HashMap<HirLocalId, MirLocalId>,
-    ) -> MirBlock {
-        let mut mir_block = MirBlock::new(MirTerminator::Return(None));
-        let mut ctx = FunctionLowerCtx { function, lowered, block: &mut mir_block, locals };
-
-        for stmt in block.stmts.iter() {
+        let mut ctx = FunctionLowerCtx {
+            type_info: self.type_info,
+            function: function.id,
+            generic_args: &generic_args,
+            block: lowered.entry,
+            lowered: &mut lowered,
+            locals: &mut locals,
+            pending_moves: Vec::new(),
+            loop_exits: Vec::new(),
+        };
+        let params = (0..function.params.len()).map(HirLocalId::from_usize);
+        self.share_params(params, function.span, &mut ctx);
+        for stmt in function.body.stmts.iter() {
             self.lower_stmt(stmt, &mut ctx);
         }
 
-        if let Some(tail) = &block.tail
-            && let Some(operand) = self.lower_expr(tail, &mut ctx)
-        {
-            ctx.block.terminator = MirTerminator::Return(Some(operand));
+        // A body that never finishes, like one ending in `panic` or `throw`,
+        // has no value to return.
+        if self.type_info.ty_of_block(function.id, &function.body) == Ty::Never {
+            if let Some(tail) = &function.body.tail {
+                let _ = self.lower_expr(tail, &mut ctx);
+            }
+            ctx.terminate(MirTerminator::Unreachable);
+            return Some(lowered);
         }
 
-        mir_block
+        let ret_ty = ctx.lowered.ret_ty.clone();
+        let ret = match &ret_ty {
+            MirTy::Fallible { ok, .. } => {
+                let value = match &function.body.tail {
+                    Some(tail) => self
+                        .lower_expr(tail, &mut ctx)
+                        .map(|operand| coerce_operand(operand, ok, tail.span, &mut ctx)),
+                    None => Some(MirOperand::Immediate(MirImmediate::Unit)),
+                };
+                value.map(|value| {
+                    let span = function.ret_ty.span;
+                    let result = ctx.lowered.push_local(None, ret_ty.clone(), span);
+                    ctx.push_stmt(MirStmt::Assign {
+                        dst: MirPlace::new(result),
+                        rhs: MirRvalue::WrapOk(value),
+                        span,
+                    });
+                    local_operand(ctx.lowered, result)
+                })
+            }
+            _ => function.body.tail.as_ref().and_then(|tail| {
+                let operand = self.lower_expr(tail, &mut ctx)?;
+                Some(coerce_operand(operand, &ret_ty, tail.span, &mut ctx))
+            }),
+        };
+        ctx.terminate(MirTerminator::Return(ret));
+        Some(lowered)
     }
 
     fn lower_stmt(&mut self, stmt: &HirStmt<'src>, ctx: &mut FunctionLowerCtx<'src, '_>) {
@@ -143,7 +468,7 @@ impl<'src, 'info, 'diag> MirLowerer<'src, 'info, 'diag> {
             HirStmt::Expr(expr) => {
                 let _ = self.lower_expr(expr, ctx);
             }
-            HirStmt::Let { local, name, init, span, .. } => {
+            HirStmt::Let { local, name, is_mutable, init, span, .. } => {
                 let Some(hir_local) = local else {
                     self.push_diag(*span, messages::MISSING_LOCAL_ID, &[]);
                     return;
@@ -153,28 +478,176 @@ impl<'src, 'info, 'diag> MirLowerer<'src, 'info, 'diag> {
                     self.push_missing_local_info(*span, *hir_local);
                     return;
                 };
-                let Some(ty) = lower_ty(&info.ty) else {
+                let Some(ty) = ctx.lower_ty(&info.ty) else {
                     self.push_unsupported_type(*span, &info.ty);
                     return;
                 };
 
-                let mir_local = ctx.lowered.push_local(Some(name.node), ty, *span);
+                // Locals captured by reference live in a cell that closures
+                // share, which is created along with the value.
+                let shared = self.type_info.is_captured_by_ref(ctx.function, *hir_local);
+                if shared && init.is_none() {
+                    self.push_unsupported_expr(*span, "deferred initialization of a shared local");
+                    return;
+                }
+                let local_ty = if shared { MirTy::Cell(Box::new(ty.clone())) } else { ty.clone() };
+                let mir_local = ctx.lowered.push_local(Some(name.node), local_ty, *span);
+                ctx.lowered.locals[mir_local.to_usize()].assign_once =
+                    !is_mutable && init.is_none();
                 ctx.locals.insert(*hir_local, mir_local);
 
                 if let Some(init) = init {
                     let Some(operand) = self.lower_expr(init, ctx) else {
                         return;
                     };
-                    ctx.block.stmts.push(MirStmt::Assign {
+                    let operand = coerce_operand(operand, &ty, init.span, ctx);
+                    ctx.push_stmt(MirStmt::Assign {
                         dst: MirPlace::new(mir_local),
-                        rhs: MirRvalue::Use(operand),
+                        rhs: if shared {
+                            MirRvalue::MakeCell(operand)
+                        } else {
+                            MirRvalue::Use(operand)
+                        },
                         span: *span,
                     });
                 }
             }
+            HirStmt::Assign { target, value, span } => {
+                let Some(place) = self.lower_place(target, ctx) else {
+                    return;
+                };
+                let Some(operand) = self.lower_expr(value, ctx) else {
+                    return;
+                };
+                self.write_place(&place, operand, (value.span, *span), ctx);
+            }
+            HirStmt::Throw { value, span } => {
+                let Some(error) = self.lower_expr(value, ctx) else {
+                    return;
+                };
+                self.lower_throw(error, *span, ctx);
+                // Statements after `throw` are unreachable; keep lowering them
+                // into a block nothing jumps to.
+                ctx.block = ctx.new_block();
+            }
+            HirStmt::Break { span } => {
+                let Some(exit) = ctx.loop_exits.last().copied() else {
+                    self.push_unsupported_expr(*span, "`break` outside of a loop");
+                    return;
+                };
+                ctx.terminate(MirTerminator::Goto(exit));
+                ctx.block = ctx.new_block();
+            }
         }
     }
 
+    /// Ends the current block with a panic carrying the message of `panic`.
+    fn lower_panic(
+        &mut self,
+        expr: &SpannedHirExpr<'src>,
+        args: &[SpannedHirExpr<'src>],
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> Option<MirOperand> {
+        let [message] = args else {
+            self.push_unsupported_expr(expr.span, "panic call");
+            return None;
+        };
+        let message = self.lower_expr(message, ctx)?;
+        ctx.terminate(MirTerminator::Panic { message, span: expr.span });
+        // Code after the call is unreachable, like statements after `throw`.
+        ctx.block = ctx.new_block();
+        Some(MirOperand::Immediate(MirImmediate::Unit))
+    }
+
+    /// Ends the current block by handing `error` to the caller, or to the
+    /// runtime when the function is `main`.
+    fn lower_throw(&mut self, error: MirOperand, span: Span, ctx: &mut FunctionLowerCtx<'src, '_>) {
+        let ret_ty = ctx.lowered.ret_ty.clone();
+        let MirTy::Fallible { err, .. } = &ret_ty else {
+            // Errors escaping `main` are printed by the runtime.
+            let throws =
+                self.type_info.function_sig(ctx.function).and_then(|sig| sig.throws.clone());
+            let error = match throws {
+                Some(ty) => self.lower_display(error.clone(), &ty, span, ctx).unwrap_or(error),
+                None => error,
+            };
+            let result = ctx.lowered.push_local(None, MirTy::Unit, span);
+            ctx.push_stmt(MirStmt::Assign {
+                dst: MirPlace::new(result),
+                rhs: MirRvalue::Call {
+                    callee: MirCallee::Runtime(RUNTIME_UNCAUGHT_ERROR),
+                    args: Box::new([error]),
+                },
+                span,
+            });
+            ctx.terminate(MirTerminator::Return(None));
+            return;
+        };
+
+        let error = coerce_operand(error, err, span, ctx);
+        let result = ctx.lowered.push_local(None, ret_ty.clone(), span);
+        ctx.push_stmt(MirStmt::Assign {
+            dst: MirPlace::new(result),
+            rhs: MirRvalue::WrapErr(error),
+            span,
+        });
+        let result = local_operand(ctx.lowered, result);
+        ctx.terminate(MirTerminator::Return(Some(result)));
+    }
+
+    /// Lowers `call?` to a branch on the call's error tag: the error is
+    /// thrown on, and the `ok` value becomes the value of the expression.
+    fn lower_try(
+        &mut self,
+        expr: &SpannedHirExpr<'src>,
+        inner: &SpannedHirExpr<'src>,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> Option<MirOperand> {
+        let result = self.lower_expr(inner, ctx)?;
+        let Some(MirTy::Fallible { ok, err }) = ctx.lowered.operand_ty(&result) else {
+            self.push_unsupported_expr(expr.span, "`?` operand");
+            return None;
+        };
+        let Some(place) = result.place() else {
+            self.push_unsupported_expr(expr.span, "`?` operand");
+            return None;
+        };
+
+        let is_err = ctx.lowered.push_local(None, MirTy::Bool, expr.span);
+        ctx.push_stmt(MirStmt::Assign {
+            dst: MirPlace::new(is_err),
+            rhs: MirRvalue::IsErr(MirOperand::Copy(place)),
+            span: expr.span,
+        });
+        let err_block = ctx.new_block();
+        let ok_block = ctx.new_block();
+        ctx.terminate(MirTerminator::Branch {
+            cond: MirOperand::Copy(MirPlace::new(is_err)),
+            then_block: err_block,
+            else_block: ok_block,
+            span: expr.span,
+        });
+
+        ctx.block = err_block;
+        let error = ctx.lowered.push_local(None, *err, expr.span);
+        ctx.push_stmt(MirStmt::Assign {
+            dst: MirPlace::new(error),
+            rhs: MirRvalue::UnwrapErr(MirOperand::Copy(place)),
+            span: expr.span,
+        });
+        let error = local_operand(ctx.lowered, error);
+        self.lower_throw(error, expr.span, ctx);
+
+        ctx.block = ok_block;
+        let value = ctx.lowered.push_local(None, *ok, expr.span);
+        ctx.push_stmt(MirStmt::Assign {
+            dst: MirPlace::new(value),
+            rhs: MirRvalue::UnwrapOk(MirOperand::Copy(place)),
+            span: expr.span,
+        });
+        Some(local_operand(ctx.lowered, value))
+    }
+
     fn lower_expr(
         &mut self,
         expr: &SpannedHirExpr<'src>,
@@ -182,174 +655,1688 @@ impl<'src, 'info, 'diag> MirLowerer<'src, 'info, 'diag> {
     ) -> Option<MirOperand> {
         match &expr.node {
             HirExpr::Literal(literal) => self.lower_literal(ctx.function, expr, literal),
-            HirExpr::Resolved(Res::Local(local)) => {
-                let Some(local) = ctx.locals.get(local).copied() else {
-                    let local = format!("{local:?}");
-                    self.push_diag(expr.span, messages::UNKNOWN_LOCAL, &[("local", &local)]);
-                    return None;
-                };
-                Some(MirOperand::Copy(MirPlace::new(local)))
-            }
-            HirExpr::Block(inner) => {
-                for stmt in inner.stmts.iter() {
-                    self.lower_stmt(stmt, ctx);
+            HirExpr::Resolved(Res::ConstParam(index)) => {
+                let ty = self.type_info.ty_of_expr(ctx.function, expr);
+                match (ctx.generic_args.get(*index), ctx.lower_ty(&ty)) {
+                    (Some(GenericArg::Const(value)), Some(MirTy::Int(ty))) => {
+                        Some(MirOperand::Immediate(MirImmediate::Int { value: *value, ty }))
+                    }
+                    _ => {
+                        self.push_unsupported_expr(expr.span, "const generic parameter");
+                        None
+                    }
                 }
-
-                inner
-                    .tail
-                    .as_ref()
-                    .and_then(|tail| self.lower_expr(tail, ctx))
-                    .or(Some(MirOperand::Immediate(MirImmediate::Unit)))
+            }
+            HirExpr::Resolved(Res::Local(local)) => self.read_local(expr, *local, ctx),
+            HirExpr::NonNullLocal(local) => self.lower_non_null(expr, *local, false, ctx),
+            HirExpr::Binary { op, lhs, rhs } => self.lower_binary(expr, *op, lhs, rhs, ctx),
+            HirExpr::Block(inner) | HirExpr::Unsafe(inner) => self.lower_block(inner, ctx),
+            HirExpr::If { cond, then, else_ } => {
+                self.lower_if(expr, cond, then, else_.as_deref(), ctx)
             }
             HirExpr::Path(_) => {
                 self.push_unsupported_expr(expr.span, "unresolved path");
                 None
             }
+            HirExpr::Turbofish { .. } => {
+                self.push_unsupported_expr(expr.span, "generic arguments outside a call");
+                None
+            }
+            HirExpr::Resolved(Res::Def(id)) if self.type_info.fn_ptr_ty(*id).is_some() => {
+                let closure = self.shim(*id);
+                let value = ctx.lowered.push_local(None, MirTy::Closure, expr.span);
+                ctx.push_stmt(MirStmt::Assign {
+                    dst: MirPlace::new(value),
+                    rhs: MirRvalue::MakeClosure {
+                        function: MirCallee::Closure(closure),
+                        captures: Box::new([]),
+                    },
+                    span: expr.span,
+                });
+                Some(local_operand(ctx.lowered, value))
+            }
+            HirExpr::Resolved(Res::Builtin(id))
+                if let Some(BuiltinLowering::Constant(bits)) =
+                    builtin_decl(*id).map(|decl| decl.lowering) =>
+            {
+                let ty = self.type_info.ty_of_expr(ctx.function, expr);
+                Some(MirOperand::Immediate(match ctx.lower_ty(&ty) {
+                    Some(MirTy::Int(ty)) => MirImmediate::Int { value: bits.into(), ty },
+                    _ => MirImmediate::Float {
+                        value: f64::from_bits(bits),
+                        ty: MirFloatTy { bits: TypeBits::B64 },
+                    },
+                }))
+            }
+            HirExpr::Resolved(res @ Res::Variant { .. }) => {
+                self.lower_record(expr, *res, Vec::new(), ctx)
+            }
             HirExpr::Resolved(_) => {
                 self.push_unsupported_expr(expr.span, "resolved item");
                 None
             }
+            HirExpr::Closure(closure) => self.lower_closure(expr, closure, ctx),
+            HirExpr::Range { start, end, inclusive } => {
+                let start = self.lower_expr(start, ctx)?;
+                let end = self.lower_expr(end, ctx)?;
+                let ty = self.type_info.ty_of_expr(ctx.function, expr);
+                let Some(ty) = ctx.lower_ty(&ty) else {
+                    self.push_unsupported_type(expr.span, &ty);
+                    return None;
+                };
+                let value = ctx.lowered.push_local(None, ty, expr.span);
+                ctx.push_stmt(MirStmt::Assign {
+                    dst: MirPlace::new(value),
+                    rhs: MirRvalue::MakeRange { start, end, inclusive: *inclusive },
+                    span: expr.span,
+                });
+                Some(local_operand(ctx.lowered, value))
+            }
+            HirExpr::For { local, iter, body, .. } => {
+                let Some(local) = local else {
+                    self.push_diag(expr.span, messages::MISSING_LOCAL_ID, &[]);
+                    return None;
+                };
+                self.lower_for(expr, *local, iter, body, ctx)?;
+                Some(MirOperand::Immediate(MirImmediate::Unit))
+            }
+            HirExpr::Struct { res: Some(res), fields, .. } => {
+                self.lower_struct(expr, *res, fields, ctx)
+            }
+            HirExpr::Struct { res: None, .. } => {
+                self.push_unsupported_expr(expr.span, "unresolved struct");
+                None
+            }
+            HirExpr::Field { base, name } => self.lower_field(expr, base, name.node, ctx),
+            HirExpr::TupleField { base, index } => self.load_field(expr, base, *index, ctx),
+            HirExpr::Tuple(items) => self.lower_tuple(expr, items, ctx),
+            HirExpr::Index { base, index } => self.lower_index(expr, (base, index), false, ctx),
             HirExpr::Call { callee, args } => self.lower_call(expr, callee, args, ctx),
+            HirExpr::Try(inner) => self.lower_try(expr, inner, ctx),
+            HirExpr::Deref(inner) => {
+                let pointer = self.lower_expr(inner, ctx)?;
+                let ty = self.type_info.ty_of_expr(ctx.function, expr);
+                // Records are shared by reference counting, which a raw
+                // pointer does not take part in.
+                let Some(ty) = ctx
+                    .lower_ty(&ty)
+                    .filter(|ty| !matches!(ty, MirTy::Record { .. } | MirTy::Union { .. }))
+                else {
+                    self.push_unsupported_type(expr.span, &ty);
+                    return None;
+                };
+                let value = ctx.lowered.push_local(None, ty, expr.span);
+                ctx.push_stmt(MirStmt::Assign {
+                    dst: MirPlace::new(value),
+                    rhs: MirRvalue::Load(pointer),
+                    span: expr.span,
+                });
+                Some(local_operand(ctx.lowered, value))
+            }
             HirExpr::Error => None,
         }
     }
 
-    fn lower_call(
+    /// Lowers the base of an indexing expression or the receiver of a method
+    /// call, which are borrowed rather than moved.
+    fn lower_borrowed(
         &mut self,
         expr: &SpannedHirExpr<'src>,
-        callee: &SpannedHirExpr<'src>,
-        args: &[SpannedHirExpr<'src>],
         ctx: &mut FunctionLowerCtx<'src, '_>,
     ) -> Option<MirOperand> {
-        let callee = self.lower_callee(ctx.function, callee)?;
-
-        let args =
-            args.iter().map(|arg| self.lower_expr(arg, ctx)).collect::<Option<Box<[_]>>>()?;
-
-        let ty = self.type_info.ty_of_expr(ctx.function, expr);
-        let Some(ret_ty) = lower_ty(&ty) else {
-            self.push_unsupported_type(expr.span, &ty);
-            return None;
+        let operand = match &expr.node {
+            HirExpr::NonNullLocal(local) => self.lower_non_null(expr, *local, true, ctx)?,
+            HirExpr::Index { base, index } => self.lower_index(expr, (base, index), true, ctx)?,
+            _ => self.lower_expr(expr, ctx)?,
         };
+        Some(match operand {
+            MirOperand::Move(place) => ctx.borrow(place),
+            operand => operand,
+        })
+    }
 
-        let dst = ctx.lowered.push_local(None, ret_ty, expr.span);
-        ctx.block.stmts.push(MirStmt::Assign {
-            dst: MirPlace::new(dst),
-            rhs: MirRvalue::Call { callee, args },
+    /// Reads a local checked against `null` as a value of its inner type.
+    /// A `borrowed` read leaves the local usable afterwards.
+    fn lower_non_null(
+        &mut self,
+        expr: &SpannedHirExpr<'src>,
+        local: HirLocalId,
+        borrowed: bool,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> Option<MirOperand> {
+        let operand = match self.read_local(expr, local, ctx)? {
+            MirOperand::Move(place) if borrowed => ctx.borrow(place),
+            operand => operand,
+        };
+        let Some(MirTy::Nullable(inner)) = ctx.lowered.operand_ty(&operand) else {
+            return Some(operand);
+        };
+        let value = ctx.lowered.push_local(None, *inner, expr.span);
+        ctx.push_stmt(MirStmt::Assign {
+            dst: MirPlace::new(value),
+            rhs: MirRvalue::UnwrapNullable(operand),
             span: expr.span,
         });
-
-        Some(MirOperand::Copy(MirPlace::new(dst)))
+        Some(local_operand(ctx.lowered, value))
     }
 
-    fn lower_callee(
+    /// Lowers `Name { field: value, ... }`. The values are evaluated in
+    /// source order and stored in the order the fields are declared in.
+    fn lower_struct(
         &mut self,
-        _function: HirId,
-        callee: &SpannedHirExpr<'src>,
-    ) -> Option<MirCallee> {
-        match &callee.node {
-            HirExpr::Resolved(Res::Def(id)) => Some(MirCallee::Function(*id)),
-            HirExpr::Resolved(Res::Builtin(id)) => {
-                let Some(decl) = builtin_decl(*id) else {
-                    let builtin = format!("{id:?}");
-                    self.push_diag(
-                        callee.span,
-                        messages::UNKNOWN_BUILTIN,
-                        &[("builtin", &builtin)],
-                    );
-                    return None;
-                };
-                match decl.lowering {
-                    BuiltinLowering::Runtime(runtime) => Some(MirCallee::Runtime(runtime)),
-                }
+        expr: &SpannedHirExpr<'src>,
+        res: Res,
+        fields: &[HirFieldValue<'src>],
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> Option<MirOperand> {
+        let declared: Vec<_> = match res {
+            Res::Def(def) => self.type_info.adt(def).map(|adt| &adt.fields[..]),
+            Res::Variant { def, index } => {
+                self.type_info.variants(def).get(index).map(|variant| &variant.fields[..])
             }
-            _ => {
-                self.push_unsupported_expr(callee.span, "call callee");
-                None
+            _ => None,
+        }
+        .unwrap_or_default()
+        .iter()
+        .map(|field| field.name)
+        .collect();
+        let mut values = vec![None; declared.len()];
+        for field in fields {
+            let value = self.lower_expr(&field.value, ctx)?;
+            if let Some(index) = declared.iter().position(|name| *name == field.name.node) {
+                values[index] = Some(value);
             }
         }
+        self.lower_record(expr, res, values, ctx)
     }
 
-    fn lower_literal(
+    /// Builds the value of struct or union `res`, or of the enum variant it
+    /// names, out of the `values` of its fields in declaration order. Fields
+    /// of the other variants are zeroed, and enums whose variants have no
+    /// fields are just the index of the variant.
+    fn lower_record(
         &mut self,
-        function: HirId,
         expr: &SpannedHirExpr<'src>,
-        literal: &HirLiteral<'src>,
+        res: Res,
+        values: Vec<Option<MirOperand>>,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
     ) -> Option<MirOperand> {
-        match literal {
-            HirLiteral::Bool(value) => Some(MirOperand::Immediate(MirImmediate::Bool(*value))),
-            HirLiteral::Char(value) => Some(MirOperand::Immediate(MirImmediate::Char(*value))),
-            HirLiteral::Str(value) => {
-                let id = self.res.module.push_constant(MirConstant::Str(value.clone()));
-                Some(MirOperand::Constant(id))
+        let ty = self.type_info.ty_of_expr(ctx.function, expr);
+        let (record_ty, fields) = match (res, ctx.lower_ty(&ty)) {
+            (Res::Variant { index, .. }, Some(MirTy::Int(ty))) => {
+                let value = index as u128;
+                return Some(MirOperand::Immediate(MirImmediate::Int { value, ty }));
             }
-            HirLiteral::Int { value, .. } => {
-                let ty = self.type_info.ty_of_expr(function, expr);
-                match lower_ty(&ty) {
-                    Some(MirTy::Int(ty)) => {
-                        Some(MirOperand::Immediate(MirImmediate::Int { value: *value, ty }))
-                    }
-                    _ => {
-                        self.push_unsupported_type(expr.span, &ty);
-                        None
-                    }
-                }
+            (_, Some(record_ty @ (MirTy::Record { .. } | MirTy::Union { .. }))) => {
+                let (MirTy::Record { fields, .. } | MirTy::Union { fields, .. }) = &record_ty
+                else {
+                    return None;
+                };
+                let fields = fields.clone();
+                (record_ty, fields)
             }
-            HirLiteral::Float { value, .. } => {
-                let ty = self.type_info.ty_of_expr(function, expr);
-                match lower_ty(&ty) {
-                    Some(MirTy::Float(ty)) => {
-                        Some(MirOperand::Immediate(MirImmediate::Float { value: *value, ty }))
-                    }
-                    _ => {
-                        self.push_unsupported_type(expr.span, &ty);
-                        None
-                    }
-                }
+            _ => {
+                self.push_unsupported_type(expr.span, &ty);
+                return None;
+            }
+        };
+        let (first, mut operands) = match res {
+            Res::Variant { def, index } => {
+                let tag = MirImmediate::Int { value: index as u128, ty: MirIntTy::VARIANT };
+                let before: usize = self.type_info.variants(def)[..index]
+                    .iter()
+                    .map(|variant| variant.fields.len())
+                    .sum();
+                (1 + before, vec![Some(MirOperand::Immediate(tag))])
             }
+            _ => (0, Vec::new()),
+        };
+        operands.resize(fields.len(), None);
+        for (index, value) in values.into_iter().enumerate() {
+            let slot = first + index;
+            operands[slot] =
+                value.map(|value| coerce_operand(value, &fields[slot], expr.span, ctx));
         }
-    }
 
-    fn push_diag(&mut self, span: Span, message: &'static str, replacements: &[(&str, &str)]) {
-        self.res.diags.push(
-            *Diagnostic::error(DiagMessage::new(message, replacements))
-                .add_label(DiagLabel::silent_primary(span)),
-        );
+        let value = ctx.lowered.push_local(None, record_ty, expr.span);
+        ctx.push_stmt(MirStmt::Assign {
+            dst: MirPlace::new(value),
+            rhs: MirRvalue::MakeRecord(operands.into_boxed_slice()),
+            span: expr.span,
+        });
+        Some(local_operand(ctx.lowered, value))
     }
 
-    fn push_missing_local_info(&mut self, span: Span, local: HirLocalId) {
-        let local = format!("{local:?}");
-        self.push_diag(span, messages::MISSING_LOCAL_INFO, &[("local", &local)]);
-    }
+    /// Lowers `(a, b, ...)` to a record of the elements in order.
+    fn lower_tuple(
+        &mut self,
+        expr: &SpannedHirExpr<'src>,
+        items: &[SpannedHirExpr<'src>],
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> Option<MirOperand> {
+        let ty = self.type_info.ty_of_expr(ctx.function, expr);
+        let Some(record_ty @ MirTy::Record { .. }) = ctx.lower_ty(&ty) else {
+            self.push_unsupported_type(expr.span, &ty);
+            return None;
+        };
+        let MirTy::Record { fields, .. } = &record_ty else {
+            return None;
+        };
+        let mut operands = Vec::with_capacity(items.len());
+        for (item, field) in items.iter().zip(fields.iter()) {
+            let value = self.lower_expr(item, ctx)?;
+            operands.push(Some(coerce_operand(value, field, item.span, ctx)));
+        }
+        let value = ctx.lowered.push_local(None, record_ty, expr.span);
+        ctx.push_stmt(MirStmt::Assign {
+            dst: MirPlace::new(value),
+            rhs: MirRvalue::MakeRecord(operands.into_boxed_slice()),
+            span: expr.span,
+        });
+        Some(local_operand(ctx.lowered, value))
+    }
+
+    /// Lowers a read of field `name` of a struct, which borrows the struct.
+    fn lower_field(
+        &mut self,
+        expr: &SpannedHirExpr<'src>,
+        base: &SpannedHirExpr<'src>,
+        name: &str,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> Option<MirOperand> {
+        let base_ty = self.type_info.ty_of_expr(ctx.function, base);
+        let index = match base_ty {
+            Ty::Struct { def, .. } | Ty::Union { def, .. } => self
+                .type_info
+                .adt(def)
+                .and_then(|adt| adt.fields.iter().position(|field| field.name == name)),
+            _ => None,
+        };
+        let Some(index) = index else {
+            self.push_unsupported_expr(expr.span, "field access");
+            return None;
+        };
+        self.load_field(expr, base, index, ctx)
+    }
+
+    /// Lowers a read of the field or tuple element at `index` of the record
+    /// `base` evaluates to, which borrows the record.
+    fn load_field(
+        &mut self,
+        expr: &SpannedHirExpr<'src>,
+        base: &SpannedHirExpr<'src>,
+        index: usize,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> Option<MirOperand> {
+        let record = self.lower_borrowed(base, ctx)?;
+        let ty = self.type_info.ty_of_expr(ctx.function, expr);
+        let Some(ty) = ctx.lower_ty(&ty) else {
+            self.push_unsupported_type(expr.span, &ty);
+            return None;
+        };
+        let value = ctx.lowered.push_local(None, ty, expr.span);
+        ctx.push_stmt(MirStmt::Assign {
+            dst: MirPlace::new(value),
+            rhs: MirRvalue::LoadField { record, index },
+            span: expr.span,
+        });
+        Some(local_operand(ctx.lowered, value))
+    }
+
+    /// Lowers `base[index]` to an element read, or to a slice when `index`
+    /// is a range.
+    /// Lowers `base[index]`. An element that is not copy can only be
+    /// `borrowed`, since moving it out would leave the collection sharing it.
+    fn lower_index(
+        &mut self,
+        expr: &SpannedHirExpr<'src>,
+        (base, index): (&SpannedHirExpr<'src>, &SpannedHirExpr<'src>),
+        borrowed: bool,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> Option<MirOperand> {
+        let base = self.lower_borrowed(base, ctx)?;
+        let index = self.lower_expr(index, ctx)?;
+        let hir_ty = self.type_info.ty_of_expr(ctx.function, expr);
+        let Some(ty) = ctx.lower_ty(&hir_ty) else {
+            self.push_unsupported_type(expr.span, &hir_ty);
+            return None;
+        };
+        let rhs = match ctx.lowered.operand_ty(&index) {
+            Some(MirTy::Range(_)) => MirRvalue::Slice { base, range: index },
+            _ if !borrowed && !ty.is_copy() => {
+                let ty = hir_ty.substitute(ctx.generic_args);
+                let ty = self.type_info.display_ty(&ty, None);
+                self.push_diag(expr.span, messages::MOVE_OUT_OF_INDEX, &[("ty", &ty)]);
+                return None;
+            }
+            _ => MirRvalue::Index { base, index },
+        };
+        let value = ctx.lowered.push_local(None, ty, expr.span);
+        ctx.push_stmt(MirStmt::Assign { dst: MirPlace::new(value), rhs, span: expr.span });
+        Some(local_operand(ctx.lowered, value))
+    }
+
+    /// Reads a local by value, loading it out of its cell when closures
+    /// capture it by reference.
+    fn read_local(
+        &mut self,
+        expr: &SpannedHirExpr<'src>,
+        local: HirLocalId,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> Option<MirOperand> {
+        let Some(local) = ctx.locals.get(&local).copied() else {
+            let local = format!("{local:?}");
+            self.push_diag(expr.span, messages::UNKNOWN_LOCAL, &[("local", &local)]);
+            return None;
+        };
+        let MirTy::Cell(inner) = ctx.lowered.locals[local.to_usize()].ty.clone() else {
+            let operand = local_operand(ctx.lowered, local);
+            if let MirOperand::Move(_) = operand {
+                ctx.pending_moves.push((local, expr.span));
+            }
+            return Some(operand);
+        };
+        let value = ctx.lowered.push_local(None, *inner, expr.span);
+        ctx.push_stmt(MirStmt::Assign {
+            dst: MirPlace::new(value),
+            rhs: MirRvalue::LoadCell(MirOperand::Copy(MirPlace::new(local))),
+            span: expr.span,
+        });
+        Some(local_operand(ctx.lowered, value))
+    }
+
+    /// Moves the parameters that closures capture by reference into cells.
+    fn share_params(
+        &mut self,
+        params: impl Iterator<Item = HirLocalId>,
+        span: Span,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) {
+        for hir_local in params {
+            if !self.type_info.is_captured_by_ref(ctx.function, hir_local) {
+                continue;
+            }
+            let Some(param) = ctx.locals.get(&hir_local).copied() else {
+                continue;
+            };
+            let local = &ctx.lowered.locals[param.to_usize()];
+            let (name, ty) = (local.name, MirTy::Cell(Box::new(local.ty.clone())));
+            let cell = ctx.lowered.push_local(name, ty, span);
+            let value = local_operand(ctx.lowered, param);
+            ctx.push_stmt(MirStmt::Assign {
+                dst: MirPlace::new(cell),
+                rhs: MirRvalue::MakeCell(value),
+                span,
+            });
+            ctx.locals.insert(hir_local, cell);
+        }
+    }
+
+    /// Lowers the body of a closure to a function of its own and builds its
+    /// environment out of the captured locals.
+    ///
+    /// The body reads every capture out of the environment on entry. Locals
+    /// captured by reference are cells, so the body shares them with the
+    /// enclosing function.
+    fn lower_closure(
+        &mut self,
+        expr: &SpannedHirExpr<'src>,
+        closure: &HirClosure<'src>,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> Option<MirOperand> {
+        let ty = self.type_info.ty_of_expr(ctx.function, expr);
+        let Ty::FnPtr { ret, .. } = &ty else {
+            self.push_unsupported_type(expr.span, &ty);
+            return None;
+        };
+        let Some(ret_ty) = ctx.lower_ty(ret) else {
+            self.push_unsupported_type(closure.body.span, ret);
+            return None;
+        };
+
+        let id = self.next_closure_id();
+        let mut lowered =
+            MirFunction::new(ctx.function, ctx.lowered.name, ret_ty, expr.span, closure.body.span);
+        lowered.closure = Some(id);
+        let mut locals = HashMap::new();
+        let mut params = vec![lowered.push_local(None, MirTy::Closure, expr.span)];
+        for param in closure.params.iter() {
+            let Some(hir_local) = param.local else {
+                self.push_diag(param.name.span, messages::MISSING_LOCAL_ID, &[]);
+                return None;
+            };
+            let Some(info) = self.type_info.local(ctx.function, hir_local) else {
+                self.push_missing_local_info(param.name.span, hir_local);
+                return None;
+            };
+            let Some(ty) = ctx.lower_ty(&info.ty) else {
+                self.push_unsupported_type(param.name.span, &info.ty);
+                return None;
+            };
+            let local = lowered.push_local(Some(param.name.node), ty, param.name.span);
+            locals.insert(hir_local, local);
+            params.push(local);
+        }
+        lowered.params = params.into_boxed_slice();
+
+        let mut entry = MirBlock::new(MirTerminator::Return(None));
+        let mut captures = Vec::with_capacity(closure.captures.len());
+        let mut fields = Vec::with_capacity(closure.captures.len());
+        for (index, capture) in closure.captures.iter().enumerate() {
+            let Some(outer) = ctx.locals.get(&capture.local).copied() else {
+                let local = format!("{:?}", capture.local);
+                self.push_diag(expr.span, messages::UNKNOWN_LOCAL, &[("local", &local)]);
+                return None;
+            };
+            let outer_local = &ctx.lowered.locals[outer.to_usize()];
+            let local = lowered.push_local(outer_local.name, outer_local.ty.clone(), expr.span);
+            entry.stmts.push(MirStmt::Assign {
+                dst: MirPlace::new(local),
+                rhs: MirRvalue::Capture(index),
+                span: expr.span,
+            });
+            locals.insert(capture.local, local);
+            fields.push(outer_local.ty.clone());
+            let capture = local_operand(ctx.lowered, outer);
+            if let MirOperand::Move(_) = capture {
+                ctx.pending_moves.push((outer, expr.span));
+            }
+            captures.push(capture);
+        }
+        lowered.env = Some(MirStruct { fields: fields.into_boxed_slice() });
+        lowered.entry = lowered.push_block(entry);
+
+        let mut body_ctx = FunctionLowerCtx {
+            type_info: self.type_info,
+            function: ctx.function,
+            generic_args: ctx.generic_args,
+            block: lowered.entry,
+            lowered: &mut lowered,
+            locals: &mut locals,
+            pending_moves: Vec::new(),
+            loop_exits: Vec::new(),
+        };
+        let params = closure.params.iter().filter_map(|param| param.local);
+        self.share_params(params, expr.span, &mut body_ctx);
+        let ret_ty = body_ctx.lowered.ret_ty.clone();
+        let ret = self
+            .lower_expr(&closure.body, &mut body_ctx)
+            .map(|operand| coerce_operand(operand, &ret_ty, closure.body.span, &mut body_ctx));
+        body_ctx.terminate(MirTerminator::Return(ret));
+        self.closures.push(lowered);
+
+        let value = ctx.lowered.push_local(None, MirTy::Closure, expr.span);
+        ctx.push_stmt(MirStmt::Assign {
+            dst: MirPlace::new(value),
+            rhs: MirRvalue::MakeClosure {
+                function: MirCallee::Closure(id),
+                captures: captures.into_boxed_slice(),
+            },
+            span: expr.span,
+        });
+        Some(local_operand(ctx.lowered, value))
+    }
+
+    fn lower_block(
+        &mut self,
+        block: &HirBlock<'src>,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> Option<MirOperand> {
+        for stmt in block.stmts.iter() {
+            self.lower_stmt(stmt, ctx);
+        }
+
+        block
+            .tail
+            .as_ref()
+            .and_then(|tail| self.lower_expr(tail, ctx))
+            .or(Some(MirOperand::Immediate(MirImmediate::Unit)))
+    }
+
+    /// Lowers `if` to a branch whose arms store their value into a shared
+    /// temporary and rejoin in a new block.
+    fn lower_if(
+        &mut self,
+        expr: &SpannedHirExpr<'src>,
+        cond: &SpannedHirExpr<'src>,
+        then: &HirBlock<'src>,
+        else_: Option<&SpannedHirExpr<'src>>,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> Option<MirOperand> {
+        let cond_operand = self.lower_expr(cond, ctx)?;
+
+        let ty = self.type_info.ty_of_expr(ctx.function, expr);
+        let Some(ty) = ctx.lower_ty(&ty) else {
+            self.push_unsupported_type(expr.span, &ty);
+            return None;
+        };
+        let result =
+            (ty != MirTy::Unit).then(|| ctx.lowered.push_local(None, ty.clone(), expr.span));
+
+        let then_block = ctx.new_block();
+        let else_block = else_.map(|_| ctx.new_block());
+        let join = ctx.new_block();
+        ctx.terminate(MirTerminator::Branch {
+            cond: cond_operand,
+            then_block,
+            else_block: else_block.unwrap_or(join),
+            span: cond.span,
+        });
+
+        ctx.block = then_block;
+        let value = self.lower_block(then, ctx);
+        self.assign_branch_value(result, value, then.span, ctx);
+        ctx.terminate(MirTerminator::Goto(join));
+
+        if let (Some(else_), Some(else_block)) = (else_, else_block) {
+            ctx.block = else_block;
+            let value = self.lower_expr(else_, ctx);
+            self.assign_branch_value(result, value, else_.span, ctx);
+            ctx.terminate(MirTerminator::Goto(join));
+        }
+
+        ctx.block = join;
+        Some(match result {
+            Some(local) => local_operand(ctx.lowered, local),
+            None => MirOperand::Immediate(MirImmediate::Unit),
+        })
+    }
+
+    fn assign_branch_value(
+        &mut self,
+        result: Option<MirLocalId>,
+        value: Option<MirOperand>,
+        span: Span,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) {
+        if let (Some(result), Some(value)) = (result, value) {
+            let ty = ctx.lowered.locals[result.to_usize()].ty.clone();
+            let value = coerce_operand(value, &ty, span, ctx);
+            ctx.push_stmt(MirStmt::Assign {
+                dst: MirPlace::new(result),
+                rhs: MirRvalue::Use(value),
+                span,
+            });
+        }
+    }
+
+    /// Lowers `for`. Loops over an integer range written in place count from
+    /// its start to its end, and loops over a vector or slice count through
+    /// its indices; any other iterator is advanced with `next()` until it
+    /// returns `null`.
+    fn lower_for(
+        &mut self,
+        expr: &SpannedHirExpr<'src>,
+        local: HirLocalId,
+        iter: &SpannedHirExpr<'src>,
+        body: &HirBlock<'src>,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> Option<()> {
+        let Some(info) = self.type_info.local(ctx.function, local) else {
+            self.push_missing_local_info(expr.span, local);
+            return None;
+        };
+        let (name, span) = (info.name, info.span);
+        let Some(item_ty) = ctx.lower_ty(&info.ty) else {
+            self.push_unsupported_type(span, &info.ty);
+            return None;
+        };
+
+        let iterated_ty =
+            self.type_info.ty_of_expr(ctx.function, iter).substitute(ctx.generic_args);
+        let head = ctx.new_block();
+        let body_block = ctx.new_block();
+        let exit = ctx.new_block();
+        let latch = match (&iter.node, &item_ty) {
+            (HirExpr::Range { start, end, inclusive }, MirTy::Int(int_ty)) => {
+                let start = self.lower_expr(start, ctx)?;
+                let end = self.lower_expr(end, ctx)?;
+                let counter = ctx.lowered.push_local(None, item_ty.clone(), iter.span);
+                let last = ctx.lowered.push_local(None, item_ty.clone(), iter.span);
+                for (dst, value) in [(counter, start), (last, end)] {
+                    ctx.push_stmt(MirStmt::Assign {
+                        dst: MirPlace::new(dst),
+                        rhs: MirRvalue::Use(value),
+                        span: iter.span,
+                    });
+                }
+                ctx.terminate(MirTerminator::Goto(head));
+
+                ctx.block = head;
+                let op = if *inclusive { MirBinOp::Le } else { MirBinOp::Lt };
+                let in_range = self.push_binary(op, counter, last, MirTy::Bool, iter.span, ctx);
+                ctx.terminate(MirTerminator::Branch {
+                    cond: in_range,
+                    then_block: body_block,
+                    else_block: exit,
+                    span: iter.span,
+                });
+
+                ctx.block = body_block;
+                let item = MirOperand::Copy(MirPlace::new(counter));
+                self.bind_loop_item(local, name, span, item, &item_ty, ctx);
+                Some((counter, last, *inclusive, *int_ty))
+            }
+            _ if iterated_ty.elem().is_some() => {
+                let sequence = self.lower_expr(iter, ctx)?;
+                let Some(sequence_ty) = ctx.lowered.operand_ty(&sequence) else {
+                    self.push_unsupported_expr(iter.span, "`for` sequence");
+                    return None;
+                };
+                let state = ctx.lowered.push_local(None, sequence_ty, iter.span);
+                let index_ty = MirTy::Int(MirIntTy::USIZE);
+                let counter = ctx.lowered.push_local(None, index_ty.clone(), iter.span);
+                let len = ctx.lowered.push_local(None, index_ty, iter.span);
+                let zero = MirImmediate::Int { value: 0, ty: MirIntTy::USIZE };
+                let state_operand = MirOperand::Copy(MirPlace::new(state));
+                for (dst, rhs) in [
+                    (state, MirRvalue::Use(sequence)),
+                    (counter, MirRvalue::Use(MirOperand::Immediate(zero))),
+                    (
+                        len,
+                        MirRvalue::Intrinsic {
+                            intrinsic: Intrinsic::Len,
+                            args: Box::new([state_operand.clone()]),
+                        },
+                    ),
+                ] {
+                    ctx.push_stmt(MirStmt::Assign {
+                        dst: MirPlace::new(dst),
+                        rhs,
+                        span: iter.span,
+                    });
+                }
+                ctx.terminate(MirTerminator::Goto(head));
+
+                ctx.block = head;
+                let in_range =
+                    self.push_binary(MirBinOp::Lt, counter, len, MirTy::Bool, iter.span, ctx);
+                ctx.terminate(MirTerminator::Branch {
+                    cond: in_range,
+                    then_block: body_block,
+                    else_block: exit,
+                    span: iter.span,
+                });
+
+                ctx.block = body_block;
+                let item = ctx.lowered.push_local(None, item_ty.clone(), iter.span);
+                ctx.push_stmt(MirStmt::Assign {
+                    dst: MirPlace::new(item),
+                    rhs: MirRvalue::Index {
+                        base: state_operand,
+                        index: MirOperand::Copy(MirPlace::new(counter)),
+                    },
+                    span: iter.span,
+                });
+                let item = local_operand(ctx.lowered, item);
+                self.bind_loop_item(local, name, span, item, &item_ty, ctx);
+                Some((counter, len, false, MirIntTy::USIZE))
+            }
+            _ => {
+                let iterator = self.lower_expr(iter, ctx)?;
+                let Some(iter_ty) = ctx.lowered.operand_ty(&iterator) else {
+                    self.push_unsupported_expr(iter.span, "`for` iterator");
+                    return None;
+                };
+                let state = ctx.lowered.push_local(None, iter_ty, iter.span);
+                ctx.push_stmt(MirStmt::Assign {
+                    dst: MirPlace::new(state),
+                    rhs: MirRvalue::Use(iterator),
+                    span: iter.span,
+                });
+                ctx.terminate(MirTerminator::Goto(head));
+
+                ctx.block = head;
+                let next_ty = MirTy::Nullable(Box::new(item_ty.clone()));
+                let state_operand = MirOperand::Copy(MirPlace::new(state));
+                // Runtime iterators are advanced by their `next` builtin,
+                // which hands the item back through a slot.
+                let next_builtin = match iterated_ty {
+                    Ty::BuiltinType { id, .. } => builtin_type_path(id)
+                        .and_then(|path| builtin_from_path(&format!("{path}::next"))),
+                    _ => None,
+                };
+                let next = match next_builtin {
+                    Some(builtin) => {
+                        let callee = self.lower_builtin_callee(builtin, iter.span)?;
+                        let slot = ctx.lowered.push_local(None, MirTy::Pointer, iter.span);
+                        ctx.push_stmt(MirStmt::Assign {
+                            dst: MirPlace::new(slot),
+                            rhs: MirRvalue::Call { callee, args: Box::new([state_operand]) },
+                            span: iter.span,
+                        });
+                        let item = self.read_slot(slot, next_ty, iter.span, ctx);
+                        item.place()?.local
+                    }
+                    None => {
+                        let next = ctx.lowered.push_local(None, next_ty, iter.span);
+                        ctx.push_stmt(MirStmt::Next {
+                            iter: state_operand,
+                            dst: MirPlace::new(next),
+                            span: iter.span,
+                        });
+                        next
+                    }
+                };
+                let is_null = ctx.lowered.push_local(None, MirTy::Bool, iter.span);
+                ctx.push_stmt(MirStmt::Assign {
+                    dst: MirPlace::new(is_null),
+                    rhs: MirRvalue::Binary {
+                        op: MirBinOp::Eq,
+                        lhs: MirOperand::Copy(MirPlace::new(next)),
+                        rhs: MirOperand::Immediate(MirImmediate::Null),
+                    },
+                    span: iter.span,
+                });
+                ctx.terminate(MirTerminator::Branch {
+                    cond: MirOperand::Copy(MirPlace::new(is_null)),
+                    then_block: exit,
+                    else_block: body_block,
+                    span: iter.span,
+                });
+
+                ctx.block = body_block;
+                let item = ctx.lowered.push_local(None, item_ty.clone(), iter.span);
+                ctx.push_stmt(MirStmt::Assign {
+                    dst: MirPlace::new(item),
+                    rhs: MirRvalue::UnwrapNullable(local_operand(ctx.lowered, next)),
+                    span: iter.span,
+                });
+                let item = local_operand(ctx.lowered, item);
+                self.bind_loop_item(local, name, span, item, &item_ty, ctx);
+                None
+            }
+        };
+
+        ctx.loop_exits.push(exit);
+        let _ = self.lower_block(body, ctx);
+        ctx.loop_exits.pop();
+        match latch {
+            // An inclusive range stops at its end before stepping, so it
+            // never steps past the largest value of its type.
+            Some((counter, last, inclusive, int_ty)) => {
+                if inclusive {
+                    let step = ctx.new_block();
+                    let at_end =
+                        self.push_binary(MirBinOp::Eq, counter, last, MirTy::Bool, iter.span, ctx);
+                    ctx.terminate(MirTerminator::Branch {
+                        cond: at_end,
+                        then_block: exit,
+                        else_block: step,
+                        span: iter.span,
+                    });
+                    ctx.block = step;
+                }
+                ctx.push_stmt(MirStmt::Assign {
+                    dst: MirPlace::new(counter),
+                    rhs: MirRvalue::Binary {
+                        op: MirBinOp::Add,
+                        lhs: MirOperand::Copy(MirPlace::new(counter)),
+                        rhs: MirOperand::Immediate(MirImmediate::Int { value: 1, ty: int_ty }),
+                    },
+                    span: iter.span,
+                });
+                ctx.terminate(MirTerminator::Goto(head));
+            }
+            None => ctx.terminate(MirTerminator::Goto(head)),
+        }
+        ctx.block = exit;
+        Some(())
+    }
+
+    /// Assigns the current item of a `for` loop to the loop variable, in a
+    /// fresh cell on every iteration when closures capture it by reference.
+    fn bind_loop_item(
+        &mut self,
+        local: HirLocalId,
+        name: &'src str,
+        span: Span,
+        item: MirOperand,
+        ty: &MirTy,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) {
+        let shared = self.type_info.is_captured_by_ref(ctx.function, local);
+        let local_ty = if shared { MirTy::Cell(Box::new(ty.clone())) } else { ty.clone() };
+        let mir_local = ctx.lowered.push_local(Some(name), local_ty, span);
+        ctx.locals.insert(local, mir_local);
+        ctx.push_stmt(MirStmt::Assign {
+            dst: MirPlace::new(mir_local),
+            rhs: if shared { MirRvalue::MakeCell(item) } else { MirRvalue::Use(item) },
+            span,
+        });
+    }
+
+    /// Assigns `lhs op rhs` to a new temporary of type `ty` and returns it.
+    fn push_binary(
+        &mut self,
+        op: MirBinOp,
+        lhs: MirLocalId,
+        rhs: MirLocalId,
+        ty: MirTy,
+        span: Span,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> MirOperand {
+        let dst = ctx.lowered.push_local(None, ty, span);
+        ctx.push_stmt(MirStmt::Assign {
+            dst: MirPlace::new(dst),
+            rhs: MirRvalue::Binary {
+                op,
+                lhs: MirOperand::Copy(MirPlace::new(lhs)),
+                rhs: MirOperand::Copy(MirPlace::new(rhs)),
+            },
+            span,
+        });
+        MirOperand::Copy(MirPlace::new(dst))
+    }
+
+    /// Lowers comparisons, and operators overloaded by the type of their left
+    /// operand. Either side of `==` and `!=` may be `null`; otherwise both
+    /// sides share a primitive type, or are compared by the result of the
+    /// `cmp` method of their builtin type or, for strings, of the runtime.
+    /// `>` and `>=` swap the operands of `<` and `<=`.
+    fn lower_binary(
+        &mut self,
+        expr: &SpannedHirExpr<'src>,
+        op: BinaryOp,
+        lhs: &SpannedHirExpr<'src>,
+        rhs: &SpannedHirExpr<'src>,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> Option<MirOperand> {
+        let lhs_ty = self.type_info.ty_of_expr(ctx.function, lhs).substitute(ctx.generic_args);
+        let method = self.type_info.operator_method(&lhs_ty, op);
+        let (op, swapped) = match op {
+            BinaryOp::Eq => (MirBinOp::Eq, false),
+            BinaryOp::Ne => (MirBinOp::Ne, false),
+            BinaryOp::Lt => (MirBinOp::Lt, false),
+            BinaryOp::Le => (MirBinOp::Le, false),
+            BinaryOp::Gt => (MirBinOp::Lt, true),
+            BinaryOp::Ge => (MirBinOp::Le, true),
+            _ => {
+                let Some(method) = method else {
+                    self.push_unsupported_expr(expr.span, "binary operator");
+                    return None;
+                };
+                let ty = self.type_info.ty_of_expr(ctx.function, expr);
+                return self.lower_operator_call(expr, method, (lhs, rhs), &ty, ctx);
+            }
+        };
+        let (lhs, rhs) = match method {
+            Some(method) => {
+                let ordering_ty = Ty::Int { signed: true, bits: ORDERING_TY.bits };
+                let ordering =
+                    self.lower_operator_call(expr, method, (lhs, rhs), &ordering_ty, ctx)?;
+                (ordering, ordering_zero())
+            }
+            None if lhs_ty == Ty::Str => {
+                let args =
+                    Box::new([self.lower_borrowed(lhs, ctx)?, self.lower_borrowed(rhs, ctx)?]);
+                let ordering = ctx.lowered.push_local(None, MirTy::Int(ORDERING_TY), expr.span);
+                let rhs = MirRvalue::Intrinsic { intrinsic: Intrinsic::StrCmp, args };
+                ctx.push_stmt(MirStmt::Assign {
+                    dst: MirPlace::new(ordering),
+                    rhs,
+                    span: expr.span,
+                });
+                (local_operand(ctx.lowered, ordering), ordering_zero())
+            }
+            // Comparisons only read their operands, so checking a vector
+            // against `null` leaves it usable.
+            None => (self.lower_borrowed(lhs, ctx)?, self.lower_borrowed(rhs, ctx)?),
+        };
+        let (lhs, rhs) = if swapped { (rhs, lhs) } else { (lhs, rhs) };
+
+        let lhs_ty = ctx.lowered.operand_ty(&lhs);
+        let rhs_ty = ctx.lowered.operand_ty(&rhs);
+        let rvalue = match (&lhs_ty, &rhs_ty) {
+            // Only nullable values can be `null`.
+            (None, Some(ty)) | (Some(ty), None) if !matches!(ty, MirTy::Nullable(_)) => {
+                MirRvalue::Use(MirOperand::Immediate(MirImmediate::Bool(op == MirBinOp::Ne)))
+            }
+            (None, _) | (_, None) => MirRvalue::Binary { op, lhs, rhs },
+            (Some(lhs_ty), Some(rhs_ty))
+                if lhs_ty == rhs_ty
+                    && matches!(
+                        lhs_ty,
+                        MirTy::Bool | MirTy::Int(_) | MirTy::Float(_) | MirTy::Char
+                    ) =>
+            {
+                MirRvalue::Binary { op, lhs, rhs }
+            }
+            (Some(lhs_ty @ MirTy::Record { .. }), Some(rhs_ty))
+                if lhs_ty == rhs_ty && matches!(op, MirBinOp::Eq | MirBinOp::Ne) =>
+            {
+                MirRvalue::Binary { op, lhs, rhs }
+            }
+            _ => {
+                self.push_unsupported_expr(expr.span, "comparison operands");
+                return None;
+            }
+        };
+
+        let dst = ctx.lowered.push_local(None, MirTy::Bool, expr.span);
+        ctx.push_stmt(MirStmt::Assign { dst: MirPlace::new(dst), rhs: rvalue, span: expr.span });
+        Some(MirOperand::Copy(MirPlace::new(dst)))
+    }
+
+    /// Lowers an overloaded operator to a call of the builtin `method`
+    /// implementing it, which borrows both operands and returns a `ty`.
+    fn lower_operator_call(
+        &mut self,
+        expr: &SpannedHirExpr<'src>,
+        method: BuiltinId,
+        (lhs, rhs): (&SpannedHirExpr<'src>, &SpannedHirExpr<'src>),
+        ty: &Ty,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> Option<MirOperand> {
+        let callee = self.lower_builtin_callee(method, expr.span)?;
+        let args = Box::new([self.lower_borrowed(lhs, ctx)?, self.lower_borrowed(rhs, ctx)?]);
+        let Some(ret_ty) = ctx.lower_ty(ty) else {
+            self.push_unsupported_type(expr.span, ty);
+            return None;
+        };
+        let ret = builtin_decl(method).map(|decl| decl.ret);
+        Some(self.emit_call((callee, args), ret, ret_ty, expr.span, ctx))
+    }
+
+    /// Converts `value` of the `Display` type `ty` to a string. Strings are
+    /// passed through and integers formatted in decimal; builtin types are
+    /// converted by their `to_string` method, which borrows `value`.
+    fn lower_display(
+        &mut self,
+        value: MirOperand,
+        ty: &Ty,
+        span: Span,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> Option<MirOperand> {
+        let ty = ty.substitute(ctx.generic_args);
+        if let Ty::Int { .. } = ty {
+            let text = ctx.lowered.push_local(None, MirTy::Str, span);
+            let rhs =
+                MirRvalue::Intrinsic { intrinsic: Intrinsic::IntToString, args: Box::new([value]) };
+            ctx.push_stmt(MirStmt::Assign { dst: MirPlace::new(text), rhs, span });
+            return Some(local_operand(ctx.lowered, text));
+        }
+        let Some(method) = self.type_info.builtin_method(&ty, "to_string") else {
+            return Some(value);
+        };
+        let callee = self.lower_builtin_callee(method, span)?;
+        let value = match value {
+            MirOperand::Move(place) => ctx.borrow(place),
+            operand => operand,
+        };
+        let ret = builtin_decl(method).map(|decl| decl.ret);
+        Some(self.emit_call((callee, Box::new([value])), ret, MirTy::Str, span, ctx))
+    }
+
+    fn lower_call(
+        &mut self,
+        expr: &SpannedHirExpr<'src>,
+        callee: &SpannedHirExpr<'src>,
+        args: &[SpannedHirExpr<'src>],
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> Option<MirOperand> {
+        let callee = strip_turbofish(callee);
+        if let HirExpr::Resolved(Res::Builtin(id)) = &callee.node
+            && builtin_decl(*id).is_some_and(|decl| decl.lowering == BuiltinLowering::Drop)
+        {
+            return self.lower_drop(expr, args, ctx);
+        }
+        if let HirExpr::Resolved(res @ Res::Variant { .. }) = callee.node {
+            let mut values = Vec::with_capacity(args.len());
+            for arg in args {
+                values.push(Some(self.lower_expr(arg, ctx)?));
+            }
+            return self.lower_record(expr, res, values, ctx);
+        }
+
+        let mut method = None;
+        match self.type_info.ty_of_expr(ctx.function, callee) {
+            Ty::FnPtr { params, .. } => {
+                return self.lower_indirect_call(expr, callee, &params, args, ctx);
+            }
+            Ty::Builtin(id) => {
+                match builtin_decl(id).map(|decl| decl.lowering) {
+                    Some(BuiltinLowering::Intrinsic(intrinsic)) => {
+                        return self.lower_intrinsic(expr, (intrinsic, callee), args, ctx);
+                    }
+                    Some(BuiltinLowering::Place(op)) => {
+                        return self.lower_place_op(expr, op, args, ctx);
+                    }
+                    Some(lowering @ (BuiltinLowering::SizeOf | BuiltinLowering::AlignOf)) => {
+                        return self.lower_layout_of(expr, (lowering, callee), ctx);
+                    }
+                    Some(BuiltinLowering::Panic) => return self.lower_panic(expr, args, ctx),
+                    Some(BuiltinLowering::Default) => return self.lower_default(expr, ctx),
+                    Some(BuiltinLowering::ToJson) => {
+                        return self.lower_to_json(expr, callee, args, ctx);
+                    }
+                    Some(BuiltinLowering::FromJson) => {
+                        return self.lower_from_json(expr, callee, args, ctx);
+                    }
+                    _ => {}
+                }
+                if let HirExpr::Field { base, .. } = &callee.node {
+                    method = Some((id, base));
+                }
+            }
+            _ => {}
+        }
+
+        let callee_args = self.type_info.callee_args(ctx.function, callee);
+        // Callee types are expressed in its own generic parameters, and its
+        // generic arguments in those of the function being lowered.
+        let lower_callee_ty =
+            |ty: &Ty, ctx: &FunctionLowerCtx<'src, '_>| ctx.lower_ty(&ty.substitute(callee_args));
+        let def = match &callee.node {
+            HirExpr::Resolved(Res::Def(id)) => Some(*id),
+            _ => self.type_info.method(ctx.function, callee),
+        };
+        let receiver = match &callee.node {
+            HirExpr::Field { base, .. } if def.is_some() => Some(base),
+            _ => None,
+        };
+        let sig = def.and_then(|id| self.type_info.function_sig(id));
+        let builtin = match &callee.node {
+            HirExpr::Resolved(Res::Builtin(id)) => Some(*id),
+            _ => method.map(|(id, _)| id),
+        };
+        let builtin_ret = builtin.and_then(builtin_decl).map(|decl| decl.ret);
+        let (callee, mut lowered_args) = match method {
+            // The receiver of a runtime method is borrowed for the call.
+            Some((id, base)) => {
+                let callee = self.lower_builtin_callee(id, callee.span)?;
+                let mut lowered_args = Vec::with_capacity(args.len() + 1);
+                lowered_args.push(self.lower_borrowed(base, ctx)?);
+                (callee, lowered_args)
+            }
+            None => (self.lower_callee(callee, ctx)?, Vec::with_capacity(args.len() + 1)),
+        };
+
+        let mut param_tys = sig
+            .map(|sig| sig.params.iter().map(|ty| lower_callee_ty(ty, ctx)).collect::<Vec<_>>())
+            .unwrap_or_default();
+        // Methods of `std::itertools` borrow their receiver like runtime
+        // methods do.
+        if let Some(base) = receiver {
+            let operand = self.lower_borrowed(base, ctx)?;
+            lowered_args.push(match param_tys.first() {
+                Some(Some(ty)) => coerce_operand(operand, ty, base.span, ctx),
+                _ => operand,
+            });
+            param_tys.remove(0);
+        }
+        let err_ty = sig.and_then(|sig| sig.throws.as_ref());
+        let builtin_params = builtin
+            .and_then(builtin_decl)
+            .map(|decl| &decl.params[usize::from(method.is_some())..]);
+        for (idx, arg) in args.iter().enumerate() {
+            // Runtime builtins borrow their arguments.
+            let operand = if builtin.is_some() {
+                self.lower_borrowed(arg, ctx)?
+            } else {
+                self.lower_expr(arg, ctx)?
+            };
+            let operand = match builtin_params.and_then(|params| params.get(idx)) {
+                Some(TypeConstraint::Implements(DISPLAY_CONTRACT)) => {
+                    let ty = self.type_info.ty_of_expr(ctx.function, arg);
+                    self.lower_display(operand, &ty, arg.span, ctx)?
+                }
+                _ => operand,
+            };
+            lowered_args.push(match param_tys.get(idx) {
+                Some(Some(ty)) => coerce_operand(operand, ty, arg.span, ctx),
+                _ => operand,
+            });
+        }
+        let args = lowered_args.into_boxed_slice();
+
+        let ty = self.type_info.ty_of_expr(ctx.function, expr);
+        let Some(mut ret_ty) = ctx.lower_ty(&ty) else {
+            self.push_unsupported_type(expr.span, &ty);
+            return None;
+        };
+        if let Some(err) = err_ty {
+            let Some(err_ty) = lower_callee_ty(err, ctx) else {
+                self.push_unsupported_type(expr.span, err);
+                return None;
+            };
+            ret_ty = MirTy::Fallible { ok: Box::new(ret_ty), err: Box::new(err_ty) };
+        }
+        Some(self.emit_call((callee, args), builtin_ret, ret_ty, expr.span, ctx))
+    }
+
+    /// Emits a call returning a `ret_ty`. Builtins returning `ret` through
+    /// a slot hand back a pointer to the result, which is read here.
+    fn emit_call(
+        &mut self,
+        (callee, args): (MirCallee, Box<[MirOperand]>),
+        builtin_ret: Option<BuiltinReturn>,
+        ret_ty: MirTy,
+        span: Span,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> MirOperand {
+        if let Some(
+            ret
+            @ (BuiltinReturn::Slot(_) | BuiltinReturn::Indirect(_) | BuiltinReturn::Fallible(_)),
+        ) = builtin_ret
+        {
+            let slot = ctx.lowered.push_local(None, MirTy::Pointer, span);
+            ctx.push_stmt(MirStmt::Assign {
+                dst: MirPlace::new(slot),
+                rhs: MirRvalue::Call { callee, args },
+                span,
+            });
+            let ty = match ret {
+                BuiltinReturn::Slot(_) => return self.read_slot(slot, ret_ty, span, ctx),
+                // The runtime lays the result out like a fallible value.
+                BuiltinReturn::Fallible(_) => {
+                    MirTy::Fallible { ok: Box::new(ret_ty), err: Box::new(MirTy::Str) }
+                }
+                _ => ret_ty,
+            };
+            let dst = ctx.lowered.push_local(None, ty, span);
+            ctx.push_stmt(MirStmt::Assign {
+                dst: MirPlace::new(dst),
+                rhs: MirRvalue::Load(MirOperand::Copy(MirPlace::new(slot))),
+                span,
+            });
+            return MirOperand::Copy(MirPlace::new(dst));
+        }
+
+        let dst = ctx.lowered.push_local(None, ret_ty, span);
+        ctx.push_stmt(MirStmt::Assign {
+            dst: MirPlace::new(dst),
+            rhs: MirRvalue::Call { callee, args },
+            span,
+        });
+        MirOperand::Copy(MirPlace::new(dst))
+    }
+
+    /// Reads the `T?` of type `ty` that a runtime builtin handed back as a
+    /// pointer to a `T`, which is null for `null`.
+    fn read_slot(
+        &mut self,
+        slot: MirLocalId,
+        ty: MirTy,
+        span: Span,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> MirOperand {
+        let MirTy::Nullable(inner) = &ty else {
+            unreachable!("slot builtins return nullable values")
+        };
+        let inner = (**inner).clone();
+        let value = ctx.lowered.push_local(None, ty.clone(), span);
+        let none_block = ctx.new_block();
+        let some_block = ctx.new_block();
+        let join = ctx.new_block();
+
+        let is_null = ctx.lowered.push_local(None, MirTy::Bool, span);
+        ctx.push_stmt(MirStmt::Assign {
+            dst: MirPlace::new(is_null),
+            rhs: MirRvalue::Binary {
+                op: MirBinOp::Eq,
+                lhs: MirOperand::Copy(MirPlace::new(slot)),
+                rhs: MirOperand::Immediate(MirImmediate::Null),
+            },
+            span,
+        });
+        ctx.terminate(MirTerminator::Branch {
+            cond: MirOperand::Copy(MirPlace::new(is_null)),
+            then_block: none_block,
+            else_block: some_block,
+            span,
+        });
+
+        ctx.block = none_block;
+        ctx.push_stmt(MirStmt::Assign {
+            dst: MirPlace::new(value),
+            rhs: MirRvalue::Use(MirOperand::Immediate(MirImmediate::Null)),
+            span,
+        });
+        ctx.terminate(MirTerminator::Goto(join));
+
+        ctx.block = some_block;
+        let item = ctx.lowered.push_local(None, inner, span);
+        ctx.push_stmt(MirStmt::Assign {
+            dst: MirPlace::new(item),
+            rhs: MirRvalue::Load(MirOperand::Copy(MirPlace::new(slot))),
+            span,
+        });
+        ctx.push_stmt(MirStmt::Assign {
+            dst: MirPlace::new(value),
+            rhs: MirRvalue::WrapNullable(local_operand(ctx.lowered, item)),
+            span,
+        });
+        ctx.terminate(MirTerminator::Goto(join));
+
+        ctx.block = join;
+        local_operand(ctx.lowered, value)
+    }
+
+    /// Lowers a call of a closure value, which passes the closure along so
+    /// its body can read the environment.
+    fn lower_indirect_call(
+        &mut self,
+        expr: &SpannedHirExpr<'src>,
+        callee: &SpannedHirExpr<'src>,
+        params: &[Ty],
+        args: &[SpannedHirExpr<'src>],
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> Option<MirOperand> {
+        let callee = self.lower_expr(callee, ctx)?;
+        let mut lowered_args = Vec::with_capacity(args.len());
+        for (arg, param) in args.iter().zip(params) {
+            let operand = self.lower_expr(arg, ctx)?;
+            lowered_args.push(match ctx.lower_ty(param) {
+                Some(ty) => coerce_operand(operand, &ty, arg.span, ctx),
+                None => operand,
+            });
+        }
+
+        let ty = self.type_info.ty_of_expr(ctx.function, expr);
+        let Some(ret_ty) = ctx.lower_ty(&ty) else {
+            self.push_unsupported_type(expr.span, &ty);
+            return None;
+        };
+        let dst = ctx.lowered.push_local(None, ret_ty, expr.span);
+        ctx.push_stmt(MirStmt::Assign {
+            dst: MirPlace::new(dst),
+            rhs: MirRvalue::CallIndirect { callee, args: lowered_args.into_boxed_slice() },
+            span: expr.span,
+        });
+        Some(local_operand(ctx.lowered, dst))
+    }
+
+    /// Lowers a call of a builtin that codegen expands inline. The receiver
+    /// of a method call becomes the first operand.
+    fn lower_intrinsic(
+        &mut self,
+        expr: &SpannedHirExpr<'src>,
+        (intrinsic, callee): (Intrinsic, &SpannedHirExpr<'src>),
+        args: &[SpannedHirExpr<'src>],
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> Option<MirOperand> {
+        let mut lowered_args = Vec::with_capacity(args.len() + 1);
+        if let HirExpr::Field { base, .. } = &callee.node {
+            lowered_args.push(self.lower_borrowed(base, ctx)?);
+        }
+        // Pushed elements, keys and values are stored as the types of the
+        // receiver.
+        let stored = match lowered_args.first().and_then(|arg| ctx.lowered.operand_ty(arg)) {
+            Some(MirTy::Vec(elem)) => vec![*elem],
+            Some(MirTy::Map { key, value }) => vec![*key, *value],
+            _ => Vec::new(),
+        };
+        for (idx, arg) in args.iter().enumerate() {
+            // A shuffled vector stays with its owner.
+            let operand = if intrinsic == Intrinsic::Shuffle {
+                self.lower_borrowed(arg, ctx)?
+            } else {
+                self.lower_expr(arg, ctx)?
+            };
+            lowered_args.push(match stored.get(idx) {
+                Some(ty) => coerce_operand(operand, ty, arg.span, ctx),
+                None => operand,
+            });
+        }
+
+        let ty = self.type_info.ty_of_expr(ctx.function, expr);
+        let Some(ty) = ctx.lower_ty(&ty) else {
+            self.push_unsupported_type(expr.span, &ty);
+            return None;
+        };
+        let dst = ctx.lowered.push_local(None, ty, expr.span);
+        ctx.push_stmt(MirStmt::Assign {
+            dst: MirPlace::new(dst),
+            rhs: MirRvalue::Intrinsic { intrinsic, args: lowered_args.into_boxed_slice() },
+            span: expr.span,
+        });
+        Some(local_operand(ctx.lowered, dst))
+    }
+
+    /// Lowers `drop(value)`. The argument is always moved, even when its type
+    /// is copy, so any later use of a dropped local is reported.
+    fn lower_drop(
+        &mut self,
+        expr: &SpannedHirExpr<'src>,
+        args: &[SpannedHirExpr<'src>],
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> Option<MirOperand> {
+        let [arg] = args else {
+            self.push_unsupported_expr(expr.span, "drop call");
+            return None;
+        };
+
+        let operand = match self.lower_expr(arg, ctx)? {
+            MirOperand::Copy(place) => {
+                ctx.pending_moves.push((place.local, arg.span));
+                MirOperand::Move(place)
+            }
+            operand => operand,
+        };
+        ctx.push_stmt(MirStmt::Drop { operand, span: expr.span });
+
+        Some(MirOperand::Immediate(MirImmediate::Unit))
+    }
+
+    fn lower_callee(
+        &mut self,
+        callee: &SpannedHirExpr<'src>,
+        ctx: &FunctionLowerCtx<'src, '_>,
+    ) -> Option<MirCallee> {
+        let id = match &callee.node {
+            HirExpr::Resolved(Res::Def(id)) => *id,
+            HirExpr::Resolved(Res::Builtin(id)) => {
+                return self.lower_builtin_callee(*id, callee.span);
+            }
+            _ => match self.type_info.method(ctx.function, callee) {
+                Some(id) => id,
+                None => {
+                    self.push_unsupported_expr(callee.span, "call callee");
+                    return None;
+                }
+            },
+        };
+        if self.type_info.is_extern(id) {
+            let Some(id) = self.externs.get(&id) else {
+                self.push_unsupported_expr(callee.span, "extern callee");
+                return None;
+            };
+            return Some(MirCallee::Extern(*id));
+        }
+        if self.type_info.generics(id).is_empty() {
+            return Some(MirCallee::Function(id));
+        }
+        let args = self
+            .type_info
+            .callee_args(ctx.function, callee)
+            .iter()
+            .map(|arg| arg.substitute(ctx.generic_args))
+            .collect();
+        self.instantiate(id, args, callee.span).map(MirCallee::Instance)
+    }
+
+    fn lower_builtin_callee(&mut self, id: BuiltinId, span: Span) -> Option<MirCallee> {
+        let Some(decl) = builtin_decl(id) else {
+            let builtin = format!("{id:?}");
+            self.push_diag(span, messages::UNKNOWN_BUILTIN, &[("builtin", &builtin)]);
+            return None;
+        };
+        match decl.lowering {
+            BuiltinLowering::Runtime(runtime) => Some(MirCallee::Runtime(runtime)),
+            BuiltinLowering::Drop => {
+                self.push_unsupported_expr(span, "drop callee");
+                None
+            }
+            BuiltinLowering::Intrinsic(_) => {
+                self.push_unsupported_expr(span, "intrinsic callee");
+                None
+            }
+            BuiltinLowering::Constant(_) => {
+                self.push_unsupported_expr(span, "constant callee");
+                None
+            }
+            BuiltinLowering::Place(_) => {
+                self.push_unsupported_expr(span, "place builtin callee");
+                None
+            }
+            BuiltinLowering::SizeOf | BuiltinLowering::AlignOf => {
+                self.push_unsupported_expr(span, "layout builtin callee");
+                None
+            }
+            BuiltinLowering::Panic => {
+                self.push_unsupported_expr(span, "panic callee");
+                None
+            }
+            BuiltinLowering::ToJson | BuiltinLowering::FromJson => {
+                self.push_unsupported_expr(span, "JSON conversion callee");
+                None
+            }
+            BuiltinLowering::Default => {
+                self.push_unsupported_expr(span, "default callee");
+                None
+            }
+        }
+    }
+
+    fn lower_literal(
+        &mut self,
+        function: HirId,
+        expr: &SpannedHirExpr<'src>,
+        literal: &HirLiteral<'src>,
+    ) -> Option<MirOperand> {
+        match literal {
+            HirLiteral::Bool(value) => Some(MirOperand::Immediate(MirImmediate::Bool(*value))),
+            HirLiteral::Char(value) => Some(MirOperand::Immediate(MirImmediate::Char(*value))),
+            HirLiteral::Str(value) => {
+                let id = self.res.module.push_constant(MirConstant::Str(value.clone()));
+                Some(MirOperand::Constant(id))
+            }
+            HirLiteral::Null => Some(MirOperand::Immediate(MirImmediate::Null)),
+            HirLiteral::Int { value, .. } => {
+                let ty = self.type_info.ty_of_expr(function, expr);
+                match lower_ty(&ty, self.type_info) {
+                    Some(MirTy::Int(ty)) => {
+                        Some(MirOperand::Immediate(MirImmediate::Int { value: *value, ty }))
+                    }
+                    _ => {
+                        self.push_unsupported_type(expr.span, &ty);
+                        None
+                    }
+                }
+            }
+            HirLiteral::Float { value, .. } => {
+                let ty = self.type_info.ty_of_expr(function, expr);
+                match lower_ty(&ty, self.type_info) {
+                    Some(MirTy::Float(ty)) => {
+                        Some(MirOperand::Immediate(MirImmediate::Float { value: *value, ty }))
+                    }
+                    _ => {
+                        self.push_unsupported_type(expr.span, &ty);
+                        None
+                    }
+                }
+            }
+        }
+    }
+
+    fn push_diag(&mut self, span: Span, message: &'static str, replacements: &[(&str, &str)]) {
+        self.res.diags.push(
+            *Diagnostic::error(DiagMessage::new(message, replacements))
+                .add_label(DiagLabel::silent_primary(span)),
+        );
+    }
+
+    fn push_missing_local_info(&mut self, span: Span, local: HirLocalId) {
+        let local = format!("{local:?}");
+        self.push_diag(span, messages::MISSING_LOCAL_INFO, &[("local", &local)]);
+    }
 
     fn push_unsupported_expr(&mut self, span: Span, expression: &str) {
         self.push_diag(span, messages::UNSUPPORTED_EXPRESSION, &[("expression", expression)]);
     }
 
     fn push_unsupported_type(&mut self, span: Span, ty: &Ty) {
-        let ty = format!("{ty:?}");
+        let ty = self.type_info.display_ty(ty, None);
         self.push_diag(span, messages::UNSUPPORTED_TYPE, &[("ty", &ty)]);
     }
 }
 
-pub fn lower_ty(ty: &Ty) -> Option<MirTy> {
+/// Number of instances a call may be nested in before instantiating it is
+/// taken to never end.
+const MAX_INSTANCE_DEPTH: usize = 64;
+
+/// Number of types the generic arguments of an instance may be built from,
+/// which bounds arguments that double with every call long before the depth
+/// limit is reached.
+const MAX_INSTANCE_SIZE: usize = 1024;
+
+/// Type of the `-1`, `0` or `1` returned by `cmp` methods and string
+/// comparisons.
+const ORDERING_TY: MirIntTy = MirIntTy { signed: true, bits: TypeBits::B64 };
+
+/// The zero an ordering is compared with.
+fn ordering_zero() -> MirOperand {
+    MirOperand::Immediate(MirImmediate::Int { value: 0, ty: ORDERING_TY })
+}
+
+/// Reads a local by value, moving it out unless its type is copy.
+fn local_operand(function: &MirFunction<'_>, local: MirLocalId) -> MirOperand {
+    let place = MirPlace::new(local);
+    if function.locals[local.to_usize()].ty.is_copy() {
+        MirOperand::Copy(place)
+    } else {
+        MirOperand::Move(place)
+    }
+}
+
+/// Converts `operand` to the nullable type `ty` through a temporary when it
+/// is `null` or a value of the inner type; other operands are returned as is.
+fn coerce_operand(
+    operand: MirOperand,
+    ty: &MirTy,
+    span: Span,
+    ctx: &mut FunctionLowerCtx<'_, '_>,
+) -> MirOperand {
+    let MirTy::Nullable(inner) = ty else {
+        return operand;
+    };
+    let rhs = match ctx.lowered.operand_ty(&operand) {
+        None => MirRvalue::Use(operand),
+        Some(actual) if actual == **inner => MirRvalue::WrapNullable(operand),
+        Some(_) => return operand,
+    };
+
+    let value = ctx.lowered.push_local(None, ty.clone(), span);
+    ctx.push_stmt(MirStmt::Assign { dst: MirPlace::new(value), rhs, span });
+    local_operand(ctx.lowered, value)
+}
+
+pub fn lower_ty(ty: &Ty, info: &TypeInfo<'_>) -> Option<MirTy> {
+    lower_ty_inner(ty, info, &mut Vec::new())
+}
+
+/// `outer` holds the structs and enums being lowered. MIR types cannot
+/// refer back to them, so recursive types have no representation.
+fn lower_ty_inner(ty: &Ty, info: &TypeInfo<'_>, outer: &mut Vec<HirId>) -> Option<MirTy> {
+    let mut lower = |ty: &Ty| lower_ty_inner(ty, info, outer);
     match ty {
-        Ty::Unit => Some(MirTy::Unit),
+        // No value of `Never` is ever made, so it takes no space.
+        Ty::Unit | Ty::Never => Some(MirTy::Unit),
         Ty::Bool => Some(MirTy::Bool),
         Ty::Int { signed, bits } => Some(MirTy::Int(MirIntTy { signed: *signed, bits: *bits })),
         Ty::Float { bits } => Some(MirTy::Float(MirFloatTy { bits: *bits })),
         Ty::Char => Some(MirTy::Char),
         Ty::Str => Some(MirTy::Str),
-        Ty::Tuple(_)
+        Ty::Nullable(inner) => lower(inner).map(|inner| MirTy::Nullable(Box::new(inner))),
+        Ty::Pointer { .. } => Some(MirTy::Pointer),
+        Ty::FnPtr { .. } => Some(MirTy::Closure),
+        Ty::BuiltinType { id: JSON | DECIMAL | READER | WRITER | LINES, .. } => {
+            Some(MirTy::Managed)
+        }
+        // Instants and durations count nanoseconds; rounding modes are codes.
+        Ty::BuiltinType { id: INSTANT | DURATION | ROUNDING_MODE, .. } => {
+            Some(MirTy::Int(MirIntTy { signed: false, bits: TypeBits::B64 }))
+        }
+        Ty::BuiltinType { id: RANGE, args } => match args.first().and_then(lower) {
+            Some(MirTy::Int(elem)) => Some(MirTy::Range(elem)),
+            _ => None,
+        },
+        // Vectors hold a reference to each managed element, which reading
+        // the element shares.
+        Ty::BuiltinType { id: VEC, args } => {
+            args.first().and_then(lower).map(|elem| MirTy::Vec(Box::new(elem)))
+        }
+        Ty::Slice(elem) => lower(elem).map(|elem| MirTy::Slice(Box::new(elem))),
+        // Like vectors, maps and sets hold a reference to managed values; the
+        // only managed keys are strings, which are compared by their
+        // contents, and records, which are compared field by field.
+        Ty::BuiltinType { id: HASH_MAP, args } => {
+            let [key, value] = args.as_ref() else {
+                return None;
+            };
+            match (lower(key), lower(value)) {
+                (Some(key), Some(value)) if is_map_key(&key) => {
+                    Some(MirTy::Map { key: Box::new(key), value: Box::new(value) })
+                }
+                _ => None,
+            }
+        }
+        Ty::BuiltinType { id: HASH_SET, args } => match args.first().and_then(lower) {
+            Some(key) if is_map_key(&key) => {
+                Some(MirTy::Map { key: Box::new(key), value: Box::new(MirTy::Unit) })
+            }
+            _ => None,
+        },
+        Ty::Struct { def, args } => {
+            let adt = info.adt(*def).filter(|adt| adt.kind == AdtKind::Struct)?;
+            let fields: Vec<_> = adt.fields.iter().map(|field| field.ty.substitute(args)).collect();
+            lower_record(*def, &fields, info, outer)
+        }
+        // Nothing records which field of a union was written, so the record
+        // could not release a managed one.
+        Ty::Union { def, args } => {
+            let adt = info.adt(*def).filter(|adt| adt.kind == AdtKind::Union)?;
+            let fields: Option<Box<[MirTy]>> = adt
+                .fields
+                .iter()
+                .map(|field| lower(&field.ty.substitute(args)).filter(|ty| !ty.is_managed()))
+                .collect();
+            Some(MirTy::Union { def: *def, fields: fields? })
+        }
+        Ty::Enum(def) => {
+            let variants = info.variants(*def);
+            if variants.iter().all(|variant| variant.fields.is_empty()) {
+                return Some(MirTy::Int(MirIntTy::VARIANT));
+            }
+            let fields: Vec<_> = std::iter::once(Ty::Int { signed: false, bits: TypeBits::B32 })
+                .chain(
+                    variants
+                        .iter()
+                        .flat_map(|variant| variant.fields.iter().map(|field| field.ty.clone())),
+                )
+                .collect();
+            lower_record(*def, &fields, info, outer)
+        }
+        Ty::Tuple(items) => {
+            let fields: Option<Box<[MirTy]>> =
+                items.iter().map(|item| lower_ty_inner(item, info, outer)).collect();
+            Some(MirTy::Record { def: None, fields: fields? })
+        }
+        Ty::Null
         | Ty::Array { .. }
-        | Ty::Struct(_)
-        | Ty::Enum(_)
         | Ty::Function(_)
         | Ty::Builtin(_)
+        | Ty::BuiltinType { .. }
+        | Ty::Param(_)
         | Ty::Unknown => None,
     }
 }
 
+/// Lowers the record of struct or enum `def` holding `fields`.
+fn lower_record(
+    def: HirId,
+    fields: &[Ty],
+    info: &TypeInfo<'_>,
+    outer: &mut Vec<HirId>,
+) -> Option<MirTy> {
+    if outer.contains(&def) {
+        return None;
+    }
+    outer.push(def);
+    let fields: Option<Box<[MirTy]>> =
+        fields.iter().map(|field| lower_ty_inner(field, info, outer)).collect();
+    outer.pop();
+    Some(MirTy::Record { def: Some(def), fields: fields? })
+}
+
+fn is_map_key(key: &MirTy) -> bool {
+    matches!(key, MirTy::Str | MirTy::Record { .. }) || !key.is_managed()
+}
+
+mod json;
+mod loops;
+mod mem;
 mod messages;
 
 #[cfg(test)]
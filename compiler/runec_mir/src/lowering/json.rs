@@ -0,0 +1,764 @@
+use std::borrow::Cow;
+
+use runec_abi::{
+    JSON_KIND_ARRAY, JSON_KIND_BOOL, JSON_KIND_NUMBER, JSON_KIND_OBJECT, JSON_KIND_STRING,
+    RUNTIME_JSON_AS_BOOL, RUNTIME_JSON_AS_NUMBER, RUNTIME_JSON_AS_STR, RUNTIME_JSON_AT,
+    RUNTIME_JSON_CHECK_INT, RUNTIME_JSON_CHECK_KIND, RUNTIME_JSON_FROM_BOOL,
+    RUNTIME_JSON_FROM_NUMBER, RUNTIME_JSON_FROM_STR, RUNTIME_JSON_GET, RUNTIME_JSON_INSERT,
+    RUNTIME_JSON_IS_NULL, RUNTIME_JSON_LEN, RUNTIME_JSON_NEW_ARRAY, RUNTIME_JSON_NEW_NULL,
+    RUNTIME_JSON_NEW_OBJECT, RUNTIME_JSON_PUSH, RuntimeFunctionId,
+};
+use runec_builtins::{Intrinsic, JSON, TypeBits, VEC};
+use runec_hir::expression::SpannedHirExpr;
+use runec_semantic::typeck::{GenericArg, Ty, VariantInfo, VariantKind};
+use runec_source::span::Span;
+
+use super::loops::{copy, push_temp, usize_immediate, usize_ty};
+use super::{
+    FunctionLowerCtx, MirLowerer, ORDERING_TY, coerce_operand, local_operand, ordering_zero,
+};
+use crate::block::{MirBinOp, MirRvalue, MirStmt, MirTerminator};
+use crate::constant::MirConstant;
+use crate::function::MirCallee;
+use crate::ids::{MirBlockId, MirLocalId};
+use crate::operand::{MirImmediate, MirOperand, MirPlace};
+use crate::ty::{MirFloatTy, MirIntTy, MirTy};
+
+/// Where a `from_json` expansion goes once it fails: the fallible result
+/// it stores the message in, and the block after the expansion.
+struct Failure {
+    result: MirLocalId,
+    done: MirBlockId,
+}
+
+impl<'src> MirLowerer<'src, '_, '_> {
+    /// Lowers `to_json(value)` to the runtime calls building the document
+    /// of the type of `value`, field by field and element by element.
+    pub(super) fn lower_to_json(
+        &mut self,
+        expr: &SpannedHirExpr<'src>,
+        callee: &SpannedHirExpr<'src>,
+        args: &[SpannedHirExpr<'src>],
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> Option<MirOperand> {
+        let (Some(ty), [arg]) = (self.json_ty(callee, ctx), args) else {
+            self.push_unsupported_expr(expr.span, "to_json call");
+            return None;
+        };
+        let Some(mir_ty) = ctx.lower_ty(&ty) else {
+            self.push_unsupported_type(expr.span, &ty);
+            return None;
+        };
+        let value = self.lower_borrowed(arg, ctx)?;
+        let value = coerce_operand(value, &mir_ty, arg.span, ctx);
+        self.encode(value, &ty, expr.span, ctx)
+    }
+
+    /// Lowers `from_json::<T>(doc)` to checks of the document against `T`
+    /// and the runtime calls reading a `T` out of it. The result is a
+    /// fallible `T` holding the message of the first check that fails.
+    pub(super) fn lower_from_json(
+        &mut self,
+        expr: &SpannedHirExpr<'src>,
+        callee: &SpannedHirExpr<'src>,
+        args: &[SpannedHirExpr<'src>],
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> Option<MirOperand> {
+        let (Some(ty), [arg]) = (self.json_ty(callee, ctx), args) else {
+            self.push_unsupported_expr(expr.span, "from_json call");
+            return None;
+        };
+        let Some(mir_ty) = ctx.lower_ty(&ty) else {
+            self.push_unsupported_type(expr.span, &ty);
+            return None;
+        };
+        let doc = self.lower_borrowed(arg, ctx)?;
+        let result_ty = MirTy::Fallible { ok: Box::new(mir_ty), err: Box::new(MirTy::Str) };
+        let result = ctx.lowered.push_local(None, result_ty, expr.span);
+        let failure = Failure { result, done: ctx.new_block() };
+
+        let value = self.decode(doc, &ty, &failure, expr.span, ctx)?;
+        ctx.push_stmt(MirStmt::Assign {
+            dst: MirPlace::new(result),
+            rhs: MirRvalue::WrapOk(value),
+            span: expr.span,
+        });
+        ctx.terminate(MirTerminator::Goto(failure.done));
+        ctx.block = failure.done;
+        Some(MirOperand::Copy(MirPlace::new(result)))
+    }
+
+    /// The type argument of a `to_json` or `from_json` call, in terms of the
+    /// instance being lowered.
+    fn json_ty(
+        &self,
+        callee: &SpannedHirExpr<'src>,
+        ctx: &FunctionLowerCtx<'src, '_>,
+    ) -> Option<Ty> {
+        match self.type_info.callee_args(ctx.function, callee).first()?.substitute(ctx.generic_args)
+        {
+            GenericArg::Type(ty) => Some(ty),
+            _ => None,
+        }
+    }
+
+    /// Builds the document of `value`, a `ty`.
+    fn encode(
+        &mut self,
+        value: MirOperand,
+        ty: &Ty,
+        span: Span,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> Option<MirOperand> {
+        match ty {
+            Ty::Bool => Some(self.call_runtime(RUNTIME_JSON_FROM_BOOL, [value], span, ctx)),
+            Ty::Int { .. } | Ty::Float { .. } => {
+                let number = cast_number(value, F64, span, ctx);
+                Some(self.call_runtime(RUNTIME_JSON_FROM_NUMBER, [number], span, ctx))
+            }
+            Ty::Str => Some(self.call_runtime(RUNTIME_JSON_FROM_STR, [value], span, ctx)),
+            Ty::BuiltinType { id: JSON, .. } => {
+                Some(copy(push_temp(MirTy::Managed, MirRvalue::Use(value), span, ctx)))
+            }
+            Ty::Nullable(inner) => {
+                let doc = ctx.lowered.push_local(None, MirTy::Managed, span);
+                let (null_block, some_block) = branch_on_null(value.clone(), span, ctx);
+                let join = ctx.new_block();
+
+                ctx.block = null_block;
+                let null = self.call_runtime(RUNTIME_JSON_NEW_NULL, [], span, ctx);
+                assign(doc, MirRvalue::Use(null), span, ctx);
+                ctx.terminate(MirTerminator::Goto(join));
+
+                ctx.block = some_block;
+                let inner_ty = ctx.lower_ty(inner)?;
+                let inner_value = push_temp(inner_ty, MirRvalue::UnwrapNullable(value), span, ctx);
+                let inner_doc = self.encode(copy(inner_value), inner, span, ctx)?;
+                assign(doc, MirRvalue::Use(inner_doc), span, ctx);
+                ctx.terminate(MirTerminator::Goto(join));
+
+                ctx.block = join;
+                Some(local_operand(ctx.lowered, doc))
+            }
+            Ty::BuiltinType { id: VEC, .. } | Ty::Slice(_) => {
+                let Some(elem) = elem_ty(ty) else {
+                    self.push_unsupported_type(span, ty);
+                    return None;
+                };
+                let array = self.call_runtime(RUNTIME_JSON_NEW_ARRAY, [], span, ctx);
+                let array = push_temp(MirTy::Managed, MirRvalue::Use(array), span, ctx);
+                let (element_loop, item) = self.begin_element_loop(value, span, ctx)?;
+                let item_doc = self.encode(item, &elem, span, ctx)?;
+                self.call_runtime(RUNTIME_JSON_PUSH, [copy(array), item_doc], span, ctx);
+                Some(self.end_element_loop(element_loop, array, ctx))
+            }
+            Ty::Struct { def, args } => {
+                let fields: Vec<_> = self
+                    .type_info
+                    .adt(*def)?
+                    .fields
+                    .iter()
+                    .map(|field| (field.name, field.ty.substitute(args)))
+                    .collect();
+                self.encode_fields(&value, (0, &fields), VariantKind::Struct, span, ctx)
+            }
+            // Variants are told apart by their index, which is the first
+            // field of the record, or the value itself when no variant has
+            // fields.
+            Ty::Enum(def) => {
+                let variants = self.type_info.variants(*def).to_vec();
+                let tag = match ctx.lower_ty(ty)? {
+                    MirTy::Int(_) => value.clone(),
+                    _ => {
+                        let rhs = MirRvalue::LoadField { record: value.clone(), index: 0 };
+                        copy(push_temp(MirTy::Int(MirIntTy::VARIANT), rhs, span, ctx))
+                    }
+                };
+                let doc = ctx.lowered.push_local(None, MirTy::Managed, span);
+                let join = ctx.new_block();
+                let mut first = 1;
+                for (index, variant) in variants.iter().enumerate() {
+                    // The last variant is the only one left.
+                    let next = (index + 1 < variants.len()).then(|| {
+                        let rhs = MirRvalue::Binary {
+                            op: MirBinOp::Eq,
+                            lhs: tag.clone(),
+                            rhs: variant_immediate(index),
+                        };
+                        let is_variant = push_temp(MirTy::Bool, rhs, span, ctx);
+                        let (variant_block, next) = (ctx.new_block(), ctx.new_block());
+                        ctx.terminate(MirTerminator::Branch {
+                            cond: copy(is_variant),
+                            then_block: variant_block,
+                            else_block: next,
+                            span,
+                        });
+                        ctx.block = variant_block;
+                        next
+                    });
+                    let variant_doc = self.encode_variant(&value, first, variant, span, ctx)?;
+                    assign(doc, MirRvalue::Use(variant_doc), span, ctx);
+                    ctx.terminate(MirTerminator::Goto(join));
+                    if let Some(next) = next {
+                        ctx.block = next;
+                    }
+                    first += variant.fields.len();
+                }
+                ctx.block = join;
+                Some(local_operand(ctx.lowered, doc))
+            }
+            _ => {
+                self.push_unsupported_type(span, ty);
+                None
+            }
+        }
+    }
+
+    /// Builds the document of `variant` of the enum `value`, whose fields
+    /// start at field `first` of the record: the name of the variant, or an
+    /// object holding its fields under its name.
+    fn encode_variant(
+        &mut self,
+        value: &MirOperand,
+        first: usize,
+        variant: &VariantInfo<'src>,
+        span: Span,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> Option<MirOperand> {
+        let name = self.str_constant(variant.name);
+        if variant.kind == VariantKind::Unit {
+            return Some(self.call_runtime(RUNTIME_JSON_FROM_STR, [name], span, ctx));
+        }
+        let fields: Vec<_> =
+            variant.fields.iter().map(|field| (field.name, field.ty.clone())).collect();
+        let payload = self.encode_fields(value, (first, &fields), variant.kind, span, ctx)?;
+        let object = self.call_runtime(RUNTIME_JSON_NEW_OBJECT, [], span, ctx);
+        let object = push_temp(MirTy::Managed, MirRvalue::Use(object), span, ctx);
+        self.call_runtime(RUNTIME_JSON_INSERT, [copy(object), name, payload], span, ctx);
+        Some(local_operand(ctx.lowered, object))
+    }
+
+    /// Builds the document of the `fields` of the record `value`, starting
+    /// at field `first`: an object keyed by their names for a struct, or an
+    /// array of them for a tuple variant.
+    fn encode_fields(
+        &mut self,
+        value: &MirOperand,
+        (first, fields): (usize, &[(&'src str, Ty)]),
+        kind: VariantKind,
+        span: Span,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> Option<MirOperand> {
+        let container = match kind {
+            VariantKind::Tuple => RUNTIME_JSON_NEW_ARRAY,
+            _ => RUNTIME_JSON_NEW_OBJECT,
+        };
+        let container = self.call_runtime(container, [], span, ctx);
+        let container = push_temp(MirTy::Managed, MirRvalue::Use(container), span, ctx);
+        for (index, (name, field_ty)) in fields.iter().enumerate() {
+            let Some(mir_ty) = ctx.lower_ty(field_ty) else {
+                self.push_unsupported_type(span, field_ty);
+                return None;
+            };
+            let rhs = MirRvalue::LoadField { record: value.clone(), index: first + index };
+            let field = push_temp(mir_ty, rhs, span, ctx);
+            let field_doc = self.encode(copy(field), field_ty, span, ctx)?;
+            if kind == VariantKind::Tuple {
+                self.call_runtime(RUNTIME_JSON_PUSH, [copy(container), field_doc], span, ctx);
+            } else {
+                let key = self.str_constant(*name);
+                self.call_runtime(
+                    RUNTIME_JSON_INSERT,
+                    [copy(container), key, field_doc],
+                    span,
+                    ctx,
+                );
+            }
+        }
+        Some(local_operand(ctx.lowered, container))
+    }
+
+    /// Reads a `ty` out of the document `doc`, branching to `failure` with
+    /// the message of the first mismatch.
+    fn decode(
+        &mut self,
+        doc: MirOperand,
+        ty: &Ty,
+        failure: &Failure,
+        span: Span,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> Option<MirOperand> {
+        let Some(mir_ty) = ctx.lower_ty(ty) else {
+            self.push_unsupported_type(span, ty);
+            return None;
+        };
+        match (ty, &mir_ty) {
+            (Ty::Bool, _) => {
+                self.check_kind(doc.clone(), JSON_KIND_BOOL, failure, span, ctx);
+                Some(self.read_slot_value(RUNTIME_JSON_AS_BOOL, [doc], mir_ty, span, ctx))
+            }
+            (Ty::Str, _) => {
+                self.check_kind(doc.clone(), JSON_KIND_STRING, failure, span, ctx);
+                Some(self.read_slot_value(RUNTIME_JSON_AS_STR, [doc], mir_ty, span, ctx))
+            }
+            (Ty::Float { .. }, _) => {
+                self.check_kind(doc.clone(), JSON_KIND_NUMBER, failure, span, ctx);
+                let number = self.read_slot_value(RUNTIME_JSON_AS_NUMBER, [doc], F64, span, ctx);
+                Some(cast_number(number, mir_ty, span, ctx))
+            }
+            (Ty::Int { .. }, MirTy::Int(MirIntTy { signed, bits })) => {
+                let bits = match bits {
+                    TypeBits::B8 => 8,
+                    TypeBits::B16 => 16,
+                    TypeBits::B32 => 32,
+                    _ => 64,
+                };
+                let args = [
+                    doc.clone(),
+                    MirOperand::Immediate(MirImmediate::Bool(*signed)),
+                    usize_immediate(bits),
+                ];
+                self.check((RUNTIME_JSON_CHECK_INT, args), failure, span, ctx);
+                let number = self.read_slot_value(RUNTIME_JSON_AS_NUMBER, [doc], F64, span, ctx);
+                Some(cast_number(number, mir_ty.clone(), span, ctx))
+            }
+            (Ty::BuiltinType { id: JSON, .. }, _) => {
+                Some(copy(push_temp(MirTy::Managed, MirRvalue::Use(doc), span, ctx)))
+            }
+            (Ty::Nullable(inner), _) => {
+                let value = ctx.lowered.push_local(None, mir_ty.clone(), span);
+                let is_null = self.call_runtime(RUNTIME_JSON_IS_NULL, [doc.clone()], span, ctx);
+                let null_block = ctx.new_block();
+                let some_block = ctx.new_block();
+                let join = ctx.new_block();
+                ctx.terminate(MirTerminator::Branch {
+                    cond: is_null,
+                    then_block: null_block,
+                    else_block: some_block,
+                    span,
+                });
+
+                ctx.block = null_block;
+                assign(value, MirRvalue::Use(MirOperand::Immediate(MirImmediate::Null)), span, ctx);
+                ctx.terminate(MirTerminator::Goto(join));
+
+                ctx.block = some_block;
+                let inner_value = self.decode(doc, inner, failure, span, ctx)?;
+                assign(value, MirRvalue::WrapNullable(inner_value), span, ctx);
+                ctx.terminate(MirTerminator::Goto(join));
+
+                ctx.block = join;
+                Some(local_operand(ctx.lowered, value))
+            }
+            (Ty::BuiltinType { id: VEC, .. }, MirTy::Vec(_)) => {
+                let Some(elem) = elem_ty(ty) else {
+                    self.push_unsupported_type(span, ty);
+                    return None;
+                };
+                self.check_kind(doc.clone(), JSON_KIND_ARRAY, failure, span, ctx);
+                let rhs = MirRvalue::Intrinsic { intrinsic: Intrinsic::VecNew, args: Box::new([]) };
+                let vec = push_temp(mir_ty.clone(), rhs, span, ctx);
+                let len = self.call_runtime(RUNTIME_JSON_LEN, [doc.clone()], span, ctx);
+                let counter = push_temp(usize_ty(), MirRvalue::Use(usize_immediate(0)), span, ctx);
+
+                let head = ctx.new_block();
+                let body = ctx.new_block();
+                let exit = ctx.new_block();
+                ctx.terminate(MirTerminator::Goto(head));
+                ctx.block = head;
+                let rhs = MirRvalue::Binary { op: MirBinOp::Lt, lhs: copy(counter), rhs: len };
+                let in_range = push_temp(MirTy::Bool, rhs, span, ctx);
+                ctx.terminate(MirTerminator::Branch {
+                    cond: copy(in_range),
+                    then_block: body,
+                    else_block: exit,
+                    span,
+                });
+
+                ctx.block = body;
+                let args = [doc, copy(counter)];
+                let item = self.read_slot_value(RUNTIME_JSON_AT, args, MirTy::Managed, span, ctx);
+                let item = self.decode(item, &elem, failure, span, ctx)?;
+                let args = Box::new([copy(vec), item]);
+                let rhs = MirRvalue::Intrinsic { intrinsic: Intrinsic::VecPush, args };
+                push_temp(MirTy::Unit, rhs, span, ctx);
+                let rhs = MirRvalue::Binary {
+                    op: MirBinOp::Add,
+                    lhs: copy(counter),
+                    rhs: usize_immediate(1),
+                };
+                assign(counter, rhs, span, ctx);
+                ctx.terminate(MirTerminator::Goto(head));
+
+                ctx.block = exit;
+                Some(local_operand(ctx.lowered, vec))
+            }
+            (Ty::Struct { def, args }, MirTy::Record { .. }) => {
+                let fields: Vec<_> = self
+                    .type_info
+                    .adt(*def)?
+                    .fields
+                    .iter()
+                    .map(|field| (field.name, field.ty.substitute(args)))
+                    .collect();
+                let values =
+                    self.decode_fields(doc, &fields, VariantKind::Struct, failure, span, ctx)?;
+                let rhs = MirRvalue::MakeRecord(values.into_iter().map(Some).collect());
+                let record = push_temp(mir_ty, rhs, span, ctx);
+                Some(local_operand(ctx.lowered, record))
+            }
+            (Ty::Enum(def), _) => {
+                let variants = self.type_info.variants(*def).to_vec();
+                let value = ctx.lowered.push_local(None, mir_ty.clone(), span);
+                let join = ctx.new_block();
+                let (units, payloads): (Vec<_>, Vec<_>) = variants
+                    .iter()
+                    .enumerate()
+                    .partition(|(_, variant)| variant.kind == VariantKind::Unit);
+                // Variants without fields are written as strings and the
+                // others as objects, so a document can only be one of them
+                // when the enum has both.
+                let (string_block, object_block, mismatch) =
+                    match (units.is_empty(), payloads.is_empty()) {
+                        (false, false) => {
+                            let args = [doc.clone(), usize_immediate(JSON_KIND_STRING as u128)];
+                            let slot = self.call_slot(RUNTIME_JSON_CHECK_KIND, args, span, ctx);
+                            let (string_block, object_block) =
+                                branch_on_null(copy(slot), span, ctx);
+                            (Some(string_block), Some(object_block), Some(slot))
+                        }
+                        (false, true) => (Some(ctx.block), None, None),
+                        _ => (None, Some(ctx.block), None),
+                    };
+
+                if let Some(block) = string_block {
+                    ctx.block = block;
+                    self.check_kind(doc.clone(), JSON_KIND_STRING, failure, span, ctx);
+                    let name = self.read_slot_value(
+                        RUNTIME_JSON_AS_STR,
+                        [doc.clone()],
+                        MirTy::Str,
+                        span,
+                        ctx,
+                    );
+                    for (index, variant) in &units {
+                        let args = Box::new([name.clone(), self.str_constant(variant.name)]);
+                        let rhs = MirRvalue::Intrinsic { intrinsic: Intrinsic::StrCmp, args };
+                        let ordering = push_temp(MirTy::Int(ORDERING_TY), rhs, span, ctx);
+                        let rhs = MirRvalue::Binary {
+                            op: MirBinOp::Eq,
+                            lhs: copy(ordering),
+                            rhs: ordering_zero(),
+                        };
+                        let matches = push_temp(MirTy::Bool, rhs, span, ctx);
+                        let (variant_block, next) = (ctx.new_block(), ctx.new_block());
+                        ctx.terminate(MirTerminator::Branch {
+                            cond: copy(matches),
+                            then_block: variant_block,
+                            else_block: next,
+                            span,
+                        });
+                        ctx.block = variant_block;
+                        let rhs = make_variant(&mir_ty, *index, 0, Vec::new());
+                        assign(value, rhs, span, ctx);
+                        ctx.terminate(MirTerminator::Goto(join));
+                        ctx.block = next;
+                    }
+                    let message = self.str_constant(expected_one_of(&units));
+                    fail(message, failure, span, ctx);
+                }
+
+                if let Some(block) = object_block {
+                    ctx.block = block;
+                    // The check handed out the message of the mismatch,
+                    // which is released with the temporary it is read into.
+                    if let Some(slot) = mismatch {
+                        load(slot, MirTy::Str, span, ctx);
+                    }
+                    self.check_kind(doc.clone(), JSON_KIND_OBJECT, failure, span, ctx);
+                    for (index, variant) in &payloads {
+                        let key = self.str_constant(variant.name);
+                        let slot = self.call_slot(RUNTIME_JSON_GET, [doc.clone(), key], span, ctx);
+                        let (missing, present) = branch_on_null(copy(slot), span, ctx);
+                        ctx.block = present;
+                        let payload = load(slot, MirTy::Managed, span, ctx);
+                        let fields: Vec<_> = variant
+                            .fields
+                            .iter()
+                            .map(|field| (field.name, field.ty.clone()))
+                            .collect();
+                        let values =
+                            self.decode_fields(payload, &fields, variant.kind, failure, span, ctx)?;
+                        let first = 1 + variants[..*index]
+                            .iter()
+                            .map(|variant| variant.fields.len())
+                            .sum::<usize>();
+                        assign(value, make_variant(&mir_ty, *index, first, values), span, ctx);
+                        ctx.terminate(MirTerminator::Goto(join));
+                        ctx.block = missing;
+                    }
+                    let message = self.str_constant(expected_one_of(&payloads));
+                    fail(message, failure, span, ctx);
+                }
+
+                ctx.block = join;
+                Some(local_operand(ctx.lowered, value))
+            }
+            _ => {
+                self.push_unsupported_type(span, ty);
+                None
+            }
+        }
+    }
+
+    /// Reads the `fields` of a struct or of a variant out of the document
+    /// `doc`: an object keyed by their names, or an array of them for a
+    /// tuple variant.
+    fn decode_fields(
+        &mut self,
+        doc: MirOperand,
+        fields: &[(&'src str, Ty)],
+        kind: VariantKind,
+        failure: &Failure,
+        span: Span,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> Option<Vec<MirOperand>> {
+        let mut values = Vec::with_capacity(fields.len());
+        if kind == VariantKind::Tuple {
+            self.check_kind(doc.clone(), JSON_KIND_ARRAY, failure, span, ctx);
+            let len = self.call_runtime(RUNTIME_JSON_LEN, [doc.clone()], span, ctx);
+            let rhs = MirRvalue::Binary {
+                op: MirBinOp::Eq,
+                lhs: len,
+                rhs: usize_immediate(fields.len() as u128),
+            };
+            let has_len = push_temp(MirTy::Bool, rhs, span, ctx);
+            let (matched, mismatched) = (ctx.new_block(), ctx.new_block());
+            ctx.terminate(MirTerminator::Branch {
+                cond: copy(has_len),
+                then_block: matched,
+                else_block: mismatched,
+                span,
+            });
+            ctx.block = mismatched;
+            let items = if fields.len() == 1 { "item" } else { "items" };
+            let message = format!("expected an array of {} {items}", fields.len());
+            let message = self.str_constant(message);
+            fail(message, failure, span, ctx);
+
+            ctx.block = matched;
+            for (index, (_, field_ty)) in fields.iter().enumerate() {
+                let args = [doc.clone(), usize_immediate(index as u128)];
+                let item = self.read_slot_value(RUNTIME_JSON_AT, args, MirTy::Managed, span, ctx);
+                values.push(self.decode(item, field_ty, failure, span, ctx)?);
+            }
+            return Some(values);
+        }
+        self.check_kind(doc.clone(), JSON_KIND_OBJECT, failure, span, ctx);
+        for (name, field_ty) in fields {
+            let key = self.str_constant(*name);
+            let slot = self.call_slot(RUNTIME_JSON_GET, [doc.clone(), key], span, ctx);
+            let (missing, present) = branch_on_null(copy(slot), span, ctx);
+            // Nullable fields may be left out, and read as `null`.
+            let value = match field_ty {
+                Ty::Nullable(_) => {
+                    let field_mir_ty = ctx.lower_ty(field_ty)?;
+                    let value = ctx.lowered.push_local(None, field_mir_ty, span);
+                    let join = ctx.new_block();
+                    ctx.block = missing;
+                    let null = MirOperand::Immediate(MirImmediate::Null);
+                    assign(value, MirRvalue::Use(null), span, ctx);
+                    ctx.terminate(MirTerminator::Goto(join));
+
+                    ctx.block = present;
+                    let field_doc = load(slot, MirTy::Managed, span, ctx);
+                    let decoded = self.decode(field_doc, field_ty, failure, span, ctx)?;
+                    assign(value, MirRvalue::Use(decoded), span, ctx);
+                    ctx.terminate(MirTerminator::Goto(join));
+                    ctx.block = join;
+                    local_operand(ctx.lowered, value)
+                }
+                _ => {
+                    ctx.block = missing;
+                    let message = self.str_constant(format!("missing field `{name}`"));
+                    fail(message, failure, span, ctx);
+
+                    ctx.block = present;
+                    let field_doc = load(slot, MirTy::Managed, span, ctx);
+                    self.decode(field_doc, field_ty, failure, span, ctx)?
+                }
+            };
+            values.push(value);
+        }
+        Some(values)
+    }
+
+    /// Branches to `failure` unless `doc` is of `kind`.
+    fn check_kind(
+        &mut self,
+        doc: MirOperand,
+        kind: usize,
+        failure: &Failure,
+        span: Span,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) {
+        let args = [doc, usize_immediate(kind as u128)];
+        self.check((RUNTIME_JSON_CHECK_KIND, args), failure, span, ctx);
+    }
+
+    /// Calls a runtime check, which hands back null when it passes or the
+    /// slot of the message, and branches to `failure` with the message.
+    fn check<const N: usize>(
+        &mut self,
+        (runtime, args): (RuntimeFunctionId, [MirOperand; N]),
+        failure: &Failure,
+        span: Span,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) {
+        let slot = self.call_slot(runtime, args, span, ctx);
+        let (passed, failed) = branch_on_null(copy(slot), span, ctx);
+        ctx.block = failed;
+        let message = load(slot, MirTy::Str, span, ctx);
+        fail(message, failure, span, ctx);
+        ctx.block = passed;
+    }
+
+    /// Calls a runtime function returning a value.
+    fn call_runtime<const N: usize>(
+        &mut self,
+        runtime: RuntimeFunctionId,
+        args: [MirOperand; N],
+        span: Span,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> MirOperand {
+        let ret_ty = match runec_abi::runtime_function(runtime).map(|decl| decl.ret) {
+            Some(runec_abi::AbiType::I8) => MirTy::Bool,
+            Some(runec_abi::AbiType::Usize) => usize_ty(),
+            Some(runec_abi::AbiType::Unit) => MirTy::Unit,
+            _ => MirTy::Managed,
+        };
+        self.emit_call((MirCallee::Runtime(runtime), Box::new(args)), None, ret_ty, span, ctx)
+    }
+
+    /// Calls a runtime function handing back the slot of its result.
+    fn call_slot<const N: usize>(
+        &mut self,
+        runtime: RuntimeFunctionId,
+        args: [MirOperand; N],
+        span: Span,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> MirLocalId {
+        let rhs = MirRvalue::Call { callee: MirCallee::Runtime(runtime), args: Box::new(args) };
+        push_temp(MirTy::Pointer, rhs, span, ctx)
+    }
+
+    /// Calls a runtime function that hands back the slot of a `ty` the
+    /// checks before it guarantee, and reads the value.
+    fn read_slot_value<const N: usize>(
+        &mut self,
+        runtime: RuntimeFunctionId,
+        args: [MirOperand; N],
+        ty: MirTy,
+        span: Span,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> MirOperand {
+        let slot = self.call_slot(runtime, args, span, ctx);
+        load(slot, ty, span, ctx)
+    }
+
+    fn str_constant(&mut self, value: impl Into<Cow<'src, str>>) -> MirOperand {
+        MirOperand::Constant(self.res.module.push_constant(MirConstant::Str(value.into())))
+    }
+}
+
+/// Numbers in documents are `f64`s.
+const F64: MirTy = MirTy::Float(MirFloatTy { bits: TypeBits::B64 });
+
+/// Element type of a vector or slice type.
+fn elem_ty(ty: &Ty) -> Option<Ty> {
+    match ty {
+        Ty::BuiltinType { id: VEC, args } => args.first().cloned(),
+        Ty::Slice(elem) => Some((**elem).clone()),
+        _ => None,
+    }
+}
+
+/// Ends the current block with a branch on whether `value` is `null`,
+/// returning the blocks taken when it is and when it is not.
+fn branch_on_null(
+    value: MirOperand,
+    span: Span,
+    ctx: &mut FunctionLowerCtx<'_, '_>,
+) -> (MirBlockId, MirBlockId) {
+    let rhs = MirRvalue::Binary {
+        op: MirBinOp::Eq,
+        lhs: value,
+        rhs: MirOperand::Immediate(MirImmediate::Null),
+    };
+    let is_null = push_temp(MirTy::Bool, rhs, span, ctx);
+    let null_block = ctx.new_block();
+    let some_block = ctx.new_block();
+    ctx.terminate(MirTerminator::Branch {
+        cond: copy(is_null),
+        then_block: null_block,
+        else_block: some_block,
+        span,
+    });
+    (null_block, some_block)
+}
+
+/// Stores `message` as the error of the `from_json` result and leaves the
+/// expansion.
+fn fail(message: MirOperand, failure: &Failure, span: Span, ctx: &mut FunctionLowerCtx<'_, '_>) {
+    assign(failure.result, MirRvalue::WrapErr(message), span, ctx);
+    ctx.terminate(MirTerminator::Goto(failure.done));
+}
+
+/// Reads the `ty` the runtime handed back the slot of.
+fn load(slot: MirLocalId, ty: MirTy, span: Span, ctx: &mut FunctionLowerCtx<'_, '_>) -> MirOperand {
+    copy(push_temp(ty, MirRvalue::Load(copy(slot)), span, ctx))
+}
+
+/// Converts the number `value` to the number type `ty`, unless it has it.
+fn cast_number(
+    value: MirOperand,
+    ty: MirTy,
+    span: Span,
+    ctx: &mut FunctionLowerCtx<'_, '_>,
+) -> MirOperand {
+    if ctx.lowered.operand_ty(&value).as_ref() == Some(&ty) {
+        return value;
+    }
+    let rhs = MirRvalue::Intrinsic { intrinsic: Intrinsic::NumberCast, args: Box::new([value]) };
+    copy(push_temp(ty, rhs, span, ctx))
+}
+
+/// The index of variant `index`, as stored in enum values.
+fn variant_immediate(index: usize) -> MirOperand {
+    MirOperand::Immediate(MirImmediate::Int { value: index as u128, ty: MirIntTy::VARIANT })
+}
+
+/// Builds variant `index` of an enum of type `ty` out of the `values` of its
+/// fields, which start at field `first` of the record. Fields of the other
+/// variants are zeroed.
+fn make_variant(ty: &MirTy, index: usize, first: usize, values: Vec<MirOperand>) -> MirRvalue {
+    let MirTy::Record { fields, .. } = ty else {
+        return MirRvalue::Use(variant_immediate(index));
+    };
+    let mut operands = vec![None; fields.len()];
+    operands[0] = Some(variant_immediate(index));
+    for (offset, value) in values.into_iter().enumerate() {
+        operands[first + offset] = Some(value);
+    }
+    MirRvalue::MakeRecord(operands.into_boxed_slice())
+}
+
+/// The message of a document naming none of `variants`.
+fn expected_one_of(variants: &[(usize, &VariantInfo<'_>)]) -> String {
+    let names: Vec<_> = variants.iter().map(|(_, variant)| format!("`{}`", variant.name)).collect();
+    format!("expected one of {}", names.join(", "))
+}
+
+fn assign(dst: MirLocalId, rhs: MirRvalue, span: Span, ctx: &mut FunctionLowerCtx<'_, '_>) {
+    ctx.push_stmt(MirStmt::Assign { dst: MirPlace::new(dst), rhs, span });
+}
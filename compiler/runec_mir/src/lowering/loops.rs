@@ -0,0 +1,113 @@
+use runec_builtins::Intrinsic;
+use runec_source::span::Span;
+
+use super::{FunctionLowerCtx, MirLowerer, local_operand};
+use crate::block::{MirBinOp, MirRvalue, MirStmt, MirTerminator};
+use crate::ids::{MirBlockId, MirLocalId};
+use crate::operand::{MirImmediate, MirOperand, MirPlace};
+use crate::ty::{MirIntTy, MirTy};
+
+/// Loop over the elements of a vector or slice by index.
+pub(super) struct ElementLoop {
+    /// Block that moves on to the next element.
+    step: MirBlockId,
+    exit: MirBlockId,
+}
+
+impl<'src> MirLowerer<'src, '_, '_> {
+    /// Starts a loop over the elements of a vector or slice and leaves `ctx`
+    /// in its body, with the current element.
+    pub(super) fn begin_element_loop(
+        &mut self,
+        sequence: MirOperand,
+        span: Span,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> Option<(ElementLoop, MirOperand)> {
+        let Some(MirTy::Vec(elem) | MirTy::Slice(elem)) = ctx.lowered.operand_ty(&sequence) else {
+            self.push_unsupported_expr(span, "element loop receiver");
+            return None;
+        };
+        let len = push_len(&sequence, span, ctx);
+        let counter = push_temp(usize_ty(), MirRvalue::Use(usize_immediate(0)), span, ctx);
+
+        let head = ctx.new_block();
+        let body = ctx.new_block();
+        let step = ctx.new_block();
+        let exit = ctx.new_block();
+        ctx.terminate(MirTerminator::Goto(head));
+
+        ctx.block = head;
+        let in_range = push_comparison(MirBinOp::Lt, copy(counter), copy(len), span, ctx);
+        ctx.terminate(MirTerminator::Branch {
+            cond: copy(in_range),
+            then_block: body,
+            else_block: exit,
+            span,
+        });
+
+        ctx.block = step;
+        let rhs =
+            MirRvalue::Binary { op: MirBinOp::Add, lhs: copy(counter), rhs: usize_immediate(1) };
+        ctx.push_stmt(MirStmt::Assign { dst: MirPlace::new(counter), rhs, span });
+        ctx.terminate(MirTerminator::Goto(head));
+
+        ctx.block = body;
+        let rhs = MirRvalue::Index { base: sequence, index: copy(counter) };
+        let item = push_temp(*elem, rhs, span, ctx);
+        Some((ElementLoop { step, exit }, local_operand(ctx.lowered, item)))
+    }
+
+    /// Closes the body of an element loop and continues after it with
+    /// `result`.
+    pub(super) fn end_element_loop(
+        &mut self,
+        element_loop: ElementLoop,
+        result: MirLocalId,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> MirOperand {
+        ctx.terminate(MirTerminator::Goto(element_loop.step));
+        ctx.block = element_loop.exit;
+        local_operand(ctx.lowered, result)
+    }
+}
+
+fn push_len(sequence: &MirOperand, span: Span, ctx: &mut FunctionLowerCtx<'_, '_>) -> MirLocalId {
+    let rhs =
+        MirRvalue::Intrinsic { intrinsic: Intrinsic::Len, args: Box::new([sequence.clone()]) };
+    push_temp(usize_ty(), rhs, span, ctx)
+}
+
+/// Compares two lengths with `op`.
+fn push_comparison(
+    op: MirBinOp,
+    lhs: MirOperand,
+    rhs: MirOperand,
+    span: Span,
+    ctx: &mut FunctionLowerCtx<'_, '_>,
+) -> MirLocalId {
+    push_temp(MirTy::Bool, MirRvalue::Binary { op, lhs, rhs }, span, ctx)
+}
+
+/// Assigns `rhs` to a new temporary of type `ty` and returns it.
+pub(super) fn push_temp(
+    ty: MirTy,
+    rhs: MirRvalue,
+    span: Span,
+    ctx: &mut FunctionLowerCtx<'_, '_>,
+) -> MirLocalId {
+    let dst = ctx.lowered.push_local(None, ty, span);
+    ctx.push_stmt(MirStmt::Assign { dst: MirPlace::new(dst), rhs, span });
+    dst
+}
+
+pub(super) fn copy(local: MirLocalId) -> MirOperand {
+    MirOperand::Copy(MirPlace::new(local))
+}
+
+pub(super) fn usize_ty() -> MirTy {
+    MirTy::Int(MirIntTy::USIZE)
+}
+
+pub(super) fn usize_immediate(value: u128) -> MirOperand {
+    MirOperand::Immediate(MirImmediate::Int { value, ty: MirIntTy::USIZE })
+}
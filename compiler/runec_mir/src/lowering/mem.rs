@@ -0,0 +1,265 @@
+use runec_builtins::{BuiltinLowering, Intrinsic, PlaceOp, TypeBits};
+use runec_hir::expression::{HirExpr, SpannedHirExpr};
+use runec_hir::resolution::Res;
+use runec_semantic::typeck::GenericArg;
+use runec_source::span::Span;
+
+use super::{FunctionLowerCtx, MirLowerer, coerce_operand, local_operand, messages};
+use crate::block::{MirRvalue, MirStmt};
+use crate::constant::MirConstant;
+use crate::ids::MirLocalId;
+use crate::operand::{MirImmediate, MirOperand, MirPlace};
+use crate::ty::{MirIntTy, MirTy};
+
+/// Place that an assignment or a `core::mem` builtin writes to, with its
+/// base and index already evaluated so that reading and writing it runs
+/// them once.
+pub(super) enum Place {
+    /// A local, or the cell holding it when closures capture it by
+    /// reference.
+    Local(MirLocalId),
+    /// An element of a vector or slice.
+    Element { base: MirOperand, index: MirOperand, elem: MirTy },
+    /// The value a raw pointer points to.
+    Pointee { pointer: MirOperand, ty: MirTy },
+}
+
+impl<'src> MirLowerer<'src, '_, '_> {
+    /// Evaluates the base and index of `expr`, a local or an element, or
+    /// the pointer of a dereference.
+    pub(super) fn lower_place(
+        &mut self,
+        expr: &SpannedHirExpr<'src>,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> Option<Place> {
+        match &expr.node {
+            HirExpr::Resolved(Res::Local(local)) | HirExpr::NonNullLocal(local) => {
+                let Some(local) = ctx.locals.get(local).copied() else {
+                    let local = format!("{local:?}");
+                    self.push_diag(expr.span, messages::UNKNOWN_LOCAL, &[("local", &local)]);
+                    return None;
+                };
+                Some(Place::Local(local))
+            }
+            HirExpr::Index { base, index } => {
+                let base_operand = self.lower_borrowed(base, ctx)?;
+                let index = self.lower_expr(index, ctx)?;
+                let elem = match ctx.lowered.operand_ty(&base_operand) {
+                    Some(MirTy::Vec(elem) | MirTy::Slice(elem)) => *elem,
+                    _ => {
+                        self.push_unsupported_expr(base.span, "indexed value");
+                        return None;
+                    }
+                };
+                Some(Place::Element { base: base_operand, index, elem })
+            }
+            // Like loads, stores through pointers leave reference-counted
+            // values out, as the memory does not hold a reference.
+            HirExpr::Deref(pointer) => {
+                let ty = self.type_info.ty_of_expr(ctx.function, expr);
+                let Some(lowered) = ctx.lower_ty(&ty).filter(|ty| !ty.is_managed()) else {
+                    self.push_unsupported_type(expr.span, &ty);
+                    return None;
+                };
+                let pointer = self.lower_expr(pointer, ctx)?;
+                Some(Place::Pointee { pointer, ty: lowered })
+            }
+            _ => {
+                self.push_unsupported_expr(expr.span, "place");
+                None
+            }
+        }
+    }
+
+    /// Stores `value`, which was lowered from an expression at
+    /// `value_span`, in `place`.
+    pub(super) fn write_place(
+        &mut self,
+        place: &Place,
+        value: MirOperand,
+        (value_span, span): (Span, Span),
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) {
+        match place {
+            Place::Local(local) => {
+                let ty = ctx.lowered.locals[local.to_usize()].ty.clone();
+                if let MirTy::Cell(inner) = &ty {
+                    let value = coerce_operand(value, inner, value_span, ctx);
+                    let cell = MirOperand::Copy(MirPlace::new(*local));
+                    ctx.push_stmt(MirStmt::StoreCell { cell, value, span });
+                    return;
+                }
+                let value = coerce_operand(value, &ty, value_span, ctx);
+                ctx.push_stmt(MirStmt::Assign {
+                    dst: MirPlace::new(*local),
+                    rhs: MirRvalue::Use(value),
+                    span,
+                });
+            }
+            Place::Element { base, index, elem } => {
+                let value = coerce_operand(value, elem, value_span, ctx);
+                ctx.push_stmt(MirStmt::StoreIndex {
+                    base: base.clone(),
+                    index: index.clone(),
+                    value,
+                    span,
+                });
+            }
+            Place::Pointee { pointer, ty } => {
+                let value = coerce_operand(value, ty, value_span, ctx);
+                ctx.push_stmt(MirStmt::Store { pointer: pointer.clone(), value, span });
+            }
+        }
+    }
+
+    /// Moves the value out of `place` into a new temporary, so that the
+    /// place can be written before the value is used.
+    fn read_place(
+        &mut self,
+        place: &Place,
+        span: Span,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> MirOperand {
+        let (ty, rhs) = match place {
+            Place::Local(local) => match ctx.lowered.locals[local.to_usize()].ty.clone() {
+                MirTy::Cell(inner) => {
+                    (*inner, MirRvalue::LoadCell(MirOperand::Copy(MirPlace::new(*local))))
+                }
+                ty => (ty, MirRvalue::Use(local_operand(ctx.lowered, *local))),
+            },
+            Place::Element { base, index, elem } => {
+                (elem.clone(), MirRvalue::Index { base: base.clone(), index: index.clone() })
+            }
+            Place::Pointee { pointer, ty } => (ty.clone(), MirRvalue::Load(pointer.clone())),
+        };
+        let value = ctx.lowered.push_local(None, ty, span);
+        ctx.push_stmt(MirStmt::Assign { dst: MirPlace::new(value), rhs, span });
+        local_operand(ctx.lowered, value)
+    }
+
+    /// Lowers `swap`, `replace` or `take` to reads and writes of
+    /// the places passed to it.
+    pub(super) fn lower_place_op(
+        &mut self,
+        expr: &SpannedHirExpr<'src>,
+        op: PlaceOp,
+        args: &[SpannedHirExpr<'src>],
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> Option<MirOperand> {
+        match (op, args) {
+            (PlaceOp::Swap, [a, b]) => {
+                let a_place = self.lower_place(a, ctx)?;
+                let b_place = self.lower_place(b, ctx)?;
+                let a_value = self.read_place(&a_place, a.span, ctx);
+                let b_value = self.read_place(&b_place, b.span, ctx);
+                self.write_place(&a_place, b_value, (b.span, expr.span), ctx);
+                self.write_place(&b_place, a_value, (a.span, expr.span), ctx);
+                Some(MirOperand::Immediate(MirImmediate::Unit))
+            }
+            (PlaceOp::Replace, [target, value]) => {
+                let place = self.lower_place(target, ctx)?;
+                let value_operand = self.lower_expr(value, ctx)?;
+                let previous = self.read_place(&place, target.span, ctx);
+                self.write_place(&place, value_operand, (value.span, expr.span), ctx);
+                Some(previous)
+            }
+            (PlaceOp::Take, [target]) => {
+                let place = self.lower_place(target, ctx)?;
+                let previous = self.read_place(&place, target.span, ctx);
+                let ty = ctx.lowered.operand_ty(&previous)?;
+                let default = self.default_value(&ty, expr.span, ctx)?;
+                self.write_place(&place, default, (expr.span, expr.span), ctx);
+                Some(previous)
+            }
+            _ => {
+                self.push_unsupported_expr(expr.span, "place builtin call");
+                None
+            }
+        }
+    }
+
+    /// Lowers `core::default::default` to the default value of the type it
+    /// is called with.
+    pub(super) fn lower_default(
+        &mut self,
+        expr: &SpannedHirExpr<'src>,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> Option<MirOperand> {
+        let ty = self.type_info.ty_of_expr(ctx.function, expr);
+        let Some(ty) = ctx.lower_ty(&ty) else {
+            self.push_unsupported_expr(expr.span, "default value");
+            return None;
+        };
+        self.default_value(&ty, expr.span, ctx)
+    }
+
+    /// Builds the value a type implementing `Default` defaults to.
+    fn default_value(
+        &mut self,
+        ty: &MirTy,
+        span: Span,
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> Option<MirOperand> {
+        let immediate = match ty {
+            MirTy::Bool => MirImmediate::Bool(false),
+            MirTy::Int(ty) => MirImmediate::Int { value: 0, ty: *ty },
+            MirTy::Float(ty) => MirImmediate::Float { value: 0.0, ty: *ty },
+            MirTy::Char => MirImmediate::Char('\0'),
+            MirTy::Nullable(_) => MirImmediate::Null,
+            MirTy::Str => {
+                let id = self.res.module.push_constant(MirConstant::Str("".into()));
+                return Some(MirOperand::Constant(id));
+            }
+            MirTy::Vec(_) | MirTy::Map { .. } => {
+                let intrinsic =
+                    if matches!(ty, MirTy::Vec(_)) { Intrinsic::VecNew } else { Intrinsic::MapNew };
+                let value = ctx.lowered.push_local(None, ty.clone(), span);
+                ctx.push_stmt(MirStmt::Assign {
+                    dst: MirPlace::new(value),
+                    rhs: MirRvalue::Intrinsic { intrinsic, args: Box::new([]) },
+                    span,
+                });
+                return Some(local_operand(ctx.lowered, value));
+            }
+            _ => {
+                self.push_unsupported_expr(span, "default value");
+                return None;
+            }
+        };
+        Some(MirOperand::Immediate(immediate))
+    }
+
+    /// Lowers `size_of::<T>()` or `align_of::<T>()` to a `usize` taken
+    /// from the layout of `T` in the instance being lowered.
+    pub(super) fn lower_layout_of(
+        &mut self,
+        expr: &SpannedHirExpr<'src>,
+        (lowering, callee): (BuiltinLowering, &SpannedHirExpr<'src>),
+        ctx: &mut FunctionLowerCtx<'src, '_>,
+    ) -> Option<MirOperand> {
+        let ty = match self.type_info.callee_args(ctx.function, callee).first() {
+            Some(arg) => match arg.substitute(ctx.generic_args) {
+                GenericArg::Type(ty) => ty,
+                _ => {
+                    self.push_unsupported_expr(callee.span, "layout argument");
+                    return None;
+                }
+            },
+            None => {
+                self.push_unsupported_expr(callee.span, "layout argument");
+                return None;
+            }
+        };
+        let Some(layout) = self.type_info.layout(&ty) else {
+            let ty = self.type_info.display_ty(&ty, None);
+            self.push_diag(expr.span, messages::NO_LAYOUT, &[("ty", &ty)]);
+            return None;
+        };
+        let value = match lowering {
+            BuiltinLowering::AlignOf => layout.align,
+            _ => layout.size,
+        };
+        let ty = MirIntTy { signed: false, bits: TypeBits::B64 };
+        Some(MirOperand::Immediate(MirImmediate::Int { value: value.into(), ty }))
+    }
+}
@@ -1,9 +1,13 @@
 use runec_utils::define_messages;
 
 define_messages! {
+    INSTANCE_DEPTH_LIMIT => "generic call nests more than { limit } instances; its generic arguments likely grow with every call",
+    INSTANCE_SIZE_LIMIT => "generic arguments of this call are built from more than { limit } types; they likely grow with every call",
     MISSING_FUNCTION_SIGNATURE => "missing function signature during MIR lowering",
     MISSING_LOCAL_ID => "missing HIR local ID during MIR lowering",
+    NO_LAYOUT => "type { ty } has no known layout for `size_of` or `align_of`",
     MISSING_LOCAL_INFO => "missing type information for local { local } during MIR lowering",
+    MOVE_OUT_OF_INDEX => "cannot move a `{ ty }` out of an index; swap it out with `core::mem::replace` or `core::mem::swap` instead",
     UNKNOWN_BUILTIN => "unknown builtin { builtin } during MIR lowering",
     UNKNOWN_LOCAL => "unknown local { local } during MIR lowering",
     UNSUPPORTED_EXPRESSION => "unsupported { expression } in MIR lowering",
@@ -1,26 +1,49 @@
 use std::borrow::Cow;
+use std::collections::HashMap;
 
-use runec_abi::RUNTIME_PRINT;
+use runec_abi::{
+    ROUND_HALF_UP, RUNTIME_DECIMAL_ADD, RUNTIME_DECIMAL_CMP, RUNTIME_DECIMAL_ROUND,
+    RUNTIME_DECIMAL_TO_STRING, RUNTIME_INPUT, RUNTIME_INSTANT_ELAPSED, RUNTIME_INSTANT_NOW,
+    RUNTIME_JSON_AS_NUMBER, RUNTIME_JSON_AS_STR, RUNTIME_JSON_AT, RUNTIME_JSON_CHECK_INT,
+    RUNTIME_JSON_CHECK_KIND, RUNTIME_JSON_FROM_NUMBER, RUNTIME_JSON_FROM_STR, RUNTIME_JSON_GET,
+    RUNTIME_JSON_INSERT, RUNTIME_JSON_IS_NULL, RUNTIME_JSON_LEN, RUNTIME_JSON_NEW_ARRAY,
+    RUNTIME_JSON_NEW_NULL, RUNTIME_JSON_NEW_OBJECT, RUNTIME_JSON_PUSH, RUNTIME_LINES_NEXT,
+    RUNTIME_LIST_DIR, RUNTIME_PRINT, RUNTIME_PRINTLN, RUNTIME_READER_LINES, RUNTIME_READER_STDIN,
+};
 use runec_ast::SpannedStr;
-use runec_builtins::{PRINT, TypeBits};
-use runec_hir::expression::{HirExpr, HirLiteral};
-use runec_hir::ids::{HirId, HirLocalId};
-use runec_hir::item::{HirFunction, HirItem};
+use runec_ast::operators::BinaryOp;
+use runec_ast::statement::Visibility;
+use runec_builtins::{
+    DECIMAL_FROM_INT, DECIMAL_TO_STRING, DROP, FILES_LIST_DIR, HASH_MAP, HASH_SET, INPUT,
+    INSTANT_NOW, INTO_ITERATOR_CONTRACT, Intrinsic, JSON_FROM_JSON, JSON_TO_JSON, MEM_REPLACE,
+    MEM_SIZE_OF, MEM_SWAP, MEM_TAKE, PANIC, PRINT, PRINTLN, RANDOM_SHUFFLE, ROUNDING_HALF_UP,
+    SET_NEW, STDIN_READER, TypeBits, VEC, VEC_NEW,
+};
+use runec_hir::expression::{
+    HirCapture, HirCaptureMode, HirClosure, HirClosureParam, HirExpr, HirLiteral, SpannedHirExpr,
+};
+use runec_hir::ids::{HirId, HirLocalId, HirModuleId};
+use runec_hir::item::{
+    HirBound, HirEnum, HirExternFunction, HirField, HirFunction, HirFunctionParam, HirGenericParam,
+    HirGenericParamKind, HirItem, HirStruct, HirVariant, HirVariantPayload,
+};
 use runec_hir::map::HirMap;
+use runec_hir::path::{HirGenericArg, HirPath, HirPathSegment};
 use runec_hir::resolution::Res;
 use runec_hir::statement::{HirBlock, HirStmt};
 use runec_hir::ty::{HirPrimitiveTy, HirType};
-use runec_semantic::typeck::TypeChecker;
+use runec_semantic::typeck::{Ty, TypeChecker, TypeInfo};
 use runec_source::byte_pos::BytePos;
 use runec_source::source_map::SourceId;
 use runec_source::span::{Span, Spanned};
 
-use crate::block::{MirRvalue, MirStmt, MirTerminator};
+use crate::block::{MirBinOp, MirRvalue, MirStmt, MirTerminator};
 use crate::constant::MirConstant;
 use crate::function::MirCallee;
-use crate::lowering::MirLowerer;
-use crate::operand::{MirImmediate, MirOperand};
-use crate::ty::{MirIntTy, MirTy};
+use crate::ids::{MirClosureId, MirExternId, MirInstanceId, MirLocalId};
+use crate::lowering::{MirLowerResult, MirLowerer, lower_ty};
+use crate::operand::{MirImmediate, MirOperand, MirPlace};
+use crate::ty::{MirIntTy, MirStruct, MirTy};
 
 const SRC: SourceId = SourceId::from_usize(0);
 
@@ -55,10 +78,13 @@ fn function_with_ret_ty(
     body: HirBlock<'static>,
 ) -> HirItem<'static> {
     HirItem::Function(HirFunction {
+        vis: Visibility::Private,
         id,
         name: SpannedStr::new(name, dummy()),
+        generics: Box::new([]),
         params: Box::new([]),
         ret_ty,
+        throws: None,
         body,
         span: dummy(),
     })
@@ -75,7 +101,13 @@ fn unsupported_return_type_error_preserves_span() {
     hir.push(function_with_ret_ty(
         HirId::from_usize(0),
         "invalid",
-        Spanned::new(HirType::Tuple(Box::new([])), return_span),
+        Spanned::new(
+            HirType::Array {
+                elem: Box::new(s(HirType::Primitive(HirPrimitiveTy::I32))),
+                len: Box::new(s(HirExpr::Literal(HirLiteral::Int { value: 2, suffix: None }))),
+            },
+            return_span,
+        ),
         empty_body(),
     ));
 
@@ -84,7 +116,7 @@ fn unsupported_return_type_error_preserves_span() {
 
     assert_eq!(result.diags.len(), 1);
     assert_eq!(result.diags[0].labels[0].span, return_span);
-    assert_eq!(result.diags[0].message.message, "unsupported type Tuple([]) in MIR lowering");
+    assert_eq!(result.diags[0].message.message, "unsupported type i32[2] in MIR lowering");
 }
 
 #[test]
@@ -142,7 +174,9 @@ fn lower_let_string_literal_to_local_assignment() {
     assert_eq!(function.locals[0].name, Some("message"));
     assert_eq!(function.locals[0].ty, MirTy::Str);
 
-    let MirStmt::Assign { dst, rhs, span } = &function.blocks[0].stmts[0];
+    let MirStmt::Assign { dst, rhs, span } = &function.blocks[0].stmts[0] else {
+        panic!("expected assignment");
+    };
     assert_eq!(*span, dummy());
     assert_eq!(dst.local.to_usize(), 0);
     assert_eq!(*rhs, MirRvalue::Use(MirOperand::Constant(crate::MirConstantId::from_usize(0))));
@@ -175,7 +209,9 @@ fn lower_print_builtin_call_to_runtime_call() {
     assert_eq!(function.locals[0].name, None);
     assert_eq!(function.locals[0].ty, MirTy::Unit);
 
-    let MirStmt::Assign { dst, rhs, span } = &function.blocks[0].stmts[0];
+    let MirStmt::Assign { dst, rhs, span } = &function.blocks[0].stmts[0] else {
+        panic!("expected assignment");
+    };
     assert_eq!(*span, dummy());
     assert_eq!(dst.local.to_usize(), 0);
 
@@ -186,6 +222,76 @@ fn lower_print_builtin_call_to_runtime_call() {
     assert_eq!(args.as_ref(), [MirOperand::Constant(crate::MirConstantId::from_usize(0))]);
 }
 
+/// `act main() { panic("boom"); println("after"); }`
+#[test]
+fn lower_panic_to_terminator_that_ends_its_block() {
+    let call = |builtin, text, span| {
+        HirStmt::Expr(Spanned::new(
+            HirExpr::Call {
+                callee: Box::new(s(HirExpr::Resolved(Res::Builtin(builtin)))),
+                args: Box::new([s(HirExpr::Literal(HirLiteral::Str(Cow::Borrowed(text))))]),
+            },
+            span,
+        ))
+    };
+    let body = HirBlock {
+        stmts: Box::new([call(PANIC, "boom", sp(14, 27)), call(PRINTLN, "after", sp(29, 45))]),
+        tail: None,
+        span: dummy(),
+    };
+
+    let mut hir = HirMap::new();
+    hir.push(unit_function_with_body(body));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty());
+
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+
+    assert!(result.diags.is_empty());
+    let function = &result.module.functions[0];
+    assert!(function.blocks[0].stmts.is_empty());
+    assert_eq!(
+        function.blocks[0].terminator,
+        MirTerminator::Panic {
+            message: MirOperand::Constant(crate::MirConstantId::from_usize(0)),
+            span: sp(14, 27),
+        }
+    );
+    assert_eq!(function.blocks[1].stmts.len(), 1);
+}
+
+/// `act fail() -> i32 { panic("boom"); }`
+#[test]
+fn lower_body_that_never_finishes_without_return_value() {
+    let body = HirBlock {
+        stmts: Box::new([HirStmt::Expr(s(HirExpr::Call {
+            callee: Box::new(s(HirExpr::Resolved(Res::Builtin(PANIC)))),
+            args: Box::new([s(HirExpr::Literal(HirLiteral::Str(Cow::Borrowed("boom"))))]),
+        }))]),
+        tail: None,
+        span: dummy(),
+    };
+
+    let mut hir = HirMap::new();
+    hir.push(function_with_ret_ty(
+        HirId::from_usize(0),
+        "fail",
+        s(HirType::Primitive(HirPrimitiveTy::I32)),
+        body,
+    ));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty());
+
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+
+    assert!(result.diags.is_empty());
+    let function = &result.module.functions[0];
+    assert!(matches!(function.blocks[0].terminator, MirTerminator::Panic { .. }));
+    assert_eq!(function.blocks[1].terminator, MirTerminator::Unreachable);
+}
+
 #[test]
 fn lower_user_function_call_to_function_callee() {
     let foo_id = HirId::from_usize(0);
@@ -213,7 +319,9 @@ fn lower_user_function_call_to_function_callee() {
     assert_eq!(result.module.entry.map(|id| id.to_usize()), Some(1));
 
     let main = &result.module.functions[1];
-    let MirStmt::Assign { dst, rhs, span } = &main.blocks[0].stmts[0];
+    let MirStmt::Assign { dst, rhs, span } = &main.blocks[0].stmts[0] else {
+        panic!("expected assignment");
+    };
     assert_eq!(*span, dummy());
     assert_eq!(dst.local.to_usize(), 0);
 
@@ -254,3 +362,2476 @@ fn lower_tail_expr_to_return_operand() {
         })))
     );
 }
+
+fn dropped_local_body(use_after_drop: bool) -> HirBlock<'static> {
+    let local = HirLocalId::from_usize(0);
+    let call = |builtin, span, arg| {
+        HirStmt::Expr(Spanned::new(
+            HirExpr::Call {
+                callee: Box::new(s(HirExpr::Resolved(Res::Builtin(builtin)))),
+                args: Box::new([Spanned::new(HirExpr::Resolved(Res::Local(local)), arg)]),
+            },
+            span,
+        ))
+    };
+
+    let mut stmts = vec![
+        HirStmt::Let {
+            local: Some(local),
+            name: SpannedStr::new("x", sp(4, 5)),
+            is_mutable: false,
+            ty: None,
+            init: Some(s(HirExpr::Literal(HirLiteral::Str(Cow::Borrowed("hi"))))),
+            span: sp(0, 11),
+        },
+        call(DROP, sp(12, 19), sp(17, 18)),
+    ];
+    if use_after_drop {
+        stmts.push(call(PRINTLN, sp(21, 31), sp(29, 30)));
+    }
+
+    HirBlock { stmts: stmts.into_boxed_slice(), tail: None, span: dummy() }
+}
+
+#[test]
+fn lower_drop_to_drop_statement_moving_its_argument() {
+    let mut hir = HirMap::new();
+    hir.push(unit_function_with_body(dropped_local_body(false)));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty());
+
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+
+    assert!(result.diags.is_empty());
+    let function = &result.module.functions[0];
+    assert_eq!(function.blocks[0].stmts.len(), 2);
+    assert_eq!(
+        function.blocks[0].stmts[1],
+        MirStmt::Drop {
+            operand: MirOperand::Move(MirPlace::new(MirLocalId::from_usize(0))),
+            span: sp(12, 19)
+        }
+    );
+}
+
+#[test]
+fn use_after_drop_reports_moved_value() {
+    let mut hir = HirMap::new();
+    hir.push(unit_function_with_body(dropped_local_body(true)));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty());
+
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+
+    assert_eq!(result.diags.len(), 1);
+    let diag = &result.diags[0];
+    assert_eq!(diag.message.message, "use of moved value: `x`");
+    assert_eq!(diag.labels.len(), 2);
+    assert_eq!(diag.labels[0].span, sp(21, 31));
+    assert_eq!(diag.labels[1].span, sp(17, 18));
+}
+
+/// `act main(flag: bool) { let x: str; if flag { x = "a"; } [else { x = "b"; }] println(x); }`
+fn deferred_init_function(with_else: bool) -> HirItem<'static> {
+    let flag = HirLocalId::from_usize(0);
+    let x = HirLocalId::from_usize(1);
+    let assign = |text: &'static str| HirBlock {
+        stmts: Box::new([HirStmt::Assign {
+            target: s(HirExpr::Resolved(Res::Local(x))),
+            value: s(HirExpr::Literal(HirLiteral::Str(Cow::Borrowed(text)))),
+            span: dummy(),
+        }]),
+        tail: None,
+        span: dummy(),
+    };
+
+    let body = HirBlock {
+        stmts: Box::new([
+            HirStmt::Let {
+                local: Some(x),
+                name: SpannedStr::new("x", sp(4, 5)),
+                is_mutable: false,
+                ty: Some(s(HirType::Primitive(HirPrimitiveTy::Str))),
+                init: None,
+                span: sp(0, 11),
+            },
+            HirStmt::Expr(s(HirExpr::If {
+                cond: Box::new(s(HirExpr::Resolved(Res::Local(flag)))),
+                then: assign("a"),
+                else_: with_else.then(|| Box::new(s(HirExpr::Block(assign("b"))))),
+            })),
+            HirStmt::Expr(Spanned::new(
+                HirExpr::Call {
+                    callee: Box::new(s(HirExpr::Resolved(Res::Builtin(PRINTLN)))),
+                    args: Box::new([s(HirExpr::Resolved(Res::Local(x)))]),
+                },
+                sp(40, 50),
+            )),
+        ]),
+        tail: None,
+        span: dummy(),
+    };
+
+    HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: HirId::from_usize(0),
+        name: SpannedStr::new("main", dummy()),
+        generics: Box::new([]),
+        params: Box::new([HirFunctionParam {
+            name: SpannedStr::new("flag", dummy()),
+            ty: s(HirType::Primitive(HirPrimitiveTy::Bool)),
+            span: dummy(),
+        }]),
+        ret_ty: s(HirType::Unit),
+        throws: None,
+        body,
+        span: dummy(),
+    })
+}
+
+#[test]
+fn lower_if_else_to_branch_and_join_blocks() {
+    let mut hir = HirMap::new();
+    hir.push(deferred_init_function(true));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty());
+
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+
+    assert!(result.diags.is_empty());
+    let function = &result.module.functions[0];
+    assert_eq!(function.blocks.len(), 4);
+    let MirTerminator::Branch { then_block, else_block, .. } = &function.blocks[0].terminator
+    else {
+        panic!("expected branch");
+    };
+    let join = MirTerminator::Goto(crate::MirBlockId::from_usize(3));
+    assert_eq!(function.blocks[then_block.to_usize()].terminator, join);
+    assert_eq!(function.blocks[else_block.to_usize()].terminator, join);
+    assert_eq!(function.blocks[3].terminator, MirTerminator::Return(None));
+}
+
+#[test]
+fn read_of_local_missing_initialization_on_a_branch_is_reported() {
+    let mut hir = HirMap::new();
+    hir.push(deferred_init_function(false));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty());
+
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+
+    assert_eq!(result.diags.len(), 1);
+    let diag = &result.diags[0];
+    assert_eq!(diag.message.message, "`x` is used before being initialized");
+    assert_eq!(diag.labels[0].span, sp(40, 50));
+    assert_eq!(diag.labels[1].span, sp(0, 11));
+}
+
+/// `act main(name: str?) { let n: i32? = 5; if name != null { println(name) } }`
+#[test]
+fn lower_null_check_to_comparison_and_unwrap() {
+    let name = HirLocalId::from_usize(0);
+    let nullable = |ty| s(HirType::Nullable(Box::new(s(HirType::Primitive(ty)))));
+    let body = HirBlock {
+        stmts: Box::new([
+            HirStmt::Let {
+                local: Some(HirLocalId::from_usize(1)),
+                name: SpannedStr::new("n", dummy()),
+                is_mutable: false,
+                ty: Some(nullable(HirPrimitiveTy::I32)),
+                init: Some(s(HirExpr::Literal(HirLiteral::Int { value: 5, suffix: None }))),
+                span: dummy(),
+            },
+            HirStmt::Expr(s(HirExpr::If {
+                cond: Box::new(s(HirExpr::Binary {
+                    op: BinaryOp::Ne,
+                    lhs: Box::new(s(HirExpr::Resolved(Res::Local(name)))),
+                    rhs: Box::new(s(HirExpr::Literal(HirLiteral::Null))),
+                })),
+                then: HirBlock {
+                    stmts: Box::new([HirStmt::Expr(s(HirExpr::Call {
+                        callee: Box::new(s(HirExpr::Resolved(Res::Builtin(PRINTLN)))),
+                        args: Box::new([s(HirExpr::NonNullLocal(name))]),
+                    }))]),
+                    tail: None,
+                    span: dummy(),
+                },
+                else_: None,
+            })),
+        ]),
+        tail: None,
+        span: dummy(),
+    };
+    let mut hir = HirMap::new();
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: HirId::from_usize(0),
+        name: SpannedStr::new("main", dummy()),
+        generics: Box::new([]),
+        params: Box::new([HirFunctionParam {
+            name: SpannedStr::new("name", dummy()),
+            ty: nullable(HirPrimitiveTy::Str),
+            span: dummy(),
+        }]),
+        ret_ty: s(HirType::Unit),
+        throws: None,
+        body,
+        span: dummy(),
+    }));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty());
+
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+
+    assert!(result.diags.is_empty());
+    let function = &result.module.functions[0];
+    assert_eq!(function.locals[0].ty, MirTy::Nullable(Box::new(MirTy::Str)));
+
+    let entry = &function.blocks[0].stmts;
+    let MirStmt::Assign { rhs: MirRvalue::WrapNullable(value), .. } = &entry[0] else {
+        panic!("expected wrapped initializer");
+    };
+    assert!(matches!(value, MirOperand::Immediate(MirImmediate::Int { value: 5, .. })));
+    let MirStmt::Assign { rhs: MirRvalue::Binary { op, lhs, rhs }, .. } = &entry[2] else {
+        panic!("expected comparison");
+    };
+    assert_eq!(*op, MirBinOp::Ne);
+    assert_eq!(*lhs, MirOperand::Copy(MirPlace::new(MirLocalId::from_usize(0))));
+    assert_eq!(*rhs, MirOperand::Immediate(MirImmediate::Null));
+
+    let MirTerminator::Branch { then_block, .. } = &function.blocks[0].terminator else {
+        panic!("expected branch");
+    };
+    let MirStmt::Assign { dst, rhs: MirRvalue::UnwrapNullable(operand), .. } =
+        &function.blocks[then_block.to_usize()].stmts[0]
+    else {
+        panic!("expected unwrap");
+    };
+    assert_eq!(*operand, MirOperand::Copy(MirPlace::new(MirLocalId::from_usize(0))));
+    assert_eq!(function.locals[dst.local.to_usize()].ty, MirTy::Str);
+}
+
+#[test]
+fn lower_null_checks_and_narrowed_reads_of_vectors_as_borrows() {
+    let v = HirLocalId::from_usize(0);
+    let len = || {
+        HirStmt::Expr(s(HirExpr::Call {
+            callee: Box::new(s(HirExpr::Field {
+                base: Box::new(s(HirExpr::NonNullLocal(v))),
+                name: SpannedStr::new("len", dummy()),
+            })),
+            args: Box::new([]),
+        }))
+    };
+    let vec_ty = HirType::Builtin {
+        ty: VEC,
+        generics: Box::new([HirGenericArg::Type(s(HirType::Primitive(HirPrimitiveTy::I32)))]),
+    };
+    // `let v: Vec<i32>? = null; if v != null { v.len(); v.len(); }`
+    let body = HirBlock {
+        stmts: Box::new([
+            HirStmt::Let {
+                local: Some(v),
+                name: SpannedStr::new("v", dummy()),
+                is_mutable: false,
+                ty: Some(s(HirType::Nullable(Box::new(s(vec_ty))))),
+                init: Some(s(HirExpr::Literal(HirLiteral::Null))),
+                span: dummy(),
+            },
+            HirStmt::Expr(s(HirExpr::If {
+                cond: Box::new(s(HirExpr::Binary {
+                    op: BinaryOp::Ne,
+                    lhs: Box::new(s(HirExpr::Resolved(Res::Local(v)))),
+                    rhs: Box::new(s(HirExpr::Literal(HirLiteral::Null))),
+                })),
+                then: HirBlock { stmts: Box::new([len(), len()]), tail: None, span: dummy() },
+                else_: None,
+            })),
+        ]),
+        tail: None,
+        span: dummy(),
+    };
+    let mut hir = HirMap::new();
+    hir.push(unit_function_with_body(body));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty(), "{:?}", typeck.diags);
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+
+    // Neither the check nor the narrowed receivers move `v`.
+    assert!(result.diags.is_empty(), "{:?}", result.diags);
+    let function = &result.module.functions[0];
+    let v = MirOperand::Copy(MirPlace::new(MirLocalId::from_usize(0)));
+    let reads: Vec<_> = function
+        .blocks
+        .iter()
+        .flat_map(|block| block.stmts.iter())
+        .filter_map(|stmt| match stmt {
+            MirStmt::Assign { rhs: MirRvalue::Binary { lhs, .. }, .. } => Some(lhs),
+            MirStmt::Assign { rhs: MirRvalue::UnwrapNullable(operand), .. } => Some(operand),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(reads, [&v, &v, &v]);
+}
+
+#[test]
+fn lower_try_to_error_branch_that_returns_the_error() {
+    let str_ty = || s(HirType::Primitive(HirPrimitiveTy::Str));
+    let throwing = |id, name, stmt| {
+        HirItem::Function(HirFunction {
+            vis: Visibility::Private,
+            id: HirId::from_usize(id),
+            name: SpannedStr::new(name, dummy()),
+            generics: Box::new([]),
+            params: Box::new([]),
+            ret_ty: s(HirType::Unit),
+            throws: Some(str_ty()),
+            body: HirBlock { stmts: Box::new([stmt]), tail: None, span: dummy() },
+            span: dummy(),
+        })
+    };
+    let mut hir = HirMap::new();
+    hir.push(throwing(
+        0,
+        "fail",
+        HirStmt::Throw {
+            value: s(HirExpr::Literal(HirLiteral::Str(Cow::Borrowed("failed")))),
+            span: dummy(),
+        },
+    ));
+    hir.push(throwing(
+        1,
+        "relay",
+        HirStmt::Expr(s(HirExpr::Try(Box::new(s(HirExpr::Call {
+            callee: Box::new(s(HirExpr::Resolved(Res::Def(HirId::from_usize(0))))),
+            args: Box::new([]),
+        }))))),
+    ));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty());
+
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+
+    assert!(result.diags.is_empty());
+    let fallible = MirTy::Fallible { ok: Box::new(MirTy::Unit), err: Box::new(MirTy::Str) };
+    let fail = &result.module.functions[0];
+    assert_eq!(fail.ret_ty, fallible);
+    assert!(matches!(
+        fail.blocks[0].stmts[0],
+        MirStmt::Assign { rhs: MirRvalue::WrapErr(MirOperand::Constant(_)), .. }
+    ));
+    assert!(matches!(fail.blocks[0].terminator, MirTerminator::Return(Some(_))));
+
+    let relay = &result.module.functions[1];
+    let entry = &relay.blocks[0].stmts;
+    let MirStmt::Assign { dst: call, rhs: MirRvalue::Call { .. }, .. } = &entry[0] else {
+        panic!("expected call");
+    };
+    assert_eq!(relay.locals[call.local.to_usize()].ty, fallible);
+    assert!(matches!(entry[1], MirStmt::Assign { rhs: MirRvalue::IsErr(_), .. }));
+
+    let MirTerminator::Branch { then_block, else_block, .. } = &relay.blocks[0].terminator else {
+        panic!("expected branch");
+    };
+    let err_block = &relay.blocks[then_block.to_usize()];
+    assert!(matches!(err_block.stmts[0], MirStmt::Assign { rhs: MirRvalue::UnwrapErr(_), .. }));
+    assert!(matches!(err_block.stmts[1], MirStmt::Assign { rhs: MirRvalue::WrapErr(_), .. }));
+    assert!(matches!(err_block.terminator, MirTerminator::Return(Some(_))));
+    assert!(matches!(
+        relay.blocks[else_block.to_usize()].stmts[0],
+        MirStmt::Assign { rhs: MirRvalue::UnwrapOk(_), .. }
+    ));
+}
+
+#[test]
+fn lower_one_function_per_generic_instantiation() {
+    let identity = HirId::from_usize(0);
+    let x = s(HirExpr::Resolved(Res::Local(HirLocalId::from_usize(0))));
+    let mut hir = HirMap::new();
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: identity,
+        name: SpannedStr::new("identity", dummy()),
+        generics: Box::new([HirGenericParam {
+            name: SpannedStr::new("T", dummy()),
+            kind: HirGenericParamKind::Type { bounds: Box::new([]) },
+            span: dummy(),
+        }]),
+        params: Box::new([HirFunctionParam {
+            name: SpannedStr::new("x", dummy()),
+            ty: s(HirType::Param(0)),
+            span: dummy(),
+        }]),
+        ret_ty: s(HirType::Param(0)),
+        throws: None,
+        body: HirBlock { stmts: Box::new([]), tail: Some(Box::new(x)), span: dummy() },
+        span: dummy(),
+    }));
+    let call = |lo, literal| {
+        HirStmt::Expr(s(HirExpr::Call {
+            callee: Box::new(Spanned::new(HirExpr::Resolved(Res::Def(identity)), sp(lo, lo + 1))),
+            args: Box::new([s(HirExpr::Literal(literal))]),
+        }))
+    };
+    hir.push(function_with_body(
+        HirId::from_usize(1),
+        "main",
+        HirBlock {
+            stmts: Box::new([
+                call(1, HirLiteral::Str("a".into())),
+                call(3, HirLiteral::Int { value: 1, suffix: None }),
+                call(5, HirLiteral::Str("b".into())),
+            ]),
+            tail: None,
+            span: dummy(),
+        },
+    ));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty());
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+    assert!(result.diags.is_empty());
+
+    let module = &result.module;
+    assert_eq!(module.instances.len(), 2);
+    let symbols: Vec<_> = module.functions.iter().map(|f| module.symbol(f)).collect();
+    assert_eq!(symbols, ["main", "identity$str", "identity$i32"]);
+    assert_eq!(module.functions[2].params.len(), 1);
+    assert_eq!(
+        module.functions[2].ret_ty,
+        MirTy::Int(MirIntTy { signed: true, bits: TypeBits::B32 })
+    );
+
+    let callees: Vec<_> = module.functions[0].blocks[0]
+        .stmts
+        .iter()
+        .filter_map(|stmt| match stmt {
+            MirStmt::Assign { rhs: MirRvalue::Call { callee, .. }, .. } => Some(*callee),
+            _ => None,
+        })
+        .collect();
+    let instance = |id| MirCallee::Instance(MirInstanceId::from_usize(id));
+    assert_eq!(callees, [instance(0), instance(1), instance(0)]);
+}
+
+/// Lowers `act f<T>(x: T) { f(grow(x)); }` called as `f(1)`, where the
+/// recursive call is at `sp(10, 11)`.
+fn lower_polymorphic_recursion(
+    grow: impl Fn(SpannedHirExpr<'static>) -> SpannedHirExpr<'static>,
+) -> MirLowerResult<'static, 'static> {
+    let f = HirId::from_usize(0);
+    let x = s(HirExpr::Resolved(Res::Local(HirLocalId::from_usize(0))));
+    let call = |span, arg| {
+        HirStmt::Expr(s(HirExpr::Call {
+            callee: Box::new(Spanned::new(HirExpr::Resolved(Res::Def(f)), span)),
+            args: Box::new([arg]),
+        }))
+    };
+    let mut hir = HirMap::new();
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: f,
+        name: SpannedStr::new("f", dummy()),
+        generics: Box::new([HirGenericParam {
+            name: SpannedStr::new("T", dummy()),
+            kind: HirGenericParamKind::Type { bounds: Box::new([]) },
+            span: dummy(),
+        }]),
+        params: Box::new([HirFunctionParam {
+            name: SpannedStr::new("x", dummy()),
+            ty: s(HirType::Param(0)),
+            span: dummy(),
+        }]),
+        ret_ty: s(HirType::Unit),
+        throws: None,
+        body: HirBlock { stmts: Box::new([call(sp(10, 11), grow(x))]), tail: None, span: dummy() },
+        span: dummy(),
+    }));
+    let one = s(HirExpr::Literal(HirLiteral::Int { value: 1, suffix: None }));
+    hir.push(function_with_body(
+        HirId::from_usize(1),
+        "main",
+        HirBlock { stmts: Box::new([call(dummy(), one)]), tail: None, span: dummy() },
+    ));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty(), "{:?}", typeck.diags);
+    MirLowerer::new(&typeck.info).lower(&hir)
+}
+
+#[test]
+fn stop_instantiating_generic_calls_whose_arguments_grow_with_every_call() {
+    // `f((x, x))` doubles the size of `T` with every instance.
+    let result = lower_polymorphic_recursion(|x| s(HirExpr::Tuple(Box::new([x.clone(), x]))));
+    assert_eq!(result.diags.len(), 1, "{:?}", result.diags);
+    assert!(result.diags[0].message.message.contains("more than 1024 types"));
+    assert_eq!(result.diags[0].labels[0].span, sp(10, 11));
+
+    // `f((x,))` only nests `T` one level deeper every time.
+    let result = lower_polymorphic_recursion(|x| s(HirExpr::Tuple(Box::new([x]))));
+    assert_eq!(result.diags.len(), 1, "{:?}", result.diags);
+    assert!(result.diags[0].message.message.contains("more than 64 instances"));
+    assert_eq!(result.diags[0].labels[0].span, sp(10, 11));
+    assert_eq!(result.module.instances.len(), 64);
+}
+
+#[test]
+fn extern_functions_cannot_return_reference_counted_values() {
+    let ret_span = sp(20, 23);
+    let mut hir = HirMap::new();
+    hir.push(HirItem::ExternFunction(HirExternFunction {
+        id: HirId::from_usize(0),
+        vis: Visibility::Private,
+        name: SpannedStr::new("greeting", dummy()),
+        params: Box::new([]),
+        ret_ty: Spanned::new(HirType::Primitive(HirPrimitiveTy::Str), ret_span),
+        span: dummy(),
+    }));
+
+    let typeck = TypeChecker::new().check(&hir);
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+
+    assert_eq!(result.diags.len(), 1);
+    assert_eq!(result.diags[0].labels[0].span, ret_span);
+    assert!(result.module.externs.is_empty());
+}
+
+#[test]
+fn lower_collections_of_managed_values() {
+    let strs = Ty::BuiltinType { id: VEC, args: Box::new([Ty::Str]) };
+    let nested = Ty::BuiltinType { id: VEC, args: Box::new([strs.clone()]) };
+    let by_name = Ty::BuiltinType { id: HASH_MAP, args: Box::new([Ty::Str, strs]) };
+
+    let info = TypeInfo::default();
+    let str_vec = MirTy::Vec(Box::new(MirTy::Str));
+    assert_eq!(lower_ty(&nested, &info), Some(MirTy::Vec(Box::new(str_vec.clone()))));
+    assert_eq!(
+        lower_ty(&by_name, &info),
+        Some(MirTy::Map { key: Box::new(MirTy::Str), value: Box::new(str_vec) })
+    );
+}
+
+#[test]
+fn lower_extern_call_and_deref_to_extern_callee_and_load() {
+    let answer = HirId::from_usize(0);
+    let i64_ty = || s(HirType::Primitive(HirPrimitiveTy::I64));
+    let deref = s(HirExpr::Deref(Box::new(s(HirExpr::Call {
+        callee: Box::new(s(HirExpr::Resolved(Res::Def(answer)))),
+        args: Box::new([]),
+    }))));
+    let body = HirBlock {
+        stmts: Box::new([]),
+        tail: Some(Box::new(s(HirExpr::Unsafe(HirBlock {
+            stmts: Box::new([]),
+            tail: Some(Box::new(deref)),
+            span: dummy(),
+        })))),
+        span: dummy(),
+    };
+
+    let mut hir = HirMap::new();
+    hir.push(HirItem::ExternFunction(HirExternFunction {
+        id: answer,
+        vis: Visibility::Private,
+        name: SpannedStr::new("answer", dummy()),
+        params: Box::new([]),
+        ret_ty: s(HirType::Pointer { mutable: false, pointee: Box::new(i64_ty()) }),
+        span: dummy(),
+    }));
+    hir.push(function_with_ret_ty(HirId::from_usize(1), "read", i64_ty(), body));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty(), "{:?}", typeck.diags);
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+    assert!(result.diags.is_empty());
+
+    let answer = &result.module.externs[0];
+    assert_eq!(answer.name, "answer");
+    assert_eq!(answer.ret_ty, MirTy::Pointer);
+    let read = &result.module.functions[0];
+    let stmts = &read.blocks[read.entry.to_usize()].stmts;
+    let [
+        MirStmt::Assign { rhs: MirRvalue::Call { callee, .. }, .. },
+        MirStmt::Assign { dst, rhs: MirRvalue::Load(MirOperand::Copy(pointer)), .. },
+    ] = stmts.as_slice()
+    else {
+        panic!("expected an extern call followed by a load: {stmts:?}");
+    };
+    assert_eq!(*callee, MirCallee::Extern(MirExternId::from_usize(0)));
+    assert_eq!(read.locals[pointer.local.to_usize()].ty, MirTy::Pointer);
+    assert_eq!(
+        read.locals[dst.local.to_usize()].ty,
+        MirTy::Int(MirIntTy { signed: true, bits: TypeBits::B64 })
+    );
+}
+
+#[test]
+fn lower_assignments_through_pointers_to_stores() {
+    let pointer = |pointee| HirType::Pointer { mutable: true, pointee: Box::new(s(pointee)) };
+    let store = |local, value| HirStmt::Assign {
+        target: s(HirExpr::Deref(Box::new(s(HirExpr::Resolved(Res::Local(
+            HirLocalId::from_usize(local),
+        )))))),
+        value: s(HirExpr::Literal(value)),
+        span: dummy(),
+    };
+    let param = |name, ty| HirFunctionParam {
+        name: SpannedStr::new(name, dummy()),
+        ty: s(ty),
+        span: dummy(),
+    };
+    let mut hir = HirMap::new();
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: HirId::from_usize(0),
+        name: SpannedStr::new("write", dummy()),
+        generics: Box::new([]),
+        params: Box::new([
+            param("p", pointer(HirType::Primitive(HirPrimitiveTy::I64))),
+            param("s", pointer(HirType::Primitive(HirPrimitiveTy::Str))),
+        ]),
+        ret_ty: s(HirType::Unit),
+        throws: None,
+        body: HirBlock {
+            stmts: Box::new([HirStmt::Expr(s(HirExpr::Unsafe(HirBlock {
+                stmts: Box::new([
+                    store(0, HirLiteral::Int { value: 7, suffix: None }),
+                    store(1, HirLiteral::Str("text".into())),
+                ]),
+                tail: None,
+                span: dummy(),
+            })))]),
+            tail: None,
+            span: dummy(),
+        },
+        span: dummy(),
+    }));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty(), "{:?}", typeck.diags);
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+
+    // Strings are reference counted, which memory behind a raw pointer
+    // does not take part in.
+    assert_eq!(result.diags.len(), 1, "{:?}", result.diags);
+    let write = &result.module.functions[0];
+    let stores: Vec<_> = write
+        .blocks
+        .iter()
+        .flat_map(|block| block.stmts.iter())
+        .filter_map(|stmt| match stmt {
+            MirStmt::Store { pointer, value, .. } => Some((pointer.clone(), value.clone())),
+            _ => None,
+        })
+        .collect();
+    let int = MirIntTy { signed: true, bits: TypeBits::B64 };
+    assert_eq!(
+        stores,
+        [(
+            MirOperand::Copy(MirPlace::new(MirLocalId::from_usize(0))),
+            MirOperand::Immediate(MirImmediate::Int { value: 7, ty: int })
+        )]
+    );
+}
+
+#[test]
+fn lower_closures_and_function_items_to_closure_bodies() {
+    let [n, x, f, h] = [0, 1, 2, 3].map(HirLocalId::from_usize);
+    let helper = HirId::from_usize(1);
+    let str_ty = || s(HirType::Primitive(HirPrimitiveTy::Str));
+    let text = |text| s(HirExpr::Literal(HirLiteral::Str(Cow::Borrowed(text))));
+    let let_ = |local, name, init| HirStmt::Let {
+        local: Some(local),
+        name: SpannedStr::new(name, dummy()),
+        is_mutable: true,
+        ty: None,
+        init: Some(init),
+        span: dummy(),
+    };
+    let call = |local, arg| {
+        HirStmt::Expr(s(HirExpr::Call {
+            callee: Box::new(s(HirExpr::Resolved(Res::Local(local)))),
+            args: Box::new([arg]),
+        }))
+    };
+    // `|x: str| { n = x; }`, which captures `n` by reference.
+    let closure = HirExpr::Closure(HirClosure {
+        params: Box::new([HirClosureParam {
+            local: Some(x),
+            name: SpannedStr::new("x", dummy()),
+            ty: Some(str_ty()),
+        }]),
+        body: Box::new(s(HirExpr::Block(HirBlock {
+            stmts: Box::new([HirStmt::Assign {
+                target: s(HirExpr::Resolved(Res::Local(n))),
+                value: s(HirExpr::Resolved(Res::Local(x))),
+                span: dummy(),
+            }]),
+            tail: None,
+            span: dummy(),
+        }))),
+        captures: Box::new([HirCapture { local: n, mode: HirCaptureMode::ByRef }]),
+    });
+    let body = HirBlock {
+        stmts: Box::new([
+            let_(n, "n", text("a")),
+            let_(f, "f", s(closure)),
+            call(f, text("b")),
+            let_(h, "h", s(HirExpr::Resolved(Res::Def(helper)))),
+            call(h, text("c")),
+        ]),
+        tail: None,
+        span: dummy(),
+    };
+
+    let mut hir = HirMap::new();
+    hir.push(unit_function_with_body(body));
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: helper,
+        name: SpannedStr::new("helper", dummy()),
+        generics: Box::new([]),
+        params: Box::new([HirFunctionParam {
+            name: SpannedStr::new("text", dummy()),
+            ty: str_ty(),
+            span: dummy(),
+        }]),
+        ret_ty: s(HirType::Unit),
+        throws: None,
+        body: empty_body(),
+        span: dummy(),
+    }));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty(), "{:?}", typeck.diags);
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+    assert!(result.diags.is_empty(), "{:?}", result.diags);
+
+    let names: Vec<_> =
+        result.module.functions.iter().map(|function| result.module.symbol(function)).collect();
+    assert_eq!(names, ["main", "main$closure0", "helper", "helper$closure1"]);
+
+    let main = &result.module.functions[0];
+    let cell = MirTy::Cell(Box::new(MirTy::Str));
+    assert_eq!(main.locals[0].ty, cell);
+    let rvalues: Vec<_> = main.blocks[main.entry.to_usize()]
+        .stmts
+        .iter()
+        .filter_map(|stmt| match stmt {
+            MirStmt::Assign { rhs, .. } => Some(rhs),
+            _ => None,
+        })
+        .collect();
+    assert!(matches!(rvalues[0], MirRvalue::MakeCell(MirOperand::Constant(_))));
+    assert_eq!(
+        *rvalues[1],
+        MirRvalue::MakeClosure {
+            function: MirCallee::Closure(MirClosureId::from_usize(0)),
+            captures: Box::new([MirOperand::Copy(MirPlace::new(MirLocalId::from_usize(0)))]),
+        }
+    );
+    // `f` can only hold the first closure, so it is called directly.
+    assert!(rvalues.iter().any(|rhs| matches!(
+        rhs,
+        MirRvalue::Call { callee: MirCallee::Closure(closure), .. }
+            if *closure == MirClosureId::from_usize(0)
+    )));
+    assert!(rvalues.iter().any(|rhs| **rhs
+        == MirRvalue::MakeClosure {
+            function: MirCallee::Closure(MirClosureId::from_usize(1)),
+            captures: Box::new([]),
+        }));
+
+    let body = &result.module.functions[1];
+    assert_eq!(body.env, Some(MirStruct { fields: Box::new([cell]) }));
+    assert_eq!(body.params.len(), 2);
+    assert_eq!(body.locals[0].ty, MirTy::Closure);
+    let stmts = &body.blocks[body.entry.to_usize()].stmts;
+    assert!(matches!(stmts[0], MirStmt::Assign { rhs: MirRvalue::Capture(0), .. }));
+    assert!(matches!(stmts[1], MirStmt::StoreCell { .. }));
+
+    let shim = &result.module.functions[3];
+    assert_eq!(shim.params.len(), 2);
+    let [MirStmt::Assign { rhs: MirRvalue::Call { callee, .. }, .. }] =
+        shim.blocks[shim.entry.to_usize()].stmts.as_slice()
+    else {
+        panic!("expected the shim to forward to `helper`");
+    };
+    assert_eq!(*callee, MirCallee::Function(helper));
+}
+
+/// `act main(v: Vec<i32>) { let f = || { v.len() }; let w = v; }`, or
+/// `act main(v: Vec<i32>) { let f = || { let w = v; }; }` when `move_in_body`
+/// is set.
+fn lower_vector_capture(move_in_body: bool) -> MirLowerResult<'static, 'static> {
+    let v = HirLocalId::from_usize(0);
+    // Locals are numbered in the order they are declared in.
+    let [f, w] = if move_in_body { [2, 1] } else { [1, 2] }.map(HirLocalId::from_usize);
+    let let_ = |local, name, init, span| HirStmt::Let {
+        local: Some(local),
+        name: SpannedStr::new(name, dummy()),
+        is_mutable: false,
+        ty: None,
+        init: Some(init),
+        span,
+    };
+    let len = s(HirExpr::Call {
+        callee: Box::new(s(HirExpr::Field {
+            base: Box::new(s(HirExpr::Resolved(Res::Local(v)))),
+            name: SpannedStr::new("len", dummy()),
+        })),
+        args: Box::new([]),
+    });
+    let moved = || Spanned::new(HirExpr::Resolved(Res::Local(v)), sp(40, 41));
+    let body = if move_in_body {
+        HirBlock { stmts: Box::new([let_(w, "w", moved(), sp(32, 42))]), tail: None, span: dummy() }
+    } else {
+        HirBlock { stmts: Box::new([]), tail: Some(Box::new(len)), span: dummy() }
+    };
+    let closure = Spanned::new(
+        HirExpr::Closure(HirClosure {
+            params: Box::new([]),
+            body: Box::new(s(HirExpr::Block(body))),
+            captures: Box::new([HirCapture { local: v, mode: HirCaptureMode::ByValue }]),
+        }),
+        sp(10, 50),
+    );
+    let mut stmts = vec![let_(f, "f", closure, sp(0, 51))];
+    if !move_in_body {
+        stmts.push(let_(w, "w", moved(), sp(52, 62)));
+    }
+
+    let mut hir = HirMap::new();
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: HirId::from_usize(0),
+        name: SpannedStr::new("main", dummy()),
+        generics: Box::new([]),
+        params: Box::new([HirFunctionParam {
+            name: SpannedStr::new("v", dummy()),
+            ty: s(HirType::Builtin {
+                ty: VEC,
+                generics: Box::new([HirGenericArg::Type(s(HirType::Primitive(
+                    HirPrimitiveTy::I32,
+                )))]),
+            }),
+            span: dummy(),
+        }]),
+        ret_ty: s(HirType::Unit),
+        throws: None,
+        body: HirBlock { stmts: stmts.into_boxed_slice(), tail: None, span: dummy() },
+        span: dummy(),
+    }));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty(), "{:?}", typeck.diags);
+    MirLowerer::new(&typeck.info).lower(&hir)
+}
+
+#[test]
+fn move_vectors_into_the_closures_that_capture_them() {
+    let result = lower_vector_capture(false);
+
+    assert_eq!(result.diags.len(), 1);
+    let diag = &result.diags[0];
+    assert_eq!(diag.message.message, "use of moved value: `v`");
+    assert_eq!(diag.labels[0].span, sp(52, 62));
+    assert_eq!(diag.labels[1].span, sp(10, 50));
+}
+
+#[test]
+fn moving_a_captured_vector_out_of_its_closure_is_reported() {
+    let result = lower_vector_capture(true);
+
+    assert_eq!(result.diags.len(), 1);
+    let diag = &result.diags[0];
+    assert_eq!(diag.message.message, "cannot move captured value `v` out of its closure");
+    assert_eq!(diag.labels[0].span, sp(40, 41));
+}
+
+/// `act main(n: i32) { for i in 0..=n {} let r = 0..n; for j in r {} }`
+#[test]
+fn lower_for_loops_over_ranges_to_counted_and_next_loops() {
+    let n = HirLocalId::from_usize(0);
+    let range = |inclusive| {
+        s(HirExpr::Range {
+            start: Box::new(s(HirExpr::Literal(HirLiteral::Int { value: 0, suffix: None }))),
+            end: Box::new(s(HirExpr::Resolved(Res::Local(n)))),
+            inclusive,
+        })
+    };
+    let for_loop = |local, name, iter| {
+        HirStmt::Expr(s(HirExpr::For {
+            local: Some(HirLocalId::from_usize(local)),
+            name: SpannedStr::new(name, dummy()),
+            iter: Box::new(iter),
+            body: empty_body(),
+        }))
+    };
+    let body = HirBlock {
+        stmts: Box::new([
+            for_loop(1, "i", range(true)),
+            HirStmt::Let {
+                local: Some(HirLocalId::from_usize(2)),
+                name: SpannedStr::new("r", dummy()),
+                is_mutable: false,
+                ty: None,
+                init: Some(range(false)),
+                span: dummy(),
+            },
+            for_loop(3, "j", s(HirExpr::Resolved(Res::Local(HirLocalId::from_usize(2))))),
+        ]),
+        tail: None,
+        span: dummy(),
+    };
+    let mut hir = HirMap::new();
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: HirId::from_usize(0),
+        name: SpannedStr::new("main", dummy()),
+        generics: Box::new([]),
+        params: Box::new([HirFunctionParam {
+            name: SpannedStr::new("n", dummy()),
+            ty: s(HirType::Primitive(HirPrimitiveTy::I32)),
+            span: dummy(),
+        }]),
+        ret_ty: s(HirType::Unit),
+        throws: None,
+        body,
+        span: dummy(),
+    }));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty(), "{:?}", typeck.diags);
+
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+
+    assert!(result.diags.is_empty(), "{:?}", result.diags);
+    let function = &result.module.functions[0];
+    let i32_ty = MirIntTy { signed: true, bits: TypeBits::B32 };
+    let stmts = || function.blocks.iter().flat_map(|block| block.stmts.iter());
+
+    // The inclusive loop compares with `<=` and stops at the end before
+    // stepping.
+    let ops = stmts()
+        .filter_map(|stmt| match stmt {
+            MirStmt::Assign { rhs: MirRvalue::Binary { op, .. }, .. } => Some(*op),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(ops, [MirBinOp::Le, MirBinOp::Eq, MirBinOp::Add, MirBinOp::Eq]);
+
+    // The stored range is advanced with `next()` until it yields `null`.
+    let ranges = stmts()
+        .filter_map(|stmt| match stmt {
+            MirStmt::Assign { dst, rhs: MirRvalue::MakeRange { inclusive, .. }, .. } => {
+                Some((&function.locals[dst.local.to_usize()].ty, *inclusive))
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(ranges, [(&MirTy::Range(i32_ty), false)]);
+    let Some(MirStmt::Next { dst, .. }) = stmts().find(|stmt| matches!(stmt, MirStmt::Next { .. }))
+    else {
+        panic!("expected next");
+    };
+    assert_eq!(
+        function.locals[dst.local.to_usize()].ty,
+        MirTy::Nullable(Box::new(MirTy::Int(i32_ty)))
+    );
+    assert!(function.locals.iter().any(|local| local.name == Some("j")));
+}
+
+#[test]
+fn lower_break_to_a_jump_to_the_exit_of_its_loop() {
+    let range = s(HirExpr::Range {
+        start: Box::new(s(HirExpr::Literal(HirLiteral::Int { value: 0, suffix: None }))),
+        end: Box::new(s(HirExpr::Literal(HirLiteral::Int { value: 3, suffix: None }))),
+        inclusive: false,
+    });
+    let body = HirBlock {
+        stmts: Box::new([HirStmt::Expr(s(HirExpr::For {
+            local: Some(HirLocalId::from_usize(0)),
+            name: SpannedStr::new("i", dummy()),
+            iter: Box::new(range),
+            body: HirBlock {
+                stmts: Box::new([HirStmt::Break { span: dummy() }]),
+                tail: None,
+                span: dummy(),
+            },
+        }))]),
+        tail: None,
+        span: dummy(),
+    };
+    let mut hir = HirMap::new();
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: HirId::from_usize(0),
+        name: SpannedStr::new("main", dummy()),
+        generics: Box::new([]),
+        params: Box::new([]),
+        ret_ty: s(HirType::Unit),
+        throws: None,
+        body,
+        span: dummy(),
+    }));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty(), "{:?}", typeck.diags);
+
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+
+    assert!(result.diags.is_empty(), "{:?}", result.diags);
+    let function = &result.module.functions[0];
+    let Some(exit) = function.blocks.iter().find_map(|block| match block.terminator {
+        MirTerminator::Branch { else_block, .. } => Some(else_block),
+        _ => None,
+    }) else {
+        panic!("expected the loop condition");
+    };
+    assert!(
+        function
+            .blocks
+            .iter()
+            .any(|block| matches!(block.terminator, MirTerminator::Goto(target) if target == exit))
+    );
+}
+
+#[test]
+fn lower_for_loops_over_lines_to_calls_of_their_runtime_next() {
+    let reader = HirLocalId::from_usize(0);
+    let lines = s(HirExpr::Call {
+        callee: Box::new(s(HirExpr::Field {
+            base: Box::new(s(HirExpr::Resolved(Res::Local(reader)))),
+            name: SpannedStr::new("lines", dummy()),
+        })),
+        args: Box::new([]),
+    });
+    let body = HirBlock {
+        stmts: Box::new([
+            HirStmt::Let {
+                local: Some(reader),
+                name: SpannedStr::new("reader", dummy()),
+                is_mutable: false,
+                ty: None,
+                init: Some(s(HirExpr::Call {
+                    callee: Box::new(s(HirExpr::Resolved(Res::Builtin(STDIN_READER)))),
+                    args: Box::new([]),
+                })),
+                span: dummy(),
+            },
+            HirStmt::Expr(s(HirExpr::For {
+                local: Some(HirLocalId::from_usize(1)),
+                name: SpannedStr::new("line", dummy()),
+                iter: Box::new(lines),
+                body: empty_body(),
+            })),
+        ]),
+        tail: None,
+        span: dummy(),
+    };
+    let mut hir = HirMap::new();
+    hir.push(unit_function_with_body(body));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty(), "{:?}", typeck.diags);
+
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+
+    assert!(result.diags.is_empty(), "{:?}", result.diags);
+    let function = &result.module.functions[0];
+    let stmts = || function.blocks.iter().flat_map(|block| block.stmts.iter());
+    assert!(!stmts().any(|stmt| matches!(stmt, MirStmt::Next { .. })));
+    let runtime_calls: Vec<_> = stmts()
+        .filter_map(|stmt| match stmt {
+            MirStmt::Assign {
+                rhs: MirRvalue::Call { callee: MirCallee::Runtime(id), .. }, ..
+            } => Some(*id),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(runtime_calls, [RUNTIME_READER_STDIN, RUNTIME_READER_LINES, RUNTIME_LINES_NEXT]);
+    let line = function.locals.iter().find(|local| local.name == Some("line")).unwrap();
+    assert_eq!(line.ty, MirTy::Str);
+}
+
+#[test]
+fn lower_vec_methods_and_indexing_to_intrinsics_that_borrow_the_vector() {
+    let v = HirLocalId::from_usize(0);
+    let local = |id| s(HirExpr::Resolved(Res::Local(id)));
+    let int = |value| s(HirExpr::Literal(HirLiteral::Int { value, suffix: None }));
+    let method =
+        |name| s(HirExpr::Field { base: Box::new(local(v)), name: SpannedStr::new(name, dummy()) });
+    let call = |callee, args: Vec<_>| {
+        s(HirExpr::Call { callee: Box::new(callee), args: args.into_boxed_slice() })
+    };
+    let index = |index| s(HirExpr::Index { base: Box::new(local(v)), index: Box::new(index) });
+    let let_stmt = |id, name, ty, init| HirStmt::Let {
+        local: Some(HirLocalId::from_usize(id)),
+        name: SpannedStr::new(name, dummy()),
+        is_mutable: id == 0,
+        ty,
+        init: Some(init),
+        span: dummy(),
+    };
+    let vec_ty = HirType::Builtin {
+        ty: VEC,
+        generics: Box::new([HirGenericArg::Type(s(HirType::Primitive(HirPrimitiveTy::I32)))]),
+    };
+    let range =
+        s(HirExpr::Range { start: Box::new(int(0)), end: Box::new(int(1)), inclusive: true });
+    let body = HirBlock {
+        stmts: Box::new([
+            let_stmt(
+                0,
+                "v",
+                Some(s(vec_ty)),
+                call(s(HirExpr::Resolved(Res::Builtin(VEC_NEW))), vec![]),
+            ),
+            HirStmt::Expr(call(method("push"), vec![int(7)])),
+            HirStmt::Assign { target: index(int(0)), value: int(8), span: dummy() },
+            let_stmt(1, "first", None, index(int(0))),
+            let_stmt(2, "both", None, index(range)),
+            let_stmt(3, "last", None, call(method("pop"), vec![])),
+        ]),
+        tail: None,
+        span: dummy(),
+    };
+    let mut hir = HirMap::new();
+    hir.push(unit_function_with_body(body));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty(), "{:?}", typeck.diags);
+
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+
+    assert!(result.diags.is_empty(), "{:?}", result.diags);
+    let function = &result.module.functions[0];
+    let i32_ty = MirTy::Int(MirIntTy { signed: true, bits: TypeBits::B32 });
+    let vec_local = MirLocalId::from_usize(0);
+    assert_eq!(function.locals[vec_local.to_usize()].ty, MirTy::Vec(Box::new(i32_ty.clone())));
+
+    let stmts = function.blocks.iter().flat_map(|block| block.stmts.iter());
+    let mut lowered = Vec::new();
+    for stmt in stmts {
+        match stmt {
+            MirStmt::Assign { rhs: MirRvalue::Intrinsic { intrinsic, args }, .. } => {
+                lowered.push(format!("{intrinsic:?}"));
+                // Method receivers are borrowed, so `v` stays usable.
+                if let Some(receiver) = args.first() {
+                    assert_eq!(*receiver, MirOperand::Copy(MirPlace::new(vec_local)));
+                }
+            }
+            MirStmt::Assign { dst, rhs: MirRvalue::Index { base, .. }, .. } => {
+                assert_eq!(*base, MirOperand::Copy(MirPlace::new(vec_local)));
+                assert_eq!(function.locals[dst.local.to_usize()].ty, i32_ty);
+                lowered.push("Index".to_string());
+            }
+            MirStmt::Assign { dst, rhs: MirRvalue::Slice { .. }, .. } => {
+                let ty = &function.locals[dst.local.to_usize()].ty;
+                assert_eq!(*ty, MirTy::Slice(Box::new(i32_ty.clone())));
+                lowered.push("Slice".to_string());
+            }
+            MirStmt::StoreIndex { .. } => lowered.push("StoreIndex".to_string()),
+            _ => {}
+        }
+    }
+    assert_eq!(lowered, ["VecNew", "VecPush", "StoreIndex", "Index", "Slice", "VecPop"]);
+}
+
+#[test]
+fn lower_set_methods_and_walk_its_elements_by_index() {
+    let set = HirLocalId::from_usize(0);
+    let int = |value| s(HirExpr::Literal(HirLiteral::Int { value, suffix: None }));
+    let method = |name| {
+        s(HirExpr::Field {
+            base: Box::new(s(HirExpr::Resolved(Res::Local(set)))),
+            name: SpannedStr::new(name, dummy()),
+        })
+    };
+    let call = |callee, args: Vec<_>| {
+        s(HirExpr::Call { callee: Box::new(callee), args: args.into_boxed_slice() })
+    };
+    let set_ty = HirType::Builtin {
+        ty: HASH_SET,
+        generics: Box::new([HirGenericArg::Type(s(HirType::Primitive(HirPrimitiveTy::I64)))]),
+    };
+    let body = HirBlock {
+        stmts: Box::new([
+            HirStmt::Let {
+                local: Some(set),
+                name: SpannedStr::new("seen", dummy()),
+                is_mutable: true,
+                ty: Some(s(set_ty)),
+                init: Some(call(s(HirExpr::Resolved(Res::Builtin(SET_NEW))), vec![])),
+                span: dummy(),
+            },
+            HirStmt::Expr(call(method("insert"), vec![int(3)])),
+            HirStmt::Expr(s(HirExpr::For {
+                local: Some(HirLocalId::from_usize(1)),
+                name: SpannedStr::new("item", dummy()),
+                iter: Box::new(call(method("to_vec"), vec![])),
+                body: HirBlock { stmts: Box::new([]), tail: None, span: dummy() },
+            })),
+        ]),
+        tail: None,
+        span: dummy(),
+    };
+    let mut hir = HirMap::new();
+    hir.push(unit_function_with_body(body));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty(), "{:?}", typeck.diags);
+
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+
+    assert!(result.diags.is_empty(), "{:?}", result.diags);
+    let function = &result.module.functions[0];
+    let i64_ty = MirTy::Int(MirIntTy { signed: true, bits: TypeBits::B64 });
+    assert_eq!(
+        function.locals[0].ty,
+        MirTy::Map { key: Box::new(i64_ty.clone()), value: Box::new(MirTy::Unit) }
+    );
+
+    let mut lowered = Vec::new();
+    for stmt in function.blocks.iter().flat_map(|block| block.stmts.iter()) {
+        match stmt {
+            MirStmt::Assign { rhs: MirRvalue::Intrinsic { intrinsic, .. }, .. } => {
+                lowered.push(format!("{intrinsic:?}"));
+            }
+            MirStmt::Assign { dst, rhs: MirRvalue::Index { base, .. }, .. } => {
+                let MirOperand::Copy(place) = base else {
+                    panic!("the walked vector is borrowed: {base:?}");
+                };
+                assert_eq!(
+                    function.locals[place.local.to_usize()].ty,
+                    MirTy::Vec(Box::new(i64_ty.clone()))
+                );
+                assert_eq!(function.locals[dst.local.to_usize()].ty, i64_ty);
+                lowered.push("Index".to_string());
+            }
+            _ => {}
+        }
+    }
+    assert_eq!(lowered, ["MapNew", "SetInsert", "MapKeys", "Len", "Index"]);
+}
+
+#[test]
+fn lower_input_to_a_nullable_read_of_the_returned_slot() {
+    let body = HirBlock {
+        stmts: Box::new([HirStmt::Let {
+            local: Some(HirLocalId::from_usize(0)),
+            name: SpannedStr::new("line", dummy()),
+            is_mutable: false,
+            ty: None,
+            init: Some(s(HirExpr::Call {
+                callee: Box::new(s(HirExpr::Resolved(Res::Builtin(INPUT)))),
+                args: Box::new([s(HirExpr::Literal(HirLiteral::Str("> ".into())))]),
+            })),
+            span: dummy(),
+        }]),
+        tail: None,
+        span: dummy(),
+    };
+    let mut hir = HirMap::new();
+    hir.push(unit_function_with_body(body));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty(), "{:?}", typeck.diags);
+
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+
+    assert!(result.diags.is_empty(), "{:?}", result.diags);
+    let function = &result.module.functions[0];
+    assert_eq!(function.locals[0].ty, MirTy::Nullable(Box::new(MirTy::Str)));
+
+    let mut lowered = Vec::new();
+    for stmt in function.blocks.iter().flat_map(|block| block.stmts.iter()) {
+        let MirStmt::Assign { dst, rhs, .. } = stmt else {
+            continue;
+        };
+        let dst_ty = &function.locals[dst.local.to_usize()].ty;
+        match rhs {
+            MirRvalue::Call { callee: MirCallee::Runtime(id), .. } => {
+                assert_eq!(*id, RUNTIME_INPUT);
+                assert_eq!(*dst_ty, MirTy::Pointer);
+                lowered.push("Call");
+            }
+            MirRvalue::Load(_) => {
+                assert_eq!(*dst_ty, MirTy::Str);
+                lowered.push("Load");
+            }
+            MirRvalue::WrapNullable(_) => lowered.push("WrapNullable"),
+            _ => {}
+        }
+    }
+    assert_eq!(lowered, ["Call", "Load", "WrapNullable"]);
+}
+
+#[test]
+fn lower_throwing_builtin_to_a_fallible_loaded_from_the_returned_slot() {
+    let mut hir = HirMap::new();
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: HirId::from_usize(0),
+        name: SpannedStr::new("list", dummy()),
+        generics: Box::new([]),
+        params: Box::new([]),
+        ret_ty: s(HirType::Unit),
+        throws: Some(s(HirType::Primitive(HirPrimitiveTy::Str))),
+        body: HirBlock {
+            stmts: Box::new([HirStmt::Let {
+                local: Some(HirLocalId::from_usize(0)),
+                name: SpannedStr::new("names", dummy()),
+                is_mutable: false,
+                ty: None,
+                init: Some(s(HirExpr::Try(Box::new(s(HirExpr::Call {
+                    callee: Box::new(s(HirExpr::Resolved(Res::Builtin(FILES_LIST_DIR)))),
+                    args: Box::new([s(HirExpr::Literal(HirLiteral::Str(".".into())))]),
+                }))))),
+                span: dummy(),
+            }]),
+            tail: None,
+            span: dummy(),
+        },
+        span: dummy(),
+    }));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty(), "{:?}", typeck.diags);
+
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+
+    assert!(result.diags.is_empty(), "{:?}", result.diags);
+    let function = &result.module.functions[0];
+    let names = MirTy::Vec(Box::new(MirTy::Str));
+    assert_eq!(function.locals[0].ty, names);
+    let entry = &function.blocks[0].stmts;
+    let MirStmt::Assign {
+        dst: slot,
+        rhs: MirRvalue::Call { callee: MirCallee::Runtime(RUNTIME_LIST_DIR), .. },
+        ..
+    } = &entry[0]
+    else {
+        panic!("expected runtime call: {:?}", entry[0]);
+    };
+    assert_eq!(function.locals[slot.local.to_usize()].ty, MirTy::Pointer);
+    let MirStmt::Assign { dst: result, rhs: MirRvalue::Load(MirOperand::Copy(place)), .. } =
+        &entry[1]
+    else {
+        panic!("expected load: {:?}", entry[1]);
+    };
+    assert_eq!(place, slot);
+    assert_eq!(
+        function.locals[result.local.to_usize()].ty,
+        MirTy::Fallible { ok: Box::new(names), err: Box::new(MirTy::Str) }
+    );
+    assert!(matches!(entry[2], MirStmt::Assign { rhs: MirRvalue::IsErr(_), .. }));
+}
+
+#[test]
+fn lower_runtime_method_with_its_receiver_as_the_first_argument() {
+    let let_stmt = |id, init| HirStmt::Let {
+        local: Some(HirLocalId::from_usize(id)),
+        name: SpannedStr::new("t", dummy()),
+        is_mutable: false,
+        ty: None,
+        init: Some(s(init)),
+        span: dummy(),
+    };
+    let mut hir = HirMap::new();
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: HirId::from_usize(0),
+        name: SpannedStr::new("main", dummy()),
+        generics: Box::new([]),
+        params: Box::new([]),
+        ret_ty: s(HirType::Unit),
+        throws: None,
+        body: HirBlock {
+            stmts: Box::new([
+                let_stmt(
+                    0,
+                    HirExpr::Call {
+                        callee: Box::new(s(HirExpr::Resolved(Res::Builtin(INSTANT_NOW)))),
+                        args: Box::new([]),
+                    },
+                ),
+                let_stmt(
+                    1,
+                    HirExpr::Call {
+                        callee: Box::new(s(HirExpr::Field {
+                            base: Box::new(s(HirExpr::Resolved(Res::Local(
+                                HirLocalId::from_usize(0),
+                            )))),
+                            name: SpannedStr::new("elapsed", dummy()),
+                        })),
+                        args: Box::new([]),
+                    },
+                ),
+            ]),
+            tail: None,
+            span: dummy(),
+        },
+        span: dummy(),
+    }));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty(), "{:?}", typeck.diags);
+
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+
+    assert!(result.diags.is_empty(), "{:?}", result.diags);
+    let function = &result.module.functions[0];
+    let nanos = MirTy::Int(MirIntTy { signed: false, bits: TypeBits::B64 });
+    assert_eq!(function.locals[0].ty, nanos);
+    assert_eq!(function.locals[1].ty, nanos);
+    let calls: Vec<_> = function.blocks[0]
+        .stmts
+        .iter()
+        .filter_map(|stmt| match stmt {
+            MirStmt::Assign {
+                rhs: MirRvalue::Call { callee: MirCallee::Runtime(id), args },
+                ..
+            } => Some((*id, args.clone())),
+            _ => None,
+        })
+        .collect();
+    let start = MirPlace::new(MirLocalId::from_usize(0));
+    assert_eq!(
+        calls,
+        [
+            (RUNTIME_INSTANT_NOW, Box::from([])),
+            (RUNTIME_INSTANT_ELAPSED, Box::from([MirOperand::Copy(start)])),
+        ]
+    );
+}
+
+#[test]
+fn lower_shuffles_of_vectors_and_slices_as_borrows() {
+    let vec_ty = HirType::Builtin {
+        ty: VEC,
+        generics: Box::new([HirGenericArg::Type(s(HirType::Primitive(HirPrimitiveTy::I64)))]),
+    };
+    let vec = || s(HirExpr::Resolved(Res::Local(HirLocalId::from_usize(0))));
+    let shuffle = |arg| {
+        HirStmt::Expr(s(HirExpr::Call {
+            callee: Box::new(s(HirExpr::Resolved(Res::Builtin(RANDOM_SHUFFLE)))),
+            args: Box::new([arg]),
+        }))
+    };
+    let int = |value| s(HirExpr::Literal(HirLiteral::Int { value, suffix: None }));
+    let slice = s(HirExpr::Index {
+        base: Box::new(vec()),
+        index: Box::new(s(HirExpr::Range {
+            start: Box::new(int(0)),
+            end: Box::new(int(1)),
+            inclusive: false,
+        })),
+    });
+    let body = HirBlock {
+        stmts: Box::new([
+            HirStmt::Let {
+                local: Some(HirLocalId::from_usize(0)),
+                name: SpannedStr::new("v", dummy()),
+                is_mutable: false,
+                ty: Some(s(vec_ty)),
+                init: Some(s(HirExpr::Call {
+                    callee: Box::new(s(HirExpr::Resolved(Res::Builtin(VEC_NEW)))),
+                    args: Box::new([]),
+                })),
+                span: dummy(),
+            },
+            shuffle(vec()),
+            shuffle(vec()),
+            shuffle(slice),
+        ]),
+        tail: None,
+        span: dummy(),
+    };
+    let mut hir = HirMap::new();
+    hir.push(unit_function_with_body(body));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty(), "{:?}", typeck.diags);
+
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+
+    assert!(result.diags.is_empty(), "{:?}", result.diags);
+    let function = &result.module.functions[0];
+    let shuffled: Vec<_> = function.blocks[0]
+        .stmts
+        .iter()
+        .filter_map(|stmt| match stmt {
+            MirStmt::Assign {
+                rhs: MirRvalue::Intrinsic { intrinsic: Intrinsic::Shuffle, args },
+                ..
+            } => match &args[..] {
+                [arg] => Some((matches!(arg, MirOperand::Copy(_)), function.operand_ty(arg)?)),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    // `v` stays owned by `main`, so it can be shuffled again, and so can a
+    // slice of it.
+    let i64_ty = MirTy::Int(MirIntTy { signed: true, bits: TypeBits::B64 });
+    assert_eq!(
+        shuffled,
+        [
+            (true, MirTy::Vec(Box::new(i64_ty.clone()))),
+            (true, MirTy::Vec(Box::new(i64_ty.clone()))),
+            (true, MirTy::Slice(Box::new(i64_ty))),
+        ]
+    );
+}
+
+#[test]
+fn lower_indirect_builtin_results_as_loads_from_the_returned_pointer() {
+    let call = |id, args: Box<[_]>| {
+        s(HirExpr::Call { callee: Box::new(s(HirExpr::Resolved(Res::Builtin(id)))), args })
+    };
+    let let_stmt = |id, init| HirStmt::Let {
+        local: Some(HirLocalId::from_usize(id)),
+        name: SpannedStr::new("d", dummy()),
+        is_mutable: false,
+        ty: None,
+        init: Some(init),
+        span: dummy(),
+    };
+    let one = s(HirExpr::Literal(HirLiteral::Int { value: 1, suffix: None }));
+    let decimal = s(HirExpr::Resolved(Res::Local(HirLocalId::from_usize(0))));
+    let body = HirBlock {
+        stmts: Box::new([
+            let_stmt(0, call(DECIMAL_FROM_INT, Box::new([one]))),
+            let_stmt(1, call(DECIMAL_TO_STRING, Box::new([decimal]))),
+        ]),
+        tail: None,
+        span: dummy(),
+    };
+    let mut hir = HirMap::new();
+    hir.push(unit_function_with_body(body));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty(), "{:?}", typeck.diags);
+
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+
+    assert!(result.diags.is_empty(), "{:?}", result.diags);
+    let function = &result.module.functions[0];
+    assert_eq!(function.locals[0].ty, MirTy::Managed);
+    let entry = &function.blocks[0].stmts;
+    let call = entry
+        .iter()
+        .position(|stmt| {
+            matches!(
+                stmt,
+                MirStmt::Assign {
+                    rhs: MirRvalue::Call {
+                        callee: MirCallee::Runtime(RUNTIME_DECIMAL_TO_STRING),
+                        ..
+                    },
+                    ..
+                }
+            )
+        })
+        .expect("to_string call");
+    let MirStmt::Assign { dst: slot, .. } = &entry[call] else { unreachable!() };
+    assert_eq!(function.locals[slot.local.to_usize()].ty, MirTy::Pointer);
+    let MirStmt::Assign { dst: text, rhs: MirRvalue::Load(MirOperand::Copy(place)), .. } =
+        &entry[call + 1]
+    else {
+        panic!("expected load: {:?}", entry[call + 1]);
+    };
+    assert_eq!(place, slot);
+    assert_eq!(function.locals[text.local.to_usize()].ty, MirTy::Str);
+}
+
+#[test]
+fn lower_decimal_operators_to_method_calls_and_display_through_to_string() {
+    let d = || s(HirExpr::Resolved(Res::Local(HirLocalId::from_usize(0))));
+    let body = HirBlock {
+        stmts: Box::new([
+            HirStmt::Let {
+                local: Some(HirLocalId::from_usize(0)),
+                name: SpannedStr::new("d", dummy()),
+                is_mutable: false,
+                ty: None,
+                init: Some(s(HirExpr::Call {
+                    callee: Box::new(s(HirExpr::Resolved(Res::Builtin(DECIMAL_FROM_INT)))),
+                    args: Box::new([s(HirExpr::Literal(HirLiteral::Int {
+                        value: 1,
+                        suffix: None,
+                    }))]),
+                })),
+                span: dummy(),
+            },
+            HirStmt::Expr(s(HirExpr::Call {
+                callee: Box::new(s(HirExpr::Resolved(Res::Builtin(PRINTLN)))),
+                args: Box::new([s(HirExpr::Binary {
+                    op: BinaryOp::Add,
+                    lhs: Box::new(d()),
+                    rhs: Box::new(d()),
+                })]),
+            })),
+        ]),
+        tail: None,
+        span: dummy(),
+    };
+    let mut hir = HirMap::new();
+    hir.push(unit_function_with_body(body));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty(), "{:?}", typeck.diags);
+
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+
+    assert!(result.diags.is_empty(), "{:?}", result.diags);
+    let calls: Vec<_> = result.module.functions[0].blocks[0]
+        .stmts
+        .iter()
+        .filter_map(|stmt| match stmt {
+            MirStmt::Assign { dst, rhs: MirRvalue::Call { callee, args }, .. } => {
+                Some((dst.local, *callee, args.clone()))
+            }
+            _ => None,
+        })
+        .collect();
+    let [_, (sum, add, add_args), (_, to_string, to_string_args), (_, println, _)] = &calls[..]
+    else {
+        panic!("expected from_int, add, to_string and println calls: {calls:?}");
+    };
+    let copy = |local| MirOperand::Copy(MirPlace::new(local));
+    assert_eq!(*add, MirCallee::Runtime(RUNTIME_DECIMAL_ADD));
+    // Both operands are borrowed, so `d` is still owned afterwards.
+    assert_eq!(add_args[..], [copy(MirLocalId::from_usize(0)), copy(MirLocalId::from_usize(0))]);
+    assert_eq!(*to_string, MirCallee::Runtime(RUNTIME_DECIMAL_TO_STRING));
+    assert_eq!(to_string_args[..], [copy(*sum)]);
+    assert_eq!(*println, MirCallee::Runtime(RUNTIME_PRINTLN));
+}
+
+#[test]
+fn lower_decimal_and_string_comparisons_to_orderings_compared_with_zero() {
+    let d = || s(HirExpr::Resolved(Res::Local(HirLocalId::from_usize(0))));
+    // Spans of their own keep the strings apart from the integer literal.
+    let str = |text: &'static str, lo| {
+        Spanned::new(HirExpr::Literal(HirLiteral::Str(text.into())), sp(lo, lo + 3))
+    };
+    let compare = |op, lhs, rhs| {
+        HirStmt::Expr(s(HirExpr::Binary { op, lhs: Box::new(lhs), rhs: Box::new(rhs) }))
+    };
+    let body = HirBlock {
+        stmts: Box::new([
+            HirStmt::Let {
+                local: Some(HirLocalId::from_usize(0)),
+                name: SpannedStr::new("d", dummy()),
+                is_mutable: false,
+                ty: None,
+                init: Some(s(HirExpr::Call {
+                    callee: Box::new(s(HirExpr::Resolved(Res::Builtin(DECIMAL_FROM_INT)))),
+                    args: Box::new([s(HirExpr::Literal(HirLiteral::Int {
+                        value: 1,
+                        suffix: None,
+                    }))]),
+                })),
+                span: dummy(),
+            },
+            compare(BinaryOp::Gt, d(), d()),
+            compare(BinaryOp::Le, str("a", 1), str("b", 5)),
+        ]),
+        tail: None,
+        span: dummy(),
+    };
+    let mut hir = HirMap::new();
+    hir.push(unit_function_with_body(body));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty(), "{:?}", typeck.diags);
+
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+
+    assert!(result.diags.is_empty(), "{:?}", result.diags);
+    let stmts = &result.module.functions[0].blocks[0].stmts;
+    let orderings: Vec<_> = stmts
+        .iter()
+        .filter_map(|stmt| match stmt {
+            MirStmt::Assign { dst, rhs: MirRvalue::Call { callee, args }, .. }
+                if *callee == MirCallee::Runtime(RUNTIME_DECIMAL_CMP) =>
+            {
+                Some((dst.local, args.clone()))
+            }
+            MirStmt::Assign { dst, rhs: MirRvalue::Intrinsic { intrinsic, args }, .. } => {
+                assert_eq!(*intrinsic, Intrinsic::StrCmp);
+                Some((dst.local, args.clone()))
+            }
+            _ => None,
+        })
+        .collect();
+    let comparisons: Vec<_> = stmts
+        .iter()
+        .filter_map(|stmt| match stmt {
+            MirStmt::Assign { rhs: MirRvalue::Binary { op, lhs, rhs }, .. } => {
+                Some((*op, lhs.clone(), rhs.clone()))
+            }
+            _ => None,
+        })
+        .collect();
+    let [(decimal, decimal_args), (string, string_args)] = &orderings[..] else {
+        panic!("expected a decimal and a string ordering: {orderings:?}");
+    };
+    let copy = |local| MirOperand::Copy(MirPlace::new(local));
+    // Both decimals are borrowed, so `d` is still owned afterwards.
+    assert_eq!(
+        decimal_args[..],
+        [copy(MirLocalId::from_usize(0)), copy(MirLocalId::from_usize(0))]
+    );
+    assert_eq!(string_args.len(), 2);
+    let zero = MirOperand::Immediate(MirImmediate::Int {
+        value: 0,
+        ty: MirIntTy { signed: true, bits: TypeBits::B64 },
+    });
+    // `>` swaps the operands of `<`.
+    assert_eq!(
+        comparisons,
+        [(MirBinOp::Lt, zero.clone(), copy(*decimal)), (MirBinOp::Le, copy(*string), zero),]
+    );
+}
+
+#[test]
+fn lower_integer_builtin_constants_as_integer_immediates() {
+    let int = |value| s(HirExpr::Literal(HirLiteral::Int { value, suffix: None }));
+    let let_stmt = |id, init| HirStmt::Let {
+        local: Some(HirLocalId::from_usize(id)),
+        name: SpannedStr::new("d", dummy()),
+        is_mutable: false,
+        ty: None,
+        init: Some(init),
+        span: dummy(),
+    };
+    let body = HirBlock {
+        stmts: Box::new([
+            let_stmt(
+                0,
+                s(HirExpr::Call {
+                    callee: Box::new(s(HirExpr::Resolved(Res::Builtin(DECIMAL_FROM_INT)))),
+                    args: Box::new([int(3)]),
+                }),
+            ),
+            let_stmt(
+                1,
+                s(HirExpr::Call {
+                    callee: Box::new(s(HirExpr::Field {
+                        base: Box::new(s(HirExpr::Resolved(Res::Local(HirLocalId::from_usize(0))))),
+                        name: SpannedStr::new("round", dummy()),
+                    })),
+                    args: Box::new([int(2), s(HirExpr::Resolved(Res::Builtin(ROUNDING_HALF_UP)))]),
+                }),
+            ),
+        ]),
+        tail: None,
+        span: dummy(),
+    };
+    let mut hir = HirMap::new();
+    hir.push(unit_function_with_body(body));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty(), "{:?}", typeck.diags);
+
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+
+    assert!(result.diags.is_empty(), "{:?}", result.diags);
+    let function = &result.module.functions[0];
+    let rounded = function.blocks[0]
+        .stmts
+        .iter()
+        .find_map(|stmt| match stmt {
+            MirStmt::Assign {
+                rhs: MirRvalue::Call { callee: MirCallee::Runtime(RUNTIME_DECIMAL_ROUND), args },
+                ..
+            } => Some(args.clone()),
+            _ => None,
+        })
+        .expect("round call");
+    assert_eq!(
+        rounded[2],
+        MirOperand::Immediate(MirImmediate::Int {
+            value: ROUND_HALF_UP as u128,
+            ty: MirIntTy { signed: false, bits: TypeBits::B64 },
+        })
+    );
+}
+
+#[test]
+fn inline_itertools_methods_borrowing_the_receiver() {
+    let collect = HirId::from_usize(0);
+    let v = HirLocalId::from_usize(0);
+    let vec_of =
+        |ty| HirType::Builtin { ty: VEC, generics: Box::new([HirGenericArg::Type(s(ty))]) };
+    let vec_new = || {
+        s(HirExpr::Call {
+            callee: Box::new(s(HirExpr::Resolved(Res::Builtin(VEC_NEW)))),
+            args: Box::new([]),
+        })
+    };
+    let generic = |name, bounds| HirGenericParam {
+        name: SpannedStr::new(name, dummy()),
+        kind: HirGenericParamKind::Type { bounds },
+        span: dummy(),
+    };
+    let into_iterator = HirBound {
+        path: HirPath {
+            from_root: false,
+            segments: Box::new([HirPathSegment {
+                name: SpannedStr::new("IntoIterator", dummy()),
+                generics: Some(Box::new([HirGenericArg::Type(s(HirType::Param(0)))])),
+                span: dummy(),
+            }]),
+            span: dummy(),
+        },
+        contract: Some(INTO_ITERATOR_CONTRACT),
+    };
+    let mut hir = HirMap::new();
+    let std = hir.add_module(HirModuleId::ROOT, "std");
+    let itertools = hir.add_module(std, "itertools");
+    // `pub act collect<T, I: IntoIterator<T>>(items: I) -> Vec<T> { Vec::new() }`
+    hir.push_in(
+        itertools,
+        HirItem::Function(HirFunction {
+            vis: Visibility::Public,
+            id: collect,
+            name: SpannedStr::new("collect", dummy()),
+            generics: Box::new([
+                generic("T", Box::new([])),
+                generic("I", Box::new([into_iterator])),
+            ]),
+            params: Box::new([HirFunctionParam {
+                name: SpannedStr::new("items", dummy()),
+                ty: s(HirType::Param(1)),
+                span: dummy(),
+            }]),
+            ret_ty: s(vec_of(HirType::Param(0))),
+            throws: None,
+            body: HirBlock { stmts: Box::new([]), tail: Some(Box::new(vec_new())), span: dummy() },
+            span: dummy(),
+        }),
+    );
+    // `let v: Vec<i32> = Vec::new(); v.collect(); v.collect();`
+    let call = |lo| {
+        HirStmt::Expr(s(HirExpr::Call {
+            callee: Box::new(Spanned::new(
+                HirExpr::Field {
+                    base: Box::new(s(HirExpr::Resolved(Res::Local(v)))),
+                    name: SpannedStr::new("collect", dummy()),
+                },
+                sp(lo, lo + 1),
+            )),
+            args: Box::new([]),
+        }))
+    };
+    let body = HirBlock {
+        stmts: Box::new([
+            HirStmt::Let {
+                local: Some(v),
+                name: SpannedStr::new("v", dummy()),
+                is_mutable: false,
+                ty: Some(s(vec_of(HirType::Primitive(HirPrimitiveTy::I32)))),
+                init: Some(vec_new()),
+                span: dummy(),
+            },
+            call(1),
+            call(3),
+        ]),
+        tail: None,
+        span: dummy(),
+    };
+    hir.push(function_with_body(HirId::from_usize(1), "main", body));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty(), "{:?}", typeck.diags);
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+
+    // The receiver is borrowed, so `v` can be used again.
+    assert!(result.diags.is_empty(), "{:?}", result.diags);
+    let module = &result.module;
+    assert_eq!(module.instances.len(), 1);
+    let main = &module.functions[0];
+    let stmts: Vec<_> = main.blocks.iter().flat_map(|block| block.stmts.iter()).collect();
+    assert!(
+        !stmts
+            .iter()
+            .any(|stmt| matches!(stmt, MirStmt::Assign { rhs: MirRvalue::Call { .. }, .. }))
+    );
+    // Both calls are inlined, each handing `v` to its own copy of `items`.
+    let receiver = MirRvalue::Use(MirOperand::Copy(MirPlace::new(MirLocalId::from_usize(0))));
+    let params: Vec<_> = stmts
+        .iter()
+        .filter_map(|stmt| match stmt {
+            MirStmt::Assign { dst, rhs, .. } if *rhs == receiver => {
+                Some(main.locals[dst.local.to_usize()].name)
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(params, [Some("items"), Some("items")]);
+}
+
+#[test]
+fn lower_swap_and_replace_of_vector_elements_to_indexed_reads_then_writes() {
+    let v = || s(HirExpr::Resolved(Res::Local(HirLocalId::from_usize(0))));
+    let int = |value| s(HirExpr::Literal(HirLiteral::Int { value, suffix: None }));
+    let element = |index| s(HirExpr::Index { base: Box::new(v()), index: Box::new(int(index)) });
+    let call = |id, args: Box<[_]>| {
+        s(HirExpr::Call { callee: Box::new(s(HirExpr::Resolved(Res::Builtin(id)))), args })
+    };
+    let let_stmt = |id, ty, init| HirStmt::Let {
+        local: Some(HirLocalId::from_usize(id)),
+        name: SpannedStr::new("v", dummy()),
+        is_mutable: id == 0,
+        ty,
+        init: Some(init),
+        span: dummy(),
+    };
+    let vec_ty = HirType::Builtin {
+        ty: VEC,
+        generics: Box::new([HirGenericArg::Type(s(HirType::Primitive(HirPrimitiveTy::I64)))]),
+    };
+    let body = HirBlock {
+        stmts: Box::new([
+            let_stmt(0, Some(s(vec_ty)), call(VEC_NEW, Box::new([]))),
+            HirStmt::Expr(call(MEM_SWAP, Box::new([element(0), element(1)]))),
+            let_stmt(1, None, call(MEM_REPLACE, Box::new([element(1), int(5)]))),
+        ]),
+        tail: None,
+        span: dummy(),
+    };
+    let mut hir = HirMap::new();
+    hir.push(unit_function_with_body(body));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty(), "{:?}", typeck.diags);
+
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+
+    assert!(result.diags.is_empty(), "{:?}", result.diags);
+    let function = &result.module.functions[0];
+    let vec = MirOperand::Copy(MirPlace::new(MirLocalId::from_usize(0)));
+    let index_value = |index: &MirOperand| match index {
+        MirOperand::Immediate(MirImmediate::Int { value, .. }) => *value,
+        _ => panic!("expected an immediate index: {index:?}"),
+    };
+    let mut reads = HashMap::new();
+    let mut lowered = Vec::new();
+    for stmt in function.blocks.iter().flat_map(|block| block.stmts.iter()) {
+        match stmt {
+            MirStmt::Assign { dst, rhs: MirRvalue::Index { base, index }, .. } => {
+                assert_eq!(*base, vec);
+                reads.insert(dst.local, index_value(index));
+                lowered.push(format!("read {}", index_value(index)));
+            }
+            MirStmt::StoreIndex { base, index, value, .. } => {
+                assert_eq!(*base, vec);
+                // Every write stores an element read before any of them.
+                let value = match value.place() {
+                    Some(place) => format!("element {}", reads[&place.local]),
+                    None => format!("{value:?}"),
+                };
+                lowered.push(format!("write {} <- {value}", index_value(index)));
+            }
+            _ => {}
+        }
+    }
+    let five = MirOperand::Immediate(MirImmediate::Int {
+        value: 5,
+        ty: MirIntTy { signed: true, bits: TypeBits::B64 },
+    });
+    assert_eq!(
+        lowered,
+        [
+            "read 0".to_string(),
+            "read 1".to_string(),
+            "write 0 <- element 1".to_string(),
+            "write 1 <- element 0".to_string(),
+            "read 1".to_string(),
+            format!("write 1 <- {five:?}"),
+        ]
+    );
+}
+
+/// `let m: Vec<Vec<i64>> = Vec::new(); m[0].len(); let a = m[0];`
+#[test]
+fn moving_a_vector_out_of_an_index_is_reported() {
+    let m = || s(HirExpr::Resolved(Res::Local(HirLocalId::from_usize(0))));
+    let int = |value| s(HirExpr::Literal(HirLiteral::Int { value, suffix: None }));
+    let element =
+        |span| Spanned::new(HirExpr::Index { base: Box::new(m()), index: Box::new(int(0)) }, span);
+    let vec_of =
+        |ty| HirType::Builtin { ty: VEC, generics: Box::new([HirGenericArg::Type(s(ty))]) };
+    let body = HirBlock {
+        stmts: Box::new([
+            HirStmt::Let {
+                local: Some(HirLocalId::from_usize(0)),
+                name: SpannedStr::new("m", dummy()),
+                is_mutable: false,
+                ty: Some(s(vec_of(vec_of(HirType::Primitive(HirPrimitiveTy::I64))))),
+                init: Some(s(HirExpr::Call {
+                    callee: Box::new(s(HirExpr::Resolved(Res::Builtin(VEC_NEW)))),
+                    args: Box::new([]),
+                })),
+                span: dummy(),
+            },
+            HirStmt::Expr(s(HirExpr::Call {
+                callee: Box::new(s(HirExpr::Field {
+                    base: Box::new(element(sp(1, 5))),
+                    name: SpannedStr::new("len", dummy()),
+                })),
+                args: Box::new([]),
+            })),
+            HirStmt::Let {
+                local: Some(HirLocalId::from_usize(1)),
+                name: SpannedStr::new("a", dummy()),
+                is_mutable: true,
+                ty: None,
+                init: Some(element(sp(20, 24))),
+                span: dummy(),
+            },
+        ]),
+        tail: None,
+        span: dummy(),
+    };
+    let mut hir = HirMap::new();
+    hir.push(unit_function_with_body(body));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty(), "{:?}", typeck.diags);
+
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+
+    // Borrowing the element as a receiver is fine; binding it would share it.
+    assert_eq!(result.diags.len(), 1);
+    let diag = &result.diags[0];
+    assert_eq!(
+        diag.message.message,
+        "cannot move a `Vec<i64>` out of an index; swap it out with `core::mem::replace` or \
+         `core::mem::swap` instead"
+    );
+    assert_eq!(diag.labels[0].span, sp(20, 24));
+}
+
+#[test]
+fn lower_swap_through_temporaries_and_size_of_to_an_immediate() {
+    let i64_ty = || s(HirType::Primitive(HirPrimitiveTy::I64));
+    let local = |id| s(HirExpr::Resolved(Res::Local(HirLocalId::from_usize(id))));
+    let let_stmt = |id, ty: Option<Spanned<HirType<'static>>>, init| HirStmt::Let {
+        local: Some(HirLocalId::from_usize(id)),
+        name: SpannedStr::new("v", dummy()),
+        is_mutable: true,
+        ty,
+        init: Some(init),
+        span: dummy(),
+    };
+    let int = |value| s(HirExpr::Literal(HirLiteral::Int { value, suffix: None }));
+    let body = HirBlock {
+        stmts: Box::new([
+            let_stmt(0, Some(i64_ty()), int(1)),
+            let_stmt(1, Some(i64_ty()), int(2)),
+            HirStmt::Expr(s(HirExpr::Call {
+                callee: Box::new(s(HirExpr::Resolved(Res::Builtin(MEM_SWAP)))),
+                args: Box::new([local(0), local(1)]),
+            })),
+            let_stmt(
+                2,
+                None,
+                s(HirExpr::Call {
+                    callee: Box::new(s(HirExpr::Turbofish {
+                        callee: Box::new(s(HirExpr::Resolved(Res::Builtin(MEM_SIZE_OF)))),
+                        args: Box::new([HirGenericArg::Type(i64_ty())]),
+                    })),
+                    args: Box::new([]),
+                }),
+            ),
+        ]),
+        tail: None,
+        span: dummy(),
+    };
+    let mut hir = HirMap::new();
+    hir.push(unit_function_with_body(body));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty(), "{:?}", typeck.diags);
+
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+
+    assert!(result.diags.is_empty(), "{:?}", result.diags);
+    let function = &result.module.functions[0];
+    let copy = |id| MirOperand::Copy(MirPlace::new(MirLocalId::from_usize(id)));
+    let assigns: Vec<_> = function.blocks[0]
+        .stmts
+        .iter()
+        .filter_map(|stmt| match stmt {
+            MirStmt::Assign { dst, rhs: MirRvalue::Use(operand), .. } => {
+                Some((dst.local.to_usize(), operand.clone()))
+            }
+            _ => None,
+        })
+        .skip(2)
+        .collect();
+    let size = MirOperand::Immediate(MirImmediate::Int {
+        value: 8,
+        ty: MirIntTy { signed: false, bits: TypeBits::B64 },
+    });
+    assert_eq!(assigns, [(2, copy(0)), (3, copy(1)), (0, copy(3)), (1, copy(2)), (4, size)]);
+}
+
+/// `let mut n = 7; let mut s = "x"; let a = take(n); let b = take(s);`
+#[test]
+fn lower_take_to_a_write_of_the_default_value() {
+    let local = |id| s(HirExpr::Resolved(Res::Local(HirLocalId::from_usize(id))));
+    let let_stmt = |id, init| HirStmt::Let {
+        local: Some(HirLocalId::from_usize(id)),
+        name: SpannedStr::new("v", dummy()),
+        is_mutable: id < 2,
+        ty: None,
+        init: Some(init),
+        span: dummy(),
+    };
+    let take = |id| {
+        s(HirExpr::Call {
+            callee: Box::new(s(HirExpr::Resolved(Res::Builtin(MEM_TAKE)))),
+            args: Box::new([local(id)]),
+        })
+    };
+    let body = HirBlock {
+        stmts: Box::new([
+            let_stmt(0, s(HirExpr::Literal(HirLiteral::Int { value: 7, suffix: None }))),
+            let_stmt(1, s(HirExpr::Literal(HirLiteral::Str(Cow::Borrowed("x"))))),
+            let_stmt(2, take(0)),
+            let_stmt(3, take(1)),
+        ]),
+        tail: None,
+        span: dummy(),
+    };
+    let mut hir = HirMap::new();
+    hir.push(unit_function_with_body(body));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty(), "{:?}", typeck.diags);
+
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+
+    assert!(result.diags.is_empty(), "{:?}", result.diags);
+    let written: Vec<_> = result.module.functions[0].blocks[0]
+        .stmts
+        .iter()
+        .filter_map(|stmt| match stmt {
+            MirStmt::Assign { dst, rhs: MirRvalue::Use(operand), .. }
+                if dst.local.to_usize() < 2 =>
+            {
+                Some(operand.clone())
+            }
+            _ => None,
+        })
+        .skip(2)
+        .collect();
+    let zero = MirOperand::Immediate(MirImmediate::Int {
+        value: 0,
+        ty: MirIntTy { signed: true, bits: TypeBits::B32 },
+    });
+    let empty = MirOperand::Constant(crate::MirConstantId::from_usize(1));
+    assert_eq!(written, [zero, empty]);
+    assert_eq!(result.module.constants[1], MirConstant::Str(Cow::Borrowed("")));
+}
+
+#[test]
+fn lower_json_round_trip_of_a_struct_to_runtime_calls_per_field() {
+    let point = HirId::from_usize(0);
+    let p = HirLocalId::from_usize(0);
+    let str_ty = || s(HirType::Primitive(HirPrimitiveTy::Str));
+    let point_ty = || s(HirType::Struct { def: point, generics: Box::new([]) });
+    let field = |name, ty| HirField {
+        vis: Visibility::Private,
+        name: SpannedStr::new(name, dummy()),
+        ty,
+        span: dummy(),
+    };
+    let call = |builtin, arg| {
+        s(HirExpr::Call {
+            callee: Box::new(s(HirExpr::Resolved(Res::Builtin(builtin)))),
+            args: Box::new([arg]),
+        })
+    };
+
+    let mut hir = HirMap::new();
+    // `struct Point { x: i32, label: str? }`
+    hir.push(HirItem::Struct(HirStruct {
+        id: point,
+        vis: Visibility::Private,
+        name: SpannedStr::new("Point", dummy()),
+        generics: Box::new([]),
+        fields: Box::new([
+            field("x", s(HirType::Primitive(HirPrimitiveTy::I32))),
+            field("label", s(HirType::Nullable(Box::new(str_ty())))),
+        ]),
+        span: dummy(),
+    }));
+    // `act load(p: Point) throws str { let back: Point = from_json(to_json(p))?; }`
+    let doc = call(JSON_TO_JSON, s(HirExpr::Resolved(Res::Local(p))));
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: HirId::from_usize(1),
+        name: SpannedStr::new("load", dummy()),
+        generics: Box::new([]),
+        params: Box::new([HirFunctionParam {
+            name: SpannedStr::new("p", dummy()),
+            ty: point_ty(),
+            span: dummy(),
+        }]),
+        ret_ty: s(HirType::Unit),
+        throws: Some(str_ty()),
+        body: HirBlock {
+            stmts: Box::new([HirStmt::Let {
+                local: Some(HirLocalId::from_usize(1)),
+                name: SpannedStr::new("back", dummy()),
+                is_mutable: false,
+                ty: Some(point_ty()),
+                init: Some(s(HirExpr::Try(Box::new(call(JSON_FROM_JSON, doc))))),
+                span: dummy(),
+            }]),
+            tail: None,
+            span: dummy(),
+        },
+        span: dummy(),
+    }));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty(), "{:?}", typeck.diags);
+
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+
+    assert!(result.diags.is_empty(), "{:?}", result.diags);
+    let load = &result.module.functions[0];
+    let mut runtimes = Vec::new();
+    let mut records = 0;
+    for stmt in load.blocks.iter().flat_map(|block| block.stmts.iter()) {
+        match stmt {
+            MirStmt::Assign {
+                rhs: MirRvalue::Call { callee: MirCallee::Runtime(runtime), .. },
+                ..
+            } => runtimes.push(*runtime),
+            MirStmt::Assign { rhs: MirRvalue::MakeRecord(_), .. } => records += 1,
+            _ => {}
+        }
+    }
+    // Each field is built into the object, then checked and read back out
+    // of it; the missing `label` is read as `null` without a check.
+    assert_eq!(
+        runtimes,
+        [
+            RUNTIME_JSON_NEW_OBJECT,
+            RUNTIME_JSON_FROM_NUMBER,
+            RUNTIME_JSON_INSERT,
+            RUNTIME_JSON_NEW_NULL,
+            RUNTIME_JSON_FROM_STR,
+            RUNTIME_JSON_INSERT,
+            RUNTIME_JSON_CHECK_KIND,
+            RUNTIME_JSON_GET,
+            RUNTIME_JSON_CHECK_INT,
+            RUNTIME_JSON_AS_NUMBER,
+            RUNTIME_JSON_GET,
+            RUNTIME_JSON_IS_NULL,
+            RUNTIME_JSON_CHECK_KIND,
+            RUNTIME_JSON_AS_STR,
+        ]
+    );
+    assert_eq!(records, 1);
+}
+
+#[test]
+fn lower_json_round_trip_of_an_enum_to_names_and_tagged_objects() {
+    let shape = HirId::from_usize(0);
+    let value = HirLocalId::from_usize(0);
+    let shape_ty = || s(HirType::Enum { def: shape, generics: Box::new([]) });
+    let i32_ty = || s(HirType::Primitive(HirPrimitiveTy::I32));
+    let variant = |name, payload| HirVariant {
+        vis: Visibility::Private,
+        name: SpannedStr::new(name, dummy()),
+        payload,
+        span: dummy(),
+    };
+    let field = |name| HirField {
+        vis: Visibility::Private,
+        name: SpannedStr::new(name, dummy()),
+        ty: i32_ty(),
+        span: dummy(),
+    };
+    let call = |builtin, arg| {
+        s(HirExpr::Call {
+            callee: Box::new(s(HirExpr::Resolved(Res::Builtin(builtin)))),
+            args: Box::new([arg]),
+        })
+    };
+
+    let mut hir = HirMap::new();
+    // `enum Shape { Empty, Circle(i32), Rect { w: i32, h: i32 } }`
+    hir.push(HirItem::Enum(HirEnum {
+        id: shape,
+        vis: Visibility::Private,
+        name: SpannedStr::new("Shape", dummy()),
+        variants: Box::new([
+            variant("Empty", HirVariantPayload::Unit),
+            variant("Circle", HirVariantPayload::Tuple(Box::new([i32_ty()]))),
+            variant("Rect", HirVariantPayload::Struct(Box::new([field("w"), field("h")]))),
+        ]),
+        span: dummy(),
+    }));
+    // `act load(value: Shape) throws str { let back: Shape = from_json(to_json(value))?; }`
+    let doc = call(JSON_TO_JSON, s(HirExpr::Resolved(Res::Local(value))));
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: HirId::from_usize(1),
+        name: SpannedStr::new("load", dummy()),
+        generics: Box::new([]),
+        params: Box::new([HirFunctionParam {
+            name: SpannedStr::new("value", dummy()),
+            ty: shape_ty(),
+            span: dummy(),
+        }]),
+        ret_ty: s(HirType::Unit),
+        throws: Some(s(HirType::Primitive(HirPrimitiveTy::Str))),
+        body: HirBlock {
+            stmts: Box::new([HirStmt::Let {
+                local: Some(HirLocalId::from_usize(1)),
+                name: SpannedStr::new("back", dummy()),
+                is_mutable: false,
+                ty: Some(shape_ty()),
+                init: Some(s(HirExpr::Try(Box::new(call(JSON_FROM_JSON, doc))))),
+                span: dummy(),
+            }]),
+            tail: None,
+            span: dummy(),
+        },
+        span: dummy(),
+    }));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty(), "{:?}", typeck.diags);
+
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+
+    assert!(result.diags.is_empty(), "{:?}", result.diags);
+    let load = &result.module.functions[0];
+    let mut runtimes = Vec::new();
+    let mut variants = Vec::new();
+    for stmt in load.blocks.iter().flat_map(|block| block.stmts.iter()) {
+        match stmt {
+            MirStmt::Assign {
+                rhs: MirRvalue::Call { callee: MirCallee::Runtime(runtime), .. },
+                ..
+            } => runtimes.push(*runtime),
+            MirStmt::Assign { rhs: MirRvalue::MakeRecord(fields), .. } => {
+                variants.push(fields[0].clone());
+            }
+            _ => {}
+        }
+    }
+    // Blocks are listed as they were made, so the check that the document
+    // is a string, in the block the variants of `to_json` join in, comes
+    // first. `Empty` is written as its name, `Circle` as an array and `Rect`
+    // as an object under theirs; reading them back compares the string with
+    // the name of `Empty` and looks up the others by theirs.
+    assert_eq!(
+        runtimes,
+        [
+            RUNTIME_JSON_CHECK_KIND,
+            RUNTIME_JSON_FROM_STR,
+            RUNTIME_JSON_NEW_ARRAY,
+            RUNTIME_JSON_FROM_NUMBER,
+            RUNTIME_JSON_PUSH,
+            RUNTIME_JSON_NEW_OBJECT,
+            RUNTIME_JSON_INSERT,
+            RUNTIME_JSON_NEW_OBJECT,
+            RUNTIME_JSON_FROM_NUMBER,
+            RUNTIME_JSON_INSERT,
+            RUNTIME_JSON_FROM_NUMBER,
+            RUNTIME_JSON_INSERT,
+            RUNTIME_JSON_NEW_OBJECT,
+            RUNTIME_JSON_INSERT,
+            RUNTIME_JSON_CHECK_KIND,
+            RUNTIME_JSON_CHECK_KIND,
+            RUNTIME_JSON_AS_STR,
+            RUNTIME_JSON_GET,
+            RUNTIME_JSON_GET,
+            RUNTIME_JSON_CHECK_KIND,
+            RUNTIME_JSON_LEN,
+            RUNTIME_JSON_AT,
+            RUNTIME_JSON_CHECK_INT,
+            RUNTIME_JSON_AS_NUMBER,
+            RUNTIME_JSON_CHECK_KIND,
+            RUNTIME_JSON_GET,
+            RUNTIME_JSON_CHECK_INT,
+            RUNTIME_JSON_AS_NUMBER,
+            RUNTIME_JSON_GET,
+            RUNTIME_JSON_CHECK_INT,
+            RUNTIME_JSON_AS_NUMBER,
+        ]
+    );
+    // Each variant read back is built with its index as the tag.
+    let tag =
+        |value| Some(MirOperand::Immediate(MirImmediate::Int { value, ty: MirIntTy::VARIANT }));
+    assert_eq!(variants, [tag(0), tag(1), tag(2)]);
+}
+
+#[test]
+fn lower_tuples_to_records_without_a_definition() {
+    let pair = HirLocalId::from_usize(0);
+    let label = HirLocalId::from_usize(1);
+    let let_stmt = |local, name, init| HirStmt::Let {
+        local: Some(local),
+        name: SpannedStr::new(name, dummy()),
+        is_mutable: false,
+        ty: None,
+        init: Some(init),
+        span: dummy(),
+    };
+    // `let pair = (1, "one"); let label = pair.1;`
+    let tuple = s(HirExpr::Tuple(Box::new([
+        s(HirExpr::Literal(HirLiteral::Int { value: 1, suffix: None })),
+        s(HirExpr::Literal(HirLiteral::Str(Cow::Borrowed("one")))),
+    ])));
+    let element =
+        s(HirExpr::TupleField { base: Box::new(s(HirExpr::Resolved(Res::Local(pair)))), index: 1 });
+    let mut hir = HirMap::new();
+    hir.push(unit_function_with_body(HirBlock {
+        stmts: Box::new([let_stmt(pair, "pair", tuple), let_stmt(label, "label", element)]),
+        tail: None,
+        span: dummy(),
+    }));
+
+    let typeck = TypeChecker::new().check(&hir);
+    assert!(typeck.diags.is_empty(), "{:?}", typeck.diags);
+
+    let result = MirLowerer::new(&typeck.info).lower(&hir);
+
+    assert!(result.diags.is_empty(), "{:?}", result.diags);
+    let main = &result.module.functions[0];
+    let tuple_ty = MirTy::Record {
+        def: None,
+        fields: Box::new([MirTy::Int(MirIntTy { signed: true, bits: TypeBits::B32 }), MirTy::Str]),
+    };
+    assert!(main.locals.iter().any(|local| local.ty == tuple_ty));
+    let stmts: Vec<_> = main.blocks.iter().flat_map(|block| block.stmts.iter()).collect();
+    assert!(stmts.iter().any(|stmt| matches!(
+        stmt,
+        MirStmt::Assign { rhs: MirRvalue::MakeRecord(elements), .. } if elements.len() == 2
+    )));
+    assert!(stmts.iter().any(|stmt| matches!(
+        stmt,
+        MirStmt::Assign { rhs: MirRvalue::LoadField { index: 1, .. }, .. }
+    )));
+}
@@ -1,11 +1,17 @@
+use std::borrow::Cow;
+
+use runec_semantic::typeck::GenericArg;
+
 use crate::constant::MirConstant;
-use crate::function::MirFunction;
-use crate::ids::{MirConstantId, MirFunctionId};
+use crate::function::{MirExtern, MirFunction, MirInstance};
+use crate::ids::{MirConstantId, MirExternId, MirFunctionId, MirInstanceId};
 
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct MirModule<'src> {
     pub constants: Vec<MirConstant<'src>>,
     pub functions: Vec<MirFunction<'src>>,
+    pub instances: Vec<MirInstance>,
+    pub externs: Vec<MirExtern<'src>>,
     pub entry: Option<MirFunctionId>,
 }
 
@@ -33,6 +39,65 @@ impl<'src> MirModule<'src> {
     pub fn function(&self, id: MirFunctionId) -> &MirFunction<'src> {
         &self.functions[id.to_usize()]
     }
+
+    pub fn push_instance(&mut self, instance: MirInstance) -> MirInstanceId {
+        let id = MirInstanceId::from_usize(self.instances.len());
+        self.instances.push(instance);
+        id
+    }
+
+    pub fn instance(&self, id: MirInstanceId) -> &MirInstance {
+        &self.instances[id.to_usize()]
+    }
+
+    pub fn push_extern(&mut self, function: MirExtern<'src>) -> MirExternId {
+        let id = MirExternId::from_usize(self.externs.len());
+        self.externs.push(function);
+        id
+    }
+
+    pub fn extern_fn(&self, id: MirExternId) -> &MirExtern<'src> {
+        &self.externs[id.to_usize()]
+    }
+
+    /// Returns the symbol `function` is emitted under. Functions outside the
+    /// crate root are prefixed with their module path, as in `math::add`,
+    /// instances append their generic arguments, as in `identity$i32`, and
+    /// closures append their index, as in `main$closure0`.
+    pub fn symbol(&self, function: &MirFunction<'src>) -> Cow<'src, str> {
+        if function.instance.is_none()
+            && function.closure.is_none()
+            && function.module_path.is_empty()
+        {
+            return Cow::Borrowed(function.name);
+        }
+
+        let mut symbol = String::new();
+        for module in function.module_path.iter() {
+            symbol.push_str(module);
+            symbol.push_str("::");
+        }
+        symbol.push_str(function.name);
+        if let Some(closure) = function.closure {
+            symbol.push_str(&format!("$closure{}", closure.to_usize()));
+        }
+        let Some(instance) = function.instance else {
+            return Cow::Owned(symbol);
+        };
+        let instance = self.instance(instance);
+        for (arg, ty) in instance.args.iter().zip(instance.arg_tys.iter()) {
+            symbol.push('$');
+            match arg {
+                GenericArg::Type(_) => match ty {
+                    Some(ty) => symbol.push_str(&ty.to_string()),
+                    None => symbol.push('_'),
+                },
+                GenericArg::Const(value) => symbol.push_str(&value.to_string()),
+                GenericArg::ConstParam(_) => symbol.push('_'),
+            }
+        }
+        Cow::Owned(symbol)
+    }
 }
 
 #[cfg(test)]
@@ -88,4 +153,15 @@ mod tests {
         assert_eq!(module.function(main_id).blocks[0].stmts.len(), 2);
         assert_eq!(module.entry, Some(main_id));
     }
+
+    #[test]
+    fn prefixes_symbols_outside_the_crate_root_with_their_module_path() {
+        let module = MirModule::new();
+        let mut function =
+            MirFunction::new(HirId::from_usize(0), "add", MirTy::Unit, dummy(), dummy());
+        assert_eq!(module.symbol(&function), "add");
+
+        function.module_path = Box::new(["math", "ops"]);
+        assert_eq!(module.symbol(&function), "math::ops::add");
+    }
 }
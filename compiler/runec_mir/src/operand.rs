@@ -15,15 +15,35 @@ impl MirPlace {
 #[derive(Debug, Clone, PartialEq)]
 pub enum MirOperand {
     Copy(MirPlace),
+    /// By-value use that leaves the place uninitialized.
+    Move(MirPlace),
     Immediate(MirImmediate),
     Constant(MirConstantId),
 }
 
+impl MirOperand {
+    /// Returns the place read by this operand, if any.
+    pub fn place(&self) -> Option<MirPlace> {
+        match self {
+            MirOperand::Copy(place) | MirOperand::Move(place) => Some(*place),
+            MirOperand::Immediate(_) | MirOperand::Constant(_) => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum MirImmediate {
     Unit,
     Bool(bool),
-    Int { value: u128, ty: MirIntTy },
-    Float { value: f64, ty: MirFloatTy },
+    Int {
+        value: u128,
+        ty: MirIntTy,
+    },
+    Float {
+        value: f64,
+        ty: MirFloatTy,
+    },
     Char(char),
+    /// `null`; only assigned directly to a nullable local.
+    Null,
 }
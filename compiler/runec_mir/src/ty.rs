@@ -1,6 +1,9 @@
+use std::fmt;
+
 use runec_builtins::TypeBits;
+use runec_hir::ids::HirId;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum MirTy {
     Unit,
     Bool,
@@ -9,15 +12,186 @@ pub enum MirTy {
     Char,
     Str,
     Bytes,
+    /// Pointer to a reference-counted runtime allocation.
+    Managed,
+    /// Raw pointer from `*const T` or `*mut T`; may be null.
+    Pointer,
+    /// Value of the inner type or `null`. Pointer-like inner types use the
+    /// null pointer as `null`; all others are prefixed with an `i8` tag.
+    Nullable(Box<MirTy>),
+    /// Result of calling a throwing function: an `i8` tag that is `1` when
+    /// an error was thrown, followed by the `ok` and then the `err` value.
+    Fallible {
+        ok: Box<MirTy>,
+        err: Box<MirTy>,
+    },
+    /// Pointer to a reference-counted closure environment: the code pointer
+    /// of the closure body followed by its captured values.
+    Closure,
+    /// Pointer to a reference-counted box holding a local that closures
+    /// capture by reference.
+    Cell(Box<MirTy>),
+    /// Integer range: its start, its end and an `i8` that is `1` when the
+    /// end is included.
+    Range(MirIntTy),
+    /// Pointer to a reference-counted runtime vector, which holds a
+    /// reference to each managed element.
+    Vec(Box<MirTy>),
+    /// Pointer to the vector the slice was taken from, the index of its
+    /// first element and the number of elements. A slice holds a reference
+    /// to the vector and reads its current buffer, so it stays valid when
+    /// the vector grows or is dropped, and shrinks with the vector.
+    Slice(Box<MirTy>),
+    /// Pointer to a reference-counted runtime hash map, which holds a
+    /// reference to each string key and managed value. Sets are maps with
+    /// `unit` values.
+    Map {
+        key: Box<MirTy>,
+        value: Box<MirTy>,
+    },
+    /// Pointer to an immutable reference-counted record holding the fields
+    /// of struct or enum `def`, or the elements of a tuple without one, in
+    /// order. Enum records start with the index of their variant, followed
+    /// by the fields of every variant; those of other variants are zeroed.
+    Record {
+        def: Option<HirId>,
+        fields: Box<[MirTy]>,
+    },
+    /// Pointer to an immutable reference-counted record of union `def`,
+    /// whose fields all start at its beginning. Nothing records which field
+    /// was written, so none of them is managed.
+    Union {
+        def: HirId,
+        fields: Box<[MirTy]>,
+    },
+}
+
+impl MirTy {
+    /// Returns `true` for values whose copies are tracked by the runtime
+    /// reference count.
+    pub fn is_managed(&self) -> bool {
+        match self {
+            MirTy::Str
+            | MirTy::Managed
+            | MirTy::Closure
+            | MirTy::Cell(_)
+            | MirTy::Vec(_)
+            | MirTy::Slice(_)
+            | MirTy::Map { .. }
+            | MirTy::Record { .. }
+            | MirTy::Union { .. } => true,
+            MirTy::Nullable(inner) => inner.is_managed(),
+            _ => false,
+        }
+    }
+
+    /// Returns `true` for values that stay usable after a by-value use.
+    ///
+    /// Strings, slices, closures, cells, records and unions are shared
+    /// rather than owned, so copying one only takes another reference to it.
+    pub fn is_copy(&self) -> bool {
+        match self {
+            MirTy::Str
+            | MirTy::Slice(_)
+            | MirTy::Closure
+            | MirTy::Cell(_)
+            | MirTy::Record { .. }
+            | MirTy::Union { .. } => true,
+            MirTy::Nullable(inner) => inner.is_copy(),
+            _ => !self.is_managed(),
+        }
+    }
+
+    /// Returns `true` for types whose first value is a pointer that is never
+    /// null, which lets their nullable form reuse it as the `null` niche.
+    pub fn is_pointer_like(&self) -> bool {
+        matches!(
+            self,
+            MirTy::Str
+                | MirTy::Bytes
+                | MirTy::Managed
+                | MirTy::Closure
+                | MirTy::Cell(_)
+                | MirTy::Vec(_)
+                | MirTy::Slice(_)
+                | MirTy::Map { .. }
+                | MirTy::Record { .. }
+                | MirTy::Union { .. }
+        )
+    }
+}
+
+impl fmt::Display for MirTy {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MirTy::Unit => formatter.write_str("unit"),
+            MirTy::Bool => formatter.write_str("bool"),
+            MirTy::Int(ty) => {
+                let sign = if ty.signed { 'i' } else { 'u' };
+                write!(formatter, "{sign}{}", bit_width(ty.bits))
+            }
+            MirTy::Float(ty) => write!(formatter, "f{}", bit_width(ty.bits)),
+            MirTy::Char => formatter.write_str("char"),
+            MirTy::Str => formatter.write_str("str"),
+            MirTy::Bytes => formatter.write_str("bytes"),
+            MirTy::Managed => formatter.write_str("managed"),
+            MirTy::Pointer => formatter.write_str("ptr"),
+            MirTy::Nullable(inner) => write!(formatter, "{inner}?"),
+            MirTy::Fallible { ok, err } => write!(formatter, "fallible<{ok},{err}>"),
+            MirTy::Closure => formatter.write_str("closure"),
+            MirTy::Cell(inner) => write!(formatter, "cell<{inner}>"),
+            MirTy::Range(elem) => write!(formatter, "range<{}>", MirTy::Int(*elem)),
+            MirTy::Vec(elem) => write!(formatter, "vec<{elem}>"),
+            MirTy::Slice(elem) => write!(formatter, "{elem}[]"),
+            MirTy::Map { key, value } => write!(formatter, "map<{key},{value}>"),
+            MirTy::Record { def: Some(def), fields } => {
+                let fields: Vec<_> = fields.iter().map(ToString::to_string).collect();
+                write!(formatter, "record{}<{}>", def.to_usize(), fields.join(","))
+            }
+            MirTy::Record { def: None, fields } => {
+                let fields: Vec<_> = fields.iter().map(ToString::to_string).collect();
+                write!(formatter, "({})", fields.join(","))
+            }
+            MirTy::Union { def, fields } => {
+                let fields: Vec<_> = fields.iter().map(ToString::to_string).collect();
+                write!(formatter, "union{}<{}>", def.to_usize(), fields.join("|"))
+            }
+        }
+    }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// Heap record such as a closure environment, whose fields are laid out in
+/// order like a value read by [`MirRvalue::Load`](crate::MirRvalue::Load).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MirStruct {
+    pub fields: Box<[MirTy]>,
+}
+
+fn bit_width(bits: TypeBits) -> u32 {
+    match bits {
+        TypeBits::B8 => 8,
+        TypeBits::B16 => 16,
+        TypeBits::B32 => 32,
+        TypeBits::B64 => 64,
+        TypeBits::B128 => 128,
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct MirIntTy {
     pub signed: bool,
     pub bits: TypeBits,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+impl MirIntTy {
+    /// Type of lengths and indices; see `Ty::USIZE`.
+    pub const USIZE: MirIntTy = MirIntTy { signed: false, bits: TypeBits::B64 };
+    /// Type of the variant index that enum records start with. Enums whose
+    /// variants have no fields are just the index.
+    pub const VARIANT: MirIntTy = MirIntTy { signed: false, bits: TypeBits::B32 };
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct MirFloatTy {
     pub bits: TypeBits,
 }
@@ -135,6 +135,7 @@ impl<'src, 'diag> Lexer<'src> {
             "if" => Token::If,
             "else" => Token::Else,
             "for" => Token::For,
+            "in" => Token::In,
             "while" => Token::While,
             "loop" => Token::Loop,
             "break" => Token::Break,
@@ -152,7 +153,11 @@ impl<'src, 'diag> Lexer<'src> {
             "impl" => Token::Impl,
             "contract" => Token::Contract,
             "use" => Token::Use,
+            "import" => Token::Import,
             "unsafe" => Token::Unsafe,
+            "extern" => Token::Extern,
+            "throw" => Token::Throw,
+            "throws" => Token::Throws,
             _ => Token::Ident(ident),
         };
 
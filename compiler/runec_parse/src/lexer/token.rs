@@ -120,6 +120,8 @@ pub enum Token<'src> {
     Else,
     /// `for`
     For,
+    /// `in`
+    In,
     /// `while`
     While,
     /// `loop`
@@ -154,8 +156,16 @@ pub enum Token<'src> {
     Contract,
     /// `use`
     Use,
+    /// `import`
+    Import,
     /// `unsafe`
     Unsafe,
+    /// `extern`
+    Extern,
+    /// `throw`
+    Throw,
+    /// `throws`
+    Throws,
 
     // Brackets
     /// `(`
@@ -258,6 +268,7 @@ impl Token<'_> {
             Token::If => IF,
             Token::Else => ELSE,
             Token::For => FOR,
+            Token::In => IN,
             Token::While => WHILE,
             Token::Loop => LOOP,
 
@@ -280,7 +291,11 @@ impl Token<'_> {
             Token::Contract => CONTRACT,
 
             Token::Use => USE,
+            Token::Import => IMPORT,
             Token::Unsafe => UNSAFE,
+            Token::Extern => EXTERN,
+            Token::Throw => THROW,
+            Token::Throws => THROWS,
 
             Token::OpenParen => OPEN_PAREN,
             Token::CloseParen => CLOSE_PAREN,
@@ -365,6 +380,7 @@ pub mod token_display {
     pub const IF: &str = "if";
     pub const ELSE: &str = "else";
     pub const FOR: &str = "for";
+    pub const IN: &str = "in";
     pub const WHILE: &str = "while";
     pub const LOOP: &str = "loop";
 
@@ -387,7 +403,11 @@ pub mod token_display {
     pub const CONTRACT: &str = "contract";
 
     pub const USE: &str = "use";
+    pub const IMPORT: &str = "import";
     pub const UNSAFE: &str = "unsafe";
+    pub const EXTERN: &str = "extern";
+    pub const THROW: &str = "throw";
+    pub const THROWS: &str = "throws";
 
     pub const ARROW: &str = "->";
     pub const DARROW: &str = "=>";
@@ -7,11 +7,13 @@ use std::path::PathBuf;
 use runec_source::source_map::{SourceId, SourceMap};
 
 pub mod lexer;
+pub mod loader;
 mod messages;
 pub mod parser;
 
 pub use lexer::lexer_struct::Lexer;
 pub use lexer::token::{Radix, SpannedToken, Token};
+pub use loader::{CrateLoadResult, CrateLoader, ModuleFile};
 pub use parser::{ParseResult, Parser};
 
 #[cfg(test)]
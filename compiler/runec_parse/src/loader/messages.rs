@@ -0,0 +1,8 @@
+use runec_utils::define_messages;
+
+define_messages! {
+    UNREADABLE_CRATE_ROOT => "cannot read crate root `{ path }`",
+    MISSING_MODULE => "cannot find module `{ module }`",
+    EXPECTED_MODULE_FILE => "expected `{ file }` or `{ dir_file }`",
+    IMPORT_CYCLE => "import cycle: `{ cycle }`",
+}
@@ -0,0 +1,177 @@
+//! Discovers the files of a crate by following `import` declarations from
+//! the crate root. `import a::b;` loads `a/b.rnw`, or `a/b/mod.rnw` when the
+//! module is a directory, both relative to the root file's directory. The
+//! modules of the standard library written in RuneWay follow the crate's own.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use runec_ast::statement::Stmt;
+use runec_builtins::STD_MODULES;
+use runec_errors::diagnostics::Diagnostic;
+use runec_errors::labels::{DiagLabel, DiagNote};
+use runec_errors::message::DiagMessage;
+use runec_source::source_loader::FileLoaderError;
+use runec_source::source_map::{Source, SourceId, SourceMap};
+use runec_source::span::Span;
+
+use crate::{Lexer, Parser};
+
+/// A file of the crate and the module it defines.
+#[derive(Debug)]
+pub struct ModuleFile {
+    /// Module path counted from the crate root; empty for the root file.
+    pub path: Box<[String]>,
+    pub source_id: SourceId,
+}
+
+pub struct CrateLoadResult {
+    /// Every loaded module, the crate root first and the standard library
+    /// last.
+    pub modules: Vec<ModuleFile>,
+    pub diags: Vec<Diagnostic<'static>>,
+}
+
+pub struct CrateLoader<'map, F> {
+    source_map: &'map mut SourceMap,
+    load: F,
+    root_dir: PathBuf,
+    modules: Vec<ModuleFile>,
+    loaded: HashSet<Box<[String]>>,
+    /// Modules on the import chain being followed, the crate root first.
+    stack: Vec<Box<[String]>>,
+    diags: Vec<Diagnostic<'static>>,
+}
+
+impl<'map, F> CrateLoader<'map, F>
+where
+    F: FnMut(PathBuf) -> Result<Source, FileLoaderError>,
+{
+    /// `load` reads a file into a `Source`, typically through
+    /// `SourceFileLoader::load`.
+    pub fn new(source_map: &'map mut SourceMap, load: F) -> Self {
+        Self {
+            source_map,
+            load,
+            root_dir: PathBuf::new(),
+            modules: Vec::new(),
+            loaded: HashSet::new(),
+            stack: Vec::new(),
+            diags: Vec::new(),
+        }
+    }
+
+    pub fn load(mut self, root: PathBuf) -> CrateLoadResult {
+        self.root_dir = root.parent().map(Path::to_path_buf).unwrap_or_default();
+        let display = root.display().to_string();
+        match (self.load)(root) {
+            Ok(source) => {
+                let source_id = self.source_map.add_file(source);
+                self.modules.push(ModuleFile { path: Box::new([]), source_id });
+                self.visit(0);
+            }
+            Err(_) => self.diags.push(*Diagnostic::error(DiagMessage::new(
+                messages::UNREADABLE_CRATE_ROOT,
+                &[("path", &display)],
+            ))),
+        }
+
+        for module in STD_MODULES {
+            let file = format!("<std>/{}.rnw", module.path[1..].join("/"));
+            let source_id =
+                self.source_map.add_file(Source::embedded(PathBuf::from(file), module.source));
+            let path = module.path.iter().map(|name| (*name).to_owned()).collect();
+            self.modules.push(ModuleFile { path, source_id });
+        }
+
+        CrateLoadResult { modules: self.modules, diags: self.diags }
+    }
+
+    /// Loads the modules imported by `modules[index]`, depth first.
+    fn visit(&mut self, index: usize) {
+        let module = &self.modules[index];
+        let imports = self.imports(module.source_id);
+        self.stack.push(module.path.clone());
+
+        for (path, span) in imports {
+            if let Some(start) = self.stack.iter().position(|module| *module == path) {
+                let cycle = self.stack[start..]
+                    .iter()
+                    .chain([&path])
+                    .map(|module| module.join("::"))
+                    .collect::<Vec<_>>()
+                    .join(" -> ");
+                self.diags.push(
+                    *Diagnostic::error(DiagMessage::new(
+                        messages::IMPORT_CYCLE,
+                        &[("cycle", &cycle)],
+                    ))
+                    .add_label(DiagLabel::silent_primary(span)),
+                );
+                continue;
+            }
+            if self.loaded.contains(&path) {
+                continue;
+            }
+
+            if let Some(source_id) = self.load_module(&path, span) {
+                self.loaded.insert(path.clone());
+                self.modules.push(ModuleFile { path, source_id });
+                self.visit(self.modules.len() - 1);
+            }
+        }
+
+        self.stack.pop();
+    }
+
+    /// Returns the module paths imported by a file with their spans. Syntax
+    /// errors are left for the parse of the whole crate to report.
+    fn imports(&self, source_id: SourceId) -> Vec<(Box<[String]>, Span)> {
+        let Ok(tokens) = Lexer::new(source_id, self.source_map).lex_full() else {
+            return Vec::new();
+        };
+
+        Parser::new(tokens, source_id, self.source_map)
+            .parse_full()
+            .stmts
+            .into_iter()
+            .filter_map(|stmt| match stmt.node {
+                Stmt::Import(path) => {
+                    Some((path.iter().map(|segment| segment.node.to_owned()).collect(), stmt.span))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn load_module(&mut self, path: &[String], span: Span) -> Option<SourceId> {
+        let (last, parents) = path.split_last()?;
+        let dir = parents.iter().fold(self.root_dir.clone(), |dir, name| dir.join(name));
+        let file = dir.join(format!("{last}.rnw"));
+        let dir_file = dir.join(last).join("mod.rnw");
+
+        for candidate in [file.clone(), dir_file.clone()] {
+            if let Ok(source) = (self.load)(candidate) {
+                return Some(self.source_map.add_file(source));
+            }
+        }
+
+        let module = path.join("::");
+        self.diags.push(
+            *Diagnostic::error(DiagMessage::new(messages::MISSING_MODULE, &[("module", &module)]))
+                .add_label(DiagLabel::silent_primary(span))
+                .set_note(DiagNote::new(
+                    messages::EXPECTED_MODULE_FILE,
+                    &[
+                        ("file", &file.display().to_string()),
+                        ("dir_file", &dir_file.display().to_string()),
+                    ],
+                )),
+        );
+        None
+    }
+}
+
+mod messages;
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+
+use runec_source::source_loader::FileLoaderError;
+use runec_source::source_map::{Source, SourceMap};
+use runec_test_utils::MockSourceFileLoader;
+
+use super::{CrateLoadResult, CrateLoader};
+
+fn load(files: &[(&str, &'static str)]) -> (SourceMap, CrateLoadResult) {
+    let files: HashMap<PathBuf, &str> =
+        files.iter().map(|(path, source)| (PathBuf::from(path), *source)).collect();
+    let mut source_map = SourceMap::new();
+    let result = CrateLoader::new(&mut source_map, |path: PathBuf| -> Result<Source, _> {
+        match files.get(&path) {
+            Some(source) => MockSourceFileLoader { source }.load(path),
+            None => Err(FileLoaderError::IoError(io::ErrorKind::NotFound.into())),
+        }
+    })
+    .load(PathBuf::from("/crate/main.rnw"));
+    (source_map, result)
+}
+
+fn messages(result: &CrateLoadResult) -> Vec<String> {
+    result.diags.iter().map(|diag| diag.message.message.to_string()).collect()
+}
+
+#[test]
+fn loads_imported_files_and_directory_modules() {
+    let (source_map, result) = load(&[
+        ("/crate/main.rnw", "import math; import net::http; act main() {}"),
+        ("/crate/math.rnw", "import net::http; act add() {}"),
+        ("/crate/net/http/mod.rnw", "act get() {}"),
+    ]);
+
+    assert!(result.diags.is_empty(), "{:?}", result.diags);
+    let modules: Vec<_> = result
+        .modules
+        .iter()
+        .map(|module| {
+            let file = source_map.get_file(&module.source_id).expect("loaded file");
+            (module.path.join("::"), file.path().display().to_string())
+        })
+        .collect();
+    assert_eq!(
+        modules,
+        [
+            (String::new(), "/crate/main.rnw".to_owned()),
+            ("math".to_owned(), "/crate/math.rnw".to_owned()),
+            ("net::http".to_owned(), "/crate/net/http/mod.rnw".to_owned()),
+            ("std::itertools".to_owned(), "<std>/itertools.rnw".to_owned()),
+        ]
+    );
+}
+
+#[test]
+fn reports_missing_modules_with_expected_files() {
+    let (_, result) = load(&[("/crate/main.rnw", "import util::strings;")]);
+
+    assert_eq!(messages(&result), ["cannot find module `util::strings`"]);
+    let note = result.diags[0].note.as_ref().expect("expected-file note");
+    assert_eq!(note.message, "expected `/crate/util/strings.rnw` or `/crate/util/strings/mod.rnw`");
+}
+
+#[test]
+fn reports_import_cycles() {
+    let (_, result) = load(&[
+        ("/crate/main.rnw", "import a;"),
+        ("/crate/a.rnw", "import b;"),
+        ("/crate/b.rnw", "import a;"),
+    ]);
+
+    assert_eq!(result.modules.len(), 4);
+    assert_eq!(messages(&result), ["import cycle: `a -> b -> a`"]);
+}
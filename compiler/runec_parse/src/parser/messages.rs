@@ -8,6 +8,7 @@ define_messages! {
     UNTERMINATED_TUPLE_TYPE_ANNOTATION => "unterminated tuple type annotation. Expect: `)`",
     UNTERMINATED_TUPLE => "unterminated tuple. Expect: `)`",
     UNTERMINATED_ARRAY => "unterminated array. Expect: `]`",
+    UNTERMINATED_STRUCT => "unterminated struct expression. Expect: `\\u007D`",
     UNTERMINATED_GENERIC => "unterminated generic. Expect: `>`",
     EXPECT_CODE_BLOCK => "expect code block",
     INTEGER_LITERAL_IS_TOO_LARGE => "integer literal is too large",
@@ -4,11 +4,15 @@ use std::num::IntErrorKind;
 use std::vec::IntoIter;
 
 use runec_ast::SpannedStr;
-use runec_ast::ast_type::{SpannedTypeAnnotation, TypeAnnotation};
-use runec_ast::expression::{Expr, FloatSuffix, IntSuffix, PrimitiveValue, SpannedExpr};
+use runec_ast::ast_type::{GenericArgument, SpannedTypeAnnotation, TypeAnnotation};
+use runec_ast::expression::{
+    ClosureParam, ElseBranch, Expr, FieldValue, FloatSuffix, IfExpr, IntSuffix, PrimitiveValue,
+    SpannedExpr, SpannedIfExpr,
+};
 use runec_ast::operators::{BinaryOp, UnaryOp};
 use runec_ast::statement::{
-    DestructPattern, FunctionArg, SpannedDestructPattern, SpannedStmt, SpannedStmtBlock, Stmt,
+    DestructPattern, FunctionArg, GenericParam, GenericParamKind, SpannedDestructPattern,
+    SpannedStmt, SpannedStmtBlock, Stmt, StructField, UseKind, Visibility,
 };
 use runec_errors::diagnostics::Diagnostic;
 use runec_errors::labels::{DiagLabel, DiagNote};
@@ -95,6 +99,11 @@ pub struct Parser<'src, 'diag> {
     source_id: SourceId,
     source_file: &'src Source,
     source_hi: BytePos,
+    /// Set while parsing an `if` condition, where `{` starts the branch body.
+    in_condition: bool,
+    /// End of a `>>` whose first `>` closed a nested generic list; its second
+    /// `>` still has to close the enclosing one.
+    pending_gt: Option<BytePos>,
     res: ParseResult<'src, 'diag>,
 }
 
@@ -110,6 +119,8 @@ impl<'src, 'diag> Parser<'src, 'diag> {
             source_id,
             source_hi: BytePos::from_usize(source_file.src().len()),
             source_file,
+            in_condition: false,
+            pending_gt: None,
             res: ParseResult::new(),
         }
     }
@@ -141,8 +152,18 @@ impl<'src, 'diag> Parser<'src, 'diag> {
     fn parse_stmt(&mut self) -> InnerParserResult<'diag, SpannedStmt<'src>> {
         let token = self.peek()?;
         match token.node {
+            Token::Pub => self.parse_pub(),
             Token::Act => self.parse_act(),
+            Token::Extern => self.parse_extern(),
+            Token::Struct => self.parse_struct(),
+            Token::Union => self.parse_union(),
+            Token::Alias => self.parse_alias(),
+            Token::Const => self.parse_const(),
             Token::Let => self.parse_let(),
+            Token::Throw => self.parse_throw(),
+            Token::Break => self.parse_break(),
+            Token::Import => self.parse_import(),
+            Token::Use => self.parse_use(),
             Token::Ident(..)
             | Token::IntLiteral { .. }
             | Token::FloatLiteral { .. }
@@ -155,11 +176,18 @@ impl<'src, 'diag> Parser<'src, 'diag> {
             | Token::Plus
             | Token::PlusPlus
             | Token::MinusMinus
+            | Token::Star
+            | Token::Unsafe
+            | Token::Or
+            | Token::OrOr
             | Token::OpenParen
             | Token::OpenBrace
             | Token::OpenBracket
             | Token::True
-            | Token::False => {
+            | Token::False
+            | Token::Null
+            | Token::If
+            | Token::For => {
                 let expr = self.parse_expr(0)?;
                 let stmt = match self.tokens.peek() {
                     Some(t) if t.node == Token::Semicolon => {
@@ -167,6 +195,7 @@ impl<'src, 'diag> Parser<'src, 'diag> {
                         let hi = self.bump()?.span.hi;
                         SpannedStmt::new(Stmt::SemiExpr(expr), Span::new(lo, hi, self.source_id))
                     }
+                    Some(t) if t.node == Token::Eq => return self.parse_assign(expr),
                     _ => {
                         let span = expr.span;
                         SpannedStmt::new(Stmt::TailExpr(expr), span)
@@ -180,16 +209,53 @@ impl<'src, 'diag> Parser<'src, 'diag> {
 
     fn parse_act(&mut self) -> InnerParserResult<'diag, SpannedStmt<'src>> {
         let lo = expect_token!(self, Token::Act, Token::Act.display())?.span.lo;
+        let ident = self.parse_ident()?;
+        let generics = self.parse_generic_params()?;
+        let (args, args_hi) = self.parse_fn_args()?;
+        let ret_ty = self.parse_ret_ty(args_hi)?;
 
-        let ident = if let Some(token) = self.tokens.next() {
-            match token.node {
-                Token::Ident(ident) => (ident, token.span),
-                _ => return Err(unexpected_token!(token, token_display::IDENTIFIER)),
-            }
+        let throws = if self.tokens.peek().is_some_and(|t| t.node == Token::Throws) {
+            self.tokens.next();
+            Some(self.parse_type_annotation()?)
         } else {
-            return Err(self.unexpected_eof());
+            None
         };
 
+        let stmt_block = self.parse_stmt_block()?;
+        let hi = stmt_block.span.hi;
+
+        Ok(SpannedStmt::new(
+            Stmt::DefineFunction {
+                vis: Visibility::Private,
+                ident,
+                generics,
+                args,
+                ret_ty,
+                throws,
+                body: stmt_block,
+            },
+            Span::new(lo, hi, self.source_id),
+        ))
+    }
+
+    /// Parses `extern act name(a: T) -> R;`, a body-less native function.
+    fn parse_extern(&mut self) -> InnerParserResult<'diag, SpannedStmt<'src>> {
+        let lo = expect_token!(self, Token::Extern, Token::Extern.display())?.span.lo;
+        expect_token!(self, Token::Act, Token::Act.display())?;
+        let ident = self.parse_ident()?;
+        let (args, args_hi) = self.parse_fn_args()?;
+        let ret_ty = self.parse_ret_ty(args_hi)?;
+        let hi = expect_token!(self, Token::Semicolon, Token::Semicolon.display())?.span.hi;
+
+        Ok(SpannedStmt::new(
+            Stmt::DefineExternFunction { vis: Visibility::Private, ident, args, ret_ty },
+            Span::new(lo, hi, self.source_id),
+        ))
+    }
+
+    /// Parses a parenthesized `name: Type` list and returns it with the
+    /// position after the closing paren.
+    fn parse_fn_args(&mut self) -> InnerParserResult<'diag, (Box<[FunctionArg<'src>]>, BytePos)> {
         expect_token!(self, Token::OpenParen, Token::OpenParen.display())?;
         let mut args = Vec::new();
         let mut args_terminating_hi = None;
@@ -224,29 +290,7 @@ impl<'src, 'diag> Parser<'src, 'diag> {
         }
 
         if let Some(args_hi) = args_terminating_hi {
-            let ret_ty = if self.tokens.peek().is_some_and(|t| t.node == Token::Arrow) {
-                self.tokens.next();
-
-                self.parse_type_annotation()?
-            } else {
-                SpannedTypeAnnotation::new(
-                    TypeAnnotation::Unit,
-                    Span::new(args_hi, args_hi, self.source_id),
-                )
-            };
-
-            let stmt_block = self.parse_stmt_block()?;
-            let hi = stmt_block.span.hi;
-
-            Ok(SpannedStmt::new(
-                Stmt::DefineFunction {
-                    ident: SpannedStr::new(ident.0, ident.1),
-                    args: args.into_boxed_slice(),
-                    ret_ty,
-                    body: stmt_block,
-                },
-                Span::new(lo, hi, self.source_id),
-            ))
+            Ok((args.into_boxed_slice(), args_hi))
         } else if let Some(args_lo) = args_lo_opt {
             Err(InnerParseErr::without_skip(
                 Diagnostic::error(DiagMessage::new(super::messages::UNTERMINATED_ARGS_BLOCK, &[]))
@@ -259,6 +303,259 @@ impl<'src, 'diag> Parser<'src, 'diag> {
         }
     }
 
+    /// Parses an optional `-> Type`, defaulting to unit at `args_hi`.
+    fn parse_ret_ty(
+        &mut self,
+        args_hi: BytePos,
+    ) -> InnerParserResult<'diag, SpannedTypeAnnotation<'src>> {
+        if self.tokens.peek().is_some_and(|t| t.node == Token::Arrow) {
+            self.tokens.next();
+            self.parse_type_annotation()
+        } else {
+            Ok(SpannedTypeAnnotation::new(
+                TypeAnnotation::Unit,
+                Span::new(args_hi, args_hi, self.source_id),
+            ))
+        }
+    }
+
+    /// Parses an item after `pub` and marks it public.
+    fn parse_pub(&mut self) -> InnerParserResult<'diag, SpannedStmt<'src>> {
+        let lo = expect_token!(self, Token::Pub, Token::Pub.display())?.span.lo;
+        let token = self.peek()?;
+        let mut stmt = match token.node {
+            Token::Act => self.parse_act()?,
+            Token::Extern => self.parse_extern()?,
+            Token::Struct => self.parse_struct()?,
+            Token::Union => self.parse_union()?,
+            Token::Alias => self.parse_alias()?,
+            Token::Const => self.parse_const()?,
+            _ => {
+                return Err(InnerParseErr::with_skip(Self::unexpected_token(token.node.display())));
+            }
+        };
+
+        match &mut stmt.node {
+            Stmt::DefineFunction { vis, .. }
+            | Stmt::DefineExternFunction { vis, .. }
+            | Stmt::DefineStruct { vis, .. }
+            | Stmt::DefineUnion { vis, .. }
+            | Stmt::DefineAlias { vis, .. }
+            | Stmt::DefineConst { vis, .. } => *vis = Visibility::Public,
+            _ => unreachable!(),
+        }
+        stmt.span.lo = lo;
+        Ok(stmt)
+    }
+
+    fn parse_const(&mut self) -> InnerParserResult<'diag, SpannedStmt<'src>> {
+        let lo = expect_token!(self, Token::Const, Token::Const.display())?.span.lo;
+        let ident = self.parse_ident()?;
+        expect_token!(self, Token::Colon, Token::Colon.display())?;
+        let ty = self.parse_type_annotation()?;
+        expect_token!(self, Token::Eq, Token::Eq.display())?;
+        let expr = self.parse_expr(0)?;
+        let hi = expect_token!(self, Token::Semicolon, Token::Semicolon.display())?.span.hi;
+
+        Ok(SpannedStmt::new(
+            Stmt::DefineConst { vis: Visibility::Private, ident, ty, expr },
+            Span::new(lo, hi, self.source_id),
+        ))
+    }
+
+    fn parse_struct(&mut self) -> InnerParserResult<'diag, SpannedStmt<'src>> {
+        let lo = expect_token!(self, Token::Struct, Token::Struct.display())?.span.lo;
+        let ident = self.parse_ident()?;
+        let generics = self.parse_generic_params()?;
+        let (fields, hi) = self.parse_fields()?;
+
+        Ok(SpannedStmt::new(
+            Stmt::DefineStruct { vis: Visibility::Private, ident, generics, fields },
+            Span::new(lo, hi, self.source_id),
+        ))
+    }
+
+    fn parse_union(&mut self) -> InnerParserResult<'diag, SpannedStmt<'src>> {
+        let lo = expect_token!(self, Token::Union, Token::Union.display())?.span.lo;
+        let ident = self.parse_ident()?;
+        let generics = self.parse_generic_params()?;
+        let (fields, hi) = self.parse_fields()?;
+
+        Ok(SpannedStmt::new(
+            Stmt::DefineUnion { vis: Visibility::Private, ident, generics, fields },
+            Span::new(lo, hi, self.source_id),
+        ))
+    }
+
+    /// Parses the `{ [pub] name: T, ... }` body of a struct or union and
+    /// returns it with the end of the closing brace.
+    fn parse_fields(&mut self) -> InnerParserResult<'diag, (Box<[StructField<'src>]>, BytePos)> {
+        expect_token!(self, Token::OpenBrace, Token::OpenBrace.display())?;
+        let mut fields = Vec::new();
+        let hi = loop {
+            let mut token = expect_token!(self, Token::Ident(..) | Token::Pub | Token::CloseBrace, [token_display::IDENTIFIER, Token::Pub.display(), Token::CloseBrace.display()], *)?;
+            let vis = if token.node == Token::Pub {
+                token = expect_token!(self, Token::Ident(..), token_display::IDENTIFIER)?;
+                Visibility::Public
+            } else {
+                Visibility::Private
+            };
+            let Token::Ident(name) = token.node else {
+                break token.span.hi;
+            };
+            expect_token!(self, Token::Colon, Token::Colon.display())?;
+            let ty = self.parse_type_annotation()?;
+            fields.push(StructField { vis, ident: SpannedStr::new(name, token.span), ty });
+
+            let token = expect_token!(self, Token::Comma | Token::CloseBrace, [Token::Comma.display(), Token::CloseBrace.display()], *)?;
+            if token.node == Token::CloseBrace {
+                break token.span.hi;
+            }
+        };
+
+        Ok((fields.into_boxed_slice(), hi))
+    }
+
+    fn parse_alias(&mut self) -> InnerParserResult<'diag, SpannedStmt<'src>> {
+        let lo = expect_token!(self, Token::Alias, Token::Alias.display())?.span.lo;
+        let ident = self.parse_ident()?;
+        let generics = self.parse_generic_params()?;
+        expect_token!(self, Token::Eq, Token::Eq.display())?;
+        let ty = self.parse_type_annotation()?;
+        let hi = expect_token!(self, Token::Semicolon, Token::Semicolon.display())?.span.hi;
+
+        Ok(SpannedStmt::new(
+            Stmt::DefineAlias { vis: Visibility::Private, ident, generics, ty },
+            Span::new(lo, hi, self.source_id),
+        ))
+    }
+
+    fn parse_import(&mut self) -> InnerParserResult<'diag, SpannedStmt<'src>> {
+        let lo = expect_token!(self, Token::Import, Token::Import.display())?.span.lo;
+        let mut path = vec![self.parse_ident()?];
+        while self.tokens.peek().is_some_and(|t| t.node == Token::DColon) {
+            self.tokens.next();
+            path.push(self.parse_ident()?);
+        }
+        let hi = expect_token!(self, Token::Semicolon, Token::Semicolon.display())?.span.hi;
+
+        Ok(SpannedStmt::new(
+            Stmt::Import(path.into_boxed_slice()),
+            Span::new(lo, hi, self.source_id),
+        ))
+    }
+
+    fn parse_use(&mut self) -> InnerParserResult<'diag, SpannedStmt<'src>> {
+        let lo = expect_token!(self, Token::Use, Token::Use.display())?.span.lo;
+        let from_root = self.tokens.peek().is_some_and(|t| t.node == Token::DColon);
+        if from_root {
+            self.tokens.next();
+        }
+
+        let mut path = vec![self.parse_ident()?];
+        let mut kind = UseKind::Single { alias: None };
+        while self.tokens.peek().is_some_and(|t| t.node == Token::DColon) {
+            self.tokens.next();
+            let token = expect_token!(self, Token::Ident(..) | Token::Star, [token_display::IDENTIFIER, Token::Star.display()], *)?;
+            match token.node {
+                Token::Ident(name) => path.push(SpannedStr::new(name, token.span)),
+                _ => {
+                    kind = UseKind::Glob;
+                    break;
+                }
+            }
+        }
+
+        if kind != UseKind::Glob && self.tokens.peek().is_some_and(|t| t.node == Token::As) {
+            self.tokens.next();
+            kind = UseKind::Single { alias: Some(self.parse_ident()?) };
+        }
+        let hi = expect_token!(self, Token::Semicolon, Token::Semicolon.display())?.span.hi;
+
+        Ok(SpannedStmt::new(
+            Stmt::Use { from_root, path: path.into_boxed_slice(), kind },
+            Span::new(lo, hi, self.source_id),
+        ))
+    }
+
+    fn parse_ident(&mut self) -> InnerParserResult<'diag, SpannedStr<'src>> {
+        let token = expect_token!(self, Token::Ident(..), token_display::IDENTIFIER)?;
+        let Token::Ident(ident) = token.node else { unreachable!() };
+        Ok(SpannedStr::new(ident, token.span))
+    }
+
+    /// Parses the optional `<T: Contract, const N: int>` list of a
+    /// declaration.
+    fn parse_generic_params(&mut self) -> InnerParserResult<'diag, Box<[GenericParam<'src>]>> {
+        if !self.tokens.peek().is_some_and(|t| t.node == Token::Lt) {
+            return Ok(Box::new([]));
+        }
+        let lo = self.bump()?.span.lo;
+
+        let mut params = Vec::new();
+        while self.eat_generic_close().is_none() {
+            if self.tokens.peek().is_none() {
+                return Err(self.unterminated_generic(lo));
+            }
+
+            let is_const = self.tokens.peek().is_some_and(|t| t.node == Token::Const);
+            if is_const {
+                self.tokens.next();
+            }
+            let ident = self.parse_ident()?;
+            let kind = if is_const {
+                expect_token!(self, Token::Colon, Token::Colon.display())?;
+                GenericParamKind::Const { ty: self.parse_type_annotation()? }
+            } else {
+                let mut bounds = Vec::new();
+                if self.tokens.peek().is_some_and(|t| t.node == Token::Colon) {
+                    self.tokens.next();
+                    bounds.push(self.parse_type_annotation()?);
+                    while self.tokens.peek().is_some_and(|t| t.node == Token::Plus) {
+                        self.tokens.next();
+                        bounds.push(self.parse_type_annotation()?);
+                    }
+                }
+                GenericParamKind::Type { bounds: bounds.into_boxed_slice() }
+            };
+            params.push(GenericParam { ident, kind });
+
+            if self.tokens.peek().is_some_and(|t| t.node == Token::Comma) {
+                self.tokens.next();
+            } else if self.eat_generic_close().is_some() {
+                break;
+            } else {
+                return Err(self.unterminated_generic(lo));
+            }
+        }
+
+        Ok(params.into_boxed_slice())
+    }
+
+    /// Consumes the `>` closing a generic list and returns its end. `>>`
+    /// closes two lists, so its second half is kept for the enclosing one.
+    fn eat_generic_close(&mut self) -> Option<BytePos> {
+        if let Some(hi) = self.pending_gt.take() {
+            return Some(hi);
+        }
+        match self.tokens.peek().map(|t| &t.node) {
+            Some(Token::Gt) => self.tokens.next().map(|token| token.span.hi),
+            Some(Token::Shr) => {
+                let hi = self.tokens.next()?.span.hi;
+                self.pending_gt = Some(hi);
+                Some(hi - 1)
+            }
+            _ => None,
+        }
+    }
+
+    fn unterminated_generic(&self, lo: BytePos) -> InnerParseErr<'diag> {
+        InnerParseErr::without_skip(
+            Diagnostic::error(DiagMessage::new(super::messages::UNTERMINATED_GENERIC, &[]))
+                .add_label(DiagLabel::silent_primary(span!(self.source_id => lo..self.source_hi))),
+        )
+    }
+
     fn parse_let(&mut self) -> InnerParserResult<'diag, SpannedStmt<'src>> {
         let lo = expect_token!(self, Token::Let, Token::Let.display())?.span.lo;
 
@@ -293,6 +590,88 @@ impl<'src, 'diag> Parser<'src, 'diag> {
         ))
     }
 
+    fn parse_throw(&mut self) -> InnerParserResult<'diag, SpannedStmt<'src>> {
+        let lo = expect_token!(self, Token::Throw, Token::Throw.display())?.span.lo;
+        let value = self.parse_expr(0)?;
+        let hi = expect_token!(self, Token::Semicolon, Token::Semicolon.display())?.span.hi;
+
+        Ok(SpannedStmt::new(Stmt::Throw(value), Span::new(lo, hi, self.source_id)))
+    }
+
+    fn parse_break(&mut self) -> InnerParserResult<'diag, SpannedStmt<'src>> {
+        let lo = expect_token!(self, Token::Break, Token::Break.display())?.span.lo;
+        let hi = expect_token!(self, Token::Semicolon, Token::Semicolon.display())?.span.hi;
+
+        Ok(SpannedStmt::new(Stmt::Break, Span::new(lo, hi, self.source_id)))
+    }
+
+    fn parse_assign(
+        &mut self,
+        target: SpannedExpr<'src>,
+    ) -> InnerParserResult<'diag, SpannedStmt<'src>> {
+        if !matches!(target.node, Expr::Ident(_) | Expr::Index { .. } | Expr::Deref(_)) {
+            return Err(InnerParseErr::with_skip(Self::unexpected_token(Token::Eq.display())));
+        }
+        expect_token!(self, Token::Eq, Token::Eq.display())?;
+
+        let expr = self.parse_expr(0)?;
+        let span = target.span;
+        let stmt = match target.node {
+            Expr::Ident(ident) => Stmt::Assign { ident: SpannedStr::new(ident, span), expr },
+            _ => Stmt::AssignPlace { target, expr },
+        };
+        let hi = expect_token!(self, Token::Semicolon, Token::Semicolon.display())?.span.hi;
+
+        Ok(SpannedStmt::new(stmt, Span::new(span.lo, hi, self.source_id)))
+    }
+
+    fn parse_if(&mut self) -> InnerParserResult<'diag, SpannedIfExpr<'src>> {
+        let lo = expect_token!(self, Token::If, Token::If.display())?.span.lo;
+
+        let in_condition = std::mem::replace(&mut self.in_condition, true);
+        let cond = self.parse_expr(0);
+        self.in_condition = in_condition;
+        let cond = cond?;
+
+        let then = self.parse_stmt_block()?;
+        let mut hi = then.span.hi;
+
+        let else_ = if self.tokens.peek().is_some_and(|t| t.node == Token::Else) {
+            self.tokens.next();
+            if self.tokens.peek().is_some_and(|t| t.node == Token::If) {
+                let nested = self.parse_if()?;
+                hi = nested.span.hi;
+                Some(ElseBranch::If(Box::new(nested)))
+            } else {
+                let block = self.parse_stmt_block()?;
+                hi = block.span.hi;
+                Some(ElseBranch::Block(block))
+            }
+        } else {
+            None
+        };
+
+        Ok(SpannedIfExpr::new(
+            IfExpr { cond: Box::new(cond), then, else_ },
+            Span::new(lo, hi, self.source_id),
+        ))
+    }
+
+    fn parse_for(&mut self) -> InnerParserResult<'diag, SpannedExpr<'src>> {
+        let lo = expect_token!(self, Token::For, Token::For.display())?.span.lo;
+        let binding = self.parse_destruct_primary()?;
+        expect_token!(self, Token::In, Token::In.display())?;
+
+        let in_condition = std::mem::replace(&mut self.in_condition, true);
+        let iter = self.parse_expr(0);
+        self.in_condition = in_condition;
+        let iter = iter?;
+
+        let body = self.parse_stmt_block()?;
+        let span = Span::new(lo, body.span.hi, self.source_id);
+        Ok(SpannedExpr::new(Expr::For { binding, iter: Box::new(iter), body }, span))
+    }
+
     fn parse_destruct_primary(&mut self) -> InnerParserResult<'diag, SpannedDestructPattern<'src>> {
         let token = expect_token!(self, Token::Ident ( .. ) | Token::OpenParen, [token_display::IDENTIFIER, Token::OpenParen.display()], *)?;
         match token.node {
@@ -359,6 +738,16 @@ impl<'src, 'diag> Parser<'src, 'diag> {
                     TypeAnnotation::Ident(ident),
                     Span::new(lo, token.span.hi, self.source_id),
                 )),
+                Token::Star => {
+                    let token = expect_token!(self, Token::Const | Token::Mut, [Token::Const.display(), Token::Mut.display()], *)?;
+                    let mutable = token.node == Token::Mut;
+                    let pointee = self.parse_type_annotation()?;
+                    let hi = pointee.span.hi;
+                    Ok(SpannedTypeAnnotation::new(
+                        TypeAnnotation::Pointer { mutable, pointee: Box::new(pointee) },
+                        Span::new(lo, hi, self.source_id),
+                    ))
+                }
                 Token::OpenParen => {
                     let mut items = Vec::new();
                     let mut terminating_hi = None;
@@ -396,6 +785,29 @@ impl<'src, 'diag> Parser<'src, 'diag> {
                         ))
                     }
                 }
+                Token::Act => {
+                    expect_token!(self, Token::OpenParen, Token::OpenParen.display())?;
+                    let mut params = Vec::new();
+                    let args_hi = loop {
+                        if self.tokens.peek().is_some_and(|t| t.node == Token::CloseParen) {
+                            break self.bump()?.span.hi;
+                        }
+                        params.push(self.parse_type_annotation()?);
+                        let token = expect_token!(self, Token::CloseParen | Token::Comma, [Token::CloseParen.display(), Token::Comma.display()], *)?;
+                        if token.node == Token::CloseParen {
+                            break token.span.hi;
+                        }
+                    };
+                    let ret = self.parse_ret_ty(args_hi)?;
+                    let hi = ret.span.hi.max(args_hi);
+                    Ok(SpannedTypeAnnotation::new(
+                        TypeAnnotation::Function {
+                            params: params.into_boxed_slice(),
+                            ret: Box::new(ret),
+                        },
+                        Span::new(lo, hi, self.source_id),
+                    ))
+                }
                 _ => todo!(),
             }
         } else {
@@ -403,12 +815,51 @@ impl<'src, 'diag> Parser<'src, 'diag> {
         }
     }
 
+    /// Parses `|a, b: T| body` or `|| body`.
+    fn parse_closure(&mut self) -> InnerParserResult<'diag, SpannedExpr<'src>> {
+        let token = expect_token!(self, Token::Or | Token::OrOr, [Token::Or.display(), Token::OrOr.display()], *)?;
+        let lo = token.span.lo;
+        let mut params = Vec::new();
+        if token.node == Token::Or {
+            loop {
+                if self.tokens.peek().is_some_and(|t| t.node == Token::Or) {
+                    self.tokens.next();
+                    break;
+                }
+                let ident = self.parse_ident()?;
+                let ty = if self.tokens.peek().is_some_and(|t| t.node == Token::Colon) {
+                    self.tokens.next();
+                    Some(self.parse_type_annotation()?)
+                } else {
+                    None
+                };
+                params.push(ClosureParam { ident, ty });
+                let token = expect_token!(self, Token::Or | Token::Comma, [Token::Or.display(), Token::Comma.display()], *)?;
+                if token.node == Token::Or {
+                    break;
+                }
+            }
+        }
+
+        let body = self.parse_expr(0)?;
+        let span = Span::new(lo, body.span.hi, self.source_id);
+        Ok(SpannedExpr::new(
+            Expr::Closure { params: params.into_boxed_slice(), body: Box::new(body) },
+            span,
+        ))
+    }
+
     fn parse_array_type_postfix(
         &mut self,
         ty: SpannedTypeAnnotation<'src>,
     ) -> InnerParserResult<'diag, SpannedTypeAnnotation<'src>> {
         expect_token!(self, Token::OpenBracket, Token::OpenBracket.display())?;
 
+        if self.tokens.peek().is_some_and(|t| t.node == Token::CloseBracket) {
+            let hi = self.bump()?.span.hi;
+            let span = Span::new(ty.span.lo, hi, self.source_id);
+            return Ok(SpannedTypeAnnotation::new(TypeAnnotation::Slice(Box::new(ty)), span));
+        }
         let length = self.parse_expr(0)?;
 
         let hi = expect_token!(self, Token::CloseBracket, Token::CloseBracket.display())?.span.hi;
@@ -420,11 +871,52 @@ impl<'src, 'diag> Parser<'src, 'diag> {
         ))
     }
 
+    /// Parses `<...>` after a type.
     fn parse_generic_type_annotation(
         &mut self,
-        _ty: SpannedTypeAnnotation<'src>,
+        ty: SpannedTypeAnnotation<'src>,
     ) -> InnerParserResult<'diag, SpannedTypeAnnotation<'src>> {
-        todo!()
+        let (args, hi) = self.parse_generic_args()?;
+        let span = Span::new(ty.span.lo, hi, self.source_id);
+        Ok(SpannedTypeAnnotation::new(TypeAnnotation::Generic { ty: Box::new(ty), args }, span))
+    }
+
+    /// Parses a `<...>` list of generic arguments and returns it with its
+    /// end. Literals and blocks are const arguments; everything else is a
+    /// type, which name resolution may still turn into a const parameter.
+    fn parse_generic_args(
+        &mut self,
+    ) -> InnerParserResult<'diag, (Box<[GenericArgument<'src>]>, BytePos)> {
+        let lo = expect_token!(self, Token::Lt, Token::Lt.display())?.span.lo;
+
+        let mut args = Vec::new();
+        let hi = loop {
+            if let Some(hi) = self.eat_generic_close() {
+                break hi;
+            }
+            let arg = match self.peek()?.node {
+                Token::IntLiteral { .. }
+                | Token::Minus
+                | Token::True
+                | Token::False
+                | Token::CharLiteral(_)
+                // Stop before `>` and `>>`, which close the list.
+                | Token::OpenBrace => {
+                    GenericArgument::Expr(self.parse_expr(pratt::lbp(&Token::Shr))?)
+                }
+                _ => GenericArgument::Type(self.parse_type_annotation()?),
+            };
+            args.push(arg);
+
+            if let Some(hi) = self.eat_generic_close() {
+                break hi;
+            } else if self.tokens.peek().is_some_and(|t| t.node == Token::Comma) {
+                self.tokens.next();
+            } else {
+                return Err(self.unterminated_generic(lo));
+            }
+        };
+        Ok((args.into_boxed_slice(), hi))
     }
 
     pub(super) fn parse_type_annotation(
@@ -463,11 +955,18 @@ impl<'src, 'diag> Parser<'src, 'diag> {
         mut ty: SpannedTypeAnnotation<'src>,
         parse_path: bool,
     ) -> InnerParserResult<'diag, SpannedTypeAnnotation<'src>> {
-        loop {
+        // A pending `>` closes the enclosing generic list before anything
+        // else can follow this type.
+        while self.pending_gt.is_none() {
             match self.tokens.peek().map(|t| &t.node) {
                 Some(Token::OpenBracket) => ty = self.parse_array_type_postfix(ty)?,
                 Some(Token::Lt) => ty = self.parse_generic_type_annotation(ty)?,
                 Some(Token::DColon) if !parse_path => ty = self.parse_path_type_annotation(ty)?,
+                Some(Token::Question) => {
+                    let hi = self.bump()?.span.hi;
+                    let span = Span::new(ty.span.lo, hi, self.source_id);
+                    ty = SpannedTypeAnnotation::new(TypeAnnotation::Nullable(Box::new(ty)), span);
+                }
                 _ => break,
             }
         }
@@ -536,6 +1035,40 @@ impl<'src, 'diag> Parser<'src, 'diag> {
         ))
     }
 
+    /// Parses the `{ name: value, ... }` of a struct expression starting at
+    /// `lo`, returning the fields and the end of the closing brace.
+    fn parse_field_values(
+        &mut self,
+        lo: BytePos,
+    ) -> InnerParserResult<'diag, (Box<[FieldValue<'src>]>, BytePos)> {
+        expect_token!(self, Token::OpenBrace, Token::OpenBrace.display())?;
+        let mut fields = Vec::new();
+        loop {
+            if self.tokens.peek().is_some_and(|t| t.node == Token::CloseBrace) {
+                let hi = self.bump()?.span.hi;
+                return Ok((fields.into_boxed_slice(), hi));
+            }
+            if self.tokens.peek().is_none() {
+                break;
+            }
+            let name = self.parse_ident()?;
+            expect_token!(self, Token::Colon, Token::Colon.display())?;
+            let value = self.parse_expr(0)?;
+            fields.push(FieldValue { name, value });
+            if self.tokens.peek().is_some_and(|t| t.node == Token::Comma) {
+                self.tokens.next();
+            }
+        }
+        Err(InnerParseErr::without_skip(
+            Diagnostic::error(DiagMessage::new(super::messages::UNTERMINATED_STRUCT, &[]))
+                .add_label(DiagLabel::silent_primary(Span::new(
+                    lo,
+                    self.source_hi,
+                    self.source_id,
+                ))),
+        ))
+    }
+
     fn parse_expr(&mut self, min_bp: u8) -> InnerParserResult<'diag, SpannedExpr<'src>> {
         let mut lhs = {
             let token = self.peek()?;
@@ -546,6 +1079,7 @@ impl<'src, 'diag> Parser<'src, 'diag> {
                 }
                 Token::True
                 | Token::False
+                | Token::Null
                 | Token::CharLiteral(..)
                 | Token::IntLiteral { .. }
                 | Token::FloatLiteral { .. }
@@ -607,6 +1141,27 @@ impl<'src, 'diag> Parser<'src, 'diag> {
                     let span = stmt_block.span;
                     SpannedExpr::new(Expr::Block(stmt_block), span)
                 }
+                Token::Unsafe => {
+                    let lo = self.bump()?.span.lo;
+                    let stmt_block = self.parse_stmt_block()?;
+                    let hi = stmt_block.span.hi;
+                    SpannedExpr::new(Expr::Unsafe(stmt_block), Span::new(lo, hi, self.source_id))
+                }
+                Token::Star => {
+                    let token = self.bump()?;
+                    let operand = self.parse_expr(pratt::rbp(&token.node))?;
+                    let hi = operand.span.hi;
+                    SpannedExpr::new(
+                        Expr::Deref(Box::new(operand)),
+                        Span::new(token.span.lo, hi, self.source_id),
+                    )
+                }
+                Token::If => {
+                    let if_expr = self.parse_if()?;
+                    SpannedExpr::new(Expr::If(if_expr.node), if_expr.span)
+                }
+                Token::For => self.parse_for()?,
+                Token::Or | Token::OrOr => self.parse_closure()?,
                 Token::OpenBracket => {
                     let lo = self.bump()?.span.lo;
                     let expr = self.parse_expr(0)?;
@@ -725,6 +1280,16 @@ impl<'src, 'diag> Parser<'src, 'diag> {
                         Span::new(lo, hi, self.source_id),
                     );
                 }
+                Token::Range | Token::RangeInclusive => {
+                    let inclusive = op_token.node == Token::RangeInclusive;
+                    self.tokens.next();
+                    let end = self.parse_expr(op_lbp + 1)?;
+                    let span = Span::new(lhs.span.lo, end.span.hi, self.source_id);
+                    lhs = SpannedExpr::new(
+                        Expr::Range { start: Box::new(lhs), end: Box::new(end), inclusive },
+                        span,
+                    );
+                }
                 Token::PlusPlus | Token::MinusMinus => {
                     let op = match op_token.node {
                         Token::PlusPlus => UnaryOp::PostInc,
@@ -755,12 +1320,21 @@ impl<'src, 'diag> Parser<'src, 'diag> {
                 }
                 Token::Dot => {
                     self.tokens.next();
-                    let ident_token =
-                        expect_token!(self, Token::Ident(..), token_display::IDENTIFIER)?;
+                    // `pair.0` names a tuple element by its decimal index.
+                    let ident_token = expect_token!(
+                        self,
+                        Token::Ident(..)
+                            | Token::IntLiteral { radix: Radix::Decimal, suffix: None, .. },
+                        token_display::IDENTIFIER
+                    )?;
                     let span = ident_token.span;
                     let lo = lhs.span.lo;
                     let hi = ident_token.span.hi;
-                    let Token::Ident(ident) = ident_token.node else { unreachable!() };
+                    let (Token::Ident(ident) | Token::IntLiteral { digits: ident, .. }) =
+                        ident_token.node
+                    else {
+                        unreachable!()
+                    };
                     lhs = SpannedExpr::new(
                         Expr::AttributeAccess {
                             value: Box::new(lhs),
@@ -770,13 +1344,71 @@ impl<'src, 'diag> Parser<'src, 'diag> {
                     )
                 }
                 Token::DColon => {
-                    todo!()
+                    self.tokens.next();
+                    if self.tokens.peek().is_some_and(|t| t.node == Token::Lt) {
+                        if !matches!(lhs.node, Expr::Ident(_) | Expr::Path(_)) {
+                            return Err(InnerParseErr::with_skip(Self::unexpected_token(
+                                Token::DColon.display(),
+                            )));
+                        }
+                        let (args, hi) = self.parse_generic_args()?;
+                        let span = Span::new(lhs.span.lo, hi, self.source_id);
+                        lhs = SpannedExpr::new(Expr::Turbofish { path: Box::new(lhs), args }, span);
+                        continue;
+                    }
+                    let segment = self.parse_ident()?;
+                    let span = Span::new(lhs.span.lo, segment.span.hi, self.source_id);
+                    let path = match lhs.node {
+                        Expr::Ident(name) => vec![SpannedStr::new(name, lhs.span), segment],
+                        Expr::Path(path) => {
+                            let mut path = path.into_vec();
+                            path.push(segment);
+                            path
+                        }
+                        _ => {
+                            return Err(InnerParseErr::with_skip(Self::unexpected_token(
+                                Token::DColon.display(),
+                            )));
+                        }
+                    };
+                    lhs = SpannedExpr::new(Expr::Path(path.into_boxed_slice()), span);
                 }
                 Token::OpenBracket => {
-                    todo!()
+                    self.tokens.next();
+                    let index = self.parse_expr(0)?;
+                    let hi =
+                        expect_token!(self, Token::CloseBracket, Token::CloseBracket.display())?
+                            .span
+                            .hi;
+                    let span = Span::new(lhs.span.lo, hi, self.source_id);
+                    lhs = SpannedExpr::new(
+                        Expr::Index { value: Box::new(lhs), index: Box::new(index) },
+                        span,
+                    );
+                }
+                Token::Question => {
+                    let hi = self.bump()?.span.hi;
+                    let span = Span::new(lhs.span.lo, hi, self.source_id);
+                    lhs = SpannedExpr::new(Expr::Try(Box::new(lhs)), span);
+                }
+                // Conditions end at the brace of their block, so struct
+                // expressions there must be parenthesized.
+                Token::OpenBrace
+                    if self.in_condition
+                        || !matches!(
+                            lhs.node,
+                            Expr::Ident(_) | Expr::Path(_) | Expr::Turbofish { .. }
+                        ) =>
+                {
+                    break;
                 }
                 Token::OpenBrace => {
-                    todo!()
+                    let lo = lhs.span.lo;
+                    let (fields, hi) = self.parse_field_values(lo)?;
+                    lhs = SpannedExpr::new(
+                        Expr::Struct { path: Box::new(lhs), fields },
+                        Span::new(lo, hi, self.source_id),
+                    );
                 }
                 Token::As => {
                     self.tokens.next();
@@ -884,6 +1516,7 @@ impl<'src, 'diag> Parser<'src, 'diag> {
                 Self::parse_float(literal, suffix, token.span)?
             }
             Token::True => PrimitiveValue::True,
+            Token::Null => PrimitiveValue::Null,
             Token::False => PrimitiveValue::False,
             Token::CharLiteral(char) => PrimitiveValue::Char(char),
             Token::StringLiteral(string) => PrimitiveValue::String(Cow::Owned(string)),
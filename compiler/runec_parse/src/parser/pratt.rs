@@ -4,6 +4,7 @@ use crate::lexer::token::Token;
 
 pub const fn lbp(tok: &Token) -> u8 {
     match tok {
+        Token::Range | Token::RangeInclusive => 30,
         Token::OrOr => 40,
         Token::AndAnd => 50,
         Token::Or => 90,
@@ -15,14 +16,15 @@ pub const fn lbp(tok: &Token) -> u8 {
         Token::Lt | Token::Le | Token::Gt | Token::Ge => 70,
         Token::EqEq | Token::Ne => 60,
         Token::PlusPlus | Token::MinusMinus => 160, // postfix
-        Token::OpenParen | Token::OpenBracket | Token::OpenBrace => 170,
+        Token::OpenParen | Token::OpenBracket | Token::OpenBrace | Token::Question => 170,
+        Token::DColon => 180,
         _ => 0,
     }
 }
 
 pub const fn rbp(tok: &Token) -> u8 {
     match tok {
-        Token::Bang | Token::Tilde | Token::Plus | Token::Minus => 150,
+        Token::Bang | Token::Tilde | Token::Plus | Token::Minus | Token::Star => 150,
         Token::PlusPlus | Token::MinusMinus => 160, // prefix
         _ => 0,
     }
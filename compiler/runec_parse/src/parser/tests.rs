@@ -52,15 +52,18 @@ fn act_parse_test() {
 
     let expected_stmts = [SpannedStmt::new(
         Stmt::DefineFunction {
+            vis: Visibility::Private,
             ident: SpannedStr::new(
                 "main",
                 Span::new(BytePos::from_usize(4), BytePos::from_usize(8), source_id),
             ),
+            generics: Box::new([]),
             args: Box::new([]),
             ret_ty: SpannedTypeAnnotation::new(
                 TypeAnnotation::Unit,
                 Span::new(BytePos::from_usize(10), BytePos::from_usize(10), source_id),
             ),
+            throws: None,
             body: SpannedStmtBlock::new(
                 Box::new([]),
                 Span::new(BytePos::from_usize(11), BytePos::from_usize(13), source_id),
@@ -82,10 +85,12 @@ fn act_with_args_parse_test() {
 
     let expected_stmts = [SpannedStmt::new(
         Stmt::DefineFunction {
+            vis: Visibility::Private,
             ident: SpannedStr::new(
                 "main",
                 Span::new(BytePos::from_usize(4), BytePos::from_usize(8), source_id),
             ),
+            generics: Box::new([]),
             args: Box::new([
                 FunctionArg {
                     ident: SpannedStr::new(
@@ -112,6 +117,7 @@ fn act_with_args_parse_test() {
                 TypeAnnotation::Ident("e"),
                 Span::new(BytePos::from_usize(24), BytePos::from_usize(25), source_id),
             ),
+            throws: None,
             body: SpannedStmtBlock::new(
                 Box::new([]),
                 Span::new(BytePos::from_usize(26), BytePos::from_usize(28), source_id),
@@ -651,3 +657,888 @@ fn attribute_access_call_parse_test() {
         )]
     );
 }
+
+#[test]
+fn if_else_if_parse_test() {
+    let (source_map, source_id) = generate_source("if a { b } else if c {} else { d; }");
+    let tokens = lex_source(&source_map, source_id);
+    let parse_result = Parser::new(tokens, source_id, &source_map).parse_full();
+
+    let span = |lo, hi| Span::new(BytePos::from_usize(lo), BytePos::from_usize(hi), source_id);
+    let ident = |name, lo, hi| Box::new(SpannedExpr::new(Expr::Ident(name), span(lo, hi)));
+    let expected_stmts = [SpannedStmt::new(
+        Stmt::TailExpr(SpannedExpr::new(
+            Expr::If(IfExpr {
+                cond: ident("a", 3, 4),
+                then: SpannedStmtBlock::new(
+                    Box::new([SpannedStmt::new(Stmt::TailExpr(*ident("b", 7, 8)), span(7, 8))]),
+                    span(5, 10),
+                ),
+                else_: Some(ElseBranch::If(Box::new(SpannedIfExpr::new(
+                    IfExpr {
+                        cond: ident("c", 19, 20),
+                        then: SpannedStmtBlock::new(Box::new([]), span(21, 23)),
+                        else_: Some(ElseBranch::Block(SpannedStmtBlock::new(
+                            Box::new([SpannedStmt::new(
+                                Stmt::SemiExpr(*ident("d", 31, 32)),
+                                span(31, 33),
+                            )]),
+                            span(29, 35),
+                        ))),
+                    },
+                    span(16, 35),
+                )))),
+            }),
+            span(0, 35),
+        )),
+        span(0, 35),
+    )];
+
+    assert_eq!(parse_result.diags.len(), 0);
+    assert_eq!(parse_result.stmts, expected_stmts);
+}
+
+#[test]
+fn assign_parse_test() {
+    let (source_map, source_id) = generate_source("a = b; if c { d = e; }");
+    let tokens = lex_source(&source_map, source_id);
+    let parse_result = Parser::new(tokens, source_id, &source_map).parse_full();
+
+    let span = |lo, hi| Span::new(BytePos::from_usize(lo), BytePos::from_usize(hi), source_id);
+    let expected_stmts = [
+        SpannedStmt::new(
+            Stmt::Assign {
+                ident: SpannedStr::new("a", span(0, 1)),
+                expr: SpannedExpr::new(Expr::Ident("b"), span(4, 5)),
+            },
+            span(0, 6),
+        ),
+        SpannedStmt::new(
+            Stmt::TailExpr(SpannedExpr::new(
+                Expr::If(IfExpr {
+                    cond: Box::new(SpannedExpr::new(Expr::Ident("c"), span(10, 11))),
+                    then: SpannedStmtBlock::new(
+                        Box::new([SpannedStmt::new(
+                            Stmt::Assign {
+                                ident: SpannedStr::new("d", span(14, 15)),
+                                expr: SpannedExpr::new(Expr::Ident("e"), span(18, 19)),
+                            },
+                            span(14, 20),
+                        )]),
+                        span(12, 22),
+                    ),
+                    else_: None,
+                }),
+                span(7, 22),
+            )),
+            span(7, 22),
+        ),
+    ];
+
+    assert_eq!(parse_result.diags.len(), 0);
+    assert_eq!(parse_result.stmts, expected_stmts);
+}
+
+#[test]
+fn nullable_let_parse_test() {
+    let (source_map, source_id) = generate_source("let a: b? = null;");
+    let tokens = lex_source(&source_map, source_id);
+    let parse_result = Parser::new(tokens, source_id, &source_map).parse_full();
+
+    let span = |lo, hi| Span::new(BytePos::from_usize(lo), BytePos::from_usize(hi), source_id);
+    let expected_stmts = [SpannedStmt::new(
+        Stmt::DefineLet {
+            pattern: SpannedDestructPattern::new(DestructPattern::Ident("a"), span(4, 5)),
+            is_mutable: false,
+            ty: Some(SpannedTypeAnnotation::new(
+                TypeAnnotation::Nullable(Box::new(SpannedTypeAnnotation::new(
+                    TypeAnnotation::Ident("b"),
+                    span(7, 8),
+                ))),
+                span(7, 9),
+            )),
+            init_expr: Some(SpannedExpr::new(Expr::Primitive(PrimitiveValue::Null), span(12, 16))),
+        },
+        span(0, 17),
+    )];
+
+    assert_eq!(parse_result.diags.len(), 0);
+    assert_eq!(parse_result.stmts, expected_stmts);
+}
+
+#[test]
+fn throws_throw_and_try_parse_test() {
+    let (source_map, source_id) = generate_source("act f() -> a throws b { throw c; g()? }");
+    let tokens = lex_source(&source_map, source_id);
+    let parse_result = Parser::new(tokens, source_id, &source_map).parse_full();
+
+    let span = |lo, hi| Span::new(BytePos::from_usize(lo), BytePos::from_usize(hi), source_id);
+    let call = SpannedExpr::new(
+        Expr::Call {
+            callee: Box::new(SpannedExpr::new(Expr::Ident("g"), span(33, 34))),
+            args: Box::new([]),
+        },
+        span(33, 36),
+    );
+    let expected_stmts = [SpannedStmt::new(
+        Stmt::DefineFunction {
+            vis: Visibility::Private,
+            ident: SpannedStr::new("f", span(4, 5)),
+            generics: Box::new([]),
+            args: Box::new([]),
+            ret_ty: SpannedTypeAnnotation::new(TypeAnnotation::Ident("a"), span(11, 12)),
+            throws: Some(SpannedTypeAnnotation::new(TypeAnnotation::Ident("b"), span(20, 21))),
+            body: SpannedStmtBlock::new(
+                Box::new([
+                    SpannedStmt::new(
+                        Stmt::Throw(SpannedExpr::new(Expr::Ident("c"), span(30, 31))),
+                        span(24, 32),
+                    ),
+                    SpannedStmt::new(
+                        Stmt::TailExpr(SpannedExpr::new(Expr::Try(Box::new(call)), span(33, 37))),
+                        span(33, 37),
+                    ),
+                ]),
+                span(22, 39),
+            ),
+        },
+        span(0, 39),
+    )];
+
+    assert_eq!(parse_result.diags.len(), 0);
+    assert_eq!(parse_result.stmts, expected_stmts);
+}
+
+#[test]
+fn generic_act_parse_test() {
+    let (source_map, source_id) = generate_source("act f<T: a + b, const N: c>() {}");
+    let tokens = lex_source(&source_map, source_id);
+    let parse_result = Parser::new(tokens, source_id, &source_map).parse_full();
+
+    let span = |lo, hi| Span::new(BytePos::from_usize(lo), BytePos::from_usize(hi), source_id);
+    let ident_ty =
+        |name, lo, hi| SpannedTypeAnnotation::new(TypeAnnotation::Ident(name), span(lo, hi));
+    let expected_stmts = [SpannedStmt::new(
+        Stmt::DefineFunction {
+            vis: Visibility::Private,
+            ident: SpannedStr::new("f", span(4, 5)),
+            generics: Box::new([
+                GenericParam {
+                    ident: SpannedStr::new("T", span(6, 7)),
+                    kind: GenericParamKind::Type {
+                        bounds: Box::new([ident_ty("a", 9, 10), ident_ty("b", 13, 14)]),
+                    },
+                },
+                GenericParam {
+                    ident: SpannedStr::new("N", span(22, 23)),
+                    kind: GenericParamKind::Const { ty: ident_ty("c", 25, 26) },
+                },
+            ]),
+            args: Box::new([]),
+            ret_ty: SpannedTypeAnnotation::new(TypeAnnotation::Unit, span(29, 29)),
+            throws: None,
+            body: SpannedStmtBlock::new(Box::new([]), span(30, 32)),
+        },
+        span(0, 32),
+    )];
+
+    assert_eq!(parse_result.diags.len(), 0);
+    assert_eq!(parse_result.stmts, expected_stmts);
+}
+
+#[test]
+fn generic_struct_parse_test() {
+    let (source_map, source_id) = generate_source("struct s<T> { a: v<w<T>>, b: x<T, 3> }");
+    let tokens = lex_source(&source_map, source_id);
+    let parse_result = Parser::new(tokens, source_id, &source_map).parse_full();
+
+    let span = |lo, hi| Span::new(BytePos::from_usize(lo), BytePos::from_usize(hi), source_id);
+    let ident_ty =
+        |name, lo, hi| SpannedTypeAnnotation::new(TypeAnnotation::Ident(name), span(lo, hi));
+    let generic = |ty, args, lo, hi| {
+        SpannedTypeAnnotation::new(TypeAnnotation::Generic { ty: Box::new(ty), args }, span(lo, hi))
+    };
+    let nested = generic(
+        ident_ty("v", 17, 18),
+        Box::new([GenericArgument::Type(generic(
+            ident_ty("w", 19, 20),
+            Box::new([GenericArgument::Type(ident_ty("T", 21, 22))]),
+            19,
+            23,
+        ))]),
+        17,
+        24,
+    );
+    let with_const = generic(
+        ident_ty("x", 29, 30),
+        Box::new([
+            GenericArgument::Type(ident_ty("T", 31, 32)),
+            GenericArgument::Expr(SpannedExpr::new(
+                Expr::Primitive(PrimitiveValue::Int { value: 3, suffix: None }),
+                span(34, 35),
+            )),
+        ]),
+        29,
+        36,
+    );
+    let expected_stmts = [SpannedStmt::new(
+        Stmt::DefineStruct {
+            vis: Visibility::Private,
+            ident: SpannedStr::new("s", span(7, 8)),
+            generics: Box::new([GenericParam {
+                ident: SpannedStr::new("T", span(9, 10)),
+                kind: GenericParamKind::Type { bounds: Box::new([]) },
+            }]),
+            fields: Box::new([
+                StructField {
+                    vis: Visibility::Private,
+                    ident: SpannedStr::new("a", span(14, 15)),
+                    ty: nested,
+                },
+                StructField {
+                    vis: Visibility::Private,
+                    ident: SpannedStr::new("b", span(26, 27)),
+                    ty: with_const,
+                },
+            ]),
+        },
+        span(0, 38),
+    )];
+
+    assert_eq!(parse_result.diags.len(), 0, "{:?}", parse_result.diags);
+    assert_eq!(parse_result.stmts, expected_stmts);
+}
+
+#[test]
+fn import_and_use_parse_test() {
+    let (source_map, source_id) =
+        generate_source("import a::b; use a::b::f as g; use ::a::*; b::f();");
+    let tokens = lex_source(&source_map, source_id);
+    let parse_result = Parser::new(tokens, source_id, &source_map).parse_full();
+
+    let span = |lo, hi| Span::new(BytePos::from_usize(lo), BytePos::from_usize(hi), source_id);
+    let ident = |name, lo, hi| SpannedStr::new(name, span(lo, hi));
+    let expected_stmts = [
+        SpannedStmt::new(
+            Stmt::Import(Box::new([ident("a", 7, 8), ident("b", 10, 11)])),
+            span(0, 12),
+        ),
+        SpannedStmt::new(
+            Stmt::Use {
+                from_root: false,
+                path: Box::new([ident("a", 17, 18), ident("b", 20, 21), ident("f", 23, 24)]),
+                kind: UseKind::Single { alias: Some(ident("g", 28, 29)) },
+            },
+            span(13, 30),
+        ),
+        SpannedStmt::new(
+            Stmt::Use {
+                from_root: true,
+                path: Box::new([ident("a", 37, 38)]),
+                kind: UseKind::Glob,
+            },
+            span(31, 42),
+        ),
+        SpannedStmt::new(
+            Stmt::SemiExpr(SpannedExpr::new(
+                Expr::Call {
+                    callee: Box::new(SpannedExpr::new(
+                        Expr::Path(Box::new([ident("b", 43, 44), ident("f", 46, 47)])),
+                        span(43, 47),
+                    )),
+                    args: Box::new([]),
+                },
+                span(43, 49),
+            )),
+            span(43, 50),
+        ),
+    ];
+
+    assert_eq!(parse_result.diags.len(), 0, "{:?}", parse_result.diags);
+    assert_eq!(parse_result.stmts, expected_stmts);
+}
+
+#[test]
+fn pub_items_fields_and_const_parse_test() {
+    let (source_map, source_id) =
+        generate_source("pub struct p { pub x: int, y: int } pub const n: int = 1;");
+    let tokens = lex_source(&source_map, source_id);
+    let parse_result = Parser::new(tokens, source_id, &source_map).parse_full();
+
+    let span = |lo, hi| Span::new(BytePos::from_usize(lo), BytePos::from_usize(hi), source_id);
+    let int_ty = |lo, hi| SpannedTypeAnnotation::new(TypeAnnotation::Ident("int"), span(lo, hi));
+    let expected_stmts = [
+        SpannedStmt::new(
+            Stmt::DefineStruct {
+                vis: Visibility::Public,
+                ident: SpannedStr::new("p", span(11, 12)),
+                generics: Box::new([]),
+                fields: Box::new([
+                    StructField {
+                        vis: Visibility::Public,
+                        ident: SpannedStr::new("x", span(19, 20)),
+                        ty: int_ty(22, 25),
+                    },
+                    StructField {
+                        vis: Visibility::Private,
+                        ident: SpannedStr::new("y", span(27, 28)),
+                        ty: int_ty(30, 33),
+                    },
+                ]),
+            },
+            span(0, 35),
+        ),
+        SpannedStmt::new(
+            Stmt::DefineConst {
+                vis: Visibility::Public,
+                ident: SpannedStr::new("n", span(46, 47)),
+                ty: int_ty(49, 52),
+                expr: SpannedExpr::new(
+                    Expr::Primitive(PrimitiveValue::Int { value: 1, suffix: None }),
+                    span(55, 56),
+                ),
+            },
+            span(36, 57),
+        ),
+    ];
+
+    assert_eq!(parse_result.diags.len(), 0, "{:?}", parse_result.diags);
+    assert_eq!(parse_result.stmts, expected_stmts);
+}
+
+#[test]
+fn alias_and_union_parse_test() {
+    let (source_map, source_id) =
+        generate_source("pub alias pair<T> = (T, T); union bits { i: int, f: f32 }");
+    let tokens = lex_source(&source_map, source_id);
+    let parse_result = Parser::new(tokens, source_id, &source_map).parse_full();
+
+    let span = |lo, hi| Span::new(BytePos::from_usize(lo), BytePos::from_usize(hi), source_id);
+    let ident_ty =
+        |name, lo, hi| SpannedTypeAnnotation::new(TypeAnnotation::Ident(name), span(lo, hi));
+    let expected_stmts = [
+        SpannedStmt::new(
+            Stmt::DefineAlias {
+                vis: Visibility::Public,
+                ident: SpannedStr::new("pair", span(10, 14)),
+                generics: Box::new([GenericParam {
+                    ident: SpannedStr::new("T", span(15, 16)),
+                    kind: GenericParamKind::Type { bounds: Box::new([]) },
+                }]),
+                ty: SpannedTypeAnnotation::new(
+                    TypeAnnotation::Tuple(Box::new([ident_ty("T", 21, 22), ident_ty("T", 24, 25)])),
+                    span(20, 26),
+                ),
+            },
+            span(0, 27),
+        ),
+        SpannedStmt::new(
+            Stmt::DefineUnion {
+                vis: Visibility::Private,
+                ident: SpannedStr::new("bits", span(34, 38)),
+                generics: Box::new([]),
+                fields: Box::new([
+                    StructField {
+                        vis: Visibility::Private,
+                        ident: SpannedStr::new("i", span(41, 42)),
+                        ty: ident_ty("int", 44, 47),
+                    },
+                    StructField {
+                        vis: Visibility::Private,
+                        ident: SpannedStr::new("f", span(49, 50)),
+                        ty: ident_ty("f32", 52, 55),
+                    },
+                ]),
+            },
+            span(28, 57),
+        ),
+    ];
+
+    assert_eq!(parse_result.diags.len(), 0, "{:?}", parse_result.diags);
+    assert_eq!(parse_result.stmts, expected_stmts);
+}
+
+#[test]
+fn extern_pointer_and_unsafe_parse_test() {
+    let (source_map, source_id) = generate_source(
+        "extern act puts(s: *const u8) -> i32; act f(p: *mut i32) -> i32 { unsafe { *p } }",
+    );
+    let tokens = lex_source(&source_map, source_id);
+    let parse_result = Parser::new(tokens, source_id, &source_map).parse_full();
+
+    let span = |lo, hi| Span::new(BytePos::from_usize(lo), BytePos::from_usize(hi), source_id);
+    let ident_ty =
+        |name, lo, hi| SpannedTypeAnnotation::new(TypeAnnotation::Ident(name), span(lo, hi));
+    let expected_stmts = [
+        SpannedStmt::new(
+            Stmt::DefineExternFunction {
+                vis: Visibility::Private,
+                ident: SpannedStr::new("puts", span(11, 15)),
+                args: Box::new([FunctionArg {
+                    ident: SpannedStr::new("s", span(16, 17)),
+                    ty: SpannedTypeAnnotation::new(
+                        TypeAnnotation::Pointer {
+                            mutable: false,
+                            pointee: Box::new(ident_ty("u8", 26, 28)),
+                        },
+                        span(19, 28),
+                    ),
+                }]),
+                ret_ty: ident_ty("i32", 33, 36),
+            },
+            span(0, 37),
+        ),
+        SpannedStmt::new(
+            Stmt::DefineFunction {
+                vis: Visibility::Private,
+                ident: SpannedStr::new("f", span(42, 43)),
+                generics: Box::new([]),
+                args: Box::new([FunctionArg {
+                    ident: SpannedStr::new("p", span(44, 45)),
+                    ty: SpannedTypeAnnotation::new(
+                        TypeAnnotation::Pointer {
+                            mutable: true,
+                            pointee: Box::new(ident_ty("i32", 52, 55)),
+                        },
+                        span(47, 55),
+                    ),
+                }]),
+                ret_ty: ident_ty("i32", 60, 63),
+                throws: None,
+                body: SpannedStmtBlock::new(
+                    Box::new([SpannedStmt::new(
+                        Stmt::TailExpr(SpannedExpr::new(
+                            Expr::Unsafe(SpannedStmtBlock::new(
+                                Box::new([SpannedStmt::new(
+                                    Stmt::TailExpr(SpannedExpr::new(
+                                        Expr::Deref(Box::new(SpannedExpr::new(
+                                            Expr::Ident("p"),
+                                            span(76, 77),
+                                        ))),
+                                        span(75, 77),
+                                    )),
+                                    span(75, 77),
+                                )]),
+                                span(73, 79),
+                            )),
+                            span(66, 79),
+                        )),
+                        span(66, 79),
+                    )]),
+                    span(64, 81),
+                ),
+            },
+            span(38, 81),
+        ),
+    ];
+
+    assert_eq!(parse_result.diags.len(), 0, "{:?}", parse_result.diags);
+    assert_eq!(parse_result.stmts, expected_stmts);
+}
+
+#[test]
+fn deref_assign_parse_test() {
+    let (source_map, source_id) = generate_source("*p = *q;");
+    let tokens = lex_source(&source_map, source_id);
+    let parse_result = Parser::new(tokens, source_id, &source_map).parse_full();
+
+    let span = |lo, hi| Span::new(BytePos::from_usize(lo), BytePos::from_usize(hi), source_id);
+    let deref = |name, lo| {
+        SpannedExpr::new(
+            Expr::Deref(Box::new(SpannedExpr::new(Expr::Ident(name), span(lo + 1, lo + 2)))),
+            span(lo, lo + 2),
+        )
+    };
+    let expected_stmts = [SpannedStmt::new(
+        Stmt::AssignPlace { target: deref("p", 0), expr: deref("q", 5) },
+        span(0, 8),
+    )];
+
+    assert_eq!(parse_result.diags.len(), 0, "{:?}", parse_result.diags);
+    assert_eq!(parse_result.stmts, expected_stmts);
+}
+
+#[test]
+fn closure_and_function_type_parse_test() {
+    let (source_map, source_id) =
+        generate_source("let f: act(int) -> int = |x| x + 1; let g = || 2;");
+    let tokens = lex_source(&source_map, source_id);
+    let parse_result = Parser::new(tokens, source_id, &source_map).parse_full();
+
+    let span = |lo, hi| Span::new(BytePos::from_usize(lo), BytePos::from_usize(hi), source_id);
+    let ident_ty =
+        |name, lo, hi| SpannedTypeAnnotation::new(TypeAnnotation::Ident(name), span(lo, hi));
+    let int = |value, lo, hi| {
+        SpannedExpr::new(Expr::Primitive(PrimitiveValue::Int { value, suffix: None }), span(lo, hi))
+    };
+    let expected_stmts = [
+        SpannedStmt::new(
+            Stmt::DefineLet {
+                pattern: SpannedDestructPattern::new(DestructPattern::Ident("f"), span(4, 5)),
+                is_mutable: false,
+                ty: Some(SpannedTypeAnnotation::new(
+                    TypeAnnotation::Function {
+                        params: Box::new([ident_ty("int", 11, 14)]),
+                        ret: Box::new(ident_ty("int", 19, 22)),
+                    },
+                    span(7, 22),
+                )),
+                init_expr: Some(SpannedExpr::new(
+                    Expr::Closure {
+                        params: Box::new([ClosureParam {
+                            ident: SpannedStr::new("x", span(26, 27)),
+                            ty: None,
+                        }]),
+                        body: Box::new(SpannedExpr::new(
+                            Expr::Binary {
+                                lhs: Box::new(SpannedExpr::new(Expr::Ident("x"), span(29, 30))),
+                                rhs: Box::new(int(1, 33, 34)),
+                                op: BinaryOp::Add,
+                            },
+                            span(29, 34),
+                        )),
+                    },
+                    span(25, 34),
+                )),
+            },
+            span(0, 35),
+        ),
+        SpannedStmt::new(
+            Stmt::DefineLet {
+                pattern: SpannedDestructPattern::new(DestructPattern::Ident("g"), span(40, 41)),
+                is_mutable: false,
+                ty: None,
+                init_expr: Some(SpannedExpr::new(
+                    Expr::Closure { params: Box::new([]), body: Box::new(int(2, 47, 48)) },
+                    span(44, 48),
+                )),
+            },
+            span(36, 49),
+        ),
+    ];
+
+    assert_eq!(parse_result.diags.len(), 0, "{:?}", parse_result.diags);
+    assert_eq!(parse_result.stmts, expected_stmts);
+}
+
+#[test]
+fn range_and_for_parse_test() {
+    let (source_map, source_id) = generate_source("for i in 0..n + 1 { f(i); } let r = 1..=3;");
+    let tokens = lex_source(&source_map, source_id);
+    let parse_result = Parser::new(tokens, source_id, &source_map).parse_full();
+
+    let span = |lo, hi| Span::new(BytePos::from_usize(lo), BytePos::from_usize(hi), source_id);
+    let int = |value, lo, hi| {
+        SpannedExpr::new(Expr::Primitive(PrimitiveValue::Int { value, suffix: None }), span(lo, hi))
+    };
+    let ident = |name, lo, hi| SpannedExpr::new(Expr::Ident(name), span(lo, hi));
+    let expected_stmts = [
+        SpannedStmt::new(
+            Stmt::TailExpr(SpannedExpr::new(
+                Expr::For {
+                    binding: SpannedDestructPattern::new(DestructPattern::Ident("i"), span(4, 5)),
+                    iter: Box::new(SpannedExpr::new(
+                        Expr::Range {
+                            start: Box::new(int(0, 9, 10)),
+                            end: Box::new(SpannedExpr::new(
+                                Expr::Binary {
+                                    lhs: Box::new(ident("n", 12, 13)),
+                                    rhs: Box::new(int(1, 16, 17)),
+                                    op: BinaryOp::Add,
+                                },
+                                span(12, 17),
+                            )),
+                            inclusive: false,
+                        },
+                        span(9, 17),
+                    )),
+                    body: SpannedStmtBlock::new(
+                        Box::new([SpannedStmt::new(
+                            Stmt::SemiExpr(SpannedExpr::new(
+                                Expr::Call {
+                                    callee: Box::new(ident("f", 20, 21)),
+                                    args: Box::new([ident("i", 22, 23)]),
+                                },
+                                span(20, 24),
+                            )),
+                            span(20, 25),
+                        )]),
+                        span(18, 27),
+                    ),
+                },
+                span(0, 27),
+            )),
+            span(0, 27),
+        ),
+        SpannedStmt::new(
+            Stmt::DefineLet {
+                pattern: SpannedDestructPattern::new(DestructPattern::Ident("r"), span(32, 33)),
+                is_mutable: false,
+                ty: None,
+                init_expr: Some(SpannedExpr::new(
+                    Expr::Range {
+                        start: Box::new(int(1, 36, 37)),
+                        end: Box::new(int(3, 40, 41)),
+                        inclusive: true,
+                    },
+                    span(36, 41),
+                )),
+            },
+            span(28, 42),
+        ),
+    ];
+
+    assert_eq!(parse_result.diags.len(), 0, "{:?}", parse_result.diags);
+    assert_eq!(parse_result.stmts, expected_stmts);
+}
+
+#[test]
+fn index_and_slice_parse_test() {
+    let (source_map, source_id) = generate_source("let s: i32[] = v[1..n]; v[0] = s[0];");
+    let tokens = lex_source(&source_map, source_id);
+    let parse_result = Parser::new(tokens, source_id, &source_map).parse_full();
+
+    let span = |lo, hi| Span::new(BytePos::from_usize(lo), BytePos::from_usize(hi), source_id);
+    let int = |value, lo, hi| {
+        SpannedExpr::new(Expr::Primitive(PrimitiveValue::Int { value, suffix: None }), span(lo, hi))
+    };
+    let ident = |name, lo, hi| SpannedExpr::new(Expr::Ident(name), span(lo, hi));
+    let index = |value: SpannedExpr<'static>, index, hi| {
+        let lo = value.span.lo;
+        SpannedExpr::new(
+            Expr::Index { value: Box::new(value), index: Box::new(index) },
+            Span::new(lo, BytePos::from_usize(hi), source_id),
+        )
+    };
+    let expected_stmts = [
+        SpannedStmt::new(
+            Stmt::DefineLet {
+                pattern: SpannedDestructPattern::new(DestructPattern::Ident("s"), span(4, 5)),
+                is_mutable: false,
+                ty: Some(SpannedTypeAnnotation::new(
+                    TypeAnnotation::Slice(Box::new(SpannedTypeAnnotation::new(
+                        TypeAnnotation::Ident("i32"),
+                        span(7, 10),
+                    ))),
+                    span(7, 12),
+                )),
+                init_expr: Some(index(
+                    ident("v", 15, 16),
+                    SpannedExpr::new(
+                        Expr::Range {
+                            start: Box::new(int(1, 17, 18)),
+                            end: Box::new(ident("n", 20, 21)),
+                            inclusive: false,
+                        },
+                        span(17, 21),
+                    ),
+                    22,
+                )),
+            },
+            span(0, 23),
+        ),
+        SpannedStmt::new(
+            Stmt::AssignPlace {
+                target: index(ident("v", 24, 25), int(0, 26, 27), 28),
+                expr: index(ident("s", 31, 32), int(0, 33, 34), 35),
+            },
+            span(24, 36),
+        ),
+    ];
+
+    assert_eq!(parse_result.diags.len(), 0, "{:?}", parse_result.diags);
+    assert_eq!(parse_result.stmts, expected_stmts);
+}
+
+#[test]
+fn turbofish_parse_test() {
+    let (source_map, source_id) = generate_source("mem::size_of::<i32>(); f()::<i32>;");
+    let tokens = lex_source(&source_map, source_id);
+    let parse_result = Parser::new(tokens, source_id, &source_map).parse_full();
+
+    let span = |lo, hi| Span::new(BytePos::from_usize(lo), BytePos::from_usize(hi), source_id);
+    let ident = |name, lo, hi| SpannedStr::new(name, span(lo, hi));
+    let expected_stmt = SpannedStmt::new(
+        Stmt::SemiExpr(SpannedExpr::new(
+            Expr::Call {
+                callee: Box::new(SpannedExpr::new(
+                    Expr::Turbofish {
+                        path: Box::new(SpannedExpr::new(
+                            Expr::Path(Box::new([ident("mem", 0, 3), ident("size_of", 5, 12)])),
+                            span(0, 12),
+                        )),
+                        args: Box::new([GenericArgument::Type(SpannedTypeAnnotation::new(
+                            TypeAnnotation::Ident("i32"),
+                            span(15, 18),
+                        ))]),
+                    },
+                    span(0, 19),
+                )),
+                args: Box::new([]),
+            },
+            span(0, 21),
+        )),
+        span(0, 22),
+    );
+
+    assert_eq!(parse_result.stmts.first(), Some(&expected_stmt));
+    // Only paths take generic arguments.
+    assert_eq!(parse_result.diags.len(), 1, "{:?}", parse_result.diags);
+}
+
+#[test]
+fn turbofish_struct_expr_parse_test() {
+    let (source_map, source_id) = generate_source("Box::<str, 3> { v: x };");
+    let tokens = lex_source(&source_map, source_id);
+    let parse_result = Parser::new(tokens, source_id, &source_map).parse_full();
+
+    let span = |lo, hi| Span::new(BytePos::from_usize(lo), BytePos::from_usize(hi), source_id);
+    let ident = |name, lo, hi| SpannedStr::new(name, span(lo, hi));
+    let expected_stmt = SpannedStmt::new(
+        Stmt::SemiExpr(SpannedExpr::new(
+            Expr::Struct {
+                path: Box::new(SpannedExpr::new(
+                    Expr::Turbofish {
+                        path: Box::new(SpannedExpr::new(Expr::Ident("Box"), span(0, 3))),
+                        args: Box::new([
+                            GenericArgument::Type(SpannedTypeAnnotation::new(
+                                TypeAnnotation::Ident("str"),
+                                span(6, 9),
+                            )),
+                            GenericArgument::Expr(SpannedExpr::new(
+                                Expr::Primitive(PrimitiveValue::Int { value: 3, suffix: None }),
+                                span(11, 12),
+                            )),
+                        ]),
+                    },
+                    span(0, 13),
+                )),
+                fields: Box::new([FieldValue {
+                    name: ident("v", 16, 17),
+                    value: SpannedExpr::new(Expr::Ident("x"), span(19, 20)),
+                }]),
+            },
+            span(0, 22),
+        )),
+        span(0, 23),
+    );
+
+    assert_eq!(parse_result.diags.len(), 0, "{:?}", parse_result.diags);
+    assert_eq!(parse_result.stmts.first(), Some(&expected_stmt));
+}
+
+#[test]
+fn struct_expr_parse_test() {
+    let (source_map, source_id) =
+        generate_source("let p = Shape::Rect { w: 1, h: x }; if ok { p; }");
+    let tokens = lex_source(&source_map, source_id);
+    let parse_result = Parser::new(tokens, source_id, &source_map).parse_full();
+
+    let span = |lo, hi| Span::new(BytePos::from_usize(lo), BytePos::from_usize(hi), source_id);
+    let ident = |name, lo, hi| SpannedStr::new(name, span(lo, hi));
+    let expected_stmt = SpannedStmt::new(
+        Stmt::DefineLet {
+            pattern: SpannedDestructPattern::new(DestructPattern::Ident("p"), span(4, 5)),
+            is_mutable: false,
+            ty: None,
+            init_expr: Some(SpannedExpr::new(
+                Expr::Struct {
+                    path: Box::new(SpannedExpr::new(
+                        Expr::Path(Box::new([ident("Shape", 8, 13), ident("Rect", 15, 19)])),
+                        span(8, 19),
+                    )),
+                    fields: Box::new([
+                        FieldValue {
+                            name: ident("w", 22, 23),
+                            value: SpannedExpr::new(
+                                Expr::Primitive(PrimitiveValue::Int { value: 1, suffix: None }),
+                                span(25, 26),
+                            ),
+                        },
+                        FieldValue {
+                            name: ident("h", 28, 29),
+                            value: SpannedExpr::new(Expr::Ident("x"), span(31, 32)),
+                        },
+                    ]),
+                },
+                span(8, 34),
+            )),
+        },
+        span(0, 35),
+    );
+
+    assert_eq!(parse_result.diags.len(), 0, "{:?}", parse_result.diags);
+    assert_eq!(parse_result.stmts.first(), Some(&expected_stmt));
+    // The brace after a condition opens the block of the `if`.
+    assert!(matches!(
+        parse_result.stmts.get(1).map(|stmt| &stmt.node),
+        Some(
+            Stmt::SemiExpr(SpannedExpr { node: Expr::If(_), .. })
+                | Stmt::TailExpr(SpannedExpr { node: Expr::If(_), .. })
+        )
+    ));
+}
+
+#[test]
+fn break_parse_test() {
+    let (source_map, source_id) = generate_source("for x in xs { break; }");
+    let tokens = lex_source(&source_map, source_id);
+    let parse_result = Parser::new(tokens, source_id, &source_map).parse_full();
+
+    let span = |lo, hi| Span::new(BytePos::from_usize(lo), BytePos::from_usize(hi), source_id);
+    let expected_stmts = [SpannedStmt::new(
+        Stmt::TailExpr(SpannedExpr::new(
+            Expr::For {
+                binding: SpannedDestructPattern::new(DestructPattern::Ident("x"), span(4, 5)),
+                iter: Box::new(SpannedExpr::new(Expr::Ident("xs"), span(9, 11))),
+                body: SpannedStmtBlock::new(
+                    Box::new([SpannedStmt::new(Stmt::Break, span(14, 20))]),
+                    span(12, 22),
+                ),
+            },
+            span(0, 22),
+        )),
+        span(0, 22),
+    )];
+
+    assert_eq!(parse_result.diags.len(), 0, "{:?}", parse_result.diags);
+    assert_eq!(parse_result.stmts, expected_stmts);
+}
+
+#[test]
+fn tuple_for_pattern_and_element_access_parse_test() {
+    let (source_map, source_id) = generate_source("for (i, x) in xs { p.0; }");
+    let tokens = lex_source(&source_map, source_id);
+    let parse_result = Parser::new(tokens, source_id, &source_map).parse_full();
+
+    let span = |lo, hi| Span::new(BytePos::from_usize(lo), BytePos::from_usize(hi), source_id);
+    let pattern =
+        |name, lo, hi| SpannedDestructPattern::new(DestructPattern::Ident(name), span(lo, hi));
+    let element = SpannedExpr::new(
+        Expr::AttributeAccess {
+            value: Box::new(SpannedExpr::new(Expr::Ident("p"), span(19, 20))),
+            name: SpannedStr::new("0", span(21, 22)),
+        },
+        span(19, 22),
+    );
+    let expected_stmts = [SpannedStmt::new(
+        Stmt::TailExpr(SpannedExpr::new(
+            Expr::For {
+                binding: SpannedDestructPattern::new(
+                    DestructPattern::Tuple(Box::new([pattern("i", 5, 6), pattern("x", 8, 9)])),
+                    span(4, 10),
+                ),
+                iter: Box::new(SpannedExpr::new(Expr::Ident("xs"), span(14, 16))),
+                body: SpannedStmtBlock::new(
+                    Box::new([SpannedStmt::new(Stmt::SemiExpr(element), span(19, 23))]),
+                    span(17, 25),
+                ),
+            },
+            span(0, 25),
+        )),
+        span(0, 25),
+    )];
+
+    assert_eq!(parse_result.diags.len(), 0, "{:?}", parse_result.diags);
+    assert_eq!(parse_result.stmts, expected_stmts);
+}
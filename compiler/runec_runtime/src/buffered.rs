@@ -0,0 +1,429 @@
+//! Buffered standard output and the readers and writers of `std::buffered`.
+//!
+//! `print` and `println` collect their output in a process-wide buffer that
+//! is written out once it fills up, before `input` prompts, on `flush`, when
+//! an error escapes `main` and when the process exits. Programs that need
+//! every write to reach the terminal at once can switch the buffer off with
+//! [`__runeway_set_stdout_buffered`].
+//!
+//! Readers and writers are managed payloads over a file or the standard
+//! streams. A writer flushes what it holds when its last reference goes
+//! away. Operations that can fail hand back a [`Fallible`] like those of
+//! `std::files`.
+//!
+//! A reader over standard input locks it only while it reads a line, so
+//! `input` and other readers can take turns with it, and writes out the
+//! buffered output first like `input` does. The iterator returned by
+//! `lines` reads one line each time it is advanced.
+
+use std::cell::Cell;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::ptr;
+use std::sync::{Mutex, Once, PoisonError};
+
+use crate::files::{Fallible, hand_out, str_from_abi};
+use crate::heap::{__runeway_alloc, __runeway_release, __runeway_retain, __runeway_set_drop};
+use crate::{RawStr, bytes_from_abi, read_line};
+
+/// Output is written out once this many bytes are pending.
+const STDOUT_CAPACITY: usize = 8 * 1024;
+
+struct StdoutBuffer {
+    pending: Vec<u8>,
+    unbuffered: bool,
+}
+
+static STDOUT: Mutex<StdoutBuffer> =
+    Mutex::new(StdoutBuffer { pending: Vec::new(), unbuffered: false });
+static FLUSH_AT_EXIT: Once = Once::new();
+
+unsafe extern "C" {
+    fn atexit(callback: extern "C" fn()) -> i32;
+}
+
+extern "C" fn flush_at_exit() {
+    __runeway_flush();
+}
+
+/// Writes the parts to standard output through the buffer, or directly when
+/// it is switched off.
+pub(crate) fn write_stdout(parts: &[&[u8]]) {
+    let mut stdout = STDOUT.lock().unwrap_or_else(PoisonError::into_inner);
+    for part in parts {
+        stdout.pending.extend_from_slice(part);
+    }
+    if stdout.unbuffered || stdout.pending.len() >= STDOUT_CAPACITY {
+        write_pending(&mut stdout);
+    } else {
+        // SAFETY: `flush_at_exit` only takes the lock, which is not held
+        // while the process exits.
+        FLUSH_AT_EXIT.call_once(|| unsafe {
+            atexit(flush_at_exit);
+        });
+    }
+}
+
+fn write_pending(stdout: &mut StdoutBuffer) {
+    let mut out = io::stdout().lock();
+    let _ = out.write_all(&stdout.pending);
+    let _ = out.flush();
+    stdout.pending.clear();
+}
+
+#[unsafe(no_mangle)]
+/// Writes out everything `print` and `println` have buffered.
+pub extern "C" fn __runeway_flush() {
+    write_pending(&mut STDOUT.lock().unwrap_or_else(PoisonError::into_inner));
+}
+
+#[unsafe(no_mangle)]
+/// Switches the standard output buffer on or off, writing out what it holds
+/// first.
+pub extern "C" fn __runeway_set_stdout_buffered(buffered: bool) {
+    let mut stdout = STDOUT.lock().unwrap_or_else(PoisonError::into_inner);
+    write_pending(&mut stdout);
+    stdout.unbuffered = !buffered;
+}
+
+/// Standard output as seen by a writer, going through the shared buffer.
+struct Stdout;
+
+impl Write for Stdout {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        write_stdout(&[bytes]);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        __runeway_flush();
+        Ok(())
+    }
+}
+
+/// Where a reader takes its lines from.
+enum Source {
+    Stdin(io::Stdin),
+    File(BufReader<File>),
+}
+
+/// Payload of a reader.
+struct Reader {
+    source: Source,
+}
+
+impl Reader {
+    fn read_line(&mut self) -> Option<Box<str>> {
+        match &mut self.source {
+            Source::Stdin(stdin) => {
+                __runeway_flush();
+                read_line(&mut stdin.lock())
+            }
+            Source::File(file) => read_line(file),
+        }
+    }
+}
+
+/// Payload of the iterator returned by `lines`, which holds a reference to
+/// its reader.
+struct Lines {
+    reader: *mut u8,
+}
+
+impl Drop for Lines {
+    fn drop(&mut self) {
+        // SAFETY: The iterator owns one reference to its reader.
+        unsafe { __runeway_release(self.reader) };
+    }
+}
+
+/// Payload of a writer, named after its file for error messages.
+struct Writer {
+    name: Box<str>,
+    sink: Box<dyn Write>,
+}
+
+impl Drop for Writer {
+    fn drop(&mut self) {
+        let _ = self.sink.flush();
+    }
+}
+
+thread_local! {
+    static OPEN_RESULT: Cell<Fallible<*mut u8>> = const { Cell::new(Fallible::EMPTY) };
+    static WRITE_RESULT: Cell<Fallible<()>> = const { Cell::new(Fallible::EMPTY) };
+    static LINE_SLOT: Cell<RawStr> = const { Cell::new(RawStr::EMPTY) };
+}
+
+unsafe extern "C" fn drop_reader(payload: *mut u8) {
+    // SAFETY: Registered only for payloads created by `alloc_reader`.
+    unsafe { ptr::drop_in_place(payload.cast::<Reader>()) };
+}
+
+unsafe extern "C" fn drop_lines(payload: *mut u8) {
+    // SAFETY: Registered only for payloads created by `__runeway_reader_lines`.
+    unsafe { ptr::drop_in_place(payload.cast::<Lines>()) };
+}
+
+unsafe extern "C" fn drop_writer(payload: *mut u8) {
+    // SAFETY: Registered only for payloads created by `alloc_writer`.
+    unsafe { ptr::drop_in_place(payload.cast::<Writer>()) };
+}
+
+fn alloc_reader(reader: Reader) -> *mut u8 {
+    let payload = __runeway_alloc(size_of::<Reader>(), align_of::<Reader>());
+    // SAFETY: The payload was just allocated with the size and alignment of
+    // `Reader`.
+    unsafe {
+        payload.cast::<Reader>().write(reader);
+        __runeway_set_drop(payload, drop_reader);
+    }
+    payload
+}
+
+fn alloc_writer(writer: Writer) -> *mut u8 {
+    let payload = __runeway_alloc(size_of::<Writer>(), align_of::<Writer>());
+    // SAFETY: The payload was just allocated with the size and alignment of
+    // `Writer`.
+    unsafe {
+        payload.cast::<Writer>().write(writer);
+        __runeway_set_drop(payload, drop_writer);
+    }
+    payload
+}
+
+#[unsafe(no_mangle)]
+/// Opens the file at the path for reading.
+///
+/// # Safety
+///
+/// `path_ptr` must be null only when `path_len` is zero. Otherwise it must
+/// point to `path_len` readable bytes that remain valid for the duration of
+/// the call.
+pub unsafe extern "C" fn __runeway_reader_open(
+    path_ptr: *const u8,
+    path_len: usize,
+) -> *const Fallible<*mut u8> {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let path = unsafe { str_from_abi(path_ptr, path_len) };
+    let result = File::open(&path)
+        .map(|file| alloc_reader(Reader { source: Source::File(BufReader::new(file)) }));
+    hand_out(&OPEN_RESULT, ("open", path.as_ref()), result)
+}
+
+#[unsafe(no_mangle)]
+/// Reader over standard input.
+pub extern "C" fn __runeway_reader_stdin() -> *mut u8 {
+    alloc_reader(Reader { source: Source::Stdin(io::stdin()) })
+}
+
+/// Hands the line out through the line slot, or null at the end of input.
+fn hand_out_line(line: Option<Box<str>>) -> *const RawStr {
+    let Some(line) = line else {
+        return ptr::null();
+    };
+    LINE_SLOT.with(|cell| {
+        cell.set(RawStr::alloc(&line));
+        cell.as_ptr().cast_const()
+    })
+}
+
+#[unsafe(no_mangle)]
+/// Reads the next line without its line terminator. Returns a pointer to
+/// the line, which the caller owns, or null at the end of the input.
+///
+/// # Safety
+///
+/// `reader` must be a live reader.
+pub unsafe extern "C" fn __runeway_reader_read_line(reader: *mut u8) -> *const RawStr {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let reader = unsafe { &mut *reader.cast::<Reader>() };
+    hand_out_line(reader.read_line())
+}
+
+#[unsafe(no_mangle)]
+/// Returns an iterator over the remaining lines, which takes a reference to
+/// the reader.
+///
+/// # Safety
+///
+/// `reader` must be a live reader.
+pub unsafe extern "C" fn __runeway_reader_lines(reader: *mut u8) -> *mut u8 {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    unsafe { __runeway_retain(reader) };
+    let payload = __runeway_alloc(size_of::<Lines>(), align_of::<Lines>());
+    // SAFETY: The payload was just allocated with the size and alignment of
+    // `Lines`.
+    unsafe {
+        payload.cast::<Lines>().write(Lines { reader });
+        __runeway_set_drop(payload, drop_lines);
+    }
+    payload
+}
+
+#[unsafe(no_mangle)]
+/// Reads the next line of the iterator's reader, like
+/// [`__runeway_reader_read_line`].
+///
+/// # Safety
+///
+/// `lines` must be a live iterator created by [`__runeway_reader_lines`].
+pub unsafe extern "C" fn __runeway_lines_next(lines: *mut u8) -> *const RawStr {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let reader = unsafe { (*lines.cast::<Lines>()).reader };
+    // SAFETY: The iterator keeps its reader alive.
+    unsafe { __runeway_reader_read_line(reader) }
+}
+
+#[unsafe(no_mangle)]
+/// Creates the file at the path, or empties it, for writing.
+///
+/// # Safety
+///
+/// Same contract as [`__runeway_reader_open`].
+pub unsafe extern "C" fn __runeway_writer_create(
+    path_ptr: *const u8,
+    path_len: usize,
+) -> *const Fallible<*mut u8> {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let path = unsafe { str_from_abi(path_ptr, path_len) };
+    let result = File::create(&path).map(|file| {
+        alloc_writer(Writer { name: path.as_str().into(), sink: Box::new(BufWriter::new(file)) })
+    });
+    hand_out(&OPEN_RESULT, ("create", path.as_ref()), result)
+}
+
+#[unsafe(no_mangle)]
+/// Writer over standard output, sharing the buffer of `print`.
+pub extern "C" fn __runeway_writer_stdout() -> *mut u8 {
+    alloc_writer(Writer { name: "<stdout>".into(), sink: Box::new(Stdout) })
+}
+
+/// Writes the parts with the writer, failing with a message naming it.
+///
+/// # Safety
+///
+/// `writer` must be a live writer.
+unsafe fn write_parts(writer: *mut u8, parts: &[&[u8]]) -> *const Fallible<()> {
+    // SAFETY: Forwarded directly from the caller.
+    let writer = unsafe { &mut *writer.cast::<Writer>() };
+    let result = parts.iter().try_for_each(|part| writer.sink.write_all(part));
+    hand_out(&WRITE_RESULT, ("write to", Path::new(&*writer.name)), result)
+}
+
+#[unsafe(no_mangle)]
+/// Writes the text.
+///
+/// # Safety
+///
+/// `writer` must be a live writer, and `(ptr, len)` must follow the contract
+/// of [`__runeway_reader_open`].
+pub unsafe extern "C" fn __runeway_writer_write(
+    writer: *mut u8,
+    ptr: *const u8,
+    len: usize,
+) -> *const Fallible<()> {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let text = unsafe { bytes_from_abi(ptr, len) }.unwrap_or_default();
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    unsafe { write_parts(writer, &[text]) }
+}
+
+#[unsafe(no_mangle)]
+/// Writes the text followed by a newline.
+///
+/// # Safety
+///
+/// Same contract as [`__runeway_writer_write`].
+pub unsafe extern "C" fn __runeway_writer_write_line(
+    writer: *mut u8,
+    ptr: *const u8,
+    len: usize,
+) -> *const Fallible<()> {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let text = unsafe { bytes_from_abi(ptr, len) }.unwrap_or_default();
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    unsafe { write_parts(writer, &[text, b"\n"]) }
+}
+
+#[unsafe(no_mangle)]
+/// Writes out everything the writer holds.
+///
+/// # Safety
+///
+/// `writer` must be a live writer.
+pub unsafe extern "C" fn __runeway_writer_flush(writer: *mut u8) -> *const Fallible<()> {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let writer = unsafe { &mut *writer.cast::<Writer>() };
+    let result = writer.sink.flush();
+    hand_out(&WRITE_RESULT, ("write to", Path::new(&*writer.name)), result)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::{fs, process};
+
+    use super::{
+        __runeway_lines_next, __runeway_reader_lines, __runeway_reader_open,
+        __runeway_reader_read_line, __runeway_writer_create, __runeway_writer_flush,
+        __runeway_writer_write, __runeway_writer_write_line,
+    };
+    use crate::heap::__runeway_release;
+    use crate::heap_stats;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("runeway-buffered-{}-{name}", process::id()))
+    }
+
+    #[test]
+    fn writes_through_a_buffer_flushed_on_demand_and_on_release() {
+        let path = temp_path("writer.txt");
+        let name = path.to_str().unwrap();
+        let before = heap_stats().live_allocations();
+        // SAFETY: The writer is live until released below.
+        unsafe {
+            let writer =
+                __runeway_writer_create(name.as_ptr(), name.len()).read().into_result().unwrap();
+            __runeway_writer_write(writer, "one".as_ptr(), 3).read().into_result().unwrap();
+            __runeway_writer_write_line(writer, "!".as_ptr(), 1).read().into_result().unwrap();
+            assert_eq!(fs::read_to_string(&path).unwrap(), "");
+            __runeway_writer_flush(writer).read().into_result().unwrap();
+            assert_eq!(fs::read_to_string(&path).unwrap(), "one!\n");
+            __runeway_writer_write_line(writer, "two".as_ptr(), 3).read().into_result().unwrap();
+            __runeway_release(writer);
+        }
+        assert_eq!(fs::read_to_string(&path).unwrap(), "one!\ntwo\n");
+        assert_eq!(heap_stats().live_allocations(), before);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn reads_lines_one_at_a_time_or_through_an_iterator() {
+        let path = temp_path("reader.txt");
+        fs::write(&path, "first\r\nsecond\nthird\n").unwrap();
+        let name = path.to_str().unwrap();
+        let before = heap_stats().live_allocations();
+        // SAFETY: The reader and iterator are live until released below.
+        unsafe {
+            let reader =
+                __runeway_reader_open(name.as_ptr(), name.len()).read().into_result().unwrap();
+            assert_eq!(__runeway_reader_read_line(reader).read().into_string(), "first");
+            let lines = __runeway_reader_lines(reader);
+            __runeway_release(reader);
+            assert_eq!(__runeway_lines_next(lines).read().into_string(), "second");
+            assert_eq!(__runeway_lines_next(lines).read().into_string(), "third");
+            assert!(__runeway_lines_next(lines).is_null());
+            __runeway_release(lines);
+        }
+        assert_eq!(heap_stats().live_allocations(), before);
+        fs::remove_file(path).unwrap();
+
+        let missing = temp_path("missing.txt");
+        let name = missing.to_str().unwrap();
+        // SAFETY: `name` is valid for the call.
+        let error = unsafe { __runeway_reader_open(name.as_ptr(), name.len()).read() };
+        assert!(error.into_result().unwrap_err().starts_with("cannot open `"));
+    }
+}
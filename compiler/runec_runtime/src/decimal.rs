@@ -0,0 +1,541 @@
+//! Exact base-10 numbers backing `std::decimal`.
+//!
+//! A decimal is an immutable managed payload holding a sign, the decimal
+//! digits of its magnitude and a scale, the number of those digits after the
+//! point. Digits have no upper bound, so sums, differences and products are
+//! exact; only division and explicit rounding drop digits, under one of the
+//! rounding modes of the runtime ABI.
+//!
+//! Results handed back through slots stay valid until the next call of the
+//! same kind on the thread. Strings handed out are owned by the caller.
+
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::ptr;
+
+use runec_abi::{ROUND_CEILING, ROUND_DOWN, ROUND_FLOOR, ROUND_HALF_UP, ROUND_UP};
+
+use crate::RawStr;
+use crate::files::{Fallible, str_from_abi};
+use crate::heap::{__runeway_alloc, __runeway_set_drop};
+
+/// Payload of a decimal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decimal {
+    /// Never set for zero.
+    negative: bool,
+    /// Digits of the magnitude, least significant first, without leading
+    /// zeros. Zero has no digits.
+    digits: Vec<u8>,
+    scale: usize,
+}
+
+impl Decimal {
+    fn new(negative: bool, mut digits: Vec<u8>, scale: usize) -> Self {
+        trim(&mut digits);
+        Self { negative: negative && !digits.is_empty(), digits, scale }
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        let (negative, unsigned) = match text.trim().as_bytes() {
+            [b'-', rest @ ..] => (true, rest),
+            [b'+', rest @ ..] => (false, rest),
+            bytes => (false, bytes),
+        };
+        let (whole, fraction) = match unsigned.iter().position(|&byte| byte == b'.') {
+            Some(point) => (&unsigned[..point], &unsigned[point + 1..]),
+            None => (unsigned, &[][..]),
+        };
+        let all = || whole.iter().chain(fraction);
+        if whole.len() + fraction.len() == 0 || !all().all(u8::is_ascii_digit) {
+            return None;
+        }
+        let digits = all().rev().map(|byte| byte - b'0').collect();
+        Some(Self::new(negative, digits, fraction.len()))
+    }
+
+    /// Digits of the magnitude scaled up to `scale`, which must not be below
+    /// the scale of the decimal.
+    fn digits_at(&self, scale: usize) -> Vec<u8> {
+        shifted(&self.digits, scale - self.scale)
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        let scale = self.scale.max(other.scale);
+        let (lhs, rhs) = (self.digits_at(scale), other.digits_at(scale));
+        if self.negative == other.negative {
+            return Self::new(self.negative, add_digits(&lhs, &rhs), scale);
+        }
+        match compare_digits(&lhs, &rhs) {
+            Ordering::Less => Self::new(other.negative, sub_digits(&rhs, &lhs), scale),
+            _ => Self::new(self.negative, sub_digits(&lhs, &rhs), scale),
+        }
+    }
+
+    fn neg(&self) -> Self {
+        Self::new(!self.negative, self.digits.clone(), self.scale)
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        let mut digits = vec![0u32; self.digits.len() + other.digits.len()];
+        for (i, &lhs) in self.digits.iter().enumerate() {
+            for (j, &rhs) in other.digits.iter().enumerate() {
+                digits[i + j] += u32::from(lhs) * u32::from(rhs);
+            }
+        }
+        let mut carry = 0;
+        let digits = digits
+            .into_iter()
+            .map(|digit| {
+                let sum = digit + carry;
+                carry = sum / 10;
+                (sum % 10) as u8
+            })
+            .collect();
+        Self::new(self.negative != other.negative, digits, self.scale + other.scale)
+    }
+
+    /// The quotient with `scale` digits after the point, or `None` when
+    /// dividing by zero.
+    fn div(&self, other: &Self, scale: usize, mode: usize) -> Option<Self> {
+        if other.digits.is_empty() {
+            return None;
+        }
+        // self / other * 10^scale == (a * 10^exponent) / b with integer
+        // magnitudes a and b.
+        let exponent = (other.scale + scale) as isize - self.scale as isize;
+        let (numerator, denominator) = if exponent >= 0 {
+            (shifted(&self.digits, exponent as usize), other.digits.clone())
+        } else {
+            (self.digits.clone(), shifted(&other.digits, exponent.unsigned_abs()))
+        };
+        let (quotient, remainder) = div_rem_digits(&numerator, &denominator);
+        let negative = self.negative != other.negative;
+        let half = compare_digits(&add_digits(&remainder, &remainder), &denominator);
+        let exact = remainder.is_empty();
+        Some(Self::rounded(negative, quotient, scale, mode, half, exact))
+    }
+
+    /// The decimal with exactly `scale` digits after the point.
+    fn round(&self, scale: usize, mode: usize) -> Self {
+        if self.scale <= scale {
+            return Self::new(self.negative, self.digits_at(scale), scale);
+        }
+        let dropped = self.scale - scale;
+        let (kept, half, exact) = match self.digits.get(dropped - 1) {
+            Some(&first) => {
+                let rest_is_zero = self.digits[..dropped - 1].iter().all(|&digit| digit == 0);
+                let half = match first.cmp(&5) {
+                    Ordering::Equal if !rest_is_zero => Ordering::Greater,
+                    ordering => ordering,
+                };
+                (self.digits[dropped..].to_vec(), half, first == 0 && rest_is_zero)
+            }
+            // Fewer digits than dropped places leave less than a half.
+            None => (Vec::new(), Ordering::Less, self.digits.is_empty()),
+        };
+        Self::rounded(self.negative, kept, scale, mode, half, exact)
+    }
+
+    /// Rounds a truncated magnitude away from zero when `mode` calls for it,
+    /// given how the dropped remainder compares to a half.
+    fn rounded(
+        negative: bool,
+        mut digits: Vec<u8>,
+        scale: usize,
+        mode: usize,
+        half: Ordering,
+        exact: bool,
+    ) -> Self {
+        let odd = digits.first().is_some_and(|digit| digit % 2 == 1);
+        let away = !exact
+            && match mode {
+                ROUND_HALF_UP => half != Ordering::Less,
+                ROUND_DOWN => false,
+                ROUND_UP => true,
+                ROUND_FLOOR => negative,
+                ROUND_CEILING => !negative,
+                // `ROUND_HALF_EVEN`
+                _ => half == Ordering::Greater || (half == Ordering::Equal && odd),
+            };
+        if away {
+            digits = add_digits(&digits, &[1]);
+        }
+        Self::new(negative, digits, scale)
+    }
+
+    fn compare(&self, other: &Self) -> Ordering {
+        if self.negative != other.negative {
+            return if self.negative { Ordering::Less } else { Ordering::Greater };
+        }
+        let scale = self.scale.max(other.scale);
+        let ordering = compare_digits(&self.digits_at(scale), &other.digits_at(scale));
+        if self.negative { ordering.reverse() } else { ordering }
+    }
+
+    fn format(&self) -> String {
+        let mut digits: Vec<u8> = self.digits.clone();
+        // Pad to at least one digit before the point.
+        digits.resize(digits.len().max(self.scale + 1), 0);
+        let mut text = String::with_capacity(digits.len() + 2);
+        if self.negative {
+            text.push('-');
+        }
+        for (index, digit) in digits.iter().enumerate().rev() {
+            text.push(char::from(b'0' + digit));
+            if index == self.scale && index > 0 {
+                text.push('.');
+            }
+        }
+        text
+    }
+}
+
+fn trim(digits: &mut Vec<u8>) {
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+}
+
+/// `digits` multiplied by `10^places`.
+fn shifted(digits: &[u8], places: usize) -> Vec<u8> {
+    if digits.is_empty() {
+        return Vec::new();
+    }
+    let mut result = vec![0; places];
+    result.extend_from_slice(digits);
+    result
+}
+
+fn compare_digits(lhs: &[u8], rhs: &[u8]) -> Ordering {
+    let significant =
+        |digits: &[u8]| digits.iter().rposition(|&digit| digit != 0).map_or(0, |i| i + 1);
+    let (lhs, rhs) = (&lhs[..significant(lhs)], &rhs[..significant(rhs)]);
+    lhs.len().cmp(&rhs.len()).then_with(|| lhs.iter().rev().cmp(rhs.iter().rev()))
+}
+
+fn add_digits(lhs: &[u8], rhs: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(lhs.len().max(rhs.len()) + 1);
+    let mut carry = 0;
+    for index in 0..lhs.len().max(rhs.len()) {
+        let sum = lhs.get(index).unwrap_or(&0) + rhs.get(index).unwrap_or(&0) + carry;
+        result.push(sum % 10);
+        carry = sum / 10;
+    }
+    if carry > 0 {
+        result.push(carry);
+    }
+    result
+}
+
+/// `lhs - rhs`, where `lhs` must not be smaller than `rhs`.
+fn sub_digits(lhs: &[u8], rhs: &[u8]) -> Vec<u8> {
+    let mut borrow = 0;
+    let mut result: Vec<u8> = lhs
+        .iter()
+        .enumerate()
+        .map(|(index, &digit)| {
+            let subtrahend = rhs.get(index).unwrap_or(&0) + borrow;
+            borrow = u8::from(digit < subtrahend);
+            digit + borrow * 10 - subtrahend
+        })
+        .collect();
+    trim(&mut result);
+    result
+}
+
+/// Quotient and remainder by schoolbook long division, one decimal digit
+/// at a time. `denominator` must not be zero.
+fn div_rem_digits(numerator: &[u8], denominator: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let mut quotient = vec![0; numerator.len()];
+    let mut remainder: Vec<u8> = Vec::new();
+    for (index, &digit) in numerator.iter().enumerate().rev() {
+        remainder.insert(0, digit);
+        trim(&mut remainder);
+        while compare_digits(&remainder, denominator) != Ordering::Less {
+            remainder = sub_digits(&remainder, denominator);
+            quotient[index] += 1;
+        }
+    }
+    trim(&mut quotient);
+    (quotient, remainder)
+}
+
+thread_local! {
+    static PARSE_RESULT: Cell<Fallible<*mut u8>> = const { Cell::new(Fallible::EMPTY) };
+    static DIV_RESULT: Cell<Fallible<*mut u8>> = const { Cell::new(Fallible::EMPTY) };
+    static TEXT_SLOT: Cell<RawStr> = const { Cell::new(RawStr::EMPTY) };
+}
+
+unsafe extern "C" fn drop_decimal(payload: *mut u8) {
+    // SAFETY: Registered only for payloads created by `alloc_decimal`.
+    unsafe { ptr::drop_in_place(payload.cast::<Decimal>()) };
+}
+
+fn alloc_decimal(decimal: Decimal) -> *mut u8 {
+    let payload = __runeway_alloc(size_of::<Decimal>(), align_of::<Decimal>());
+    // SAFETY: The payload was just allocated with the size and alignment of
+    // `Decimal`.
+    unsafe {
+        payload.cast::<Decimal>().write(decimal);
+        __runeway_set_drop(payload, drop_decimal);
+    }
+    payload
+}
+
+/// # Safety
+///
+/// `decimal` must be a live decimal created by this module.
+unsafe fn decimal_ref<'a>(decimal: *mut u8) -> &'a Decimal {
+    // SAFETY: Forwarded directly from the caller.
+    unsafe { &*decimal.cast::<Decimal>() }
+}
+
+/// Stores `value` in `slot` and returns its address.
+fn hand_out<T: Copy>(slot: &'static std::thread::LocalKey<Cell<T>>, value: T) -> *const T {
+    slot.with(|cell| {
+        cell.set(value);
+        cell.as_ptr().cast_const()
+    })
+}
+
+#[unsafe(no_mangle)]
+/// Parses an optionally signed decimal such as `-12.50`, keeping trailing
+/// zeros as part of the scale.
+///
+/// # Safety
+///
+/// `text_ptr` must be null only when `text_len` is zero. Otherwise it must
+/// point to `text_len` readable bytes that remain valid for the duration of
+/// the call.
+pub unsafe extern "C" fn __runeway_decimal_parse(
+    text_ptr: *const u8,
+    text_len: usize,
+) -> *const Fallible<*mut u8> {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let text = unsafe { str_from_abi(text_ptr, text_len) };
+    let result =
+        Decimal::parse(&text).map(alloc_decimal).ok_or_else(|| format!("invalid decimal `{text}`"));
+    hand_out(&PARSE_RESULT, Fallible::new(result))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn __runeway_decimal_from_int(value: i64) -> *mut u8 {
+    let digits = value.unsigned_abs().to_string().bytes().rev().map(|byte| byte - b'0').collect();
+    alloc_decimal(Decimal::new(value < 0, digits, 0))
+}
+
+#[unsafe(no_mangle)]
+/// Formats the decimal with all the digits of its scale.
+///
+/// # Safety
+///
+/// `decimal` must be a live decimal.
+pub unsafe extern "C" fn __runeway_decimal_to_string(decimal: *mut u8) -> *const RawStr {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let text = unsafe { decimal_ref(decimal) }.format();
+    hand_out(&TEXT_SLOT, RawStr::alloc(&text))
+}
+
+#[unsafe(no_mangle)]
+/// The exact sum, at the larger scale of the two.
+///
+/// # Safety
+///
+/// `lhs` and `rhs` must be live decimals.
+pub unsafe extern "C" fn __runeway_decimal_add(lhs: *mut u8, rhs: *mut u8) -> *mut u8 {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let (lhs, rhs) = unsafe { (decimal_ref(lhs), decimal_ref(rhs)) };
+    alloc_decimal(lhs.add(rhs))
+}
+
+#[unsafe(no_mangle)]
+/// The exact difference, at the larger scale of the two.
+///
+/// # Safety
+///
+/// `lhs` and `rhs` must be live decimals.
+pub unsafe extern "C" fn __runeway_decimal_sub(lhs: *mut u8, rhs: *mut u8) -> *mut u8 {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let (lhs, rhs) = unsafe { (decimal_ref(lhs), decimal_ref(rhs)) };
+    alloc_decimal(lhs.add(&rhs.neg()))
+}
+
+#[unsafe(no_mangle)]
+/// The exact product, at the sum of the two scales.
+///
+/// # Safety
+///
+/// `lhs` and `rhs` must be live decimals.
+pub unsafe extern "C" fn __runeway_decimal_mul(lhs: *mut u8, rhs: *mut u8) -> *mut u8 {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let (lhs, rhs) = unsafe { (decimal_ref(lhs), decimal_ref(rhs)) };
+    alloc_decimal(lhs.mul(rhs))
+}
+
+#[unsafe(no_mangle)]
+/// The quotient rounded to `scale` digits after the point with `mode`.
+/// Fails when dividing by zero.
+///
+/// # Safety
+///
+/// `lhs` and `rhs` must be live decimals.
+pub unsafe extern "C" fn __runeway_decimal_div(
+    lhs: *mut u8,
+    rhs: *mut u8,
+    scale: usize,
+    mode: usize,
+) -> *const Fallible<*mut u8> {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let (lhs, rhs) = unsafe { (decimal_ref(lhs), decimal_ref(rhs)) };
+    let result =
+        lhs.div(rhs, scale, mode).map(alloc_decimal).ok_or_else(|| "division by zero".to_owned());
+    hand_out(&DIV_RESULT, Fallible::new(result))
+}
+
+#[unsafe(no_mangle)]
+/// The decimal rounded or padded to exactly `scale` digits after the point.
+///
+/// # Safety
+///
+/// `decimal` must be a live decimal.
+pub unsafe extern "C" fn __runeway_decimal_round(
+    decimal: *mut u8,
+    scale: usize,
+    mode: usize,
+) -> *mut u8 {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    alloc_decimal(unsafe { decimal_ref(decimal) }.round(scale, mode))
+}
+
+#[unsafe(no_mangle)]
+/// `-1`, `0` or `1` as `lhs` is below, equal to or above `rhs`, whatever
+/// their scales.
+///
+/// # Safety
+///
+/// `lhs` and `rhs` must be live decimals.
+pub unsafe extern "C" fn __runeway_decimal_cmp(lhs: *mut u8, rhs: *mut u8) -> i64 {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let (lhs, rhs) = unsafe { (decimal_ref(lhs), decimal_ref(rhs)) };
+    lhs.compare(rhs) as i64
+}
+
+#[unsafe(no_mangle)]
+/// # Safety
+///
+/// `decimal` must be a live decimal.
+pub unsafe extern "C" fn __runeway_decimal_neg(decimal: *mut u8) -> *mut u8 {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    alloc_decimal(unsafe { decimal_ref(decimal) }.neg())
+}
+
+#[unsafe(no_mangle)]
+/// Number of digits after the point.
+///
+/// # Safety
+///
+/// `decimal` must be a live decimal.
+pub unsafe extern "C" fn __runeway_decimal_scale(decimal: *mut u8) -> usize {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    unsafe { decimal_ref(decimal) }.scale
+}
+
+#[cfg(test)]
+mod tests {
+    use runec_abi::{
+        ROUND_CEILING, ROUND_DOWN, ROUND_FLOOR, ROUND_HALF_EVEN, ROUND_HALF_UP, ROUND_UP,
+    };
+
+    use super::{
+        __runeway_decimal_div, __runeway_decimal_from_int, __runeway_decimal_parse,
+        __runeway_decimal_to_string, Decimal,
+    };
+    use crate::heap::__runeway_release;
+    use crate::heap_stats;
+
+    fn decimal(text: &str) -> Decimal {
+        Decimal::parse(text).unwrap()
+    }
+
+    #[test]
+    fn parses_and_formats_with_their_scale() {
+        for text in ["0", "1.50", "-0.05", "123456789012345678901234567890.000001"] {
+            assert_eq!(decimal(text).format(), text);
+        }
+        assert_eq!(decimal("-0.00").format(), "0.00");
+        assert_eq!(decimal("+.5").format(), "0.5");
+        for text in ["", "-", ".", "1.2.3", "1e5", "12a"] {
+            assert_eq!(Decimal::parse(text), None, "{text}");
+        }
+    }
+
+    #[test]
+    fn adds_subtracts_and_multiplies_exactly() {
+        assert_eq!(decimal("0.1").add(&decimal("0.2")).format(), "0.3");
+        assert_eq!(decimal("1.05").add(&decimal("-3")).format(), "-1.95");
+        assert_eq!(decimal("19.99").mul(&decimal("3")).format(), "59.97");
+        assert_eq!(decimal("-1.5").mul(&decimal("-0.25")).format(), "0.375");
+        assert_eq!(
+            decimal("99999999999999999999").add(&decimal("1")).format(),
+            "100000000000000000000"
+        );
+    }
+
+    #[test]
+    fn rounds_with_each_mode() {
+        let round = |text, mode| decimal(text).round(0, mode).format();
+        let cases = [
+            (ROUND_HALF_EVEN, ["2", "2", "-2", "3"]),
+            (ROUND_HALF_UP, ["3", "2", "-3", "3"]),
+            (ROUND_DOWN, ["2", "2", "-2", "2"]),
+            (ROUND_UP, ["3", "3", "-3", "3"]),
+            (ROUND_FLOOR, ["2", "2", "-3", "2"]),
+            (ROUND_CEILING, ["3", "3", "-2", "3"]),
+        ];
+        for (mode, expected) in cases {
+            let rounded = ["2.5", "2.1", "-2.5", "2.51"].map(|text| round(text, mode));
+            assert_eq!(rounded, expected, "mode {mode}");
+        }
+        assert_eq!(decimal("0.004").round(2, ROUND_UP).format(), "0.01");
+        assert_eq!(decimal("0.0004").round(2, ROUND_HALF_UP).format(), "0.00");
+        assert_eq!(decimal("1.5").round(3, ROUND_DOWN).format(), "1.500");
+        assert_eq!(decimal("9.995").round(2, ROUND_HALF_EVEN).format(), "10.00");
+    }
+
+    #[test]
+    fn divides_to_a_scale_and_compares_across_scales() {
+        let div = |lhs, rhs, scale, mode| decimal(lhs).div(&decimal(rhs), scale, mode);
+        assert_eq!(div("1", "3", 4, ROUND_HALF_EVEN).unwrap().format(), "0.3333");
+        assert_eq!(div("2", "3", 2, ROUND_HALF_EVEN).unwrap().format(), "0.67");
+        assert_eq!(div("-10.00", "4", 1, ROUND_HALF_EVEN).unwrap().format(), "-2.5");
+        assert_eq!(div("0.25", "0.5", 0, ROUND_HALF_EVEN).unwrap().format(), "0");
+        assert_eq!(div("0.25", "0.5", 0, ROUND_HALF_UP).unwrap().format(), "1");
+        assert_eq!(div("1", "0.00", 2, ROUND_HALF_EVEN), None);
+        assert!(decimal("1.10").compare(&decimal("1.1")).is_eq());
+        assert!(decimal("-2").compare(&decimal("1.5")).is_lt());
+        assert!(decimal("-1.5").compare(&decimal("-2")).is_gt());
+    }
+
+    #[test]
+    fn hands_out_results_through_the_runtime_abi() {
+        let before = heap_stats().live_allocations();
+        // SAFETY: Every decimal is live until released below.
+        unsafe {
+            let price = __runeway_decimal_parse("10.00".as_ptr(), 5).read().into_result().unwrap();
+            let count = __runeway_decimal_from_int(-3);
+            let share = __runeway_decimal_div(price, count, 2, ROUND_HALF_EVEN)
+                .read()
+                .into_result()
+                .unwrap();
+            assert_eq!(__runeway_decimal_to_string(share).read().into_string(), "-3.33");
+            let zero = __runeway_decimal_from_int(0);
+            let error = __runeway_decimal_div(price, zero, 2, ROUND_HALF_EVEN).read().into_result();
+            assert_eq!(error.unwrap_err(), "division by zero");
+            for decimal in [price, count, share, zero] {
+                __runeway_release(decimal);
+            }
+        }
+        assert_eq!(heap_stats().live_allocations(), before);
+    }
+}
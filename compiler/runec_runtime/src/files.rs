@@ -0,0 +1,284 @@
+//! Files and directories backing `std::files`.
+//!
+//! Operations that can fail return a pointer to a [`Fallible`], which is laid
+//! out like a MIR fallible value so generated code can load it as one. It
+//! stays valid until the next operation of the same kind on the thread.
+//! Errors are messages naming the path and the OS error. Strings handed out
+//! here, file contents, names and error messages alike, are managed and
+//! owned by the caller.
+
+use std::cell::Cell;
+use std::fs;
+use std::io::{self, Write};
+use std::mem::MaybeUninit;
+use std::path::Path;
+use std::thread::LocalKey;
+
+use crate::vec::str_vec;
+use crate::{RawStr, bytes_from_abi};
+
+/// Result of a file operation: an `i8` tag that is `1` when it failed,
+/// followed by the value, set only on success, and the error message, set
+/// only on failure.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct Fallible<T: Copy> {
+    failed: u8,
+    value: MaybeUninit<T>,
+    error: RawStr,
+}
+
+impl<T: Copy> Fallible<T> {
+    pub(crate) const EMPTY: Self =
+        Self { failed: 0, value: MaybeUninit::uninit(), error: RawStr::EMPTY };
+
+    pub(crate) fn new(result: Result<T, String>) -> Self {
+        match result {
+            Ok(value) => Self { failed: 0, value: MaybeUninit::new(value), error: RawStr::EMPTY },
+            Err(message) => {
+                Self { failed: 1, value: MaybeUninit::uninit(), error: RawStr::alloc(&message) }
+            }
+        }
+    }
+
+    /// The value, or the error message, whose reference is released.
+    #[cfg(test)]
+    pub(crate) fn into_result(self) -> Result<T, String> {
+        if self.failed == 0 {
+            // SAFETY: The value is set whenever the operation succeeded.
+            Ok(unsafe { self.value.assume_init() })
+        } else {
+            // SAFETY: Error messages are live managed strings.
+            Err(unsafe { self.error.into_string() })
+        }
+    }
+}
+
+thread_local! {
+    static UNIT_RESULT: Cell<Fallible<()>> = const { Cell::new(Fallible::EMPTY) };
+    static STR_RESULT: Cell<Fallible<RawStr>> = const { Cell::new(Fallible::EMPTY) };
+    static VEC_RESULT: Cell<Fallible<*mut u8>> = const { Cell::new(Fallible::EMPTY) };
+}
+
+/// Stores `result` in `slot` and returns its address, with a failure
+/// described as being unable to `action` the file at `path`.
+pub(crate) fn hand_out<T: Copy>(
+    slot: &'static LocalKey<Cell<Fallible<T>>>,
+    (action, path): (&str, &Path),
+    result: io::Result<T>,
+) -> *const Fallible<T> {
+    let result = Fallible::new(
+        result.map_err(|error| format!("cannot {action} `{}`: {error}", path.display())),
+    );
+    slot.with(|cell| {
+        cell.set(result);
+        cell.as_ptr().cast_const()
+    })
+}
+
+/// # Safety
+///
+/// Same contract as [`bytes_from_abi`].
+pub(crate) unsafe fn str_from_abi(ptr: *const u8, len: usize) -> String {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let bytes = unsafe { bytes_from_abi(ptr, len) }.unwrap_or_default();
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+#[unsafe(no_mangle)]
+/// Reads the whole file at the path as a string.
+///
+/// # Safety
+///
+/// `path_ptr` must be null only when `path_len` is zero. Otherwise it must
+/// point to `path_len` readable bytes that remain valid for the duration of
+/// the call.
+pub unsafe extern "C" fn __runeway_read_to_string(
+    path_ptr: *const u8,
+    path_len: usize,
+) -> *const Fallible<RawStr> {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let path = unsafe { str_from_abi(path_ptr, path_len) };
+    let result = fs::read_to_string(&path).map(|text| RawStr::alloc(&text));
+    hand_out(&STR_RESULT, ("read", path.as_ref()), result)
+}
+
+#[unsafe(no_mangle)]
+/// Writes the contents to the file at the path, creating it or replacing
+/// what it held.
+///
+/// # Safety
+///
+/// Each `(ptr, len)` pair must be null only when its length is zero.
+/// Otherwise it must point to that many readable bytes that remain valid for
+/// the duration of the call.
+pub unsafe extern "C" fn __runeway_write(
+    path_ptr: *const u8,
+    path_len: usize,
+    contents_ptr: *const u8,
+    contents_len: usize,
+) -> *const Fallible<()> {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let path = unsafe { str_from_abi(path_ptr, path_len) };
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let contents = unsafe { bytes_from_abi(contents_ptr, contents_len) }.unwrap_or_default();
+    hand_out(&UNIT_RESULT, ("write", path.as_ref()), fs::write(&path, contents))
+}
+
+#[unsafe(no_mangle)]
+/// Appends the contents to the file at the path, creating it if needed.
+///
+/// # Safety
+///
+/// Same contract as [`__runeway_write`].
+pub unsafe extern "C" fn __runeway_append(
+    path_ptr: *const u8,
+    path_len: usize,
+    contents_ptr: *const u8,
+    contents_len: usize,
+) -> *const Fallible<()> {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let path = unsafe { str_from_abi(path_ptr, path_len) };
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let contents = unsafe { bytes_from_abi(contents_ptr, contents_len) }.unwrap_or_default();
+    let result = fs::OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(contents));
+    hand_out(&UNIT_RESULT, ("append to", path.as_ref()), result)
+}
+
+#[unsafe(no_mangle)]
+/// Returns `true` if a file or directory exists at the path.
+///
+/// # Safety
+///
+/// Same contract as [`__runeway_read_to_string`].
+pub unsafe extern "C" fn __runeway_exists(path_ptr: *const u8, path_len: usize) -> bool {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let path = unsafe { str_from_abi(path_ptr, path_len) };
+    Path::new(&path).exists()
+}
+
+#[unsafe(no_mangle)]
+/// Removes the file or empty directory at the path.
+///
+/// # Safety
+///
+/// Same contract as [`__runeway_read_to_string`].
+pub unsafe extern "C" fn __runeway_remove(
+    path_ptr: *const u8,
+    path_len: usize,
+) -> *const Fallible<()> {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let path = unsafe { str_from_abi(path_ptr, path_len) };
+    let result =
+        if Path::new(&path).is_dir() { fs::remove_dir(&path) } else { fs::remove_file(&path) };
+    hand_out(&UNIT_RESULT, ("remove", path.as_ref()), result)
+}
+
+#[unsafe(no_mangle)]
+/// Returns a new vector of the names of the entries in the directory at the
+/// path, in sorted order.
+///
+/// # Safety
+///
+/// Same contract as [`__runeway_read_to_string`].
+pub unsafe extern "C" fn __runeway_list_dir(
+    path_ptr: *const u8,
+    path_len: usize,
+) -> *const Fallible<*mut u8> {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let path = unsafe { str_from_abi(path_ptr, path_len) };
+    let names = fs::read_dir(&path).and_then(|entries| {
+        let mut names = entries
+            .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+            .collect::<io::Result<Vec<_>>>()?;
+        names.sort();
+        Ok(names)
+    });
+    let result = names.map(str_vec);
+    hand_out(&VEC_RESULT, ("list", path.as_ref()), result)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::{fs, process};
+
+    use super::{
+        __runeway_append, __runeway_exists, __runeway_list_dir, __runeway_read_to_string,
+        __runeway_remove, __runeway_write, Fallible,
+    };
+    use crate::RawStr;
+    use crate::heap::{__runeway_release, heap_stats};
+    use crate::vec::RawVec;
+
+    /// Creates an empty directory for one test under the system temporary
+    /// directory.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("runeway-files-{}-{name}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn text(raw: RawStr) -> String {
+        let bytes = unsafe { std::slice::from_raw_parts(raw.ptr, raw.len) };
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    /// The value of a result, or its error message.
+    fn read<T: Copy>(result: *const Fallible<T>) -> Result<T, String> {
+        unsafe { *result }.into_result()
+    }
+
+    #[test]
+    fn writes_appends_reads_and_removes_files() {
+        let dir = temp_dir("round-trip");
+        let path = dir.join("notes.txt").to_str().unwrap().to_owned();
+        let (ptr, len) = (path.as_ptr(), path.len());
+        let before = heap_stats();
+
+        unsafe {
+            assert!(!__runeway_exists(ptr, len));
+            read(__runeway_write(ptr, len, b"one\n".as_ptr(), 4)).unwrap();
+            read(__runeway_append(ptr, len, b"two\n".as_ptr(), 4)).unwrap();
+            assert!(__runeway_exists(ptr, len));
+            let contents = read(__runeway_read_to_string(ptr, len)).unwrap();
+            assert_eq!(contents.into_string(), "one\ntwo\n");
+
+            read(__runeway_remove(ptr, len)).unwrap();
+            assert!(!__runeway_exists(ptr, len));
+            let error = read(__runeway_read_to_string(ptr, len)).unwrap_err();
+            assert!(error.starts_with(&format!("cannot read `{path}`: ")), "{error}");
+        }
+        assert_eq!(heap_stats().live_allocations(), before.live_allocations());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn lists_directory_entries_in_sorted_order() {
+        let dir = temp_dir("listing");
+        for name in ["b.txt", "a.txt", "c"] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+        let path = dir.to_str().unwrap();
+
+        let vec = read(unsafe { __runeway_list_dir(path.as_ptr(), path.len()) }).unwrap();
+        let names = {
+            let raw = unsafe { &*vec.cast::<RawVec>() };
+            (0..raw.len())
+                .map(|index| text(unsafe { raw.slot(index).cast::<RawStr>().read() }))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names, ["a.txt", "b.txt", "c"]);
+        unsafe { __runeway_release(vec) };
+
+        let missing = dir.join("missing");
+        let missing = missing.to_str().unwrap();
+        assert!(read(unsafe { __runeway_list_dir(missing.as_ptr(), missing.len()) }).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,563 @@
+//! Reference-counted heap used by managed RuneWay values.
+//!
+//! Every allocation is prefixed with a [`Header`] that stores the reference
+//! count and the payload layout. Generated code only ever sees the payload
+//! pointer: it is returned by [`__runeway_alloc`] with a count of one and is
+//! freed when [`__runeway_release`] drops the count to zero.
+//!
+//! Payloads that hold references to other managed values, such as closure
+//! environments, register a drop function with [`__runeway_set_drop`] that
+//! releases them.
+//!
+//! Reference counting alone never frees a cycle, such as a closure stored in
+//! a variable it captures by reference. Payloads that can be part of one
+//! also register a trace function with [`__runeway_set_trace`], which
+//! reports the managed pointers they hold. [`__runeway_collect_cycles`] uses
+//! those to find the traced payloads only referenced by each other, by trial
+//! deletion, and frees them. It runs once the number of traced payloads has
+//! doubled since the last collection, and when the JIT finishes a program.
+//!
+//! A program the JIT runs can unwind out of its frames on a panic without
+//! releasing the values they held. [`__runeway_begin_run`] and
+//! [`__runeway_end_run`] record the payloads allocated in between, so the
+//! ones still live after a panic can be freed before the next run.
+//!
+//! String literals are laid out behind a header by code generation, with a
+//! count of [`IMMORTAL_REFCOUNT`]. They live in the program image, so
+//! retaining and releasing them leaves the header untouched. The cycle
+//! collector never tracks them either.
+
+use std::alloc::{self, Layout};
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+
+use runec_abi::{HEADER_WORDS, IMMORTAL_REFCOUNT};
+
+/// Releases the managed values held by a payload that is about to be freed.
+pub type DropFn = unsafe extern "C" fn(*mut u8);
+
+/// Calls the visitor with every managed pointer held by a payload, leaving
+/// the reference counts untouched.
+pub type TraceFn = unsafe extern "C" fn(*mut u8, VisitFn);
+
+/// Receives the pointers reported by a [`TraceFn`].
+pub type VisitFn = unsafe extern "C" fn(*mut u8);
+
+/// Number of traced payloads below which cycles are never collected.
+const MIN_COLLECTION_THRESHOLD: usize = 1024;
+
+#[repr(C)]
+struct Header {
+    strong: Cell<usize>,
+    size: usize,
+    align: usize,
+    drop: Cell<Option<DropFn>>,
+}
+
+const HEADER_SIZE: usize = size_of::<Header>();
+const _: () = assert!(HEADER_SIZE == HEADER_WORDS * size_of::<usize>());
+
+/// Allocation counters for the current thread.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct HeapStats {
+    pub allocations: usize,
+    pub frees: usize,
+    pub live_bytes: usize,
+}
+
+impl HeapStats {
+    pub fn live_allocations(&self) -> usize {
+        self.allocations - self.frees
+    }
+}
+
+thread_local! {
+    static STATS: Cell<HeapStats> = Cell::new(HeapStats::default());
+    /// Live payloads registered with [`__runeway_set_trace`].
+    static TRACED: RefCell<HashMap<*mut u8, TraceFn>> = RefCell::new(HashMap::new());
+    /// Number of traced payloads at which cycles are next collected.
+    static COLLECTION_THRESHOLD: Cell<usize> = const { Cell::new(MIN_COLLECTION_THRESHOLD) };
+    static COLLECTING: Cell<bool> = const { Cell::new(false) };
+    /// Pointers reported by the trace function being run.
+    static CHILDREN: RefCell<Vec<*mut u8>> = const { RefCell::new(Vec::new()) };
+    /// Live payloads allocated since [`__runeway_begin_run`], if a run is
+    /// being recorded.
+    static RUN: RefCell<Option<HashSet<*mut u8>>> = const { RefCell::new(None) };
+}
+
+/// Returns the allocation counters of the calling thread.
+///
+/// Programs run on the thread that invokes them, so tests can compare the
+/// counters before and after a run to check that nothing leaked.
+pub fn heap_stats() -> HeapStats {
+    STATS.with(Cell::get)
+}
+
+fn update_stats(update: impl FnOnce(&mut HeapStats)) {
+    STATS.with(|stats| {
+        let mut value = stats.get();
+        update(&mut value);
+        stats.set(value);
+    });
+}
+
+/// Returns the allocation layout and the payload offset for a payload layout.
+///
+/// The header is stored directly before the payload, so it can be found from
+/// the payload pointer without knowing the payload alignment.
+fn block_layout(size: usize, align: usize) -> Option<(Layout, usize)> {
+    let align = align.max(align_of::<Header>());
+    let offset = HEADER_SIZE.checked_next_multiple_of(align)?;
+    let layout = Layout::from_size_align(offset.checked_add(size)?, align).ok()?;
+    Some((layout, offset))
+}
+
+/// # Safety
+///
+/// `ptr` must be a live payload pointer returned by [`__runeway_alloc`].
+unsafe fn header<'a>(ptr: *mut u8) -> &'a Header {
+    // SAFETY: Every payload is preceded by its header.
+    unsafe { &*ptr.sub(HEADER_SIZE).cast::<Header>() }
+}
+
+/// Whether releasing the reference held by the caller frees the payload.
+///
+/// # Safety
+///
+/// `ptr` must be a live payload pointer returned by [`__runeway_alloc`].
+pub(crate) unsafe fn is_last_reference(ptr: *mut u8) -> bool {
+    // SAFETY: Forwarded directly from the caller.
+    unsafe { header(ptr) }.strong.get() == 1
+}
+
+#[unsafe(no_mangle)]
+/// Allocates a managed payload of `size` bytes aligned to `align` and returns
+/// it with a reference count of one.
+///
+/// The process is aborted when the layout is invalid or memory is exhausted.
+pub extern "C" fn __runeway_alloc(size: usize, align: usize) -> *mut u8 {
+    let Some((layout, offset)) = block_layout(size, align) else {
+        std::process::abort();
+    };
+
+    // SAFETY: The layout always contains the non-empty header.
+    let base = unsafe { alloc::alloc(layout) };
+    if base.is_null() {
+        alloc::handle_alloc_error(layout);
+    }
+
+    // SAFETY: `offset` is within the allocation and leaves room for the header.
+    let payload = unsafe { base.add(offset) };
+    // SAFETY: The header slot is aligned because `offset` is a multiple of the
+    // block alignment and the header size is a multiple of its alignment.
+    unsafe {
+        payload.sub(HEADER_SIZE).cast::<Header>().write(Header {
+            strong: Cell::new(1),
+            size,
+            align,
+            drop: Cell::new(None),
+        });
+    }
+
+    update_stats(|stats| {
+        stats.allocations += 1;
+        stats.live_bytes += size;
+    });
+    RUN.with_borrow_mut(|run| {
+        if let Some(run) = run {
+            run.insert(payload);
+        }
+    });
+    payload
+}
+
+#[unsafe(no_mangle)]
+/// Increments the reference count of a managed payload.
+///
+/// # Safety
+///
+/// `ptr` must be null, a live payload pointer returned by
+/// [`__runeway_alloc`], or an immortal payload.
+pub unsafe extern "C" fn __runeway_retain(ptr: *mut u8) {
+    if ptr.is_null() {
+        return;
+    }
+
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let header = unsafe { header(ptr) };
+    if header.strong.get() != IMMORTAL_REFCOUNT {
+        header.strong.set(header.strong.get() + 1);
+    }
+}
+
+#[unsafe(no_mangle)]
+/// Decrements the reference count of a managed payload and frees it once no
+/// references are left.
+///
+/// # Safety
+///
+/// `ptr` must be null, a live payload pointer returned by
+/// [`__runeway_alloc`], or an immortal payload. The caller gives up the
+/// reference it releases.
+pub unsafe extern "C" fn __runeway_release(ptr: *mut u8) {
+    if ptr.is_null() {
+        return;
+    }
+
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let header = unsafe { header(ptr) };
+    if header.strong.get() == IMMORTAL_REFCOUNT {
+        return;
+    }
+    let strong = header.strong.get() - 1;
+    if strong > 0 {
+        header.strong.set(strong);
+        return;
+    }
+
+    if let Some(drop) = header.drop.get() {
+        // Only payloads with a drop function hold references, so only they
+        // can be traced.
+        let _ = TRACED.try_with(|traced| traced.borrow_mut().remove(&ptr));
+        // SAFETY: The payload is still live; it is freed only afterwards.
+        unsafe { drop(ptr) };
+    }
+
+    // SAFETY: The count dropped to zero and the payload has been dropped.
+    unsafe { free(ptr) };
+}
+
+/// Frees the block of a payload that has already been dropped.
+///
+/// # Safety
+///
+/// `ptr` must be a payload pointer returned by [`__runeway_alloc`] that is
+/// not used afterwards.
+unsafe fn free(ptr: *mut u8) {
+    // SAFETY: Forwarded directly from the caller.
+    let header = unsafe { header(ptr) };
+    let size = header.size;
+    let (layout, offset) =
+        block_layout(size, header.align).expect("live allocation has a valid layout");
+    // SAFETY: `ptr` was produced by `__runeway_alloc` with this layout.
+    unsafe { alloc::dealloc(ptr.sub(offset), layout) };
+
+    update_stats(|stats| {
+        stats.frees += 1;
+        stats.live_bytes -= size;
+    });
+    let _ = RUN.try_with(|run| {
+        if let Some(run) = run.borrow_mut().as_mut() {
+            run.remove(&ptr);
+        }
+    });
+}
+
+#[unsafe(no_mangle)]
+/// Registers `drop` to be called with a managed payload right before it is
+/// freed.
+///
+/// # Safety
+///
+/// `ptr` must be a live payload pointer returned by [`__runeway_alloc`], and
+/// `drop` must be safe to call with it once.
+pub unsafe extern "C" fn __runeway_set_drop(ptr: *mut u8, drop: DropFn) {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    unsafe { header(ptr) }.drop.set(Some(drop));
+}
+
+#[unsafe(no_mangle)]
+/// Registers `trace` to report the managed pointers held by a payload, so
+/// that cycles through it can be collected. Immortal payloads are never
+/// freed and are not traced.
+///
+/// Cycles are collected first when the number of traced payloads has
+/// reached the threshold; the payload being registered counts as a
+/// reference from outside to everything it holds.
+///
+/// # Safety
+///
+/// `ptr` must be a live payload pointer returned by [`__runeway_alloc`], or
+/// an immortal payload. Its drop function must release exactly the pointers
+/// `trace` reports, and `trace` must be safe to call with it while it is
+/// live.
+pub unsafe extern "C" fn __runeway_set_trace(ptr: *mut u8, trace: TraceFn) {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    if unsafe { header(ptr) }.strong.get() == IMMORTAL_REFCOUNT {
+        return;
+    }
+    if TRACED.with_borrow(HashMap::len) >= COLLECTION_THRESHOLD.get() {
+        __runeway_collect_cycles();
+    }
+    TRACED.with_borrow_mut(|traced| traced.insert(ptr, trace));
+}
+
+unsafe extern "C" fn record_child(child: *mut u8) {
+    CHILDREN.with_borrow_mut(|children| children.push(child));
+}
+
+#[unsafe(no_mangle)]
+/// Frees the traced payloads that are only referenced by each other.
+///
+/// A traced payload with more references than the traced payloads hold to
+/// it is referenced from outside of them, so it is live along with
+/// everything it holds. The others form unreachable cycles: each of them is
+/// kept alive with an extra reference while their drop functions release
+/// what they hold, and they are all freed afterwards.
+pub extern "C" fn __runeway_collect_cycles() {
+    if COLLECTING.replace(true) {
+        return;
+    }
+    let traced = TRACED.with_borrow(HashMap::clone);
+    let children: HashMap<_, Vec<_>> = traced
+        .iter()
+        .map(|(&ptr, &trace)| {
+            // SAFETY: Traced payloads are live until they are released.
+            unsafe { trace(ptr, record_child) };
+            let mut children = CHILDREN.with_borrow_mut(std::mem::take);
+            children.retain(|child| traced.contains_key(child));
+            (ptr, children)
+        })
+        .collect();
+
+    let mut internal = HashMap::<*mut u8, usize>::new();
+    for child in children.values().flatten() {
+        *internal.entry(*child).or_default() += 1;
+    }
+    // SAFETY: Traced payloads are live.
+    let mut pending: Vec<_> = traced
+        .keys()
+        .copied()
+        .filter(|ptr| {
+            unsafe { header(*ptr) }.strong.get() > internal.get(ptr).copied().unwrap_or(0)
+        })
+        .collect();
+    let mut live = HashSet::new();
+    while let Some(ptr) = pending.pop() {
+        if live.insert(ptr) {
+            pending.extend(&children[&ptr]);
+        }
+    }
+
+    let garbage: Vec<_> = traced.keys().copied().filter(|ptr| !live.contains(ptr)).collect();
+    TRACED.with_borrow_mut(|traced| {
+        for ptr in &garbage {
+            traced.remove(ptr);
+        }
+    });
+    // SAFETY: The garbage is live until freed below, and the extra reference
+    // keeps releases of it by the drop functions from freeing it early.
+    unsafe {
+        for &ptr in &garbage {
+            let strong = &header(ptr).strong;
+            strong.set(strong.get() + 1);
+        }
+        for &ptr in &garbage {
+            if let Some(drop) = header(ptr).drop.take() {
+                drop(ptr);
+            }
+        }
+        for &ptr in &garbage {
+            free(ptr);
+        }
+    }
+
+    let survivors = TRACED.with_borrow(HashMap::len);
+    COLLECTION_THRESHOLD.set((survivors * 2).max(MIN_COLLECTION_THRESHOLD));
+    COLLECTING.set(false);
+}
+
+#[unsafe(no_mangle)]
+/// Starts recording the payloads allocated on this thread, replacing any
+/// recording already in progress.
+pub extern "C" fn __runeway_begin_run() {
+    RUN.set(Some(HashSet::new()));
+}
+
+#[unsafe(no_mangle)]
+/// Stops recording allocations. When `unwound` is set, the program that ran
+/// since [`__runeway_begin_run`] unwound without releasing its values, and
+/// the payloads it allocated that are still live are freed without running
+/// their drop functions.
+///
+/// # Safety
+///
+/// When `unwound` is set, none of the recorded payloads may be used
+/// afterwards.
+pub unsafe extern "C" fn __runeway_end_run(unwound: bool) {
+    let Some(live) = RUN.take() else {
+        return;
+    };
+    if !unwound {
+        return;
+    }
+    TRACED.with_borrow_mut(|traced| traced.retain(|ptr, _| !live.contains(ptr)));
+    for ptr in live {
+        // SAFETY: The payload is live, and its references to other payloads
+        // of the run are not followed since those are freed here as well.
+        unsafe { free(ptr) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use runec_abi::{HEADER_WORDS, IMMORTAL_REFCOUNT};
+
+    use super::{
+        __runeway_alloc, __runeway_begin_run, __runeway_collect_cycles, __runeway_end_run,
+        __runeway_release, __runeway_retain, __runeway_set_drop, __runeway_set_trace, VisitFn,
+        heap_stats,
+    };
+
+    unsafe extern "C" fn release_inner(ptr: *mut u8) {
+        unsafe { __runeway_release(ptr.cast::<*mut u8>().read()) };
+    }
+
+    unsafe extern "C" fn trace_inner(ptr: *mut u8, visit: VisitFn) {
+        unsafe { visit(ptr.cast::<*mut u8>().read()) };
+    }
+
+    /// Allocates a payload holding one reference to `inner`, which may be
+    /// null, and traces it.
+    fn traced_node(inner: *mut u8) -> *mut u8 {
+        let node = __runeway_alloc(8, 8);
+        unsafe {
+            node.cast::<*mut u8>().write(inner);
+            __runeway_set_drop(node, release_inner);
+            __runeway_set_trace(node, trace_inner);
+        }
+        node
+    }
+
+    #[test]
+    fn release_frees_allocation_once_unreferenced() {
+        let before = heap_stats();
+
+        let ptr = __runeway_alloc(24, 8);
+        assert_eq!(ptr as usize % 8, 0);
+        unsafe {
+            __runeway_retain(ptr);
+            __runeway_release(ptr);
+        }
+        assert_eq!(heap_stats().live_allocations(), before.live_allocations() + 1);
+
+        unsafe { __runeway_release(ptr) };
+        let after = heap_stats();
+        assert_eq!(after.allocations, before.allocations + 1);
+        assert_eq!(after.live_allocations(), before.live_allocations());
+        assert_eq!(after.live_bytes, before.live_bytes);
+    }
+
+    #[test]
+    fn respects_over_aligned_payloads() {
+        let ptr = __runeway_alloc(1, 64);
+        assert_eq!(ptr as usize % 64, 0);
+        unsafe { __runeway_release(ptr) };
+    }
+
+    #[test]
+    fn null_is_ignored() {
+        let before = heap_stats();
+        unsafe {
+            __runeway_retain(std::ptr::null_mut());
+            __runeway_release(std::ptr::null_mut());
+        }
+        assert_eq!(heap_stats(), before);
+    }
+
+    #[test]
+    fn immortal_payloads_are_never_counted_or_freed() {
+        #[repr(C)]
+        struct Literal {
+            header: [usize; HEADER_WORDS],
+            bytes: [u8; 2],
+        }
+
+        let before = heap_stats();
+        let literal = Literal { header: [IMMORTAL_REFCOUNT, 2, 1, 0], bytes: *b"hi" };
+        let payload = literal.bytes.as_ptr().cast_mut();
+        unsafe {
+            __runeway_retain(payload);
+            __runeway_release(payload);
+            __runeway_release(payload);
+        }
+        assert_eq!(literal.header[0], IMMORTAL_REFCOUNT);
+        assert_eq!(heap_stats(), before);
+    }
+
+    #[test]
+    fn drop_releases_nested_payloads_before_freeing() {
+        let before = heap_stats();
+        let inner = __runeway_alloc(8, 8);
+        let outer = __runeway_alloc(8, 8);
+        unsafe {
+            outer.cast::<*mut u8>().write(inner);
+            __runeway_set_drop(outer, release_inner);
+            __runeway_release(outer);
+        }
+        assert_eq!(heap_stats().live_allocations(), before.live_allocations());
+    }
+
+    #[test]
+    fn collects_cycles_only_referenced_by_themselves() {
+        let before = heap_stats();
+        let first = traced_node(std::ptr::null_mut());
+        let second = traced_node(first);
+        // `first` and `second` now only keep each other alive.
+        unsafe { first.cast::<*mut u8>().write(second) };
+
+        // `kept` is still referenced here, and keeps its own cycle alive.
+        let kept = traced_node(std::ptr::null_mut());
+        unsafe { __runeway_retain(kept) };
+        let partner = traced_node(kept);
+        unsafe { kept.cast::<*mut u8>().write(partner) };
+
+        __runeway_collect_cycles();
+        assert_eq!(heap_stats().live_allocations(), before.live_allocations() + 2);
+        unsafe { __runeway_release(kept) };
+        __runeway_collect_cycles();
+        assert_eq!(heap_stats().live_allocations(), before.live_allocations());
+        assert_eq!(heap_stats().live_bytes, before.live_bytes);
+    }
+
+    #[test]
+    fn ending_an_unwound_run_frees_what_it_left_live() {
+        let before = heap_stats();
+        __runeway_begin_run();
+        let released = __runeway_alloc(8, 8);
+        unsafe { __runeway_release(released) };
+        // A traced payload holding another, as left by unwound frames.
+        traced_node(__runeway_alloc(8, 8));
+        unsafe { __runeway_end_run(true) };
+        assert_eq!(heap_stats().live_allocations(), before.live_allocations());
+        assert_eq!(heap_stats().live_bytes, before.live_bytes);
+
+        __runeway_begin_run();
+        let kept = __runeway_alloc(8, 8);
+        unsafe { __runeway_end_run(false) };
+        assert_eq!(heap_stats().live_allocations(), before.live_allocations() + 1);
+        unsafe { __runeway_release(kept) };
+    }
+
+    #[test]
+    fn collection_skips_immortal_payloads() {
+        #[repr(C)]
+        struct Literal {
+            header: [usize; HEADER_WORDS],
+            bytes: [u8; 8],
+        }
+
+        let before = heap_stats();
+        let literal = Literal { header: [IMMORTAL_REFCOUNT, 8, 8, 0], bytes: [0; 8] };
+        let immortal = literal.bytes.as_ptr().cast_mut();
+        let node = traced_node(immortal);
+        unsafe {
+            __runeway_set_trace(immortal, trace_inner);
+            immortal.cast::<*mut u8>().write(node);
+            __runeway_release(node);
+        }
+        __runeway_collect_cycles();
+        assert_eq!(literal.header[0], IMMORTAL_REFCOUNT);
+        assert_eq!(heap_stats().live_allocations(), before.live_allocations());
+    }
+}
@@ -0,0 +1,1037 @@
+//! JSON documents backing `std::json`.
+//!
+//! A document is a managed payload holding a [`Json`]. Array elements and
+//! object values are documents of their own, each holding one reference, so
+//! reading one out shares it with its container. Pushing or inserting a
+//! value stores a deep copy of it instead, so a document never contains
+//! itself and reference counting frees every document. Objects keep their
+//! keys in insertion order.
+//!
+//! Parsing and serializing reject documents nested deeper than
+//! [`MAX_DEPTH`] levels rather than exhausting the stack. Documents built by
+//! pushing and inserting may nest deeper; copying and freeing them does not
+//! recurse.
+//!
+//! Values handed back through slots stay valid until the next call of the
+//! same kind on the thread; documents read out of a slot carry a reference
+//! of their own, as do the strings handed out.
+
+use std::cell::Cell;
+use std::fmt::Write as _;
+use std::ptr;
+
+use runec_abi::{
+    JSON_KIND_ARRAY, JSON_KIND_BOOL, JSON_KIND_NULL, JSON_KIND_NUMBER, JSON_KIND_STRING,
+};
+
+use crate::RawStr;
+use crate::files::{Fallible, str_from_abi};
+use crate::heap::{
+    __runeway_alloc, __runeway_release, __runeway_retain, __runeway_set_drop, is_last_reference,
+};
+use crate::vec::str_vec;
+
+/// Deepest nesting of arrays and objects that is parsed or serialized.
+pub const MAX_DEPTH: usize = 512;
+
+/// Payload of a document.
+#[derive(Debug)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(Box<str>),
+    Array(Vec<*mut u8>),
+    Object(Vec<(Box<str>, *mut u8)>),
+}
+
+impl Json {
+    /// Moves the children of an array or object out of it.
+    fn take_children(&mut self) -> Vec<*mut u8> {
+        match self {
+            Json::Array(items) => std::mem::take(items),
+            Json::Object(entries) => entries.drain(..).map(|(_, value)| value).collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl Drop for Json {
+    /// Releases the children without recursing: the children of a document
+    /// about to be freed are taken out of it first, so arbitrarily deep
+    /// documents do not exhaust the stack.
+    fn drop(&mut self) {
+        let mut pending = self.take_children();
+        while let Some(child) = pending.pop() {
+            // SAFETY: Containers own one reference to each of their
+            // children, and every child is a document.
+            unsafe {
+                if is_last_reference(child) {
+                    pending.append(&mut json_ref(child).take_children());
+                }
+                __runeway_release(child);
+            }
+        }
+    }
+}
+
+thread_local! {
+    static PARSE_RESULT: Cell<Fallible<*mut u8>> = const { Cell::new(Fallible::EMPTY) };
+    static TEXT_RESULT: Cell<Fallible<RawStr>> = const { Cell::new(Fallible::EMPTY) };
+    static TEXT_SLOT: Cell<RawStr> = const { Cell::new(RawStr::EMPTY) };
+    static BOOL_SLOT: Cell<bool> = const { Cell::new(false) };
+    static NUMBER_SLOT: Cell<f64> = const { Cell::new(0.0) };
+    static DOCUMENT_SLOT: Cell<*mut u8> = const { Cell::new(ptr::null_mut()) };
+}
+
+unsafe extern "C" fn drop_json(payload: *mut u8) {
+    // SAFETY: Registered only for payloads created by `alloc_json`.
+    unsafe { ptr::drop_in_place(payload.cast::<Json>()) };
+}
+
+fn alloc_json(json: Json) -> *mut u8 {
+    let payload = __runeway_alloc(size_of::<Json>(), align_of::<Json>());
+    // SAFETY: The payload was just allocated with the size and alignment of
+    // `Json`.
+    unsafe {
+        payload.cast::<Json>().write(json);
+        __runeway_set_drop(payload, drop_json);
+    }
+    payload
+}
+
+/// # Safety
+///
+/// `json` must be a live document created by this module.
+unsafe fn json_ref<'a>(json: *mut u8) -> &'a mut Json {
+    // SAFETY: Forwarded directly from the caller.
+    unsafe { &mut *json.cast::<Json>() }
+}
+
+/// Stores `value` in `slot` and returns its address.
+fn hand_out<T: Copy>(slot: &'static std::thread::LocalKey<Cell<T>>, value: T) -> *const T {
+    slot.with(|cell| {
+        cell.set(value);
+        cell.as_ptr().cast_const()
+    })
+}
+
+/// Hands out a new reference to the document `child`, or null for `None`.
+fn hand_out_document(child: Option<*mut u8>) -> *const *mut u8 {
+    let Some(child) = child else {
+        return ptr::null();
+    };
+    // SAFETY: `child` is owned by a live container.
+    unsafe { __runeway_retain(child) };
+    hand_out(&DOCUMENT_SLOT, child)
+}
+
+fn too_deep() -> String {
+    format!("nesting deeper than {MAX_DEPTH} levels")
+}
+
+/// Recursive-descent parser tracking the position for error messages and
+/// the nesting depth.
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> String {
+        let before = &self.text[..self.pos];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().unwrap_or_default().chars().count() + 1;
+        format!("line {line}, column {column}: {message}")
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c @ (' ' | '\t' | '\n' | '\r')) = self.peek() {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        if self.eat(expected) { Ok(()) } else { Err(self.error(&format!("expected `{expected}`"))) }
+    }
+
+    fn document(&mut self) -> Result<*mut u8, String> {
+        let value = self.value()?;
+        self.skip_whitespace();
+        if self.pos < self.text.len() {
+            // SAFETY: `value` was just created with one reference.
+            unsafe { __runeway_release(value) };
+            return Err(self.error("trailing characters after the document"));
+        }
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<*mut u8, String> {
+        self.skip_whitespace();
+        let json = match self.peek() {
+            Some('{' | '[') if self.depth == MAX_DEPTH => return Err(self.error(&too_deep())),
+            Some(open @ ('{' | '[')) => {
+                self.depth += 1;
+                let value = if open == '{' { self.object() } else { self.array() };
+                self.depth -= 1;
+                return value;
+            }
+            Some('"') => Json::String(self.string()?.into()),
+            Some('-' | '0'..='9') => Json::Number(self.number()?),
+            Some(_) if self.keyword("null") => Json::Null,
+            Some(_) if self.keyword("true") => Json::Bool(true),
+            Some(_) if self.keyword("false") => Json::Bool(false),
+            Some(_) => return Err(self.error("expected a value")),
+            None => return Err(self.error("unexpected end of input")),
+        };
+        Ok(alloc_json(json))
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let found = self.text[self.pos..].starts_with(keyword);
+        if found {
+            self.pos += keyword.len();
+        }
+        found
+    }
+
+    fn number(&mut self) -> Result<f64, String> {
+        let start = self.pos;
+        while let Some(c @ ('-' | '+' | '.' | 'e' | 'E' | '0'..='9')) = self.peek() {
+            self.pos += c.len_utf8();
+        }
+        self.text[start..self.pos].parse().map_err(|_| {
+            self.pos = start;
+            self.error("invalid number")
+        })
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.pos += 1;
+        let mut string = String::new();
+        loop {
+            let Some(c) = self.peek() else {
+                return Err(self.error("unterminated string"));
+            };
+            self.pos += c.len_utf8();
+            match c {
+                '"' => return Ok(string),
+                '\\' => {
+                    let escaped = match self.peek() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            string.push(self.unicode_escape()?);
+                            continue;
+                        }
+                        _ => return Err(self.error("invalid escape")),
+                    };
+                    self.pos += 1;
+                    string.push(escaped);
+                }
+                _ => string.push(c),
+            }
+        }
+    }
+
+    /// Reads the hex digits of a `\u` escape at the position, combining a
+    /// high surrogate with the low surrogate escaped right after it.
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let high = self.hex_code()?;
+        let code = match high {
+            0xd800..=0xdbff => {
+                if !self.text[self.pos..].starts_with("\\u") {
+                    return Err(self.error("unpaired surrogate in unicode escape"));
+                }
+                self.pos += 1;
+                let low = self.hex_code()?;
+                if !(0xdc00..=0xdfff).contains(&low) {
+                    return Err(self.error("unpaired surrogate in unicode escape"));
+                }
+                0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+            }
+            0xdc00..=0xdfff => return Err(self.error("unpaired surrogate in unicode escape")),
+            code => code,
+        };
+        char::from_u32(code).ok_or_else(|| self.error("invalid unicode escape"))
+    }
+
+    /// Reads `u` and four hex digits.
+    fn hex_code(&mut self) -> Result<u32, String> {
+        self.pos += 1;
+        let code = self
+            .text
+            .get(self.pos..self.pos + 4)
+            .filter(|hex| hex.bytes().all(|byte| byte.is_ascii_hexdigit()))
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+        self.pos += 4;
+        Ok(code)
+    }
+
+    fn array(&mut self) -> Result<*mut u8, String> {
+        self.pos += 1;
+        let array = alloc_json(Json::Array(Vec::new()));
+        if self.eat(']') {
+            return Ok(array);
+        }
+        loop {
+            // The array owns what was parsed so far and frees it on errors.
+            let item = self.value().inspect_err(|_| unsafe { __runeway_release(array) })?;
+            // SAFETY: `array` was just created by `alloc_json`.
+            if let Json::Array(items) = unsafe { json_ref(array) } {
+                items.push(item);
+            }
+            if self.eat(']') {
+                return Ok(array);
+            }
+            self.expect(',').inspect_err(|_| unsafe { __runeway_release(array) })?;
+        }
+    }
+
+    fn object(&mut self) -> Result<*mut u8, String> {
+        self.pos += 1;
+        let object = alloc_json(Json::Object(Vec::new()));
+        if self.eat('}') {
+            return Ok(object);
+        }
+        let result = loop {
+            self.skip_whitespace();
+            if self.peek() != Some('"') {
+                break Err(self.error("expected a string key"));
+            }
+            let key = match self.string().and_then(|key| self.expect(':').map(|()| key)) {
+                Ok(key) => key,
+                Err(error) => break Err(error),
+            };
+            let value = match self.value() {
+                Ok(value) => value,
+                Err(error) => break Err(error),
+            };
+            // SAFETY: `object` was just created by `alloc_json`.
+            unsafe { insert(object, key.into(), value) };
+            if self.eat('}') {
+                break Ok(object);
+            }
+            if let Err(error) = self.expect(',') {
+                break Err(error);
+            }
+        };
+        // The object owns what was parsed so far and frees it on errors.
+        result.inspect_err(|_| unsafe { __runeway_release(object) })
+    }
+}
+
+/// Stores `value` under `key`, replacing and releasing any previous value.
+///
+/// # Safety
+///
+/// `object` must be a live document and `value` a reference the object
+/// takes over.
+unsafe fn insert(object: *mut u8, key: Box<str>, value: *mut u8) {
+    // SAFETY: Forwarded directly from the caller.
+    let Json::Object(entries) = (unsafe { json_ref(object) }) else {
+        // SAFETY: The reference was handed over and is not stored.
+        unsafe { __runeway_release(value) };
+        return;
+    };
+    match entries.iter_mut().find(|(existing, _)| *existing == key) {
+        Some((_, slot)) => {
+            let previous = std::mem::replace(slot, value);
+            // SAFETY: The object owned one reference to the replaced value.
+            unsafe { __runeway_release(previous) };
+        }
+        None => entries.push((key, value)),
+    }
+}
+
+/// New document with the same contents as `json`, sharing nothing with it.
+///
+/// # Safety
+///
+/// `json` must be a live document.
+unsafe fn deep_copy(json: *mut u8) -> *mut u8 {
+    // Containers are copied empty and filled from a work list rather than
+    // recursively, so arbitrarily deep documents do not exhaust the stack.
+    let shallow_copy = |json: *mut u8| {
+        // SAFETY: Only called with `json` and its descendants, which are
+        // live while `json` is.
+        let copy = match unsafe { json_ref(json) } {
+            Json::Null => Json::Null,
+            Json::Bool(value) => Json::Bool(*value),
+            Json::Number(value) => Json::Number(*value),
+            Json::String(value) => Json::String(value.clone()),
+            Json::Array(items) => Json::Array(Vec::with_capacity(items.len())),
+            Json::Object(entries) => Json::Object(Vec::with_capacity(entries.len())),
+        };
+        alloc_json(copy)
+    };
+    let root = shallow_copy(json);
+    let mut pending = vec![(json, root)];
+    while let Some((source, copy)) = pending.pop() {
+        // SAFETY: `source` is `json` or one of its descendants, and `copy`
+        // is its fresh copy, a distinct document.
+        match (unsafe { json_ref(source) }, unsafe { json_ref(copy) }) {
+            (Json::Array(items), Json::Array(copies)) => {
+                for &item in items.iter() {
+                    let item_copy = shallow_copy(item);
+                    copies.push(item_copy);
+                    pending.push((item, item_copy));
+                }
+            }
+            (Json::Object(entries), Json::Object(copies)) => {
+                for (key, value) in entries.iter() {
+                    let value_copy = shallow_copy(*value);
+                    copies.push((key.clone(), value_copy));
+                    pending.push((*value, value_copy));
+                }
+            }
+            _ => {}
+        }
+    }
+    root
+}
+
+fn write_string(out: &mut String, string: &str) {
+    out.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if u32::from(c) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", u32::from(c));
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Serializes `json`, indenting nested values by two spaces per `depth`
+/// level when `pretty` is set. Fails once arrays and objects nest deeper
+/// than [`MAX_DEPTH`].
+///
+/// # Safety
+///
+/// `json` must be a live document.
+unsafe fn serialize(
+    out: &mut String,
+    json: *mut u8,
+    pretty: bool,
+    depth: usize,
+) -> Result<(), String> {
+    let newline = |out: &mut String, depth: usize| {
+        if pretty {
+            out.push('\n');
+            out.extend(std::iter::repeat_n("  ", depth));
+        }
+    };
+    // SAFETY: Forwarded directly from the caller; children are live while
+    // their container is.
+    let json = unsafe { json_ref(json) };
+    if matches!(json, Json::Array(_) | Json::Object(_)) && depth == MAX_DEPTH {
+        return Err(too_deep());
+    }
+    match json {
+        Json::Null => out.push_str("null"),
+        Json::Bool(value) => out.push_str(if *value { "true" } else { "false" }),
+        Json::Number(value) if value.is_finite() => {
+            let _ = write!(out, "{value}");
+        }
+        Json::Number(_) => out.push_str("null"),
+        Json::String(value) => write_string(out, value),
+        Json::Array(items) if items.is_empty() => out.push_str("[]"),
+        Json::Array(items) => {
+            out.push('[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                newline(out, depth + 1);
+                unsafe { serialize(out, *item, pretty, depth + 1) }?;
+            }
+            newline(out, depth);
+            out.push(']');
+        }
+        Json::Object(entries) if entries.is_empty() => out.push_str("{}"),
+        Json::Object(entries) => {
+            out.push('{');
+            for (index, (key, value)) in entries.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                newline(out, depth + 1);
+                write_string(out, key);
+                out.push_str(if pretty { ": " } else { ":" });
+                unsafe { serialize(out, *value, pretty, depth + 1) }?;
+            }
+            newline(out, depth);
+            out.push('}');
+        }
+    }
+    Ok(())
+}
+
+/// Serializes `json` into a string handed back as a fallible result.
+///
+/// # Safety
+///
+/// `json` must be a live document.
+unsafe fn hand_out_text(json: *mut u8, pretty: bool) -> *const Fallible<RawStr> {
+    let mut out = String::new();
+    // SAFETY: Forwarded directly from the caller.
+    let result = unsafe { serialize(&mut out, json, pretty, 0) };
+    hand_out(&TEXT_RESULT, Fallible::new(result.map(|()| RawStr::alloc(&out))))
+}
+
+#[unsafe(no_mangle)]
+/// Parses the text as a JSON document. Errors give the line and column.
+///
+/// # Safety
+///
+/// `text_ptr` must be null only when `text_len` is zero. Otherwise it must
+/// point to `text_len` readable bytes that remain valid for the duration of
+/// the call.
+pub unsafe extern "C" fn __runeway_json_parse(
+    text_ptr: *const u8,
+    text_len: usize,
+) -> *const Fallible<*mut u8> {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let text = unsafe { str_from_abi(text_ptr, text_len) };
+    let result = Parser { text: &text, pos: 0, depth: 0 }.document();
+    hand_out(&PARSE_RESULT, Fallible::new(result))
+}
+
+#[unsafe(no_mangle)]
+/// Serializes the document on a single line. Fails for documents nested
+/// deeper than [`MAX_DEPTH`].
+///
+/// # Safety
+///
+/// `json` must be a live document.
+pub unsafe extern "C" fn __runeway_json_stringify(json: *mut u8) -> *const Fallible<RawStr> {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    unsafe { hand_out_text(json, false) }
+}
+
+#[unsafe(no_mangle)]
+/// Serializes the document with nested values on their own lines, indented
+/// by two spaces. Fails like [`__runeway_json_stringify`].
+///
+/// # Safety
+///
+/// `json` must be a live document.
+pub unsafe extern "C" fn __runeway_json_pretty(json: *mut u8) -> *const Fallible<RawStr> {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    unsafe { hand_out_text(json, true) }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn __runeway_json_new_null() -> *mut u8 {
+    alloc_json(Json::Null)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn __runeway_json_from_bool(value: bool) -> *mut u8 {
+    alloc_json(Json::Bool(value))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn __runeway_json_from_number(value: f64) -> *mut u8 {
+    alloc_json(Json::Number(value))
+}
+
+#[unsafe(no_mangle)]
+/// # Safety
+///
+/// Same contract as [`__runeway_json_parse`].
+pub unsafe extern "C" fn __runeway_json_from_str(ptr: *const u8, len: usize) -> *mut u8 {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    alloc_json(Json::String(unsafe { str_from_abi(ptr, len) }.into()))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn __runeway_json_new_array() -> *mut u8 {
+    alloc_json(Json::Array(Vec::new()))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn __runeway_json_new_object() -> *mut u8 {
+    alloc_json(Json::Object(Vec::new()))
+}
+
+#[unsafe(no_mangle)]
+/// # Safety
+///
+/// `json` must be a live document.
+pub unsafe extern "C" fn __runeway_json_is_null(json: *mut u8) -> bool {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    matches!(unsafe { json_ref(json) }, Json::Null)
+}
+
+#[unsafe(no_mangle)]
+/// The value of a boolean document, or null for other documents.
+///
+/// # Safety
+///
+/// `json` must be a live document.
+pub unsafe extern "C" fn __runeway_json_as_bool(json: *mut u8) -> *const bool {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    match unsafe { json_ref(json) } {
+        Json::Bool(value) => hand_out(&BOOL_SLOT, *value),
+        _ => ptr::null(),
+    }
+}
+
+#[unsafe(no_mangle)]
+/// The value of a number document, or null for other documents.
+///
+/// # Safety
+///
+/// `json` must be a live document.
+pub unsafe extern "C" fn __runeway_json_as_number(json: *mut u8) -> *const f64 {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    match unsafe { json_ref(json) } {
+        Json::Number(value) => hand_out(&NUMBER_SLOT, *value),
+        _ => ptr::null(),
+    }
+}
+
+#[unsafe(no_mangle)]
+/// The value of a string document, or null for other documents.
+///
+/// # Safety
+///
+/// `json` must be a live document.
+pub unsafe extern "C" fn __runeway_json_as_str(json: *mut u8) -> *const RawStr {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    match unsafe { json_ref(json) } {
+        Json::String(value) => hand_out(&TEXT_SLOT, RawStr::alloc(value)),
+        _ => ptr::null(),
+    }
+}
+
+#[unsafe(no_mangle)]
+/// Number of elements of an array, entries of an object or characters of a
+/// string; zero for other documents.
+///
+/// # Safety
+///
+/// `json` must be a live document.
+pub unsafe extern "C" fn __runeway_json_len(json: *mut u8) -> usize {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    match unsafe { json_ref(json) } {
+        Json::Array(items) => items.len(),
+        Json::Object(entries) => entries.len(),
+        Json::String(value) => value.chars().count(),
+        _ => 0,
+    }
+}
+
+#[unsafe(no_mangle)]
+/// Element `index` of an array, or null when out of bounds or for other
+/// documents.
+///
+/// # Safety
+///
+/// `json` must be a live document.
+pub unsafe extern "C" fn __runeway_json_at(json: *mut u8, index: usize) -> *const *mut u8 {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let item = match unsafe { json_ref(json) } {
+        Json::Array(items) => items.get(index).copied(),
+        _ => None,
+    };
+    hand_out_document(item)
+}
+
+#[unsafe(no_mangle)]
+/// Value of an object under the key, or null when missing or for other
+/// documents.
+///
+/// # Safety
+///
+/// `json` must be a live document, and `(key_ptr, key_len)` must follow the
+/// contract of [`__runeway_json_parse`].
+pub unsafe extern "C" fn __runeway_json_get(
+    json: *mut u8,
+    key_ptr: *const u8,
+    key_len: usize,
+) -> *const *mut u8 {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let key = unsafe { str_from_abi(key_ptr, key_len) };
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let value = match unsafe { json_ref(json) } {
+        Json::Object(entries) => {
+            entries.iter().find(|(existing, _)| **existing == *key).map(|(_, value)| *value)
+        }
+        _ => None,
+    };
+    hand_out_document(value)
+}
+
+#[unsafe(no_mangle)]
+/// New vector of the keys of an object in insertion order; empty for other
+/// documents.
+///
+/// # Safety
+///
+/// `json` must be a live document.
+pub unsafe extern "C" fn __runeway_json_keys(json: *mut u8) -> *mut u8 {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    match unsafe { json_ref(json) } {
+        Json::Object(entries) => str_vec(entries.iter().map(|(key, _)| key)),
+        _ => str_vec::<&str>([]),
+    }
+}
+
+#[unsafe(no_mangle)]
+/// Appends a deep copy of the value to an array. Does nothing for other
+/// documents.
+///
+/// # Safety
+///
+/// `json` and `value` must be live documents.
+pub unsafe extern "C" fn __runeway_json_push(json: *mut u8, value: *mut u8) {
+    // SAFETY: Forwarded directly from the runtime ABI contract. The copy is
+    // made before the array is borrowed, as `value` may be the array.
+    unsafe {
+        if matches!(json_ref(json), Json::Array(_)) {
+            let copy = deep_copy(value);
+            if let Json::Array(items) = json_ref(json) {
+                items.push(copy);
+            }
+        }
+    }
+}
+
+#[unsafe(no_mangle)]
+/// Stores a deep copy of the value in an object under the key, replacing
+/// any previous value. Does nothing for other documents.
+///
+/// # Safety
+///
+/// `json` and `value` must be live documents, and `(key_ptr, key_len)` must
+/// follow the contract of [`__runeway_json_parse`].
+pub unsafe extern "C" fn __runeway_json_insert(
+    json: *mut u8,
+    key_ptr: *const u8,
+    key_len: usize,
+    value: *mut u8,
+) {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let key = unsafe { str_from_abi(key_ptr, key_len) };
+    // SAFETY: Forwarded directly from the runtime ABI contract; the object
+    // takes over the copy.
+    unsafe {
+        if matches!(json_ref(json), Json::Object(_)) {
+            insert(json, key.into(), deep_copy(value));
+        }
+    }
+}
+
+/// Kind of a document with its article, as named in error messages.
+fn kind_name(json: &Json) -> &'static str {
+    match json {
+        Json::Null => "null",
+        Json::Bool(_) => "a boolean",
+        Json::Number(_) => "a number",
+        Json::String(_) => "a string",
+        Json::Array(_) => "an array",
+        Json::Object(_) => "an object",
+    }
+}
+
+/// Hands out `message` as a new string, or null for `None`.
+fn hand_out_mismatch(message: Option<String>) -> *const RawStr {
+    match message {
+        Some(message) => hand_out(&TEXT_SLOT, RawStr::alloc(&message)),
+        None => ptr::null(),
+    }
+}
+
+#[unsafe(no_mangle)]
+/// Null if the document is of `kind`, one of the `JSON_KIND_*` codes of the
+/// runtime ABI; otherwise a new string describing the mismatch, such as
+/// `expected a string, found a number`.
+///
+/// # Safety
+///
+/// `json` must be a live document.
+pub unsafe extern "C" fn __runeway_json_check_kind(json: *mut u8, kind: usize) -> *const RawStr {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let json = unsafe { json_ref(json) };
+    let expected = match kind {
+        JSON_KIND_NULL => "null",
+        JSON_KIND_BOOL => "a boolean",
+        JSON_KIND_NUMBER => "a number",
+        JSON_KIND_STRING => "a string",
+        JSON_KIND_ARRAY => "an array",
+        _ => "an object",
+    };
+    let actual = kind_name(json);
+    hand_out_mismatch((actual != expected).then(|| format!("expected {expected}, found {actual}")))
+}
+
+#[unsafe(no_mangle)]
+/// Null if the document is a number without a fractional part that fits an
+/// integer of `bits` bits, signed or not; otherwise a new string describing
+/// the mismatch, such as ``expected an integer of type `u8`, found 300``.
+///
+/// # Safety
+///
+/// `json` must be a live document.
+pub unsafe extern "C" fn __runeway_json_check_int(
+    json: *mut u8,
+    signed: bool,
+    bits: usize,
+) -> *const RawStr {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let json = unsafe { json_ref(json) };
+    // Powers of two are exact, so the bounds are too.
+    let end = 2f64.powi(bits as i32 - i32::from(signed));
+    let start = if signed { -end } else { 0.0 };
+    let fits = matches!(json, Json::Number(value)
+        if value.fract() == 0.0 && *value >= start && *value < end);
+    let message = (!fits).then(|| {
+        let actual = match json {
+            Json::Number(value) => value.to_string(),
+            json => kind_name(json).to_string(),
+        };
+        let ty = format!("{}{bits}", if signed { 'i' } else { 'u' });
+        format!("expected an integer of type `{ty}`, found {actual}")
+    });
+    hand_out_mismatch(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use runec_abi::{JSON_KIND_ARRAY, JSON_KIND_NUMBER, JSON_KIND_OBJECT};
+
+    use super::{
+        __runeway_json_as_str, __runeway_json_at, __runeway_json_check_int,
+        __runeway_json_check_kind, __runeway_json_from_number, __runeway_json_from_str,
+        __runeway_json_get, __runeway_json_insert, __runeway_json_len, __runeway_json_new_array,
+        __runeway_json_new_object, __runeway_json_parse, __runeway_json_pretty,
+        __runeway_json_push, __runeway_json_stringify, Json, MAX_DEPTH, alloc_json,
+    };
+    use crate::files::Fallible;
+    use crate::heap::__runeway_release;
+    use crate::{RawStr, heap_stats};
+
+    fn text(result: *const Fallible<RawStr>) -> Result<String, String> {
+        // SAFETY: The runtime hands over a result the caller owns.
+        unsafe { *result }.into_result().map(|raw| unsafe { raw.into_string() })
+    }
+
+    fn parse(source: &str) -> Result<*mut u8, String> {
+        // SAFETY: `source` is valid for the call.
+        unsafe { *__runeway_json_parse(source.as_ptr(), source.len()) }.into_result()
+    }
+
+    #[test]
+    fn parses_and_reserializes_documents() {
+        let source = r#" {"name": "rune\nway", "tags": [1, 2.5, true, null], "nested": {}} "#;
+        let json = parse(source).unwrap();
+        // SAFETY: `json` is a live document.
+        unsafe {
+            assert_eq!(
+                text(__runeway_json_stringify(json)).unwrap(),
+                r#"{"name":"rune\nway","tags":[1,2.5,true,null],"nested":{}}"#
+            );
+            assert_eq!(
+                text(__runeway_json_pretty(json)).unwrap(),
+                "{\n  \"name\": \"rune\\nway\",\n  \"tags\": [\n    1,\n    2.5,\n    true,\n    null\n  ],\n  \"nested\": {}\n}"
+            );
+            let tags = *__runeway_json_get(json, "tags".as_ptr(), 4);
+            assert_eq!(__runeway_json_len(tags), 4);
+            assert!(__runeway_json_at(tags, 4).is_null());
+            __runeway_release(tags);
+            __runeway_release(json);
+        }
+    }
+
+    #[test]
+    fn reports_parse_errors_with_line_and_column() {
+        assert_eq!(parse("[1,\n  2,,]").unwrap_err(), "line 2, column 5: expected a value");
+        assert_eq!(parse("{\"a\" 1}").unwrap_err(), "line 1, column 6: expected `:`");
+        assert_eq!(
+            parse("[1] x").unwrap_err(),
+            "line 1, column 5: trailing characters after the document"
+        );
+    }
+
+    #[test]
+    fn builds_documents_and_frees_them_with_their_children() {
+        let before = heap_stats().live_allocations();
+        // SAFETY: Every document is live until released below.
+        unsafe {
+            let object = __runeway_json_new_object();
+            let array = __runeway_json_new_array();
+            let number = __runeway_json_from_number(3.0);
+            __runeway_json_push(array, number);
+            __runeway_json_insert(object, "xs".as_ptr(), 2, array);
+            let name = __runeway_json_from_str("a\"b".as_ptr(), 3);
+            __runeway_json_insert(object, "name".as_ptr(), 4, name);
+            __runeway_json_insert(object, "name".as_ptr(), 4, number);
+            for value in [number, array, name] {
+                __runeway_release(value);
+            }
+            assert_eq!(text(__runeway_json_stringify(object)).unwrap(), r#"{"xs":[3],"name":3}"#);
+            __runeway_release(object);
+        }
+        assert_eq!(heap_stats().live_allocations(), before);
+    }
+
+    #[test]
+    fn pushes_and_inserts_copies_so_documents_cannot_contain_themselves() {
+        let before = heap_stats().live_allocations();
+        // SAFETY: Every document is live until released below.
+        unsafe {
+            let array = __runeway_json_new_array();
+            __runeway_json_push(array, array);
+            __runeway_json_push(array, array);
+            let object = __runeway_json_new_object();
+            __runeway_json_insert(object, "self".as_ptr(), 4, object);
+            __runeway_json_insert(object, "array".as_ptr(), 5, array);
+            __runeway_json_push(array, object);
+            assert_eq!(
+                text(__runeway_json_stringify(array)).unwrap(),
+                r#"[[],[[]],{"self":{},"array":[[],[[]]]}]"#
+            );
+            assert_eq!(
+                text(__runeway_json_stringify(object)).unwrap(),
+                r#"{"self":{},"array":[[],[[]]]}"#
+            );
+            __runeway_release(array);
+            __runeway_release(object);
+        }
+        assert_eq!(heap_stats().live_allocations(), before);
+    }
+
+    #[test]
+    fn combines_surrogate_pairs_and_rejects_unpaired_surrogates() {
+        let json = parse(r#""\ud83e\uDD80 \u00e9""#).unwrap();
+        // SAFETY: `json` is a live document.
+        unsafe {
+            assert_eq!((*__runeway_json_as_str(json)).into_string(), "\u{1f980} \u{e9}");
+            __runeway_release(json);
+        }
+        for source in [r#""\ud83e""#, r#""\ud83eA""#, r#""\udd80""#] {
+            assert!(parse(source).unwrap_err().contains("unpaired surrogate"), "{source}");
+        }
+        assert_eq!(parse(r#""\u+041""#).unwrap_err(), "line 1, column 4: invalid unicode escape");
+    }
+
+    #[test]
+    fn limits_the_nesting_depth_of_parsed_and_serialized_documents() {
+        let nested = |depth| "[".repeat(depth) + &"]".repeat(depth);
+        assert_eq!(
+            parse(&nested(MAX_DEPTH + 1)).unwrap_err(),
+            format!("line 1, column {}: nesting deeper than {MAX_DEPTH} levels", MAX_DEPTH + 1)
+        );
+        let before = heap_stats().live_allocations();
+        let json = parse(&nested(MAX_DEPTH)).unwrap();
+        // SAFETY: Every document is live until released below.
+        unsafe {
+            assert_eq!(text(__runeway_json_stringify(json)).unwrap(), nested(MAX_DEPTH));
+            let outer = __runeway_json_new_array();
+            __runeway_json_push(outer, json);
+            assert_eq!(
+                text(__runeway_json_pretty(outer)).unwrap_err(),
+                format!("nesting deeper than {MAX_DEPTH} levels")
+            );
+            __runeway_release(outer);
+            __runeway_release(json);
+        }
+        assert_eq!(heap_stats().live_allocations(), before);
+    }
+
+    #[test]
+    fn copies_and_frees_documents_nested_past_the_limit() {
+        let before = heap_stats().live_allocations();
+        let mut nested = alloc_json(Json::Array(Vec::new()));
+        for _ in 0..100_000 {
+            nested = alloc_json(Json::Array(vec![nested]));
+        }
+        // SAFETY: Every document is live until released below.
+        unsafe {
+            let outer = __runeway_json_new_array();
+            __runeway_json_push(outer, nested);
+            __runeway_release(nested);
+            assert_eq!(__runeway_json_len(outer), 1);
+            assert_eq!(
+                text(__runeway_json_stringify(outer)).unwrap_err(),
+                format!("nesting deeper than {MAX_DEPTH} levels")
+            );
+            __runeway_release(outer);
+        }
+        assert_eq!(heap_stats().live_allocations(), before);
+    }
+
+    #[test]
+    fn checks_kinds_and_integer_ranges_of_documents() {
+        let mismatch = |message: *const RawStr| {
+            // SAFETY: The runtime hands over a string the caller owns, or
+            // null.
+            (!message.is_null()).then(|| unsafe { (*message).into_string() })
+        };
+        let json = parse(r#"[300, -128, 3.5, 18446744073709551616, {}]"#).unwrap();
+        // SAFETY: Every document is live until released below.
+        unsafe {
+            assert_eq!(mismatch(__runeway_json_check_kind(json, JSON_KIND_ARRAY)), None);
+            assert_eq!(
+                mismatch(__runeway_json_check_kind(json, JSON_KIND_OBJECT)).as_deref(),
+                Some("expected an object, found an array")
+            );
+            let item = |index| *__runeway_json_at(json, index);
+            let [large, small, fraction, huge, object] = [0, 1, 2, 3, 4].map(item);
+            assert_eq!(mismatch(__runeway_json_check_int(large, false, 16)), None);
+            assert_eq!(
+                mismatch(__runeway_json_check_int(large, false, 8)).as_deref(),
+                Some("expected an integer of type `u8`, found 300")
+            );
+            assert_eq!(mismatch(__runeway_json_check_int(small, true, 8)), None);
+            assert!(mismatch(__runeway_json_check_int(small, false, 64)).is_some());
+            assert_eq!(
+                mismatch(__runeway_json_check_int(fraction, true, 64)).as_deref(),
+                Some("expected an integer of type `i64`, found 3.5")
+            );
+            assert!(mismatch(__runeway_json_check_int(huge, false, 64)).is_some());
+            assert_eq!(
+                mismatch(__runeway_json_check_int(object, true, 32)).as_deref(),
+                Some("expected an integer of type `i32`, found an object")
+            );
+            assert_eq!(
+                mismatch(__runeway_json_check_kind(object, JSON_KIND_NUMBER)).as_deref(),
+                Some("expected a number, found an object")
+            );
+            for value in [large, small, fraction, huge, object, json] {
+                __runeway_release(value);
+            }
+        }
+    }
+}
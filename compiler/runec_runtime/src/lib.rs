@@ -1,6 +1,47 @@
-use std::io::{self, Write};
+use std::cell::Cell;
+use std::hash::{DefaultHasher, Hasher};
+use std::io::{self, BufRead, Write};
 
-use runec_abi::{RUNTIME_PRINT, RUNTIME_PRINTLN, RuntimeFunctionId, runtime_function};
+use runec_abi::{
+    RUNTIME_ACOS, RUNTIME_ALLOC, RUNTIME_APPEND, RUNTIME_ASIN, RUNTIME_ATAN, RUNTIME_ATAN2,
+    RUNTIME_BEGIN_RUN, RUNTIME_COLLECT_CYCLES, RUNTIME_COS, RUNTIME_DECIMAL_ADD,
+    RUNTIME_DECIMAL_CMP, RUNTIME_DECIMAL_DIV, RUNTIME_DECIMAL_FROM_INT, RUNTIME_DECIMAL_MUL,
+    RUNTIME_DECIMAL_NEG, RUNTIME_DECIMAL_PARSE, RUNTIME_DECIMAL_ROUND, RUNTIME_DECIMAL_SCALE,
+    RUNTIME_DECIMAL_SUB, RUNTIME_DECIMAL_TO_STRING, RUNTIME_END_RUN, RUNTIME_EXISTS, RUNTIME_EXP,
+    RUNTIME_FLUSH, RUNTIME_INDEX_OUT_OF_BOUNDS, RUNTIME_INPUT, RUNTIME_INSTANT_ELAPSED,
+    RUNTIME_INSTANT_NOW, RUNTIME_INT_TO_STRING, RUNTIME_JSON_AS_BOOL, RUNTIME_JSON_AS_NUMBER,
+    RUNTIME_JSON_AS_STR, RUNTIME_JSON_AT, RUNTIME_JSON_CHECK_INT, RUNTIME_JSON_CHECK_KIND,
+    RUNTIME_JSON_FROM_BOOL, RUNTIME_JSON_FROM_NUMBER, RUNTIME_JSON_FROM_STR, RUNTIME_JSON_GET,
+    RUNTIME_JSON_INSERT, RUNTIME_JSON_IS_NULL, RUNTIME_JSON_KEYS, RUNTIME_JSON_LEN,
+    RUNTIME_JSON_NEW_ARRAY, RUNTIME_JSON_NEW_NULL, RUNTIME_JSON_NEW_OBJECT, RUNTIME_JSON_PARSE,
+    RUNTIME_JSON_PRETTY, RUNTIME_JSON_PUSH, RUNTIME_JSON_STRINGIFY, RUNTIME_LINES_NEXT,
+    RUNTIME_LIST_DIR, RUNTIME_LN, RUNTIME_LOG2, RUNTIME_LOG10, RUNTIME_MAP_GET, RUNTIME_MAP_INSERT,
+    RUNTIME_MAP_KEYS, RUNTIME_MAP_NEW, RUNTIME_MAP_NEW_RECORDS, RUNTIME_MAP_REMOVE,
+    RUNTIME_MAP_VALUES, RUNTIME_PANIC, RUNTIME_POW, RUNTIME_PRINT, RUNTIME_PRINTLN,
+    RUNTIME_RANDOM_EMPTY_RANGE, RUNTIME_RANDOM_FLOAT, RUNTIME_RANDOM_FLOAT_RANGE,
+    RUNTIME_RANDOM_INT_RANGE, RUNTIME_RANDOM_NEXT, RUNTIME_RANDOM_OS_SEED, RUNTIME_RANDOM_SEED,
+    RUNTIME_RANDOM_SHUFFLE, RUNTIME_READ_TO_STRING, RUNTIME_READER_LINES, RUNTIME_READER_OPEN,
+    RUNTIME_READER_READ_LINE, RUNTIME_READER_STDIN, RUNTIME_RELEASE, RUNTIME_REMOVE,
+    RUNTIME_RETAIN, RUNTIME_ROUND, RUNTIME_SET_DROP, RUNTIME_SET_STDOUT_BUFFERED,
+    RUNTIME_SET_TRACE, RUNTIME_SIN, RUNTIME_SLEEP, RUNTIME_SLICE_OUT_OF_BOUNDS, RUNTIME_STR_CMP,
+    RUNTIME_STR_HASH, RUNTIME_TAN, RUNTIME_UNCAUGHT_ERROR, RUNTIME_UNIX_TIME, RUNTIME_VEC_NEW,
+    RUNTIME_VEC_POP, RUNTIME_VEC_PUSH, RUNTIME_WRITE, RUNTIME_WRITER_CREATE, RUNTIME_WRITER_FLUSH,
+    RUNTIME_WRITER_STDOUT, RUNTIME_WRITER_WRITE, RUNTIME_WRITER_WRITE_LINE, RuntimeFunctionId,
+    runtime_function,
+};
+
+pub mod buffered;
+pub mod decimal;
+pub mod files;
+pub mod heap;
+pub mod json;
+pub mod map;
+pub mod math;
+pub mod random;
+pub mod time;
+pub mod vec;
+
+pub use heap::{HeapStats, heap_stats};
 
 pub type RuntimeFunctionAddress = *const u8;
 
@@ -11,27 +52,269 @@ pub struct RuntimeSymbol {
     pub address: RuntimeFunctionAddress,
 }
 
-pub fn symbols() -> [RuntimeSymbol; 2] {
+pub fn symbols() -> [RuntimeSymbol; 103] {
     [
-        RuntimeSymbol {
-            id: RUNTIME_PRINT,
-            name: runtime_function(RUNTIME_PRINT).expect("print runtime declaration").symbol,
-            address: __runeway_print as RuntimeFunctionAddress,
-        },
-        RuntimeSymbol {
-            id: RUNTIME_PRINTLN,
-            name: runtime_function(RUNTIME_PRINTLN).expect("println runtime declaration").symbol,
-            address: __runeway_println as RuntimeFunctionAddress,
-        },
+        runtime_symbol(RUNTIME_PRINT, __runeway_print as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_PRINTLN, __runeway_println as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_ALLOC, heap::__runeway_alloc as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_RETAIN, heap::__runeway_retain as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_RELEASE, heap::__runeway_release as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_UNCAUGHT_ERROR, __runeway_uncaught_error as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_SET_DROP, heap::__runeway_set_drop as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_VEC_NEW, vec::__runeway_vec_new as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_VEC_PUSH, vec::__runeway_vec_push as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_VEC_POP, vec::__runeway_vec_pop as RuntimeFunctionAddress),
+        runtime_symbol(
+            RUNTIME_INDEX_OUT_OF_BOUNDS,
+            vec::__runeway_index_out_of_bounds as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_SLICE_OUT_OF_BOUNDS,
+            vec::__runeway_slice_out_of_bounds as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(RUNTIME_MAP_NEW, map::__runeway_map_new as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_MAP_INSERT, map::__runeway_map_insert as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_MAP_GET, map::__runeway_map_get as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_MAP_REMOVE, map::__runeway_map_remove as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_MAP_KEYS, map::__runeway_map_keys as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_MAP_VALUES, map::__runeway_map_values as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_INPUT, __runeway_input as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_POW, math::__runeway_pow as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_ROUND, math::__runeway_round as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_SIN, math::__runeway_sin as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_COS, math::__runeway_cos as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_TAN, math::__runeway_tan as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_ASIN, math::__runeway_asin as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_ACOS, math::__runeway_acos as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_ATAN, math::__runeway_atan as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_ATAN2, math::__runeway_atan2 as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_EXP, math::__runeway_exp as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_LN, math::__runeway_ln as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_LOG2, math::__runeway_log2 as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_LOG10, math::__runeway_log10 as RuntimeFunctionAddress),
+        runtime_symbol(
+            RUNTIME_READ_TO_STRING,
+            files::__runeway_read_to_string as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(RUNTIME_WRITE, files::__runeway_write as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_APPEND, files::__runeway_append as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_EXISTS, files::__runeway_exists as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_REMOVE, files::__runeway_remove as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_LIST_DIR, files::__runeway_list_dir as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_INSTANT_NOW, time::__runeway_instant_now as RuntimeFunctionAddress),
+        runtime_symbol(
+            RUNTIME_INSTANT_ELAPSED,
+            time::__runeway_instant_elapsed as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(RUNTIME_SLEEP, time::__runeway_sleep as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_UNIX_TIME, time::__runeway_unix_time as RuntimeFunctionAddress),
+        runtime_symbol(
+            RUNTIME_RANDOM_SEED,
+            random::__runeway_random_seed as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_RANDOM_OS_SEED,
+            random::__runeway_random_os_seed as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_RANDOM_NEXT,
+            random::__runeway_random_next as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_RANDOM_INT_RANGE,
+            random::__runeway_random_int_range as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_RANDOM_FLOAT,
+            random::__runeway_random_float as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_RANDOM_FLOAT_RANGE,
+            random::__runeway_random_float_range as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_RANDOM_SHUFFLE,
+            random::__runeway_random_shuffle as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(RUNTIME_JSON_PARSE, json::__runeway_json_parse as RuntimeFunctionAddress),
+        runtime_symbol(
+            RUNTIME_JSON_STRINGIFY,
+            json::__runeway_json_stringify as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(RUNTIME_JSON_PRETTY, json::__runeway_json_pretty as RuntimeFunctionAddress),
+        runtime_symbol(
+            RUNTIME_JSON_NEW_NULL,
+            json::__runeway_json_new_null as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_JSON_FROM_BOOL,
+            json::__runeway_json_from_bool as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_JSON_FROM_NUMBER,
+            json::__runeway_json_from_number as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_JSON_FROM_STR,
+            json::__runeway_json_from_str as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_JSON_NEW_ARRAY,
+            json::__runeway_json_new_array as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_JSON_NEW_OBJECT,
+            json::__runeway_json_new_object as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_JSON_IS_NULL,
+            json::__runeway_json_is_null as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_JSON_AS_BOOL,
+            json::__runeway_json_as_bool as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_JSON_AS_NUMBER,
+            json::__runeway_json_as_number as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(RUNTIME_JSON_AS_STR, json::__runeway_json_as_str as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_JSON_LEN, json::__runeway_json_len as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_JSON_AT, json::__runeway_json_at as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_JSON_GET, json::__runeway_json_get as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_JSON_KEYS, json::__runeway_json_keys as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_JSON_PUSH, json::__runeway_json_push as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_JSON_INSERT, json::__runeway_json_insert as RuntimeFunctionAddress),
+        runtime_symbol(
+            RUNTIME_DECIMAL_PARSE,
+            decimal::__runeway_decimal_parse as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_DECIMAL_FROM_INT,
+            decimal::__runeway_decimal_from_int as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_DECIMAL_TO_STRING,
+            decimal::__runeway_decimal_to_string as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_DECIMAL_ADD,
+            decimal::__runeway_decimal_add as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_DECIMAL_SUB,
+            decimal::__runeway_decimal_sub as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_DECIMAL_MUL,
+            decimal::__runeway_decimal_mul as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_DECIMAL_DIV,
+            decimal::__runeway_decimal_div as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_DECIMAL_ROUND,
+            decimal::__runeway_decimal_round as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_DECIMAL_CMP,
+            decimal::__runeway_decimal_cmp as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_DECIMAL_NEG,
+            decimal::__runeway_decimal_neg as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_DECIMAL_SCALE,
+            decimal::__runeway_decimal_scale as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(RUNTIME_FLUSH, buffered::__runeway_flush as RuntimeFunctionAddress),
+        runtime_symbol(
+            RUNTIME_SET_STDOUT_BUFFERED,
+            buffered::__runeway_set_stdout_buffered as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_READER_OPEN,
+            buffered::__runeway_reader_open as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_READER_STDIN,
+            buffered::__runeway_reader_stdin as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_READER_READ_LINE,
+            buffered::__runeway_reader_read_line as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_READER_LINES,
+            buffered::__runeway_reader_lines as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_WRITER_CREATE,
+            buffered::__runeway_writer_create as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_WRITER_STDOUT,
+            buffered::__runeway_writer_stdout as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_WRITER_WRITE,
+            buffered::__runeway_writer_write as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_WRITER_WRITE_LINE,
+            buffered::__runeway_writer_write_line as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_WRITER_FLUSH,
+            buffered::__runeway_writer_flush as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(RUNTIME_PANIC, __runeway_panic as RuntimeFunctionAddress),
+        runtime_symbol(
+            RUNTIME_LINES_NEXT,
+            buffered::__runeway_lines_next as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(RUNTIME_SET_TRACE, heap::__runeway_set_trace as RuntimeFunctionAddress),
+        runtime_symbol(
+            RUNTIME_COLLECT_CYCLES,
+            heap::__runeway_collect_cycles as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(RUNTIME_BEGIN_RUN, heap::__runeway_begin_run as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_END_RUN, heap::__runeway_end_run as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_INT_TO_STRING, __runeway_int_to_string as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_STR_CMP, __runeway_str_cmp as RuntimeFunctionAddress),
+        runtime_symbol(RUNTIME_STR_HASH, __runeway_str_hash as RuntimeFunctionAddress),
+        runtime_symbol(
+            RUNTIME_MAP_NEW_RECORDS,
+            map::__runeway_map_new_records as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_RANDOM_EMPTY_RANGE,
+            random::__runeway_random_empty_range as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_JSON_CHECK_KIND,
+            json::__runeway_json_check_kind as RuntimeFunctionAddress,
+        ),
+        runtime_symbol(
+            RUNTIME_JSON_CHECK_INT,
+            json::__runeway_json_check_int as RuntimeFunctionAddress,
+        ),
     ]
 }
 
+fn runtime_symbol(id: RuntimeFunctionId, address: RuntimeFunctionAddress) -> RuntimeSymbol {
+    let name = runtime_function(id).expect("runtime declaration").symbol;
+    RuntimeSymbol { id, name, address }
+}
+
 pub fn resolve_symbol(name: &str) -> Option<RuntimeFunctionAddress> {
     symbols().into_iter().find(|symbol| symbol.name == name).map(|symbol| symbol.address)
 }
 
 #[unsafe(no_mangle)]
-/// Writes `len` bytes starting at `ptr` to standard output.
+/// Writes `len` bytes starting at `ptr` to the standard output buffer.
 ///
 /// # Safety
 ///
@@ -43,14 +326,12 @@ pub unsafe extern "C" fn __runeway_print(ptr: *const u8, len: usize) {
         return;
     };
 
-    let mut stdout = io::stdout().lock();
-    let _ = stdout.write_all(bytes);
-    let _ = stdout.flush();
+    buffered::write_stdout(&[bytes]);
 }
 
 #[unsafe(no_mangle)]
-/// Writes `len` bytes starting at `ptr` to standard output, followed by a
-/// newline.
+/// Writes `len` bytes starting at `ptr` to the standard output buffer,
+/// followed by a newline.
 ///
 /// # Safety
 ///
@@ -62,10 +343,220 @@ pub unsafe extern "C" fn __runeway_println(ptr: *const u8, len: usize) {
         return;
     };
 
-    let mut stdout = io::stdout().lock();
-    let _ = stdout.write_all(bytes);
-    let _ = stdout.write_all(b"\n");
-    let _ = stdout.flush();
+    buffered::write_stdout(&[bytes, b"\n"]);
+}
+
+/// `str` as generated code passes it around.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct RawStr {
+    pub ptr: *const u8,
+    pub len: usize,
+}
+
+impl RawStr {
+    pub(crate) const EMPTY: Self = Self { ptr: std::ptr::null(), len: 0 };
+
+    /// Copies `text` into a new managed payload. Generated code owns the
+    /// reference and releases it like that of any other `str`.
+    pub(crate) fn alloc(text: &str) -> Self {
+        let ptr = heap::__runeway_alloc(text.len(), 1);
+        // SAFETY: The payload was just allocated with room for `text`.
+        unsafe { std::ptr::copy_nonoverlapping(text.as_ptr(), ptr, text.len()) };
+        Self { ptr, len: text.len() }
+    }
+
+    /// Copies the string out and releases the reference to it.
+    ///
+    /// # Safety
+    ///
+    /// The string must be a live managed string or a literal.
+    #[cfg(test)]
+    pub(crate) unsafe fn into_string(self) -> String {
+        // SAFETY: Forwarded from the caller.
+        let bytes = unsafe { bytes_from_abi(self.ptr, self.len) }.unwrap_or_default();
+        let text = String::from_utf8_lossy(bytes).into_owned();
+        // SAFETY: Forwarded from the caller.
+        unsafe { heap::__runeway_release(self.ptr.cast_mut()) };
+        text
+    }
+}
+
+thread_local! {
+    /// The last line read by `input`.
+    static INPUT_SLOT: Cell<RawStr> = const { Cell::new(RawStr::EMPTY) };
+    /// The last integer formatted by `int_to_string`.
+    static INT_TEXT_SLOT: Cell<RawStr> = const { Cell::new(RawStr::EMPTY) };
+}
+
+#[unsafe(no_mangle)]
+/// Formats an integer in decimal. Narrower integers are extended to 64 bits
+/// first: sign-extended when `signed` is `1`, which formats `value` as an
+/// `i64`, and zero-extended otherwise, which formats it as a `u64`.
+///
+/// Returns a pointer to the `(ptr, len)` of the text, valid until the next
+/// call. The caller owns the text.
+pub extern "C" fn __runeway_int_to_string(value: i64, signed: i8) -> *const RawStr {
+    let text = if signed != 0 { value.to_string() } else { (value as u64).to_string() };
+    INT_TEXT_SLOT.with(|cell| {
+        cell.set(RawStr::alloc(&text));
+        cell.as_ptr().cast_const()
+    })
+}
+
+#[unsafe(no_mangle)]
+/// Compares two strings by their bytes, which orders them by code point.
+/// Returns `-1`, `0` or `1` as the first is less than, equal to or greater
+/// than the second.
+///
+/// # Safety
+///
+/// Each pointer must be null only when its length is zero. Otherwise it
+/// must point to that many readable bytes that remain valid for the
+/// duration of the call.
+pub unsafe extern "C" fn __runeway_str_cmp(
+    lhs_ptr: *const u8,
+    lhs_len: usize,
+    rhs_ptr: *const u8,
+    rhs_len: usize,
+) -> i64 {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let (lhs, rhs) =
+        unsafe { (bytes_from_abi(lhs_ptr, lhs_len), bytes_from_abi(rhs_ptr, rhs_len)) };
+    lhs.unwrap_or_default().cmp(rhs.unwrap_or_default()) as i64
+}
+
+#[unsafe(no_mangle)]
+/// Hashes a string by its bytes, as the hash functions generated for struct
+/// and enum map keys do for their string fields.
+///
+/// # Safety
+///
+/// `ptr` must be null only when `len` is zero. Otherwise it must point to
+/// `len` readable bytes that remain valid for the duration of the call.
+pub unsafe extern "C" fn __runeway_str_hash(ptr: *const u8, len: usize) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    hasher.write(unsafe { bytes_from_abi(ptr, len) }.unwrap_or_default());
+    hasher.finish()
+}
+
+#[unsafe(no_mangle)]
+/// Writes the prompt to standard output along with any buffered output and
+/// reads a line from standard input, without its line terminator.
+///
+/// Returns a pointer to the `(ptr, len)` of the line, valid until the next
+/// call, or null at the end of input. The caller owns the line.
+///
+/// # Safety
+///
+/// `ptr` must be null only when `len` is zero. Otherwise it must point to
+/// `len` readable bytes that remain valid for the duration of the call.
+pub unsafe extern "C" fn __runeway_input(ptr: *const u8, len: usize) -> *const u8 {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let prompt = unsafe { bytes_from_abi(ptr, len) }.unwrap_or_default();
+
+    buffered::write_stdout(&[prompt]);
+    buffered::__runeway_flush();
+
+    let Some(line) = read_line(&mut io::stdin().lock()) else {
+        return std::ptr::null();
+    };
+    let line = RawStr::alloc(&line);
+    INPUT_SLOT.with(|cell| {
+        cell.set(line);
+        cell.as_ptr().cast()
+    })
+}
+
+/// Reads a line without its `\n` or `\r\n`, or `None` at the end of input or
+/// when reading fails.
+fn read_line(reader: &mut impl BufRead) -> Option<Box<str>> {
+    let mut bytes = Vec::new();
+    match reader.read_until(b'\n', &mut bytes) {
+        Ok(0) | Err(_) => return None,
+        Ok(_) => {}
+    }
+    if bytes.ends_with(b"\n") {
+        bytes.pop();
+        if bytes.ends_with(b"\r") {
+            bytes.pop();
+        }
+    }
+    Some(String::from_utf8_lossy(&bytes).into())
+}
+
+#[unsafe(no_mangle)]
+/// Reports an error that escaped `main` on standard error, along with the
+/// source location of the `throw` or `?` it escaped through, and exits the
+/// process with status 1.
+///
+/// # Safety
+///
+/// Each pointer must be null only when its length is zero. Otherwise it
+/// must point to that many readable bytes that remain valid for the
+/// duration of the call.
+pub unsafe extern "C" fn __runeway_uncaught_error(
+    msg_ptr: *const u8,
+    msg_len: usize,
+    file_ptr: *const u8,
+    file_len: usize,
+    line: usize,
+    col: usize,
+) {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let message = unsafe { bytes_from_abi(msg_ptr, msg_len) }.unwrap_or_default();
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let file = unsafe { bytes_from_abi(file_ptr, file_len) }.unwrap_or_default();
+
+    buffered::__runeway_flush();
+    let mut stderr = io::stderr().lock();
+    let _ = writeln!(stderr, "{}", uncaught_message(message, file, line, col));
+    let _ = stderr.flush();
+    std::process::exit(1);
+}
+
+#[unsafe(no_mangle)]
+/// Reports a call of `panic` or a failed bounds check with its source
+/// location on standard error and exits the process with status 101.
+///
+/// # Safety
+///
+/// Each pointer must be null only when its length is zero. Otherwise it
+/// must point to that many readable bytes that remain valid for the
+/// duration of the call.
+pub unsafe extern "C" fn __runeway_panic(
+    msg_ptr: *const u8,
+    msg_len: usize,
+    file_ptr: *const u8,
+    file_len: usize,
+    line: usize,
+    col: usize,
+) {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let message = unsafe { bytes_from_abi(msg_ptr, msg_len) }.unwrap_or_default();
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let file = unsafe { bytes_from_abi(file_ptr, file_len) }.unwrap_or_default();
+
+    buffered::__runeway_flush();
+    let mut stderr = io::stderr().lock();
+    let _ = writeln!(stderr, "{}", panic_message(message, file, line, col));
+    let _ = stderr.flush();
+    std::process::exit(101);
+}
+
+/// Formats a panic at `file:line:col` the way the JIT reports it.
+fn panic_message(message: &[u8], file: &[u8], line: usize, col: usize) -> String {
+    let message = String::from_utf8_lossy(message);
+    let file = String::from_utf8_lossy(file);
+    format!("panicked at {file}:{line}:{col}: {message}")
+}
+
+/// Formats an error thrown out of `main` at `file:line:col`.
+fn uncaught_message(message: &[u8], file: &[u8], line: usize, col: usize) -> String {
+    let message = String::from_utf8_lossy(message);
+    let file = String::from_utf8_lossy(file);
+    format!("uncaught error at {file}:{line}:{col}: {message}")
 }
 
 unsafe fn bytes_from_abi<'a>(ptr: *const u8, len: usize) -> Option<&'a [u8]> {
@@ -82,7 +573,10 @@ unsafe fn bytes_from_abi<'a>(ptr: *const u8, len: usize) -> Option<&'a [u8]> {
 mod tests {
     use runec_abi::RUNTIME_FUNCTIONS;
 
-    use super::{resolve_symbol, symbols};
+    use super::{
+        __runeway_int_to_string, __runeway_str_cmp, panic_message, read_line, resolve_symbol,
+        symbols, uncaught_message,
+    };
 
     #[test]
     fn exports_every_declared_runtime_symbol() {
@@ -92,4 +586,55 @@ mod tests {
             assert!(resolve_symbol(declaration.symbol).is_some());
         }
     }
+
+    #[test]
+    fn formats_integers_by_their_signedness() {
+        let format = |value, signed| {
+            // SAFETY: The runtime hands out a new managed string.
+            unsafe { (*__runeway_int_to_string(value, signed)).into_string() }
+        };
+        assert_eq!(format(-42, 1), "-42");
+        assert_eq!(format(-1, 0), "18446744073709551615");
+        assert_eq!(format(7, 0), "7");
+    }
+
+    #[test]
+    fn orders_strings_by_code_point() {
+        let cmp = |lhs: &str, rhs: &str| {
+            // SAFETY: Both strings are live for the duration of the call.
+            unsafe { __runeway_str_cmp(lhs.as_ptr(), lhs.len(), rhs.as_ptr(), rhs.len()) }
+        };
+        assert_eq!(cmp("apple", "apple"), 0);
+        assert_eq!(cmp("apple", "banana"), -1);
+        assert_eq!(cmp("ab", "a"), 1);
+        assert_eq!(cmp("Z", "a"), -1);
+        assert_eq!(cmp("\u{e9}", "z"), 1);
+        assert_eq!(cmp("", ""), 0);
+    }
+
+    #[test]
+    fn reads_lines_without_terminators_until_the_end_of_input() {
+        let mut input = std::io::Cursor::new("first\r\nsecond\n\nlast");
+        assert_eq!(read_line(&mut input).as_deref(), Some("first"));
+        assert_eq!(read_line(&mut input).as_deref(), Some("second"));
+        assert_eq!(read_line(&mut input).as_deref(), Some(""));
+        assert_eq!(read_line(&mut input).as_deref(), Some("last"));
+        assert_eq!(read_line(&mut input), None);
+    }
+
+    #[test]
+    fn formats_panics_with_their_source_location() {
+        assert_eq!(
+            panic_message(b"bad state", b"src/main.rnw", 3, 14),
+            "panicked at src/main.rnw:3:14: bad state"
+        );
+    }
+
+    #[test]
+    fn formats_uncaught_errors_with_the_location_they_escaped_through() {
+        assert_eq!(
+            uncaught_message(b"not found", b"src/main.rnw", 7, 5),
+            "uncaught error at src/main.rnw:7:5: not found"
+        );
+    }
 }
@@ -0,0 +1,506 @@
+//! Hash maps and sets backing `std::collections::{HashMap, HashSet}`.
+//!
+//! A map is a managed payload holding a [`RawMap`]. As with vectors, the
+//! runtime never interprets keys or values: generated code passes pointers
+//! to them and reads results through the slots returned here. Keys are
+//! hashed and compared by their bytes, except for strings, whose contents
+//! are used instead, and struct and enum records, which generated code
+//! hashes and compares field by field. Sets are maps with zero-sized values.
+//!
+//! Entries are stored densely in insertion order, which [`__runeway_map_keys`]
+//! and [`__runeway_map_values`] follow. Removing an entry moves the last one
+//! into its place.
+//!
+//! Like vectors, maps hold a reference to the string and record keys and
+//! managed values stored in them. Generated code hands one over with every
+//! key and value it inserts; a key that is already present is released, and replaced or
+//! removed values are handed back to the caller. Maps of managed values
+//! report them to the cycle collector.
+
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hasher};
+use std::ptr;
+
+use runec_abi::{ELEM_MANAGED, ELEM_PLAIN, MAP_KEY_BYTES, MAP_KEY_STR};
+
+use crate::heap::{__runeway_alloc, __runeway_set_drop, __runeway_set_trace, VisitFn};
+use crate::vec::{__runeway_vec_new, __runeway_vec_push, RawVec, release_elem, retain_elem};
+
+/// Hashes the struct or enum record a key points to.
+pub type RecordHashFn = unsafe extern "C" fn(*const u8) -> u64;
+/// Returns `1` when two records are equal and `0` otherwise.
+pub type RecordEqFn = unsafe extern "C" fn(*const u8, *const u8) -> u8;
+
+#[derive(Debug, Copy, Clone)]
+enum KeyKind {
+    Bytes,
+    /// A string pointer followed by its length.
+    Str,
+    /// A pointer to a record, hashed and compared by the functions generated
+    /// for its type.
+    Record {
+        hash: RecordHashFn,
+        eq: RecordEqFn,
+    },
+}
+
+/// Payload of a map.
+///
+/// Generated code reads `len` directly, so it must stay the first field.
+#[repr(C)]
+pub struct RawMap {
+    len: usize,
+    key_kind: KeyKind,
+    keys: RawVec,
+    values: RawVec,
+    /// Hash of every key, by entry index.
+    hashes: Vec<u64>,
+    /// Indices of the entries with each hash.
+    buckets: HashMap<u64, Vec<usize>>,
+    /// Holds the value replaced or removed by the last insert or remove,
+    /// whose reference belongs to the caller.
+    scratch: RawVec,
+}
+
+impl RawMap {
+    /// Returns the bytes that identify the key stored at `key`.
+    ///
+    /// # Safety
+    ///
+    /// `key` must point to a key of this map's key type.
+    unsafe fn key_bytes<'a>(&self, key: *const u8) -> &'a [u8] {
+        match self.key_kind {
+            // SAFETY: Keys are `elem_size` readable bytes.
+            KeyKind::Bytes | KeyKind::Record { .. } => unsafe {
+                std::slice::from_raw_parts(key, self.keys.elem_size())
+            },
+            KeyKind::Str => {
+                // SAFETY: String keys are a pointer followed by a length.
+                let (ptr, len) =
+                    unsafe { (key.cast::<*const u8>().read(), key.cast::<usize>().add(1).read()) };
+                if len == 0 {
+                    return &[];
+                }
+                // SAFETY: Strings point to `len` readable bytes.
+                unsafe { std::slice::from_raw_parts(ptr, len) }
+            }
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `key` must point to a key of this map's key type.
+    unsafe fn hash(&self, key: *const u8) -> u64 {
+        if let KeyKind::Record { hash, .. } = self.key_kind {
+            // SAFETY: Record keys point to records of the type `hash` was
+            // generated for.
+            return unsafe { hash(key.cast::<*const u8>().read()) };
+        }
+        let mut hasher = DefaultHasher::new();
+        // SAFETY: Forwarded from the caller.
+        hasher.write(unsafe { self.key_bytes(key) });
+        hasher.finish()
+    }
+
+    /// Returns the index of the entry whose key equals the one at `key`.
+    ///
+    /// # Safety
+    ///
+    /// `key` must point to a key of this map's key type.
+    unsafe fn find(&self, key: *const u8, hash: u64) -> Option<usize> {
+        if let KeyKind::Record { eq, .. } = self.key_kind {
+            // SAFETY: Record keys point to records of the type `eq` was
+            // generated for.
+            let wanted = unsafe { key.cast::<*const u8>().read() };
+            return self.buckets.get(&hash)?.iter().copied().find(|index| {
+                // SAFETY: Stored keys have the key type.
+                unsafe { eq(self.keys.slot(*index).cast::<*const u8>().read(), wanted) != 0 }
+            });
+        }
+        // SAFETY: Forwarded from the caller.
+        let wanted = unsafe { self.key_bytes(key) };
+        self.buckets.get(&hash)?.iter().copied().find(|index| {
+            // SAFETY: Stored keys have the key type.
+            unsafe { self.key_bytes(self.keys.slot(*index)) == wanted }
+        })
+    }
+
+    /// Copies the value of entry `index` into the scratch slot and returns it.
+    fn save_value(&mut self, index: usize) -> *mut u8 {
+        if self.scratch.len() == 0 {
+            self.scratch.push();
+        }
+        let scratch = self.scratch.slot(0);
+        // SAFETY: Both slots hold one value and belong to distinct buffers.
+        unsafe {
+            ptr::copy_nonoverlapping(self.values.slot(index), scratch, self.values.elem_size())
+        };
+        scratch
+    }
+
+    /// Removes entry `index`, moving the last entry into its place.
+    fn swap_remove(&mut self, index: usize) {
+        let hash = self.hashes[index];
+        if let Some(bucket) = self.buckets.get_mut(&hash) {
+            bucket.retain(|entry| *entry != index);
+            if bucket.is_empty() {
+                self.buckets.remove(&hash);
+            }
+        }
+
+        let last = self.len - 1;
+        if index != last {
+            for slots in [&self.keys, &self.values] {
+                // SAFETY: Both slots are in bounds and distinct.
+                unsafe {
+                    ptr::copy_nonoverlapping(slots.slot(last), slots.slot(index), slots.elem_size())
+                };
+            }
+            let moved = self.hashes[last];
+            self.hashes[index] = moved;
+            if let Some(entry) = self
+                .buckets
+                .get_mut(&moved)
+                .and_then(|bucket| bucket.iter_mut().find(|entry| **entry == last))
+            {
+                *entry = index;
+            }
+        }
+        self.keys.pop();
+        self.values.pop();
+        self.hashes.pop();
+        self.len = last;
+    }
+}
+
+unsafe extern "C" fn drop_map(payload: *mut u8) {
+    // SAFETY: Registered only for payloads created by `new_map`.
+    unsafe { ptr::drop_in_place(payload.cast::<RawMap>()) };
+}
+
+unsafe extern "C" fn trace_map(payload: *mut u8, visit: VisitFn) {
+    // SAFETY: Registered only for payloads created by `new_map`.
+    // Keys are strings or records of them, which cannot reach the map, and
+    // the reference to the scratch value belongs to the caller.
+    unsafe { (*payload.cast::<RawMap>()).values.trace(visit) };
+}
+
+/// Copies the slots of `from` into a new vector, which takes its own
+/// reference to managed elements.
+fn collect(from: &RawVec) -> *mut u8 {
+    let kind = if from.is_managed() { ELEM_MANAGED } else { ELEM_PLAIN };
+    let vec = __runeway_vec_new(from.elem_size(), from.elem_align(), kind);
+    for index in 0..from.len() {
+        // SAFETY: `vec` was just created, and each new slot is distinct
+        // from the slot it is filled from.
+        unsafe {
+            let slot = __runeway_vec_push(vec);
+            ptr::copy_nonoverlapping(from.slot(index), slot, from.elem_size());
+            if from.is_managed() {
+                retain_elem(slot);
+            }
+        }
+    }
+    vec
+}
+
+#[unsafe(no_mangle)]
+/// Creates an empty map. `key_kind` is [`MAP_KEY_BYTES`] or [`MAP_KEY_STR`],
+/// and `value_kind` is [`ELEM_PLAIN`] or [`ELEM_MANAGED`].
+pub extern "C" fn __runeway_map_new(
+    key_size: usize,
+    key_align: usize,
+    key_kind: usize,
+    value_size: usize,
+    value_align: usize,
+    value_kind: usize,
+) -> *mut u8 {
+    let key_kind = match key_kind {
+        MAP_KEY_BYTES => KeyKind::Bytes,
+        MAP_KEY_STR => KeyKind::Str,
+        _ => std::process::abort(),
+    };
+    new_map((key_kind, key_size, key_align), value_size, value_align, value_kind)
+}
+
+#[unsafe(no_mangle)]
+/// Creates an empty map whose keys are pointers to struct or enum records,
+/// hashed with `key_hash` and compared with `key_eq`. `value_kind` is
+/// [`ELEM_PLAIN`] or [`ELEM_MANAGED`].
+pub extern "C" fn __runeway_map_new_records(
+    key_hash: RecordHashFn,
+    key_eq: RecordEqFn,
+    value_size: usize,
+    value_align: usize,
+    value_kind: usize,
+) -> *mut u8 {
+    let key_kind = KeyKind::Record { hash: key_hash, eq: key_eq };
+    let pointer = (key_kind, size_of::<*const u8>(), align_of::<*const u8>());
+    new_map(pointer, value_size, value_align, value_kind)
+}
+
+fn new_map(
+    (key_kind, key_size, key_align): (KeyKind, usize, usize),
+    value_size: usize,
+    value_align: usize,
+    value_kind: usize,
+) -> *mut u8 {
+    let managed_values = match value_kind {
+        ELEM_PLAIN => false,
+        ELEM_MANAGED => true,
+        _ => std::process::abort(),
+    };
+    let map = RawMap {
+        len: 0,
+        key_kind,
+        keys: RawVec::new(key_size, key_align, !matches!(key_kind, KeyKind::Bytes)),
+        values: RawVec::new(value_size, value_align, managed_values),
+        hashes: Vec::new(),
+        buckets: HashMap::new(),
+        scratch: RawVec::new(value_size, value_align, false),
+    };
+    let payload = __runeway_alloc(size_of::<RawMap>(), align_of::<RawMap>());
+    // SAFETY: The payload was just allocated with the size and alignment of
+    // `RawMap`.
+    unsafe {
+        payload.cast::<RawMap>().write(map);
+        __runeway_set_drop(payload, drop_map);
+        if managed_values {
+            __runeway_set_trace(payload, trace_map);
+        }
+    }
+    payload
+}
+
+#[unsafe(no_mangle)]
+/// Inserts a copy of the key and value, taking over the references they
+/// hold. Returns a slot holding the value it replaced, readable until the
+/// next insert or remove, or null if the key was not in the map yet.
+///
+/// # Safety
+///
+/// `map` must be a live map, and `key` and `value` must point to a key and
+/// a value of its types.
+pub unsafe extern "C" fn __runeway_map_insert(
+    map: *mut u8,
+    key: *const u8,
+    value: *const u8,
+) -> *mut u8 {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let map = unsafe { &mut *map.cast::<RawMap>() };
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let hash = unsafe { map.hash(key) };
+    let value_size = map.values.elem_size();
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    if let Some(index) = unsafe { map.find(key, hash) } {
+        if map.keys.is_managed() {
+            // SAFETY: The stored key is kept, so the reference handed over
+            // with the new one is not needed.
+            unsafe { release_elem(key) };
+        }
+        let previous = map.save_value(index);
+        // SAFETY: `value` points to a value, which cannot overlap the map.
+        unsafe { ptr::copy_nonoverlapping(value, map.values.slot(index), value_size) };
+        return previous;
+    }
+
+    let index = map.len;
+    // SAFETY: `key` and `value` point to a key and a value, which cannot
+    // overlap the new slots.
+    unsafe {
+        ptr::copy_nonoverlapping(key, map.keys.push(), map.keys.elem_size());
+        ptr::copy_nonoverlapping(value, map.values.push(), value_size);
+    }
+    map.hashes.push(hash);
+    map.buckets.entry(hash).or_default().push(index);
+    map.len += 1;
+    ptr::null_mut()
+}
+
+#[unsafe(no_mangle)]
+/// Returns the slot of the value stored for the key, or null if there is
+/// none. The slot is valid until the map is next modified.
+///
+/// # Safety
+///
+/// `map` must be a live map, and `key` must point to a key of its key type.
+pub unsafe extern "C" fn __runeway_map_get(map: *mut u8, key: *const u8) -> *mut u8 {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let map = unsafe { &*map.cast::<RawMap>() };
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let index = unsafe { map.find(key, map.hash(key)) };
+    index.map_or(ptr::null_mut(), |index| map.values.slot(index))
+}
+
+#[unsafe(no_mangle)]
+/// Removes the key. Returns a slot holding its value, readable until the
+/// next insert or remove, or null if the key was not in the map.
+///
+/// # Safety
+///
+/// `map` must be a live map, and `key` must point to a key of its key type.
+pub unsafe extern "C" fn __runeway_map_remove(map: *mut u8, key: *const u8) -> *mut u8 {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let map = unsafe { &mut *map.cast::<RawMap>() };
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    let Some(index) = (unsafe { map.find(key, map.hash(key)) }) else {
+        return ptr::null_mut();
+    };
+    if map.keys.is_managed() {
+        // SAFETY: The stored key holds a reference and is removed below.
+        unsafe { release_elem(map.keys.slot(index)) };
+    }
+    let removed = map.save_value(index);
+    map.swap_remove(index);
+    removed
+}
+
+#[unsafe(no_mangle)]
+/// Returns a new vector holding a copy of every key.
+///
+/// # Safety
+///
+/// `map` must be a live map.
+pub unsafe extern "C" fn __runeway_map_keys(map: *mut u8) -> *mut u8 {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    collect(&unsafe { &*map.cast::<RawMap>() }.keys)
+}
+
+#[unsafe(no_mangle)]
+/// Returns a new vector holding a copy of every value, in the same order as
+/// [`__runeway_map_keys`].
+///
+/// # Safety
+///
+/// `map` must be a live map.
+pub unsafe extern "C" fn __runeway_map_values(map: *mut u8) -> *mut u8 {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    collect(&unsafe { &*map.cast::<RawMap>() }.values)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+
+    use runec_abi::{ELEM_PLAIN, MAP_KEY_BYTES, MAP_KEY_STR};
+
+    use super::{
+        __runeway_map_get, __runeway_map_insert, __runeway_map_keys, __runeway_map_new,
+        __runeway_map_new_records, __runeway_map_remove, RawMap,
+    };
+    use crate::RawStr;
+    use crate::heap::{__runeway_alloc, __runeway_release, heap_stats};
+
+    fn len(map: *mut u8) -> usize {
+        unsafe { (*map.cast::<RawMap>()).len }
+    }
+
+    #[test]
+    fn inserts_replaces_and_removes_byte_keys() {
+        let before = heap_stats();
+        let map = __runeway_map_new(4, 4, MAP_KEY_BYTES, 8, 8, ELEM_PLAIN);
+        for key in 0..20u32 {
+            let value = u64::from(key) * 10;
+            let previous = unsafe {
+                __runeway_map_insert(map, ptr::from_ref(&key).cast(), ptr::from_ref(&value).cast())
+            };
+            assert!(previous.is_null());
+        }
+        assert_eq!(len(map), 20);
+
+        let (key, value) = (7u32, 700u64);
+        let previous = unsafe {
+            __runeway_map_insert(map, ptr::from_ref(&key).cast(), ptr::from_ref(&value).cast())
+        };
+        assert_eq!(unsafe { previous.cast::<u64>().read() }, 70);
+        let slot = unsafe { __runeway_map_get(map, ptr::from_ref(&key).cast()) };
+        assert_eq!(unsafe { slot.cast::<u64>().read() }, 700);
+
+        let removed = unsafe { __runeway_map_remove(map, ptr::from_ref(&3u32).cast()) };
+        assert_eq!(unsafe { removed.cast::<u64>().read() }, 30);
+        assert!(unsafe { __runeway_map_get(map, ptr::from_ref(&3u32).cast()) }.is_null());
+        assert!(unsafe { __runeway_map_remove(map, ptr::from_ref(&3u32).cast()) }.is_null());
+        assert_eq!(len(map), 19);
+        // The last entry moved into the gap and can still be found.
+        let slot = unsafe { __runeway_map_get(map, ptr::from_ref(&19u32).cast()) };
+        assert_eq!(unsafe { slot.cast::<u64>().read() }, 190);
+
+        unsafe { __runeway_release(map) };
+        assert_eq!(heap_stats().live_allocations(), before.live_allocations());
+    }
+
+    #[test]
+    fn compares_string_keys_by_contents_and_releases_them() {
+        let before = heap_stats();
+        let set = __runeway_map_new(16, 8, MAP_KEY_STR, 0, 1, ELEM_PLAIN);
+        let first = RawStr::alloc("key");
+        let second = RawStr::alloc("key");
+        let (first, second) = (ptr::from_ref(&first).cast(), ptr::from_ref(&second).cast());
+
+        assert!(unsafe { __runeway_map_insert(set, first, first) }.is_null());
+        assert!(!unsafe { __runeway_map_insert(set, second, second) }.is_null());
+        assert_eq!(len(set), 1);
+        assert_eq!(heap_stats().live_allocations(), before.live_allocations() + 2);
+
+        let keys = unsafe { __runeway_map_keys(set) };
+        assert_eq!(unsafe { keys.cast::<[usize; 2]>().read() }[1], 1);
+        unsafe {
+            __runeway_release(keys);
+            __runeway_release(set);
+        }
+        assert_eq!(heap_stats().live_allocations(), before.live_allocations());
+    }
+
+    /// Records of one `u32` field that hash to their parity, so distinct
+    /// keys share buckets and are told apart by `eq_records`.
+    unsafe extern "C" fn hash_record(record: *const u8) -> u64 {
+        u64::from(unsafe { record.cast::<u32>().read() } % 2)
+    }
+
+    unsafe extern "C" fn eq_records(lhs: *const u8, rhs: *const u8) -> u8 {
+        u8::from(unsafe { lhs.cast::<u32>().read() == rhs.cast::<u32>().read() })
+    }
+
+    #[test]
+    fn compares_record_keys_with_their_functions_and_releases_them() {
+        let before = heap_stats();
+        let map = __runeway_map_new_records(hash_record, eq_records, 8, 8, ELEM_PLAIN);
+        let record = |field: u32| {
+            let record = __runeway_alloc(4, 4);
+            unsafe { record.cast::<u32>().write(field) };
+            record
+        };
+        for field in 0..4u32 {
+            let key = record(field);
+            let value = u64::from(field) * 10;
+            let previous = unsafe {
+                __runeway_map_insert(map, ptr::from_ref(&key).cast(), ptr::from_ref(&value).cast())
+            };
+            assert!(previous.is_null());
+        }
+        assert_eq!(len(map), 4);
+
+        // An equal record at another address finds the stored entry, and
+        // the map releases it since it keeps the key it has.
+        let key = record(2);
+        let value = 200u64;
+        let previous = unsafe {
+            __runeway_map_insert(map, ptr::from_ref(&key).cast(), ptr::from_ref(&value).cast())
+        };
+        assert_eq!(unsafe { previous.cast::<u64>().read() }, 20);
+        assert_eq!(len(map), 4);
+        assert_eq!(heap_stats().live_allocations(), before.live_allocations() + 5);
+
+        let key = record(3);
+        let slot = unsafe { __runeway_map_get(map, ptr::from_ref(&key).cast()) };
+        assert_eq!(unsafe { slot.cast::<u64>().read() }, 30);
+        let removed = unsafe { __runeway_map_remove(map, ptr::from_ref(&key).cast()) };
+        assert_eq!(unsafe { removed.cast::<u64>().read() }, 30);
+        assert!(unsafe { __runeway_map_get(map, ptr::from_ref(&key).cast()) }.is_null());
+        unsafe {
+            __runeway_release(key);
+            __runeway_release(map);
+        }
+        assert_eq!(heap_stats().live_allocations(), before.live_allocations());
+    }
+}
@@ -0,0 +1,99 @@
+//! Float functions of `std::math` with no single Cranelift instruction.
+//! Simpler ones such as `sqrt` and `floor` are expanded inline instead.
+
+#[unsafe(no_mangle)]
+/// `base` raised to the power `exp`.
+pub extern "C" fn __runeway_pow(base: f64, exp: f64) -> f64 {
+    base.powf(exp)
+}
+
+#[unsafe(no_mangle)]
+/// `x` rounded to the nearest integer, with halves rounded away from zero.
+pub extern "C" fn __runeway_round(x: f64) -> f64 {
+    x.round()
+}
+
+#[unsafe(no_mangle)]
+/// Sine of `x`, in radians.
+pub extern "C" fn __runeway_sin(x: f64) -> f64 {
+    x.sin()
+}
+
+#[unsafe(no_mangle)]
+/// Cosine of `x`, in radians.
+pub extern "C" fn __runeway_cos(x: f64) -> f64 {
+    x.cos()
+}
+
+#[unsafe(no_mangle)]
+/// Tangent of `x`, in radians.
+pub extern "C" fn __runeway_tan(x: f64) -> f64 {
+    x.tan()
+}
+
+#[unsafe(no_mangle)]
+/// Arcsine of `x`, in radians.
+pub extern "C" fn __runeway_asin(x: f64) -> f64 {
+    x.asin()
+}
+
+#[unsafe(no_mangle)]
+/// Arccosine of `x`, in radians.
+pub extern "C" fn __runeway_acos(x: f64) -> f64 {
+    x.acos()
+}
+
+#[unsafe(no_mangle)]
+/// Arctangent of `x`, in radians.
+pub extern "C" fn __runeway_atan(x: f64) -> f64 {
+    x.atan()
+}
+
+#[unsafe(no_mangle)]
+/// Four-quadrant arctangent of `y / x`, in radians.
+pub extern "C" fn __runeway_atan2(y: f64, x: f64) -> f64 {
+    y.atan2(x)
+}
+
+#[unsafe(no_mangle)]
+/// `e` raised to the power `x`.
+pub extern "C" fn __runeway_exp(x: f64) -> f64 {
+    x.exp()
+}
+
+#[unsafe(no_mangle)]
+/// Natural logarithm of `x`.
+pub extern "C" fn __runeway_ln(x: f64) -> f64 {
+    x.ln()
+}
+
+#[unsafe(no_mangle)]
+/// Base-2 logarithm of `x`.
+pub extern "C" fn __runeway_log2(x: f64) -> f64 {
+    x.log2()
+}
+
+#[unsafe(no_mangle)]
+/// Base-10 logarithm of `x`.
+pub extern "C" fn __runeway_log10(x: f64) -> f64 {
+    x.log10()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{__runeway_atan2, __runeway_ln, __runeway_pow, __runeway_round};
+
+    #[test]
+    fn rounds_halves_away_from_zero() {
+        assert_eq!(__runeway_round(2.5), 3.0);
+        assert_eq!(__runeway_round(-2.5), -3.0);
+        assert_eq!(__runeway_round(2.4), 2.0);
+    }
+
+    #[test]
+    fn forwards_to_the_float_functions() {
+        assert_eq!(__runeway_pow(2.0, 10.0), 1024.0);
+        assert_eq!(__runeway_ln(1.0), 0.0);
+        assert_eq!(__runeway_atan2(1.0, 1.0), std::f64::consts::FRAC_PI_4);
+    }
+}
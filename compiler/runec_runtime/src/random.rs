@@ -0,0 +1,190 @@
+//! Pseudo-random numbers behind `std::random`.
+//!
+//! Each thread owns a xoshiro256** generator. It is seeded from
+//! [`__runeway_random_os_seed`] on first use unless the program calls
+//! [`__runeway_random_seed`] first, so fixing the seed makes every later
+//! draw reproducible.
+
+use std::cell::Cell;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::ptr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::RawStr;
+use crate::vec::hand_out_bounds_message;
+
+thread_local! {
+    static STATE: Cell<Option<[u64; 4]>> = const { Cell::new(None) };
+}
+
+/// Expands `seed` into a full generator state with SplitMix64, which never
+/// yields the all-zero state xoshiro cannot leave.
+fn state_from_seed(mut seed: u64) -> [u64; 4] {
+    [(); 4].map(|()| {
+        seed = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = seed;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    })
+}
+
+fn next(state: &mut [u64; 4]) -> u64 {
+    let result = state[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+    let t = state[1] << 17;
+    state[2] ^= state[0];
+    state[3] ^= state[1];
+    state[1] ^= state[2];
+    state[0] ^= state[3];
+    state[2] ^= t;
+    state[3] = state[3].rotate_left(45);
+    result
+}
+
+/// Uniform value below `bound`, which must not be zero, by Lemire's
+/// multiply-and-reject method.
+fn below(bound: u64) -> u64 {
+    let threshold = bound.wrapping_neg() % bound;
+    loop {
+        let product = u128::from(__runeway_random_next()) * u128::from(bound);
+        if product as u64 >= threshold {
+            return (product >> 64) as u64;
+        }
+    }
+}
+
+#[unsafe(no_mangle)]
+/// Restarts the generator of the current thread from `seed`.
+pub extern "C" fn __runeway_random_seed(seed: u64) {
+    STATE.set(Some(state_from_seed(seed)));
+}
+
+#[unsafe(no_mangle)]
+/// Seed drawn from operating-system entropy, through the randomly keyed
+/// hasher of the standard library.
+pub extern "C" fn __runeway_random_os_seed() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    hasher.write_u128(now.as_nanos());
+    hasher.finish()
+}
+
+#[unsafe(no_mangle)]
+/// Next 64 random bits.
+pub extern "C" fn __runeway_random_next() -> u64 {
+    let mut state = STATE.get().unwrap_or_else(|| state_from_seed(__runeway_random_os_seed()));
+    let value = next(&mut state);
+    STATE.set(Some(state));
+    value
+}
+
+#[unsafe(no_mangle)]
+/// Uniform integer in `lo..hi`. Generated code checks that the range is
+/// not empty first, and panics through [`__runeway_random_empty_range`]
+/// otherwise; an empty range still yields `lo` here.
+pub extern "C" fn __runeway_random_int_range(lo: i64, hi: i64) -> i64 {
+    if hi <= lo {
+        return lo;
+    }
+    lo.wrapping_add(below(hi.wrapping_sub(lo) as u64) as i64)
+}
+
+#[unsafe(no_mangle)]
+/// Formats the panic message of `int_range` called with an empty range.
+pub extern "C" fn __runeway_random_empty_range(lo: i64, hi: i64) -> *const RawStr {
+    hand_out_bounds_message(format!("cannot draw from the empty range {lo}..{hi}"))
+}
+
+#[unsafe(no_mangle)]
+/// Uniform float in `0.0..1.0`.
+pub extern "C" fn __runeway_random_float() -> f64 {
+    (__runeway_random_next() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[unsafe(no_mangle)]
+/// Uniform float in `lo..hi`.
+pub extern "C" fn __runeway_random_float_range(lo: f64, hi: f64) -> f64 {
+    lo + (hi - lo) * __runeway_random_float()
+}
+
+#[unsafe(no_mangle)]
+/// Shuffles the `len` elements of `elem_size` bytes at `elems` in place
+/// with Fisher-Yates. Compiled code passes the buffer of a vector, or the
+/// part of it a slice views.
+///
+/// # Safety
+///
+/// `elems` must point to `len` initialized elements of `elem_size` bytes
+/// that nothing else accesses during the call.
+pub unsafe extern "C" fn __runeway_random_shuffle(elems: *mut u8, len: usize, elem_size: usize) {
+    for index in (1..len).rev() {
+        let other = below(index as u64 + 1) as usize;
+        if other != index {
+            // SAFETY: Both slots are in bounds and distinct, so the
+            // element-sized ranges do not overlap.
+            unsafe {
+                ptr::swap_nonoverlapping(
+                    elems.add(index * elem_size),
+                    elems.add(other * elem_size),
+                    elem_size,
+                )
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        __runeway_random_empty_range, __runeway_random_float, __runeway_random_float_range,
+        __runeway_random_int_range, __runeway_random_next, __runeway_random_seed,
+        __runeway_random_shuffle,
+    };
+
+    #[test]
+    fn fixed_seeds_repeat_the_same_draws() {
+        let draws = || {
+            __runeway_random_seed(42);
+            [(); 8].map(|()| __runeway_random_next())
+        };
+        assert_eq!(draws(), draws());
+        __runeway_random_seed(43);
+        assert_ne!(__runeway_random_next(), draws()[0]);
+    }
+
+    #[test]
+    fn ranges_stay_within_their_bounds() {
+        __runeway_random_seed(7);
+        for _ in 0..1000 {
+            assert!((-3..4).contains(&__runeway_random_int_range(-3, 4)));
+            assert!((0.0..1.0).contains(&__runeway_random_float()));
+            assert!((2.0..2.5).contains(&__runeway_random_float_range(2.0, 2.5)));
+        }
+        assert_eq!(__runeway_random_int_range(5, 5), 5);
+        assert_eq!(__runeway_random_int_range(i64::MIN, i64::MIN + 1), i64::MIN);
+    }
+
+    #[test]
+    fn formats_empty_range_messages_for_the_panic_handler() {
+        // SAFETY: The message stays alive until the next bounds failure.
+        let raw = unsafe { __runeway_random_empty_range(5, 2).read() };
+        let text = unsafe { std::slice::from_raw_parts(raw.ptr, raw.len) };
+        assert_eq!(text, b"cannot draw from the empty range 5..2");
+    }
+
+    #[test]
+    fn shuffling_permutes_only_the_given_elements() {
+        let mut values: Vec<u32> = (0..60).collect();
+        __runeway_random_seed(1);
+        // SAFETY: Elements 5..55 are initialized `u32`s of `values`.
+        unsafe { __runeway_random_shuffle(values[5..].as_mut_ptr().cast(), 50, size_of::<u32>()) };
+
+        assert_eq!(values[..5], [0, 1, 2, 3, 4]);
+        assert_eq!(values[55..], [55, 56, 57, 58, 59]);
+        let mut shuffled = values[5..55].to_vec();
+        assert_ne!(shuffled, (5..55).collect::<Vec<_>>());
+        shuffled.sort_unstable();
+        assert_eq!(shuffled, (5..55).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,58 @@
+//! Clocks behind `std::time`. Instants and durations cross the ABI as `u64`
+//! nanosecond counts; instants count from the first time the program reads
+//! the monotonic clock.
+
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+static EPOCH: OnceLock<Instant> = OnceLock::new();
+
+fn nanos(duration: Duration) -> u64 {
+    u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)
+}
+
+#[unsafe(no_mangle)]
+/// Current reading of the monotonic clock.
+pub extern "C" fn __runeway_instant_now() -> u64 {
+    nanos(EPOCH.get_or_init(Instant::now).elapsed())
+}
+
+#[unsafe(no_mangle)]
+/// Time passed since `instant`, or zero for an instant in the future.
+pub extern "C" fn __runeway_instant_elapsed(instant: u64) -> u64 {
+    __runeway_instant_now().saturating_sub(instant)
+}
+
+#[unsafe(no_mangle)]
+/// Blocks the current thread for at least `duration` nanoseconds.
+pub extern "C" fn __runeway_sleep(duration: u64) {
+    thread::sleep(Duration::from_nanos(duration));
+}
+
+#[unsafe(no_mangle)]
+/// Wall-clock time since the Unix epoch, or zero if the clock is set before
+/// it.
+pub extern "C" fn __runeway_unix_time() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, nanos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        __runeway_instant_elapsed, __runeway_instant_now, __runeway_sleep, __runeway_unix_time,
+    };
+
+    #[test]
+    fn sleeping_advances_the_monotonic_clock() {
+        let start = __runeway_instant_now();
+        __runeway_sleep(2_000_000);
+        assert!(__runeway_instant_elapsed(start) >= 2_000_000);
+        assert_eq!(__runeway_instant_elapsed(u64::MAX), 0);
+    }
+
+    #[test]
+    fn unix_time_is_after_2020() {
+        assert!(__runeway_unix_time() > 1_577_836_800 * 1_000_000_000);
+    }
+}
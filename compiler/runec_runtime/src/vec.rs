@@ -0,0 +1,353 @@
+//! Growable vectors backing `core::vec::Vec<T>`.
+//!
+//! A vector is a managed payload holding a [`RawVec`]. The element buffer is
+//! a separate allocation owned by the payload and freed by its drop function.
+//! The runtime never looks at element values: generated code receives a
+//! pointer to an element slot and reads or writes the value itself, so only
+//! the element size and alignment are known here, along with whether each
+//! element starts with a managed pointer. The vector holds a reference to
+//! such elements, as generated code takes one for every element it writes,
+//! and releases the ones still stored when it is dropped. Vectors of managed
+//! elements report them to the cycle collector.
+//!
+//! Buffers are not tracked by [`heap_stats`](crate::heap_stats); only the
+//! vector payload is.
+
+use std::alloc::{self, Layout};
+use std::cell::{Cell, RefCell};
+use std::ptr;
+
+use runec_abi::{ELEM_MANAGED, ELEM_PLAIN};
+
+use crate::RawStr;
+use crate::heap::{
+    __runeway_alloc, __runeway_release, __runeway_retain, __runeway_set_drop, __runeway_set_trace,
+    VisitFn,
+};
+
+/// Payload of a vector.
+///
+/// Generated code reads `ptr` and `len` directly, so they must stay the
+/// first two fields.
+#[repr(C)]
+pub struct RawVec {
+    ptr: *mut u8,
+    len: usize,
+    cap: usize,
+    elem_size: usize,
+    elem_align: usize,
+    /// Whether every element starts with a pointer to a managed payload.
+    managed: bool,
+}
+
+impl RawVec {
+    pub(crate) fn new(elem_size: usize, elem_align: usize, managed: bool) -> Self {
+        if !elem_align.is_power_of_two() || (managed && elem_size < size_of::<*mut u8>()) {
+            std::process::abort();
+        }
+        Self { ptr: ptr::null_mut(), len: 0, cap: 0, elem_size, elem_align, managed }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn elem_size(&self) -> usize {
+        self.elem_size
+    }
+
+    pub(crate) fn elem_align(&self) -> usize {
+        self.elem_align
+    }
+
+    pub(crate) fn is_managed(&self) -> bool {
+        self.managed
+    }
+
+    /// Returns the slot of the element at `index`, which must be in bounds.
+    pub(crate) fn slot(&self, index: usize) -> *mut u8 {
+        debug_assert!(index < self.len);
+        // SAFETY: In-bounds slots are inside the buffer; zero-sized elements
+        // all share the dangling pointer.
+        unsafe { self.ptr.add(index * self.elem_size) }
+    }
+
+    /// Appends an uninitialized element and returns its slot.
+    pub(crate) fn push(&mut self) -> *mut u8 {
+        if self.len == self.cap {
+            self.grow();
+        }
+        self.len += 1;
+        self.slot(self.len - 1)
+    }
+
+    /// Removes the last element and returns its slot, which stays readable
+    /// until the next push.
+    pub(crate) fn pop(&mut self) -> Option<*mut u8> {
+        if self.len == 0 {
+            return None;
+        }
+        let slot = self.slot(self.len - 1);
+        self.len -= 1;
+        Some(slot)
+    }
+
+    /// Calls `visit` with every stored managed element.
+    pub(crate) fn trace(&self, visit: VisitFn) {
+        if self.managed {
+            for index in 0..self.len {
+                // SAFETY: Managed elements start with a payload pointer.
+                unsafe { visit(self.slot(index).cast::<*mut u8>().read()) };
+            }
+        }
+    }
+
+    fn buffer_layout(&self, cap: usize) -> Layout {
+        let size = self.elem_size.checked_mul(cap).unwrap_or_else(|| std::process::abort());
+        Layout::from_size_align(size, self.elem_align).unwrap_or_else(|_| std::process::abort())
+    }
+
+    /// Makes room for at least one more element.
+    fn grow(&mut self) {
+        let cap = (self.cap * 2).max(4);
+        let new_layout = self.buffer_layout(cap);
+        if new_layout.size() == 0 {
+            // Zero-sized elements only need a well-aligned dangling pointer.
+            self.ptr = ptr::without_provenance_mut(self.elem_align);
+            self.cap = usize::MAX;
+            return;
+        }
+
+        let ptr = if self.cap == 0 {
+            // SAFETY: The layout has a non-zero size.
+            unsafe { alloc::alloc(new_layout) }
+        } else {
+            // SAFETY: `ptr` was allocated with the layout for the current
+            // capacity, and the new size is non-zero.
+            unsafe { alloc::realloc(self.ptr, self.buffer_layout(self.cap), new_layout.size()) }
+        };
+        if ptr.is_null() {
+            alloc::handle_alloc_error(new_layout);
+        }
+        self.ptr = ptr;
+        self.cap = cap;
+    }
+}
+
+/// Takes another reference to the managed value at the start of `slot`.
+///
+/// # Safety
+///
+/// `slot` must hold an element of a vector of managed elements.
+pub(crate) unsafe fn retain_elem(slot: *const u8) {
+    // SAFETY: Managed elements start with a payload pointer.
+    unsafe { __runeway_retain(slot.cast::<*mut u8>().read()) };
+}
+
+/// Releases the managed value at the start of `slot`.
+///
+/// # Safety
+///
+/// Same contract as [`retain_elem`]; the reference held through `slot` is
+/// given up.
+pub(crate) unsafe fn release_elem(slot: *const u8) {
+    // SAFETY: Managed elements start with a payload pointer.
+    unsafe { __runeway_release(slot.cast::<*mut u8>().read()) };
+}
+
+impl Drop for RawVec {
+    fn drop(&mut self) {
+        if self.managed {
+            for index in 0..self.len {
+                // SAFETY: Every stored element holds a reference.
+                unsafe { release_elem(self.slot(index)) };
+            }
+        }
+        if self.cap > 0 && self.elem_size > 0 {
+            // SAFETY: The buffer was allocated with the layout for `cap`.
+            unsafe { alloc::dealloc(self.ptr, self.buffer_layout(self.cap)) };
+        }
+    }
+}
+
+unsafe extern "C" fn drop_vec(payload: *mut u8) {
+    // SAFETY: Registered only for payloads created by `__runeway_vec_new`.
+    unsafe { ptr::drop_in_place(payload.cast::<RawVec>()) };
+}
+
+unsafe extern "C" fn trace_vec(payload: *mut u8, visit: VisitFn) {
+    // SAFETY: Registered only for payloads created by `__runeway_vec_new`.
+    unsafe { (*payload.cast::<RawVec>()).trace(visit) };
+}
+
+/// Returns a new vector holding a managed copy of each string.
+pub(crate) fn str_vec<S: AsRef<str>>(strs: impl IntoIterator<Item = S>) -> *mut u8 {
+    let vec = __runeway_vec_new(size_of::<RawStr>(), align_of::<RawStr>(), ELEM_MANAGED);
+    for text in strs {
+        // SAFETY: `vec` was just created with slots sized for a `RawStr`.
+        unsafe { __runeway_vec_push(vec).cast::<RawStr>().write(RawStr::alloc(text.as_ref())) };
+    }
+    vec
+}
+
+#[unsafe(no_mangle)]
+/// Creates an empty vector of elements with the given size and alignment.
+/// `elem_kind` is [`ELEM_PLAIN`] or [`ELEM_MANAGED`].
+pub extern "C" fn __runeway_vec_new(
+    elem_size: usize,
+    elem_align: usize,
+    elem_kind: usize,
+) -> *mut u8 {
+    let managed = match elem_kind {
+        ELEM_PLAIN => false,
+        ELEM_MANAGED => true,
+        _ => std::process::abort(),
+    };
+    let vec = RawVec::new(elem_size, elem_align, managed);
+    let payload = __runeway_alloc(size_of::<RawVec>(), align_of::<RawVec>());
+    // SAFETY: The payload was just allocated with the size and alignment of
+    // `RawVec`.
+    unsafe {
+        payload.cast::<RawVec>().write(vec);
+        __runeway_set_drop(payload, drop_vec);
+        if managed {
+            __runeway_set_trace(payload, trace_vec);
+        }
+    }
+    payload
+}
+
+#[unsafe(no_mangle)]
+/// Appends an uninitialized element and returns a pointer to it. The caller
+/// must write the element before the vector is read again, handing over a
+/// reference to managed elements.
+///
+/// # Safety
+///
+/// `vec` must be a live vector created by [`__runeway_vec_new`].
+pub unsafe extern "C" fn __runeway_vec_push(vec: *mut u8) -> *mut u8 {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    unsafe { &mut *vec.cast::<RawVec>() }.push()
+}
+
+#[unsafe(no_mangle)]
+/// Removes the last element and returns a pointer to it, or null if the
+/// vector is empty. The element stays readable until the next push, and the
+/// caller takes over the reference to a managed element.
+///
+/// # Safety
+///
+/// `vec` must be a live vector created by [`__runeway_vec_new`].
+pub unsafe extern "C" fn __runeway_vec_pop(vec: *mut u8) -> *mut u8 {
+    // SAFETY: Forwarded directly from the runtime ABI contract.
+    unsafe { &mut *vec.cast::<RawVec>() }.pop().unwrap_or(ptr::null_mut())
+}
+
+thread_local! {
+    static BOUNDS_MESSAGE: RefCell<String> = const { RefCell::new(String::new()) };
+    static BOUNDS_SLOT: Cell<RawStr> = const { Cell::new(RawStr::EMPTY) };
+}
+
+/// Keeps `message` until the next bounds failure on the thread and returns
+/// the address of a string borrowing it, which generated code passes on to
+/// the runtime `panic` along with the source location.
+pub(crate) fn hand_out_bounds_message(message: String) -> *const RawStr {
+    BOUNDS_MESSAGE.with_borrow_mut(|stored| *stored = message);
+    let raw =
+        BOUNDS_MESSAGE.with_borrow(|stored| RawStr { ptr: stored.as_ptr(), len: stored.len() });
+    BOUNDS_SLOT.with(|slot| {
+        slot.set(raw);
+        slot.as_ptr().cast_const()
+    })
+}
+
+#[unsafe(no_mangle)]
+/// Formats the panic message of an out-of-bounds index.
+pub extern "C" fn __runeway_index_out_of_bounds(index: usize, len: usize) -> *const RawStr {
+    hand_out_bounds_message(format!(
+        "index out of bounds: the len is {len} but the index is {index}"
+    ))
+}
+
+#[unsafe(no_mangle)]
+/// Formats the panic message of an out-of-bounds slice range.
+pub extern "C" fn __runeway_slice_out_of_bounds(
+    start: usize,
+    end: usize,
+    len: usize,
+) -> *const RawStr {
+    hand_out_bounds_message(format!("range {start}..{end} out of bounds for length {len}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use runec_abi::ELEM_PLAIN;
+
+    use super::{
+        __runeway_index_out_of_bounds, __runeway_slice_out_of_bounds, __runeway_vec_new,
+        __runeway_vec_pop, __runeway_vec_push, RawVec, str_vec,
+    };
+    use crate::RawStr;
+    use crate::heap::{__runeway_release, heap_stats};
+
+    #[test]
+    fn pushes_and_pops_through_element_slots() {
+        let before = heap_stats();
+        let vec = __runeway_vec_new(4, 4, ELEM_PLAIN);
+        for value in 0..10u32 {
+            unsafe { __runeway_vec_push(vec).cast::<u32>().write(value * 3) };
+        }
+        let raw = unsafe { &*vec.cast::<RawVec>() };
+        assert_eq!(raw.len, 10);
+        assert_eq!(unsafe { raw.ptr.cast::<u32>().add(4).read() }, 12);
+
+        assert_eq!(unsafe { __runeway_vec_pop(vec).cast::<u32>().read() }, 27);
+        for _ in 0..9 {
+            assert!(!unsafe { __runeway_vec_pop(vec) }.is_null());
+        }
+        assert!(unsafe { __runeway_vec_pop(vec) }.is_null());
+
+        unsafe { __runeway_release(vec) };
+        assert_eq!(heap_stats().live_allocations(), before.live_allocations());
+    }
+
+    #[test]
+    fn zero_sized_elements_never_allocate_a_buffer() {
+        let vec = __runeway_vec_new(0, 1, ELEM_PLAIN);
+        for _ in 0..100 {
+            assert!(!unsafe { __runeway_vec_push(vec) }.is_null());
+        }
+        unsafe { __runeway_release(vec) };
+    }
+
+    #[test]
+    fn releases_the_managed_elements_it_still_holds() {
+        let before = heap_stats();
+        let vec = str_vec(["one", "two", "three"]);
+        let popped = unsafe { __runeway_vec_pop(vec).cast::<RawStr>().read() };
+        unsafe { __runeway_release(vec) };
+        assert_eq!(heap_stats().live_allocations(), before.live_allocations() + 1);
+
+        unsafe { __runeway_release(popped.ptr.cast_mut()) };
+        assert_eq!(heap_stats().live_allocations(), before.live_allocations());
+    }
+
+    #[test]
+    fn formats_bounds_messages_for_the_panic_handler() {
+        let text = |raw: *const RawStr| {
+            // SAFETY: The message stays alive until the next bounds failure.
+            let raw = unsafe { raw.read() };
+            unsafe { std::str::from_utf8(std::slice::from_raw_parts(raw.ptr, raw.len)) }
+                .unwrap()
+                .to_owned()
+        };
+        assert_eq!(
+            text(__runeway_index_out_of_bounds(5, 3)),
+            "index out of bounds: the len is 3 but the index is 5"
+        );
+        assert_eq!(
+            text(__runeway_slice_out_of_bounds(2, 7, 4)),
+            "range 2..7 out of bounds for length 4"
+        );
+    }
+}
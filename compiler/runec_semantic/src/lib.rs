@@ -1,3 +1,4 @@
+pub mod privacy;
 pub mod resolving;
 pub mod semantic;
 pub mod typeck;
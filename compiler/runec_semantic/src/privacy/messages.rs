@@ -0,0 +1,10 @@
+use runec_utils::define_messages;
+
+define_messages! {
+    PRIVATE_ITEM => "`{name}` is private to module `{module}`",
+    PRIVATE_FIELD => "field `{name}` of `{ty}` is private to module `{module}`",
+    DEFINED_HERE => "defined here",
+    UNREACHABLE_PUB => "`{name}` is `pub` but unreachable from the crate root",
+    NOT_IMPORTED => "module `{module}` is not imported by any reachable module",
+    PRIVATE_PARENT => "`{parent}` is not `pub`",
+}
@@ -0,0 +1,629 @@
+use std::collections::HashSet;
+
+use runec_ast::SpannedStr;
+use runec_ast::statement::Visibility;
+use runec_builtins::STD_MODULES;
+use runec_errors::diagnostics::Diagnostic;
+use runec_errors::labels::{DiagLabel, DiagNote};
+use runec_errors::message::DiagMessage;
+use runec_hir::expression::{HirExpr, SpannedHirExpr};
+use runec_hir::ids::{HirId, HirModuleId};
+use runec_hir::item::{HirGenericParamKind, HirItem, HirStruct, HirUnion, HirVariantPayload};
+use runec_hir::map::HirMap;
+use runec_hir::path::HirGenericArg;
+use runec_hir::resolution::Res;
+use runec_hir::statement::{HirBlock, HirStmt};
+use runec_hir::ty::{HirType, SpannedHirType};
+use runec_source::span::Span;
+
+use crate::typeck::{Ty, TypeInfo};
+
+pub const UNREACHABLE_PUB_LINT: &str = "unreachable_pub";
+
+pub struct PrivacyResult {
+    pub diags: Vec<Diagnostic<'static>>,
+}
+
+/// Rejects uses of private items and fields outside the module defining them
+/// and its submodules, and lints `pub` declarations that cannot be reached
+/// from the crate root. Runs on type-checked HIR, which gives the types of
+/// the values whose fields are read.
+pub struct PrivacyChecker<'hir, 'src> {
+    hir: &'hir HirMap<'src>,
+    info: &'hir TypeInfo<'src>,
+    /// Item being checked, and its module.
+    item: HirId,
+    module: HirModuleId,
+    diags: Vec<Diagnostic<'static>>,
+}
+
+impl<'hir, 'src> PrivacyChecker<'hir, 'src> {
+    pub fn new(hir: &'hir HirMap<'src>, info: &'hir TypeInfo<'src>) -> Self {
+        Self { hir, info, item: HirId::from_usize(0), module: HirModuleId::ROOT, diags: Vec::new() }
+    }
+
+    pub fn check(mut self) -> PrivacyResult {
+        for (id, item) in self.hir.iter() {
+            self.item = id;
+            self.module = self.hir.item_module(id);
+            match item {
+                HirItem::Function(function) => {
+                    for param in function.generics.iter() {
+                        if let HirGenericParamKind::Const { ty } = &param.kind {
+                            self.check_ty(ty);
+                        }
+                    }
+                    for param in function.params.iter() {
+                        self.check_ty(&param.ty);
+                    }
+                    self.check_ty(&function.ret_ty);
+                    if let Some(throws) = &function.throws {
+                        self.check_ty(throws);
+                    }
+                    self.check_block(&function.body);
+                }
+                HirItem::ExternFunction(function) => {
+                    for param in function.params.iter() {
+                        self.check_ty(&param.ty);
+                    }
+                    self.check_ty(&function.ret_ty);
+                }
+                HirItem::Struct(HirStruct { fields, .. })
+                | HirItem::Union(HirUnion { fields, .. }) => {
+                    for field in fields.iter() {
+                        self.check_ty(&field.ty);
+                    }
+                }
+                HirItem::Alias(alias) => self.check_ty(&alias.ty),
+                HirItem::Enum(enm) => {
+                    for variant in enm.variants.iter() {
+                        match &variant.payload {
+                            HirVariantPayload::Unit => {}
+                            HirVariantPayload::Tuple(items) => {
+                                for item in items.iter() {
+                                    self.check_ty(item);
+                                }
+                            }
+                            HirVariantPayload::Struct(fields) => {
+                                for field in fields.iter() {
+                                    self.check_ty(&field.ty);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        self.lint_unreachable_pub();
+        PrivacyResult { diags: self.diags }
+    }
+
+    fn check_block(&mut self, block: &HirBlock<'src>) {
+        for stmt in block.stmts.iter() {
+            match stmt {
+                HirStmt::Expr(expr) | HirStmt::Throw { value: expr, .. } => self.check_expr(expr),
+                HirStmt::Let { ty, init, .. } => {
+                    if let Some(ty) = ty {
+                        self.check_ty(ty);
+                    }
+                    if let Some(init) = init {
+                        self.check_expr(init);
+                    }
+                }
+                HirStmt::Assign { target, value, .. } => {
+                    self.check_expr(target);
+                    self.check_expr(value);
+                }
+                HirStmt::Break { .. } => {}
+            }
+        }
+
+        if let Some(tail) = &block.tail {
+            self.check_expr(tail);
+        }
+    }
+
+    fn check_expr(&mut self, expr: &SpannedHirExpr<'src>) {
+        match &expr.node {
+            HirExpr::Resolved(Res::Def(def) | Res::Variant { def, .. }) => {
+                self.check_access(*def, expr.span)
+            }
+            HirExpr::Struct { path, res, fields } => {
+                if let Some(Res::Def(def) | Res::Variant { def, .. }) = res {
+                    self.check_access(*def, path.span);
+                }
+                for field in fields.iter() {
+                    if let Some(Res::Def(def)) = res {
+                        self.check_field_access(*def, &field.name);
+                    }
+                    self.check_expr(&field.value);
+                }
+            }
+            HirExpr::Call { callee, args } => {
+                self.check_expr(callee);
+                for arg in args.iter() {
+                    self.check_expr(arg);
+                }
+            }
+            HirExpr::Turbofish { callee, args } => {
+                self.check_expr(callee);
+                for arg in args.iter() {
+                    match arg {
+                        HirGenericArg::Type(ty) => self.check_ty(ty),
+                        HirGenericArg::Const(expr) => self.check_expr(expr),
+                    }
+                }
+            }
+            HirExpr::Binary { lhs, rhs, .. } | HirExpr::Index { base: lhs, index: rhs } => {
+                self.check_expr(lhs);
+                self.check_expr(rhs);
+            }
+            HirExpr::Field { base, name } => {
+                self.check_expr(base);
+                if let Ty::Struct { def, .. } | Ty::Union { def, .. } =
+                    self.info.ty_of_expr(self.item, base)
+                {
+                    self.check_field_access(def, name);
+                }
+            }
+            HirExpr::TupleField { base: inner, .. }
+            | HirExpr::Try(inner)
+            | HirExpr::Deref(inner) => self.check_expr(inner),
+            HirExpr::Tuple(items) => {
+                for item in items.iter() {
+                    self.check_expr(item);
+                }
+            }
+            HirExpr::Block(block) | HirExpr::Unsafe(block) => self.check_block(block),
+            HirExpr::If { cond, then, else_ } => {
+                self.check_expr(cond);
+                self.check_block(then);
+                if let Some(else_) = else_ {
+                    self.check_expr(else_);
+                }
+            }
+            HirExpr::Closure(closure) => {
+                for ty in closure.params.iter().filter_map(|param| param.ty.as_ref()) {
+                    self.check_ty(ty);
+                }
+                self.check_expr(&closure.body);
+            }
+            HirExpr::Range { start, end, .. } => {
+                self.check_expr(start);
+                self.check_expr(end);
+            }
+            HirExpr::For { iter, body, .. } => {
+                self.check_expr(iter);
+                self.check_block(body);
+            }
+            HirExpr::Error
+            | HirExpr::Literal(_)
+            | HirExpr::Path(_)
+            | HirExpr::Resolved(_)
+            | HirExpr::NonNullLocal(_) => {}
+        }
+    }
+
+    fn check_ty(&mut self, ty: &SpannedHirType<'src>) {
+        match &ty.node {
+            HirType::Struct { def, generics }
+            | HirType::Union { def, generics }
+            | HirType::Enum { def, generics } => {
+                self.check_access(*def, ty.span);
+                for arg in generics.iter() {
+                    match arg {
+                        HirGenericArg::Type(ty) => self.check_ty(ty),
+                        HirGenericArg::Const(expr) => self.check_expr(expr),
+                    }
+                }
+            }
+            HirType::Builtin { generics, .. } => {
+                for arg in generics.iter() {
+                    match arg {
+                        HirGenericArg::Type(ty) => self.check_ty(ty),
+                        HirGenericArg::Const(expr) => self.check_expr(expr),
+                    }
+                }
+            }
+            HirType::Tuple(items) => {
+                for item in items.iter() {
+                    self.check_ty(item);
+                }
+            }
+            HirType::Array { elem, len } => {
+                self.check_ty(elem);
+                self.check_expr(len);
+            }
+            HirType::Slice(inner)
+            | HirType::Nullable(inner)
+            | HirType::Pointer { pointee: inner, .. } => self.check_ty(inner),
+            HirType::Function { params, ret } => {
+                for param in params.iter() {
+                    self.check_ty(param);
+                }
+                self.check_ty(ret);
+            }
+            HirType::Error
+            | HirType::Unresolved(_)
+            | HirType::Primitive(_)
+            | HirType::Param(_)
+            | HirType::Unit => {}
+        }
+    }
+
+    /// A private item is visible in its own module and the modules nested in
+    /// it.
+    fn check_access(&mut self, def: HirId, span: Span) {
+        let item = self.hir.get(def);
+        let owner = self.hir.item_module(def);
+        if item.vis() == Visibility::Public || self.hir.is_within(self.module, owner) {
+            return;
+        }
+
+        let module = self.module_name(owner);
+        self.diags.push(
+            *Diagnostic::error(DiagMessage::new(
+                messages::PRIVATE_ITEM,
+                &[("name", item.name().node), ("module", &module)],
+            ))
+            .add_label(DiagLabel::silent_primary(span))
+            .add_label(DiagLabel::simple_secondary(messages::DEFINED_HERE, item.name().span)),
+        );
+    }
+
+    /// A private field of a struct or union is visible where a private item
+    /// of the module defining the type would be.
+    fn check_field_access(&mut self, def: HirId, name: &SpannedStr<'src>) {
+        let (HirItem::Struct(HirStruct { fields, .. }) | HirItem::Union(HirUnion { fields, .. })) =
+            self.hir.get(def)
+        else {
+            return;
+        };
+        let Some(field) = fields.iter().find(|field| field.name.node == name.node) else {
+            return;
+        };
+        let owner = self.hir.item_module(def);
+        if field.vis == Visibility::Public || self.hir.is_within(self.module, owner) {
+            return;
+        }
+
+        let ty = self.hir.get(def).name().node;
+        let module = self.module_name(owner);
+        self.diags.push(
+            *Diagnostic::error(DiagMessage::new(
+                messages::PRIVATE_FIELD,
+                &[("name", name.node), ("ty", ty), ("module", &module)],
+            ))
+            .add_label(DiagLabel::silent_primary(name.span))
+            .add_label(DiagLabel::simple_secondary(messages::DEFINED_HERE, field.name.span)),
+        );
+    }
+
+    /// Warns about `pub` items of modules that no import chain from the crate
+    /// root reaches, and about `pub` fields and variants of private types.
+    fn lint_unreachable_pub(&mut self) {
+        let reachable = self.reachable_modules();
+        for (id, item) in self.hir.iter() {
+            let module = self.hir.item_module(id);
+            if item.vis() == Visibility::Public && !reachable.contains(&module) {
+                let note =
+                    DiagNote::new(messages::NOT_IMPORTED, &[("module", &self.module_name(module))]);
+                self.push_unreachable_pub(item.name().node, item.name().span, note);
+            }
+
+            let members: Vec<_> = match item {
+                HirItem::Struct(HirStruct { fields, .. })
+                | HirItem::Union(HirUnion { fields, .. }) => {
+                    fields.iter().map(|field| (field.vis, &field.name)).collect()
+                }
+                HirItem::Enum(enm) => {
+                    enm.variants.iter().map(|variant| (variant.vis, &variant.name)).collect()
+                }
+                HirItem::Function(_) | HirItem::ExternFunction(_) | HirItem::Alias(_) => Vec::new(),
+            };
+            if item.vis() == Visibility::Private {
+                for (vis, name) in members {
+                    if vis == Visibility::Public {
+                        let note = DiagNote::new(
+                            messages::PRIVATE_PARENT,
+                            &[("parent", item.name().node)],
+                        );
+                        self.push_unreachable_pub(name.node, name.span, note);
+                    }
+                }
+            }
+        }
+    }
+
+    fn push_unreachable_pub(&mut self, name: &str, span: Span, note: DiagNote) {
+        self.diags.push(
+            *Diagnostic::warning(DiagMessage::new(messages::UNREACHABLE_PUB, &[("name", name)]))
+                .add_label(DiagLabel::silent_primary(span))
+                .set_note(note)
+                .set_lint_type(UNREACHABLE_PUB_LINT),
+        );
+    }
+
+    /// The crate root, the standard library modules and every module
+    /// imported by a reachable module.
+    fn reachable_modules(&self) -> HashSet<HirModuleId> {
+        let std_modules = STD_MODULES
+            .iter()
+            .filter_map(|module| self.hir.find_module(module.path.iter().copied()));
+        let mut queue: Vec<_> = [HirModuleId::ROOT].into_iter().chain(std_modules).collect();
+        let mut reachable: HashSet<_> = queue.iter().copied().collect();
+        while let Some(module) = queue.pop() {
+            for import in self.hir.module(module).imports.iter() {
+                let names = import.path.segments.iter().map(|segment| segment.name.node);
+                if let Some(target) = self.hir.find_module(names)
+                    && reachable.insert(target)
+                {
+                    queue.push(target);
+                }
+            }
+        }
+        reachable
+    }
+
+    fn module_name(&self, module: HirModuleId) -> String {
+        let path = self.hir.module_path(module);
+        if path.is_empty() { "crate".to_owned() } else { path.join("::") }
+    }
+}
+
+mod messages;
+#[cfg(test)]
+mod tests {
+    use runec_ast::SpannedStr;
+    use runec_ast::statement::Visibility;
+    use runec_errors::diagnostics::DiagType;
+    use runec_hir::expression::{HirExpr, HirFieldValue, HirLiteral};
+    use runec_hir::ids::{HirId, HirLocalId, HirModuleId};
+    use runec_hir::item::{HirField, HirFunction, HirItem, HirStruct};
+    use runec_hir::map::HirMap;
+    use runec_hir::module::HirImport;
+    use runec_hir::path::{HirPath, HirPathSegment};
+    use runec_hir::resolution::Res;
+    use runec_hir::statement::{HirBlock, HirStmt};
+    use runec_hir::ty::{HirPrimitiveTy, HirType};
+    use runec_source::byte_pos::BytePos;
+    use runec_source::source_map::SourceId;
+    use runec_source::span::{Span, Spanned};
+
+    use super::{PrivacyChecker, UNREACHABLE_PUB_LINT};
+    use crate::typeck::TypeChecker;
+
+    fn sp(lo: usize, hi: usize) -> Span {
+        Span::new(BytePos::from_usize(lo), BytePos::from_usize(hi), SourceId::from_usize(0))
+    }
+
+    /// A function named `name` whose body calls each of `calls`.
+    fn function(
+        id: usize,
+        vis: Visibility,
+        name: &'static str,
+        calls: &[usize],
+    ) -> HirItem<'static> {
+        let stmts = calls
+            .iter()
+            .map(|callee| {
+                HirStmt::Expr(Spanned::new(
+                    HirExpr::Call {
+                        callee: Box::new(Spanned::new(
+                            HirExpr::Resolved(Res::Def(HirId::from_usize(*callee))),
+                            sp(100 + callee, 101 + callee),
+                        )),
+                        args: Box::new([]),
+                    },
+                    sp(0, 0),
+                ))
+            })
+            .collect();
+        HirItem::Function(HirFunction {
+            id: HirId::from_usize(id),
+            vis,
+            name: SpannedStr::new(name, sp(id, id + 1)),
+            generics: Box::new([]),
+            params: Box::new([]),
+            ret_ty: Spanned::new(HirType::Unit, sp(0, 0)),
+            throws: None,
+            body: HirBlock { stmts, tail: None, span: sp(0, 0) },
+            span: sp(0, 0),
+        })
+    }
+
+    fn import(name: &'static str) -> HirImport<'static> {
+        let segment = HirPathSegment {
+            name: SpannedStr::new(name, sp(0, 0)),
+            generics: None,
+            span: sp(0, 0),
+        };
+        HirImport {
+            path: HirPath { from_root: false, segments: Box::new([segment]), span: sp(0, 0) },
+            span: sp(0, 0),
+        }
+    }
+
+    #[test]
+    fn rejects_private_items_used_outside_their_module() {
+        let mut hir = HirMap::new();
+        let a = hir.add_module(HirModuleId::ROOT, "a");
+        hir.module_mut(HirModuleId::ROOT).imports.push(import("a"));
+        hir.push_in(a, function(0, Visibility::Private, "secret", &[]));
+        hir.push_in(a, function(1, Visibility::Public, "open", &[0, 3]));
+        hir.push(function(2, Visibility::Private, "main", &[0, 1]));
+        hir.push(function(3, Visibility::Private, "helper", &[]));
+
+        let info = TypeChecker::new().check(&hir).info;
+        let result = PrivacyChecker::new(&hir, &info).check();
+
+        // `a::open` may call the private `secret` of its own module and the
+        // private `helper` of its parent, but `main` may not call `secret`.
+        assert_eq!(result.diags.len(), 1, "{:?}", result.diags);
+        let diag = &result.diags[0];
+        assert_eq!(diag.message.message, "`secret` is private to module `a`");
+        assert_eq!(diag.labels[0].span, sp(100, 101));
+        assert_eq!(diag.labels[1].span, sp(0, 1));
+    }
+
+    #[test]
+    fn lints_pub_items_unreachable_from_the_crate_root() {
+        let mut hir = HirMap::new();
+        let imported = hir.add_module(HirModuleId::ROOT, "imported");
+        let orphan = hir.add_module(HirModuleId::ROOT, "orphan");
+        hir.module_mut(HirModuleId::ROOT).imports.push(import("imported"));
+        hir.push_in(imported, function(0, Visibility::Public, "reachable", &[]));
+        hir.push_in(orphan, function(1, Visibility::Public, "lost", &[]));
+        hir.push(HirItem::Struct(HirStruct {
+            id: HirId::from_usize(2),
+            vis: Visibility::Private,
+            name: SpannedStr::new("Point", sp(0, 0)),
+            generics: Box::new([]),
+            fields: Box::new([HirField {
+                vis: Visibility::Public,
+                name: SpannedStr::new("x", sp(0, 0)),
+                ty: Spanned::new(HirType::Primitive(HirPrimitiveTy::I32), sp(0, 0)),
+                span: sp(0, 0),
+            }]),
+            span: sp(0, 0),
+        }));
+
+        let info = TypeChecker::new().check(&hir).info;
+        let result = PrivacyChecker::new(&hir, &info).check();
+
+        let lints: Vec<_> = result
+            .diags
+            .iter()
+            .map(|diag| {
+                assert_eq!(diag.diag_type, DiagType::Warning);
+                assert!(diag.lint_type.as_ref().is_some_and(|lint| lint.is(UNREACHABLE_PUB_LINT)));
+                (
+                    diag.message.message.to_string(),
+                    diag.note.as_ref().map(|note| note.message.clone()).unwrap_or_default(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            lints,
+            [
+                (
+                    "`lost` is `pub` but unreachable from the crate root".to_owned(),
+                    "module `orphan` is not imported by any reachable module".to_owned()
+                ),
+                (
+                    "`x` is `pub` but unreachable from the crate root".to_owned(),
+                    "`Point` is not `pub`".to_owned()
+                ),
+            ]
+        );
+    }
+
+    /// `pub struct Pt { pub x: i32, hidden: str }` defined in `lib`, and a
+    /// function `name` that builds one and reads both of its fields.
+    fn forging_function(id: usize, name: &'static str) -> HirItem<'static> {
+        let p = || Spanned::new(HirExpr::Resolved(Res::Local(HirLocalId::from_usize(0))), sp(0, 0));
+        let field = |name, lo| {
+            HirStmt::Expr(Spanned::new(
+                HirExpr::Field { base: Box::new(p()), name: SpannedStr::new(name, sp(lo, lo + 1)) },
+                sp(0, 0),
+            ))
+        };
+        let value = |name, lo, value| HirFieldValue {
+            name: SpannedStr::new(name, sp(lo, lo + 1)),
+            value: Spanned::new(HirExpr::Literal(value), sp(0, 0)),
+        };
+        let segment = |name| HirPathSegment {
+            name: SpannedStr::new(name, sp(0, 0)),
+            generics: None,
+            span: sp(0, 0),
+        };
+        let literal = HirExpr::Struct {
+            path: HirPath {
+                from_root: false,
+                segments: Box::new([segment("lib"), segment("Pt")]),
+                span: sp(0, 0),
+            },
+            res: Some(Res::Def(HirId::from_usize(0))),
+            fields: Box::new([
+                value("x", 200, HirLiteral::Int { value: 1, suffix: None }),
+                value("hidden", 201, HirLiteral::Str("forged".into())),
+            ]),
+        };
+        HirItem::Function(HirFunction {
+            id: HirId::from_usize(id),
+            vis: Visibility::Public,
+            name: SpannedStr::new(name, sp(0, 0)),
+            generics: Box::new([]),
+            params: Box::new([]),
+            ret_ty: Spanned::new(HirType::Unit, sp(0, 0)),
+            throws: None,
+            body: HirBlock {
+                stmts: Box::new([
+                    HirStmt::Let {
+                        local: Some(HirLocalId::from_usize(0)),
+                        name: SpannedStr::new("p", sp(0, 0)),
+                        is_mutable: false,
+                        ty: None,
+                        init: Some(Spanned::new(literal, sp(0, 0))),
+                        span: sp(0, 0),
+                    },
+                    field("x", 202),
+                    field("hidden", 203),
+                ]),
+                tail: None,
+                span: sp(0, 0),
+            },
+            span: sp(0, 0),
+        })
+    }
+
+    #[test]
+    fn rejects_private_fields_used_outside_their_module() {
+        let mut hir = HirMap::new();
+        let lib = hir.add_module(HirModuleId::ROOT, "lib");
+        hir.module_mut(HirModuleId::ROOT).imports.push(import("lib"));
+        let field = |vis, name, ty, lo| HirField {
+            vis,
+            name: SpannedStr::new(name, sp(lo, lo + 1)),
+            ty: Spanned::new(HirType::Primitive(ty), sp(0, 0)),
+            span: sp(0, 0),
+        };
+        hir.push_in(
+            lib,
+            HirItem::Struct(HirStruct {
+                id: HirId::from_usize(0),
+                vis: Visibility::Public,
+                name: SpannedStr::new("Pt", sp(0, 0)),
+                generics: Box::new([]),
+                fields: Box::new([
+                    field(Visibility::Public, "x", HirPrimitiveTy::I32, 10),
+                    field(Visibility::Private, "hidden", HirPrimitiveTy::Str, 11),
+                ]),
+                span: sp(0, 0),
+            }),
+        );
+        hir.push_in(lib, forging_function(1, "make"));
+        hir.push(forging_function(2, "main"));
+
+        let info = TypeChecker::new().check(&hir).info;
+        let result = PrivacyChecker::new(&hir, &info).check();
+
+        // `lib::make` may build and read `hidden`, but `main` may do neither.
+        let errors: Vec<_> = result
+            .diags
+            .iter()
+            .map(|diag| {
+                (diag.message.message.to_string(), diag.labels[0].span, diag.labels[1].span)
+            })
+            .collect();
+        let private = |lo| {
+            (
+                "field `hidden` of `Pt` is private to module `lib`".to_owned(),
+                sp(lo, lo + 1),
+                sp(11, 12),
+            )
+        };
+        assert_eq!(errors, [private(201), private(203)]);
+    }
+}
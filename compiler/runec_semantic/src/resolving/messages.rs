@@ -3,6 +3,17 @@ use runec_utils::define_messages;
 define_messages! {
     DUPLICATE_ITEM => "item `{name}` is defined multiple times",
     DUPLICATE_LOCAL => "local `{name}` is defined multiple times",
+    DUPLICATE_GENERIC => "generic parameter `{name}` is defined multiple times",
     UNRESOLVED_NAME => "cannot resolve value `{name}`",
     UNRESOLVED_TYPE => "cannot resolve type `{name}`",
+    UNRESOLVED_STRUCT => "cannot resolve struct, union or variant `{name}`",
+    UNRESOLVED_CONTRACT => "cannot resolve contract `{name}`",
+    UNRESOLVED_IMPORT => "cannot resolve import `{name}`",
+    USE_CYCLE => "`use {name}` depends on itself",
+    ALIAS_CYCLE => "alias `{name}` refers to itself",
+    ALIAS_ARGUMENT_COUNT_MISMATCH => "alias `{name}` expects {expected} generic arguments, found {actual}",
+    EXPECTED_TYPE_ARGUMENT => "expected a type for generic parameter `{name}`, found a constant",
+    EXPECTED_CONST_ARGUMENT => "expected a constant for generic parameter `{name}`, found a type",
+    GLOB_OF_NON_MODULE => "cannot glob-import `{name}`: it is not a module",
+    BREAK_OUTSIDE_LOOP => "`break` outside of a `for` loop",
 }
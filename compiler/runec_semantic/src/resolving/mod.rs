@@ -1,68 +1,186 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use runec_builtins::builtin_from_name;
+use runec_ast::SpannedStr;
+use runec_ast::operators::BinaryOp;
+use runec_ast::statement::Visibility;
+use runec_builtins::{
+    BUILTINS, BuiltinId, BuiltinLowering, builtin_decl, builtin_from_name, builtin_type_from_name,
+    builtin_type_path, contract_from_name, is_builtin_module,
+};
 use runec_errors::diagnostics::Diagnostic;
 use runec_errors::labels::DiagLabel;
 use runec_errors::message::DiagMessage;
-use runec_hir::expression::{HirExpr, SpannedHirExpr};
-use runec_hir::ids::{HirId, HirLocalId};
-use runec_hir::item::{HirItem, HirVariantPayload};
+use runec_hir::expression::{
+    HirCapture, HirCaptureMode, HirExpr, HirLiteral, SpannedHirExpr, strip_turbofish,
+};
+use runec_hir::ids::{HirId, HirLocalId, HirModuleId};
+use runec_hir::item::{HirGenericParam, HirGenericParamKind, HirItem, HirVariantPayload};
 use runec_hir::map::HirMap;
+use runec_hir::module::HirUseKind;
+use runec_hir::path::{HirGenericArg, HirPath};
 use runec_hir::resolution::Res;
 use runec_hir::statement::{HirBlock, HirStmt};
 use runec_hir::ty::{HirPrimitiveTy, HirType, SpannedHirType};
-use runec_source::span::Span;
+use runec_source::span::{Span, Spanned};
 
 pub struct ResolveResult {
     pub diags: Vec<Diagnostic<'static>>,
 }
 
 pub struct Resolver<'src> {
-    items: HashMap<&'src str, ResolvedItem>,
+    /// Names visible in each module, indexed by `HirModuleId`.
+    scopes: Vec<ModuleScope<'src>>,
+    /// Module of the item being resolved.
+    module: HirModuleId,
+    uses: HashMap<UseKey, UseState>,
+    /// `use` declarations currently being resolved, innermost last.
+    use_stack: Vec<UseKey>,
+    /// Generic parameters of the item being resolved, in declaration order.
+    generics: Vec<(&'src str, GenericKind)>,
+    aliases: HashMap<HirId, AliasDef<'src>>,
+    /// Names of the variants of each enum, in declaration order.
+    variants: HashMap<HirId, Box<[&'src str]>>,
     diags: Vec<Diagnostic<'static>>,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum GenericKind {
+    Type,
+    Const,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 struct ResolvedItem {
     id: HirId,
     kind: ResolvedItemKind,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 enum ResolvedItemKind {
     Function,
     Struct,
+    Union,
     Enum,
+    Alias,
+}
+
+/// An `alias` declaration, resolved on first use.
+struct AliasDef<'src> {
+    name: SpannedStr<'src>,
+    module: HirModuleId,
+    generics: Vec<(&'src str, GenericKind)>,
+    state: AliasState<'src>,
+}
+
+enum AliasState<'src> {
+    Unresolved(SpannedHirType<'src>),
+    InProgress,
+    Resolved(SpannedHirType<'src>),
+}
+
+/// What a name in a module namespace refers to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Binding {
+    Item(ResolvedItem),
+    Module(HirModuleId),
+    Builtin(BuiltinId),
+    /// A builtin module such as `core::mem`, by its full path.
+    BuiltinModule(String),
+    /// Variant `index` of enum `def`, named by a path through the enum.
+    Variant {
+        def: HirId,
+        index: usize,
+    },
+}
+
+#[derive(Default)]
+struct ModuleScope<'src> {
+    /// Path of the module from the crate root, such as `std::itertools`.
+    path: String,
+    /// Items, child modules, imports and single `use` bindings.
+    names: HashMap<&'src str, Binding>,
+    /// Names brought in by glob `use`s; shadowed by `names`.
+    globs: HashMap<&'src str, Binding>,
+}
+
+/// A `use` declaration, by its module and position in that module.
+type UseKey = (HirModuleId, usize);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum UseState {
+    InProgress,
+    /// In progress and part of a reported cycle.
+    Cyclic,
+    Done,
 }
 
 impl<'src> Resolver<'src> {
     pub fn new() -> Self {
-        Self { items: HashMap::new(), diags: Vec::new() }
+        Self {
+            scopes: Vec::new(),
+            module: HirModuleId::ROOT,
+            uses: HashMap::new(),
+            use_stack: Vec::new(),
+            generics: Vec::new(),
+            aliases: HashMap::new(),
+            variants: HashMap::new(),
+            diags: Vec::new(),
+        }
     }
 
     pub fn resolve(mut self, hir: &mut HirMap<'src>) -> ResolveResult {
         self.collect_items(hir);
+        self.resolve_uses(hir);
+        self.collect_aliases(hir);
 
-        for (_, item) in hir.iter_mut() {
+        let item_modules: Vec<_> = hir.iter().map(|(id, _)| hir.item_module(id)).collect();
+        for ((_, item), module) in hir.iter_mut().zip(item_modules) {
+            self.module = module;
             match item {
                 HirItem::Function(function) => {
+                    self.enter_generics(&mut function.generics);
                     for param in function.params.iter_mut() {
                         self.resolve_ty(&mut param.ty);
                     }
                     self.resolve_ty(&mut function.ret_ty);
+                    if let Some(throws) = &mut function.throws {
+                        self.resolve_ty(throws);
+                    }
 
                     let mut locals = LocalScope::new();
                     for param in function.params.iter() {
                         locals.define(param.name.node, param.span, &mut self.diags);
                     }
                     self.resolve_block(&mut function.body, &mut locals);
+                    if !locals.shared.is_empty() {
+                        mark_shared_captures_in_block(&mut function.body, &locals.shared);
+                    }
+                }
+                HirItem::ExternFunction(function) => {
+                    self.generics.clear();
+                    for param in function.params.iter_mut() {
+                        self.resolve_ty(&mut param.ty);
+                    }
+                    self.resolve_ty(&mut function.ret_ty);
                 }
                 HirItem::Struct(strukt) => {
+                    self.enter_generics(&mut strukt.generics);
                     for field in strukt.fields.iter_mut() {
                         self.resolve_ty(&mut field.ty);
                     }
                 }
+                HirItem::Union(union) => {
+                    self.enter_generics(&mut union.generics);
+                    for field in union.fields.iter_mut() {
+                        self.resolve_ty(&mut field.ty);
+                    }
+                }
+                HirItem::Alias(alias) => {
+                    self.enter_generics(&mut alias.generics);
+                    alias.ty = self.alias_target(alias.id);
+                }
                 HirItem::Enum(enm) => {
+                    self.generics.clear();
                     for variant in enm.variants.iter_mut() {
                         match &mut variant.payload {
                             HirVariantPayload::Unit => {}
@@ -85,22 +203,426 @@ impl<'src> Resolver<'src> {
         ResolveResult { diags: self.diags }
     }
 
+    /// Fills every module namespace with its child modules, items and
+    /// imports.
     fn collect_items(&mut self, hir: &HirMap<'src>) {
+        self.scopes = hir.modules().map(|_| ModuleScope::default()).collect();
+
+        for (module_id, module) in hir.modules() {
+            let mut names = Vec::new();
+            let mut current = Some(module_id);
+            while let Some(id) = current.filter(|id| *id != HirModuleId::ROOT) {
+                names.push(hir.module(id).name);
+                current = hir.module(id).parent;
+            }
+            names.reverse();
+            let scope = &mut self.scopes[module_id.to_usize()];
+            scope.path = names.join("::");
+            for &child in module.children.iter() {
+                scope.names.insert(hir.module(child).name, Binding::Module(child));
+            }
+
+            for &id in module.items.iter() {
+                let item = hir.get(id);
+                if let HirItem::Enum(enm) = item {
+                    let names = enm.variants.iter().map(|variant| variant.name.node).collect();
+                    self.variants.insert(id, names);
+                }
+                let kind = match item {
+                    HirItem::Function(_) | HirItem::ExternFunction(_) => ResolvedItemKind::Function,
+                    HirItem::Struct(_) => ResolvedItemKind::Struct,
+                    HirItem::Union(_) => ResolvedItemKind::Union,
+                    HirItem::Enum(_) => ResolvedItemKind::Enum,
+                    HirItem::Alias(_) => ResolvedItemKind::Alias,
+                };
+                self.define(module_id, item.name(), Binding::Item(ResolvedItem { id, kind }));
+            }
+
+            for import in module.imports.iter() {
+                // Imports of missing files are reported by the crate loader.
+                let names = import.path.segments.iter().map(|segment| segment.name.node);
+                if let (Some(target), Some(last)) =
+                    (hir.find_module(names), import.path.segments.last())
+                {
+                    self.define(module_id, &last.name, Binding::Module(target));
+                }
+            }
+        }
+    }
+
+    fn collect_aliases(&mut self, hir: &HirMap<'src>) {
         for (id, item) in hir.iter() {
-            let kind = match item {
-                HirItem::Function(_) => ResolvedItemKind::Function,
-                HirItem::Struct(_) => ResolvedItemKind::Struct,
-                HirItem::Enum(_) => ResolvedItemKind::Enum,
+            if let HirItem::Alias(alias) = item {
+                let def = AliasDef {
+                    name: alias.name.clone(),
+                    module: hir.item_module(id),
+                    generics: generic_kinds(&alias.generics),
+                    state: AliasState::Unresolved(alias.ty.clone()),
+                };
+                self.aliases.insert(id, def);
+            }
+        }
+    }
+
+    fn define(&mut self, module: HirModuleId, name: &SpannedStr<'src>, binding: Binding) {
+        let names = &mut self.scopes[module.to_usize()].names;
+        match names.get(name.node) {
+            Some(existing) if *existing != binding => self.diags.push(diagnostic(
+                messages::DUPLICATE_ITEM,
+                &[("name", name.node)],
+                name.span,
+            )),
+            Some(_) => {}
+            None => {
+                names.insert(name.node, binding);
+            }
+        }
+    }
+
+    fn resolve_uses(&mut self, hir: &HirMap<'src>) {
+        for (module_id, module) in hir.modules() {
+            for index in 0..module.uses.len() {
+                self.resolve_use(hir, (module_id, index));
+            }
+        }
+    }
+
+    /// Resolves a `use` declaration and binds its names, resolving the
+    /// declarations it depends on first.
+    fn resolve_use(&mut self, hir: &HirMap<'src>, key: UseKey) {
+        match self.uses.get(&key) {
+            Some(UseState::InProgress) => {
+                self.report_use_cycle(hir, key);
+                return;
+            }
+            Some(UseState::Cyclic | UseState::Done) => return,
+            None => {}
+        }
+
+        let (module, index) = key;
+        let use_ = &hir.module(module).uses[index];
+        self.uses.insert(key, UseState::InProgress);
+        self.use_stack.push(key);
+        let resolved = self.resolve_path(Some(hir), module, &use_.path);
+        self.use_stack.pop();
+        let cyclic = self.uses.insert(key, UseState::Done) == Some(UseState::Cyclic);
+
+        match (resolved, &use_.kind) {
+            // A cycle has already been reported for this declaration.
+            (None, _) if cyclic => {}
+            (None, _) => {
+                let name = format_path(&use_.path);
+                self.diags.push(diagnostic(
+                    messages::UNRESOLVED_IMPORT,
+                    &[("name", &name)],
+                    use_.path.span,
+                ));
+            }
+            (Some(binding), HirUseKind::Single { .. }) => {
+                if let Some(name) = use_.binding() {
+                    self.define(module, name, binding);
+                }
+            }
+            (Some(Binding::Module(target)), HirUseKind::Glob) => {
+                // Single `use`s of the target are re-exported through the glob.
+                let target_uses = &hir.module(target).uses;
+                for (target_index, target_use) in target_uses.iter().enumerate() {
+                    if target_use.binding().is_some() {
+                        self.resolve_use(hir, (target, target_index));
+                    }
+                }
+
+                // Globs only bring in the items `module` is allowed to see.
+                let names: Vec<_> = self.scopes[target.to_usize()]
+                    .names
+                    .iter()
+                    .filter(|(_, binding)| match binding {
+                        Binding::Item(item) => {
+                            hir.get(item.id).vis() == Visibility::Public
+                                || hir.is_within(module, hir.item_module(item.id))
+                        }
+                        _ => true,
+                    })
+                    .map(|(name, binding)| (*name, binding.clone()))
+                    .collect();
+                let globs = &mut self.scopes[module.to_usize()].globs;
+                for (name, binding) in names {
+                    globs.entry(name).or_insert(binding);
+                }
+            }
+            (Some(Binding::BuiltinModule(path)), HirUseKind::Glob) => {
+                let globs = &mut self.scopes[module.to_usize()].globs;
+                for (index, decl) in BUILTINS.iter().enumerate() {
+                    if decl.module == path {
+                        globs
+                            .entry(decl.name)
+                            .or_insert(Binding::Builtin(BuiltinId::from_index(index)));
+                    }
+                }
+            }
+            (Some(_), HirUseKind::Glob) => {
+                let name = format_path(&use_.path);
+                self.diags.push(diagnostic(
+                    messages::GLOB_OF_NON_MODULE,
+                    &[("name", &name)],
+                    use_.path.span,
+                ));
+            }
+        }
+    }
+
+    /// Reports `key` as depending on itself and marks every declaration of
+    /// the cycle so that none of them is reported again as unresolved.
+    fn report_use_cycle(&mut self, hir: &HirMap<'src>, key: UseKey) {
+        let start = self.use_stack.iter().position(|entry| *entry == key).unwrap_or(0);
+        for entry in self.use_stack[start..].iter() {
+            self.uses.insert(*entry, UseState::Cyclic);
+        }
+
+        let use_ = &hir.module(key.0).uses[key.1];
+        let name = format_path(&use_.path);
+        self.diags.push(diagnostic(messages::USE_CYCLE, &[("name", &name)], use_.path.span));
+    }
+
+    /// Looks `name` up in the namespace of `module`. With `hir` given, the
+    /// `use` declarations that may bind it are resolved on demand.
+    fn lookup(
+        &mut self,
+        hir: Option<&HirMap<'src>>,
+        module: HirModuleId,
+        name: &str,
+    ) -> Option<Binding> {
+        if let Some(binding) = self.scopes[module.to_usize()].names.get(name) {
+            return Some(binding.clone());
+        }
+
+        if let Some(hir) = hir {
+            let uses = &hir.module(module).uses;
+            for (index, use_) in uses.iter().enumerate() {
+                if use_.binding().is_some_and(|binding| binding.node == name) {
+                    self.resolve_use(hir, (module, index));
+                }
+            }
+            if let Some(binding) = self.scopes[module.to_usize()].names.get(name) {
+                return Some(binding.clone());
+            }
+
+            for (index, use_) in uses.iter().enumerate() {
+                // A glob still being resolved has not contributed any names
+                // yet; the other globs may still provide this one.
+                let in_progress = self
+                    .uses
+                    .get(&(module, index))
+                    .is_some_and(|state| matches!(state, UseState::InProgress | UseState::Cyclic));
+                if use_.kind == HirUseKind::Glob && !in_progress {
+                    self.resolve_use(hir, (module, index));
+                }
+            }
+        }
+
+        self.scopes[module.to_usize()].globs.get(name).cloned()
+    }
+
+    /// Resolves `path` segment by segment, starting in `module` (or the
+    /// crate root for `::a::b`). A leading name that is not bound there may
+    /// still be a top-level module, a builtin module, a builtin type whose
+    /// associated functions follow, or a prelude builtin.
+    fn resolve_path(
+        &mut self,
+        hir: Option<&HirMap<'src>>,
+        module: HirModuleId,
+        path: &HirPath<'src>,
+    ) -> Option<Binding> {
+        let (first, rest) = path.segments.split_first()?;
+        let name = first.name.node;
+
+        let start = if path.from_root { HirModuleId::ROOT } else { module };
+        let mut binding = self
+            .lookup(hir, start, name)
+            .or_else(|| match self.scopes[HirModuleId::ROOT.to_usize()].names.get(name) {
+                Some(binding @ Binding::Module(_)) => Some(binding.clone()),
+                _ => None,
+            })
+            .or_else(|| is_builtin_module(name).then(|| Binding::BuiltinModule(name.to_owned())))
+            .or_else(|| {
+                builtin_type_from_name(name).and_then(builtin_type_path).map(Binding::BuiltinModule)
+            })
+            .or_else(|| builtin_from_name(name).map(Binding::Builtin))?;
+
+        for segment in rest {
+            let name = segment.name.node;
+            binding = match binding {
+                // A module of the crate may share its path with builtin
+                // modules, as `std::itertools` does with `std::json`.
+                Binding::Module(module) => self.lookup(hir, module, name).or_else(|| {
+                    let prefix = &self.scopes[module.to_usize()].path;
+                    builtin_member(&format!("{prefix}::{name}"))
+                })?,
+                Binding::BuiltinModule(prefix) => builtin_member(&format!("{prefix}::{name}"))?,
+                Binding::Item(ResolvedItem { id, kind: ResolvedItemKind::Enum }) => {
+                    let index =
+                        self.variants.get(&id)?.iter().position(|variant| *variant == name)?;
+                    Binding::Variant { def: id, index }
+                }
+                Binding::Item(_) | Binding::Builtin(_) | Binding::Variant { .. } => return None,
             };
-            let resolved = ResolvedItem { id, kind };
-            if self.items.insert(item.name().node, resolved).is_some() {
+        }
+
+        Some(binding)
+    }
+
+    /// Brings the generic parameters of an item into scope and resolves
+    /// their bounds and const parameter types.
+    fn enter_generics(&mut self, generics: &mut [HirGenericParam<'src>]) {
+        self.generics = generic_kinds(generics);
+        for (index, param) in generics.iter().enumerate() {
+            if self.generics[..index].iter().any(|(name, _)| *name == param.name.node) {
+                self.diags.push(diagnostic(
+                    messages::DUPLICATE_GENERIC,
+                    &[("name", param.name.node)],
+                    param.name.span,
+                ));
+            }
+        }
+
+        for param in generics.iter_mut() {
+            match &mut param.kind {
+                HirGenericParamKind::Type { bounds } => {
+                    for bound in bounds.iter_mut() {
+                        bound.contract = contract_from_path(&bound.path);
+                        if let Some(last) = bound.path.segments.last_mut()
+                            && let Some(args) = last.generics.take()
+                        {
+                            last.generics = Some(self.resolve_generic_args(args));
+                        }
+                        if bound.contract.is_none() {
+                            let name = format_path(&bound.path);
+                            self.diags.push(diagnostic(
+                                messages::UNRESOLVED_CONTRACT,
+                                &[("name", &name)],
+                                bound.path.span,
+                            ));
+                        }
+                    }
+                }
+                HirGenericParamKind::Const { ty } => self.resolve_ty(ty),
+            }
+        }
+    }
+
+    /// Returns the resolved target of alias `id`, resolving it in the module
+    /// and generic scope of its declaration on first use.
+    fn alias_target(&mut self, id: HirId) -> SpannedHirType<'src> {
+        let def = self.aliases.get_mut(&id).expect("aliases are collected before resolution");
+        let mut ty = match std::mem::replace(&mut def.state, AliasState::InProgress) {
+            AliasState::Resolved(ty) => {
+                def.state = AliasState::Resolved(ty.clone());
+                return ty;
+            }
+            AliasState::InProgress => {
+                let name = def.name.clone();
                 self.diags.push(diagnostic(
-                    messages::DUPLICATE_ITEM,
-                    &[("name", item.name().node)],
-                    item.name().span,
+                    messages::ALIAS_CYCLE,
+                    &[("name", name.node)],
+                    name.span,
                 ));
+                return Spanned::new(HirType::Error, name.span);
             }
+            AliasState::Unresolved(ty) => ty,
+        };
+
+        let module = std::mem::replace(&mut self.module, def.module);
+        let generics = std::mem::replace(&mut self.generics, def.generics.clone());
+        self.resolve_ty(&mut ty);
+        self.module = module;
+        self.generics = generics;
+
+        if let Some(def) = self.aliases.get_mut(&id) {
+            def.state = AliasState::Resolved(ty.clone());
+        }
+        ty
+    }
+
+    /// Replaces a use of alias `id` with its target, substituting the
+    /// generic parameters of the alias with `args`.
+    fn expand_alias(
+        &mut self,
+        id: HirId,
+        args: Box<[HirGenericArg<'src>]>,
+        span: Span,
+    ) -> HirType<'src> {
+        let target = self.alias_target(id);
+        let Some(def) = self.aliases.get(&id) else {
+            return HirType::Error;
+        };
+        if def.generics.len() != args.len() {
+            let expected = def.generics.len().to_string();
+            let actual = args.len().to_string();
+            self.diags.push(diagnostic(
+                messages::ALIAS_ARGUMENT_COUNT_MISMATCH,
+                &[("name", def.name.node), ("expected", &expected), ("actual", &actual)],
+                span,
+            ));
+            return HirType::Error;
+        }
+
+        for ((name, kind), arg) in def.generics.iter().zip(args.iter()) {
+            let (message, span) = match (kind, arg) {
+                (GenericKind::Type, HirGenericArg::Const(expr)) => {
+                    (messages::EXPECTED_TYPE_ARGUMENT, expr.span)
+                }
+                (GenericKind::Const, HirGenericArg::Type(ty)) => {
+                    (messages::EXPECTED_CONST_ARGUMENT, ty.span)
+                }
+                _ => continue,
+            };
+            self.diags.push(diagnostic(message, &[("name", name)], span));
+            return HirType::Error;
+        }
+
+        substitute_ty(target.node, &args)
+    }
+
+    /// Returns the index of the generic parameter of `kind` named by `path`.
+    fn generic_param(&self, path: &HirPath<'_>, kind: GenericKind) -> Option<usize> {
+        if path.from_root || path.segments.len() != 1 || path.segments[0].generics.is_some() {
+            return None;
         }
+
+        let name = path.segments[0].name.node;
+        self.generics.iter().position(|generic| *generic == (name, kind))
+    }
+
+    fn resolve_generic_args(
+        &mut self,
+        args: Box<[HirGenericArg<'src>]>,
+    ) -> Box<[HirGenericArg<'src>]> {
+        args.into_iter()
+            .map(|arg| match arg {
+                // `Type<N>` parses `N` as a type even when it names a const
+                // parameter.
+                HirGenericArg::Type(ty) => match &ty.node {
+                    HirType::Unresolved(path)
+                        if let Some(index) = self.generic_param(path, GenericKind::Const) =>
+                    {
+                        HirGenericArg::Const(Spanned::new(
+                            HirExpr::Resolved(Res::ConstParam(index)),
+                            ty.span,
+                        ))
+                    }
+                    _ => {
+                        let mut ty = ty;
+                        self.resolve_ty(&mut ty);
+                        HirGenericArg::Type(ty)
+                    }
+                },
+                HirGenericArg::Const(mut expr) => {
+                    let mut empty = LocalScope::new();
+                    self.resolve_expr(&mut expr, &mut empty);
+                    HirGenericArg::Const(expr)
+                }
+            })
+            .collect()
     }
 
     fn resolve_block(&mut self, block: &mut HirBlock<'src>, locals: &mut LocalScope<'src>) {
@@ -125,6 +647,21 @@ impl<'src> Resolver<'src> {
                 }
                 *local = Some(locals.define(name.node, name.span, &mut self.diags));
             }
+            HirStmt::Assign { target, value, .. } => {
+                self.resolve_expr(value, locals);
+                self.resolve_expr(target, locals);
+                locals.write(target);
+            }
+            HirStmt::Throw { value, .. } => {
+                self.resolve_expr(value, locals);
+                locals.diverges = true;
+            }
+            HirStmt::Break { span } => {
+                if locals.loops == 0 {
+                    self.diags.push(diagnostic(messages::BREAK_OUTSIDE_LOOP, &[], *span));
+                }
+                locals.diverges = true;
+            }
         }
     }
 
@@ -132,14 +669,27 @@ impl<'src> Resolver<'src> {
         match &mut expr.node {
             HirExpr::Path(path) => {
                 if let Some(local) = locals.get_path(path) {
-                    expr.node = HirExpr::Resolved(Res::Local(local));
-                } else if let Some(builtin) = builtin_from_path(path) {
-                    expr.node = HirExpr::Resolved(Res::Builtin(builtin));
-                } else if !path.from_root && path.segments.len() == 1 {
-                    if let Some(item) = self.items.get(path.segments[0].name.node).copied() {
-                        if matches!(item.kind, ResolvedItemKind::Function) {
-                            expr.node = HirExpr::Resolved(Res::Def(item.id));
-                        } else {
+                    locals.capture(local);
+                    expr.node = if locals.non_null.contains(&local) {
+                        HirExpr::NonNullLocal(local)
+                    } else {
+                        HirExpr::Resolved(Res::Local(local))
+                    };
+                } else if let Some(index) = self.generic_param(path, GenericKind::Const) {
+                    expr.node = HirExpr::Resolved(Res::ConstParam(index));
+                } else {
+                    match self.resolve_path(None, self.module, path) {
+                        Some(Binding::Item(ResolvedItem {
+                            id,
+                            kind: ResolvedItemKind::Function,
+                        })) => expr.node = HirExpr::Resolved(Res::Def(id)),
+                        Some(Binding::Builtin(builtin)) => {
+                            expr.node = HirExpr::Resolved(Res::Builtin(builtin))
+                        }
+                        Some(Binding::Variant { def, index }) => {
+                            expr.node = HirExpr::Resolved(Res::Variant { def, index })
+                        }
+                        _ => {
                             let name = format_path(path);
                             self.diags.push(diagnostic(
                                 messages::UNRESOLVED_NAME,
@@ -147,73 +697,322 @@ impl<'src> Resolver<'src> {
                                 expr.span,
                             ));
                         }
-                    } else {
-                        let name = format_path(path);
-                        self.diags.push(diagnostic(
-                            messages::UNRESOLVED_NAME,
-                            &[("name", &name)],
-                            expr.span,
-                        ));
                     }
-                } else {
-                    let name = format_path(path);
-                    self.diags.push(diagnostic(
-                        messages::UNRESOLVED_NAME,
-                        &[("name", &name)],
-                        expr.span,
-                    ));
                 }
             }
+            HirExpr::Turbofish { callee, args } => {
+                self.resolve_expr(callee, locals);
+                *args = self.resolve_generic_args(std::mem::take(args));
+            }
             HirExpr::Call { callee, args } => {
                 self.resolve_expr(callee, locals);
                 for arg in args.iter_mut() {
                     self.resolve_expr(arg, locals);
                 }
+                // `core::mem::swap` and the like write to their leading
+                // arguments as assignments do.
+                if let HirExpr::Resolved(Res::Builtin(id)) = strip_turbofish(callee).node
+                    && let Some(BuiltinLowering::Place(op)) =
+                        builtin_decl(id).map(|decl| decl.lowering)
+                {
+                    for arg in args.iter_mut().take(op.places()) {
+                        locals.write(arg);
+                    }
+                }
+            }
+            HirExpr::Block(block) | HirExpr::Unsafe(block) => self.resolve_block(block, locals),
+            HirExpr::If { cond, then, else_ } => {
+                self.resolve_expr(cond, locals);
+
+                // A local that a closure writes to may become `null` whenever
+                // the closure is called, so checking it proves nothing.
+                let check = null_check(cond).filter(|(local, _)| !locals.shared.contains(local));
+                let (then_non_null, else_non_null) = match check {
+                    Some((local, BinaryOp::Ne)) => (Some(local), None),
+                    Some((local, _)) => (None, Some(local)),
+                    None => (None, None),
+                };
+                let before = locals.non_null.clone();
+
+                locals.non_null.extend(then_non_null);
+                self.resolve_block(then, locals);
+                let after_then = std::mem::replace(&mut locals.non_null, before.clone());
+                let then_diverges = std::mem::take(&mut locals.diverges);
+
+                locals.non_null.extend(else_non_null);
+                if let Some(else_) = else_ {
+                    self.resolve_expr(else_, locals);
+                }
+                let else_diverges = std::mem::take(&mut locals.diverges);
+
+                match (then_diverges, else_diverges) {
+                    (true, true) => locals.diverges = true,
+                    // Code after the `if` is only reached through the branch
+                    // that does not throw, so its facts carry over.
+                    (true, false) => {}
+                    (false, true) => locals.non_null = after_then,
+                    // Only facts that held before the `if` and survived both
+                    // branches still hold after it.
+                    (false, false) => locals
+                        .non_null
+                        .retain(|local| before.contains(local) && after_then.contains(local)),
+                }
+            }
+            HirExpr::Binary { lhs, rhs, .. } | HirExpr::Index { base: lhs, index: rhs } => {
+                self.resolve_expr(lhs, locals);
+                self.resolve_expr(rhs, locals);
+            }
+            HirExpr::Try(inner) | HirExpr::Deref(inner) => self.resolve_expr(inner, locals),
+            HirExpr::Field { base, .. } | HirExpr::TupleField { base, .. } => {
+                self.resolve_expr(base, locals)
+            }
+            HirExpr::Tuple(items) => {
+                for item in items.iter_mut() {
+                    self.resolve_expr(item, locals);
+                }
+            }
+            HirExpr::Closure(closure) => {
+                for param in closure.params.iter_mut() {
+                    if let Some(ty) = &mut param.ty {
+                        self.resolve_ty(ty);
+                    }
+                }
+
+                // Parameters and locals of the closure shadow the enclosing
+                // ones only inside its body.
+                let names = locals.names.clone();
+                let non_null = std::mem::take(&mut locals.non_null);
+                for param in closure.params.iter() {
+                    locals.names.remove(param.name.node);
+                }
+                locals
+                    .closures
+                    .push(ClosureFrame { first_local: locals.next, captures: Vec::new() });
+                // `break` cannot leave a loop around the closure.
+                let loops = std::mem::take(&mut locals.loops);
+                for param in closure.params.iter_mut() {
+                    param.local =
+                        Some(locals.define(param.name.node, param.name.span, &mut self.diags));
+                }
+                self.resolve_expr(&mut closure.body, locals);
+
+                locals.loops = loops;
+                let frame = locals.closures.pop().expect("closure frame was pushed above");
+                closure.captures = frame
+                    .captures
+                    .into_iter()
+                    .map(|local| HirCapture { local, mode: HirCaptureMode::ByValue })
+                    .collect();
+                locals.names = names;
+                // Checks made before the closure was created no longer hold
+                // for the locals it writes to.
+                locals.non_null =
+                    non_null.into_iter().filter(|local| !locals.shared.contains(local)).collect();
+                locals.diverges = false;
+            }
+            HirExpr::Range { start, end, .. } => {
+                self.resolve_expr(start, locals);
+                self.resolve_expr(end, locals);
+            }
+            HirExpr::For { local, name, iter, body } => {
+                self.resolve_expr(iter, locals);
+
+                // The body runs again after its own writes, so checks made
+                // before the loop no longer hold for the locals it writes to,
+                // neither inside the body nor after the loop.
+                let mut written = HashSet::new();
+                self.written_in_block(body, &mut written);
+                for name in written {
+                    if let Some(local) = locals.names.get(name) {
+                        locals.non_null.remove(local);
+                    }
+                }
+
+                // The loop variable is only visible in the body, and the body
+                // may run any number of times, so checks made inside it do
+                // not survive the loop.
+                let names = locals.names.clone();
+                let non_null = locals.non_null.clone();
+                locals.names.remove(name.node);
+                *local = Some(locals.define(name.node, name.span, &mut self.diags));
+                locals.loops += 1;
+                self.resolve_block(body, locals);
+                locals.loops -= 1;
+                locals.names = names;
+                locals.non_null = non_null;
+                locals.diverges = false;
+            }
+            HirExpr::Struct { path, res, fields } => {
+                *res = match self.resolve_path(None, self.module, path) {
+                    Some(Binding::Item(ResolvedItem {
+                        id,
+                        kind: ResolvedItemKind::Struct | ResolvedItemKind::Union,
+                    })) => Some(Res::Def(id)),
+                    Some(Binding::Variant { def, index }) => Some(Res::Variant { def, index }),
+                    _ => {
+                        let name = format_path(path);
+                        self.diags.push(diagnostic(
+                            messages::UNRESOLVED_STRUCT,
+                            &[("name", &name)],
+                            path.span,
+                        ));
+                        None
+                    }
+                };
+                if let Some(segment) = path.segments.last_mut()
+                    && let Some(generics) = segment.generics.take()
+                {
+                    segment.generics = Some(self.resolve_generic_args(generics));
+                }
+                for field in fields.iter_mut() {
+                    self.resolve_expr(&mut field.value, locals);
+                }
             }
-            HirExpr::Block(block) => self.resolve_block(block, locals),
+            HirExpr::NonNullLocal(_) => {}
             HirExpr::Error | HirExpr::Literal(_) | HirExpr::Resolved(_) => {}
         }
     }
 
+    /// Collects the names of the locals `block` may write to, before it is
+    /// resolved: assignment targets and the places builtins such as
+    /// `core::mem::swap` write to, including those inside closures.
+    /// Shadowing is ignored, which only ever names more locals.
+    fn written_in_block(&mut self, block: &HirBlock<'src>, written: &mut HashSet<&'src str>) {
+        for stmt in block.stmts.iter() {
+            match stmt {
+                HirStmt::Expr(expr) | HirStmt::Throw { value: expr, .. } => {
+                    self.written_in_expr(expr, written)
+                }
+                HirStmt::Let { init, .. } => {
+                    if let Some(init) = init {
+                        self.written_in_expr(init, written);
+                    }
+                }
+                HirStmt::Assign { target, value, .. } => {
+                    written.extend(local_name(target));
+                    self.written_in_expr(target, written);
+                    self.written_in_expr(value, written);
+                }
+                HirStmt::Break { .. } => {}
+            }
+        }
+        if let Some(tail) = &block.tail {
+            self.written_in_expr(tail, written);
+        }
+    }
+
+    fn written_in_expr(&mut self, expr: &SpannedHirExpr<'src>, written: &mut HashSet<&'src str>) {
+        match &expr.node {
+            HirExpr::Call { callee, args } => {
+                if let HirExpr::Path(path) = &strip_turbofish(callee).node {
+                    // Only the builtin is of interest here; failures are
+                    // reported when the call itself is resolved.
+                    let diags = self.diags.len();
+                    let binding = self.resolve_path(None, self.module, path);
+                    self.diags.truncate(diags);
+                    if let Some(Binding::Builtin(id)) = binding
+                        && let Some(BuiltinLowering::Place(op)) =
+                            builtin_decl(id).map(|decl| decl.lowering)
+                    {
+                        written.extend(args.iter().take(op.places()).filter_map(local_name));
+                    }
+                }
+                self.written_in_expr(callee, written);
+                for arg in args.iter() {
+                    self.written_in_expr(arg, written);
+                }
+            }
+            HirExpr::Closure(closure) => self.written_in_expr(&closure.body, written),
+            HirExpr::Struct { fields, .. } => {
+                for field in fields.iter() {
+                    self.written_in_expr(&field.value, written);
+                }
+            }
+            HirExpr::Binary { lhs, rhs, .. }
+            | HirExpr::Index { base: lhs, index: rhs }
+            | HirExpr::Range { start: lhs, end: rhs, .. } => {
+                self.written_in_expr(lhs, written);
+                self.written_in_expr(rhs, written);
+            }
+            HirExpr::Tuple(items) => {
+                for item in items.iter() {
+                    self.written_in_expr(item, written);
+                }
+            }
+            HirExpr::Field { base: inner, .. }
+            | HirExpr::TupleField { base: inner, .. }
+            | HirExpr::Turbofish { callee: inner, .. }
+            | HirExpr::Try(inner)
+            | HirExpr::Deref(inner) => self.written_in_expr(inner, written),
+            HirExpr::Block(block) | HirExpr::Unsafe(block) => self.written_in_block(block, written),
+            HirExpr::If { cond, then, else_ } => {
+                self.written_in_expr(cond, written);
+                self.written_in_block(then, written);
+                if let Some(else_) = else_ {
+                    self.written_in_expr(else_, written);
+                }
+            }
+            HirExpr::For { iter, body, .. } => {
+                self.written_in_expr(iter, written);
+                self.written_in_block(body, written);
+            }
+            HirExpr::Error
+            | HirExpr::Literal(_)
+            | HirExpr::Path(_)
+            | HirExpr::Resolved(_)
+            | HirExpr::NonNullLocal(_) => {}
+        }
+    }
+
     fn resolve_ty(&mut self, ty: &mut SpannedHirType<'src>) {
         match &mut ty.node {
             HirType::Error => {}
             HirType::Unresolved(path) => {
-                if let Some(primitive) = primitive_from_path(path) {
+                if let Some(index) = self.generic_param(path, GenericKind::Type) {
+                    ty.node = HirType::Param(index);
+                } else if let Some(primitive) = primitive_from_path(path) {
                     ty.node = HirType::Primitive(primitive);
-                } else if !path.from_root && path.segments.len() == 1 {
-                    if let Some(item) = self.items.get(path.segments[0].name.node).copied() {
-                        match item.kind {
-                            ResolvedItemKind::Struct => {
-                                ty.node = HirType::Struct { def: item.id, generics: Box::new([]) };
-                            }
-                            ResolvedItemKind::Enum => {
-                                ty.node = HirType::Enum { def: item.id, generics: Box::new([]) };
-                            }
-                            ResolvedItemKind::Function => {
-                                let name = format_path(path);
-                                self.diags.push(diagnostic(
-                                    messages::UNRESOLVED_TYPE,
-                                    &[("name", &name)],
-                                    ty.span,
-                                ));
-                            }
+                } else {
+                    let item = match self.resolve_path(None, self.module, path) {
+                        Some(Binding::Item(item)) => Some(item),
+                        _ => None,
+                    };
+                    let generics = path
+                        .segments
+                        .last_mut()
+                        .and_then(|segment| segment.generics.take())
+                        .unwrap_or_default();
+                    match item.map(|item| (item.id, item.kind)) {
+                        Some((def, ResolvedItemKind::Struct)) => {
+                            let generics = self.resolve_generic_args(generics);
+                            ty.node = HirType::Struct { def, generics };
+                        }
+                        Some((def, ResolvedItemKind::Union)) => {
+                            let generics = self.resolve_generic_args(generics);
+                            ty.node = HirType::Union { def, generics };
+                        }
+                        Some((def, ResolvedItemKind::Enum)) => {
+                            let generics = self.resolve_generic_args(generics);
+                            ty.node = HirType::Enum { def, generics };
+                        }
+                        Some((def, ResolvedItemKind::Alias)) => {
+                            let generics = self.resolve_generic_args(generics);
+                            ty.node = self.expand_alias(def, generics, ty.span);
+                        }
+                        // Items of the crate shadow builtin types.
+                        None if let Some(builtin) = builtin_type_from_path(path) => {
+                            let generics = self.resolve_generic_args(generics);
+                            ty.node = HirType::Builtin { ty: builtin, generics };
+                        }
+                        Some((_, ResolvedItemKind::Function)) | None => {
+                            let name = format_path(path);
+                            self.diags.push(diagnostic(
+                                messages::UNRESOLVED_TYPE,
+                                &[("name", &name)],
+                                ty.span,
+                            ));
                         }
-                    } else {
-                        let name = format_path(path);
-                        self.diags.push(diagnostic(
-                            messages::UNRESOLVED_TYPE,
-                            &[("name", &name)],
-                            ty.span,
-                        ));
                     }
-                } else {
-                    let name = format_path(path);
-                    self.diags.push(diagnostic(
-                        messages::UNRESOLVED_TYPE,
-                        &[("name", &name)],
-                        ty.span,
-                    ));
                 }
             }
             HirType::Tuple(items) => {
@@ -221,14 +1020,26 @@ impl<'src> Resolver<'src> {
                     self.resolve_ty(item);
                 }
             }
+            HirType::Slice(inner)
+            | HirType::Nullable(inner)
+            | HirType::Pointer { pointee: inner, .. } => self.resolve_ty(inner),
             HirType::Array { elem, len } => {
                 self.resolve_ty(elem);
                 let mut empty = LocalScope::new();
                 self.resolve_expr(len, &mut empty);
             }
-            HirType::Primitive(_)
+            HirType::Function { params, ret } => {
+                for param in params.iter_mut() {
+                    self.resolve_ty(param);
+                }
+                self.resolve_ty(ret);
+            }
+            HirType::Builtin { .. }
+            | HirType::Primitive(_)
             | HirType::Struct { .. }
+            | HirType::Union { .. }
             | HirType::Enum { .. }
+            | HirType::Param(_)
             | HirType::Unit => {}
         }
     }
@@ -243,11 +1054,48 @@ impl<'src> Default for Resolver<'src> {
 struct LocalScope<'src> {
     names: HashMap<&'src str, HirLocalId>,
     next: usize,
+    /// Locals known to be non-null at the current point.
+    non_null: HashSet<HirLocalId>,
+    /// Set once the current branch has thrown, so its end is unreachable.
+    diverges: bool,
+    /// Closures whose body is being resolved, innermost last.
+    closures: Vec<ClosureFrame>,
+    /// `for` loops around the current point, inside the innermost closure.
+    loops: usize,
+    /// Locals assigned inside a closure that captured them.
+    shared: HashSet<HirLocalId>,
+}
+
+struct ClosureFrame {
+    /// Locals numbered from here on belong to the closure itself.
+    first_local: usize,
+    captures: Vec<HirLocalId>,
 }
 
 impl<'src> LocalScope<'src> {
     fn new() -> Self {
-        Self { names: HashMap::new(), next: 0 }
+        Self {
+            names: HashMap::new(),
+            next: 0,
+            non_null: HashSet::new(),
+            diverges: false,
+            closures: Vec::new(),
+            loops: 0,
+            shared: HashSet::new(),
+        }
+    }
+
+    /// Records a use of `local` as a capture of every enclosing closure it
+    /// was defined outside of.
+    fn capture(&mut self, local: HirLocalId) {
+        for frame in self.closures.iter_mut().rev() {
+            if local.to_usize() >= frame.first_local {
+                break;
+            }
+            if !frame.captures.contains(&local) {
+                frame.captures.push(local);
+            }
+        }
     }
 
     fn define(
@@ -258,12 +1106,31 @@ impl<'src> LocalScope<'src> {
     ) -> HirLocalId {
         let id = HirLocalId::from_usize(self.next);
         self.next += 1;
-        if self.names.insert(name, id).is_some() {
+        // Hidden locals, such as the one holding the value a tuple pattern
+        // destructures, start with `$` and are defined again by every use.
+        if self.names.insert(name, id).is_some() && !name.starts_with('$') {
             diags.push(diagnostic(messages::DUPLICATE_LOCAL, &[("name", name)], span));
         }
         id
     }
 
+    /// Records a write to `target`, an assignment target or a place a
+    /// builtin writes to.
+    fn write(&mut self, target: &mut SpannedHirExpr<'src>) {
+        // The written value may be `null` again.
+        if let HirExpr::NonNullLocal(local) = target.node {
+            target.node = HirExpr::Resolved(Res::Local(local));
+            self.non_null.remove(&local);
+        }
+        // A closure writing to a captured local must share it with the
+        // enclosing function instead of copying it.
+        if let HirExpr::Resolved(Res::Local(local)) = target.node
+            && self.closures.last().is_some_and(|c| local.to_usize() < c.first_local)
+        {
+            self.shared.insert(local);
+        }
+    }
+
     fn get_path(&self, path: &runec_hir::path::HirPath<'src>) -> Option<HirLocalId> {
         if path.from_root || path.segments.len() != 1 {
             return None;
@@ -273,9 +1140,209 @@ impl<'src> LocalScope<'src> {
     }
 }
 
-fn primitive_from_path(path: &runec_hir::path::HirPath<'_>) -> Option<HirPrimitiveTy> {
-    if path.from_root || path.segments.len() != 1 {
-        return None;
+/// Switches captures of locals that some closure assigns to to
+/// [`HirCaptureMode::ByRef`], so every closure sees the same variable.
+fn mark_shared_captures_in_block(block: &mut HirBlock<'_>, shared: &HashSet<HirLocalId>) {
+    for stmt in block.stmts.iter_mut() {
+        match stmt {
+            HirStmt::Expr(expr) | HirStmt::Throw { value: expr, .. } => {
+                mark_shared_captures(expr, shared)
+            }
+            HirStmt::Let { init, .. } => {
+                if let Some(init) = init {
+                    mark_shared_captures(init, shared);
+                }
+            }
+            HirStmt::Assign { target, value, .. } => {
+                mark_shared_captures(target, shared);
+                mark_shared_captures(value, shared);
+            }
+            HirStmt::Break { .. } => {}
+        }
+    }
+    if let Some(tail) = &mut block.tail {
+        mark_shared_captures(tail, shared);
+    }
+}
+
+fn mark_shared_captures(expr: &mut SpannedHirExpr<'_>, shared: &HashSet<HirLocalId>) {
+    match &mut expr.node {
+        HirExpr::Closure(closure) => {
+            for capture in closure.captures.iter_mut() {
+                if shared.contains(&capture.local) {
+                    capture.mode = HirCaptureMode::ByRef;
+                }
+            }
+            mark_shared_captures(&mut closure.body, shared);
+        }
+        HirExpr::Call { callee, args } => {
+            mark_shared_captures(callee, shared);
+            for arg in args.iter_mut() {
+                mark_shared_captures(arg, shared);
+            }
+        }
+        HirExpr::Struct { fields, .. } => {
+            for field in fields.iter_mut() {
+                mark_shared_captures(&mut field.value, shared);
+            }
+        }
+        HirExpr::Binary { lhs, rhs, .. } | HirExpr::Index { base: lhs, index: rhs } => {
+            mark_shared_captures(lhs, shared);
+            mark_shared_captures(rhs, shared);
+        }
+        HirExpr::Tuple(items) => {
+            for item in items.iter_mut() {
+                mark_shared_captures(item, shared);
+            }
+        }
+        HirExpr::Field { base: inner, .. }
+        | HirExpr::TupleField { base: inner, .. }
+        | HirExpr::Turbofish { callee: inner, .. }
+        | HirExpr::Try(inner)
+        | HirExpr::Deref(inner) => mark_shared_captures(inner, shared),
+        HirExpr::Block(block) | HirExpr::Unsafe(block) => {
+            mark_shared_captures_in_block(block, shared)
+        }
+        HirExpr::If { cond, then, else_ } => {
+            mark_shared_captures(cond, shared);
+            mark_shared_captures_in_block(then, shared);
+            if let Some(else_) = else_ {
+                mark_shared_captures(else_, shared);
+            }
+        }
+        HirExpr::Range { start, end, .. } => {
+            mark_shared_captures(start, shared);
+            mark_shared_captures(end, shared);
+        }
+        HirExpr::For { iter, body, .. } => {
+            mark_shared_captures(iter, shared);
+            mark_shared_captures_in_block(body, shared);
+        }
+        HirExpr::Error
+        | HirExpr::Literal(_)
+        | HirExpr::Path(_)
+        | HirExpr::Resolved(_)
+        | HirExpr::NonNullLocal(_) => {}
+    }
+}
+
+/// Matches `local != null` and `local == null` (in either operand order) and
+/// returns the checked local with the comparison operator.
+/// Returns the name of the local an unresolved place expression is, if it
+/// is a bare name.
+fn local_name<'src>(expr: &SpannedHirExpr<'src>) -> Option<&'src str> {
+    match &expr.node {
+        HirExpr::Path(path) if !path.from_root && path.segments.len() == 1 => {
+            Some(path.segments[0].name.node)
+        }
+        _ => None,
+    }
+}
+
+fn null_check(cond: &SpannedHirExpr<'_>) -> Option<(HirLocalId, BinaryOp)> {
+    let HirExpr::Binary { op: op @ (BinaryOp::Eq | BinaryOp::Ne), lhs, rhs } = &cond.node else {
+        return None;
+    };
+    let checked = match (&lhs.node, &rhs.node) {
+        (value, HirExpr::Literal(HirLiteral::Null))
+        | (HirExpr::Literal(HirLiteral::Null), value) => value,
+        _ => return None,
+    };
+    match checked {
+        HirExpr::Resolved(Res::Local(local)) | HirExpr::NonNullLocal(local) => Some((*local, *op)),
+        _ => None,
+    }
+}
+
+fn generic_kinds<'src>(generics: &[HirGenericParam<'src>]) -> Vec<(&'src str, GenericKind)> {
+    generics
+        .iter()
+        .map(|param| match param.kind {
+            HirGenericParamKind::Type { .. } => (param.name.node, GenericKind::Type),
+            HirGenericParamKind::Const { .. } => (param.name.node, GenericKind::Const),
+        })
+        .collect()
+}
+
+/// Replaces the generic parameters of an alias target with the arguments of
+/// a use of the alias, indexed like the alias parameter list.
+fn substitute_ty<'src>(ty: HirType<'src>, args: &[HirGenericArg<'src>]) -> HirType<'src> {
+    let substitute = |ty: SpannedHirType<'src>| ty.map(|ty| substitute_ty(ty, args));
+    match ty {
+        HirType::Param(index) => match args.get(index) {
+            Some(HirGenericArg::Type(arg)) => arg.node.clone(),
+            _ => HirType::Error,
+        },
+        HirType::Struct { def, generics } => {
+            HirType::Struct { def, generics: substitute_args(generics, args) }
+        }
+        HirType::Union { def, generics } => {
+            HirType::Union { def, generics: substitute_args(generics, args) }
+        }
+        HirType::Enum { def, generics } => {
+            HirType::Enum { def, generics: substitute_args(generics, args) }
+        }
+        HirType::Builtin { ty, generics } => {
+            HirType::Builtin { ty, generics: substitute_args(generics, args) }
+        }
+        HirType::Tuple(items) => HirType::Tuple(items.into_iter().map(substitute).collect()),
+        HirType::Array { elem, len } => HirType::Array {
+            elem: Box::new(substitute(*elem)),
+            len: Box::new(substitute_const(*len, args)),
+        },
+        HirType::Slice(elem) => HirType::Slice(Box::new(substitute(*elem))),
+        HirType::Nullable(inner) => HirType::Nullable(Box::new(substitute(*inner))),
+        HirType::Pointer { mutable, pointee } => {
+            HirType::Pointer { mutable, pointee: Box::new(substitute(*pointee)) }
+        }
+        HirType::Function { params, ret } => HirType::Function {
+            params: params.into_iter().map(substitute).collect(),
+            ret: Box::new(substitute(*ret)),
+        },
+        ty @ (HirType::Error | HirType::Unresolved(_) | HirType::Primitive(_) | HirType::Unit) => {
+            ty
+        }
+    }
+}
+
+fn substitute_args<'src>(
+    generics: Box<[HirGenericArg<'src>]>,
+    args: &[HirGenericArg<'src>],
+) -> Box<[HirGenericArg<'src>]> {
+    generics
+        .into_iter()
+        .map(|arg| match arg {
+            HirGenericArg::Type(ty) => HirGenericArg::Type(ty.map(|ty| substitute_ty(ty, args))),
+            HirGenericArg::Const(expr) => HirGenericArg::Const(substitute_const(expr, args)),
+        })
+        .collect()
+}
+
+fn substitute_const<'src>(
+    expr: SpannedHirExpr<'src>,
+    args: &[HirGenericArg<'src>],
+) -> SpannedHirExpr<'src> {
+    match &expr.node {
+        HirExpr::Resolved(Res::ConstParam(index)) => match args.get(*index) {
+            Some(HirGenericArg::Const(arg)) => Spanned::new(arg.node.clone(), expr.span),
+            _ => expr,
+        },
+        _ => expr,
+    }
+}
+
+/// Binds the builtin or builtin module at `path`.
+fn builtin_member(path: &str) -> Option<Binding> {
+    if let Some(builtin) = runec_builtins::builtin_from_path(path) {
+        Some(Binding::Builtin(builtin))
+    } else {
+        is_builtin_module(path).then(|| Binding::BuiltinModule(path.to_owned()))
+    }
+}
+
+fn primitive_from_path(path: &runec_hir::path::HirPath<'_>) -> Option<HirPrimitiveTy> {
+    if path.from_root || path.segments.len() != 1 {
+        return None;
     }
 
     match path.segments[0].name.node {
@@ -298,12 +1365,25 @@ fn primitive_from_path(path: &runec_hir::path::HirPath<'_>) -> Option<HirPrimiti
     }
 }
 
-fn builtin_from_path(path: &runec_hir::path::HirPath<'_>) -> Option<runec_builtins::BuiltinId> {
-    if path.from_root || path.segments.len() != 1 || path.segments[0].generics.is_some() {
+fn builtin_type_from_path(path: &HirPath<'_>) -> Option<runec_builtins::BuiltinTypeId> {
+    if path.from_root {
+        return None;
+    }
+
+    let name = path.segments.iter().map(|segment| segment.name.node).collect::<Vec<_>>();
+    builtin_type_from_name(&name.join("::"))
+}
+
+/// Looks the contract of a bound up; only its last segment may have
+/// generic arguments, as in `IntoIterator<T>`.
+fn contract_from_path(path: &HirPath<'_>) -> Option<runec_builtins::ContractId> {
+    let (_, parents) = path.segments.split_last()?;
+    if parents.iter().any(|segment| segment.generics.is_some()) {
         return None;
     }
 
-    builtin_from_name(path.segments[0].name.node)
+    let name = path.segments.iter().map(|segment| segment.name.node).collect::<Vec<_>>();
+    contract_from_name(&name.join("::"))
 }
 
 fn format_path(path: &runec_hir::path::HirPath<'_>) -> String {
@@ -332,12 +1412,22 @@ mod messages;
 #[cfg(test)]
 mod tests {
     use runec_ast::SpannedStr;
-    use runec_builtins::builtin_from_name;
-    use runec_hir::expression::{HirExpr, SpannedHirExpr};
-    use runec_hir::ids::{HirId, HirLocalId};
-    use runec_hir::item::{HirFunction, HirFunctionParam, HirItem};
+    use runec_ast::operators::BinaryOp;
+    use runec_ast::statement::Visibility;
+    use runec_builtins::DISPLAY_CONTRACT;
+    use runec_builtins::{DROP, builtin_from_name};
+    use runec_hir::expression::{
+        HirCapture, HirCaptureMode, HirClosure, HirClosureParam, HirExpr, HirLiteral,
+        SpannedHirExpr,
+    };
+    use runec_hir::ids::{HirId, HirLocalId, HirModuleId};
+    use runec_hir::item::{
+        HirAlias, HirBound, HirField, HirFunction, HirFunctionParam, HirGenericParam,
+        HirGenericParamKind, HirItem, HirUnion,
+    };
     use runec_hir::map::HirMap;
-    use runec_hir::path::{HirPath, HirPathSegment};
+    use runec_hir::module::{HirImport, HirUse, HirUseKind};
+    use runec_hir::path::{HirGenericArg, HirPath, HirPathSegment};
     use runec_hir::resolution::Res;
     use runec_hir::statement::{HirBlock, HirStmt};
     use runec_hir::ty::{HirPrimitiveTy, HirType};
@@ -373,14 +1463,17 @@ mod tests {
     fn resolves_params_lets_and_builtins() {
         let mut hir = HirMap::new();
         hir.push(HirItem::Function(HirFunction {
+            vis: Visibility::Private,
             id: HirId::from_usize(0),
             name: SpannedStr::new("main", sp(0, 0)),
+            generics: Box::new([]),
             params: Box::new([HirFunctionParam {
                 name: SpannedStr::new("x", sp(0, 0)),
                 ty: s(HirType::Primitive(HirPrimitiveTy::I32)),
                 span: sp(0, 0),
             }]),
             ret_ty: s(HirType::Unit),
+            throws: None,
             body: HirBlock {
                 stmts: Box::new([
                     HirStmt::Let {
@@ -427,15 +1520,372 @@ mod tests {
         assert_eq!(args[0].node, HirExpr::Resolved(Res::Local(HirLocalId::from_usize(1))));
     }
 
+    #[test]
+    fn narrows_nullable_local_inside_null_check() {
+        let println = |arg| {
+            HirStmt::Expr(s(HirExpr::Call {
+                callee: Box::new(path_expr("println")),
+                args: Box::new([arg]),
+            }))
+        };
+        let mut hir = HirMap::new();
+        hir.push(HirItem::Function(HirFunction {
+            vis: Visibility::Private,
+            id: HirId::from_usize(0),
+            name: SpannedStr::new("main", sp(0, 0)),
+            generics: Box::new([]),
+            params: Box::new([HirFunctionParam {
+                name: SpannedStr::new("name", sp(0, 0)),
+                ty: s(HirType::Nullable(Box::new(s(HirType::Primitive(HirPrimitiveTy::Str))))),
+                span: sp(0, 0),
+            }]),
+            ret_ty: s(HirType::Unit),
+            throws: None,
+            body: HirBlock {
+                stmts: Box::new([
+                    HirStmt::Expr(s(HirExpr::If {
+                        cond: Box::new(s(HirExpr::Binary {
+                            op: BinaryOp::Ne,
+                            lhs: Box::new(path_expr("name")),
+                            rhs: Box::new(s(HirExpr::Literal(HirLiteral::Null))),
+                        })),
+                        then: HirBlock {
+                            stmts: Box::new([println(path_expr("name"))]),
+                            tail: None,
+                            span: sp(0, 0),
+                        },
+                        else_: None,
+                    })),
+                    println(path_expr("name")),
+                ]),
+                tail: None,
+                span: sp(0, 0),
+            },
+            span: sp(0, 0),
+        }));
+
+        let result = Resolver::new().resolve(&mut hir);
+        assert!(result.diags.is_empty());
+
+        let HirItem::Function(function) = hir.get(HirId::from_usize(0)) else {
+            panic!("expected function");
+        };
+        let name = HirLocalId::from_usize(0);
+        fn println_arg<'a>(stmt: &'a HirStmt<'static>) -> &'a HirExpr<'static> {
+            let HirStmt::Expr(call) = stmt else {
+                panic!("expected call statement");
+            };
+            let HirExpr::Call { args, .. } = &call.node else {
+                panic!("expected call");
+            };
+            &args[0].node
+        }
+        let HirStmt::Expr(if_expr) = &function.body.stmts[0] else {
+            panic!("expected if");
+        };
+        let HirExpr::If { then, .. } = &if_expr.node else {
+            panic!("expected if");
+        };
+        assert_eq!(*println_arg(&then.stmts[0]), HirExpr::NonNullLocal(name));
+        assert_eq!(*println_arg(&function.body.stmts[1]), HirExpr::Resolved(Res::Local(name)));
+    }
+
+    #[test]
+    fn drops_narrowing_of_locals_that_a_closure_assigns() {
+        let println = |arg| {
+            HirStmt::Expr(s(HirExpr::Call {
+                callee: Box::new(path_expr("println")),
+                args: Box::new([arg]),
+            }))
+        };
+        let null = || s(HirExpr::Literal(HirLiteral::Null));
+        let if_not_null = |stmts: Vec<HirStmt<'static>>| {
+            HirStmt::Expr(s(HirExpr::If {
+                cond: Box::new(s(HirExpr::Binary {
+                    op: BinaryOp::Ne,
+                    lhs: Box::new(path_expr("n")),
+                    rhs: Box::new(null()),
+                })),
+                then: HirBlock { stmts: stmts.into_boxed_slice(), tail: None, span: sp(0, 0) },
+                else_: None,
+            }))
+        };
+        let clear = s(HirExpr::Closure(HirClosure {
+            params: Box::new([]),
+            body: Box::new(s(HirExpr::Block(HirBlock {
+                stmts: Box::new([HirStmt::Assign {
+                    target: path_expr("n"),
+                    value: null(),
+                    span: sp(0, 0),
+                }]),
+                tail: None,
+                span: sp(0, 0),
+            }))),
+            captures: Box::new([]),
+        }));
+        let call_c = HirStmt::Expr(s(HirExpr::Call {
+            callee: Box::new(path_expr("c")),
+            args: Box::new([]),
+        }));
+        let mut hir = HirMap::new();
+        hir.push(HirItem::Function(HirFunction {
+            vis: Visibility::Private,
+            id: HirId::from_usize(0),
+            name: SpannedStr::new("main", sp(0, 0)),
+            generics: Box::new([]),
+            params: Box::new([]),
+            ret_ty: s(HirType::Unit),
+            throws: None,
+            body: HirBlock {
+                stmts: Box::new([
+                    HirStmt::Let {
+                        local: None,
+                        name: SpannedStr::new("n", sp(0, 0)),
+                        is_mutable: true,
+                        ty: Some(s(HirType::Nullable(Box::new(s(HirType::Primitive(
+                            HirPrimitiveTy::Str,
+                        )))))),
+                        init: Some(s(HirExpr::Literal(HirLiteral::Str("x".into())))),
+                        span: sp(0, 0),
+                    },
+                    if_not_null(vec![
+                        HirStmt::Let {
+                            local: None,
+                            name: SpannedStr::new("c", sp(0, 0)),
+                            is_mutable: false,
+                            ty: None,
+                            init: Some(clear),
+                            span: sp(0, 0),
+                        },
+                        call_c.clone(),
+                        println(path_expr("n")),
+                    ]),
+                    if_not_null(vec![println(path_expr("n"))]),
+                ]),
+                tail: None,
+                span: sp(0, 0),
+            },
+            span: sp(0, 0),
+        }));
+
+        let result = Resolver::new().resolve(&mut hir);
+        assert!(result.diags.is_empty(), "{:?}", result.diags);
+
+        let HirItem::Function(function) = hir.get(HirId::from_usize(0)) else {
+            panic!("expected function");
+        };
+        let n = HirExpr::Resolved(Res::Local(HirLocalId::from_usize(0)));
+        let then_stmts = |index: usize| {
+            let HirStmt::Expr(if_expr) = &function.body.stmts[index] else {
+                panic!("expected if");
+            };
+            let HirExpr::If { then, .. } = &if_expr.node else { panic!("expected if") };
+            &then.stmts
+        };
+        let println_arg = |stmt: &HirStmt<'static>| {
+            let HirStmt::Expr(call) = stmt else { panic!("expected call statement") };
+            let HirExpr::Call { args, .. } = &call.node else { panic!("expected call") };
+            args[0].node.clone()
+        };
+        assert_eq!(println_arg(&then_stmts(1)[2]), n);
+        assert_eq!(println_arg(&then_stmts(2)[0]), n);
+    }
+
+    #[test]
+    fn drops_narrowing_of_locals_that_a_loop_body_assigns() {
+        let null = || s(HirExpr::Literal(HirLiteral::Null));
+        // `if x != null { for i in 0..2 { let y = x; x = null; } }`
+        let body = HirBlock {
+            stmts: Box::new([
+                HirStmt::Let {
+                    local: None,
+                    name: SpannedStr::new("y", sp(0, 0)),
+                    is_mutable: false,
+                    ty: None,
+                    init: Some(path_expr("x")),
+                    span: sp(0, 0),
+                },
+                HirStmt::Assign { target: path_expr("x"), value: null(), span: sp(0, 0) },
+            ]),
+            tail: None,
+            span: sp(0, 0),
+        };
+        let int = |value| s(HirExpr::Literal(HirLiteral::Int { value, suffix: None }));
+        let for_loop = s(HirExpr::For {
+            local: None,
+            name: SpannedStr::new("i", sp(0, 0)),
+            iter: Box::new(s(HirExpr::Range {
+                start: Box::new(int(0)),
+                end: Box::new(int(2)),
+                inclusive: false,
+            })),
+            body,
+        });
+        let mut hir = HirMap::new();
+        hir.push(HirItem::Function(HirFunction {
+            vis: Visibility::Private,
+            id: HirId::from_usize(0),
+            name: SpannedStr::new("main", sp(0, 0)),
+            generics: Box::new([]),
+            params: Box::new([HirFunctionParam {
+                name: SpannedStr::new("x", sp(0, 0)),
+                ty: s(HirType::Nullable(Box::new(s(HirType::Primitive(HirPrimitiveTy::Str))))),
+                span: sp(0, 0),
+            }]),
+            ret_ty: s(HirType::Unit),
+            throws: None,
+            body: HirBlock {
+                stmts: Box::new([HirStmt::Expr(s(HirExpr::If {
+                    cond: Box::new(s(HirExpr::Binary {
+                        op: BinaryOp::Ne,
+                        lhs: Box::new(path_expr("x")),
+                        rhs: Box::new(null()),
+                    })),
+                    then: HirBlock {
+                        stmts: Box::new([HirStmt::Expr(for_loop)]),
+                        tail: Some(Box::new(path_expr("x"))),
+                        span: sp(0, 0),
+                    },
+                    else_: None,
+                }))]),
+                tail: None,
+                span: sp(0, 0),
+            },
+            span: sp(0, 0),
+        }));
+
+        let result = Resolver::new().resolve(&mut hir);
+        assert!(result.diags.is_empty(), "{:?}", result.diags);
+
+        let HirItem::Function(function) = hir.get(HirId::from_usize(0)) else {
+            panic!("expected function");
+        };
+        let HirStmt::Expr(if_expr) = &function.body.stmts[0] else { panic!("expected if") };
+        let HirExpr::If { then, .. } = &if_expr.node else { panic!("expected if") };
+        let HirStmt::Expr(for_loop) = &then.stmts[0] else { panic!("expected for") };
+        let HirExpr::For { body, .. } = &for_loop.node else { panic!("expected for") };
+        let HirStmt::Let { init: Some(init), .. } = &body.stmts[0] else { panic!("expected let") };
+        // The second iteration reads the `null` the first one assigned, and
+        // so does the code after the loop.
+        let x = HirExpr::Resolved(Res::Local(HirLocalId::from_usize(0)));
+        assert_eq!(init.node, x);
+        assert_eq!(then.tail.as_ref().map(|tail| &tail.node), Some(&x));
+    }
+
+    #[test]
+    fn reports_break_outside_of_loops_and_inside_closures_in_loops() {
+        let brk = |lo, hi| HirStmt::Break { span: sp(lo, hi) };
+        let block = |stmts: Vec<HirStmt<'static>>| HirBlock {
+            stmts: stmts.into_boxed_slice(),
+            tail: None,
+            span: sp(0, 0),
+        };
+        let closure = s(HirExpr::Closure(HirClosure {
+            params: Box::new([]),
+            body: Box::new(s(HirExpr::Block(block(vec![brk(5, 11)])))),
+            captures: Box::new([]),
+        }));
+        // `break; for x in xs { break; || { break; }; }`
+        let for_loop = s(HirExpr::For {
+            local: None,
+            name: SpannedStr::new("x", sp(0, 0)),
+            iter: Box::new(s(HirExpr::Literal(HirLiteral::Str("xs".into())))),
+            body: block(vec![brk(3, 4), HirStmt::Expr(closure)]),
+        });
+        let mut hir = HirMap::new();
+        hir.push(HirItem::Function(HirFunction {
+            vis: Visibility::Private,
+            id: HirId::from_usize(0),
+            name: SpannedStr::new("main", sp(0, 0)),
+            generics: Box::new([]),
+            params: Box::new([]),
+            ret_ty: s(HirType::Unit),
+            throws: None,
+            body: block(vec![brk(1, 2), HirStmt::Expr(for_loop)]),
+            span: sp(0, 0),
+        }));
+
+        let result = Resolver::new().resolve(&mut hir);
+
+        let spans: Vec<_> = result
+            .diags
+            .iter()
+            .inspect(|diag| assert_eq!(diag.message.message, "`break` outside of a `for` loop"))
+            .map(|diag| diag.labels[0].span)
+            .collect();
+        assert_eq!(spans, [sp(1, 2), sp(5, 11)]);
+    }
+
+    #[test]
+    fn resolves_generic_params_bounds_and_const_params() {
+        let path = |name| match path_expr(name).node {
+            HirExpr::Path(path) => path,
+            _ => unreachable!(),
+        };
+        let generic = |name, kind| HirGenericParam {
+            name: SpannedStr::new(name, sp(0, 0)),
+            kind,
+            span: sp(0, 0),
+        };
+        let mut hir = HirMap::new();
+        hir.push(HirItem::Function(HirFunction {
+            vis: Visibility::Private,
+            id: HirId::from_usize(0),
+            name: SpannedStr::new("show", sp(0, 0)),
+            generics: Box::new([
+                generic(
+                    "T",
+                    HirGenericParamKind::Type {
+                        bounds: Box::new([HirBound { path: path("Display"), contract: None }]),
+                    },
+                ),
+                generic(
+                    "N",
+                    HirGenericParamKind::Const { ty: s(HirType::Unresolved(path("int"))) },
+                ),
+            ]),
+            params: Box::new([HirFunctionParam {
+                name: SpannedStr::new("x", sp(0, 0)),
+                ty: s(HirType::Unresolved(path("T"))),
+                span: sp(0, 0),
+            }]),
+            ret_ty: s(HirType::Unresolved(path("int"))),
+            throws: None,
+            body: HirBlock {
+                stmts: Box::new([]),
+                tail: Some(Box::new(path_expr("N"))),
+                span: sp(0, 0),
+            },
+            span: sp(0, 0),
+        }));
+
+        let result = Resolver::new().resolve(&mut hir);
+        assert!(result.diags.is_empty());
+
+        let HirItem::Function(function) = hir.get(HirId::from_usize(0)) else {
+            panic!("expected function");
+        };
+        let HirGenericParamKind::Type { bounds } = &function.generics[0].kind else {
+            panic!("expected type parameter");
+        };
+        assert_eq!(bounds[0].contract, Some(DISPLAY_CONTRACT));
+        assert_eq!(function.params[0].ty.node, HirType::Param(0));
+        let tail = function.body.tail.as_ref().expect("tail");
+        assert_eq!(tail.node, HirExpr::Resolved(Res::ConstParam(1)));
+    }
+
     #[test]
     fn reports_unresolved_name_as_diagnostic() {
         let unresolved_span = sp(10, 17);
         let mut hir = HirMap::new();
         hir.push(HirItem::Function(HirFunction {
+            vis: Visibility::Private,
             id: HirId::from_usize(0),
             name: SpannedStr::new("main", sp(0, 4)),
+            generics: Box::new([]),
             params: Box::new([]),
             ret_ty: s(HirType::Unit),
+            throws: None,
             body: HirBlock {
                 stmts: Box::new([HirStmt::Expr(Spanned::new(
                     HirExpr::Path(HirPath {
@@ -461,4 +1911,357 @@ mod tests {
         assert_eq!(result.diags[0].labels[0].span, unresolved_span);
         assert_eq!(result.diags[0].message.message, "cannot resolve value `missing`");
     }
+
+    fn path(from_root: bool, names: &[&'static str]) -> HirPath<'static> {
+        HirPath {
+            from_root,
+            segments: names
+                .iter()
+                .map(|name| HirPathSegment {
+                    name: SpannedStr::new(name, sp(0, 0)),
+                    generics: None,
+                    span: sp(0, 0),
+                })
+                .collect(),
+            span: sp(0, 0),
+        }
+    }
+
+    fn function(id: usize, name: &'static str, stmts: Box<[HirStmt<'static>]>) -> HirItem<'static> {
+        HirItem::Function(HirFunction {
+            vis: Visibility::Private,
+            id: HirId::from_usize(id),
+            name: SpannedStr::new(name, sp(0, 0)),
+            generics: Box::new([]),
+            params: Box::new([]),
+            ret_ty: s(HirType::Unit),
+            throws: None,
+            body: HirBlock { stmts, tail: None, span: sp(0, 0) },
+            span: sp(0, 0),
+        })
+    }
+
+    fn use_(names: &[&'static str], kind: HirUseKind<'static>) -> HirUse<'static> {
+        HirUse { path: path(false, names), kind, span: sp(0, 0) }
+    }
+
+    #[test]
+    fn resolves_paths_through_imports_globs_and_aliases() {
+        let call = |names: &[&'static str]| {
+            HirStmt::Expr(s(HirExpr::Call {
+                callee: Box::new(s(HirExpr::Path(path(false, names)))),
+                args: Box::new([]),
+            }))
+        };
+        let mut hir = HirMap::new();
+        let a = hir.add_module(HirModuleId::ROOT, "a");
+        let b = hir.add_module(a, "b");
+        let c = hir.add_module(HirModuleId::ROOT, "c");
+        hir.push_in(b, function(0, "helper", Box::new([])));
+        let mut other = function(1, "other", Box::new([]));
+        if let HirItem::Function(other) = &mut other {
+            other.vis = Visibility::Public;
+        }
+        hir.push_in(c, other);
+        hir.push(function(
+            2,
+            "main",
+            Box::new([
+                call(&["h"]),
+                call(&["b", "helper"]),
+                call(&["a", "b", "helper"]),
+                call(&["other"]),
+                call(&["drop"]),
+            ]),
+        ));
+
+        let root = hir.module_mut(HirModuleId::ROOT);
+        root.imports.push(HirImport { path: path(false, &["a", "b"]), span: sp(0, 0) });
+        root.uses.push(use_(
+            &["b", "helper"],
+            HirUseKind::Single { alias: Some(SpannedStr::new("h", sp(0, 0))) },
+        ));
+        root.uses.push(use_(&["c"], HirUseKind::Glob));
+        root.uses.push(use_(&["core", "mem", "drop"], HirUseKind::Single { alias: None }));
+
+        let result = Resolver::new().resolve(&mut hir);
+        assert!(result.diags.is_empty(), "{:?}", result.diags);
+
+        let HirItem::Function(main) = hir.get(HirId::from_usize(2)) else {
+            panic!("expected function");
+        };
+        let callees: Vec<_> = main
+            .body
+            .stmts
+            .iter()
+            .map(|stmt| match stmt {
+                HirStmt::Expr(Spanned { node: HirExpr::Call { callee, .. }, .. }) => &callee.node,
+                _ => panic!("expected call"),
+            })
+            .collect();
+        let helper = HirExpr::Resolved(Res::Def(HirId::from_usize(0)));
+        assert_eq!(callees[..3], [&helper, &helper, &helper]);
+        assert_eq!(callees[3], &HirExpr::Resolved(Res::Def(HirId::from_usize(1))));
+        assert_eq!(callees[4], &HirExpr::Resolved(Res::Builtin(DROP)));
+    }
+
+    #[test]
+    fn resolves_builtins_through_crate_modules_sharing_their_path() {
+        let call = |names: &[&'static str]| {
+            HirStmt::Expr(s(HirExpr::Call {
+                callee: Box::new(s(HirExpr::Path(path(false, names)))),
+                args: Box::new([]),
+            }))
+        };
+        let mut hir = HirMap::new();
+        let std = hir.add_module(HirModuleId::ROOT, "std");
+        let itertools = hir.add_module(std, "itertools");
+        hir.push_in(itertools, function(0, "helper", Box::new([])));
+        hir.module_mut(itertools)
+            .uses
+            .push(use_(&["std", "mem", "drop"], HirUseKind::Single { alias: None }));
+        hir.push(function(
+            1,
+            "main",
+            Box::new([call(&["std", "itertools", "helper"]), call(&["std", "mem", "drop"])]),
+        ));
+
+        let result = Resolver::new().resolve(&mut hir);
+        assert!(result.diags.is_empty(), "{:?}", result.diags);
+
+        let HirItem::Function(main) = hir.get(HirId::from_usize(1)) else {
+            panic!("expected function");
+        };
+        let callees: Vec<_> = main
+            .body
+            .stmts
+            .iter()
+            .map(|stmt| match stmt {
+                HirStmt::Expr(Spanned { node: HirExpr::Call { callee, .. }, .. }) => &callee.node,
+                _ => panic!("expected call"),
+            })
+            .collect();
+        assert_eq!(
+            callees,
+            [
+                &HirExpr::Resolved(Res::Def(HirId::from_usize(0))),
+                &HirExpr::Resolved(Res::Builtin(DROP)),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_use_cycles_and_unresolved_imports() {
+        let mut hir = HirMap::new();
+        let a = hir.add_module(HirModuleId::ROOT, "a");
+        let b = hir.add_module(HirModuleId::ROOT, "b");
+        hir.module_mut(a).uses.push(use_(&["b", "x"], HirUseKind::Single { alias: None }));
+        hir.module_mut(b).uses.push(use_(&["a", "x"], HirUseKind::Single { alias: None }));
+        hir.module_mut(HirModuleId::ROOT)
+            .uses
+            .push(use_(&["a", "missing"], HirUseKind::Single { alias: None }));
+
+        let result = Resolver::new().resolve(&mut hir);
+
+        let messages: Vec<_> =
+            result.diags.iter().map(|diag| diag.message.message.to_string()).collect();
+        assert_eq!(
+            messages,
+            ["cannot resolve import `a::missing`", "`use b::x` depends on itself"]
+        );
+    }
+
+    fn alias(
+        id: usize,
+        name: &'static str,
+        generics: &[&'static str],
+        ty: HirType<'static>,
+    ) -> HirItem<'static> {
+        HirItem::Alias(HirAlias {
+            id: HirId::from_usize(id),
+            vis: Visibility::Private,
+            name: SpannedStr::new(name, sp(0, 0)),
+            generics: generics
+                .iter()
+                .map(|name| HirGenericParam {
+                    name: SpannedStr::new(name, sp(0, 0)),
+                    kind: HirGenericParamKind::Type { bounds: Box::new([]) },
+                    span: sp(0, 0),
+                })
+                .collect(),
+            ty: s(ty),
+            span: sp(0, 0),
+        })
+    }
+
+    fn named_ty(
+        name: &'static str,
+        args: Option<Box<[HirGenericArg<'static>]>>,
+    ) -> HirType<'static> {
+        let mut path = path(false, &[name]);
+        path.segments[0].generics = args;
+        HirType::Unresolved(path)
+    }
+
+    fn with_params(mut item: HirItem<'static>, types: Vec<HirType<'static>>) -> HirItem<'static> {
+        if let HirItem::Function(function) = &mut item {
+            function.params = types
+                .into_iter()
+                .zip(["a", "b", "c"])
+                .map(|(ty, name)| HirFunctionParam {
+                    name: SpannedStr::new(name, sp(0, 0)),
+                    ty: s(ty),
+                    span: sp(0, 0),
+                })
+                .collect();
+        }
+        item
+    }
+
+    #[test]
+    fn expands_aliases_with_generic_substitution() {
+        let mut hir = HirMap::new();
+        let pair = HirType::Tuple(Box::new([s(named_ty("T", None)), s(named_ty("T", None))]));
+        hir.push(alias(0, "Pair", &["T"], pair));
+        hir.push(alias(1, "Num", &[], named_ty("i32", None)));
+        hir.push(HirItem::Union(HirUnion {
+            id: HirId::from_usize(2),
+            vis: Visibility::Private,
+            name: SpannedStr::new("Bits", sp(0, 0)),
+            generics: Box::new([]),
+            fields: Box::new([HirField {
+                vis: Visibility::Private,
+                name: SpannedStr::new("i", sp(0, 0)),
+                ty: s(named_ty("Num", None)),
+                span: sp(0, 0),
+            }]),
+            span: sp(0, 0),
+        }));
+        let num_pair =
+            named_ty("Pair", Some(Box::new([HirGenericArg::Type(s(named_ty("Num", None)))])));
+        hir.push(with_params(
+            function(3, "f", Box::new([])),
+            vec![num_pair, named_ty("Bits", None)],
+        ));
+
+        let result = Resolver::new().resolve(&mut hir);
+        assert!(result.diags.is_empty(), "{:?}", result.diags);
+
+        let i32_ty = || s(HirType::Primitive(HirPrimitiveTy::I32));
+        let HirItem::Function(function) = hir.get(HirId::from_usize(3)) else {
+            panic!("expected function");
+        };
+        assert_eq!(function.params[0].ty.node, HirType::Tuple(Box::new([i32_ty(), i32_ty()])));
+        assert_eq!(
+            function.params[1].ty.node,
+            HirType::Union { def: HirId::from_usize(2), generics: Box::new([]) }
+        );
+        let HirItem::Alias(pair) = hir.get(HirId::from_usize(0)) else {
+            panic!("expected alias");
+        };
+        assert_eq!(
+            pair.ty.node,
+            HirType::Tuple(Box::new([s(HirType::Param(0)), s(HirType::Param(0))]))
+        );
+    }
+
+    #[test]
+    fn reports_alias_cycles_and_argument_counts() {
+        let mut hir = HirMap::new();
+        hir.push(alias(0, "A", &[], named_ty("B", None)));
+        hir.push(alias(1, "B", &[], named_ty("A", None)));
+        hir.push(alias(2, "Pair", &["T"], named_ty("T", None)));
+        hir.push(with_params(function(3, "f", Box::new([])), vec![named_ty("Pair", None)]));
+
+        let result = Resolver::new().resolve(&mut hir);
+
+        let messages: Vec<_> =
+            result.diags.iter().map(|diag| diag.message.message.to_string()).collect();
+        assert_eq!(
+            messages,
+            ["alias `A` refers to itself", "alias `Pair` expects 1 generic arguments, found 0"]
+        );
+    }
+
+    #[test]
+    fn captures_outer_locals_by_value_unless_a_closure_assigns_them() {
+        let closure = |params: &[&'static str], body| {
+            s(HirExpr::Closure(HirClosure {
+                params: params
+                    .iter()
+                    .map(|name| HirClosureParam {
+                        local: None,
+                        name: SpannedStr::new(name, sp(0, 0)),
+                        ty: None,
+                    })
+                    .collect(),
+                body: Box::new(body),
+                captures: Box::new([]),
+            }))
+        };
+        let let_stmt = |name, init| HirStmt::Let {
+            local: None,
+            name: SpannedStr::new(name, sp(0, 0)),
+            is_mutable: true,
+            ty: None,
+            init: Some(init),
+            span: sp(0, 0),
+        };
+        let block = |stmts: Vec<HirStmt<'static>>| {
+            s(HirExpr::Block(HirBlock {
+                stmts: stmts.into_boxed_slice(),
+                tail: None,
+                span: sp(0, 0),
+            }))
+        };
+        let assign_n =
+            HirStmt::Assign { target: path_expr("n"), value: path_expr("step"), span: sp(0, 0) };
+        let inner = closure(&[], path_expr("get"));
+        let mut hir = HirMap::new();
+        hir.push(HirItem::Function(HirFunction {
+            vis: Visibility::Private,
+            id: HirId::from_usize(0),
+            name: SpannedStr::new("main", sp(0, 0)),
+            generics: Box::new([]),
+            params: Box::new([]),
+            ret_ty: s(HirType::Unit),
+            throws: None,
+            body: HirBlock {
+                stmts: Box::new([
+                    let_stmt("n", s(HirExpr::Literal(HirLiteral::Int { value: 0, suffix: None }))),
+                    let_stmt("get", closure(&[], path_expr("n"))),
+                    let_stmt("inc", closure(&["step"], block(vec![assign_n]))),
+                    let_stmt("outer", closure(&[], block(vec![let_stmt("c", inner)]))),
+                ]),
+                tail: None,
+                span: sp(0, 0),
+            },
+            span: sp(0, 0),
+        }));
+
+        let result = Resolver::new().resolve(&mut hir);
+        assert!(result.diags.is_empty(), "{:?}", result.diags);
+
+        let HirItem::Function(function) = hir.get(HirId::from_usize(0)) else {
+            panic!("expected function");
+        };
+        let closure_at = |index: usize| {
+            let HirStmt::Let { init: Some(init), .. } = &function.body.stmts[index] else {
+                panic!("expected let");
+            };
+            let HirExpr::Closure(closure) = &init.node else { panic!("expected closure") };
+            closure
+        };
+        let capture = |local, mode| HirCapture { local: HirLocalId::from_usize(local), mode };
+        assert_eq!(*closure_at(1).captures, [capture(0, HirCaptureMode::ByRef)]);
+        let inc = closure_at(2);
+        assert_eq!(inc.params[0].local, Some(HirLocalId::from_usize(2)));
+        assert_eq!(*inc.captures, [capture(0, HirCaptureMode::ByRef)]);
+        let outer = closure_at(3);
+        assert_eq!(*outer.captures, [capture(1, HirCaptureMode::ByValue)]);
+        let HirExpr::Block(body) = &outer.body.node else { panic!("expected block") };
+        let HirStmt::Let { init: Some(init), .. } = &body.stmts[0] else { panic!("expected let") };
+        let HirExpr::Closure(inner) = &init.node else { panic!("expected closure") };
+        assert_eq!(*inner.captures, [capture(1, HirCaptureMode::ByValue)]);
+    }
 }
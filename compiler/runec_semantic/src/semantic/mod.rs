@@ -1,6 +1,7 @@
-use runec_errors::diagnostics::Diagnostic;
+use runec_errors::diagnostics::{DiagType, Diagnostic};
 use runec_hir::map::HirMap;
 
+use crate::privacy::PrivacyChecker;
 use crate::resolving::Resolver;
 use crate::typeck::{TypeCheckResult, TypeChecker, TypeInfo};
 
@@ -10,8 +11,10 @@ pub struct SemanticResult<'src> {
 }
 
 impl<'src> SemanticResult<'src> {
+    /// Whether any diagnostic is an error; lint warnings do not stop
+    /// compilation.
     pub fn has_errors(&self) -> bool {
-        !self.diags.is_empty()
+        self.diags.iter().any(|diag| diag.diag_type == DiagType::Error)
     }
 }
 
@@ -25,7 +28,9 @@ impl SemanticChecker {
     pub fn check<'src>(&self, hir: &mut HirMap<'src>) -> SemanticResult<'src> {
         let resolve = Resolver::new().resolve(hir);
         let TypeCheckResult { info, diags: mut type_diags } = TypeChecker::new().check(hir);
+        let mut privacy = PrivacyChecker::new(hir, &info).check();
         let mut diags = resolve.diags;
+        diags.append(&mut privacy.diags);
         diags.append(&mut type_diags);
 
         SemanticResult { info, diags }
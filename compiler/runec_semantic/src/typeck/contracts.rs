@@ -0,0 +1,160 @@
+use runec_ast::operators::BinaryOp;
+use runec_builtins::{
+    ADD_CONTRACT, ContractId, DEFAULT_CONTRACT, DERIVED_CONTRACTS, DERIVED_JSON_CONTRACTS,
+    DISPLAY_CONTRACT, FROM_JSON_CONTRACT, INTO_ITERATOR_CONTRACT, MUL_CONTRACT, ORD_CONTRACT,
+    PrimitiveType, SUB_CONTRACT, TO_JSON_CONTRACT, TypeBits, TypeConstraint, VEC,
+    builtin_type_implements, primitive_implements,
+};
+use runec_hir::ids::HirId;
+use runec_source::span::Span;
+
+use super::{GenericParamInfo, Ty, TypeChecker, is_comparison, messages};
+
+impl<'src> TypeChecker<'src> {
+    pub(super) fn check_constraint(&mut self, span: Span, constraint: TypeConstraint, actual: Ty) {
+        let contract_id = match constraint {
+            // Exact types are checked against the inferred signature.
+            TypeConstraint::Any | TypeConstraint::Exactly(_) | TypeConstraint::OneOf(_) => return,
+            TypeConstraint::Implements(contract_id) => contract_id,
+        };
+        if actual == Ty::Unknown || self.implements(&actual, contract_id) {
+            return;
+        }
+        if let Ty::Nullable(inner) = &actual
+            && self.implements(inner, contract_id)
+        {
+            self.push_nullable_diag(span, inner, &actual);
+            return;
+        }
+
+        let contract = contract_id.to_string();
+        let actual = self.ty_name(&actual);
+        self.push_diag(
+            messages::CONTRACT_NOT_IMPLEMENTED,
+            &[("actual", &actual), ("contract", &contract)],
+            span,
+        );
+    }
+
+    /// Returns `true` if `ty` implements the contract, either directly or,
+    /// for a type parameter, through a bound of the item in scope.
+    pub(super) fn implements(&self, ty: &Ty, contract_id: ContractId) -> bool {
+        self.implements_inner(ty, contract_id, &mut Vec::new())
+    }
+
+    /// Structs, enums and tuples derive [`DERIVED_CONTRACTS`] from their
+    /// fields, and structs and enums [`DERIVED_JSON_CONTRACTS`]; nullables, vectors
+    /// and slices convert to JSON when their elements do. `outer` holds the
+    /// definitions being checked, which a recursive field is assumed to
+    /// implement.
+    fn implements_inner(&self, ty: &Ty, contract_id: ContractId, outer: &mut Vec<HirId>) -> bool {
+        let derived = DERIVED_CONTRACTS.contains(&contract_id);
+        let json_derived = DERIVED_JSON_CONTRACTS.contains(&contract_id);
+        let (def, fields) = match ty {
+            Ty::Param(index) => {
+                let generics = self.scope.map(|item| self.info.generics(item)).unwrap_or_default();
+                return matches!(
+                    generics.get(*index),
+                    Some(GenericParamInfo::Type { bounds, .. })
+                        if bounds.iter().any(|bound| bound.contract == contract_id)
+                );
+            }
+            Ty::Nullable(inner) if json_derived => {
+                return self.implements_inner(inner, contract_id, outer);
+            }
+            Ty::Tuple(items) if derived => {
+                return items.iter().all(|item| self.implements_inner(item, contract_id, outer));
+            }
+            Ty::BuiltinType { id: VEC, args } if json_derived => {
+                return args
+                    .first()
+                    .is_some_and(|elem| self.implements_inner(elem, contract_id, outer));
+            }
+            // Documents cannot be read back into borrowed elements.
+            Ty::Slice(elem) if contract_id == TO_JSON_CONTRACT => {
+                return self.implements_inner(elem, contract_id, outer);
+            }
+            Ty::Struct { def, args } if derived || json_derived => {
+                let Some(adt) = self.info.adt(*def) else {
+                    return false;
+                };
+                (*def, adt.fields.iter().map(|field| field.ty.substitute(args)).collect())
+            }
+            Ty::Enum(def) if derived || json_derived => (
+                *def,
+                self.info
+                    .variants(*def)
+                    .iter()
+                    .flat_map(|variant| variant.fields.iter().map(|field| field.ty.clone()))
+                    .collect::<Vec<_>>(),
+            ),
+            _ => return ty_implements(ty, contract_id),
+        };
+        if outer.contains(&def) {
+            return true;
+        }
+        outer.push(def);
+        let implements =
+            fields.iter().all(|field| self.implements_inner(field, contract_id, outer));
+        outer.pop();
+        implements
+    }
+
+    /// Returns the generic arguments `ty` implements contract `contract_id`
+    /// with, such as the item type of an `IntoIterator`.
+    pub(super) fn contract_args(&self, ty: &Ty, contract_id: ContractId) -> Option<Box<[Ty]>> {
+        match contract_id {
+            INTO_ITERATOR_CONTRACT => Some(Box::new([self.iterable_item(ty)?])),
+            _ => None,
+        }
+    }
+
+    /// Returns the type of the items a `for` loop over a value of type `ty`
+    /// yields: the elements of a vector or slice, the items of an iterator,
+    /// or the argument of the `IntoIterator` bound of a type parameter.
+    pub(super) fn iterable_item(&self, ty: &Ty) -> Option<Ty> {
+        let Ty::Param(index) = ty else {
+            return ty.elem().cloned().or_else(|| ty.iterator_item());
+        };
+        let generics = self.scope.map(|item| self.info.generics(item)).unwrap_or_default();
+        let Some(GenericParamInfo::Type { bounds, .. }) = generics.get(*index) else {
+            return None;
+        };
+        let bound = bounds.iter().find(|bound| bound.contract == INTO_ITERATOR_CONTRACT)?;
+        bound.args.first().cloned()
+    }
+}
+
+pub(super) fn ty_implements(ty: &Ty, contract_id: ContractId) -> bool {
+    let primitive = match ty {
+        // The runtime formats and converts integers of at most 64 bits.
+        Ty::Int { bits: TypeBits::B128, .. }
+            if [DISPLAY_CONTRACT, TO_JSON_CONTRACT, FROM_JSON_CONTRACT].contains(&contract_id) =>
+        {
+            return false;
+        }
+        Ty::Bool => PrimitiveType::Bool,
+        Ty::Char => PrimitiveType::Char,
+        Ty::Int { .. } => PrimitiveType::Int,
+        Ty::Float { .. } => PrimitiveType::Float,
+        Ty::Str => PrimitiveType::Str,
+        Ty::BuiltinType { id, .. } => return builtin_type_implements(*id, contract_id),
+        // Every nullable type defaults to `null`.
+        Ty::Nullable(_) => return contract_id == DEFAULT_CONTRACT,
+        Ty::Slice(_) => return contract_id == INTO_ITERATOR_CONTRACT,
+        _ => return false,
+    };
+
+    primitive_implements(primitive, contract_id)
+}
+
+/// Returns the contract whose implementations overload `op`.
+pub(super) fn operator_contract(op: BinaryOp) -> Option<ContractId> {
+    match op {
+        BinaryOp::Add => Some(ADD_CONTRACT),
+        BinaryOp::Sub => Some(SUB_CONTRACT),
+        BinaryOp::Mul => Some(MUL_CONTRACT),
+        _ if is_comparison(op) => Some(ORD_CONTRACT),
+        _ => None,
+    }
+}
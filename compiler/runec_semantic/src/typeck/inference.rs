@@ -0,0 +1,257 @@
+use runec_builtins::{BuiltinDecl, TypeConstraint};
+use runec_hir::expression::{HirExpr, HirLiteral};
+use runec_hir::ids::HirId;
+use runec_hir::path::HirGenericArg;
+use runec_hir::resolution::Res;
+use runec_source::span::Span;
+
+use super::{FunctionSig, GenericArg, GenericParamInfo, Ty, TypeChecker, messages};
+
+impl<'src> TypeChecker<'src> {
+    /// Infers the generic arguments of a call to `callee` from the argument
+    /// types and checks them against the parameter bounds.
+    pub(super) fn infer_generic_args(
+        &mut self,
+        callee: HirId,
+        sig: &FunctionSig,
+        actuals: &[Ty],
+        span: Span,
+    ) -> Box<[GenericArg]> {
+        let generics = self.info.generics(callee).to_vec();
+        let inferred: Box<[GenericArg]> = generics
+            .iter()
+            .zip(self.infer_from_args(callee, sig, actuals))
+            .map(|(param, arg)| {
+                arg.unwrap_or_else(|| {
+                    self.push_diag(messages::CANNOT_INFER_GENERIC, &[("name", param.name())], span);
+                    GenericArg::Type(Ty::Unknown)
+                })
+            })
+            .collect();
+
+        for (param, arg) in generics.iter().zip(inferred.iter()) {
+            let (GenericParamInfo::Type { bounds, .. }, GenericArg::Type(ty)) = (param, arg) else {
+                continue;
+            };
+            for bound in bounds.iter() {
+                self.check_constraint(span, TypeConstraint::Implements(bound.contract), ty.clone());
+                let Some(actual) = self.contract_args(ty, bound.contract) else {
+                    continue;
+                };
+                for (expected, actual) in bound.args.iter().zip(actual) {
+                    self.expect_assignable(span, expected.substitute(&inferred), actual);
+                }
+            }
+        }
+        inferred
+    }
+
+    /// Infers what it can of the generic arguments of a call to `callee`
+    /// from the types of its arguments, `Unknown` for those not checked
+    /// yet. The contract arguments of a bound follow from the type it
+    /// bounds, as `U` from `T` in `T: IntoIterator<U>`.
+    pub(super) fn infer_from_args(
+        &self,
+        callee: HirId,
+        sig: &FunctionSig,
+        actuals: &[Ty],
+    ) -> Vec<Option<GenericArg>> {
+        let generics = self.info.generics(callee);
+        let mut inferred = vec![None; generics.len()];
+        for (expected, actual) in sig.params.iter().zip(actuals) {
+            // Function items are passed as `act(...)` values.
+            let actual = match actual {
+                Ty::Function(id) => self.info.fn_ptr_ty(*id).unwrap_or(Ty::Unknown),
+                actual => actual.clone(),
+            };
+            infer_generic_args(expected, &actual, &mut inferred);
+        }
+
+        for (param, arg) in generics.iter().zip(inferred.clone()) {
+            let (GenericParamInfo::Type { bounds, .. }, Some(GenericArg::Type(ty))) = (param, arg)
+            else {
+                continue;
+            };
+            for bound in bounds.iter() {
+                let actual = self.contract_args(&ty, bound.contract).unwrap_or_default();
+                for (expected, actual) in bound.args.iter().zip(actual) {
+                    infer_generic_args(expected, &actual, &mut inferred);
+                }
+            }
+        }
+        inferred
+    }
+
+    /// Checks the generic arguments of a use of struct, union or function
+    /// `def` against its parameters. Returns `None` when they do not match.
+    pub(super) fn lower_generic_args(
+        &mut self,
+        def: HirId,
+        args: &[HirGenericArg<'src>],
+        span: Span,
+    ) -> Option<Box<[GenericArg]>> {
+        let generics = self.info.generics(def).to_vec();
+        if !self.check_generic_arg_count(span, generics.len(), args.len()) {
+            return None;
+        }
+
+        let mut lowered = Vec::with_capacity(args.len());
+        for (param, arg) in generics.iter().zip(args) {
+            lowered.push(match (param, arg) {
+                (GenericParamInfo::Type { bounds, .. }, HirGenericArg::Type(ty)) => {
+                    let lowered = self.lower_ty(ty);
+                    for bound in bounds.iter() {
+                        self.check_constraint(
+                            ty.span,
+                            TypeConstraint::Implements(bound.contract),
+                            lowered.clone(),
+                        );
+                    }
+                    GenericArg::Type(lowered)
+                }
+                (GenericParamInfo::Const { .. }, HirGenericArg::Const(expr)) => match &expr.node {
+                    HirExpr::Literal(HirLiteral::Int { value, .. }) => GenericArg::Const(*value),
+                    HirExpr::Resolved(Res::ConstParam(index)) => GenericArg::ConstParam(*index),
+                    _ => {
+                        self.push_diag(messages::NON_LITERAL_CONST_ARGUMENT, &[], expr.span);
+                        return None;
+                    }
+                },
+                (GenericParamInfo::Type { name, .. }, HirGenericArg::Const(expr)) => {
+                    self.push_diag(messages::EXPECTED_TYPE_ARGUMENT, &[("name", name)], expr.span);
+                    return None;
+                }
+                (GenericParamInfo::Const { name, .. }, HirGenericArg::Type(ty)) => {
+                    self.push_diag(messages::EXPECTED_CONST_ARGUMENT, &[("name", name)], ty.span);
+                    return None;
+                }
+            });
+        }
+        Some(lowered.into_boxed_slice())
+    }
+
+    /// Lowers the explicit generic arguments of a call to builtin `decl`,
+    /// whose generic parameters are all types. Their bounds are checked with
+    /// those of inferred arguments.
+    pub(super) fn lower_builtin_generic_args(
+        &mut self,
+        decl: &BuiltinDecl,
+        args: &[HirGenericArg<'src>],
+        span: Span,
+    ) -> Vec<Option<GenericArg>> {
+        if !self.check_generic_arg_count(span, decl.generics.len(), args.len()) {
+            return vec![Some(GenericArg::Type(Ty::Unknown)); decl.generics.len()];
+        }
+        decl.generics
+            .iter()
+            .zip(args)
+            .map(|(name, arg)| match arg {
+                HirGenericArg::Type(ty) => Some(GenericArg::Type(self.lower_ty(ty))),
+                HirGenericArg::Const(expr) => {
+                    self.push_diag(messages::EXPECTED_TYPE_ARGUMENT, &[("name", name)], expr.span);
+                    Some(GenericArg::Type(Ty::Unknown))
+                }
+            })
+            .collect()
+    }
+
+    pub(super) fn check_generic_arg_count(
+        &mut self,
+        span: Span,
+        expected: usize,
+        actual: usize,
+    ) -> bool {
+        if expected == actual {
+            return true;
+        }
+        let expected = expected.to_string();
+        let actual = actual.to_string();
+        self.push_diag(
+            messages::GENERIC_ARGUMENT_COUNT_MISMATCH,
+            &[("expected", &expected), ("actual", &actual)],
+            span,
+        );
+        false
+    }
+}
+
+/// Whether `actual` is built by the same type constructor as `expected`,
+/// whatever their type arguments.
+pub(super) fn same_shape(expected: &Ty, actual: &Ty) -> bool {
+    match (expected, actual) {
+        (Ty::BuiltinType { id: expected, .. }, Ty::BuiltinType { id: actual, .. }) => {
+            expected == actual
+        }
+        _ => std::mem::discriminant(expected) == std::mem::discriminant(actual),
+    }
+}
+
+/// Matches the parameter type `expected` against the argument type `actual`
+/// and records the generic arguments it determines. The first match wins;
+/// conflicting later ones are reported as type mismatches.
+pub(super) fn infer_generic_args(expected: &Ty, actual: &Ty, inferred: &mut [Option<GenericArg>]) {
+    match (expected, actual) {
+        (_, Ty::Unknown | Ty::Null) => {}
+        (Ty::Param(index), _) => {
+            if let Some(slot @ None) = inferred.get_mut(*index) {
+                *slot = Some(GenericArg::Type(actual.clone()));
+            }
+        }
+        (Ty::Nullable(expected), actual) => {
+            infer_generic_args(expected, &actual.clone().non_null(), inferred)
+        }
+        (Ty::Tuple(expected), Ty::Tuple(actual)) => {
+            for (expected, actual) in expected.iter().zip(actual.iter()) {
+                infer_generic_args(expected, actual, inferred);
+            }
+        }
+        (Ty::Array { elem: expected, .. }, Ty::Array { elem: actual, .. })
+        | (Ty::Slice(expected), Ty::Slice(actual))
+        | (Ty::Pointer { pointee: expected, .. }, Ty::Pointer { pointee: actual, .. }) => {
+            infer_generic_args(expected, actual, inferred)
+        }
+        (
+            Ty::FnPtr { params: expected_params, ret: expected_ret },
+            Ty::FnPtr { params: actual_params, ret: actual_ret },
+        ) => {
+            for (expected, actual) in expected_params.iter().zip(actual_params.iter()) {
+                infer_generic_args(expected, actual, inferred);
+            }
+            infer_generic_args(expected_ret, actual_ret, inferred);
+        }
+        (
+            Ty::Struct { def: expected_def, args: expected },
+            Ty::Struct { def: actual_def, args: actual },
+        )
+        | (
+            Ty::Union { def: expected_def, args: expected },
+            Ty::Union { def: actual_def, args: actual },
+        ) if expected_def == actual_def => {
+            for (expected, actual) in expected.iter().zip(actual.iter()) {
+                match (expected, actual) {
+                    (GenericArg::Type(expected), GenericArg::Type(actual)) => {
+                        infer_generic_args(expected, actual, inferred)
+                    }
+                    (
+                        GenericArg::ConstParam(index),
+                        GenericArg::Const(_) | GenericArg::ConstParam(_),
+                    ) => {
+                        if let Some(slot @ None) = inferred.get_mut(*index) {
+                            *slot = Some(actual.clone());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        (
+            Ty::BuiltinType { id: expected_id, args: expected },
+            Ty::BuiltinType { id: actual_id, args: actual },
+        ) if expected_id == actual_id => {
+            for (expected, actual) in expected.iter().zip(actual.iter()) {
+                infer_generic_args(expected, actual, inferred);
+            }
+        }
+        _ => {}
+    }
+}
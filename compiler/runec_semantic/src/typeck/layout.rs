@@ -0,0 +1,169 @@
+use runec_builtins::{
+    DECIMAL, DURATION, HASH_MAP, HASH_SET, INSTANT, JSON, LINES, RANGE, READER, ROUNDING_MODE,
+    TypeBits, VEC, WRITER,
+};
+use runec_hir::ids::HirId;
+
+use super::{AdtKind, GenericArg, Ty, TypeInfo};
+
+/// Size and alignment of a type in bytes, for a 64-bit target.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Layout {
+    pub size: u64,
+    pub align: u64,
+}
+
+impl Layout {
+    pub const fn new(size: u64, align: u64) -> Self {
+        Self { size, align }
+    }
+
+    const fn scalar(size: u64) -> Self {
+        Self { size, align: size }
+    }
+
+    /// Appends a field of layout `field` after the current end and returns
+    /// its offset.
+    fn push_field(&mut self, field: Layout) -> u64 {
+        let offset = self.size.next_multiple_of(field.align);
+        self.size = offset + field.size;
+        self.align = self.align.max(field.align);
+        offset
+    }
+
+    /// Rounds the size up so that arrays of the type keep every element
+    /// aligned.
+    fn pad_to_align(self) -> Self {
+        Self { size: self.size.next_multiple_of(self.align), align: self.align }
+    }
+}
+
+impl<'src> TypeInfo<'src> {
+    /// Returns the layout of `ty`, or `None` for types without a known one:
+    /// generic parameters, enums, function items, arrays of unknown length
+    /// and types that contain themselves.
+    pub fn layout(&self, ty: &Ty) -> Option<Layout> {
+        self.layout_inner(ty, &mut Vec::new())
+    }
+
+    /// `outer` holds the structs and unions whose layout is being computed.
+    fn layout_inner(&self, ty: &Ty, outer: &mut Vec<HirId>) -> Option<Layout> {
+        let layout = match ty {
+            Ty::Unit | Ty::Never => Layout::new(0, 1),
+            Ty::Bool => Layout::scalar(1),
+            Ty::Char => Layout::scalar(4),
+            Ty::Int { bits, .. } | Ty::Float { bits } => Layout::scalar(bits_size(*bits)),
+            // Nanosecond counts and rounding mode codes.
+            Ty::BuiltinType { id: INSTANT | DURATION | ROUNDING_MODE, .. } => Layout::scalar(8),
+            // Function values point at a closure environment and vectors,
+            // maps, sets, JSON documents, decimals, readers, writers and line
+            // iterators at their managed payload.
+            Ty::Pointer { .. }
+            | Ty::FnPtr { .. }
+            | Ty::BuiltinType {
+                id: VEC | HASH_MAP | HASH_SET | JSON | DECIMAL | READER | WRITER | LINES,
+                ..
+            } => Layout::scalar(8),
+            // Pointer and length.
+            Ty::Str => Layout::new(16, 8),
+            // Vector, index of the first element and length.
+            Ty::Slice(_) => Layout::new(24, 8),
+            Ty::Tuple(items) => self.sequential(items.iter(), outer)?,
+            Ty::Array { elem, len: Some(len) } => {
+                let elem = self.layout_inner(elem, outer)?;
+                Layout::new(elem.size.checked_mul(*len)?, elem.align)
+            }
+            Ty::Struct { def, args } | Ty::Union { def, args } => {
+                if outer.contains(def) {
+                    return None;
+                }
+                outer.push(*def);
+                let layout = self.adt_layout(*def, args, outer);
+                outer.pop();
+                layout?
+            }
+            // A null string or managed builtin value is a null pointer;
+            // other types get a leading tag byte.
+            Ty::Nullable(inner)
+                if matches!(
+                    **inner,
+                    Ty::Str
+                        | Ty::BuiltinType {
+                            id: VEC
+                                | HASH_MAP
+                                | HASH_SET
+                                | JSON
+                                | DECIMAL
+                                | READER
+                                | WRITER
+                                | LINES,
+                            ..
+                        }
+                ) =>
+            {
+                self.layout_inner(inner, outer)?
+            }
+            Ty::Nullable(inner) => self.sequential([&Ty::Bool, &**inner].into_iter(), outer)?,
+            // Start, end and whether the end is included.
+            Ty::BuiltinType { id: RANGE, args } => {
+                let elem = args.first()?;
+                self.sequential([elem, elem, &Ty::Bool].into_iter(), outer)?
+            }
+            Ty::Array { len: None, .. }
+            | Ty::Enum(_)
+            | Ty::Function(_)
+            | Ty::Builtin(_)
+            | Ty::BuiltinType { .. }
+            | Ty::Null
+            | Ty::Param(_)
+            | Ty::Unknown => return None,
+        };
+        Some(layout)
+    }
+
+    fn adt_layout(
+        &self,
+        def: HirId,
+        args: &[GenericArg],
+        outer: &mut Vec<HirId>,
+    ) -> Option<Layout> {
+        let adt = self.adt(def)?;
+        let fields: Vec<_> = adt.fields.iter().map(|field| field.ty.substitute(args)).collect();
+        match adt.kind {
+            AdtKind::Struct => self.sequential(fields.iter(), outer),
+            // Every field starts at offset 0.
+            AdtKind::Union => {
+                let mut layout = Layout::new(0, 1);
+                for field in fields.iter() {
+                    let field = self.layout_inner(field, outer)?;
+                    layout.size = layout.size.max(field.size);
+                    layout.align = layout.align.max(field.align);
+                }
+                Some(layout.pad_to_align())
+            }
+        }
+    }
+
+    /// Lays out `items` one after another in declaration order.
+    fn sequential<'a>(
+        &self,
+        items: impl Iterator<Item = &'a Ty>,
+        outer: &mut Vec<HirId>,
+    ) -> Option<Layout> {
+        let mut layout = Layout::new(0, 1);
+        for item in items {
+            layout.push_field(self.layout_inner(item, outer)?);
+        }
+        Some(layout.pad_to_align())
+    }
+}
+
+fn bits_size(bits: TypeBits) -> u64 {
+    match bits {
+        TypeBits::B8 => 1,
+        TypeBits::B16 => 2,
+        TypeBits::B32 => 4,
+        TypeBits::B64 => 8,
+        TypeBits::B128 => 16,
+    }
+}
@@ -9,4 +9,43 @@ define_messages! {
     ARGUMENT_COUNT_MISMATCH => "expected {expected} arguments, found {actual}",
     TYPE_MISMATCH => "expected type `{expected}`, found `{actual}`",
     CONTRACT_NOT_IMPLEMENTED => "type `{actual}` does not implement `{contract}`",
+    INVALID_ASSIGNMENT_TARGET => "only local bindings, elements and dereferenced pointers can be assigned to",
+    ASSIGN_TO_IMMUTABLE => "cannot assign to immutable binding `{name}`",
+    DECLARE_MUTABLE => "declare the binding with `let mut {name}` to assign to it",
+    PLACE_ARGUMENT => "`{name}` writes to this argument, so it must be a local binding or an element",
+    UNSUPPORTED_OPERATOR => "operator `{op}` is not supported yet",
+    CANNOT_COMPARE => "values of type `{ty}` cannot be compared with `{op}`",
+    NULLABLE_MISMATCH => "expected `{expected}`, found nullable `{actual}`",
+    CHECK_FOR_NULL => "check the value with `if value != null` to use it as non-nullable",
+    CANNOT_INFER_NULL => "cannot infer the type of `null`; add a nullable type annotation",
+    UNHANDLED_ERROR => "unhandled error of type `{error}`",
+    PROPAGATE_WITH_TRY => "propagate the error to the caller with `?`",
+    TRY_WITHOUT_THROWING_CALL => "`?` can only be applied to a call of a throwing function",
+    THROW_OUTSIDE_THROWING_FUNCTION => "error of type `{error}` is thrown from a function without a `throws` clause",
+    ADD_THROWS_CLAUSE => "add `throws {error}` to the function signature",
+    CANNOT_INFER_GENERIC => "cannot infer generic parameter `{name}` from the call arguments",
+    GENERIC_ARGUMENT_COUNT_MISMATCH => "expected {expected} generic arguments, found {actual}",
+    TURBOFISH_NOT_CALLED => "generic arguments can only be given to a function that is called",
+    EXPECTED_TYPE_ARGUMENT => "expected a type for generic parameter `{name}`, found a constant",
+    EXPECTED_CONST_ARGUMENT => "expected a constant for generic parameter `{name}`, found a type",
+    NON_LITERAL_CONST_ARGUMENT => "const generic arguments must be integer literals",
+    NO_SUCH_FIELD => "type `{ty}` has no field `{name}`",
+    DUPLICATE_FIELD => "field `{name}` is given more than once",
+    MISSING_FIELD => "missing field `{name}` in `{ty}`",
+    VARIANT_FORM_MISMATCH => "variant `{name}` is constructed {form}",
+    UNION_LITERAL_FIELDS => "a literal of union `{ty}` must give exactly one field, found {actual}",
+    UNION_FIELD_OUTSIDE_UNSAFE => "access to union field `{name}` requires an `unsafe` block",
+    CANNOT_DEREF => "type `{actual}` cannot be dereferenced",
+    DEREF_OUTSIDE_UNSAFE => "dereference of a raw pointer requires an `unsafe` block",
+    ASSIGN_THROUGH_CONST => "cannot assign through `{ty}`; only `*mut` pointers can be written through",
+    EXTERN_CALL_OUTSIDE_UNSAFE => "call to an `extern` function requires an `unsafe` block",
+    NOT_FFI_SAFE => "type `{ty}` cannot be passed across an `extern` boundary",
+    GENERIC_MAIN => "`main` cannot have generic parameters",
+    CANNOT_INFER_CLOSURE_PARAM => "cannot infer the type of closure parameter `{name}`; add a type annotation",
+    THROW_IN_CLOSURE => "errors cannot be thrown out of a closure",
+    NON_INTEGER_RANGE => "range bounds must be integers, found `{actual}`",
+    CANNOT_INDEX => "type `{ty}` cannot be indexed",
+    NON_INTEGER_INDEX => "index must be an integer or a range of integers, found `{actual}`",
+    METHOD_NOT_CALLED => "method `{name}` must be called",
+    FUNCTION_NOT_A_VALUE => "generic, throwing and `extern` functions cannot be used as values",
 }
@@ -1,43 +1,320 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
+use runec_ast::SpannedStr;
 use runec_ast::expression::{FloatSuffix, IntSuffix};
+use runec_ast::operators::BinaryOp;
+use runec_ast::statement::Visibility;
 use runec_builtins::{
-    BuiltinId, BuiltinReturn, ContractId, PrimitiveType, TypeBits, TypeConstraint, builtin_decl,
-    primitive_implements,
+    BuiltinDecl, BuiltinId, BuiltinLowering, BuiltinReturn, BuiltinTy, BuiltinTypeId, ContractId,
+    DISPLAY_CONTRACT, ITERATOR_CONTRACT, ITERTOOLS, LINES, RANGE, TypeBits, TypeConstraint, VEC,
+    builtin_decl, builtin_from_path, builtin_type_decl, builtin_type_path, contract_decl,
 };
 use runec_errors::diagnostics::Diagnostic;
-use runec_errors::labels::DiagLabel;
+use runec_errors::labels::{DiagLabel, DiagNote};
 use runec_errors::message::DiagMessage;
-use runec_hir::expression::{HirExpr, HirLiteral, SpannedHirExpr};
+use runec_hir::expression::{
+    HirCaptureMode, HirClosure, HirExpr, HirFieldValue, HirLiteral, SpannedHirExpr, strip_turbofish,
+};
 use runec_hir::ids::{HirId, HirLocalId};
-use runec_hir::item::{HirFunction, HirItem};
+use runec_hir::item::{
+    HirBound, HirFunction, HirGenericParam, HirGenericParamKind, HirItem, HirVariantPayload,
+};
 use runec_hir::map::HirMap;
+use runec_hir::path::{HirGenericArg, HirPath};
 use runec_hir::resolution::Res;
 use runec_hir::statement::{HirBlock, HirStmt};
 use runec_hir::ty::{HirPrimitiveTy, HirType, SpannedHirType};
 use runec_source::span::Span;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+use self::contracts::{operator_contract, ty_implements};
+use self::inference::{infer_generic_args, same_shape};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Ty {
     Unit,
     Bool,
-    Int { signed: bool, bits: TypeBits },
-    Float { bits: TypeBits },
+    Int {
+        signed: bool,
+        bits: TypeBits,
+    },
+    Float {
+        bits: TypeBits,
+    },
     Char,
     Str,
     Tuple(Box<[Ty]>),
-    Array { elem: Box<Ty>, len: Option<u64> },
-    Struct(HirId),
+    Array {
+        elem: Box<Ty>,
+        len: Option<u64>,
+    },
+    Struct {
+        def: HirId,
+        args: Box<[GenericArg]>,
+    },
+    Union {
+        def: HirId,
+        args: Box<[GenericArg]>,
+    },
     Enum(HirId),
+    /// A function item, by its definition.
     Function(HirId),
+    /// `act(A, B) -> R`; a closure or a function item used as a value.
+    FnPtr {
+        params: Box<[Ty]>,
+        ret: Box<Ty>,
+    },
     Builtin(BuiltinId),
+    /// Generic type provided by the compiler, such as `Range<int>`.
+    BuiltinType {
+        id: BuiltinTypeId,
+        args: Box<[Ty]>,
+    },
+    /// `T[]`; a view of elements stored elsewhere.
+    Slice(Box<Ty>),
+    /// `T?`; never wraps another nullable type.
+    Nullable(Box<Ty>),
+    /// `*const T` or `*mut T`
+    Pointer {
+        mutable: bool,
+        pointee: Box<Ty>,
+    },
+    /// Type of the `null` literal before it meets a nullable type.
+    Null,
+    /// Type of expressions that never produce a value, such as `panic(...)`
+    /// and blocks ending in `throw`. It converts to every other type.
+    Never,
+    /// Type parameter of the enclosing item, by index.
+    Param(usize),
     Unknown,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GenericArg {
+    Type(Ty),
+    Const(u128),
+    /// Const generic parameter of the enclosing item, by index.
+    ConstParam(usize),
+}
+
+impl GenericArg {
+    pub fn substitute(&self, args: &[GenericArg]) -> GenericArg {
+        match self {
+            GenericArg::Type(ty) => GenericArg::Type(ty.substitute(args)),
+            GenericArg::ConstParam(index) => match args.get(*index) {
+                Some(arg @ (GenericArg::Const(_) | GenericArg::ConstParam(_))) => arg.clone(),
+                _ => self.clone(),
+            },
+            GenericArg::Const(_) => self.clone(),
+        }
+    }
+}
+
+impl Ty {
+    /// Type of lengths and indices. It is pointer-sized, which is 64 bits on
+    /// every supported target.
+    pub const USIZE: Ty = Ty::Int { signed: false, bits: TypeBits::B64 };
+
+    /// Returns `T?` for `T`, leaving types that can already hold `null` as is.
+    pub fn nullable(self) -> Ty {
+        match self {
+            Ty::Nullable(_) | Ty::Null | Ty::Unknown => self,
+            ty => Ty::Nullable(Box::new(ty)),
+        }
+    }
+
+    /// Returns `T` for `T?` and any other type unchanged.
+    pub fn non_null(self) -> Ty {
+        match self {
+            Ty::Nullable(inner) => *inner,
+            ty => ty,
+        }
+    }
+
+    /// Returns `true` if a value of type `actual` can be stored where `self`
+    /// is expected. `T` and `null` are implicitly converted to `T?`, and
+    /// `*mut T` to `*const T`.
+    pub fn accepts(&self, actual: &Ty) -> bool {
+        if *self == Ty::Unknown || matches!(actual, Ty::Unknown | Ty::Never) || self == actual {
+            return true;
+        }
+
+        match (self, actual) {
+            (Ty::Nullable(inner), actual) => *actual == Ty::Null || **inner == *actual,
+            (
+                Ty::Pointer { mutable: false, pointee: expected },
+                Ty::Pointer { pointee: actual, .. },
+            ) => expected == actual,
+            _ => false,
+        }
+    }
+
+    /// Replaces the generic parameters in `self` with `args`, indexed like
+    /// the parameter list they were declared in.
+    pub fn substitute(&self, args: &[GenericArg]) -> Ty {
+        match self {
+            Ty::Param(index) => match args.get(*index) {
+                Some(GenericArg::Type(ty)) => ty.clone(),
+                _ => Ty::Unknown,
+            },
+            Ty::Tuple(items) => Ty::Tuple(items.iter().map(|item| item.substitute(args)).collect()),
+            Ty::Array { elem, len } => {
+                Ty::Array { elem: Box::new(elem.substitute(args)), len: *len }
+            }
+            Ty::Struct { def, args: own } => {
+                Ty::Struct { def: *def, args: own.iter().map(|arg| arg.substitute(args)).collect() }
+            }
+            Ty::Union { def, args: own } => {
+                Ty::Union { def: *def, args: own.iter().map(|arg| arg.substitute(args)).collect() }
+            }
+            Ty::Slice(elem) => Ty::Slice(Box::new(elem.substitute(args))),
+            Ty::Nullable(inner) => inner.substitute(args).nullable(),
+            Ty::Pointer { mutable, pointee } => {
+                Ty::Pointer { mutable: *mutable, pointee: Box::new(pointee.substitute(args)) }
+            }
+            Ty::FnPtr { params, ret } => Ty::FnPtr {
+                params: params.iter().map(|param| param.substitute(args)).collect(),
+                ret: Box::new(ret.substitute(args)),
+            },
+            Ty::BuiltinType { id, args: own } => Ty::BuiltinType {
+                id: *id,
+                args: own.iter().map(|arg| arg.substitute(args)).collect(),
+            },
+            ty => ty.clone(),
+        }
+    }
+
+    /// Returns the number of types `self` is built from, itself included.
+    pub fn size(&self) -> usize {
+        let args = |args: &[GenericArg]| {
+            args.iter()
+                .map(|arg| match arg {
+                    GenericArg::Type(ty) => ty.size(),
+                    GenericArg::Const(_) | GenericArg::ConstParam(_) => 0,
+                })
+                .sum::<usize>()
+        };
+        1 + match self {
+            Ty::Tuple(items) | Ty::BuiltinType { args: items, .. } => {
+                items.iter().map(Ty::size).sum()
+            }
+            Ty::Struct { args: own, .. } | Ty::Union { args: own, .. } => args(own),
+            Ty::Array { elem: inner, .. }
+            | Ty::Slice(inner)
+            | Ty::Nullable(inner)
+            | Ty::Pointer { pointee: inner, .. } => inner.size(),
+            Ty::FnPtr { params, ret } => params.iter().map(Ty::size).sum::<usize>() + ret.size(),
+            _ => 0,
+        }
+    }
+
+    /// Returns the type of the items `next()` yields for an iterator of
+    /// type `self`.
+    pub fn iterator_item(&self) -> Option<Ty> {
+        match self {
+            Ty::BuiltinType { id: RANGE, args } => args.first().cloned(),
+            Ty::BuiltinType { id: LINES, .. } => Some(Ty::Str),
+            _ => None,
+        }
+    }
+
+    /// Returns the element type of a vector or slice.
+    pub fn elem(&self) -> Option<&Ty> {
+        match self {
+            Ty::BuiltinType { id: VEC, args } => args.first(),
+            Ty::Slice(elem) => Some(elem),
+            _ => None,
+        }
+    }
+
+    /// Returns the type of `self[index]`: an element for an integer index
+    /// and a slice for a range.
+    pub fn indexed(&self, index: &Ty) -> Option<Ty> {
+        let elem = self.elem()?.clone();
+        match index {
+            Ty::Int { .. } => Some(elem),
+            Ty::BuiltinType { id: RANGE, .. } => Some(Ty::Slice(Box::new(elem))),
+            _ => None,
+        }
+    }
+
+    /// Returns the type both `self` and `other` convert to, if any.
+    pub fn common(&self, other: &Ty) -> Option<Ty> {
+        if *self == Ty::Unknown || other.accepts(self) {
+            Some(other.clone())
+        } else if self.accepts(other) {
+            Some(self.clone())
+        } else if *self == Ty::Null {
+            Some(other.clone().nullable())
+        } else if *other == Ty::Null {
+            Some(self.clone().nullable())
+        } else {
+            None
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct FunctionSig {
     pub params: Box<[Ty]>,
     pub ret: Ty,
+    /// Error type from the `throws` clause.
+    pub throws: Option<Ty>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GenericParamInfo<'src> {
+    Type { name: &'src str, bounds: Box<[BoundInfo]> },
+    Const { name: &'src str, ty: Ty },
+}
+
+impl<'src> GenericParamInfo<'src> {
+    pub fn name(&self) -> &'src str {
+        match self {
+            GenericParamInfo::Type { name, .. } | GenericParamInfo::Const { name, .. } => name,
+        }
+    }
+}
+
+/// A contract a type parameter implements, with the generic arguments of
+/// the contract, as `U` in `T: IntoIterator<U>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoundInfo {
+    pub contract: ContractId,
+    pub args: Box<[Ty]>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AdtKind {
+    Struct,
+    Union,
+}
+
+/// Fields of a struct or union, typed in terms of its generic parameters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdtInfo<'src> {
+    pub kind: AdtKind,
+    pub fields: Box<[FieldInfo<'src>]>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldInfo<'src> {
+    pub name: &'src str,
+    pub ty: Ty,
+}
+
+/// A variant of an enum. Tuple payloads have fields named `""`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariantInfo<'src> {
+    pub name: &'src str,
+    pub kind: VariantKind,
+    pub fields: Box<[FieldInfo<'src>]>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum VariantKind {
+    Unit,
+    Tuple,
+    Struct,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -51,7 +328,28 @@ pub struct LocalInfo<'src> {
 #[derive(Debug, Default)]
 pub struct TypeInfo<'src> {
     function_sigs: HashMap<HirId, FunctionSig>,
+    /// Generic parameters of functions, structs and unions.
+    generics: HashMap<HirId, Box<[GenericParamInfo<'src>]>>,
+    adts: HashMap<HirId, AdtInfo<'src>>,
+    variants: HashMap<HirId, Box<[VariantInfo<'src>]>>,
+    /// `extern act` declarations; their signatures are in `function_sigs`.
+    externs: HashSet<HirId>,
+    /// Generic arguments inferred for calls of generic functions and for
+    /// struct expressions of generic structs, keyed by the calling function
+    /// and the span of the callee expression or struct path.
+    callee_args: HashMap<(HirId, Span), Box<[GenericArg]>>,
+    /// Functions of `std::itertools` called as methods, keyed like
+    /// `callee_args`.
+    methods: HashMap<(HirId, Span), HirId>,
+    /// Integer types taken by unsuffixed integer literals where one was
+    /// expected, keyed like `callee_args`. Other literals default to `i32`.
+    int_literals: HashMap<(HirId, Span), Ty>,
     locals: HashMap<HirId, Vec<LocalInfo<'src>>>,
+    /// Locals that some closure captures by reference, keyed by the function
+    /// defining them.
+    by_ref_captures: HashSet<(HirId, HirLocalId)>,
+    /// Names of the items, for showing types in diagnostics.
+    item_names: HashMap<HirId, &'src str>,
 }
 
 impl<'src> TypeInfo<'src> {
@@ -59,6 +357,134 @@ impl<'src> TypeInfo<'src> {
         self.function_sigs.get(&id)
     }
 
+    pub fn generics(&self, item: HirId) -> &[GenericParamInfo<'src>] {
+        self.generics.get(&item).map(Box::as_ref).unwrap_or_default()
+    }
+
+    pub fn is_extern(&self, id: HirId) -> bool {
+        self.externs.contains(&id)
+    }
+
+    /// Returns `ty` as it is written in source, naming generic parameters
+    /// after those of item `scope`.
+    pub fn display_ty(&self, ty: &Ty, scope: Option<HirId>) -> String {
+        let generic_args = |args: &[GenericArg]| {
+            if args.is_empty() {
+                return String::new();
+            }
+            let args = args.iter().map(|arg| match arg {
+                GenericArg::Type(ty) => self.display_ty(ty, scope),
+                GenericArg::Const(value) => value.to_string(),
+                GenericArg::ConstParam(index) => self.display_param(*index, scope),
+            });
+            format!("<{}>", comma_list(args))
+        };
+        let item_name = |id: &HirId| self.item_names.get(id).copied().unwrap_or("<unknown>");
+        match ty {
+            Ty::Unit => "()".to_owned(),
+            Ty::Bool => "bool".to_owned(),
+            Ty::Int { signed, bits } => {
+                format!("{}{}", if *signed { 'i' } else { 'u' }, bits_count(*bits))
+            }
+            Ty::Float { bits } => format!("f{}", bits_count(*bits)),
+            Ty::Char => "char".to_owned(),
+            Ty::Str => "str".to_owned(),
+            Ty::Tuple(items) => {
+                format!("({})", comma_list(items.iter().map(|item| self.display_ty(item, scope))))
+            }
+            Ty::Array { elem, len } => {
+                let len = len.map(|len| len.to_string()).unwrap_or_else(|| "_".to_owned());
+                format!("{}[{len}]", self.display_ty(elem, scope))
+            }
+            Ty::Struct { def, args } | Ty::Union { def, args } => {
+                format!("{}{}", item_name(def), generic_args(args))
+            }
+            Ty::Enum(def) | Ty::Function(def) => item_name(def).to_owned(),
+            Ty::FnPtr { params, ret } => format!(
+                "act({}) -> {}",
+                comma_list(params.iter().map(|param| self.display_ty(param, scope))),
+                self.display_ty(ret, scope)
+            ),
+            Ty::Builtin(id) => builtin_decl(*id).map_or("<builtin>", |decl| decl.name).to_owned(),
+            Ty::BuiltinType { id, args } => {
+                let name = builtin_type_decl(*id).map_or("<builtin>", |decl| decl.name);
+                let args: Vec<_> = args.iter().cloned().map(GenericArg::Type).collect();
+                format!("{name}{}", generic_args(&args))
+            }
+            Ty::Slice(elem) => format!("{}[]", self.display_ty(elem, scope)),
+            Ty::Nullable(inner) => format!("{}?", self.display_ty(inner, scope)),
+            Ty::Pointer { mutable, pointee } => format!(
+                "*{} {}",
+                if *mutable { "mut" } else { "const" },
+                self.display_ty(pointee, scope)
+            ),
+            Ty::Null => "null".to_owned(),
+            Ty::Never => "never".to_owned(),
+            Ty::Param(index) => self.display_param(*index, scope),
+            Ty::Unknown => "{unknown}".to_owned(),
+        }
+    }
+
+    fn display_param(&self, index: usize, scope: Option<HirId>) -> String {
+        scope
+            .and_then(|scope| self.generics(scope).get(index))
+            .map_or_else(|| format!("<parameter {index}>"), |param| param.name().to_owned())
+    }
+
+    pub fn adt(&self, def: HirId) -> Option<&AdtInfo<'src>> {
+        self.adts.get(&def)
+    }
+
+    pub fn variants(&self, def: HirId) -> &[VariantInfo<'src>] {
+        self.variants.get(&def).map(Box::as_ref).unwrap_or_default()
+    }
+
+    /// Returns the type of field `name` of a struct or union value of type
+    /// `ty`, with the generic arguments of `ty` substituted.
+    pub fn field_ty(&self, ty: &Ty, name: &str) -> Option<Ty> {
+        let (Ty::Struct { def, args } | Ty::Union { def, args }) = ty else {
+            return None;
+        };
+        let field = self.adt(*def)?.fields.iter().find(|field| field.name == name)?;
+        Some(field.ty.substitute(args))
+    }
+
+    /// Returns the builtin method that `op` is lowered to for operands of
+    /// type `ty`, such as `add` of `Decimal` for `+`: the method of the
+    /// operator's contract, if `ty` implements it.
+    pub fn operator_method(&self, ty: &Ty, op: BinaryOp) -> Option<BuiltinId> {
+        let contract = operator_contract(op)?;
+        if !ty_implements(ty, contract) {
+            return None;
+        }
+        self.builtin_method(ty, contract_decl(contract)?.methods.first()?)
+    }
+
+    /// Returns the builtin method `name` of a value of type `ty`, such as
+    /// `push` of `Vec<T>`. Fields take precedence over methods.
+    pub fn builtin_method(&self, ty: &Ty, name: &str) -> Option<BuiltinId> {
+        let owner = match ty {
+            Ty::BuiltinType { id, .. } => builtin_type_path(*id)?,
+            Ty::Slice(_) => "core::slice".to_owned(),
+            _ => return None,
+        };
+        builtin_from_path(&format!("{owner}::{name}"))
+            .filter(|id| builtin_decl(*id).is_some_and(|decl| !decl.params.is_empty()))
+    }
+
+    /// Returns the generic arguments `callee` is instantiated with inside
+    /// `function`; empty unless it names a generic function.
+    pub fn callee_args(&self, function: HirId, callee: &SpannedHirExpr<'src>) -> &[GenericArg] {
+        let callee = strip_turbofish(callee);
+        self.callee_args.get(&(function, callee.span)).map(Box::as_ref).unwrap_or_default()
+    }
+
+    /// Returns the function that the method `callee` of a call in `function`
+    /// calls, with the receiver as its first argument.
+    pub fn method(&self, function: HirId, callee: &SpannedHirExpr<'src>) -> Option<HirId> {
+        self.methods.get(&(function, callee.span)).copied()
+    }
+
     pub fn locals(&self, function: HirId) -> &[LocalInfo<'src>] {
         self.locals.get(&function).map(Vec::as_slice).unwrap_or_default()
     }
@@ -67,41 +493,175 @@ impl<'src> TypeInfo<'src> {
         self.locals.get(&function).and_then(|locals| locals.get(local.to_usize()))
     }
 
+    /// Returns `true` if a closure captures `local` by reference, so that it
+    /// has to live in a cell shared with the closure.
+    pub fn is_captured_by_ref(&self, function: HirId, local: HirLocalId) -> bool {
+        self.by_ref_captures.contains(&(function, local))
+    }
+
     pub fn ty_of_expr(&self, function: HirId, expr: &SpannedHirExpr<'src>) -> Ty {
         match &expr.node {
             HirExpr::Error => Ty::Unknown,
-            HirExpr::Literal(literal) => ty_of_literal(literal),
+            HirExpr::Literal(literal) => self
+                .int_literals
+                .get(&(function, expr.span))
+                .cloned()
+                .unwrap_or_else(|| ty_of_literal(literal)),
             HirExpr::Resolved(res) => self.ty_of_res(function, *res),
+            HirExpr::NonNullLocal(local) => self.ty_of_res(function, Res::Local(*local)).non_null(),
+            HirExpr::Turbofish { callee, .. } => self.ty_of_expr(function, callee),
             HirExpr::Call { callee, .. } => self.call_return_ty(function, callee),
-            HirExpr::Block(block) => self.ty_of_block(function, block),
+            HirExpr::Try(inner) => self.ty_of_expr(function, inner),
+            HirExpr::Deref(inner) => match self.ty_of_expr(function, inner) {
+                Ty::Pointer { pointee, .. } => *pointee,
+                _ => Ty::Unknown,
+            },
+            HirExpr::Field { base, name } => {
+                let base = self.ty_of_expr(function, base);
+                self.field_ty(&base, name.node)
+                    .or_else(|| self.builtin_method(&base, name.node).map(Ty::Builtin))
+                    .or_else(|| self.method(function, expr).map(Ty::Function))
+                    .unwrap_or(Ty::Unknown)
+            }
+            HirExpr::TupleField { base, index } => match self.ty_of_expr(function, base) {
+                Ty::Tuple(items) => items.get(*index).cloned().unwrap_or(Ty::Unknown),
+                _ => Ty::Unknown,
+            },
+            HirExpr::Tuple(items) => {
+                Ty::Tuple(items.iter().map(|item| self.ty_of_expr(function, item)).collect())
+            }
+            HirExpr::Index { base, index } => self
+                .ty_of_expr(function, base)
+                .indexed(&self.ty_of_expr(function, index))
+                .unwrap_or(Ty::Unknown),
+            HirExpr::Binary { op, lhs, .. } => {
+                let lhs = self.ty_of_expr(function, lhs);
+                match self.operator_method(&lhs, *op) {
+                    Some(_) if !is_comparison(*op) => lhs,
+                    _ => binary_ty(*op),
+                }
+            }
+            HirExpr::Block(block) | HirExpr::Unsafe(block) => self.ty_of_block(function, block),
+            HirExpr::If { then, else_, .. } => match else_ {
+                Some(else_) => self
+                    .ty_of_block(function, then)
+                    .common(&self.ty_of_expr(function, else_))
+                    .unwrap_or(Ty::Unknown),
+                None => Ty::Unit,
+            },
+            HirExpr::Closure(closure) => self.closure_ty(function, closure),
+            HirExpr::Range { start, end, .. } => {
+                let start = self.ty_of_expr(function, start);
+                let elem = start.common(&self.ty_of_expr(function, end)).unwrap_or(start);
+                Ty::BuiltinType { id: RANGE, args: Box::new([elem]) }
+            }
+            HirExpr::For { .. } => Ty::Unit,
+            HirExpr::Struct { path, res, .. } => match res {
+                Some(Res::Def(def)) => {
+                    let def = *def;
+                    let args =
+                        self.callee_args.get(&(function, path.span)).cloned().unwrap_or_default();
+                    match self.adt(def).map(|adt| adt.kind) {
+                        Some(AdtKind::Union) => Ty::Union { def, args },
+                        _ => Ty::Struct { def, args },
+                    }
+                }
+                Some(Res::Variant { def, .. }) => Ty::Enum(*def),
+                _ => Ty::Unknown,
+            },
             HirExpr::Path(_) => Ty::Unknown,
         }
     }
 
+    fn closure_ty(&self, function: HirId, closure: &HirClosure<'src>) -> Ty {
+        let params = closure
+            .params
+            .iter()
+            .map(|param| {
+                param
+                    .local
+                    .and_then(|local| self.local(function, local))
+                    .map(|local| local.ty.clone())
+                    .unwrap_or(Ty::Unknown)
+            })
+            .collect();
+        Ty::FnPtr { params, ret: Box::new(self.ty_of_expr(function, &closure.body)) }
+    }
+
     fn ty_of_res(&self, function: HirId, res: Res) -> Ty {
         match res {
             Res::Local(local) => {
                 self.local(function, local).map(|local| local.ty.clone()).unwrap_or(Ty::Unknown)
             }
             Res::Def(id) => Ty::Function(id),
-            Res::Builtin(id) => Ty::Builtin(id),
+            Res::Variant { def, .. } => Ty::Enum(def),
+            Res::Builtin(id) => builtin_res_ty(id),
+            Res::ConstParam(index) => self.const_param_ty(function, index),
+        }
+    }
+
+    fn const_param_ty(&self, function: HirId, index: usize) -> Ty {
+        match self.generics(function).get(index) {
+            Some(GenericParamInfo::Const { ty, .. }) => ty.clone(),
+            _ => Ty::Unknown,
         }
     }
 
     fn call_return_ty(&self, function: HirId, callee: &SpannedHirExpr<'src>) -> Ty {
+        if let HirExpr::Resolved(Res::Variant { def, .. }) = strip_turbofish(callee).node {
+            return Ty::Enum(def);
+        }
         match self.ty_of_expr(function, callee) {
-            Ty::Function(id) => {
-                self.function_sig(id).map(|sig| sig.ret.clone()).unwrap_or(Ty::Unknown)
-            }
-            Ty::Builtin(id) => {
-                builtin_decl(id).map(|decl| builtin_return_ty(decl.ret)).unwrap_or(Ty::Unknown)
-            }
+            Ty::Function(id) => self
+                .function_sig(id)
+                .map(|sig| sig.ret.substitute(self.callee_args(function, callee)))
+                .unwrap_or(Ty::Unknown),
+            Ty::Builtin(id) => builtin_decl(id)
+                .map(|decl| {
+                    builtin_return_ty(decl.ret).substitute(self.callee_args(function, callee))
+                })
+                .unwrap_or(Ty::Unknown),
+            Ty::FnPtr { ret, .. } => *ret,
             _ => Ty::Unknown,
         }
     }
 
+    /// Returns the `act(...) -> R` type a function item converts to when used
+    /// as a value. Generic, throwing and `extern` functions have none.
+    pub fn fn_ptr_ty(&self, id: HirId) -> Option<Ty> {
+        let sig = self.function_sig(id)?;
+        if sig.throws.is_some() || self.is_extern(id) || !self.generics(id).is_empty() {
+            return None;
+        }
+        Some(Ty::FnPtr { params: sig.params.clone(), ret: Box::new(sig.ret.clone()) })
+    }
+
+    /// Returns the error type thrown by calling `callee`, if it throws.
+    pub fn callee_throws(&self, function: HirId, callee: &SpannedHirExpr<'src>) -> Option<Ty> {
+        match self.ty_of_expr(function, callee) {
+            Ty::Function(id) => self.function_sig(id).and_then(|sig| {
+                let throws = sig.throws.as_ref()?;
+                Some(throws.substitute(self.callee_args(function, callee)))
+            }),
+            Ty::Builtin(id) => builtin_decl(id)
+                .filter(|decl| matches!(decl.ret, BuiltinReturn::Fallible(_)))
+                .map(|_| Ty::Str),
+            _ => None,
+        }
+    }
+
+    /// Returns the type of the value of `block`: that of its tail, or `()`
+    /// without one unless a statement never finishes.
     pub fn ty_of_block(&self, function: HirId, block: &HirBlock<'src>) -> Ty {
-        block.tail.as_ref().map(|tail| self.ty_of_expr(function, tail)).unwrap_or(Ty::Unit)
+        if let Some(tail) = &block.tail {
+            return self.ty_of_expr(function, tail);
+        }
+        let diverges = block.stmts.iter().any(|stmt| match stmt {
+            HirStmt::Throw { .. } | HirStmt::Break { .. } => true,
+            HirStmt::Expr(expr) => self.ty_of_expr(function, expr) == Ty::Never,
+            HirStmt::Let { .. } | HirStmt::Assign { .. } => false,
+        });
+        if diverges { Ty::Never } else { Ty::Unit }
     }
 }
 
@@ -112,16 +672,52 @@ pub struct TypeCheckResult<'src> {
 
 pub struct TypeChecker<'src> {
     info: TypeInfo<'src>,
+    /// Item whose generic parameters are in scope.
+    scope: Option<HirId>,
+    /// Number of `unsafe` blocks around the expression being checked.
+    unsafe_depth: usize,
+    /// Number of closures around the expression being checked.
+    closure_depth: usize,
+    /// Locals captured by the closures around the expression being checked.
+    captures: Vec<HirLocalId>,
+    /// Locals declared by a `let` without a value, keyed by their function.
+    deferred_lets: HashSet<(HirId, HirLocalId)>,
+    /// Public functions of `std::itertools` by name, which are methods of
+    /// every `IntoIterator`.
+    iterator_methods: HashMap<&'src str, HirId>,
     diags: Vec<Diagnostic<'static>>,
 }
 
 impl<'src> TypeChecker<'src> {
     pub fn new() -> Self {
-        Self { info: TypeInfo::default(), diags: Vec::new() }
+        Self {
+            info: TypeInfo::default(),
+            scope: None,
+            unsafe_depth: 0,
+            closure_depth: 0,
+            captures: Vec::new(),
+            deferred_lets: HashSet::new(),
+            iterator_methods: HashMap::new(),
+            diags: Vec::new(),
+        }
     }
 
     pub fn check(mut self, hir: &HirMap<'src>) -> TypeCheckResult<'src> {
+        self.info.item_names = hir.iter().map(|(id, item)| (id, item.name().node)).collect();
+        self.collect_generics(hir);
         self.collect_function_sigs(hir);
+        if let Some(module) = hir.find_module(ITERTOOLS.path.iter().copied()) {
+            self.iterator_methods = hir
+                .module(module)
+                .items
+                .iter()
+                .filter(|id| {
+                    let item = hir.get(**id);
+                    matches!(item, HirItem::Function(_)) && item.vis() == Visibility::Public
+                })
+                .map(|id| (hir.get(*id).name().node, *id))
+                .collect();
+        }
 
         for (_, item) in hir.iter() {
             if let HirItem::Function(function) = item {
@@ -132,42 +728,188 @@ impl<'src> TypeChecker<'src> {
         TypeCheckResult { info: self.info, diags: self.diags }
     }
 
+    fn collect_generics(&mut self, hir: &HirMap<'src>) {
+        for (id, item) in hir.iter() {
+            let generics = match item {
+                HirItem::Function(function) => &function.generics,
+                HirItem::Struct(strukt) => &strukt.generics,
+                HirItem::Union(union) => &union.generics,
+                // Uses of aliases are replaced by their targets during name
+                // resolution.
+                HirItem::ExternFunction(_) | HirItem::Enum(_) | HirItem::Alias(_) => continue,
+            };
+            let generics = generics.iter().map(|param| self.lower_generic_param(param)).collect();
+            self.info.generics.insert(id, generics);
+        }
+    }
+
+    fn lower_generic_param(&mut self, param: &HirGenericParam<'src>) -> GenericParamInfo<'src> {
+        match &param.kind {
+            HirGenericParamKind::Type { bounds } => GenericParamInfo::Type {
+                name: param.name.node,
+                bounds: bounds.iter().filter_map(|bound| self.lower_bound(bound)).collect(),
+            },
+            HirGenericParamKind::Const { ty } => {
+                GenericParamInfo::Const { name: param.name.node, ty: self.lower_ty(ty) }
+            }
+        }
+    }
+
+    fn lower_bound(&mut self, bound: &HirBound<'src>) -> Option<BoundInfo> {
+        let contract = bound.contract?;
+        let args = bound.path.segments.last().and_then(|last| last.generics.as_deref());
+        let args = args.unwrap_or_default();
+        let params = contract_decl(contract).map(|decl| decl.generics).unwrap_or_default();
+        if !self.check_generic_arg_count(bound.path.span, params.len(), args.len()) {
+            return None;
+        }
+        let args = params
+            .iter()
+            .zip(args)
+            .map(|(name, arg)| match arg {
+                HirGenericArg::Type(ty) => self.lower_ty(ty),
+                HirGenericArg::Const(expr) => {
+                    self.push_diag(messages::EXPECTED_TYPE_ARGUMENT, &[("name", name)], expr.span);
+                    Ty::Unknown
+                }
+            })
+            .collect();
+        Some(BoundInfo { contract, args })
+    }
+
     fn collect_function_sigs(&mut self, hir: &HirMap<'src>) {
         for (id, item) in hir.iter() {
             if let HirItem::Function(function) = item {
+                self.scope = Some(id);
                 let params = function.params.iter().map(|param| self.lower_ty(&param.ty)).collect();
                 let ret = self.lower_ty(&function.ret_ty);
-                self.info.function_sigs.insert(id, FunctionSig { params, ret });
+                let throws = function.throws.as_ref().map(|ty| self.lower_ty(ty));
+                self.info.function_sigs.insert(id, FunctionSig { params, ret, throws });
+            }
+            if let HirItem::ExternFunction(function) = item {
+                self.scope = None;
+                let params = function
+                    .params
+                    .iter()
+                    .map(|param| self.lower_extern_ty(&param.ty, false))
+                    .collect();
+                let ret = self.lower_extern_ty(&function.ret_ty, true);
+                self.info.function_sigs.insert(id, FunctionSig { params, ret, throws: None });
+                self.info.externs.insert(id);
+            }
+            if let HirItem::Enum(enumeration) = item {
+                self.scope = None;
+                let variants = enumeration
+                    .variants
+                    .iter()
+                    .map(|variant| {
+                        let (kind, fields) = match &variant.payload {
+                            HirVariantPayload::Unit => (VariantKind::Unit, Box::default()),
+                            HirVariantPayload::Tuple(items) => (
+                                VariantKind::Tuple,
+                                items
+                                    .iter()
+                                    .map(|ty| FieldInfo { name: "", ty: self.lower_ty(ty) })
+                                    .collect(),
+                            ),
+                            HirVariantPayload::Struct(fields) => (
+                                VariantKind::Struct,
+                                fields
+                                    .iter()
+                                    .map(|field| FieldInfo {
+                                        name: field.name.node,
+                                        ty: self.lower_ty(&field.ty),
+                                    })
+                                    .collect(),
+                            ),
+                        };
+                        VariantInfo { name: variant.name.node, kind, fields }
+                    })
+                    .collect();
+                self.info.variants.insert(id, variants);
             }
+            let (kind, fields) = match item {
+                HirItem::Struct(strukt) => (AdtKind::Struct, &strukt.fields),
+                HirItem::Union(union) => (AdtKind::Union, &union.fields),
+                _ => continue,
+            };
+            self.scope = Some(id);
+            let fields = fields
+                .iter()
+                .map(|field| FieldInfo { name: field.name.node, ty: self.lower_ty(&field.ty) })
+                .collect();
+            self.info.adts.insert(id, AdtInfo { kind, fields });
+        }
+        self.scope = None;
+    }
+
+    /// Lowers a type of an `extern act` signature, which must map onto a C
+    /// ABI scalar. `()` is only allowed as the return type.
+    fn lower_extern_ty(&mut self, ty: &SpannedHirType<'src>, is_ret: bool) -> Ty {
+        let lowered = self.lower_ty(ty);
+        let ffi_safe = match &lowered {
+            Ty::Bool | Ty::Int { .. } | Ty::Float { .. } | Ty::Pointer { .. } | Ty::Unknown => true,
+            Ty::Unit => is_ret,
+            _ => false,
+        };
+        if !ffi_safe {
+            let name = self.ty_name(&lowered);
+            self.push_diag(messages::NOT_FFI_SAFE, &[("ty", &name)], ty.span);
         }
+        lowered
+    }
+
+    /// Returns `ty` as it is written in source, for diagnostics.
+    fn ty_name(&self, ty: &Ty) -> String {
+        self.info.display_ty(ty, self.scope)
     }
 
     fn check_function(&mut self, function: &HirFunction<'src>) {
-        let mut locals = Vec::new();
-        for param in function.params.iter() {
-            locals.push(LocalInfo {
+        self.scope = Some(function.id);
+        let Some(sig) = self.info.function_sig(function.id).cloned() else {
+            return;
+        };
+        let locals = function
+            .params
+            .iter()
+            .zip(sig.params.iter())
+            .map(|(param, ty)| LocalInfo {
                 name: param.name.node,
-                ty: self.lower_ty(&param.ty),
+                ty: ty.clone(),
                 is_mutable: false,
                 span: param.span,
-            });
-        }
+            })
+            .collect();
         self.info.locals.insert(function.id, locals);
 
-        self.check_block(function.id, &function.body);
+        if function.name.node == "main" {
+            if let Some(param) = function.generics.first() {
+                self.push_diag(messages::GENERIC_MAIN, &[], param.span);
+            }
+            // Errors escaping `main` are printed before the program exits.
+            if let (Some(throws), Some(ty)) = (&function.throws, sig.throws) {
+                self.check_constraint(
+                    throws.span,
+                    TypeConstraint::Implements(DISPLAY_CONTRACT),
+                    ty,
+                );
+            }
+        }
+
+        self.check_block(function.id, &function.body, Some(&sig.ret));
         let actual = self.info.ty_of_block(function.id, &function.body);
-        let expected =
-            self.info.function_sig(function.id).map(|sig| sig.ret.clone()).unwrap_or(Ty::Unknown);
-        self.expect_assignable(function.body.span, expected, actual);
+        self.expect_assignable(function.body.span, sig.ret, actual);
     }
 
-    fn check_block(&mut self, function: HirId, block: &HirBlock<'src>) {
+    /// Checks the statements of `block`, and its tail where a value of type
+    /// `expected` is wanted.
+    fn check_block(&mut self, function: HirId, block: &HirBlock<'src>, expected: Option<&Ty>) {
         for stmt in block.stmts.iter() {
             self.check_stmt(function, stmt);
         }
 
         if let Some(tail) = &block.tail {
-            self.check_expr(function, tail);
+            self.check_expr_expecting(function, tail, expected);
         }
     }
 
@@ -183,46 +925,524 @@ impl<'src> TypeChecker<'src> {
                 };
 
                 let declared = ty.as_ref().map(|ty| self.lower_ty(ty));
-                let init_ty = init.as_ref().map(|expr| self.check_expr(function, expr));
+                let init_ty = init
+                    .as_ref()
+                    .map(|expr| self.check_expr_expecting(function, expr, declared.as_ref()));
+                let init_ty = match (init_ty, init) {
+                    (Some(Ty::Function(id)), Some(init)) if declared.is_none() => {
+                        Some(self.fn_item_value(id, init.span))
+                    }
+                    (init_ty, _) => init_ty,
+                };
                 let final_ty = declared.clone().or(init_ty.clone()).unwrap_or(Ty::Unknown);
+                if final_ty == Ty::Null {
+                    self.push_diag(messages::CANNOT_INFER_NULL, &[], *span);
+                }
 
                 if let (Some(expected), Some(actual)) = (declared, init_ty) {
                     self.expect_assignable(*span, expected, actual);
                 }
 
-                let locals = self.info.locals.entry(function).or_default();
-                let local_idx = local.to_usize();
-                if locals.len() == local_idx {
-                    locals.push(LocalInfo {
-                        name: name.node,
-                        ty: final_ty,
-                        is_mutable: *is_mutable,
-                        span: *span,
-                    });
-                } else if let Some(info) = locals.get_mut(local_idx) {
-                    info.ty = final_ty;
-                } else {
-                    let local = format!("{local:?}");
-                    self.push_diag(messages::UNKNOWN_LOCAL, &[("local", &local)], *span);
+                let info = LocalInfo {
+                    name: name.node,
+                    ty: final_ty,
+                    is_mutable: *is_mutable,
+                    span: *span,
+                };
+                if init.is_none() {
+                    self.deferred_lets.insert((function, *local));
                 }
+                self.define_local(function, *local, info);
             }
+            HirStmt::Assign { target, value, span } => {
+                let element = match &target.node {
+                    HirExpr::Index { base, index } => Some(self.check_index(function, base, index)),
+                    HirExpr::Deref(_) => Some(self.check_expr(function, target)),
+                    _ => None,
+                };
+                // A local whose type is already known takes the value like
+                // a declared `let` would.
+                let declared = match &target.node {
+                    HirExpr::Resolved(Res::Local(local)) => self
+                        .info
+                        .local(function, *local)
+                        .map(|info| info.ty.clone())
+                        .filter(|ty| *ty != Ty::Unknown),
+                    _ => element.clone(),
+                };
+                let actual = self.check_expr_expecting(function, value, declared.as_ref());
+                match &target.node {
+                    HirExpr::Resolved(Res::Local(local)) => {
+                        self.check_binding_mutable(function, *local, target.span, true);
+                        let expected = self.check_res(function, Res::Local(*local), target.span);
+                        if expected == Ty::Unknown {
+                            if actual == Ty::Null {
+                                self.push_diag(messages::CANNOT_INFER_NULL, &[], *span);
+                            }
+                            // `let x;` takes the type of its first assignment.
+                            if let Some(info) = self
+                                .info
+                                .locals
+                                .get_mut(&function)
+                                .and_then(|locals| locals.get_mut(local.to_usize()))
+                            {
+                                info.ty = actual;
+                            }
+                        } else {
+                            self.expect_assignable(value.span, expected, actual);
+                        }
+                    }
+                    HirExpr::Index { index, .. }
+                        if !matches!(
+                            self.info.ty_of_expr(function, index),
+                            Ty::BuiltinType { id: RANGE, .. }
+                        ) =>
+                    {
+                        self.check_place_mutable(function, target);
+                        let expected = element.unwrap_or(Ty::Unknown);
+                        self.expect_assignable(value.span, expected, actual);
+                    }
+                    HirExpr::Deref(pointer) => {
+                        let pointer = self.info.ty_of_expr(function, pointer);
+                        if let Ty::Pointer { mutable: false, .. } = pointer {
+                            let ty = self.ty_name(&pointer);
+                            self.push_diag(
+                                messages::ASSIGN_THROUGH_CONST,
+                                &[("ty", &ty)],
+                                target.span,
+                            );
+                        }
+                        let expected = element.unwrap_or(Ty::Unknown);
+                        self.expect_assignable(value.span, expected, actual);
+                    }
+                    HirExpr::Error | HirExpr::Path(_) => {}
+                    _ => self.push_diag(messages::INVALID_ASSIGNMENT_TARGET, &[], *span),
+                }
+            }
+            HirStmt::Throw { value, .. } => {
+                let actual = self.check_expr(function, value);
+                self.check_thrown(function, actual, value.span);
+            }
+            HirStmt::Break { .. } => {}
         }
     }
 
+    /// Reports a write to an immutable binding. When the write `initializes`
+    /// its place, a `let` declared without a value may still be assigned
+    /// directly; MIR checks that this happens at most once on every path.
+    fn check_binding_mutable(
+        &mut self,
+        function: HirId,
+        local: HirLocalId,
+        span: Span,
+        initializes: bool,
+    ) {
+        let Some(info) = self.info.local(function, local) else {
+            return;
+        };
+        let deferred = initializes
+            && !self.captures.contains(&local)
+            && self.deferred_lets.contains(&(function, local));
+        if info.is_mutable || deferred {
+            return;
+        }
+        let name = info.name;
+        self.diags.push(
+            *Diagnostic::error(DiagMessage::new(messages::ASSIGN_TO_IMMUTABLE, &[("name", name)]))
+                .add_label(DiagLabel::silent_primary(span))
+                .set_note(DiagNote::new(messages::DECLARE_MUTABLE, &[("name", name)])),
+        );
+    }
+
+    /// Reports a write into an element of a place, such as `v[0]`, whose
+    /// root binding is immutable. Places rooted at anything other than a
+    /// local are not checked.
+    fn check_place_mutable(&mut self, function: HirId, place: &SpannedHirExpr<'src>) {
+        let mut root = place;
+        while let HirExpr::Index { base, .. } = &root.node {
+            root = base;
+        }
+        if let HirExpr::Resolved(Res::Local(local)) | HirExpr::NonNullLocal(local) = &root.node {
+            self.check_binding_mutable(function, *local, root.span, false);
+        }
+    }
+
+    fn define_local(&mut self, function: HirId, local: HirLocalId, info: LocalInfo<'src>) {
+        let locals = self.info.locals.entry(function).or_default();
+        let local_idx = local.to_usize();
+        if locals.len() == local_idx {
+            locals.push(info);
+        } else if let Some(existing) = locals.get_mut(local_idx) {
+            existing.ty = info.ty;
+        } else {
+            let local = format!("{local:?}");
+            self.push_diag(messages::UNKNOWN_LOCAL, &[("local", &local)], info.span);
+        }
+    }
+
+    /// Checks `expr` where a value of type `expected` is wanted; closures take
+    /// the types of unannotated parameters from it.
+    fn check_expr_expecting(
+        &mut self,
+        function: HirId,
+        expr: &SpannedHirExpr<'src>,
+        expected: Option<&Ty>,
+    ) -> Ty {
+        match &expr.node {
+            HirExpr::Closure(closure) => self.check_closure(function, closure, expected),
+            HirExpr::Literal(HirLiteral::Int { suffix: None, .. }) => {
+                match expected.map(|ty| ty.clone().non_null()) {
+                    Some(ty @ Ty::Int { .. }) => {
+                        self.info.int_literals.insert((function, expr.span), ty.clone());
+                        ty
+                    }
+                    _ => self.check_expr(function, expr),
+                }
+            }
+            HirExpr::Call { callee, args } => {
+                self.check_call(function, callee, args, (expr.span, expected), false)
+            }
+            HirExpr::Try(inner) => self.check_try(function, (inner, expr.span), expected),
+            HirExpr::Struct { path, res, fields } => {
+                self.check_struct_expr(function, (path, *res), fields, expected)
+            }
+            HirExpr::Block(_) | HirExpr::Unsafe(_) | HirExpr::If { .. } => {
+                self.check_branching_expr(function, expr, expected)
+            }
+            HirExpr::Tuple(items) => {
+                let expected = match expected {
+                    Some(Ty::Tuple(expected)) if expected.len() == items.len() => Some(expected),
+                    _ => None,
+                };
+                let items = items.iter().enumerate().map(|(index, item)| {
+                    self.check_expr_expecting(function, item, expected.map(|items| &items[index]))
+                });
+                Ty::Tuple(items.collect())
+            }
+            _ => self.check_expr(function, expr),
+        }
+    }
+
+    /// Checks `call?` at `span`, whose value is that of the call, where a
+    /// value of type `expected` is wanted.
+    fn check_try(
+        &mut self,
+        function: HirId,
+        (inner, span): (&SpannedHirExpr<'src>, Span),
+        expected: Option<&Ty>,
+    ) -> Ty {
+        let HirExpr::Call { callee, args } = &inner.node else {
+            self.check_expr(function, inner);
+            self.push_diag(messages::TRY_WITHOUT_THROWING_CALL, &[], span);
+            return Ty::Unknown;
+        };
+        let ty = self.check_call(function, callee, args, (inner.span, expected), true);
+        match self.info.callee_throws(function, callee) {
+            Some(error) => self.check_thrown(function, error, span),
+            None => self.push_diag(messages::TRY_WITHOUT_THROWING_CALL, &[], span),
+        }
+        ty
+    }
+
+    /// Checks a block, `unsafe` block or `if`, whose value is the tail of
+    /// the block taken, where a value of type `expected` is wanted.
+    fn check_branching_expr(
+        &mut self,
+        function: HirId,
+        expr: &SpannedHirExpr<'src>,
+        expected: Option<&Ty>,
+    ) -> Ty {
+        match &expr.node {
+            HirExpr::Block(block) => self.check_block(function, block, expected),
+            HirExpr::Unsafe(block) => {
+                self.unsafe_depth += 1;
+                self.check_block(function, block, expected);
+                self.unsafe_depth -= 1;
+            }
+            HirExpr::If { cond, then, else_ } => {
+                let cond_ty = self.check_expr(function, cond);
+                self.expect_assignable(cond.span, Ty::Bool, cond_ty);
+
+                self.check_block(function, then, expected);
+                let then_ty = self.info.ty_of_block(function, then);
+                match else_ {
+                    Some(else_) => {
+                        let else_ty = self.check_expr_expecting(function, else_, expected);
+                        if then_ty.common(&else_ty).is_none() {
+                            self.expect_assignable(else_.span, then_ty, else_ty);
+                        }
+                    }
+                    // Without `else` the value is `()`, so the branch must be too.
+                    None => self.expect_assignable(then.span, Ty::Unit, then_ty),
+                }
+            }
+            _ => return self.check_expr(function, expr),
+        }
+        self.info.ty_of_expr(function, expr)
+    }
+
+    fn check_closure(
+        &mut self,
+        function: HirId,
+        closure: &HirClosure<'src>,
+        expected: Option<&Ty>,
+    ) -> Ty {
+        let (expected_params, expected_ret) = match expected {
+            Some(Ty::FnPtr { params, ret }) if params.len() == closure.params.len() => {
+                (Some(params), Some(ret))
+            }
+            _ => (None, None),
+        };
+
+        for (index, param) in closure.params.iter().enumerate() {
+            let Some(local) = param.local else {
+                self.push_diag(messages::MISSING_LOCAL_ID, &[], param.name.span);
+                continue;
+            };
+            let ty = match (&param.ty, expected_params) {
+                (Some(ty), _) => self.lower_ty(ty),
+                (None, Some(params)) if params[index] != Ty::Unknown => params[index].clone(),
+                (None, _) => {
+                    self.push_diag(
+                        messages::CANNOT_INFER_CLOSURE_PARAM,
+                        &[("name", param.name.node)],
+                        param.name.span,
+                    );
+                    Ty::Unknown
+                }
+            };
+            let info =
+                LocalInfo { name: param.name.node, ty, is_mutable: false, span: param.name.span };
+            self.define_local(function, local, info);
+        }
+
+        for capture in closure.captures.iter() {
+            if capture.mode == HirCaptureMode::ByRef {
+                self.info.by_ref_captures.insert((function, capture.local));
+            }
+        }
+
+        let outer_captures = self.captures.len();
+        self.captures.extend(closure.captures.iter().map(|capture| capture.local));
+        self.closure_depth += 1;
+        let ret = self.check_expr(function, &closure.body);
+        self.closure_depth -= 1;
+        self.captures.truncate(outer_captures);
+        if let Some(expected) = expected_ret {
+            self.expect_assignable(closure.body.span, (**expected).clone(), ret);
+        }
+        self.info.closure_ty(function, closure)
+    }
+
+    /// Converts function item `id` to the value it is used as, reporting
+    /// functions that cannot be one.
+    fn fn_item_value(&mut self, id: HirId, span: Span) -> Ty {
+        self.info.fn_ptr_ty(id).unwrap_or_else(|| {
+            self.push_diag(messages::FUNCTION_NOT_A_VALUE, &[], span);
+            Ty::Unknown
+        })
+    }
+
     fn check_expr(&mut self, function: HirId, expr: &SpannedHirExpr<'src>) -> Ty {
         match &expr.node {
+            HirExpr::Closure(closure) => self.check_closure(function, closure, None),
             HirExpr::Error => Ty::Unknown,
             HirExpr::Literal(literal) => ty_of_literal(literal),
             HirExpr::Resolved(res) => self.check_res(function, *res, expr.span),
+            HirExpr::NonNullLocal(local) => {
+                self.check_res(function, Res::Local(*local), expr.span).non_null()
+            }
+            HirExpr::Binary { op, lhs, rhs } => {
+                let lhs_ty = self.check_expr(function, lhs);
+                let rhs_ty = self.check_expr(function, rhs);
+                match op {
+                    _ if is_comparison(*op) => {
+                        self.check_comparison(*op, (lhs_ty, rhs_ty), rhs.span, expr.span);
+                    }
+                    _ if self.info.operator_method(&lhs_ty, *op).is_some() => {
+                        self.expect_assignable(rhs.span, lhs_ty.clone(), rhs_ty);
+                        return lhs_ty;
+                    }
+                    _ => {
+                        let op = format!("{op:?}");
+                        self.push_diag(messages::UNSUPPORTED_OPERATOR, &[("op", &op)], expr.span);
+                    }
+                }
+                binary_ty(*op)
+            }
             HirExpr::Path(_) => {
                 self.push_diag(messages::UNRESOLVED_EXPRESSION, &[], expr.span);
                 Ty::Unknown
             }
-            HirExpr::Block(block) => {
-                self.check_block(function, block);
-                self.info.ty_of_block(function, block)
+            HirExpr::Turbofish { callee, .. } => {
+                self.check_expr(function, callee);
+                self.push_diag(messages::TURBOFISH_NOT_CALLED, &[], expr.span);
+                Ty::Unknown
+            }
+            HirExpr::Block(_) | HirExpr::Unsafe(_) | HirExpr::If { .. } => {
+                self.check_branching_expr(function, expr, None)
+            }
+            HirExpr::Deref(inner) => match self.check_expr(function, inner) {
+                Ty::Pointer { pointee, .. } => {
+                    if self.unsafe_depth == 0 {
+                        self.push_diag(messages::DEREF_OUTSIDE_UNSAFE, &[], expr.span);
+                    }
+                    *pointee
+                }
+                Ty::Unknown => Ty::Unknown,
+                actual => {
+                    let actual = self.ty_name(&actual);
+                    self.push_diag(messages::CANNOT_DEREF, &[("actual", &actual)], expr.span);
+                    Ty::Unknown
+                }
+            },
+            HirExpr::Call { callee, args } => {
+                self.check_call(function, callee, args, (expr.span, None), false)
+            }
+            HirExpr::Try(inner) => self.check_try(function, (inner, expr.span), None),
+            HirExpr::Field { base, name } => {
+                let base_ty = self.check_expr(function, base);
+                if self.info.field_ty(&base_ty, name.node).is_none()
+                    && self.info.builtin_method(&base_ty, name.node).is_some()
+                {
+                    self.push_diag(messages::METHOD_NOT_CALLED, &[("name", name.node)], expr.span);
+                    return Ty::Unknown;
+                }
+                self.check_field(base_ty, name, expr.span)
+            }
+            HirExpr::TupleField { base, index } => match self.check_expr(function, base) {
+                Ty::Tuple(items) if *index < items.len() => items[*index].clone(),
+                Ty::Unknown => Ty::Unknown,
+                base_ty => {
+                    let ty = self.ty_name(&base_ty);
+                    let name = index.to_string();
+                    self.push_diag(
+                        messages::NO_SUCH_FIELD,
+                        &[("ty", &ty), ("name", &name)],
+                        expr.span,
+                    );
+                    Ty::Unknown
+                }
+            },
+            HirExpr::Tuple(_) => self.check_expr_expecting(function, expr, None),
+            HirExpr::Index { base, index } => self.check_index(function, base, index),
+            HirExpr::Range { start, end, .. } => {
+                // An unsuffixed literal bound adopts the type of the other one.
+                let (start_ty, end_ty) =
+                    if matches!(start.node, HirExpr::Literal(HirLiteral::Int { suffix: None, .. }))
+                    {
+                        let end_ty = self.check_expr(function, end);
+                        (self.check_expr_expecting(function, start, Some(&end_ty)), end_ty)
+                    } else {
+                        let start_ty = self.check_expr(function, start);
+                        (
+                            start_ty.clone(),
+                            self.check_expr_expecting(function, end, Some(&start_ty)),
+                        )
+                    };
+                if !matches!(start_ty, Ty::Int { .. } | Ty::Unknown) {
+                    let actual = self.ty_name(&start_ty);
+                    self.push_diag(messages::NON_INTEGER_RANGE, &[("actual", &actual)], start.span);
+                } else if start_ty.common(&end_ty).is_none() {
+                    self.expect_assignable(end.span, start_ty, end_ty);
+                }
+                self.info.ty_of_expr(function, expr)
+            }
+            HirExpr::For { local, name, iter, body } => {
+                let iter_ty = self.check_expr(function, iter);
+                let item_ty = match self.iterable_item(&iter_ty) {
+                    Some(item) => item,
+                    None => {
+                        self.check_constraint(
+                            iter.span,
+                            TypeConstraint::Implements(ITERATOR_CONTRACT),
+                            iter_ty.clone(),
+                        );
+                        Ty::Unknown
+                    }
+                };
+                match local {
+                    Some(local) => {
+                        let info = LocalInfo {
+                            name: name.node,
+                            ty: item_ty,
+                            is_mutable: false,
+                            span: name.span,
+                        };
+                        self.define_local(function, *local, info);
+                    }
+                    None => self.push_diag(messages::MISSING_LOCAL_ID, &[], name.span),
+                }
+
+                self.check_block(function, body, None);
+                let body_ty = self.info.ty_of_block(function, body);
+                self.expect_assignable(body.span, Ty::Unit, body_ty);
+                Ty::Unit
+            }
+            HirExpr::Struct { path, res, fields } => {
+                self.check_struct_expr(function, (path, *res), fields, None)
+            }
+        }
+    }
+
+    /// Checks the access of field `name` of a value of type `base_ty`.
+    fn check_field(&mut self, base_ty: Ty, name: &SpannedStr<'src>, span: Span) -> Ty {
+        let Some(ty) = self.info.field_ty(&base_ty, name.node) else {
+            if base_ty != Ty::Unknown {
+                let ty = self.ty_name(&base_ty);
+                self.push_diag(
+                    messages::NO_SUCH_FIELD,
+                    &[("ty", &ty), ("name", name.node)],
+                    name.span,
+                );
+            }
+            return Ty::Unknown;
+        };
+        if let Ty::Union { .. } = base_ty
+            && self.unsafe_depth == 0
+        {
+            self.push_diag(messages::UNION_FIELD_OUTSIDE_UNSAFE, &[("name", name.node)], span);
+        }
+        ty
+    }
+
+    /// Checks `base[index]` and returns the element type, or the slice type
+    /// when `index` is a range.
+    fn check_index(
+        &mut self,
+        function: HirId,
+        base: &SpannedHirExpr<'src>,
+        index: &SpannedHirExpr<'src>,
+    ) -> Ty {
+        let base_ty = self.check_expr(function, base);
+        let index_ty = self.check_expr_expecting(function, index, Some(&Ty::USIZE));
+        if base_ty.elem().is_none() {
+            if base_ty != Ty::Unknown {
+                let ty = self.ty_name(&base_ty);
+                self.push_diag(messages::CANNOT_INDEX, &[("ty", &ty)], base.span);
+            }
+            return Ty::Unknown;
+        }
+        match base_ty.indexed(&index_ty) {
+            Some(ty) => ty,
+            None if index_ty == Ty::Unknown => Ty::Unknown,
+            None => {
+                let actual = self.ty_name(&index_ty);
+                self.push_diag(messages::NON_INTEGER_INDEX, &[("actual", &actual)], index.span);
+                Ty::Unknown
+            }
+        }
+    }
+
+    /// Whether `expr` is a place a builtin can write to: a local binding or
+    /// an element, but not a slice.
+    fn is_place(&self, function: HirId, expr: &SpannedHirExpr<'src>) -> bool {
+        match &expr.node {
+            HirExpr::Resolved(Res::Local(_)) | HirExpr::NonNullLocal(_) => true,
+            HirExpr::Index { index, .. } => {
+                !matches!(self.info.ty_of_expr(function, index), Ty::BuiltinType { id: RANGE, .. })
             }
-            HirExpr::Call { callee, args } => self.check_call(function, callee, args, expr.span),
+            _ => false,
         }
     }
 
@@ -238,41 +1458,251 @@ impl<'src> TypeChecker<'src> {
                 )
             }
             Res::Def(id) => Ty::Function(id),
-            Res::Builtin(id) => Ty::Builtin(id),
+            Res::Variant { def, index } => {
+                self.variant_of_kind(def, index, VariantKind::Unit, span);
+                Ty::Enum(def)
+            }
+            Res::Builtin(id) => builtin_res_ty(id),
+            Res::ConstParam(index) => self.info.const_param_ty(function, index),
         }
     }
 
+    /// Returns variant `index` of enum `def` if it is constructed in the
+    /// form of `kind`, and reports it otherwise.
+    fn variant_of_kind(
+        &mut self,
+        def: HirId,
+        index: usize,
+        kind: VariantKind,
+        span: Span,
+    ) -> Option<VariantInfo<'src>> {
+        let variant = self.info.variants(def).get(index)?.clone();
+        if variant.kind == kind {
+            return Some(variant);
+        }
+        let name = format!("{}::{}", self.ty_name(&Ty::Enum(def)), variant.name);
+        let form = match variant.kind {
+            VariantKind::Unit => "without a payload",
+            VariantKind::Tuple => "with `(...)`",
+            VariantKind::Struct => "with `{ ... }`",
+        };
+        self.push_diag(messages::VARIANT_FORM_MISMATCH, &[("name", &name), ("form", form)], span);
+        None
+    }
+
+    /// Checks `Name { field: value, ... }` of a struct or of an enum variant
+    /// with named fields. The generic arguments of a struct are taken from
+    /// the `expected` type of the same struct, or inferred from the values.
+    fn check_struct_expr(
+        &mut self,
+        function: HirId,
+        (path, res): (&HirPath<'src>, Option<Res>),
+        fields: &[HirFieldValue<'src>],
+        expected: Option<&Ty>,
+    ) -> Ty {
+        let path_span = path.span;
+        let resolved = match res {
+            Some(Res::Def(def)) => self.info.adt(def).map(|adt| {
+                let args = Box::default();
+                let ty = match adt.kind {
+                    AdtKind::Struct => Ty::Struct { def, args },
+                    AdtKind::Union => Ty::Union { def, args },
+                };
+                (ty, adt.fields.clone())
+            }),
+            Some(Res::Variant { def, index }) => self
+                .variant_of_kind(def, index, VariantKind::Struct, path_span)
+                .map(|variant| (Ty::Enum(def), variant.fields)),
+            _ => None,
+        };
+        let Some((ty, declared)) = resolved else {
+            for field in fields {
+                self.check_expr(function, &field.value);
+            }
+            return Ty::Unknown;
+        };
+        let adt = match &ty {
+            Ty::Struct { def, .. } | Ty::Union { def, .. } => Some(*def),
+            _ => None,
+        };
+        let generics = adt.map_or(0, |def| self.info.generics(def).len());
+        let explicit = path.segments.last().and_then(|segment| segment.generics.as_deref());
+        let known = match (&ty, expected.map(|ty| ty.clone().non_null())) {
+            (Ty::Struct { def, .. } | Ty::Union { def, .. }, _)
+                if let Some(explicit) = explicit =>
+            {
+                let args = self.lower_generic_args(*def, explicit, path_span);
+                Some(args.unwrap_or_else(|| vec![GenericArg::Type(Ty::Unknown); generics].into()))
+            }
+            (Ty::Struct { def, .. }, Some(Ty::Struct { def: expected, args }))
+            | (Ty::Union { def, .. }, Some(Ty::Union { def: expected, args }))
+                if *def == expected =>
+            {
+                Some(args)
+            }
+            _ if generics == 0 => Some(Box::default()),
+            _ => None,
+        };
+
+        let mut given = Vec::new();
+        let mut values = Vec::new();
+        for field in fields {
+            let name = field.name.node;
+            let field_ty = declared
+                .iter()
+                .find(|declared| declared.name == name)
+                .map(|declared| declared.ty.clone());
+            if field_ty.is_none() {
+                let ty = self.ty_name(&ty);
+                self.push_diag(
+                    messages::NO_SUCH_FIELD,
+                    &[("ty", &ty), ("name", name)],
+                    field.name.span,
+                );
+            } else if given.contains(&name) {
+                self.push_diag(messages::DUPLICATE_FIELD, &[("name", name)], field.name.span);
+            }
+            given.push(name);
+            let expected =
+                field_ty.as_ref().zip(known.as_ref()).map(|(ty, args)| ty.substitute(args));
+            let actual = self.check_expr_expecting(function, &field.value, expected.as_ref());
+            values.push((field_ty.unwrap_or(Ty::Unknown), actual, field.value.span));
+        }
+        if let Ty::Union { .. } = ty {
+            // A union literal writes the storage of exactly one field.
+            if fields.len() != 1 {
+                let name = self.ty_name(&ty);
+                let actual = fields.len().to_string();
+                self.push_diag(
+                    messages::UNION_LITERAL_FIELDS,
+                    &[("ty", &name), ("actual", &actual)],
+                    path_span,
+                );
+            }
+        } else {
+            for field in declared.iter().filter(|declared| !given.contains(&declared.name)) {
+                let ty = self.ty_name(&ty);
+                self.push_diag(
+                    messages::MISSING_FIELD,
+                    &[("name", field.name), ("ty", &ty)],
+                    path_span,
+                );
+            }
+        }
+
+        let Some(def) = adt else {
+            for (expected, actual, span) in values {
+                self.expect_assignable(span, expected, actual);
+            }
+            return ty;
+        };
+        let args = known.unwrap_or_else(|| {
+            let (params, actuals): (Vec<_>, Vec<_>) = values
+                .iter()
+                .map(|(expected, actual, _)| (expected.clone(), actual.clone()))
+                .unzip();
+            let sig = FunctionSig { params: params.into(), ret: Ty::Unit, throws: None };
+            self.infer_generic_args(def, &sig, &actuals, path_span)
+        });
+        for (expected, actual, span) in values {
+            self.expect_assignable(span, expected.substitute(&args), actual);
+        }
+        if !args.is_empty() {
+            self.info.callee_args.insert((function, path_span), args.clone());
+        }
+        match ty {
+            Ty::Union { .. } => Ty::Union { def, args },
+            _ => Ty::Struct { def, args },
+        }
+    }
+
+    /// Checks a call whose result is `expected` to have some type; calls to
+    /// throwing functions must be `propagated` with `?`.
     fn check_call(
         &mut self,
         function: HirId,
         callee: &SpannedHirExpr<'src>,
         args: &[SpannedHirExpr<'src>],
-        span: Span,
+        (span, expected): (Span, Option<&Ty>),
+        propagated: bool,
     ) -> Ty {
-        match self.check_expr(function, callee) {
-            Ty::Function(id) => {
-                let Some(sig) = self.info.function_sig(id).cloned() else {
-                    return Ty::Unknown;
-                };
-                self.check_arg_count(span, sig.params.len(), args.len());
-                for (arg, expected) in args.iter().zip(sig.params.iter()) {
-                    let actual = self.check_expr(function, arg);
-                    self.expect_assignable(arg.span, expected.clone(), actual);
+        let explicit = match &callee.node {
+            HirExpr::Turbofish { args, .. } => Some(&**args),
+            _ => None,
+        };
+        let callee = strip_turbofish(callee);
+        if let HirExpr::Resolved(Res::Variant { def, index }) = callee.node {
+            if let Some(explicit) = explicit {
+                self.check_generic_arg_count(callee.span, 0, explicit.len());
+            }
+            let params = self
+                .variant_of_kind(def, index, VariantKind::Tuple, callee.span)
+                .map(|variant| variant.fields.iter().map(|field| field.ty.clone()).collect());
+            self.check_args(function, params, args, span);
+            return Ty::Enum(def);
+        }
+        let callee_ty = match &callee.node {
+            HirExpr::Field { base, name } => {
+                let base_ty = self.check_expr(function, base);
+                if self.info.field_ty(&base_ty, name.node).is_none()
+                    && let Some(method) = self.info.builtin_method(&base_ty, name.node)
+                {
+                    if let Some(BuiltinDecl {
+                        lowering: BuiltinLowering::Intrinsic(intrinsic), ..
+                    }) = builtin_decl(method)
+                        && intrinsic.writes_receiver()
+                    {
+                        self.check_place_mutable(function, base);
+                    }
+                    let receiver = Some((base_ty, base.span));
+                    let callee = (method, callee.span, None);
+                    return self.check_builtin_call(
+                        function,
+                        callee,
+                        receiver,
+                        args,
+                        (span, expected, propagated),
+                    );
                 }
-                sig.ret
+                if self.info.field_ty(&base_ty, name.node).is_none()
+                    && let Some(&method) = self.iterator_methods.get(name.node)
+                    && self.iterable_item(&base_ty).is_some()
+                {
+                    self.info.methods.insert((function, callee.span), method);
+                    return self.check_function_call(
+                        function,
+                        (method, callee.span, explicit),
+                        Some((base_ty, base.span)),
+                        args,
+                        (span, propagated),
+                    );
+                }
+                self.check_field(base_ty, name, callee.span)
             }
-            Ty::Builtin(builtin) => {
-                let Some(decl) = builtin_decl(builtin) else {
-                    return Ty::Unknown;
-                };
-
-                self.check_arg_count(span, decl.params.len(), args.len());
-                for (arg, constraint) in args.iter().zip(decl.params.iter()) {
-                    let actual = self.check_expr(function, arg);
-                    self.check_constraint(arg.span, *constraint, actual);
+            _ => self.check_expr(function, callee),
+        };
+        match callee_ty {
+            Ty::Function(id) => self.check_function_call(
+                function,
+                (id, callee.span, explicit),
+                None,
+                args,
+                (span, propagated),
+            ),
+            Ty::FnPtr { params, ret } => {
+                if let Some(explicit) = explicit {
+                    self.check_generic_arg_count(callee.span, 0, explicit.len());
                 }
-                builtin_return_ty(decl.ret)
+                self.check_args(function, Some(params.into_vec()), args, span);
+                *ret
             }
+            Ty::Builtin(builtin) => self.check_builtin_call(
+                function,
+                (builtin, callee.span, explicit),
+                None,
+                args,
+                (span, expected, propagated),
+            ),
             Ty::Unknown => {
                 for arg in args {
                     self.check_expr(function, arg);
@@ -283,68 +1713,355 @@ impl<'src> TypeChecker<'src> {
                 for arg in args {
                     self.check_expr(function, arg);
                 }
-                let actual = format!("{actual:?}");
+                let actual = self.ty_name(&actual);
                 self.push_diag(messages::NOT_CALLABLE, &[("actual", &actual)], span);
                 Ty::Unknown
             }
         }
     }
 
-    fn check_arg_count(&mut self, span: Span, expected: usize, actual: usize) {
-        if expected != actual {
-            let expected = expected.to_string();
-            let actual = actual.to_string();
-            self.push_diag(
-                messages::ARGUMENT_COUNT_MISMATCH,
-                &[("expected", &expected), ("actual", &actual)],
-                span,
-            );
+    /// Checks a call of function `id`, whose first parameter is the
+    /// `receiver` for method calls. Generic arguments not given `explicit`ly
+    /// are inferred from the arguments.
+    fn check_function_call(
+        &mut self,
+        function: HirId,
+        (id, callee_span, explicit): (HirId, Span, Option<&[HirGenericArg<'src>]>),
+        receiver: Option<(Ty, Span)>,
+        args: &[SpannedHirExpr<'src>],
+        (span, propagated): (Span, bool),
+    ) -> Ty {
+        let Some(sig) = self.info.function_sig(id).cloned() else {
+            return Ty::Unknown;
+        };
+        if self.info.is_extern(id) && self.unsafe_depth == 0 {
+            self.push_diag(messages::EXTERN_CALL_OUTSIDE_UNSAFE, &[], span);
+        }
+        let offset = usize::from(receiver.is_some());
+        self.check_arg_count(span, sig.params.len().saturating_sub(offset), args.len());
+        let explicit = explicit.map(|explicit| {
+            self.lower_generic_args(id, explicit, callee_span).unwrap_or_else(|| {
+                vec![GenericArg::Type(Ty::Unknown); self.info.generics(id).len()].into()
+            })
+        });
+        // Closures are checked after the other arguments, whose types give
+        // the generic arguments their parameters depend on.
+        let is_generic = !self.info.generics(id).is_empty();
+        let mut actuals = vec![Ty::Unknown; offset + args.len()];
+        let mut spans: Vec<_> = args.iter().map(|arg| arg.span).collect();
+        if let Some((ty, span)) = receiver {
+            actuals[0] = ty;
+            spans.insert(0, span);
+        }
+        let (closures, others): (Vec<_>, Vec<_>) =
+            (0..args.len()).partition(|index| matches!(args[*index].node, HirExpr::Closure(_)));
+        for index in others.into_iter().chain(closures) {
+            let param = sig.params.get(offset + index);
+            let expected = match &explicit {
+                Some(explicit) => param.map(|ty| ty.substitute(explicit)),
+                None if is_generic && matches!(args[index].node, HirExpr::Closure(_)) => {
+                    let inferred = self.infer_from_args(id, &sig, &actuals);
+                    let inferred: Vec<_> = inferred
+                        .into_iter()
+                        .map(|arg| arg.unwrap_or(GenericArg::Type(Ty::Unknown)))
+                        .collect();
+                    param.map(|ty| ty.substitute(&inferred))
+                }
+                // Parameters that do not mention the generics are known
+                // before inference.
+                None => param.filter(|ty| !is_generic || ty.substitute(&[]) == **ty).cloned(),
+            };
+            actuals[offset + index] =
+                self.check_expr_expecting(function, &args[index], expected.as_ref());
         }
+        let generic_args = match explicit {
+            Some(explicit) => explicit,
+            None => self.infer_generic_args(id, &sig, &actuals, span),
+        };
+        for ((span, actual), expected) in spans.into_iter().zip(actuals).zip(sig.params.iter()) {
+            self.expect_assignable(span, expected.substitute(&generic_args), actual);
+        }
+        if !generic_args.is_empty() {
+            self.info.callee_args.insert((function, callee_span), generic_args.clone());
+        }
+        if let Some(error) = &sig.throws
+            && !propagated
+        {
+            self.push_unhandled_error(&error.substitute(&generic_args), span);
+        }
+        sig.ret.substitute(&generic_args)
     }
 
-    fn expect_assignable(&mut self, span: Span, expected: Ty, actual: Ty) {
-        if expected == Ty::Unknown || actual == Ty::Unknown || expected == actual {
+    /// Checks the arguments of a call at `span` against `params`, which are
+    /// not generic; without them the arguments are only checked themselves.
+    fn check_args(
+        &mut self,
+        function: HirId,
+        params: Option<Vec<Ty>>,
+        args: &[SpannedHirExpr<'src>],
+        span: Span,
+    ) {
+        let Some(params) = params else {
+            for arg in args {
+                self.check_expr(function, arg);
+            }
             return;
+        };
+        self.check_arg_count(span, params.len(), args.len());
+        for (arg, expected) in args.iter().zip(params.iter()) {
+            let actual = self.check_expr_expecting(function, arg, Some(expected));
+            self.expect_assignable(arg.span, expected.clone(), actual);
+        }
+        for arg in args.iter().skip(params.len()) {
+            self.check_expr(function, arg);
+        }
+    }
+
+    /// Checks a call of builtin `id`, whose first parameter is the
+    /// `receiver` for method calls. Generic parameters not given
+    /// `explicit`ly are inferred from the arguments and from the type
+    /// `expected` of the result.
+    fn check_builtin_call(
+        &mut self,
+        function: HirId,
+        (id, callee_span, explicit): (BuiltinId, Span, Option<&[HirGenericArg<'src>]>),
+        receiver: Option<(Ty, Span)>,
+        args: &[SpannedHirExpr<'src>],
+        (span, expected, propagated): (Span, Option<&Ty>, bool),
+    ) -> Ty {
+        let Some(decl) = builtin_decl(id) else {
+            return Ty::Unknown;
+        };
+        if matches!(decl.ret, BuiltinReturn::Fallible(_)) && !propagated {
+            self.push_unhandled_error(&Ty::Str, span);
+        }
+        let params = &decl.params[usize::from(receiver.is_some())..];
+        self.check_arg_count(span, params.len(), args.len());
+
+        let mut inferred = match explicit {
+            Some(explicit) => self.lower_builtin_generic_args(decl, explicit, callee_span),
+            None => vec![None; decl.generics.len()],
+        };
+        let mut exact = Vec::new();
+        if let (Some((ty, span)), Some(TypeConstraint::Exactly(param))) =
+            (receiver, decl.params.first())
+        {
+            let param = builtin_ty(*param);
+            infer_generic_args(&param, &ty, &mut inferred);
+            exact.push((param, ty, span));
+        }
+        for (arg, constraint) in args.iter().zip(params) {
+            let param = match constraint {
+                TypeConstraint::Exactly(param) => builtin_ty(*param),
+                TypeConstraint::OneOf(options) => {
+                    let actual = self.check_expr(function, arg);
+                    let param = options
+                        .iter()
+                        .map(|option| builtin_ty(*option))
+                        .find(|option| same_shape(option, &actual))
+                        .unwrap_or_else(|| builtin_ty(options[0]));
+                    infer_generic_args(&param, &actual, &mut inferred);
+                    exact.push((param, actual, arg.span));
+                    continue;
+                }
+                _ => {
+                    let actual = self.check_expr(function, arg);
+                    self.check_constraint(arg.span, *constraint, actual);
+                    continue;
+                }
+            };
+            let known = inferred
+                .iter()
+                .map(|arg| arg.clone().unwrap_or(GenericArg::Type(Ty::Unknown)))
+                .collect::<Vec<_>>();
+            let actual = self.check_expr_expecting(function, arg, Some(&param.substitute(&known)));
+            infer_generic_args(&param, &actual, &mut inferred);
+            exact.push((param, actual, arg.span));
+        }
+        for arg in args.iter().skip(params.len()) {
+            self.check_expr(function, arg);
+        }
+        // Builtins that write to their leading arguments need places that
+        // hold exactly the parameter type, or they could store a value the
+        // place cannot hold.
+        let places = match decl.lowering {
+            BuiltinLowering::Place(op) => op.places(),
+            _ => 0,
+        };
+        // They read the place before writing it, so even a `let` declared
+        // without a value must be mutable to be passed.
+        for arg in args.iter().take(places) {
+            match &arg.node {
+                HirExpr::Resolved(Res::Local(local)) | HirExpr::NonNullLocal(local) => {
+                    self.check_binding_mutable(function, *local, arg.span, false);
+                }
+                _ if !self.is_place(function, arg) => {
+                    self.push_diag(messages::PLACE_ARGUMENT, &[("name", decl.name)], arg.span);
+                }
+                _ => self.check_place_mutable(function, arg),
+            }
+        }
+
+        let ret = builtin_return_ty(decl.ret);
+        if let Some(expected) = expected {
+            infer_generic_args(&ret, expected, &mut inferred);
+        }
+        let generic_args: Box<[GenericArg]> = decl
+            .generics
+            .iter()
+            .zip(inferred)
+            .map(|(name, arg)| {
+                arg.unwrap_or_else(|| {
+                    self.push_diag(messages::CANNOT_INFER_GENERIC, &[("name", name)], span);
+                    GenericArg::Type(Ty::Unknown)
+                })
+            })
+            .collect();
+        for (index, (param, actual, span)) in exact.into_iter().enumerate() {
+            let param = param.substitute(&generic_args);
+            if index < places && param.accepts(&actual) && !actual.accepts(&param) {
+                let expected = self.ty_name(&param);
+                let actual = self.ty_name(&actual);
+                self.push_diag(
+                    messages::TYPE_MISMATCH,
+                    &[("expected", &expected), ("actual", &actual)],
+                    span,
+                );
+            } else {
+                self.expect_assignable(span, param, actual);
+            }
+        }
+        for (arg, bounds) in generic_args.iter().zip(decl.bounds) {
+            if let GenericArg::Type(ty) = arg {
+                for bound in bounds.iter() {
+                    self.check_constraint(span, TypeConstraint::Implements(*bound), ty.clone());
+                }
+            }
+        }
+        let ret = ret.substitute(&generic_args);
+        if !generic_args.is_empty() {
+            self.info.callee_args.insert((function, callee_span), generic_args);
         }
+        ret
+    }
 
-        let expected = format!("{expected:?}");
-        let actual = format!("{actual:?}");
-        self.push_diag(
-            messages::TYPE_MISMATCH,
-            &[("expected", &expected), ("actual", &actual)],
-            span,
+    /// Reports a call at `span` that throws `error` without `?`.
+    fn push_unhandled_error(&mut self, error: &Ty, span: Span) {
+        let error = self.ty_name(error);
+        self.diags.push(
+            *Diagnostic::error(DiagMessage::new(messages::UNHANDLED_ERROR, &[("error", &error)]))
+                .add_label(DiagLabel::silent_primary(span))
+                .set_note(DiagNote::new(messages::PROPAGATE_WITH_TRY, &[])),
         );
     }
 
-    fn check_constraint(&mut self, span: Span, constraint: TypeConstraint, actual: Ty) {
-        let TypeConstraint::Implements(contract_id) = constraint;
-        if actual == Ty::Unknown || ty_implements(&actual, contract_id) {
+    /// Checks that an error of type `actual` thrown at `span` can leave the
+    /// enclosing function.
+    fn check_thrown(&mut self, function: HirId, actual: Ty, span: Span) {
+        if self.closure_depth > 0 {
+            self.push_diag(messages::THROW_IN_CLOSURE, &[], span);
             return;
         }
+        match self.info.function_sig(function).map(|sig| sig.throws.clone()) {
+            Some(Some(expected)) => self.expect_assignable(span, expected, actual),
+            Some(None) => {
+                let error = self.ty_name(&actual);
+                self.diags.push(
+                    *Diagnostic::error(DiagMessage::new(
+                        messages::THROW_OUTSIDE_THROWING_FUNCTION,
+                        &[("error", &error)],
+                    ))
+                    .add_label(DiagLabel::silent_primary(span))
+                    .set_note(DiagNote::new(messages::ADD_THROWS_CLAUSE, &[("error", &error)])),
+                );
+            }
+            None => {}
+        }
+    }
 
-        let contract = contract_id.to_string();
-        let actual = format!("{actual:?}");
-        self.push_diag(
-            messages::CONTRACT_NOT_IMPLEMENTED,
-            &[("actual", &actual), ("contract", &contract)],
-            span,
-        );
+    fn check_arg_count(&mut self, span: Span, expected: usize, actual: usize) {
+        if expected != actual {
+            let expected = expected.to_string();
+            let actual = actual.to_string();
+            self.push_diag(
+                messages::ARGUMENT_COUNT_MISMATCH,
+                &[("expected", &expected), ("actual", &actual)],
+                span,
+            );
+        }
     }
 
     fn lower_ty(&mut self, ty: &SpannedHirType<'src>) -> Ty {
         match &ty.node {
             HirType::Error => Ty::Unknown,
             HirType::Primitive(primitive) => primitive_ty(*primitive),
-            HirType::Struct { def, .. } => Ty::Struct(*def),
+            HirType::Param(index) => Ty::Param(*index),
+            HirType::Struct { def, generics } => {
+                match self.lower_generic_args(*def, generics, ty.span) {
+                    Some(args) => Ty::Struct { def: *def, args },
+                    None => Ty::Unknown,
+                }
+            }
+            HirType::Union { def, generics } => {
+                match self.lower_generic_args(*def, generics, ty.span) {
+                    Some(args) => Ty::Union { def: *def, args },
+                    None => Ty::Unknown,
+                }
+            }
             HirType::Enum { def, .. } => Ty::Enum(*def),
+            HirType::Builtin { ty: id, generics } => {
+                let params = builtin_type_decl(*id).map_or(&[][..], |decl| decl.generics);
+                if params.len() != generics.len() {
+                    let expected = params.len().to_string();
+                    let actual = generics.len().to_string();
+                    self.push_diag(
+                        messages::GENERIC_ARGUMENT_COUNT_MISMATCH,
+                        &[("expected", &expected), ("actual", &actual)],
+                        ty.span,
+                    );
+                    return Ty::Unknown;
+                }
+                let bounds = builtin_type_decl(*id).map_or(&[][..], |decl| decl.bounds);
+                let mut args = Vec::with_capacity(generics.len());
+                for (index, (param, arg)) in params.iter().zip(generics.iter()).enumerate() {
+                    match arg {
+                        HirGenericArg::Type(arg) => {
+                            let lowered = self.lower_ty(arg);
+                            for contract_id in bounds.get(index).copied().unwrap_or_default() {
+                                let constraint = TypeConstraint::Implements(*contract_id);
+                                self.check_constraint(arg.span, constraint, lowered.clone());
+                            }
+                            args.push(lowered);
+                        }
+                        HirGenericArg::Const(expr) => {
+                            self.push_diag(
+                                messages::EXPECTED_TYPE_ARGUMENT,
+                                &[("name", param)],
+                                expr.span,
+                            );
+                            return Ty::Unknown;
+                        }
+                    }
+                }
+                Ty::BuiltinType { id: *id, args: args.into_boxed_slice() }
+            }
             HirType::Unit => Ty::Unit,
             HirType::Tuple(items) => {
                 let items = items.iter().map(|item| self.lower_ty(item)).collect();
                 Ty::Tuple(items)
             }
+            HirType::Slice(elem) => Ty::Slice(Box::new(self.lower_ty(elem))),
+            HirType::Nullable(inner) => self.lower_ty(inner).nullable(),
+            HirType::Pointer { mutable, pointee } => {
+                Ty::Pointer { mutable: *mutable, pointee: Box::new(self.lower_ty(pointee)) }
+            }
             HirType::Array { elem, len } => {
                 Ty::Array { elem: Box::new(self.lower_ty(elem)), len: const_array_len(len) }
             }
+            HirType::Function { params, ret } => Ty::FnPtr {
+                params: params.iter().map(|param| self.lower_ty(param)).collect(),
+                ret: Box::new(self.lower_ty(ret)),
+            },
             HirType::Unresolved(_) => {
                 self.push_diag(messages::UNRESOLVED_TYPE, &[], ty.span);
                 Ty::Unknown
@@ -366,19 +2083,55 @@ impl<'src> Default for TypeChecker<'src> {
     }
 }
 
+/// Returns the type of a builtin used as a value: constants have the type
+/// of their value, and functions their own type to be called.
+fn builtin_res_ty(id: BuiltinId) -> Ty {
+    match builtin_decl(id) {
+        Some(decl) if matches!(decl.lowering, BuiltinLowering::Constant(_)) => {
+            builtin_return_ty(decl.ret)
+        }
+        _ => Ty::Builtin(id),
+    }
+}
+
 fn builtin_return_ty(ret: BuiltinReturn) -> Ty {
     match ret {
         BuiltinReturn::Unit => Ty::Unit,
+        BuiltinReturn::Value(ty) => builtin_ty(ty),
+        BuiltinReturn::Slot(ty) => builtin_ty(ty).nullable(),
+        BuiltinReturn::Indirect(ty) | BuiltinReturn::Fallible(ty) => builtin_ty(ty),
+        BuiltinReturn::Never => Ty::Never,
     }
 }
 
-fn ty_implements(ty: &Ty, contract_id: ContractId) -> bool {
-    let primitive = match ty {
-        Ty::Str => PrimitiveType::Str,
-        _ => return false,
-    };
+/// Returns the type of a builtin signature, with its generic parameters as
+/// [`Ty::Param`].
+fn builtin_ty(ty: BuiltinTy) -> Ty {
+    match ty {
+        BuiltinTy::Unit => Ty::Unit,
+        BuiltinTy::Bool => Ty::Bool,
+        BuiltinTy::Str => Ty::Str,
+        BuiltinTy::Usize | BuiltinTy::U64 => Ty::USIZE,
+        BuiltinTy::I64 => Ty::Int { signed: true, bits: TypeBits::B64 },
+        BuiltinTy::F64 => Ty::Float { bits: TypeBits::B64 },
+        BuiltinTy::Generic(index) => Ty::Param(index),
+        BuiltinTy::Nullable(inner) => builtin_ty(*inner).nullable(),
+        BuiltinTy::Slice(elem) => Ty::Slice(Box::new(builtin_ty(*elem))),
+        BuiltinTy::Builtin(id, args) => {
+            Ty::BuiltinType { id, args: args.iter().map(|arg| builtin_ty(*arg)).collect() }
+        }
+    }
+}
+
+pub fn is_comparison(op: BinaryOp) -> bool {
+    matches!(
+        op,
+        BinaryOp::Eq | BinaryOp::Ne | BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge
+    )
+}
 
-    primitive_implements(primitive, contract_id)
+fn binary_ty(op: BinaryOp) -> Ty {
+    if is_comparison(op) { Ty::Bool } else { Ty::Unknown }
 }
 
 pub fn ty_of_literal(literal: &HirLiteral<'_>) -> Ty {
@@ -388,9 +2141,24 @@ pub fn ty_of_literal(literal: &HirLiteral<'_>) -> Ty {
         HirLiteral::Bool(_) => Ty::Bool,
         HirLiteral::Char(_) => Ty::Char,
         HirLiteral::Str(_) => Ty::Str,
+        HirLiteral::Null => Ty::Null,
+    }
+}
+
+fn bits_count(bits: TypeBits) -> u32 {
+    match bits {
+        TypeBits::B8 => 8,
+        TypeBits::B16 => 16,
+        TypeBits::B32 => 32,
+        TypeBits::B64 => 64,
+        TypeBits::B128 => 128,
     }
 }
 
+fn comma_list(items: impl Iterator<Item = String>) -> String {
+    items.collect::<Vec<_>>().join(", ")
+}
+
 fn primitive_ty(ty: HirPrimitiveTy) -> Ty {
     match ty {
         HirPrimitiveTy::I8 => Ty::Int { signed: true, bits: TypeBits::B8 },
@@ -443,124 +2211,11 @@ fn const_array_len(expr: &SpannedHirExpr<'_>) -> Option<u64> {
     u64::try_from(*value).ok()
 }
 
+mod contracts;
+mod inference;
+pub mod layout;
 mod messages;
+mod narrowing;
 
 #[cfg(test)]
-mod tests {
-    use runec_ast::SpannedStr;
-    use runec_builtins::PRINTLN;
-    use runec_hir::expression::{HirExpr, HirLiteral};
-    use runec_hir::ids::HirId;
-    use runec_hir::item::{HirFunction, HirItem};
-    use runec_hir::map::HirMap;
-    use runec_hir::resolution::Res;
-    use runec_hir::statement::{HirBlock, HirStmt};
-    use runec_hir::ty::{HirPrimitiveTy, HirType};
-    use runec_source::byte_pos::BytePos;
-    use runec_source::source_map::SourceId;
-    use runec_source::span::{Span, Spanned};
-
-    use super::{Ty, TypeChecker};
-
-    const SRC: SourceId = SourceId::from_usize(0);
-
-    fn sp(lo: usize, hi: usize) -> Span {
-        Span::new(BytePos::from_usize(lo), BytePos::from_usize(hi), SRC)
-    }
-
-    fn s<T>(node: T) -> Spanned<T> {
-        Spanned::new(node, sp(0, 0))
-    }
-
-    #[test]
-    fn reports_function_return_type_mismatch() {
-        let mut hir = HirMap::new();
-        hir.push(HirItem::Function(HirFunction {
-            id: HirId::from_usize(0),
-            name: SpannedStr::new("main", sp(0, 0)),
-            params: Box::new([]),
-            ret_ty: s(HirType::Primitive(HirPrimitiveTy::I32)),
-            body: HirBlock {
-                stmts: Box::new([]),
-                tail: Some(Box::new(s(HirExpr::Literal(HirLiteral::Bool(true))))),
-                span: sp(0, 0),
-            },
-            span: sp(0, 0),
-        }));
-
-        let result = TypeChecker::new().check(&hir);
-        assert_eq!(result.diags.len(), 1);
-        assert_eq!(result.diags[0].labels[0].span, sp(0, 0));
-        assert!(result.diags[0].message.message.contains("expected type"));
-        assert!(result.diags[0].message.message.contains("Bool"));
-    }
-
-    #[test]
-    fn accepts_string_for_display_builtin() {
-        let mut hir = HirMap::new();
-        hir.push(function_with_builtin_arg(HirLiteral::Str("hello".into())));
-
-        let result = TypeChecker::new().check(&hir);
-        assert!(result.diags.is_empty());
-    }
-
-    #[test]
-    fn distinguishes_builtin_reference_from_call_result() {
-        let function_id = HirId::from_usize(0);
-        let builtin = s(HirExpr::Resolved(Res::Builtin(PRINTLN)));
-        let call = s(HirExpr::Call {
-            callee: Box::new(s(HirExpr::Resolved(Res::Builtin(PRINTLN)))),
-            args: Box::new([s(HirExpr::Literal(HirLiteral::Str("hello".into())))]),
-        });
-
-        let mut hir = HirMap::new();
-        hir.push(HirItem::Function(HirFunction {
-            id: function_id,
-            name: SpannedStr::new("main", sp(0, 0)),
-            params: Box::new([]),
-            ret_ty: s(HirType::Unit),
-            body: HirBlock { stmts: Box::new([HirStmt::Expr(call)]), tail: None, span: sp(0, 0) },
-            span: sp(0, 0),
-        }));
-
-        let result = TypeChecker::new().check(&hir);
-        assert!(result.diags.is_empty());
-        assert_eq!(result.info.ty_of_expr(function_id, &builtin), Ty::Builtin(PRINTLN));
-
-        let HirItem::Function(function) = hir.get(function_id) else {
-            panic!("expected function");
-        };
-        let HirStmt::Expr(call) = &function.body.stmts[0] else {
-            panic!("expected call");
-        };
-        assert_eq!(result.info.ty_of_expr(function_id, call), Ty::Unit);
-    }
-
-    #[test]
-    fn rejects_type_without_display_impl() {
-        let mut hir = HirMap::new();
-        hir.push(function_with_builtin_arg(HirLiteral::Int { value: 42, suffix: None }));
-
-        let result = TypeChecker::new().check(&hir);
-        assert_eq!(result.diags.len(), 1);
-        assert!(result.diags[0].message.message.contains("core::fmt::Display"));
-    }
-
-    fn function_with_builtin_arg(literal: HirLiteral<'static>) -> HirItem<'static> {
-        HirItem::Function(HirFunction {
-            id: HirId::from_usize(0),
-            name: SpannedStr::new("main", sp(0, 0)),
-            params: Box::new([]),
-            ret_ty: s(HirType::Unit),
-            body: HirBlock {
-                stmts: Box::new([HirStmt::Expr(s(HirExpr::Call {
-                    callee: Box::new(s(HirExpr::Resolved(Res::Builtin(PRINTLN)))),
-                    args: Box::new([s(HirExpr::Literal(literal))]),
-                }))]),
-                tail: None,
-                span: sp(0, 0),
-            },
-            span: sp(0, 0),
-        })
-    }
-}
+mod tests;
@@ -0,0 +1,91 @@
+use runec_ast::operators::BinaryOp;
+use runec_builtins::{EQ_CONTRACT, ORD_CONTRACT};
+use runec_errors::diagnostics::Diagnostic;
+use runec_errors::labels::{DiagLabel, DiagNote};
+use runec_errors::message::DiagMessage;
+use runec_source::span::Span;
+
+use super::{Ty, TypeChecker, messages};
+
+impl<'src> TypeChecker<'src> {
+    /// Checks the operands of a comparison. `==` and `!=` compare `null` with
+    /// any value, and values of a shared `Eq` or `Ord` type; the other
+    /// comparisons need a shared `Ord` type.
+    pub(super) fn check_comparison(
+        &mut self,
+        op: BinaryOp,
+        (lhs, rhs): (Ty, Ty),
+        rhs_span: Span,
+        span: Span,
+    ) {
+        let equality = matches!(op, BinaryOp::Eq | BinaryOp::Ne);
+        if equality && (lhs == Ty::Null || rhs == Ty::Null) {
+            return;
+        }
+        if [&lhs, &rhs].iter().any(|ty| matches!(ty, Ty::Unknown | Ty::Never)) {
+            return;
+        }
+        let Some(ty) = lhs.common(&rhs) else {
+            self.expect_assignable(rhs_span, lhs, rhs);
+            return;
+        };
+        let comparable = lhs == rhs
+            && (self.implements(&ty, ORD_CONTRACT)
+                || (equality && self.implements(&ty, EQ_CONTRACT)));
+        if !comparable {
+            let ty = self.ty_name(&ty);
+            let op = comparison_symbol(op);
+            self.push_diag(messages::CANNOT_COMPARE, &[("ty", &ty), ("op", op)], span);
+        }
+    }
+
+    pub(super) fn expect_assignable(&mut self, span: Span, expected: Ty, actual: Ty) {
+        if expected.accepts(&actual) {
+            return;
+        }
+        // Function items convert to `act(...)` values of the same signature.
+        if let (Ty::FnPtr { .. }, Ty::Function(id)) = (&expected, &actual) {
+            let actual = self.fn_item_value(*id, span);
+            return self.expect_assignable(span, expected, actual);
+        }
+
+        if let Ty::Nullable(inner) = &actual
+            && expected.accepts(inner)
+        {
+            self.push_nullable_diag(span, &expected, &actual);
+            return;
+        }
+
+        let expected = self.ty_name(&expected);
+        let actual = self.ty_name(&actual);
+        self.push_diag(
+            messages::TYPE_MISMATCH,
+            &[("expected", &expected), ("actual", &actual)],
+            span,
+        );
+    }
+
+    pub(super) fn push_nullable_diag(&mut self, span: Span, expected: &Ty, actual: &Ty) {
+        let expected = self.ty_name(expected);
+        let actual = self.ty_name(actual);
+        self.diags.push(
+            *Diagnostic::error(DiagMessage::new(
+                messages::NULLABLE_MISMATCH,
+                &[("expected", &expected), ("actual", &actual)],
+            ))
+            .add_label(DiagLabel::silent_primary(span))
+            .set_note(DiagNote::new(messages::CHECK_FOR_NULL, &[])),
+        );
+    }
+}
+
+fn comparison_symbol(op: BinaryOp) -> &'static str {
+    match op {
+        BinaryOp::Eq => "==",
+        BinaryOp::Ne => "!=",
+        BinaryOp::Lt => "<",
+        BinaryOp::Le => "<=",
+        BinaryOp::Gt => ">",
+        _ => ">=",
+    }
+}
@@ -0,0 +1,2300 @@
+use runec_ast::SpannedStr;
+use runec_ast::expression::IntSuffix;
+use runec_ast::operators::BinaryOp;
+use runec_ast::statement::Visibility;
+use runec_builtins::{
+    DECIMAL, DECIMAL_FROM_INT, DISPLAY_CONTRACT, DURATION, DURATION_FROM_MILLIS, FILES_EXISTS,
+    FILES_WRITE, HASH_MAP, HASH_SET, INSTANT, INSTANT_NOW, INTO_ITERATOR_CONTRACT, JSON,
+    JSON_FROM_STR, JSON_NEW_OBJECT, JSON_STRINGIFY, MAP_NEW, MATH_CHECKED_ADD, MATH_MIN, MATH_PI,
+    MATH_SQRT, MEM_REPLACE, MEM_SIZE_OF, MEM_SWAP, MEM_TAKE, PANIC, PRINTLN, RANDOM_FLOAT,
+    RANDOM_INT_RANGE, RANDOM_SEED, RANDOM_SHUFFLE, RANGE, ROUNDING_HALF_UP, ROUNDING_MODE, SET_NEW,
+    TIME_SLEEP, TypeBits, VEC, VEC_NEW,
+};
+use runec_hir::expression::{
+    HirCapture, HirCaptureMode, HirClosure, HirClosureParam, HirExpr, HirFieldValue, HirLiteral,
+};
+use runec_hir::ids::{HirId, HirLocalId, HirModuleId};
+use runec_hir::item::{
+    HirBound, HirExternFunction, HirField, HirFunction, HirFunctionParam, HirGenericParam,
+    HirGenericParamKind, HirItem, HirStruct, HirUnion,
+};
+use runec_hir::map::HirMap;
+use runec_hir::path::{HirGenericArg, HirPath, HirPathSegment};
+use runec_hir::resolution::Res;
+use runec_hir::statement::{HirBlock, HirStmt};
+use runec_hir::ty::{HirPrimitiveTy, HirType};
+use runec_source::byte_pos::BytePos;
+use runec_source::source_map::SourceId;
+use runec_source::span::{Span, Spanned};
+
+use super::layout::Layout;
+use super::{GenericArg, Ty, TypeChecker, ty_of_literal};
+
+const SRC: SourceId = SourceId::from_usize(0);
+
+fn sp(lo: usize, hi: usize) -> Span {
+    Span::new(BytePos::from_usize(lo), BytePos::from_usize(hi), SRC)
+}
+
+fn s<T>(node: T) -> Spanned<T> {
+    Spanned::new(node, sp(0, 0))
+}
+
+#[test]
+fn reports_function_return_type_mismatch() {
+    let mut hir = HirMap::new();
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: HirId::from_usize(0),
+        name: SpannedStr::new("main", sp(0, 0)),
+        generics: Box::new([]),
+        params: Box::new([]),
+        ret_ty: s(HirType::Primitive(HirPrimitiveTy::I32)),
+        throws: None,
+        body: HirBlock {
+            stmts: Box::new([]),
+            tail: Some(Box::new(s(HirExpr::Literal(HirLiteral::Bool(true))))),
+            span: sp(0, 0),
+        },
+        span: sp(0, 0),
+    }));
+
+    let result = TypeChecker::new().check(&hir);
+    assert_eq!(result.diags.len(), 1);
+    assert_eq!(result.diags[0].labels[0].span, sp(0, 0));
+    assert!(result.diags[0].message.message.contains("expected type"));
+    assert!(result.diags[0].message.message.contains("`bool`"));
+}
+
+#[test]
+fn accepts_string_for_display_builtin() {
+    let mut hir = HirMap::new();
+    hir.push(function_with_builtin_arg(HirLiteral::Str("hello".into())));
+
+    let result = TypeChecker::new().check(&hir);
+    assert!(result.diags.is_empty());
+}
+
+#[test]
+fn distinguishes_builtin_reference_from_call_result() {
+    let function_id = HirId::from_usize(0);
+    let builtin = s(HirExpr::Resolved(Res::Builtin(PRINTLN)));
+    let call = s(HirExpr::Call {
+        callee: Box::new(s(HirExpr::Resolved(Res::Builtin(PRINTLN)))),
+        args: Box::new([s(HirExpr::Literal(HirLiteral::Str("hello".into())))]),
+    });
+
+    let mut hir = HirMap::new();
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: function_id,
+        name: SpannedStr::new("main", sp(0, 0)),
+        generics: Box::new([]),
+        params: Box::new([]),
+        ret_ty: s(HirType::Unit),
+        throws: None,
+        body: HirBlock { stmts: Box::new([HirStmt::Expr(call)]), tail: None, span: sp(0, 0) },
+        span: sp(0, 0),
+    }));
+
+    let result = TypeChecker::new().check(&hir);
+    assert!(result.diags.is_empty());
+    assert_eq!(result.info.ty_of_expr(function_id, &builtin), Ty::Builtin(PRINTLN));
+
+    let HirItem::Function(function) = hir.get(function_id) else {
+        panic!("expected function");
+    };
+    let HirStmt::Expr(call) = &function.body.stmts[0] else {
+        panic!("expected call");
+    };
+    assert_eq!(result.info.ty_of_expr(function_id, call), Ty::Unit);
+}
+
+#[test]
+fn rejects_type_without_display_impl() {
+    for (literal, ty) in [
+        (HirLiteral::Float { value: 4.2, suffix: None }, "f64"),
+        (HirLiteral::Int { value: 42, suffix: Some(IntSuffix::I128) }, "i128"),
+    ] {
+        let mut hir = HirMap::new();
+        hir.push(function_with_builtin_arg(literal));
+
+        let result = TypeChecker::new().check(&hir);
+        assert_eq!(result.diags.len(), 1);
+        assert_eq!(
+            result.diags[0].message.message,
+            format!("type `{ty}` does not implement `core::fmt::Display`")
+        );
+    }
+
+    let mut hir = HirMap::new();
+    hir.push(function_with_builtin_arg(HirLiteral::Int { value: 42, suffix: None }));
+    assert!(TypeChecker::new().check(&hir).diags.is_empty());
+}
+
+#[test]
+fn infers_deferred_local_from_assignment_and_checks_condition() {
+    let x = HirLocalId::from_usize(0);
+    let function_id = HirId::from_usize(0);
+    let mut hir = HirMap::new();
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: function_id,
+        name: SpannedStr::new("main", sp(0, 0)),
+        generics: Box::new([]),
+        params: Box::new([]),
+        ret_ty: s(HirType::Unit),
+        throws: None,
+        body: HirBlock {
+            stmts: Box::new([
+                HirStmt::Let {
+                    local: Some(x),
+                    name: SpannedStr::new("x", sp(0, 0)),
+                    is_mutable: false,
+                    ty: None,
+                    init: None,
+                    span: sp(0, 0),
+                },
+                HirStmt::Expr(s(HirExpr::If {
+                    cond: Box::new(Spanned::new(
+                        HirExpr::Literal(HirLiteral::Int { value: 1, suffix: None }),
+                        sp(5, 6),
+                    )),
+                    then: HirBlock {
+                        stmts: Box::new([HirStmt::Assign {
+                            target: s(HirExpr::Resolved(Res::Local(x))),
+                            value: s(HirExpr::Literal(HirLiteral::Str("a".into()))),
+                            span: sp(0, 0),
+                        }]),
+                        tail: None,
+                        span: sp(0, 0),
+                    },
+                    else_: None,
+                })),
+            ]),
+            tail: None,
+            span: sp(0, 0),
+        },
+        span: sp(0, 0),
+    }));
+
+    let result = TypeChecker::new().check(&hir);
+    assert_eq!(result.diags.len(), 1);
+    assert_eq!(result.diags[0].labels[0].span, sp(5, 6));
+    assert_eq!(result.info.local(function_id, x).map(|local| &local.ty), Some(&Ty::Str));
+}
+
+#[test]
+fn rejects_assignment_to_immutable_bindings() {
+    let [x, y, d] = [0, 1, 2].map(HirLocalId::from_usize);
+    let int = |value| s(HirExpr::Literal(HirLiteral::Int { value, suffix: None }));
+    let let_stmt = |local, name, is_mutable, init| HirStmt::Let {
+        local: Some(local),
+        name: SpannedStr::new(name, sp(0, 0)),
+        is_mutable,
+        ty: None,
+        init,
+        span: sp(0, 0),
+    };
+    let assign = |local, span| HirStmt::Assign {
+        target: Spanned::new(HirExpr::Resolved(Res::Local(local)), span),
+        value: int(2),
+        span: sp(0, 0),
+    };
+    let closure = |local, span| {
+        HirStmt::Expr(s(HirExpr::Closure(HirClosure {
+            params: Box::new([]),
+            body: Box::new(s(HirExpr::Block(HirBlock {
+                stmts: Box::new([assign(local, span)]),
+                tail: None,
+                span: sp(0, 0),
+            }))),
+            captures: Box::new([HirCapture { local, mode: HirCaptureMode::ByRef }]),
+        })))
+    };
+    let mut hir = HirMap::new();
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: HirId::from_usize(0),
+        name: SpannedStr::new("main", sp(0, 0)),
+        generics: Box::new([]),
+        params: Box::new([]),
+        ret_ty: s(HirType::Unit),
+        throws: None,
+        body: HirBlock {
+            stmts: Box::new([
+                let_stmt(x, "x", false, Some(int(1))),
+                let_stmt(y, "y", true, Some(int(1))),
+                let_stmt(d, "d", false, None),
+                assign(x, sp(1, 2)),
+                assign(y, sp(3, 4)),
+                assign(d, sp(5, 6)),
+                closure(y, sp(7, 8)),
+                closure(d, sp(9, 10)),
+            ]),
+            tail: None,
+            span: sp(0, 0),
+        },
+        span: sp(0, 0),
+    }));
+
+    let result = TypeChecker::new().check(&hir);
+    let rejected: Vec<_> = result
+        .diags
+        .iter()
+        .map(|diag| (diag.message.message.as_ref(), diag.labels[0].span))
+        .collect();
+    assert_eq!(
+        rejected,
+        [
+            ("cannot assign to immutable binding `x`", sp(1, 2)),
+            ("cannot assign to immutable binding `d`", sp(9, 10)),
+        ]
+    );
+}
+
+#[test]
+fn gives_panic_and_throw_the_never_type() {
+    let i32_ty = || s(HirType::Primitive(HirPrimitiveTy::I32));
+    let str_lit = |value: &'static str| s(HirExpr::Literal(HirLiteral::Str(value.into())));
+    let throw = |value| HirStmt::Throw { value, span: sp(0, 0) };
+    let block = |stmts: Box<[HirStmt<'static>]>| HirBlock { stmts, tail: None, span: sp(0, 0) };
+    let function = |id, name, params, throws, stmts| {
+        HirItem::Function(HirFunction {
+            vis: Visibility::Private,
+            id: HirId::from_usize(id),
+            name: SpannedStr::new(name, sp(0, 0)),
+            generics: Box::new([]),
+            params,
+            ret_ty: i32_ty(),
+            throws,
+            body: block(stmts),
+            span: sp(0, 0),
+        })
+    };
+    let mut hir = HirMap::new();
+    hir.push(function(
+        0,
+        "fail",
+        Box::new([]),
+        None,
+        Box::new([HirStmt::Expr(s(HirExpr::Call {
+            callee: Box::new(s(HirExpr::Resolved(Res::Builtin(PANIC)))),
+            args: Box::new([str_lit("x")]),
+        }))]),
+    ));
+    hir.push(function(
+        1,
+        "pick",
+        Box::new([HirFunctionParam {
+            name: SpannedStr::new("b", sp(0, 0)),
+            ty: s(HirType::Primitive(HirPrimitiveTy::Bool)),
+            span: sp(0, 0),
+        }]),
+        Some(s(HirType::Primitive(HirPrimitiveTy::Str))),
+        Box::new([HirStmt::Expr(s(HirExpr::If {
+            cond: Box::new(s(HirExpr::Resolved(Res::Local(HirLocalId::from_usize(0))))),
+            then: block(Box::new([throw(str_lit("a"))])),
+            else_: Some(Box::new(s(HirExpr::Block(block(Box::new([throw(str_lit("b"))])))))),
+        }))]),
+    ));
+    hir.push(function(
+        2,
+        "unchecked",
+        Box::new([]),
+        None,
+        Box::new([throw(s(HirExpr::Literal(HirLiteral::Int { value: 1, suffix: None })))]),
+    ));
+
+    let result = TypeChecker::new().check(&hir);
+
+    // Only the throw without a `throws` clause is rejected, and its note
+    // names the error type as it is written.
+    let rejected: Vec<_> = result
+        .diags
+        .iter()
+        .map(|diag| {
+            (diag.message.message.as_str(), diag.note.as_ref().map(|note| note.message.as_str()))
+        })
+        .collect();
+    assert_eq!(
+        rejected,
+        [(
+            "error of type `i32` is thrown from a function without a `throws` clause",
+            Some("add `throws i32` to the function signature"),
+        )]
+    );
+}
+
+#[test]
+fn rejects_nullable_argument_until_checked_against_null() {
+    let name = HirLocalId::from_usize(0);
+    let println = |arg: HirExpr<'static>, span| {
+        HirStmt::Expr(s(HirExpr::Call {
+            callee: Box::new(s(HirExpr::Resolved(Res::Builtin(PRINTLN)))),
+            args: Box::new([Spanned::new(arg, span)]),
+        }))
+    };
+    let mut hir = HirMap::new();
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: HirId::from_usize(0),
+        name: SpannedStr::new("main", sp(0, 0)),
+        generics: Box::new([]),
+        params: Box::new([HirFunctionParam {
+            name: SpannedStr::new("name", sp(0, 0)),
+            ty: s(HirType::Nullable(Box::new(s(HirType::Primitive(HirPrimitiveTy::Str))))),
+            span: sp(0, 0),
+        }]),
+        ret_ty: s(HirType::Unit),
+        throws: None,
+        body: HirBlock {
+            stmts: Box::new([
+                println(HirExpr::NonNullLocal(name), sp(1, 2)),
+                println(HirExpr::Resolved(Res::Local(name)), sp(3, 4)),
+            ]),
+            tail: None,
+            span: sp(0, 0),
+        },
+        span: sp(0, 0),
+    }));
+
+    let result = TypeChecker::new().check(&hir);
+    assert_eq!(result.diags.len(), 1);
+    assert_eq!(result.diags[0].labels[0].span, sp(3, 4));
+    assert_eq!(result.diags[0].message.message, "expected `str`, found nullable `str?`");
+    assert!(result.diags[0].note.is_some());
+}
+
+#[test]
+fn requires_annotation_for_null_initializer() {
+    let mut hir = HirMap::new();
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: HirId::from_usize(0),
+        name: SpannedStr::new("main", sp(0, 0)),
+        generics: Box::new([]),
+        params: Box::new([]),
+        ret_ty: s(HirType::Unit),
+        throws: None,
+        body: HirBlock {
+            stmts: Box::new([HirStmt::Let {
+                local: Some(HirLocalId::from_usize(0)),
+                name: SpannedStr::new("x", sp(4, 5)),
+                is_mutable: false,
+                ty: None,
+                init: Some(s(HirExpr::Literal(HirLiteral::Null))),
+                span: sp(0, 14),
+            }]),
+            tail: None,
+            span: sp(0, 0),
+        },
+        span: sp(0, 0),
+    }));
+
+    let result = TypeChecker::new().check(&hir);
+    assert_eq!(result.diags.len(), 1);
+    assert_eq!(result.diags[0].labels[0].span, sp(0, 14));
+    assert!(result.diags[0].message.message.contains("cannot infer the type of `null`"));
+}
+
+#[test]
+fn requires_thrown_errors_to_be_propagated() {
+    let call = |span| {
+        Spanned::new(
+            HirExpr::Call {
+                callee: Box::new(s(HirExpr::Resolved(Res::Def(HirId::from_usize(0))))),
+                args: Box::new([]),
+            },
+            span,
+        )
+    };
+    let mut hir = HirMap::new();
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: HirId::from_usize(0),
+        name: SpannedStr::new("fail", sp(0, 0)),
+        generics: Box::new([]),
+        params: Box::new([]),
+        ret_ty: s(HirType::Unit),
+        throws: Some(s(HirType::Primitive(HirPrimitiveTy::Str))),
+        body: HirBlock {
+            stmts: Box::new([HirStmt::Throw {
+                value: s(HirExpr::Literal(HirLiteral::Str("failed".into()))),
+                span: sp(0, 0),
+            }]),
+            tail: None,
+            span: sp(0, 0),
+        },
+        span: sp(0, 0),
+    }));
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: HirId::from_usize(1),
+        name: SpannedStr::new("run", sp(0, 0)),
+        generics: Box::new([]),
+        params: Box::new([]),
+        ret_ty: s(HirType::Unit),
+        throws: None,
+        body: HirBlock {
+            stmts: Box::new([
+                HirStmt::Expr(call(sp(1, 2))),
+                HirStmt::Expr(Spanned::new(HirExpr::Try(Box::new(call(sp(3, 4)))), sp(3, 5))),
+            ]),
+            tail: None,
+            span: sp(0, 0),
+        },
+        span: sp(0, 0),
+    }));
+
+    let result = TypeChecker::new().check(&hir);
+    assert_eq!(result.diags.len(), 2);
+    assert_eq!(result.diags[0].labels[0].span, sp(1, 2));
+    assert_eq!(result.diags[0].message.message, "unhandled error of type `str`");
+    assert_eq!(result.diags[1].labels[0].span, sp(3, 5));
+    assert!(result.diags[1].message.message.contains("without a `throws` clause"));
+}
+
+#[test]
+fn infers_generics_from_returned_and_assigned_types_and_explicit_struct_arguments() {
+    let (boxed, mk, main) = (HirId::from_usize(0), HirId::from_usize(1), HirId::from_usize(2));
+    let i64_ty = Ty::Int { signed: true, bits: TypeBits::B64 };
+    let vec_ty = || HirType::Builtin {
+        ty: VEC,
+        generics: Box::new([HirGenericArg::Type(s(HirType::Primitive(HirPrimitiveTy::I64)))]),
+    };
+    let vec_new = |lo| {
+        s(HirExpr::Call {
+            callee: Box::new(Spanned::new(
+                HirExpr::Resolved(Res::Builtin(VEC_NEW)),
+                sp(lo, lo + 1),
+            )),
+            args: Box::new([]),
+        })
+    };
+    let function = |id, name, ret_ty, body| {
+        HirItem::Function(HirFunction {
+            vis: Visibility::Private,
+            id,
+            name: SpannedStr::new(name, sp(0, 0)),
+            generics: Box::new([]),
+            params: Box::new([]),
+            ret_ty: s(ret_ty),
+            throws: None,
+            body,
+            span: sp(0, 0),
+        })
+    };
+    let x = HirLocalId::from_usize(0);
+    let segment = |generics| HirPathSegment {
+        name: SpannedStr::new("Box", sp(0, 0)),
+        generics,
+        span: sp(0, 0),
+    };
+    let literal = HirExpr::Struct {
+        path: HirPath {
+            from_root: false,
+            segments: Box::new([segment(Some(Box::new([
+                HirGenericArg::Type(s(HirType::Primitive(HirPrimitiveTy::Str))),
+                HirGenericArg::Const(s(HirExpr::Literal(HirLiteral::Int {
+                    value: 3,
+                    suffix: None,
+                }))),
+            ])))]),
+            span: sp(20, 23),
+        },
+        res: Some(Res::Def(boxed)),
+        fields: Box::new([HirFieldValue {
+            name: SpannedStr::new("value", sp(0, 0)),
+            value: s(HirExpr::Literal(HirLiteral::Str("s".into()))),
+        }]),
+    };
+
+    let mut hir = HirMap::new();
+    hir.push(HirItem::Struct(HirStruct {
+        id: boxed,
+        vis: Visibility::Private,
+        name: SpannedStr::new("Box", sp(0, 0)),
+        generics: Box::new([
+            HirGenericParam {
+                name: SpannedStr::new("T", sp(0, 0)),
+                kind: HirGenericParamKind::Type { bounds: Box::new([]) },
+                span: sp(0, 0),
+            },
+            HirGenericParam {
+                name: SpannedStr::new("N", sp(0, 0)),
+                kind: HirGenericParamKind::Const { ty: s(HirType::Primitive(HirPrimitiveTy::I32)) },
+                span: sp(0, 0),
+            },
+        ]),
+        fields: Box::new([HirField {
+            vis: Visibility::Private,
+            name: SpannedStr::new("value", sp(0, 0)),
+            ty: s(HirType::Param(0)),
+            span: sp(0, 0),
+        }]),
+        span: sp(0, 0),
+    }));
+    // `act mk() -> Vec<i64> { Vec::new() }`
+    hir.push(function(
+        mk,
+        "mk",
+        vec_ty(),
+        HirBlock { stmts: Box::new([]), tail: Some(Box::new(vec_new(10))), span: sp(0, 0) },
+    ));
+    // `let mut x: Vec<i64> = mk(); x = Vec::new(); let b = Box::<str, 3> { .. };`
+    hir.push(function(
+        main,
+        "main",
+        HirType::Unit,
+        HirBlock {
+            stmts: Box::new([
+                HirStmt::Let {
+                    local: Some(x),
+                    name: SpannedStr::new("x", sp(0, 0)),
+                    is_mutable: true,
+                    ty: Some(s(vec_ty())),
+                    init: Some(s(HirExpr::Call {
+                        callee: Box::new(s(HirExpr::Resolved(Res::Def(mk)))),
+                        args: Box::new([]),
+                    })),
+                    span: sp(0, 0),
+                },
+                HirStmt::Assign {
+                    target: s(HirExpr::Resolved(Res::Local(x))),
+                    value: vec_new(11),
+                    span: sp(0, 0),
+                },
+                HirStmt::Let {
+                    local: Some(HirLocalId::from_usize(1)),
+                    name: SpannedStr::new("b", sp(0, 0)),
+                    is_mutable: false,
+                    ty: None,
+                    init: Some(s(literal)),
+                    span: sp(0, 0),
+                },
+            ]),
+            tail: None,
+            span: sp(0, 0),
+        },
+    ));
+
+    let result = TypeChecker::new().check(&hir);
+
+    assert!(result.diags.is_empty(), "{:?}", result.diags);
+    let vec_new_args = |function, lo| {
+        let callee = Spanned::new(HirExpr::Resolved(Res::Builtin(VEC_NEW)), sp(lo, lo + 1));
+        result.info.callee_args(function, &callee).to_vec()
+    };
+    assert_eq!(vec_new_args(mk, 10), [GenericArg::Type(i64_ty.clone())]);
+    assert_eq!(vec_new_args(main, 11), [GenericArg::Type(i64_ty)]);
+    assert_eq!(
+        result.info.local(main, HirLocalId::from_usize(1)).unwrap().ty,
+        Ty::Struct {
+            def: boxed,
+            args: Box::new([GenericArg::Type(Ty::Str), GenericArg::Const(3)])
+        }
+    );
+}
+
+#[test]
+fn infers_generic_arguments_and_checks_bounds() {
+    let identity = HirId::from_usize(0);
+    let main = HirId::from_usize(1);
+    let call = |callee_span, literal| {
+        s(HirExpr::Call {
+            callee: Box::new(Spanned::new(HirExpr::Resolved(Res::Def(identity)), callee_span)),
+            args: Box::new([s(HirExpr::Literal(literal))]),
+        })
+    };
+    let mut hir = HirMap::new();
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: identity,
+        name: SpannedStr::new("identity", sp(0, 0)),
+        generics: Box::new([HirGenericParam {
+            name: SpannedStr::new("T", sp(0, 0)),
+            kind: HirGenericParamKind::Type {
+                bounds: Box::new([HirBound {
+                    path: HirPath { from_root: false, segments: Box::new([]), span: sp(0, 0) },
+                    contract: Some(DISPLAY_CONTRACT),
+                }]),
+            },
+            span: sp(0, 0),
+        }]),
+        params: Box::new([HirFunctionParam {
+            name: SpannedStr::new("x", sp(0, 0)),
+            ty: s(HirType::Param(0)),
+            span: sp(0, 0),
+        }]),
+        ret_ty: s(HirType::Param(0)),
+        throws: None,
+        body: HirBlock {
+            stmts: Box::new([HirStmt::Expr(s(HirExpr::Call {
+                callee: Box::new(s(HirExpr::Resolved(Res::Builtin(PRINTLN)))),
+                args: Box::new([s(HirExpr::Resolved(Res::Local(HirLocalId::from_usize(0))))]),
+            }))]),
+            tail: Some(Box::new(s(HirExpr::Resolved(Res::Local(HirLocalId::from_usize(0)))))),
+            span: sp(0, 0),
+        },
+        span: sp(0, 0),
+    }));
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: main,
+        name: SpannedStr::new("main", sp(0, 0)),
+        generics: Box::new([]),
+        params: Box::new([]),
+        ret_ty: s(HirType::Unit),
+        throws: None,
+        body: HirBlock {
+            stmts: Box::new([
+                HirStmt::Let {
+                    local: Some(HirLocalId::from_usize(0)),
+                    name: SpannedStr::new("a", sp(0, 0)),
+                    is_mutable: false,
+                    ty: None,
+                    init: Some(call(sp(1, 2), HirLiteral::Str("hi".into()))),
+                    span: sp(0, 0),
+                },
+                HirStmt::Expr(call(sp(3, 4), HirLiteral::Float { value: 1.0, suffix: None })),
+            ]),
+            tail: None,
+            span: sp(0, 0),
+        },
+        span: sp(0, 0),
+    }));
+
+    let result = TypeChecker::new().check(&hir);
+    assert_eq!(result.diags.len(), 1);
+    assert!(result.diags[0].message.message.contains("core::fmt::Display"));
+    assert_eq!(
+        result.info.local(main, HirLocalId::from_usize(0)).map(|local| &local.ty),
+        Some(&Ty::Str)
+    );
+    let callee = Spanned::new(HirExpr::Resolved(Res::Def(identity)), sp(1, 2));
+    assert_eq!(result.info.callee_args(main, &callee), [GenericArg::Type(Ty::Str)]);
+}
+
+fn function_with_builtin_arg(literal: HirLiteral<'static>) -> HirItem<'static> {
+    HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: HirId::from_usize(0),
+        name: SpannedStr::new("main", sp(0, 0)),
+        generics: Box::new([]),
+        params: Box::new([]),
+        ret_ty: s(HirType::Unit),
+        throws: None,
+        body: HirBlock {
+            stmts: Box::new([HirStmt::Expr(s(HirExpr::Call {
+                callee: Box::new(s(HirExpr::Resolved(Res::Builtin(PRINTLN)))),
+                args: Box::new([s(HirExpr::Literal(literal))]),
+            }))]),
+            tail: None,
+            span: sp(0, 0),
+        },
+        span: sp(0, 0),
+    })
+}
+
+fn field(name: &'static str, ty: HirPrimitiveTy) -> HirField<'static> {
+    HirField {
+        vis: Visibility::Private,
+        name: SpannedStr::new(name, sp(0, 0)),
+        ty: s(HirType::Primitive(ty)),
+        span: sp(0, 0),
+    }
+}
+
+#[test]
+fn types_union_literals_that_give_exactly_one_field() {
+    let bits = HirId::from_usize(0);
+    let int = || s(HirExpr::Literal(HirLiteral::Int { value: 1, suffix: None }));
+    let literal = |lo, values: Vec<&'static str>| HirExpr::Struct {
+        path: HirPath {
+            from_root: false,
+            segments: Box::new([HirPathSegment {
+                name: SpannedStr::new("Bits", sp(lo, lo + 4)),
+                generics: None,
+                span: sp(lo, lo + 4),
+            }]),
+            span: sp(lo, lo + 4),
+        },
+        res: Some(Res::Def(bits)),
+        fields: values
+            .into_iter()
+            .map(|name| HirFieldValue { name: SpannedStr::new(name, sp(0, 0)), value: int() })
+            .collect(),
+    };
+    let let_stmt = |local, init| HirStmt::Let {
+        local: Some(HirLocalId::from_usize(local)),
+        name: SpannedStr::new("b", sp(0, 0)),
+        is_mutable: false,
+        ty: None,
+        init: Some(s(init)),
+        span: sp(0, 0),
+    };
+    let mut hir = HirMap::new();
+    hir.push(HirItem::Union(HirUnion {
+        id: bits,
+        vis: Visibility::Private,
+        name: SpannedStr::new("Bits", sp(0, 0)),
+        generics: Box::new([]),
+        fields: Box::new([field("i", HirPrimitiveTy::I64), field("j", HirPrimitiveTy::I64)]),
+        span: sp(0, 0),
+    }));
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: HirId::from_usize(1),
+        name: SpannedStr::new("main", sp(0, 0)),
+        generics: Box::new([]),
+        params: Box::new([]),
+        ret_ty: s(HirType::Unit),
+        throws: None,
+        body: HirBlock {
+            stmts: Box::new([
+                let_stmt(0, literal(10, vec!["j"])),
+                let_stmt(1, literal(20, vec!["i", "j"])),
+                let_stmt(2, literal(30, vec![])),
+            ]),
+            tail: None,
+            span: sp(0, 0),
+        },
+        span: sp(0, 0),
+    }));
+
+    let result = TypeChecker::new().check(&hir);
+
+    let diags: Vec<_> = result
+        .diags
+        .iter()
+        .map(|diag| (diag.message.message.to_string(), diag.labels[0].span))
+        .collect();
+    assert_eq!(
+        diags,
+        [
+            (
+                "a literal of union `Bits` must give exactly one field, found 2".to_string(),
+                sp(20, 24)
+            ),
+            (
+                "a literal of union `Bits` must give exactly one field, found 0".to_string(),
+                sp(30, 34)
+            ),
+        ]
+    );
+    let local = |id| result.info.local(HirId::from_usize(1), HirLocalId::from_usize(id));
+    assert_eq!(local(0).unwrap().ty, Ty::Union { def: bits, args: Box::new([]) });
+}
+
+#[test]
+fn computes_union_layout_and_gates_field_access() {
+    let bits = HirId::from_usize(0);
+    let header = HirId::from_usize(1);
+    let bits_ty = || s(HirType::Union { def: bits, generics: Box::new([]) });
+    let read = |name| {
+        s(HirExpr::Field {
+            base: Box::new(s(HirExpr::Resolved(Res::Local(HirLocalId::from_usize(0))))),
+            name: SpannedStr::new(name, sp(0, 0)),
+        })
+    };
+    let mut hir = HirMap::new();
+    hir.push(HirItem::Union(HirUnion {
+        id: bits,
+        vis: Visibility::Private,
+        name: SpannedStr::new("Bits", sp(0, 0)),
+        generics: Box::new([]),
+        fields: Box::new([field("i", HirPrimitiveTy::I32), field("f", HirPrimitiveTy::F64)]),
+        span: sp(0, 0),
+    }));
+    hir.push(HirItem::Struct(HirStruct {
+        id: header,
+        vis: Visibility::Private,
+        name: SpannedStr::new("Header", sp(0, 0)),
+        generics: Box::new([]),
+        fields: Box::new([
+            field("tag", HirPrimitiveTy::U8),
+            HirField { ty: bits_ty(), ..field("bits", HirPrimitiveTy::U8) },
+            field("len", HirPrimitiveTy::U16),
+        ]),
+        span: sp(0, 0),
+    }));
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: HirId::from_usize(2),
+        name: SpannedStr::new("read", sp(0, 0)),
+        generics: Box::new([]),
+        params: Box::new([HirFunctionParam {
+            name: SpannedStr::new("bits", sp(0, 0)),
+            ty: bits_ty(),
+            span: sp(0, 0),
+        }]),
+        ret_ty: s(HirType::Primitive(HirPrimitiveTy::I32)),
+        throws: None,
+        body: HirBlock {
+            stmts: Box::new([HirStmt::Expr(read("missing")), HirStmt::Expr(read("i"))]),
+            tail: Some(Box::new(s(HirExpr::Unsafe(HirBlock {
+                stmts: Box::new([]),
+                tail: Some(Box::new(read("i"))),
+                span: sp(0, 0),
+            })))),
+            span: sp(0, 0),
+        },
+        span: sp(0, 0),
+    }));
+
+    let result = TypeChecker::new().check(&hir);
+
+    let messages: Vec<_> =
+        result.diags.iter().map(|diag| diag.message.message.to_string()).collect();
+    assert_eq!(
+        messages,
+        [
+            "type `Bits` has no field `missing`",
+            "access to union field `i` requires an `unsafe` block",
+        ]
+    );
+    let layout = |ty| result.info.layout(&ty);
+    assert_eq!(layout(Ty::Union { def: bits, args: Box::new([]) }), Some(Layout::new(8, 8)));
+    assert_eq!(layout(Ty::Struct { def: header, args: Box::new([]) }), Some(Layout::new(24, 8)));
+    assert_eq!(
+        layout(Ty::Nullable(Box::new(Ty::Int { signed: true, bits: TypeBits::B32 }))),
+        Some(Layout::new(8, 4))
+    );
+}
+
+#[test]
+fn gates_pointer_derefs_and_extern_calls_on_unsafe() {
+    let puts = HirId::from_usize(0);
+    let param = |name, ty| HirFunctionParam {
+        name: SpannedStr::new(name, sp(0, 0)),
+        ty: s(ty),
+        span: sp(0, 0),
+    };
+    let pointer = |mutable| HirType::Pointer {
+        mutable,
+        pointee: Box::new(s(HirType::Primitive(HirPrimitiveTy::U8))),
+    };
+    let p = || s(HirExpr::Resolved(Res::Local(HirLocalId::from_usize(0))));
+    let call_puts = || {
+        s(HirExpr::Call {
+            callee: Box::new(s(HirExpr::Resolved(Res::Def(puts)))),
+            args: Box::new([p()]),
+        })
+    };
+    let mut hir = HirMap::new();
+    for (id, name, param_ty) in [
+        (puts, "puts", pointer(false)),
+        (HirId::from_usize(1), "log", HirType::Primitive(HirPrimitiveTy::Str)),
+    ] {
+        hir.push(HirItem::ExternFunction(HirExternFunction {
+            id,
+            vis: Visibility::Private,
+            name: SpannedStr::new(name, sp(0, 0)),
+            params: Box::new([param("s", param_ty)]),
+            ret_ty: s(HirType::Primitive(HirPrimitiveTy::I32)),
+            span: sp(0, 0),
+        }));
+    }
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: HirId::from_usize(2),
+        name: SpannedStr::new("write", sp(0, 0)),
+        generics: Box::new([]),
+        params: Box::new([param("p", pointer(true))]),
+        ret_ty: s(HirType::Primitive(HirPrimitiveTy::I32)),
+        throws: None,
+        body: HirBlock {
+            stmts: Box::new([
+                HirStmt::Expr(s(HirExpr::Deref(Box::new(p())))),
+                HirStmt::Expr(call_puts()),
+                HirStmt::Expr(s(HirExpr::Unsafe(HirBlock {
+                    stmts: Box::new([HirStmt::Expr(s(HirExpr::Deref(Box::new(s(
+                        HirExpr::Literal(HirLiteral::Bool(true)),
+                    )))))]),
+                    tail: Some(Box::new(s(HirExpr::Deref(Box::new(p()))))),
+                    span: sp(0, 0),
+                }))),
+            ]),
+            tail: Some(Box::new(s(HirExpr::Unsafe(HirBlock {
+                stmts: Box::new([]),
+                tail: Some(Box::new(call_puts())),
+                span: sp(0, 0),
+            })))),
+            span: sp(0, 0),
+        },
+        span: sp(0, 0),
+    }));
+
+    let result = TypeChecker::new().check(&hir);
+
+    let messages: Vec<_> =
+        result.diags.iter().map(|diag| diag.message.message.to_string()).collect();
+    assert_eq!(
+        messages,
+        [
+            "type `str` cannot be passed across an `extern` boundary",
+            "dereference of a raw pointer requires an `unsafe` block",
+            "call to an `extern` function requires an `unsafe` block",
+            "type `bool` cannot be dereferenced",
+        ]
+    );
+    assert!(result.info.is_extern(puts));
+    assert_eq!(
+        result.info.layout(&Ty::Pointer { mutable: false, pointee: Box::new(Ty::Str) }),
+        Some(Layout::new(8, 8))
+    );
+}
+
+#[test]
+fn checks_stores_through_pointers_are_unsafe_and_mutable() {
+    let param = |name, mutable| HirFunctionParam {
+        name: SpannedStr::new(name, sp(0, 0)),
+        ty: s(HirType::Pointer {
+            mutable,
+            pointee: Box::new(s(HirType::Primitive(HirPrimitiveTy::U8))),
+        }),
+        span: sp(0, 0),
+    };
+    let store = |local, value, span| HirStmt::Assign {
+        target: Spanned::new(
+            HirExpr::Deref(Box::new(s(HirExpr::Resolved(Res::Local(HirLocalId::from_usize(
+                local,
+            )))))),
+            span,
+        ),
+        value: s(HirExpr::Literal(HirLiteral::Int { value, suffix: None })),
+        span,
+    };
+    let mut hir = HirMap::new();
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: HirId::from_usize(0),
+        name: SpannedStr::new("write", sp(0, 0)),
+        generics: Box::new([]),
+        params: Box::new([param("p", true), param("c", false)]),
+        ret_ty: s(HirType::Unit),
+        throws: None,
+        body: HirBlock {
+            stmts: Box::new([
+                HirStmt::Expr(s(HirExpr::Unsafe(HirBlock {
+                    stmts: Box::new([store(0, 1, sp(1, 2)), store(1, 2, sp(3, 4))]),
+                    tail: None,
+                    span: sp(0, 0),
+                }))),
+                store(0, 3, sp(5, 6)),
+            ]),
+            tail: None,
+            span: sp(0, 0),
+        },
+        span: sp(0, 0),
+    }));
+
+    let result = TypeChecker::new().check(&hir);
+
+    let diags: Vec<_> = result
+        .diags
+        .iter()
+        .map(|diag| (diag.message.message.to_string(), diag.labels[0].span))
+        .collect();
+    assert_eq!(
+        diags,
+        [
+            (
+                "cannot assign through `*const u8`; only `*mut` pointers can be written \
+                 through"
+                    .to_owned(),
+                sp(3, 4)
+            ),
+            ("dereference of a raw pointer requires an `unsafe` block".to_owned(), sp(5, 6)),
+        ]
+    );
+}
+
+#[test]
+fn infers_closure_params_and_converts_function_items() {
+    let i32_ty = || s(HirType::Primitive(HirPrimitiveTy::I32));
+    let fn_ty = || HirType::Function { params: Box::new([i32_ty()]), ret: Box::new(i32_ty()) };
+    let param = |name, ty| HirFunctionParam {
+        name: SpannedStr::new(name, sp(0, 0)),
+        ty: s(ty),
+        span: sp(0, 0),
+    };
+    let local = |id| s(HirExpr::Resolved(Res::Local(HirLocalId::from_usize(id))));
+    let int = |value| s(HirExpr::Literal(HirLiteral::Int { value, suffix: None }));
+    let closure = |name, id| {
+        s(HirExpr::Closure(HirClosure {
+            params: Box::new([HirClosureParam {
+                local: Some(HirLocalId::from_usize(id)),
+                name: SpannedStr::new(name, sp(0, 0)),
+                ty: None,
+            }]),
+            body: Box::new(local(id)),
+            captures: Box::new([]),
+        }))
+    };
+    let let_stmt = |id, name, ty: Option<HirType<'static>>, init| HirStmt::Let {
+        local: Some(HirLocalId::from_usize(id)),
+        name: SpannedStr::new(name, sp(0, 0)),
+        is_mutable: false,
+        ty: ty.map(s),
+        init: Some(init),
+        span: sp(0, 0),
+    };
+    let call = |callee, args: Vec<_>| {
+        s(HirExpr::Call { callee: Box::new(callee), args: args.into_boxed_slice() })
+    };
+    let apply = HirId::from_usize(0);
+    let double = HirId::from_usize(1);
+    let main = HirId::from_usize(2);
+
+    let mut hir = HirMap::new();
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: apply,
+        name: SpannedStr::new("apply", sp(0, 0)),
+        generics: Box::new([]),
+        params: Box::new([
+            param("f", fn_ty()),
+            param("x", HirType::Primitive(HirPrimitiveTy::I32)),
+        ]),
+        ret_ty: i32_ty(),
+        throws: None,
+        body: HirBlock {
+            stmts: Box::new([]),
+            tail: Some(Box::new(call(local(0), vec![local(1)]))),
+            span: sp(0, 0),
+        },
+        span: sp(0, 0),
+    }));
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: double,
+        name: SpannedStr::new("double", sp(0, 0)),
+        generics: Box::new([]),
+        params: Box::new([param("x", HirType::Primitive(HirPrimitiveTy::I32))]),
+        ret_ty: i32_ty(),
+        throws: None,
+        body: HirBlock { stmts: Box::new([]), tail: Some(Box::new(local(0))), span: sp(0, 0) },
+        span: sp(0, 0),
+    }));
+    let def = |id| s(HirExpr::Resolved(Res::Def(id)));
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: main,
+        name: SpannedStr::new("main", sp(0, 0)),
+        generics: Box::new([]),
+        params: Box::new([]),
+        ret_ty: i32_ty(),
+        throws: None,
+        body: HirBlock {
+            stmts: Box::new([
+                let_stmt(1, "inc", Some(fn_ty()), closure("x", 0)),
+                HirStmt::Expr(call(def(apply), vec![closure("y", 2), int(1)])),
+                HirStmt::Expr(call(def(apply), vec![def(double), int(2)])),
+                let_stmt(3, "twice", None, def(double)),
+                let_stmt(5, "bad", None, closure("z", 4)),
+            ]),
+            tail: Some(Box::new(call(local(1), vec![s(HirExpr::Literal(HirLiteral::Bool(true)))]))),
+            span: sp(0, 0),
+        },
+        span: sp(0, 0),
+    }));
+
+    let result = TypeChecker::new().check(&hir);
+
+    let messages: Vec<_> =
+        result.diags.iter().map(|diag| diag.message.message.to_string()).collect();
+    assert_eq!(
+        messages,
+        [
+            "cannot infer the type of closure parameter `z`; add a type annotation",
+            "expected type `i32`, found `bool`",
+        ]
+    );
+    let fn_ptr = Ty::FnPtr {
+        params: Box::new([Ty::Int { signed: true, bits: TypeBits::B32 }]),
+        ret: Box::new(Ty::Int { signed: true, bits: TypeBits::B32 }),
+    };
+    let local_ty = |id| result.info.local(main, HirLocalId::from_usize(id)).unwrap().ty.clone();
+    assert_eq!(local_ty(1), fn_ptr);
+    assert_eq!(local_ty(2), Ty::Int { signed: true, bits: TypeBits::B32 });
+    assert_eq!(local_ty(3), fn_ptr);
+    assert_eq!(result.info.layout(&fn_ptr), Some(Layout::new(8, 8)));
+}
+
+#[test]
+fn infers_vec_elements_and_checks_indexing() {
+    let i64_ty = Ty::Int { signed: true, bits: TypeBits::B64 };
+    let local = |id| s(HirExpr::Resolved(Res::Local(HirLocalId::from_usize(id))));
+    let int = |value| s(HirExpr::Literal(HirLiteral::Int { value, suffix: None }));
+    let let_stmt = |id, name, ty: Option<HirType<'static>>, init| HirStmt::Let {
+        local: Some(HirLocalId::from_usize(id)),
+        name: SpannedStr::new(name, sp(0, 0)),
+        is_mutable: true,
+        ty: ty.map(s),
+        init: Some(init),
+        span: sp(0, 0),
+    };
+    let method = |id, name| {
+        s(HirExpr::Field { base: Box::new(local(id)), name: SpannedStr::new(name, sp(0, 0)) })
+    };
+    let call = |callee, args: Vec<_>| {
+        s(HirExpr::Call { callee: Box::new(callee), args: args.into_boxed_slice() })
+    };
+    let index = |base, index| s(HirExpr::Index { base: Box::new(base), index: Box::new(index) });
+    let range =
+        s(HirExpr::Range { start: Box::new(int(1)), end: Box::new(int(3)), inclusive: false });
+    let vec_new = || s(HirExpr::Resolved(Res::Builtin(VEC_NEW)));
+    let vec_ty = HirType::Builtin {
+        ty: VEC,
+        generics: Box::new([HirGenericArg::Type(s(HirType::Primitive(HirPrimitiveTy::I64)))]),
+    };
+    let main = HirId::from_usize(0);
+
+    let mut hir = HirMap::new();
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: main,
+        name: SpannedStr::new("main", sp(0, 0)),
+        generics: Box::new([]),
+        params: Box::new([]),
+        ret_ty: s(HirType::Unit),
+        throws: None,
+        body: HirBlock {
+            stmts: Box::new([
+                let_stmt(0, "v", Some(vec_ty), call(vec_new(), vec![])),
+                HirStmt::Expr(call(method(0, "push"), vec![int(1)])),
+                let_stmt(1, "first", None, index(local(0), int(0))),
+                let_stmt(2, "rest", None, index(local(0), range)),
+                let_stmt(3, "n", None, call(method(2, "len"), vec![])),
+                let_stmt(4, "last", None, call(method(0, "pop"), vec![])),
+                let_stmt(5, "w", None, call(vec_new(), vec![])),
+                HirStmt::Expr(index(s(HirExpr::Literal(HirLiteral::Bool(true))), int(0))),
+                HirStmt::Expr(index(local(0), s(HirExpr::Literal(HirLiteral::Bool(true))))),
+                HirStmt::Expr(method(0, "len")),
+            ]),
+            tail: None,
+            span: sp(0, 0),
+        },
+        span: sp(0, 0),
+    }));
+
+    let result = TypeChecker::new().check(&hir);
+
+    let messages: Vec<_> =
+        result.diags.iter().map(|diag| diag.message.message.to_string()).collect();
+    assert_eq!(
+        messages,
+        [
+            "cannot infer generic parameter `T` from the call arguments",
+            "type `bool` cannot be indexed",
+            "index must be an integer or a range of integers, found `bool`",
+            "method `len` must be called",
+        ]
+    );
+    let local_ty = |id| result.info.local(main, HirLocalId::from_usize(id)).unwrap().ty.clone();
+    assert_eq!(local_ty(0), Ty::BuiltinType { id: VEC, args: Box::new([i64_ty.clone()]) });
+    assert_eq!(local_ty(1), i64_ty);
+    assert_eq!(local_ty(2), Ty::Slice(Box::new(i64_ty.clone())));
+    assert_eq!(local_ty(3), Ty::USIZE);
+    assert_eq!(local_ty(4), i64_ty.nullable());
+    assert_eq!(result.info.layout(&local_ty(2)), Some(Layout::new(24, 8)));
+}
+
+#[test]
+fn checks_hash_keys_of_collections() {
+    let local = |id| s(HirExpr::Resolved(Res::Local(HirLocalId::from_usize(id))));
+    let int = |value| s(HirExpr::Literal(HirLiteral::Int { value, suffix: None }));
+    let string = || s(HirExpr::Literal(HirLiteral::Str("a".into())));
+    let let_stmt = |id, name, ty: Option<HirType<'static>>, init| HirStmt::Let {
+        local: Some(HirLocalId::from_usize(id)),
+        name: SpannedStr::new(name, sp(0, 0)),
+        is_mutable: true,
+        ty: ty.map(s),
+        init: Some(init),
+        span: sp(0, 0),
+    };
+    let method = |id, name| {
+        s(HirExpr::Field { base: Box::new(local(id)), name: SpannedStr::new(name, sp(0, 0)) })
+    };
+    let call = |callee, args: Vec<_>| {
+        s(HirExpr::Call { callee: Box::new(callee), args: args.into_boxed_slice() })
+    };
+    let collection = |id, args: Vec<HirType<'static>>| HirType::Builtin {
+        ty: id,
+        generics: args.into_iter().map(|arg| HirGenericArg::Type(s(arg))).collect(),
+    };
+    let strukt = |def| HirType::Struct { def: HirId::from_usize(def), generics: Box::new([]) };
+    let field = |name, ty| HirField {
+        vis: Visibility::Private,
+        name: SpannedStr::new(name, sp(0, 0)),
+        ty: s(ty),
+        span: sp(0, 0),
+    };
+    let main = HirId::from_usize(0);
+
+    let mut hir = HirMap::new();
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: main,
+        name: SpannedStr::new("main", sp(0, 0)),
+        generics: Box::new([]),
+        params: Box::new([]),
+        ret_ty: s(HirType::Unit),
+        throws: None,
+        body: HirBlock {
+            stmts: Box::new([
+                let_stmt(
+                    0,
+                    "m",
+                    Some(collection(
+                        HASH_MAP,
+                        vec![
+                            HirType::Primitive(HirPrimitiveTy::Str),
+                            HirType::Primitive(HirPrimitiveTy::I64),
+                        ],
+                    )),
+                    call(s(HirExpr::Resolved(Res::Builtin(MAP_NEW))), vec![]),
+                ),
+                let_stmt(1, "previous", None, call(method(0, "insert"), vec![string(), int(1)])),
+                let_stmt(2, "found", None, call(method(0, "contains_key"), vec![string()])),
+                let_stmt(3, "keys", None, call(method(0, "keys"), vec![])),
+                HirStmt::Expr(s(HirExpr::For {
+                    local: Some(HirLocalId::from_usize(4)),
+                    name: SpannedStr::new("key", sp(0, 0)),
+                    iter: Box::new(local(3)),
+                    body: HirBlock { stmts: Box::new([]), tail: None, span: sp(0, 0) },
+                })),
+                let_stmt(
+                    5,
+                    "points",
+                    Some(collection(HASH_SET, vec![strukt(1)])),
+                    call(s(HirExpr::Resolved(Res::Builtin(SET_NEW))), vec![]),
+                ),
+                let_stmt(
+                    6,
+                    "callbacks",
+                    Some(collection(HASH_SET, vec![strukt(2)])),
+                    call(s(HirExpr::Resolved(Res::Builtin(SET_NEW))), vec![]),
+                ),
+                let_stmt(
+                    7,
+                    "weights",
+                    Some(collection(HASH_SET, vec![HirType::Primitive(HirPrimitiveTy::F64)])),
+                    call(s(HirExpr::Resolved(Res::Builtin(SET_NEW))), vec![]),
+                ),
+            ]),
+            tail: None,
+            span: sp(0, 0),
+        },
+        span: sp(0, 0),
+    }));
+    hir.push(HirItem::Struct(HirStruct {
+        id: HirId::from_usize(1),
+        vis: Visibility::Private,
+        name: SpannedStr::new("Point", sp(0, 0)),
+        generics: Box::new([]),
+        fields: Box::new([
+            field("x", HirType::Primitive(HirPrimitiveTy::I64)),
+            field("label", HirType::Primitive(HirPrimitiveTy::Str)),
+        ]),
+        span: sp(0, 0),
+    }));
+    hir.push(HirItem::Struct(HirStruct {
+        id: HirId::from_usize(2),
+        vis: Visibility::Private,
+        name: SpannedStr::new("Callback", sp(0, 0)),
+        generics: Box::new([]),
+        fields: Box::new([field(
+            "run",
+            HirType::Function { params: Box::new([]), ret: Box::new(s(HirType::Unit)) },
+        )]),
+        span: sp(0, 0),
+    }));
+
+    let result = TypeChecker::new().check(&hir);
+
+    let messages: Vec<_> =
+        result.diags.iter().map(|diag| diag.message.message.to_string()).collect();
+    // `Point` hashes field by field, but a struct holding a function
+    // cannot, and floats have no total equality.
+    let rejected = ["Callback", "f64"];
+    let expected: Vec<_> = rejected
+        .iter()
+        .flat_map(|ty| {
+            [
+                format!("type `{ty}` does not implement `core::hash::Hash`"),
+                format!("type `{ty}` does not implement `core::cmp::Eq`"),
+            ]
+        })
+        .collect();
+    assert_eq!(messages, expected);
+    let local_ty = |id| result.info.local(main, HirLocalId::from_usize(id)).unwrap().ty.clone();
+    let i64_ty = Ty::Int { signed: true, bits: TypeBits::B64 };
+    assert_eq!(local_ty(1), i64_ty.nullable());
+    assert_eq!(local_ty(2), Ty::Bool);
+    assert_eq!(local_ty(3), Ty::BuiltinType { id: VEC, args: Box::new([Ty::Str]) });
+    assert_eq!(local_ty(4), Ty::Str);
+    assert_eq!(result.info.layout(&local_ty(0)), Some(Layout::new(8, 8)));
+}
+
+#[test]
+fn checks_math_bounds_and_types_math_constants() {
+    let int = || HirLiteral::Int { value: 2, suffix: None };
+    let float = || HirLiteral::Float { value: 2.0, suffix: None };
+    let builtin = |id| s(HirExpr::Resolved(Res::Builtin(id)));
+    let call = |id, args: Vec<HirLiteral<'static>>| {
+        s(HirExpr::Call {
+            callee: Box::new(builtin(id)),
+            args: args.into_iter().map(|arg| s(HirExpr::Literal(arg))).collect(),
+        })
+    };
+    let let_stmt = |id, init| HirStmt::Let {
+        local: Some(HirLocalId::from_usize(id)),
+        name: SpannedStr::new("x", sp(0, 0)),
+        is_mutable: false,
+        ty: None,
+        init: Some(init),
+        span: sp(0, 0),
+    };
+    let main = HirId::from_usize(0);
+
+    let mut hir = HirMap::new();
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: main,
+        name: SpannedStr::new("main", sp(0, 0)),
+        generics: Box::new([]),
+        params: Box::new([]),
+        ret_ty: s(HirType::Unit),
+        throws: None,
+        body: HirBlock {
+            stmts: Box::new([
+                let_stmt(0, call(MATH_SQRT, vec![float()])),
+                let_stmt(1, call(MATH_CHECKED_ADD, vec![int(), int()])),
+                let_stmt(2, builtin(MATH_PI)),
+                let_stmt(3, call(MATH_MIN, vec![int(), int()])),
+                let_stmt(4, call(MATH_SQRT, vec![int()])),
+            ]),
+            tail: None,
+            span: sp(0, 0),
+        },
+        span: sp(0, 0),
+    }));
+
+    let result = TypeChecker::new().check(&hir);
+
+    let messages: Vec<_> =
+        result.diags.iter().map(|diag| diag.message.message.to_string()).collect();
+    assert_eq!(messages, ["type `i32` does not implement `core::num::Float`"]);
+    let int_ty = ty_of_literal(&int());
+    let local_ty = |id| result.info.local(main, HirLocalId::from_usize(id)).unwrap().ty.clone();
+    let f64_ty = Ty::Float { bits: TypeBits::B64 };
+    assert_eq!(local_ty(0), ty_of_literal(&float()));
+    assert_eq!(local_ty(1), int_ty.clone().nullable());
+    assert_eq!(local_ty(2), f64_ty);
+    assert_eq!(local_ty(3), int_ty);
+}
+
+#[test]
+fn requires_propagating_errors_thrown_by_file_builtins() {
+    let string = || s(HirExpr::Literal(HirLiteral::Str("notes.txt".into())));
+    let call = |id, args: Vec<_>| HirExpr::Call {
+        callee: Box::new(s(HirExpr::Resolved(Res::Builtin(id)))),
+        args: args.into_boxed_slice(),
+    };
+    let main = HirId::from_usize(0);
+
+    let mut hir = HirMap::new();
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: main,
+        name: SpannedStr::new("main", sp(0, 0)),
+        generics: Box::new([]),
+        params: Box::new([]),
+        ret_ty: s(HirType::Unit),
+        throws: None,
+        body: HirBlock {
+            stmts: Box::new([
+                HirStmt::Let {
+                    local: Some(HirLocalId::from_usize(0)),
+                    name: SpannedStr::new("found", sp(0, 0)),
+                    is_mutable: false,
+                    ty: None,
+                    init: Some(s(call(FILES_EXISTS, vec![string()]))),
+                    span: sp(0, 0),
+                },
+                HirStmt::Expr(s(call(FILES_WRITE, vec![string(), string()]))),
+                HirStmt::Expr(s(HirExpr::Try(Box::new(s(call(
+                    FILES_WRITE,
+                    vec![string(), string()],
+                )))))),
+            ]),
+            tail: None,
+            span: sp(0, 0),
+        },
+        span: sp(0, 0),
+    }));
+
+    let result = TypeChecker::new().check(&hir);
+
+    let messages: Vec<_> =
+        result.diags.iter().map(|diag| diag.message.message.to_string()).collect();
+    assert_eq!(
+        messages,
+        [
+            "unhandled error of type `str`",
+            "error of type `str` is thrown from a function without a `throws` clause",
+        ]
+    );
+    let found = result.info.local(main, HirLocalId::from_usize(0)).unwrap();
+    assert_eq!(found.ty, Ty::Bool);
+}
+
+#[test]
+fn types_instants_and_durations_from_time_builtins() {
+    let local = |id| s(HirExpr::Resolved(Res::Local(HirLocalId::from_usize(id))));
+    let builtin = |id| s(HirExpr::Resolved(Res::Builtin(id)));
+    let method = |id, name| {
+        s(HirExpr::Field { base: Box::new(local(id)), name: SpannedStr::new(name, sp(0, 0)) })
+    };
+    let call = |callee, args: Vec<_>| {
+        s(HirExpr::Call { callee: Box::new(callee), args: args.into_boxed_slice() })
+    };
+    let let_stmt = |id, init| HirStmt::Let {
+        local: Some(HirLocalId::from_usize(id)),
+        name: SpannedStr::new("t", sp(0, 0)),
+        is_mutable: false,
+        ty: None,
+        init: Some(init),
+        span: sp(0, 0),
+    };
+    let millis = s(HirExpr::Literal(HirLiteral::Int { value: 5, suffix: None }));
+    let main = HirId::from_usize(0);
+
+    let mut hir = HirMap::new();
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: main,
+        name: SpannedStr::new("main", sp(0, 0)),
+        generics: Box::new([]),
+        params: Box::new([]),
+        ret_ty: s(HirType::Unit),
+        throws: None,
+        body: HirBlock {
+            stmts: Box::new([
+                let_stmt(0, call(builtin(INSTANT_NOW), vec![])),
+                let_stmt(1, call(builtin(DURATION_FROM_MILLIS), vec![millis])),
+                HirStmt::Expr(call(builtin(TIME_SLEEP), vec![local(1)])),
+                let_stmt(2, call(method(0, "elapsed"), vec![])),
+                let_stmt(3, call(method(2, "add"), vec![local(1)])),
+                let_stmt(4, call(method(3, "as_secs_f64"), vec![])),
+                let_stmt(5, call(method(3, "as_millis"), vec![])),
+            ]),
+            tail: None,
+            span: sp(0, 0),
+        },
+        span: sp(0, 0),
+    }));
+
+    let result = TypeChecker::new().check(&hir);
+
+    assert!(result.diags.is_empty(), "{:?}", result.diags);
+    let local_ty = |id| result.info.local(main, HirLocalId::from_usize(id)).unwrap().ty.clone();
+    let instant = Ty::BuiltinType { id: INSTANT, args: Box::new([]) };
+    let duration = Ty::BuiltinType { id: DURATION, args: Box::new([]) };
+    assert_eq!(local_ty(0), instant);
+    assert_eq!(local_ty(1), duration);
+    assert_eq!(local_ty(2), duration);
+    assert_eq!(local_ty(3), duration);
+    assert_eq!(local_ty(4), Ty::Float { bits: TypeBits::B64 });
+    assert_eq!(local_ty(5), Ty::USIZE);
+    assert_eq!(result.info.layout(&duration), Some(Layout::new(8, 8)));
+}
+
+#[test]
+fn types_random_draws_and_shuffles_of_vectors_and_slices() {
+    let int = |value| s(HirExpr::Literal(HirLiteral::Int { value, suffix: None }));
+    let call = |id, args: Vec<_>| {
+        s(HirExpr::Call {
+            callee: Box::new(s(HirExpr::Resolved(Res::Builtin(id)))),
+            args: args.into_boxed_slice(),
+        })
+    };
+    let let_stmt = |id, ty: Option<HirType<'static>>, init| HirStmt::Let {
+        local: Some(HirLocalId::from_usize(id)),
+        name: SpannedStr::new("r", sp(0, 0)),
+        is_mutable: false,
+        ty: ty.map(s),
+        init: Some(init),
+        span: sp(0, 0),
+    };
+    let vec_ty = HirType::Builtin {
+        ty: VEC,
+        generics: Box::new([HirGenericArg::Type(s(HirType::Primitive(HirPrimitiveTy::Str)))]),
+    };
+    let words = || s(HirExpr::Resolved(Res::Local(HirLocalId::from_usize(0))));
+    let some_words = s(HirExpr::Index {
+        base: Box::new(words()),
+        index: Box::new(s(HirExpr::Range {
+            start: Box::new(int(0)),
+            end: Box::new(int(1)),
+            inclusive: false,
+        })),
+    });
+    let main = HirId::from_usize(0);
+
+    let mut hir = HirMap::new();
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: main,
+        name: SpannedStr::new("main", sp(0, 0)),
+        generics: Box::new([]),
+        params: Box::new([]),
+        ret_ty: s(HirType::Unit),
+        throws: None,
+        body: HirBlock {
+            stmts: Box::new([
+                let_stmt(0, Some(vec_ty), call(VEC_NEW, vec![])),
+                HirStmt::Expr(call(RANDOM_SEED, vec![int(42)])),
+                HirStmt::Expr(call(RANDOM_SHUFFLE, vec![words()])),
+                HirStmt::Expr(call(RANDOM_SHUFFLE, vec![some_words])),
+                let_stmt(1, None, call(RANDOM_INT_RANGE, vec![int(1), int(7)])),
+                let_stmt(2, None, call(RANDOM_FLOAT, vec![])),
+            ]),
+            tail: None,
+            span: sp(0, 0),
+        },
+        span: sp(0, 0),
+    }));
+
+    let result = TypeChecker::new().check(&hir);
+
+    assert!(result.diags.is_empty(), "{:?}", result.diags);
+    let local_ty = |id| result.info.local(main, HirLocalId::from_usize(id)).unwrap().ty.clone();
+    assert_eq!(local_ty(1), Ty::Int { signed: true, bits: TypeBits::B64 });
+    assert_eq!(local_ty(2), Ty::Float { bits: TypeBits::B64 });
+}
+
+#[test]
+fn types_json_documents_and_their_accessors() {
+    let local = |id| s(HirExpr::Resolved(Res::Local(HirLocalId::from_usize(id))));
+    let text = |value: &'static str| s(HirExpr::Literal(HirLiteral::Str(value.into())));
+    let builtin = |id| s(HirExpr::Resolved(Res::Builtin(id)));
+    let method = |id, name| {
+        s(HirExpr::Field { base: Box::new(local(id)), name: SpannedStr::new(name, sp(0, 0)) })
+    };
+    let call = |callee, args: Vec<_>| {
+        s(HirExpr::Call { callee: Box::new(callee), args: args.into_boxed_slice() })
+    };
+    let let_stmt = |id, init| HirStmt::Let {
+        local: Some(HirLocalId::from_usize(id)),
+        name: SpannedStr::new("j", sp(0, 0)),
+        is_mutable: false,
+        ty: None,
+        init: Some(init),
+        span: sp(0, 0),
+    };
+    let main = HirId::from_usize(0);
+
+    let mut hir = HirMap::new();
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: main,
+        name: SpannedStr::new("main", sp(0, 0)),
+        generics: Box::new([]),
+        params: Box::new([]),
+        ret_ty: s(HirType::Unit),
+        throws: Some(s(HirType::Primitive(HirPrimitiveTy::Str))),
+        body: HirBlock {
+            stmts: Box::new([
+                let_stmt(0, call(builtin(JSON_NEW_OBJECT), vec![])),
+                let_stmt(1, call(builtin(JSON_FROM_STR), vec![text("rune")])),
+                HirStmt::Expr(call(method(0, "insert"), vec![text("name"), local(1)])),
+                let_stmt(2, call(method(0, "get"), vec![text("name")])),
+                let_stmt(3, call(method(1, "as_str"), vec![])),
+                let_stmt(4, call(method(0, "keys"), vec![])),
+                let_stmt(
+                    5,
+                    s(HirExpr::Try(Box::new(call(builtin(JSON_STRINGIFY), vec![local(0)])))),
+                ),
+            ]),
+            tail: None,
+            span: sp(0, 0),
+        },
+        span: sp(0, 0),
+    }));
+
+    let result = TypeChecker::new().check(&hir);
+
+    assert!(result.diags.is_empty(), "{:?}", result.diags);
+    let local_ty = |id| result.info.local(main, HirLocalId::from_usize(id)).unwrap().ty.clone();
+    let json = Ty::BuiltinType { id: JSON, args: Box::new([]) };
+    assert_eq!(local_ty(0), json);
+    assert_eq!(local_ty(2), json.clone().nullable());
+    assert_eq!(local_ty(3), Ty::Str.nullable());
+    assert_eq!(local_ty(4), Ty::BuiltinType { id: VEC, args: Box::new([Ty::Str]) });
+    assert_eq!(local_ty(5), Ty::Str);
+    assert_eq!(result.info.layout(&json.nullable()), Some(Layout::new(8, 8)));
+}
+
+#[test]
+fn types_decimal_arithmetic_and_rounding_modes() {
+    let local = |id| s(HirExpr::Resolved(Res::Local(HirLocalId::from_usize(id))));
+    let binary = |op, lhs, rhs| s(HirExpr::Binary { op, lhs: Box::new(lhs), rhs: Box::new(rhs) });
+    let builtin = |id| s(HirExpr::Resolved(Res::Builtin(id)));
+    let int = |value| s(HirExpr::Literal(HirLiteral::Int { value, suffix: None }));
+    let method = |id, name| {
+        s(HirExpr::Field { base: Box::new(local(id)), name: SpannedStr::new(name, sp(0, 0)) })
+    };
+    let call = |callee, args: Vec<_>| {
+        s(HirExpr::Call { callee: Box::new(callee), args: args.into_boxed_slice() })
+    };
+    let let_stmt = |id, init| HirStmt::Let {
+        local: Some(HirLocalId::from_usize(id)),
+        name: SpannedStr::new("d", sp(0, 0)),
+        is_mutable: false,
+        ty: None,
+        init: Some(init),
+        span: sp(0, 0),
+    };
+    let main = HirId::from_usize(0);
+
+    let mut hir = HirMap::new();
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: main,
+        name: SpannedStr::new("main", sp(0, 0)),
+        generics: Box::new([]),
+        params: Box::new([]),
+        ret_ty: s(HirType::Unit),
+        throws: None,
+        body: HirBlock {
+            stmts: Box::new([
+                let_stmt(0, call(builtin(DECIMAL_FROM_INT), vec![int(7)])),
+                let_stmt(1, builtin(ROUNDING_HALF_UP)),
+                let_stmt(2, call(method(0, "mul"), vec![local(0)])),
+                let_stmt(3, call(method(2, "round"), vec![int(2), local(1)])),
+                let_stmt(4, call(method(3, "cmp"), vec![local(0)])),
+                let_stmt(5, call(method(3, "to_string"), vec![])),
+                let_stmt(
+                    6,
+                    binary(BinaryOp::Add, local(0), binary(BinaryOp::Mul, local(2), local(3))),
+                ),
+                HirStmt::Expr(call(builtin(PRINTLN), vec![local(6)])),
+            ]),
+            tail: None,
+            span: sp(0, 0),
+        },
+        span: sp(0, 0),
+    }));
+
+    let result = TypeChecker::new().check(&hir);
+
+    assert!(result.diags.is_empty(), "{:?}", result.diags);
+    let local_ty = |id| result.info.local(main, HirLocalId::from_usize(id)).unwrap().ty.clone();
+    let decimal = Ty::BuiltinType { id: DECIMAL, args: Box::new([]) };
+    assert_eq!(local_ty(0), decimal);
+    assert_eq!(local_ty(1), Ty::BuiltinType { id: ROUNDING_MODE, args: Box::new([]) });
+    assert_eq!(local_ty(3), decimal);
+    assert_eq!(local_ty(4), Ty::Int { signed: true, bits: TypeBits::B64 });
+    assert_eq!(local_ty(5), Ty::Str);
+    assert_eq!(local_ty(6), decimal);
+    assert_eq!(result.info.layout(&decimal.nullable()), Some(Layout::new(8, 8)));
+}
+
+#[test]
+fn overloads_only_operators_whose_contract_the_left_operand_implements() {
+    let decimal = || {
+        s(HirExpr::Call {
+            callee: Box::new(s(HirExpr::Resolved(Res::Builtin(DECIMAL_FROM_INT)))),
+            args: Box::new([s(HirExpr::Literal(HirLiteral::Int { value: 1, suffix: None }))]),
+        })
+    };
+    let int = || s(HirExpr::Literal(HirLiteral::Int { value: 1, suffix: None }));
+    let binary = |op, lhs, rhs| {
+        HirStmt::Expr(s(HirExpr::Binary { op, lhs: Box::new(lhs), rhs: Box::new(rhs) }))
+    };
+
+    let mut hir = HirMap::new();
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: HirId::from_usize(0),
+        name: SpannedStr::new("main", sp(0, 0)),
+        generics: Box::new([]),
+        params: Box::new([]),
+        ret_ty: s(HirType::Unit),
+        throws: None,
+        body: HirBlock {
+            stmts: Box::new([
+                binary(BinaryOp::Sub, decimal(), int()),
+                binary(BinaryOp::Div, decimal(), decimal()),
+                binary(BinaryOp::Add, int(), int()),
+            ]),
+            tail: None,
+            span: sp(0, 0),
+        },
+        span: sp(0, 0),
+    }));
+
+    let result = TypeChecker::new().check(&hir);
+
+    let messages: Vec<_> = result.diags.iter().map(|diag| &diag.message.message).collect();
+    assert_eq!(messages.len(), 3, "{messages:?}");
+    assert!(messages[0].starts_with("expected type"), "{messages:?}");
+    assert_eq!(messages[1], "operator `Div` is not supported yet");
+    assert_eq!(messages[2], "operator `Add` is not supported yet");
+}
+
+#[test]
+fn compares_only_values_of_a_shared_eq_or_ord_type() {
+    let call = |id| {
+        s(HirExpr::Call {
+            callee: Box::new(s(HirExpr::Resolved(Res::Builtin(id)))),
+            args: Box::new([]),
+        })
+    };
+    let decimal = || {
+        s(HirExpr::Call {
+            callee: Box::new(s(HirExpr::Resolved(Res::Builtin(DECIMAL_FROM_INT)))),
+            args: Box::new([s(HirExpr::Literal(HirLiteral::Int { value: 1, suffix: None }))]),
+        })
+    };
+    let literal = |literal| s(HirExpr::Literal(literal));
+    let binary = |op, lhs, rhs| {
+        HirStmt::Expr(s(HirExpr::Binary { op, lhs: Box::new(lhs), rhs: Box::new(rhs) }))
+    };
+
+    let mut hir = HirMap::new();
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: HirId::from_usize(0),
+        name: SpannedStr::new("main", sp(0, 0)),
+        generics: Box::new([]),
+        params: Box::new([]),
+        ret_ty: s(HirType::Unit),
+        throws: None,
+        body: HirBlock {
+            stmts: Box::new([
+                binary(BinaryOp::Lt, decimal(), decimal()),
+                binary(
+                    BinaryOp::Ge,
+                    literal(HirLiteral::Str("a".into())),
+                    literal(HirLiteral::Str("b".into())),
+                ),
+                binary(
+                    BinaryOp::Eq,
+                    literal(HirLiteral::Bool(true)),
+                    literal(HirLiteral::Bool(false)),
+                ),
+                binary(BinaryOp::Ne, call(INSTANT_NOW), literal(HirLiteral::Null)),
+                binary(
+                    BinaryOp::Lt,
+                    literal(HirLiteral::Bool(true)),
+                    literal(HirLiteral::Bool(false)),
+                ),
+                binary(BinaryOp::Eq, call(INSTANT_NOW), call(INSTANT_NOW)),
+                binary(BinaryOp::Gt, decimal(), literal(HirLiteral::Str("1".into()))),
+            ]),
+            tail: None,
+            span: sp(0, 0),
+        },
+        span: sp(0, 0),
+    }));
+
+    let result = TypeChecker::new().check(&hir);
+
+    let messages: Vec<_> = result.diags.iter().map(|diag| &diag.message.message).collect();
+    assert_eq!(
+        messages,
+        [
+            "values of type `bool` cannot be compared with `<`",
+            "values of type `Instant` cannot be compared with `==`",
+            "expected type `Decimal`, found `str`",
+        ]
+    );
+}
+
+#[test]
+fn calls_itertools_functions_as_methods_of_iterables() {
+    let i64_ty = Ty::Int { signed: true, bits: TypeBits::B64 };
+    let filter = HirId::from_usize(0);
+    let main = HirId::from_usize(1);
+    let local = |id| s(HirExpr::Resolved(Res::Local(HirLocalId::from_usize(id))));
+    let vec_of =
+        |ty| HirType::Builtin { ty: VEC, generics: Box::new([HirGenericArg::Type(s(ty))]) };
+    let method = |base, lo| {
+        Spanned::new(
+            HirExpr::Field { base: Box::new(base), name: SpannedStr::new("filter", sp(0, 0)) },
+            sp(lo, lo + 1),
+        )
+    };
+    let closure = |param| {
+        let x = local(param);
+        s(HirExpr::Closure(HirClosure {
+            params: Box::new([HirClosureParam {
+                local: Some(HirLocalId::from_usize(param)),
+                name: SpannedStr::new("x", sp(0, 0)),
+                ty: None,
+            }]),
+            body: Box::new(s(HirExpr::Binary {
+                op: BinaryOp::Eq,
+                lhs: Box::new(x.clone()),
+                rhs: Box::new(x),
+            })),
+            captures: Box::new([]),
+        }))
+    };
+    let let_stmt = |id, ty: Option<HirType<'static>>, init| HirStmt::Let {
+        local: Some(HirLocalId::from_usize(id)),
+        name: SpannedStr::new("v", sp(0, 0)),
+        is_mutable: false,
+        ty: ty.map(s),
+        init: Some(init),
+        span: sp(0, 0),
+    };
+    let generic = |name, bounds| HirGenericParam {
+        name: SpannedStr::new(name, sp(0, 0)),
+        kind: HirGenericParamKind::Type { bounds },
+        span: sp(0, 0),
+    };
+    let into_iterator = HirBound {
+        path: HirPath {
+            from_root: false,
+            segments: Box::new([HirPathSegment {
+                name: SpannedStr::new("IntoIterator", sp(0, 0)),
+                generics: Some(Box::new([HirGenericArg::Type(s(HirType::Param(0)))])),
+                span: sp(0, 0),
+            }]),
+            span: sp(0, 0),
+        },
+        contract: Some(INTO_ITERATOR_CONTRACT),
+    };
+    let vec_new = || {
+        s(HirExpr::Call {
+            callee: Box::new(s(HirExpr::Resolved(Res::Builtin(VEC_NEW)))),
+            args: Box::new([]),
+        })
+    };
+
+    let mut hir = HirMap::new();
+    let std = hir.add_module(HirModuleId::ROOT, "std");
+    let itertools = hir.add_module(std, "itertools");
+    // `pub act filter<T, I: IntoIterator<T>>(items: I, keep: act(T) -> bool) -> Vec<T>`
+    hir.push_in(
+        itertools,
+        HirItem::Function(HirFunction {
+            vis: Visibility::Public,
+            id: filter,
+            name: SpannedStr::new("filter", sp(0, 0)),
+            generics: Box::new([
+                generic("T", Box::new([])),
+                generic("I", Box::new([into_iterator])),
+            ]),
+            params: Box::new([
+                HirFunctionParam {
+                    name: SpannedStr::new("items", sp(0, 0)),
+                    ty: s(HirType::Param(1)),
+                    span: sp(0, 0),
+                },
+                HirFunctionParam {
+                    name: SpannedStr::new("keep", sp(0, 0)),
+                    ty: s(HirType::Function {
+                        params: Box::new([s(HirType::Param(0))]),
+                        ret: Box::new(s(HirType::Primitive(HirPrimitiveTy::Bool))),
+                    }),
+                    span: sp(0, 0),
+                },
+            ]),
+            ret_ty: s(vec_of(HirType::Param(0))),
+            throws: None,
+            body: HirBlock { stmts: Box::new([]), tail: Some(Box::new(vec_new())), span: sp(0, 0) },
+            span: sp(0, 0),
+        }),
+    );
+    // `let v: Vec<i64> = Vec::new(); let kept = v.filter(|x| x == x); true.filter(v);`
+    let bool_base = s(HirExpr::Literal(HirLiteral::Bool(true)));
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: main,
+        name: SpannedStr::new("main", sp(0, 0)),
+        generics: Box::new([]),
+        params: Box::new([]),
+        ret_ty: s(HirType::Unit),
+        throws: None,
+        body: HirBlock {
+            stmts: Box::new([
+                let_stmt(0, Some(vec_of(HirType::Primitive(HirPrimitiveTy::I64))), vec_new()),
+                let_stmt(
+                    2,
+                    None,
+                    s(HirExpr::Call {
+                        callee: Box::new(method(local(0), 1)),
+                        args: Box::new([closure(1)]),
+                    }),
+                ),
+                HirStmt::Expr(s(HirExpr::Call {
+                    callee: Box::new(method(bool_base.clone(), 3)),
+                    args: Box::new([local(0)]),
+                })),
+            ]),
+            tail: None,
+            span: sp(0, 0),
+        },
+        span: sp(0, 0),
+    }));
+
+    let result = TypeChecker::new().check(&hir);
+
+    let messages: Vec<_> =
+        result.diags.iter().map(|diag| diag.message.message.to_string()).collect();
+    assert_eq!(messages, ["type `bool` has no field `filter`"]);
+    assert_eq!(result.info.method(main, &method(local(0), 1)), Some(filter));
+    assert_eq!(result.info.method(main, &method(bool_base, 3)), None);
+    let local_ty = |id| result.info.local(main, HirLocalId::from_usize(id)).unwrap().ty.clone();
+    assert_eq!(local_ty(1), i64_ty);
+    assert_eq!(local_ty(2), Ty::BuiltinType { id: VEC, args: Box::new([i64_ty]) });
+}
+
+#[test]
+fn checks_explicit_generic_arguments_and_mem_places() {
+    let i64_ty = || s(HirType::Primitive(HirPrimitiveTy::I64));
+    let local = |id| s(HirExpr::Resolved(Res::Local(HirLocalId::from_usize(id))));
+    let int = |value| s(HirExpr::Literal(HirLiteral::Int { value, suffix: None }));
+    let call = |id, args: Vec<_>| {
+        s(HirExpr::Call {
+            callee: Box::new(s(HirExpr::Resolved(Res::Builtin(id)))),
+            args: args.into_boxed_slice(),
+        })
+    };
+    let let_stmt = |id, ty: Option<HirType<'static>>, init| HirStmt::Let {
+        local: Some(HirLocalId::from_usize(id)),
+        name: SpannedStr::new("v", sp(0, 0)),
+        is_mutable: id < 2,
+        ty: ty.map(s),
+        init: Some(init),
+        span: sp(0, 0),
+    };
+    let range =
+        s(HirExpr::Range { start: Box::new(int(0)), end: Box::new(int(1)), inclusive: false });
+    let size_of = Spanned::new(HirExpr::Resolved(Res::Builtin(MEM_SIZE_OF)), sp(10, 17));
+    let size_of_call = s(HirExpr::Call {
+        callee: Box::new(s(HirExpr::Turbofish {
+            callee: Box::new(size_of.clone()),
+            args: Box::new([HirGenericArg::Type(i64_ty())]),
+        })),
+        args: Box::new([]),
+    });
+    let main = HirId::from_usize(0);
+
+    let mut hir = HirMap::new();
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: main,
+        name: SpannedStr::new("main", sp(0, 0)),
+        generics: Box::new([]),
+        params: Box::new([]),
+        ret_ty: s(HirType::Unit),
+        throws: None,
+        body: HirBlock {
+            stmts: Box::new([
+                let_stmt(0, Some(HirType::Primitive(HirPrimitiveTy::I64)), int(1)),
+                let_stmt(1, Some(HirType::Primitive(HirPrimitiveTy::I64)), int(2)),
+                HirStmt::Expr(call(MEM_SWAP, vec![local(0), local(1)])),
+                let_stmt(2, None, call(MEM_REPLACE, vec![local(0), int(3)])),
+                let_stmt(3, None, size_of_call),
+                HirStmt::Expr(call(MEM_SWAP, vec![local(0), int(4)])),
+                let_stmt(4, None, call(MEM_TAKE, vec![local(1)])),
+                let_stmt(5, Some(HirType::Primitive(HirPrimitiveTy::I64)), int(5)),
+                HirStmt::Expr(call(MEM_SWAP, vec![local(0), local(5)])),
+                let_stmt(6, None, range),
+                HirStmt::Expr(call(MEM_TAKE, vec![local(6)])),
+            ]),
+            tail: None,
+            span: sp(0, 0),
+        },
+        span: sp(0, 0),
+    }));
+
+    let result = TypeChecker::new().check(&hir);
+
+    let messages: Vec<_> =
+        result.diags.iter().map(|diag| diag.message.message.to_string()).collect();
+    assert_eq!(
+        messages,
+        [
+            "`swap` writes to this argument, so it must be a local binding or an element",
+            "cannot assign to immutable binding `v`",
+            "cannot assign to immutable binding `v`",
+            "type `Range<i32>` does not implement `core::default::Default`",
+        ]
+    );
+    let i64_ty = Ty::Int { signed: true, bits: TypeBits::B64 };
+    let local_ty = |id| result.info.local(main, HirLocalId::from_usize(id)).unwrap().ty.clone();
+    assert_eq!(local_ty(2), i64_ty);
+    assert_eq!(local_ty(3), Ty::Int { signed: false, bits: TypeBits::B64 });
+    assert_eq!(local_ty(4), i64_ty);
+    assert_eq!(result.info.callee_args(main, &size_of), [GenericArg::Type(i64_ty)]);
+}
+
+#[test]
+fn rejects_writes_into_elements_of_immutable_bindings() {
+    let v =
+        |lo| Spanned::new(HirExpr::Resolved(Res::Local(HirLocalId::from_usize(0))), sp(lo, lo + 1));
+    let int = |value| s(HirExpr::Literal(HirLiteral::Int { value, suffix: None }));
+    let string = || s(HirExpr::Literal(HirLiteral::Str("a".into())));
+    let element = |lo| s(HirExpr::Index { base: Box::new(v(lo)), index: Box::new(int(0)) });
+    let call = |id, args: Vec<_>| {
+        s(HirExpr::Call {
+            callee: Box::new(s(HirExpr::Resolved(Res::Builtin(id)))),
+            args: args.into_boxed_slice(),
+        })
+    };
+    let method = |lo, name| {
+        s(HirExpr::Field { base: Box::new(v(lo)), name: SpannedStr::new(name, sp(0, 0)) })
+    };
+    let vec_ty = HirType::Builtin {
+        ty: VEC,
+        generics: Box::new([HirGenericArg::Type(s(HirType::Primitive(HirPrimitiveTy::Str)))]),
+    };
+    let body = |is_mutable| HirBlock {
+        stmts: Box::new([
+            HirStmt::Let {
+                local: Some(HirLocalId::from_usize(0)),
+                name: SpannedStr::new("v", sp(0, 0)),
+                is_mutable,
+                ty: Some(s(vec_ty.clone())),
+                init: Some(call(VEC_NEW, vec![])),
+                span: sp(0, 0),
+            },
+            HirStmt::Expr(call(MEM_SWAP, vec![element(1), element(2)])),
+            HirStmt::Expr(call(MEM_REPLACE, vec![element(3), string()])),
+            HirStmt::Assign { target: element(4), value: string(), span: sp(0, 0) },
+            HirStmt::Expr(s(HirExpr::Call {
+                callee: Box::new(method(5, "push")),
+                args: Box::new([string()]),
+            })),
+            HirStmt::Expr(s(HirExpr::Call {
+                callee: Box::new(method(6, "len")),
+                args: Box::new([]),
+            })),
+        ]),
+        tail: None,
+        span: sp(0, 0),
+    };
+    let check = |is_mutable| {
+        let mut hir = HirMap::new();
+        hir.push(HirItem::Function(HirFunction {
+            vis: Visibility::Private,
+            id: HirId::from_usize(0),
+            name: SpannedStr::new("main", sp(0, 0)),
+            generics: Box::new([]),
+            params: Box::new([]),
+            ret_ty: s(HirType::Unit),
+            throws: None,
+            body: body(is_mutable),
+            span: sp(0, 0),
+        }));
+        TypeChecker::new().check(&hir).diags
+    };
+
+    let diags = check(false);
+
+    let found: Vec<_> =
+        diags.iter().map(|diag| (diag.message.message.to_string(), diag.labels[0].span)).collect();
+    let immutable = |lo| ("cannot assign to immutable binding `v`".to_owned(), sp(lo, lo + 1));
+    assert_eq!(found, [immutable(1), immutable(2), immutable(3), immutable(4), immutable(5)]);
+    assert!(check(true).is_empty());
+}
+
+#[test]
+fn types_tuples_by_their_elements() {
+    let u8_ty = || s(HirType::Primitive(HirPrimitiveTy::U8));
+    let str_ty = || s(HirType::Primitive(HirPrimitiveTy::Str));
+    let p = || s(HirExpr::Resolved(Res::Local(HirLocalId::from_usize(0))));
+    let element = |index| s(HirExpr::TupleField { base: Box::new(p()), index });
+    let pair = s(HirExpr::Tuple(Box::new([
+        s(HirExpr::Literal(HirLiteral::Int { value: 1, suffix: None })),
+        element(1),
+    ])));
+    let mut hir = HirMap::new();
+    // `act pick(p: (u8, str)) -> u8 { let q: (u8, str) = (1, p.1); p.2 }`
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: HirId::from_usize(0),
+        name: SpannedStr::new("pick", sp(0, 0)),
+        generics: Box::new([]),
+        params: Box::new([HirFunctionParam {
+            name: SpannedStr::new("p", sp(0, 0)),
+            ty: s(HirType::Tuple(Box::new([u8_ty(), str_ty()]))),
+            span: sp(0, 0),
+        }]),
+        ret_ty: u8_ty(),
+        throws: None,
+        body: HirBlock {
+            stmts: Box::new([HirStmt::Let {
+                local: Some(HirLocalId::from_usize(1)),
+                name: SpannedStr::new("q", sp(0, 0)),
+                is_mutable: false,
+                ty: Some(s(HirType::Tuple(Box::new([u8_ty(), str_ty()])))),
+                init: Some(pair),
+                span: sp(0, 0),
+            }]),
+            tail: Some(Box::new(element(2))),
+            span: sp(0, 0),
+        },
+        span: sp(0, 0),
+    }));
+
+    let result = TypeChecker::new().check(&hir);
+
+    let messages: Vec<_> =
+        result.diags.iter().map(|diag| diag.message.message.to_string()).collect();
+    assert_eq!(messages, ["type `(u8, str)` has no field `2`"]);
+    let pair_ty = Ty::Tuple(Box::new([Ty::Int { signed: false, bits: TypeBits::B8 }, Ty::Str]));
+    let q = result.info.local(HirId::from_usize(0), HirLocalId::from_usize(1)).unwrap();
+    assert_eq!(q.ty, pair_ty);
+}
+
+#[test]
+fn infers_items_of_into_iterator_bounds() {
+    let each = HirId::from_usize(0);
+    let twice = HirId::from_usize(1);
+    let main = HirId::from_usize(2);
+    let i32_ty = || s(HirType::Primitive(HirPrimitiveTy::I32));
+    let local = |id| s(HirExpr::Resolved(Res::Local(HirLocalId::from_usize(id))));
+    let int = |value| s(HirExpr::Literal(HirLiteral::Int { value, suffix: None }));
+    let param = |name, ty| HirFunctionParam {
+        name: SpannedStr::new(name, sp(0, 0)),
+        ty: s(ty),
+        span: sp(0, 0),
+    };
+    let function = |id, name, generics, params, body| {
+        HirItem::Function(HirFunction {
+            vis: Visibility::Private,
+            id,
+            name: SpannedStr::new(name, sp(0, 0)),
+            generics,
+            params,
+            ret_ty: s(HirType::Unit),
+            throws: None,
+            body,
+            span: sp(0, 0),
+        })
+    };
+    let into_iterator = HirBound {
+        path: HirPath {
+            from_root: false,
+            segments: Box::new([HirPathSegment {
+                name: SpannedStr::new("IntoIterator", sp(0, 0)),
+                generics: Some(Box::new([HirGenericArg::Type(s(HirType::Param(0)))])),
+                span: sp(0, 0),
+            }]),
+            span: sp(0, 0),
+        },
+        contract: Some(INTO_ITERATOR_CONTRACT),
+    };
+    let generic = |name, bounds| HirGenericParam {
+        name: SpannedStr::new(name, sp(0, 0)),
+        kind: HirGenericParamKind::Type { bounds },
+        span: sp(0, 0),
+    };
+    let range =
+        || s(HirExpr::Range { start: Box::new(int(1)), end: Box::new(int(3)), inclusive: false });
+    let call = |lo, f| {
+        HirStmt::Expr(s(HirExpr::Call {
+            callee: Box::new(Spanned::new(HirExpr::Resolved(Res::Def(each)), sp(lo, lo + 1))),
+            args: Box::new([range(), f]),
+        }))
+    };
+    let mut hir = HirMap::new();
+    // `act each<T, I: IntoIterator<T>>(items: I, f: act(T) -> T) {
+    //     for x in items { f(x); }
+    // }`
+    hir.push(function(
+        each,
+        "each",
+        Box::new([generic("T", Box::new([])), generic("I", Box::new([into_iterator]))]),
+        Box::new([
+            param("items", HirType::Param(1)),
+            param(
+                "f",
+                HirType::Function {
+                    params: Box::new([s(HirType::Param(0))]),
+                    ret: Box::new(s(HirType::Param(0))),
+                },
+            ),
+        ]),
+        HirBlock {
+            stmts: Box::new([HirStmt::Expr(s(HirExpr::For {
+                local: Some(HirLocalId::from_usize(2)),
+                name: SpannedStr::new("x", sp(0, 0)),
+                iter: Box::new(local(0)),
+                body: HirBlock {
+                    stmts: Box::new([HirStmt::Expr(s(HirExpr::Call {
+                        callee: Box::new(local(1)),
+                        args: Box::new([local(2)]),
+                    }))]),
+                    tail: None,
+                    span: sp(0, 0),
+                },
+            }))]),
+            tail: None,
+            span: sp(0, 0),
+        },
+    ));
+    // `act twice(n: i32) -> i32 { n }`
+    hir.push(HirItem::Function(HirFunction {
+        vis: Visibility::Private,
+        id: twice,
+        name: SpannedStr::new("twice", sp(0, 0)),
+        generics: Box::new([]),
+        params: Box::new([param("n", HirType::Primitive(HirPrimitiveTy::I32))]),
+        ret_ty: i32_ty(),
+        throws: None,
+        body: HirBlock { stmts: Box::new([]), tail: Some(Box::new(local(0))), span: sp(0, 0) },
+        span: sp(0, 0),
+    }));
+    // `each(1..3, |y| y); each(1..3, twice);`
+    let closure = s(HirExpr::Closure(HirClosure {
+        params: Box::new([HirClosureParam {
+            local: Some(HirLocalId::from_usize(0)),
+            name: SpannedStr::new("y", sp(0, 0)),
+            ty: None,
+        }]),
+        body: Box::new(local(0)),
+        captures: Box::new([]),
+    }));
+    let twice_item = s(HirExpr::Resolved(Res::Def(twice)));
+    hir.push(function(
+        main,
+        "main",
+        Box::new([]),
+        Box::new([]),
+        HirBlock {
+            stmts: Box::new([call(1, closure), call(3, twice_item)]),
+            tail: None,
+            span: sp(0, 0),
+        },
+    ));
+
+    let result = TypeChecker::new().check(&hir);
+
+    let messages: Vec<_> =
+        result.diags.iter().map(|diag| diag.message.message.to_string()).collect();
+    assert!(messages.is_empty(), "{messages:?}");
+    let x = result.info.local(each, HirLocalId::from_usize(2)).unwrap();
+    assert_eq!(x.ty, Ty::Param(0));
+    let i32 = Ty::Int { signed: true, bits: TypeBits::B32 };
+    let y = result.info.local(main, HirLocalId::from_usize(0)).unwrap();
+    assert_eq!(y.ty, i32);
+    let range_ty = Ty::BuiltinType { id: RANGE, args: Box::new([i32.clone()]) };
+    for lo in [1, 3] {
+        let callee = Spanned::new(HirExpr::Resolved(Res::Def(each)), sp(lo, lo + 1));
+        assert_eq!(
+            result.info.callee_args(main, &callee),
+            [GenericArg::Type(i32.clone()), GenericArg::Type(range_ty.clone())]
+        );
+    }
+}
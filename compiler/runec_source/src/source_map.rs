@@ -3,6 +3,7 @@ use std::path::{Path, PathBuf};
 use memmap2::Mmap;
 
 use crate::byte_pos::BytePos;
+use crate::span::Span;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct SourceId(u16);
@@ -91,31 +92,58 @@ impl SourceLineStarts {
 
 #[derive(Debug)]
 pub enum Source {
-    File { path: PathBuf, mmap: Mmap, lines: SourceLineStarts },
+    File {
+        path: PathBuf,
+        mmap: Mmap,
+        lines: SourceLineStarts,
+    },
+    /// Source compiled into the compiler, such as the modules of the
+    /// standard library written in RuneWay. `path` only names it in
+    /// diagnostics.
+    Embedded {
+        path: PathBuf,
+        src: &'static str,
+        lines: SourceLineStarts,
+    },
 }
 
 impl Source {
     pub const MAX_FILE_SIZE: usize = BytePos::MAX;
 
+    pub fn embedded(path: PathBuf, src: &'static str) -> Source {
+        Source::Embedded { path, src, lines: SourceLineStarts::compute_from_source(src) }
+    }
+
     pub fn src(&self) -> &str {
         match self {
             Source::File { mmap, .. } => unsafe { str::from_utf8_unchecked(mmap) },
+            Source::Embedded { src, .. } => src,
         }
     }
 
     pub fn path(&self) -> &Path {
         match self {
-            Source::File { path, .. } => path,
+            Source::File { path, .. } | Source::Embedded { path, .. } => path,
         }
     }
 
     pub fn lines(&self) -> &SourceLineStarts {
         match self {
-            Source::File { lines, .. } => lines,
+            Source::File { lines, .. } | Source::Embedded { lines, .. } => lines,
         }
     }
 }
 
+/// File, line and column that a span starts at.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SourceLocation<'a> {
+    pub path: &'a Path,
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column, counted in characters.
+    pub col: usize,
+}
+
 #[derive(Debug)]
 pub struct SourceMap {
     files: Vec<Source>,
@@ -141,12 +169,26 @@ impl SourceMap {
     pub fn get_file(&self, id: &SourceId) -> Option<&Source> {
         self.files.get(id.to_usize())
     }
+
+    /// Resolves the start of `span` to a location in its file, or `None`
+    /// when the file is not in the map or the span lies outside of it.
+    pub fn location(&self, span: Span) -> Option<SourceLocation<'_>> {
+        let file = self.get_file(&span.src_id)?;
+        let (line, line_start) = file.lines().line_search(span.lo);
+        let col = file.src().get(line_start.to_usize()..span.lo.to_usize())?.chars().count() + 1;
+        Some(SourceLocation { path: file.path(), line: line.to_usize() + 1, col })
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::path::{Path, PathBuf};
+
+    use memmap2::MmapOptions;
+
     use crate::byte_pos::BytePos;
-    use crate::source_map::{LineIndex, SourceId, SourceLineStarts};
+    use crate::source_map::{LineIndex, Source, SourceId, SourceLineStarts, SourceMap};
+    use crate::span::Span;
 
     #[test]
     #[should_panic(expected = "SourceId overflow")]
@@ -244,4 +286,37 @@ mod tests {
 
         assert_eq!(source_line_starts.last_line_number(), LineIndex::from_usize(2));
     }
+
+    #[test]
+    fn location_counts_lines_and_characters_from_one() {
+        let source = "let a = 1;\nlet ф = panic(\"x\");";
+        let mut mmap = MmapOptions::new().len(source.len()).map_anon().unwrap();
+        mmap.copy_from_slice(source.as_bytes());
+        let mut source_map = SourceMap::new();
+        let id = source_map.add_file(Source::File {
+            path: PathBuf::from("main.rnw"),
+            mmap: mmap.make_read_only().unwrap(),
+            lines: SourceLineStarts::compute_from_source(source),
+        });
+
+        let at = |pos| Span::new(BytePos::from_usize(pos), BytePos::from_usize(pos), id);
+        let location = source_map.location(at(20)).unwrap();
+        assert_eq!(location.path, Path::new("main.rnw"));
+        assert_eq!((location.line, location.col), (2, 9));
+        assert_eq!(source_map.location(at(0)).map(|location| location.col), Some(1));
+        assert_eq!(source_map.location(at(source.len() + 1)), None);
+        assert_eq!(source_map.location(Span { src_id: SourceId::from_usize(1), ..at(0) }), None);
+    }
+
+    #[test]
+    fn embedded_sources_locate_spans_like_files() {
+        let mut source_map = SourceMap::new();
+        let id = source_map.add_file(Source::embedded(PathBuf::from("<std>/a.rnw"), "a\nbc"));
+
+        let file = source_map.get_file(&id).unwrap();
+        assert_eq!((file.src(), file.path()), ("a\nbc", Path::new("<std>/a.rnw")));
+        let at = Span::new(BytePos::from_usize(3), BytePos::from_usize(4), id);
+        let location = source_map.location(at).unwrap();
+        assert_eq!((location.line, location.col), (2, 2));
+    }
 }
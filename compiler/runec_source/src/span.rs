@@ -3,7 +3,7 @@ use std::ops::{Deref, Range};
 use crate::byte_pos::BytePos;
 use crate::source_map::SourceId;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct Span {
     pub lo: BytePos,
     pub hi: BytePos,
@@ -31,7 +31,7 @@ macro_rules! span {
     };
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Spanned<T> {
     pub node: T,
     pub span: Span,